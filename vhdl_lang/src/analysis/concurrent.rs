@@ -288,25 +288,20 @@ impl<'a, 't> AnalyzeContext<'a, 't> {
                     diagnostics,
                 ))?
                 else {
+                    // Continue checking missing names even if the entity is not found
+                    self.analyze_map_aspect(scope, &mut instance.generic_map, diagnostics)?;
+                    self.analyze_map_aspect(scope, &mut instance.port_map, diagnostics)?;
                     return Ok(());
                 };
                 match resolved {
                     ResolvedName::Design(ent) => match ent.kind() {
                         Design::Entity(_, ent_region) => {
-                            if let Designator::Identifier(entity_ident) = ent.designator() {
-                                if let Some(library_name) = ent.library_name() {
-                                    if let Some(ref mut architecture_name) = architecture_name {
-                                        if let Some(arch) = as_fatal(self.get_architecture(
-                                            diagnostics,
-                                            library_name,
-                                            self.ctx.get_pos(architecture_name.item.token),
-                                            entity_ident,
-                                            &architecture_name.item.item,
-                                        ))? {
-                                            architecture_name.set_unique_reference(&arch);
-                                        }
-                                    }
-                                }
+                            if let Some(ref mut architecture_name) = architecture_name {
+                                self.resolve_architecture_name(
+                                    &ent,
+                                    architecture_name,
+                                    diagnostics,
+                                )?;
                             }
 
                             let (generic_region, port_region) = ent_region.to_entity_formal();
@@ -434,6 +429,87 @@ impl<'a, 't> AnalyzeContext<'a, 't> {
         }
     }
 
+    /// Resolve the architecture of an entity, i.e. `rtl` in `entity work.ent(rtl)`
+    fn resolve_architecture_name(
+        &self,
+        entity: &AnyEnt,
+        architecture_name: &mut WithRef<Ident>,
+        diagnostics: &mut dyn DiagnosticHandler,
+    ) -> FatalResult {
+        if let Designator::Identifier(entity_ident) = entity.designator() {
+            if let Some(library_name) = entity.library_name() {
+                if let Some(arch) = as_fatal(self.get_architecture(
+                    diagnostics,
+                    library_name,
+                    self.ctx.get_pos(architecture_name.item.token),
+                    entity_ident,
+                    &architecture_name.item.item,
+                ))? {
+                    architecture_name.set_unique_reference(&arch);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// LRM 7.3.2.2 Entity aspect of a binding indication
+    pub fn analyze_entity_aspect(
+        &self,
+        scope: &Scope<'a>,
+        entity_aspect: &mut EntityAspect,
+        diagnostics: &mut dyn DiagnosticHandler,
+    ) -> FatalResult {
+        match entity_aspect {
+            EntityAspect::Entity(entity_name, architecture_name) => {
+                let Some(resolved) = as_fatal(self.name_resolve(
+                    scope,
+                    entity_name.span,
+                    &mut entity_name.item,
+                    diagnostics,
+                ))?
+                else {
+                    return Ok(());
+                };
+                match resolved {
+                    ResolvedName::Design(ent) if matches!(ent.kind(), Design::Entity(..)) => {
+                        if let Some(architecture_name) = architecture_name {
+                            self.resolve_architecture_name(&ent, architecture_name, diagnostics)?;
+                        }
+                    }
+                    other => {
+                        diagnostics.push(
+                            other.kind_error(entity_name.suffix_pos().pos(self.ctx), "entity"),
+                        );
+                    }
+                }
+            }
+            EntityAspect::Configuration(config_name) => {
+                let Some(resolved) = as_fatal(self.name_resolve(
+                    scope,
+                    config_name.span,
+                    &mut config_name.item,
+                    diagnostics,
+                ))?
+                else {
+                    return Ok(());
+                };
+                match resolved {
+                    ResolvedName::Design(ent) if matches!(ent.kind(), Design::Configuration) => {}
+                    other => {
+                        diagnostics.push(
+                            other.kind_error(
+                                config_name.suffix_pos().pos(self.ctx),
+                                "configuration",
+                            ),
+                        );
+                    }
+                }
+            }
+            EntityAspect::Open => {}
+        }
+        Ok(())
+    }
+
     pub fn analyze_map_aspect(
         &self,
         scope: &Scope<'a>,
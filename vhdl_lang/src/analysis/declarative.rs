@@ -606,7 +606,11 @@ impl<'a, 't> AnalyzeContext<'a, 't> {
                     scope.add(ent, diagnostics);
                 }
             }
-            Declaration::Configuration(..) => {}
+            Declaration::Configuration(configuration) => {
+                if let Some(ref mut entity_aspect) = configuration.bind_ind.entity_aspect {
+                    self.analyze_entity_aspect(scope, entity_aspect, diagnostics)?;
+                }
+            }
             Declaration::View(view) => {
                 if let Some(view) = as_fatal(self.analyze_view_declaration(
                     scope,
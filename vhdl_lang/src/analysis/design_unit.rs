@@ -118,7 +118,10 @@ impl<'a, 't> AnalyzeContext<'a, 't> {
                     );
                 }
             }
+
+            self.resolve_configured_architecture(named_entity, &mut unit.block_config.block_spec)?;
         };
+        self.analyze_configuration_items(&root_region, &mut unit.block_config.items, diagnostics)?;
 
         self.arena.define(
             self.ctx,
@@ -132,6 +135,70 @@ impl<'a, 't> AnalyzeContext<'a, 't> {
         Ok(())
     }
 
+    /// Resolve the architecture of the outermost block configuration, i.e. `rtl` in `for rtl`.
+    /// A missing architecture is not reported, the reference is only kept
+    /// such that the architecture name is found when searching for references.
+    fn resolve_configured_architecture(
+        &self,
+        entity: DesignEnt<'a>,
+        block_spec: &mut WithTokenSpan<Name>,
+    ) -> FatalResult {
+        let pos = block_spec.pos(self.ctx);
+        let Name::Designator(ref mut designator) = block_spec.item else {
+            return Ok(());
+        };
+        let (Designator::Identifier(entity_name), Designator::Identifier(architecture_name)) =
+            (entity.designator(), &designator.item)
+        else {
+            return Ok(());
+        };
+
+        if let Some(arch) = as_fatal(self.get_architecture(
+            &mut NullDiagnostics,
+            self.work_library_name(),
+            &pos,
+            entity_name,
+            architecture_name,
+        ))? {
+            designator.set_unique_reference(&arch);
+        }
+        Ok(())
+    }
+
+    /// Analyze the binding indications of the component configurations
+    /// within a block configuration
+    fn analyze_configuration_items(
+        &self,
+        scope: &Scope<'a>,
+        items: &mut [ConfigurationItem],
+        diagnostics: &mut dyn DiagnosticHandler,
+    ) -> FatalResult {
+        for item in items.iter_mut() {
+            match item {
+                ConfigurationItem::Block(block_config) => {
+                    self.analyze_configuration_items(scope, &mut block_config.items, diagnostics)?;
+                }
+                ConfigurationItem::Component(component_config) => {
+                    if let Some(BindingIndication {
+                        entity_aspect: Some(ref mut entity_aspect),
+                        ..
+                    }) = component_config.bind_ind
+                    {
+                        self.analyze_entity_aspect(scope, entity_aspect, diagnostics)?;
+                    }
+                    if let Some(ref mut block_config) = component_config.block_config {
+                        self.analyze_configuration_items(
+                            scope,
+                            &mut block_config.items,
+                            diagnostics,
+                        )?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn analyze_package(
         &self,
         unit: &mut PackageDeclaration,
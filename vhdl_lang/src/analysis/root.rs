@@ -458,12 +458,20 @@ impl DesignRoot {
             if let Some(library_name) = ent.library_name() {
                 match ent.kind() {
                     // Find entity with same name as component in the library
+                    // together with the architectures and configurations of the entity
                     AnyEntKind::Component(_) => {
                         if let Some(design) = self.get_design_entity(library_name, ident) {
-                            return vec![design.into()];
+                            let entity: EntRef = design.into();
+                            return std::iter::once(entity)
+                                .chain(
+                                    self.find_implementation(entity).into_iter().filter(|ent| {
+                                        !matches!(ent.kind(), AnyEntKind::Component(_))
+                                    }),
+                                )
+                                .collect();
                         }
                     }
-                    // Find components and architectures to entity
+                    // Find components, architectures and configurations to entity
                     AnyEntKind::Design(Design::Entity(..)) => {
                         let ent_id = ent.id;
                         let mut searcher = FindAllEnt::new(self, |ent| match ent.kind() {
@@ -482,7 +490,14 @@ impl DesignRoot {
                         });
 
                         let _ = self.search_library(library_name, &mut searcher);
-                        return searcher.result;
+
+                        let mut configurations = FindConfigurations::new(self, ent_id);
+                        let _ = self.search_library(library_name, &mut configurations);
+                        return searcher
+                            .result
+                            .into_iter()
+                            .chain(configurations.result)
+                            .collect();
                     }
                     _ => {}
                 }
@@ -600,6 +615,12 @@ impl DesignRoot {
         result
     }
 
+    pub fn find_design_unit_references(&self, source: &Source) -> Vec<(SrcPos, EntRef)> {
+        let mut searcher = FindDesignUnitReferences::new(self);
+        let _ = self.search_source(source, &mut searcher);
+        searcher.references
+    }
+
     pub fn find_all_unresolved(&self) -> (usize, Vec<SrcPos>) {
         let mut searcher = FindAllUnresolved::default();
         let _ = self.search(&mut searcher);
@@ -682,6 +703,11 @@ impl DesignRoot {
         }
     }
 
+    /// The `std.standard` package, if it has been analyzed
+    pub(crate) fn standard_package(&self) -> Option<&AnyEnt> {
+        self.standard_pkg_id.map(|id| self.get_ent(id))
+    }
+
     pub fn search(&self, searcher: &mut impl Searcher) -> SearchResult {
         for library in self.libraries.values() {
             for unit_id in library.sorted_unit_ids() {
@@ -692,7 +718,7 @@ impl DesignRoot {
         NotFound
     }
 
-    fn units_by_source<'a>(
+    pub(crate) fn units_by_source<'a>(
         &'a self,
         source: &'a Source,
     ) -> impl Iterator<Item = &'a LockedUnit> + 'a {
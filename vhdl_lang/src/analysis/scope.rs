@@ -162,23 +162,84 @@ impl<'a> ScopeInner<'a> {
 
         match result {
             Some(visible) => Ok(visible),
-            None => Err(Diagnostic::new(
-                span.pos(ctx),
-                match designator {
-                    Designator::Identifier(ident) => {
-                        format!("No declaration of '{ident}'")
+            None => {
+                let mut diagnostic = Diagnostic::new(
+                    span.pos(ctx),
+                    match designator {
+                        Designator::Identifier(ident) => {
+                            format!("No declaration of '{ident}'")
+                        }
+                        Designator::OperatorSymbol(operator) => {
+                            format!("No declaration of operator '{operator}'")
+                        }
+                        Designator::Character(chr) => {
+                            format!("No declaration of '{chr}'")
+                        }
+                        Designator::Anonymous(_) => "No declaration of <anonymous>".to_owned(),
+                    },
+                    ErrorCode::Unresolved,
+                );
+                if let Designator::Identifier(ident) = designator {
+                    for ent in self.similar_names(ident) {
+                        diagnostic = diagnostic.opt_related(
+                            ent.decl_pos(),
+                            format!("Did you mean '{}'?", ent.designator()),
+                        );
                     }
-                    Designator::OperatorSymbol(operator) => {
-                        format!("No declaration of operator '{operator}'")
-                    }
-                    Designator::Character(chr) => {
-                        format!("No declaration of '{chr}'")
-                    }
-                    Designator::Anonymous(_) => "No declaration of <anonymous>".to_owned(),
-                },
-                ErrorCode::Unresolved,
-            )),
+                }
+                Err(diagnostic)
+            }
+        }
+    }
+
+    /// Call `f` with the names declared in or made visible in this region or an enclosing region
+    fn for_each_name(&self, f: &mut dyn FnMut(&Designator, EntRef<'a>)) {
+        for (designator, named_entities) in self.region.entities.iter() {
+            f(designator, named_entities.first());
         }
+        for (designator, ent) in self.region.visibility.names() {
+            f(designator, ent);
+        }
+        if let Some(ref parent) = self.parent {
+            parent.0.borrow().for_each_name(f);
+        }
+    }
+
+    /// The names that are most similar to an identifier that could not be resolved,
+    /// such as names with a typo or with a different case of an extended identifier
+    fn similar_names(&self, ident: &Symbol) -> Vec<EntRef<'a>> {
+        const MAX_SUGGESTIONS: usize = 3;
+
+        let name = ident.name_utf8().to_lowercase();
+        let max_distance = match name.chars().count() {
+            0..=2 => return Vec::new(),
+            3..=7 => 1,
+            8..=11 => 2,
+            _ => 3,
+        };
+
+        let mut similar: Vec<(usize, String, EntRef<'a>)> = Vec::new();
+        self.for_each_name(&mut |designator, ent| {
+            let Designator::Identifier(other) = designator else {
+                return;
+            };
+            if other == ident || ent.decl_pos().is_none() {
+                return;
+            }
+            let other_name = other.name_utf8();
+            if similar.iter().any(|(_, name, _)| *name == other_name) {
+                return;
+            }
+            if let Some(distance) = edit_distance(&name, &other_name.to_lowercase(), max_distance) {
+                similar.push((distance, other_name, ent));
+            }
+        });
+        similar.sort_by(|(d1, n1, _), (d2, n2, _)| d1.cmp(d2).then_with(|| n1.cmp(n2)));
+        similar
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, _, ent)| ent)
+            .collect()
     }
 
     fn lookup(
@@ -386,3 +447,40 @@ impl Diagnostic {
         diagnostic
     }
 }
+
+/// The Levenshtein distance between two names, `None` when it exceeds `max_distance`
+fn edit_distance(name: &str, other: &str, max_distance: usize) -> Option<usize> {
+    let name: Vec<char> = name.chars().collect();
+    let other: Vec<char> = other.chars().collect();
+    if name.len().abs_diff(other.len()) > max_distance {
+        return None;
+    }
+
+    let mut previous: Vec<usize> = (0..=other.len()).collect();
+    for (i, chr) in name.iter().enumerate() {
+        let mut current = vec![i + 1; other.len() + 1];
+        for (j, other_chr) in other.iter().enumerate() {
+            let substitution = previous[j] + usize::from(chr != other_chr);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        if current.iter().min().is_some_and(|min| *min > max_distance) {
+            return None;
+        }
+        previous = current;
+    }
+    Some(previous[other.len()]).filter(|distance| *distance <= max_distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance_of_names() {
+        assert_eq!(edit_distance("counter", "counter", 1), Some(0));
+        assert_eq!(edit_distance("countr", "counter", 1), Some(1));
+        assert_eq!(edit_distance("conuter", "counter", 2), Some(2));
+        assert_eq!(edit_distance("conuter", "counter", 1), None);
+        assert_eq!(edit_distance("clk", "reset", 3), None);
+    }
+}
@@ -6,6 +6,7 @@
 
 use super::*;
 use vhdl_lang::data::error_codes::ErrorCode;
+use vhdl_lang::VHDLStandard;
 
 #[test]
 fn check_library_clause_library_exists() {
@@ -1079,3 +1080,51 @@ end package;
 ",
     );
 }
+
+#[test]
+fn std_env_has_vhdl_2019_declarations() {
+    let code = "
+use std.env.all;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+begin
+  process
+    variable now_rec : time_record;
+    variable dir : directory;
+    variable status : dir_open_status;
+  begin
+    now_rec := localtime;
+    dir_open(dir, dir_workingdir, status);
+    report tool_name & ' ' & vhdl_version;
+    wait;
+  end process;
+end architecture;
+";
+    let mut builder = LibraryBuilder::with_standard(VHDLStandard::VHDL2019);
+    builder.code("libname", code);
+    check_no_diagnostics(&builder.analyze());
+
+    // The declarations of VHDL-2008 do not include the additions
+    let mut builder = LibraryBuilder::new();
+    let code = builder.code(
+        "libname",
+        "
+use std.env.all;
+
+package pkg is
+  constant sep : string := dir_separator;
+end package;
+",
+    );
+    check_diagnostics(
+        builder.analyze(),
+        vec![Diagnostic::new(
+            code.s1("dir_separator"),
+            "No declaration of 'dir_separator'",
+            ErrorCode::Unresolved,
+        )],
+    );
+}
@@ -306,6 +306,10 @@ begin
   inst: ent0;
 end architecture;
 
+configuration cfg of ent0 is
+  for a
+  end for;
+end configuration;
       ",
     );
 
@@ -322,8 +326,12 @@ end architecture;
         .search_reference(code.source(), code.sa("component ", "ent0").start())
         .unwrap();
 
-    assert_eq!(root.find_implementation(ent), vec![arch, comp]);
-    assert_eq!(root.find_implementation(comp), vec![ent]);
+    let cfg = root
+        .search_reference(code.source(), code.s1("cfg").start())
+        .unwrap();
+
+    assert_eq!(root.find_implementation(ent), vec![arch, comp, cfg]);
+    assert_eq!(root.find_implementation(comp), vec![ent, arch, cfg]);
 }
 
 #[test]
@@ -311,6 +311,45 @@ end architecture;
     );
 }
 
+#[test]
+fn resolves_actuals_of_missing_entity_instance() {
+    let mut builder = LibraryBuilder::new();
+    let code = builder.code(
+        "libname",
+        "
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal foo : bit;
+begin
+  inst : entity work.missing port map (x => foo, y => undefined);
+end architecture;
+",
+    );
+
+    let (root, diagnostics) = builder.get_analyzed_root();
+    check_diagnostics(
+        diagnostics,
+        vec![
+            Diagnostic::new(
+                code.s1("missing"),
+                "No primary unit 'missing' within library 'libname'",
+                ErrorCode::Unresolved,
+            ),
+            Diagnostic::new(
+                code.s1("undefined"),
+                "No declaration of 'undefined'",
+                ErrorCode::Unresolved,
+            ),
+        ],
+    );
+    assert_eq!(
+        root.search_reference_pos(code.source(), code.s("foo", 2).start()),
+        Some(code.s1("foo").pos())
+    );
+}
+
 #[test]
 fn resolves_component_instance() {
     check_missing(
@@ -365,12 +404,19 @@ fn search_configuration_instance() {
 entity ent is
 end entity;
 
+architecture a of ent is
+begin
+end architecture;
+
 configuration decl of ent is
   for a
   end for;
 end configuration;
 
-architecture a of ent is
+entity top is
+end entity;
+
+architecture a of top is
 begin
   inst : configuration work.decl;
 end architecture;
@@ -548,3 +594,85 @@ end architecture;
         Some(&code.s1("empty").pos())
     );
 }
+
+#[test]
+fn finds_all_references_to_architecture() {
+    let mut builder = LibraryBuilder::new();
+    let code = builder.code(
+        "libname",
+        "
+entity ent is
+end entity;
+
+architecture rtl of ent is
+begin
+end architecture rtl;
+
+entity top is
+end entity;
+
+architecture struct of top is
+    component comp
+    end component;
+    for all : comp use entity work.ent(rtl);
+begin
+    inst : entity work.ent(rtl);
+    comp_inst : comp;
+end architecture;
+
+configuration cfg of top is
+    for struct
+        for comp_inst : comp
+            use entity work.ent(rtl);
+        end for;
+    end for;
+end configuration;
+",
+    );
+
+    let (root, diagnostics) = builder.get_analyzed_root();
+    check_no_diagnostics(&diagnostics);
+
+    assert_eq!(
+        root.search_reference_pos(code.source(), code.s("rtl", 5).start()),
+        Some(code.s("rtl", 1).pos())
+    );
+    assert_eq!(
+        root.search_reference_pos(code.source(), code.s("struct", 2).start()),
+        Some(code.s1("struct").pos())
+    );
+
+    let references: Vec<_> = (1..=5).map(|idx| code.s("rtl", idx).pos()).collect();
+    assert_eq_unordered(
+        &root.find_all_references_pos(&code.s1("rtl").pos()),
+        &references,
+    );
+}
+
+#[test]
+fn error_on_missing_entity_in_configuration_specification() {
+    let mut builder = LibraryBuilder::new();
+    let code = builder.code(
+        "libname",
+        "
+entity ent is
+end entity;
+
+architecture a of ent is
+    component comp
+    end component;
+    for all : comp use entity work.missing;
+begin
+end architecture;
+",
+    );
+
+    check_diagnostics(
+        builder.analyze(),
+        vec![Diagnostic::new(
+            code.s1("missing"),
+            "No primary unit 'missing' within library 'libname'",
+            ErrorCode::Unresolved,
+        )],
+    );
+}
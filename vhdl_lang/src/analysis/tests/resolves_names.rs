@@ -2164,3 +2164,34 @@ end package;",
     let (_root, diagnostics) = builder.get_analyzed_root();
     check_no_diagnostics(&diagnostics);
 }
+
+#[test]
+fn suggests_similar_names_of_unresolved_names() {
+    let mut builder = LibraryBuilder::new();
+    let code = builder.in_declarative_region(
+        "
+signal write_enable : bit;
+signal write_enables : bit_vector(0 to 1);
+constant c0 : bit := write_enabl;
+constant c1 : bit := unrelated;
+",
+    );
+    let diagnostics = builder.analyze();
+    check_diagnostics(
+        diagnostics,
+        vec![
+            Diagnostic::new(
+                code.s1("write_enabl;").s1("write_enabl"),
+                "No declaration of 'write_enabl'",
+                ErrorCode::Unresolved,
+            )
+            .related(code.s1("write_enable"), "Did you mean 'write_enable'?")
+            .related(code.s1("write_enables"), "Did you mean 'write_enables'?"),
+            Diagnostic::new(
+                code.s1("unrelated"),
+                "No declaration of 'unrelated'",
+                ErrorCode::Unresolved,
+            ),
+        ],
+    );
+}
@@ -79,7 +79,7 @@ end architecture;"
         let mut root = DesignRoot::new(self.code_builder.symbols.clone());
         let mut diagnostics = Vec::new();
 
-        add_standard_library(self.symbols(), &mut root);
+        add_standard_library_of(self.symbols(), &mut root, self.code_builder.standard);
 
         for (library_name, codes) in self.libraries.iter() {
             for code in codes {
@@ -119,18 +119,25 @@ fn standard_package() -> Source {
         .to_string(),
     )
 }
-fn textio_package() -> Source {
+fn textio_package(standard: VHDLStandard) -> Source {
+    let bytes: &[u8] = if standard >= VHDLStandard::VHDL2019 {
+        include_bytes!("../../../../vhdl_libraries/std/2019/textio.vhd")
+    } else {
+        include_bytes!("../../../../vhdl_libraries/std/textio.vhd")
+    };
     Source::inline(
         Path::new("textio.vhd"),
-        &Latin1String::new(include_bytes!("../../../../vhdl_libraries/std/textio.vhd")).to_string(),
+        &Latin1String::new(bytes).to_string(),
     )
 }
 
-fn env_package() -> Source {
-    Source::inline(
-        Path::new("env.vhd"),
-        &Latin1String::new(include_bytes!("../../../../vhdl_libraries/std/env.vhd")).to_string(),
-    )
+fn env_package(standard: VHDLStandard) -> Source {
+    let bytes: &[u8] = if standard >= VHDLStandard::VHDL2019 {
+        include_bytes!("../../../../vhdl_libraries/std/2019/env.vhd")
+    } else {
+        include_bytes!("../../../../vhdl_libraries/std/env.vhd")
+    };
+    Source::inline(Path::new("env.vhd"), &Latin1String::new(bytes).to_string())
 }
 
 fn std_logic_1164_package() -> Source {
@@ -144,13 +151,22 @@ fn std_logic_1164_package() -> Source {
 }
 
 pub fn add_standard_library(symbols: Arc<Symbols>, root: &mut DesignRoot) {
+    add_standard_library_of(symbols, root, VHDLStandard::default());
+}
+
+/// Add the std library with the declarations of the VHDL standard
+pub fn add_standard_library_of(
+    symbols: Arc<Symbols>,
+    root: &mut DesignRoot,
+    standard: VHDLStandard,
+) {
     let builder = CodeBuilder {
         symbols: symbols.clone(),
-        standard: VHDLStandard::default(),
+        standard,
     };
     let std_standard = builder.code_from_source(standard_package());
-    let std_textio = builder.code_from_source(textio_package());
-    let std_env = builder.code_from_source(env_package());
+    let std_textio = builder.code_from_source(textio_package(standard));
+    let std_env = builder.code_from_source(env_package(standard));
     let std_sym = symbols.symtab().insert_utf8("std");
 
     root.add_design_file(std_sym.clone(), std_standard.design_file());
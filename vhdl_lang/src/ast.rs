@@ -1340,7 +1340,7 @@ pub enum InstantiationList {
 /// LRM 7.3.2 Binding indication
 #[derive(PartialEq, Debug, Clone)]
 pub enum EntityAspect {
-    Entity(WithTokenSpan<Name>, Option<Ident>),
+    Entity(WithTokenSpan<Name>, Option<WithRef<Ident>>),
     Configuration(WithTokenSpan<Name>),
     Open,
 }
@@ -209,6 +209,20 @@ impl SecondaryKind {
 }
 
 impl AnyDesignUnit {
+    /// The context clause preceding the unit.
+    /// For a context declaration these are the items of the declaration.
+    pub fn context_clause(&self) -> &ContextClause {
+        match self {
+            AnyDesignUnit::Primary(AnyPrimaryUnit::Context(context)) => &context.items,
+            AnyDesignUnit::Primary(AnyPrimaryUnit::Entity(unit)) => &unit.context_clause,
+            AnyDesignUnit::Primary(AnyPrimaryUnit::Package(unit)) => &unit.context_clause,
+            AnyDesignUnit::Primary(AnyPrimaryUnit::PackageInstance(unit)) => &unit.context_clause,
+            AnyDesignUnit::Primary(AnyPrimaryUnit::Configuration(unit)) => &unit.context_clause,
+            AnyDesignUnit::Secondary(AnySecondaryUnit::Architecture(unit)) => &unit.context_clause,
+            AnyDesignUnit::Secondary(AnySecondaryUnit::PackageBody(unit)) => &unit.context_clause,
+        }
+    }
+
     pub fn as_primary_mut(&mut self) -> Option<&mut AnyPrimaryUnit> {
         if let AnyDesignUnit::Primary(unit) = self {
             Some(unit)
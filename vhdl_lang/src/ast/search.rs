@@ -7,7 +7,7 @@
 
 use super::*;
 use crate::analysis::DesignRoot;
-use crate::named_entity::{EntRef, HasEntityId, Reference, Related};
+use crate::named_entity::{AnyEntKind, Design, EntRef, EntityId, HasEntityId, Reference, Related};
 use crate::syntax::{HasTokenSpan, TokenAccess};
 
 #[must_use]
@@ -103,6 +103,16 @@ pub trait Searcher {
     fn search_with_pos(&mut self, _ctx: &dyn TokenAccess, _pos: &SrcPos) -> SearchState {
         NotFinished
     }
+
+    /// Search an expression before searching its sub-expressions
+    fn search_expression(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        _pos: &SrcPos,
+        _expr: &Expression,
+    ) -> SearchState {
+        NotFinished
+    }
 }
 
 pub trait Search {
@@ -834,6 +844,7 @@ fn search_pos_expr(
     searcher: &mut impl Searcher,
 ) -> SearchResult {
     return_if_finished!(searcher.search_with_pos(ctx, pos));
+    return_if_finished!(searcher.search_expression(ctx, pos, expr));
     match expr {
         Expression::Binary(ref op, ref left, ref right) => {
             return_if_found!(searcher
@@ -1080,8 +1091,8 @@ impl Search for Declaration {
                 return_if_found!(package_instance.search(ctx, searcher));
             }
 
-            Declaration::Configuration(_) => {
-                // @TODO
+            Declaration::Configuration(configuration) => {
+                return_if_found!(configuration.bind_ind.entity_aspect.search(ctx, searcher));
             }
             Declaration::View(view) => {
                 return_if_found!(searcher
@@ -1343,7 +1354,51 @@ impl Search for ConfigurationDeclaration {
         return_if_found!(searcher
             .search_decl(ctx, FoundDeclaration::Configuration(self))
             .or_not_found());
-        self.entity_name.search(ctx, searcher)
+        return_if_found!(self.entity_name.search(ctx, searcher));
+        self.block_config.search(ctx, searcher)
+    }
+}
+
+impl Search for BlockConfiguration {
+    fn search(&self, ctx: &dyn TokenAccess, searcher: &mut impl Searcher) -> SearchResult {
+        return_if_found!(self.block_spec.search(ctx, searcher));
+        self.items.search(ctx, searcher)
+    }
+}
+
+impl Search for ConfigurationItem {
+    fn search(&self, ctx: &dyn TokenAccess, searcher: &mut impl Searcher) -> SearchResult {
+        match self {
+            ConfigurationItem::Block(block_config) => block_config.search(ctx, searcher),
+            ConfigurationItem::Component(component_config) => {
+                if let Some(bind_ind) = &component_config.bind_ind {
+                    return_if_found!(bind_ind.entity_aspect.search(ctx, searcher));
+                }
+                component_config.block_config.search(ctx, searcher)
+            }
+        }
+    }
+}
+
+impl Search for EntityAspect {
+    fn search(&self, ctx: &dyn TokenAccess, searcher: &mut impl Searcher) -> SearchResult {
+        match self {
+            EntityAspect::Entity(ent_name, architecture_name) => {
+                return_if_found!(ent_name.search(ctx, searcher));
+                if let Some(architecture_name) = architecture_name {
+                    return_if_found!(searcher
+                        .search_pos_with_ref(
+                            ctx,
+                            architecture_name.item.pos(ctx),
+                            &architecture_name.reference
+                        )
+                        .or_not_found());
+                }
+                NotFound
+            }
+            EntityAspect::Configuration(config_name) => config_name.search(ctx, searcher),
+            EntityAspect::Open => NotFound,
+        }
     }
 }
 
@@ -1528,6 +1583,36 @@ impl<'a, T: FnMut(EntRef<'a>) -> bool> Searcher for FindAllEnt<'a, T> {
     }
 }
 
+// Search for the configurations of an entity
+pub struct FindConfigurations<'a> {
+    root: &'a DesignRoot,
+    entity: EntityId,
+    pub result: Vec<EntRef<'a>>,
+}
+
+impl<'a> FindConfigurations<'a> {
+    pub fn new(root: &'a DesignRoot, entity: EntityId) -> FindConfigurations<'a> {
+        FindConfigurations {
+            root,
+            entity,
+            result: Vec::default(),
+        }
+    }
+}
+
+impl<'a> Searcher for FindConfigurations<'a> {
+    fn search_decl(&mut self, _ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        if let FoundDeclaration::Configuration(config) = decl {
+            if config.entity_name.item.get_suffix_reference() == Some(self.entity) {
+                if let Some(id) = config.ident.decl.get() {
+                    self.result.push(self.root.get_ent(id));
+                }
+            }
+        }
+        SearchState::NotFinished
+    }
+}
+
 // Search for a declaration/definition and format it
 pub struct FormatDeclaration<'a> {
     ent: EntRef<'a>,
@@ -1665,7 +1750,7 @@ impl<'a> Searcher for FindAllReferences<'a> {
 }
 
 impl<'a> FoundDeclaration<'a> {
-    fn end_ident_pos(&self) -> Option<TokenId> {
+    pub(crate) fn end_ident_pos(&self) -> Option<TokenId> {
         match self {
             FoundDeclaration::InterfaceObject(_) => None,
             FoundDeclaration::ForIndex(..) => None,
@@ -1879,6 +1964,49 @@ impl Searcher for FindAllUnresolved {
     }
 }
 
+/// Find the references to design units, such as the packages of use clauses,
+/// the contexts of context references and the entities of instantiations
+pub struct FindDesignUnitReferences<'a> {
+    root: &'a DesignRoot,
+    pub references: Vec<(SrcPos, EntRef<'a>)>,
+}
+
+impl<'a> FindDesignUnitReferences<'a> {
+    pub fn new(root: &'a DesignRoot) -> FindDesignUnitReferences<'a> {
+        FindDesignUnitReferences {
+            root,
+            references: Vec::new(),
+        }
+    }
+}
+
+impl<'a> Searcher for FindDesignUnitReferences<'a> {
+    fn search_pos_with_ref(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        reference: &Reference,
+    ) -> SearchState {
+        if let Some(id) = reference.get() {
+            let ent = self.root.get_ent(id);
+            if matches!(
+                ent.kind(),
+                AnyEntKind::Design(
+                    Design::Entity(..)
+                        | Design::Configuration
+                        | Design::Package(..)
+                        | Design::UninstPackage(..)
+                        | Design::PackageInstance(..)
+                        | Design::Context(..)
+                )
+            ) {
+                self.references.push((pos.clone(), ent));
+            }
+        }
+        NotFinished
+    }
+}
+
 pub fn clear_references(tree: &mut impl Search, ctx: &dyn TokenAccess) {
     struct ReferenceClearer;
 
@@ -0,0 +1,281 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Who calls a subprogram and which subprograms it calls
+
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::data::SrcPos;
+use crate::named_entity::{
+    AnyEntKind, Concurrent, Design, EntRef, EntityId, HasEntityId, Overloaded, Reference,
+};
+use crate::syntax::TokenAccess;
+use fnv::FnvHashSet;
+
+/// The calls from a caller or to a callee
+#[derive(Clone, Debug)]
+pub struct Calls<'a> {
+    /// The caller of incoming calls or the callee of outgoing calls.
+    /// A caller is a subprogram body, a process or a design unit.
+    pub ent: EntRef<'a>,
+    /// The names of the called subprogram at the call sites
+    pub positions: Vec<SrcPos>,
+}
+
+/// Whether `ent` is a function or procedure
+pub(crate) fn is_subprogram(ent: EntRef) -> bool {
+    matches!(
+        ent.as_actual().kind(),
+        AnyEntKind::Overloaded(overloaded) if !matches!(overloaded, Overloaded::EnumLiteral(..))
+    )
+}
+
+/// The subprograms that call `ent`, or the processes and design units
+/// when a call is not within a subprogram
+pub(crate) fn incoming_calls<'a>(root: &'a DesignRoot, ent: EntRef<'a>) -> Vec<Calls<'a>> {
+    let graph = CallGraph::new(root);
+    let callee = ent.as_actual().declaration();
+    let mut calls: Vec<Calls> = Vec::new();
+    for (pos, other) in graph.calls.iter() {
+        if other.id() != callee.id() {
+            continue;
+        }
+        if let Some(caller) = graph.caller_of(pos) {
+            add_call(&mut calls, caller, pos);
+        }
+    }
+    calls
+}
+
+/// The subprograms that are called within the body of `ent`
+pub(crate) fn outgoing_calls<'a>(root: &'a DesignRoot, ent: EntRef<'a>) -> Vec<Calls<'a>> {
+    let graph = CallGraph::new(root);
+    let caller = ent.as_actual().declaration();
+    let mut calls: Vec<Calls> = Vec::new();
+    for (pos, callee) in graph.calls.iter() {
+        if graph
+            .caller_of(pos)
+            .is_some_and(|other| other.as_actual().declaration().id() == caller.id())
+        {
+            add_call(&mut calls, callee, pos);
+        }
+    }
+    calls
+}
+
+fn add_call<'a>(calls: &mut Vec<Calls<'a>>, ent: EntRef<'a>, pos: &SrcPos) {
+    if let Some(existing) = calls.iter_mut().find(|calls| calls.ent.id() == ent.id()) {
+        existing.positions.push(pos.clone());
+    } else {
+        calls.push(Calls {
+            ent,
+            positions: vec![pos.clone()],
+        });
+    }
+}
+
+struct CallGraph<'a> {
+    root: &'a DesignRoot,
+    /// The extent of each caller
+    scopes: Vec<(SrcPos, EntRef<'a>)>,
+    /// The name of the subprogram at each call site together with the declaration it calls
+    calls: Vec<(SrcPos, EntRef<'a>)>,
+    /// The identifiers and end labels of declarations, which are not calls
+    declarations: FnvHashSet<SrcPos>,
+    found: FnvHashSet<(SrcPos, EntityId)>,
+}
+
+impl<'a> CallGraph<'a> {
+    fn new(root: &'a DesignRoot) -> CallGraph<'a> {
+        let mut graph = CallGraph {
+            root,
+            scopes: Vec::new(),
+            calls: Vec::new(),
+            declarations: FnvHashSet::default(),
+            found: FnvHashSet::default(),
+        };
+        let _ = root.search(&mut graph);
+        let declarations = std::mem::take(&mut graph.declarations);
+        graph.calls.retain(|(pos, _)| !declarations.contains(pos));
+        graph
+    }
+
+    /// The innermost named caller that encloses `pos`
+    fn caller_of(&self, pos: &SrcPos) -> Option<EntRef<'a>> {
+        self.scopes
+            .iter()
+            .filter(|(scope, _)| {
+                scope.source == pos.source
+                    && scope.start() <= pos.start()
+                    && pos.end() <= scope.end()
+            })
+            .max_by_key(|(scope, _)| scope.start())
+            .map(|(_, ent)| *ent)
+    }
+}
+
+/// Whether calls within the declaration of `ent` are attributed to it
+fn is_caller(ent: EntRef) -> bool {
+    if ent.decl_pos().is_none() {
+        // Calls within anonymous processes belong to the enclosing design unit
+        return false;
+    }
+    matches!(
+        ent.kind(),
+        AnyEntKind::Overloaded(Overloaded::Subprogram(..) | Overloaded::UninstSubprogram(..))
+            | AnyEntKind::Concurrent(Some(Concurrent::Process))
+            | AnyEntKind::Design(
+                Design::Entity(..)
+                    | Design::Architecture(..)
+                    | Design::Package(..)
+                    | Design::PackageBody
+                    | Design::UninstPackage(..)
+            )
+    )
+}
+
+impl<'a> Searcher for CallGraph<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        if let Some(id) = decl.ent_id() {
+            let ent = self.root.get_ent(id);
+            if let Some(decl_pos) = ent.decl_pos() {
+                self.declarations.insert(decl_pos.clone());
+            }
+            if let Some(pos) = decl.end_ident_pos() {
+                self.declarations.insert(ctx.get_pos(pos).clone());
+            }
+            if is_caller(ent) {
+                let span = ent.src_span.pos(ctx);
+                if !self.scopes.iter().any(|(other, _)| *other == span) {
+                    self.scopes.push((span, ent));
+                }
+            }
+        }
+        NotFinished
+    }
+
+    fn search_pos_with_ref(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        reference: &Reference,
+    ) -> SearchState {
+        if let Some(id) = reference.get() {
+            let ent = self.root.get_ent(id);
+            if is_subprogram(ent) {
+                let callee = ent.as_actual().declaration();
+                // The same position is found through every library that a file is mapped to
+                if self.found.insert((pos.clone(), callee.id())) {
+                    self.calls.push((pos.clone(), callee));
+                }
+            }
+        }
+        NotFinished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::syntax::test::Code;
+
+    fn summary(calls: Vec<Calls>) -> Vec<(String, Vec<SrcPos>)> {
+        calls
+            .into_iter()
+            .map(|calls| (calls.ent.designator().to_string(), calls.positions))
+            .collect()
+    }
+
+    fn code() -> (LibraryBuilder, Code) {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+package pkg is
+  function inc(value : natural) return natural;
+  procedure update(signal target : out natural);
+end package;
+
+package body pkg is
+  function inc(value : natural) return natural is
+  begin
+    return value + 1;
+  end function;
+
+  procedure update(signal target : out natural) is
+  begin
+    target <= inc(inc(0));
+  end procedure;
+end package body;
+
+use work.pkg.all;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal counter : natural;
+begin
+  main: process
+  begin
+    update(counter);
+    wait;
+  end process;
+
+  counter <= inc(1);
+end architecture;",
+        );
+        (builder, code)
+    }
+
+    #[test]
+    fn finds_callers_of_subprogram() {
+        let (builder, code) = code();
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let inc = root
+            .search_reference(code.source(), code.s1("inc").start())
+            .unwrap();
+        assert_eq!(
+            summary(incoming_calls(&root, inc)),
+            vec![
+                (
+                    "update".to_owned(),
+                    vec![code.s("inc", 3).pos(), code.s("inc", 4).pos()]
+                ),
+                ("a".to_owned(), vec![code.s("inc", 5).pos()]),
+            ]
+        );
+
+        let update = root
+            .search_reference(code.source(), code.s1("update").start())
+            .unwrap();
+        assert_eq!(
+            summary(incoming_calls(&root, update)),
+            vec![("main".to_owned(), vec![code.s("update", 3).pos()])]
+        );
+    }
+
+    #[test]
+    fn finds_callees_of_subprogram() {
+        let (builder, code) = code();
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let update = root
+            .search_reference(code.source(), code.s1("update").start())
+            .unwrap();
+        assert_eq!(
+            summary(outgoing_calls(&root, update)),
+            vec![(
+                "inc".to_owned(),
+                vec![code.s("inc", 3).pos(), code.s("inc", 4).pos()]
+            )]
+        );
+    }
+}
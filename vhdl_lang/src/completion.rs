@@ -7,10 +7,15 @@
 use crate::analysis::DesignRoot;
 use crate::ast::search::{Found, FoundDeclaration, NotFinished, NotFound, SearchState, Searcher};
 use crate::ast::{
-    AnyDesignUnit, AnyPrimaryUnit, ConcurrentStatement, Designator, MapAspect, ObjectClass,
+    AnyDesignUnit, AnyPrimaryUnit, AttributeDesignator, ConcurrentStatement, Designator, HasUnitId,
+    MapAspect, ObjectClass, RangeAttribute, SignalAttribute, TypeAttribute,
 };
 use crate::data::{ContentReader, Symbol};
-use crate::named_entity::{self, AsUnique, DesignEnt, HasEntityId, NamedEntities, Region};
+use crate::named_entity::{
+    self, AsUnique, DesignEnt, HasEntityId, NamedEntities, Region, Type, TypeEnt,
+};
+use crate::refactor::enum_literals;
+use crate::snippet::{snippet_context, templates, Snippet};
 use crate::syntax::Kind::*;
 use crate::syntax::{Kind, Symbols, Token, TokenAccess, Tokenizer, Value};
 use crate::{AnyEntKind, Design, EntRef, EntityId, HasTokenSpan, Overloaded, Position, Source};
@@ -46,6 +51,19 @@ pub enum CompletionItem<'a> {
     /// The second argument is a vector of architectures that are associated
     /// to this entity
     EntityInstantiation(EntRef<'a>, Vec<EntRef<'a>>),
+    /// A predefined attribute after the tick of a name, e.g. `sig'event`
+    Attribute(AttributeDesignator),
+    /// A snippet of the config
+    Snippet(&'a Snippet),
+    /// A built-in template, which is only useful for clients that support snippets
+    Template(&'a Snippet),
+    /// An aggregate that associates every element of a record type, i.e.,
+    /// ```vhdl
+    /// (valid => , data => )
+    /// ```
+    ///
+    /// The first argument is the record type and the second its elements
+    RecordAggregate(EntRef<'a>, Vec<EntRef<'a>>),
 }
 
 macro_rules! kind {
@@ -244,7 +262,7 @@ impl<'a> Searcher for MapAspectSearcher<'a> {
 /// `tokenize_input(input)` -> {USE, ieee, DOT, std_logic_1164, DOT, a}
 ///
 /// On error, or if the source is empty, returns an empty vector.
-fn tokenize_input(symbols: &Symbols, source: &Source, cursor: Position) -> Vec<Token> {
+pub(crate) fn tokenize_input(symbols: &Symbols, source: &Source, cursor: Position) -> Vec<Token> {
     let contents = source.contents();
     let mut tokenizer = Tokenizer::new(symbols, source, ContentReader::new(&contents));
     let mut tokens = Vec::new();
@@ -490,6 +508,19 @@ pub fn list_completion_options<'a>(
     root: &'a DesignRoot,
     source: &Source,
     cursor: Position,
+) -> Vec<CompletionItem<'a>> {
+    list_completion_options_with_snippets(root, &[], false, source, cursor)
+}
+
+/// Lists the completion options together with the snippets, and optionally the built-in
+/// templates, that are offered at the cursor position.
+/// Snippets are not offered when completing a selected name.
+pub(crate) fn list_completion_options_with_snippets<'a>(
+    root: &'a DesignRoot,
+    snippets: &'a [Snippet],
+    with_templates: bool,
+    source: &Source,
+    cursor: Position,
 ) -> Vec<CompletionItem<'a>> {
     let tokens = tokenize_input(root.symbols(), source, cursor);
     match &tokens[..] {
@@ -504,17 +535,568 @@ pub fn list_completion_options<'a>(
         | [.., kind!(Use), ident!(library), kind!(Dot), ident!(selected), kind!(Dot), kind!(StringLiteral | Identifier)] => {
             list_available_declarations(root, library, selected)
         }
-        [.., kind!(LeftPar | Comma)] | [.., kind!(LeftPar | Comma), kind!(Identifier)] => {
+        [prefix @ .., kind!(Dot)] | [prefix @ .., kind!(Dot), kind!(Identifier)] => {
+            match list_record_elements(root, source, cursor, prefix) {
+                Some(elements) => elements,
+                None => list_visible_completions(
+                    root,
+                    snippets,
+                    with_templates,
+                    &tokens,
+                    source,
+                    cursor,
+                ),
+            }
+        }
+        [prefix @ .., kind!(Tick)] | [prefix @ .., kind!(Tick), kind!(Identifier)] => {
+            list_attributes(root, source, cursor, prefix).unwrap_or_default()
+        }
+        [.., kind!(When | Bar)] | [.., kind!(When | Bar), kind!(Identifier)] => {
+            match list_uncovered_literals(root, source, cursor, &tokens) {
+                Some(literals) => literals,
+                None => list_visible_completions(
+                    root,
+                    snippets,
+                    with_templates,
+                    &tokens,
+                    source,
+                    cursor,
+                ),
+            }
+        }
+        [prefix @ .., kind!(LTE | ColonEq)]
+        | [prefix @ .., kind!(LTE | ColonEq), kind!(Identifier)] => {
+            let mut completions =
+                list_visible_completions(root, snippets, with_templates, &tokens, source, cursor);
+            if let Some(aggregate) = record_aggregate(root, source, cursor, prefix) {
+                completions.insert(0, aggregate);
+            }
+            completions
+        }
+        [.., kind!(Entity), ident!(library), kind!(Dot), ident!(entity), kind!(LeftPar)]
+        | [.., kind!(Entity), ident!(library), kind!(Dot), ident!(entity), kind!(LeftPar), kind!(Identifier)] => {
+            list_architectures(root, source, library, entity)
+        }
+        [.., kind!(LeftPar | Comma)] | [.., kind!(LeftPar | Comma), kind!(Identifier)]
+            if is_formal_of_map_aspect(&tokens) =>
+        {
             let mut searcher = MapAspectSearcher::new(root, cursor);
-            let _ = root.search_source(source, &mut searcher);
-            searcher.completions
+            if matches!(root.search_source(source, &mut searcher), Found) {
+                searcher.completions
+            } else {
+                // The map aspect of an incomplete instantiation is not known
+                list_visible_completions(root, snippets, with_templates, &tokens, source, cursor)
+            }
+        }
+        _ => list_visible_completions(root, snippets, with_templates, &tokens, source, cursor),
+    }
+}
+
+/// Lists the elements of the record that `prefix` denotes, e.g. `rec.inner` or `arr(0)`.
+/// Returns `None` when the prefix is not an object of a record type.
+fn list_record_elements<'a>(
+    root: &'a DesignRoot,
+    source: &Source,
+    cursor: Position,
+    prefix: &[Token],
+) -> Option<Vec<CompletionItem<'a>>> {
+    let (_, names) = selected_names(prefix)?;
+    let typ = type_of_name(root, source, cursor, &names)?;
+    // Record elements of an access type are selected from the accessed object
+    let record = typ.accessed_type().unwrap_or(typ);
+    let Type::Record(region) = record.base_type().kind() else {
+        return None;
+    };
+    Some(
+        region
+            .iter()
+            .map(|elem| CompletionItem::Simple(elem.into()))
+            .collect(),
+    )
+}
+
+/// The aggregate of the record type of the target of an assignment such as `rec.inner <= `
+/// or of the type of a declaration such as `constant c : rec_t := `.
+/// Returns `None` when the target is not of a record type or `<=` is a comparison.
+fn record_aggregate<'a>(
+    root: &'a DesignRoot,
+    source: &Source,
+    cursor: Position,
+    target: &[Token],
+) -> Option<CompletionItem<'a>> {
+    let (start, names) = selected_names(target)?;
+    if start > 0
+        && !matches!(
+            target[start - 1].kind,
+            SemiColon | Colon | Begin | Then | Else | RightArrow | Loop | Generate
+        )
+    {
+        return None;
+    }
+
+    let typ = match names[..] {
+        [(name, 0)] => declared_type(root, source, cursor, name),
+        _ => None,
+    }
+    .or_else(|| type_of_name(root, source, cursor, &names))?;
+    let Type::Record(region) = typ.base_type().kind() else {
+        return None;
+    };
+    Some(CompletionItem::RecordAggregate(
+        typ.into(),
+        region.iter().map(|elem| elem.into()).collect(),
+    ))
+}
+
+/// The type that a type mark such as `rec_t` denotes
+fn declared_type<'a>(
+    root: &'a DesignRoot,
+    source: &Source,
+    cursor: Position,
+    name: &Token,
+) -> Option<TypeEnt<'a>> {
+    if let Some(ent) = root.search_reference(source, name.pos.start()) {
+        return TypeEnt::from_any(ent);
+    }
+    let Value::Identifier(symbol) = &name.value else {
+        return None;
+    };
+    let designator = Designator::Identifier(symbol.clone());
+    declarations_before(root, source, cursor)
+        .into_iter()
+        .filter(|ent| ent.designator() == &designator)
+        .find_map(TypeEnt::from_any)
+}
+
+/// Lists the literals of the enumeration type of the case statement at the cursor
+/// that are not a choice of the alternatives before the cursor.
+/// Returns `None` when the cursor is not at the choices of a case alternative.
+fn list_uncovered_literals<'a>(
+    root: &'a DesignRoot,
+    source: &Source,
+    cursor: Position,
+    tokens: &[Token],
+) -> Option<Vec<CompletionItem<'a>>> {
+    let tokens = match tokens {
+        [tokens @ .., kind!(Identifier)] => tokens,
+        _ => tokens,
+    };
+    // The choices such as `when a | b` before the cursor follow the end of a statement
+    let when = tokens.iter().rposition(|token| token.kind == When)?;
+    if !tokens[when + 1..]
+        .iter()
+        .all(|token| matches!(token.kind, Identifier | Character | Bar))
+        || !matches!(tokens.get(when.checked_sub(1)?)?.kind, Is | SemiColon)
+    {
+        return None;
+    }
+
+    let mut covered = Vec::new();
+    let mut depth = 0;
+    let mut case = None;
+    for (idx, token) in tokens.iter().enumerate().rev() {
+        match token.kind {
+            Case if idx > 0 && tokens[idx - 1].kind == End => depth += 1,
+            Case if depth == 0 => {
+                case = Some(idx);
+                break;
+            }
+            Case => depth -= 1,
+            When | Bar if depth == 0 => {
+                // Only the complete choices of the alternatives
+                if let [choice, kind!(RightArrow | Bar), ..] = &tokens[idx + 1..] {
+                    match &choice.value {
+                        Value::Identifier(symbol) => {
+                            covered.push(Designator::Identifier(symbol.clone()))
+                        }
+                        Value::Character(chr) => covered.push(Designator::Character(*chr)),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let case = case?;
+    let is = case + tokens[case..].iter().position(|token| token.kind == Is)?;
+    let expression = &tokens[case + 1..is];
+    let (start, names) = selected_names(expression)?;
+    if start != 0 {
+        return None;
+    }
+    let typ = type_of_name(root, source, cursor, &names)?.base_type();
+    if !matches!(typ.kind(), Type::Enum(_)) {
+        return None;
+    }
+    Some(
+        enum_literals(root, typ)
+            .into_iter()
+            .filter(|literal| !covered.contains(literal.designator()))
+            .map(CompletionItem::Simple)
+            .collect(),
+    )
+}
+
+/// Lists the attributes of the type or object that `prefix` denotes,
+/// followed by the user-defined attributes that are declared before the cursor
+fn list_attributes<'a>(
+    root: &'a DesignRoot,
+    source: &Source,
+    cursor: Position,
+    prefix: &[Token],
+) -> Option<Vec<CompletionItem<'a>>> {
+    use AttributeDesignator::*;
+
+    let (_, names) = selected_names(prefix)?;
+    let (last, indexes) = names.last()?;
+    let type_mark = if *indexes == 0 {
+        root.search_reference(source, last.pos.start())
+            .and_then(TypeEnt::from_any)
+    } else {
+        None
+    };
+
+    let mut attributes = Vec::new();
+    let typ = match type_mark {
+        Some(typ) => {
+            if typ.is_scalar() {
+                attributes.extend([
+                    Left,
+                    Right,
+                    High,
+                    Low,
+                    Ascending,
+                    Image,
+                    AttributeDesignator::Value,
+                ]);
+                if typ.base().is_discrete() || typ.base().is_physical() {
+                    attributes.extend([Pos, Val, Succ, Pred, LeftOf, RightOf]);
+                }
+            }
+            typ
+        }
+        None => {
+            let typ = type_of_name(root, source, cursor, &names)?;
+            attributes.push(AttributeDesignator::Type(TypeAttribute::Subtype));
+            let (first, _) = names.first()?;
+            if is_signal(root, source, cursor, first) {
+                attributes.extend(
+                    [
+                        SignalAttribute::Event,
+                        SignalAttribute::Active,
+                        SignalAttribute::LastEvent,
+                        SignalAttribute::LastActive,
+                        SignalAttribute::LastValue,
+                        SignalAttribute::Stable,
+                        SignalAttribute::Quiet,
+                        SignalAttribute::Delayed,
+                        SignalAttribute::Transaction,
+                        SignalAttribute::Driving,
+                        SignalAttribute::DrivingValue,
+                    ]
+                    .map(Signal),
+                );
+            }
+            typ
+        }
+    };
+    if typ.array_type().is_some() {
+        attributes.extend([
+            Left,
+            Right,
+            High,
+            Low,
+            Ascending,
+            Length,
+            AttributeDesignator::Range(RangeAttribute::Range),
+            AttributeDesignator::Range(RangeAttribute::ReverseRange),
+            AttributeDesignator::Type(TypeAttribute::Element),
+        ]);
+    }
+    attributes.extend([SimpleName, InstanceName, PathName]);
+
+    let mut completions: Vec<CompletionItem> = attributes
+        .into_iter()
+        .map(CompletionItem::Attribute)
+        .collect();
+    completions.extend(
+        declarations_before(root, source, cursor)
+            .into_iter()
+            .filter(|ent| matches!(ent.kind(), AnyEntKind::Attribute(_)))
+            .map(CompletionItem::Simple),
+    );
+    Some(completions)
+}
+
+/// Whether the first name of a prefix denotes a signal
+fn is_signal(root: &DesignRoot, source: &Source, cursor: Position, name: &Token) -> bool {
+    let ent = root
+        .search_reference(source, name.pos.start())
+        .or_else(|| match &name.value {
+            Value::Identifier(symbol) => declared_object(root, source, cursor, symbol),
+            _ => None,
+        });
+    matches!(
+        ent.map(|ent| ent.kind()),
+        Some(AnyEntKind::Object(object)) if object.class == ObjectClass::Signal
+    )
+}
+
+/// The type of the object that a name such as `rec.inner` or `arr(0)` denotes
+fn type_of_name<'a>(
+    root: &'a DesignRoot,
+    source: &Source,
+    cursor: Position,
+    names: &[(&Token, usize)],
+) -> Option<TypeEnt<'a>> {
+    // The names of complete code are resolved, but the name before the cursor
+    // is typically not analyzed yet
+    let (mut idx, ent) = names
+        .iter()
+        .enumerate()
+        .rev()
+        .find_map(|(idx, (name, _))| {
+            let ent = root.search_reference(source, name.pos.start())?;
+            Some((idx, ent))
+        })
+        .or_else(|| {
+            let Value::Identifier(symbol) = &names.first()?.0.value else {
+                return None;
+            };
+            Some((0, declared_object(root, source, cursor, symbol)?))
+        })?;
+
+    let mut typ = type_of_object(ent)?;
+    loop {
+        for _ in 0..names[idx].1 {
+            typ = typ.array_type()?.0;
+        }
+
+        idx += 1;
+        let Some((name, _)) = names.get(idx) else {
+            return Some(typ);
+        };
+        let record = typ.accessed_type().unwrap_or(typ);
+        let Type::Record(region) = record.base_type().kind() else {
+            return None;
+        };
+        let Value::Identifier(symbol) = &name.value else {
+            return None;
+        };
+        typ = region
+            .lookup(&Designator::Identifier(symbol.clone()))?
+            .type_mark();
+    }
+}
+
+/// The names of a prefix such as `a.b(0).c` at the end of `tokens` together with the number
+/// of index lists after each name, and the index of the first token of the prefix
+fn selected_names(prefix: &[Token]) -> Option<(usize, Vec<(&Token, usize)>)> {
+    let mut names = Vec::new();
+    let mut end = prefix.len();
+    loop {
+        let mut indexes = 0;
+        while end > 0 && prefix[end - 1].kind == RightPar {
+            end = matching_left_par(&prefix[..end])?;
+            indexes += 1;
+        }
+        let name = prefix.get(end.checked_sub(1)?)?;
+        if name.kind != Identifier {
+            return None;
+        }
+        end -= 1;
+        names.push((name, indexes));
+
+        if end > 0 && prefix[end - 1].kind == Dot {
+            end -= 1;
+        } else {
+            break;
+        }
+    }
+    names.reverse();
+    Some((end, names))
+}
+
+/// The index of the left parenthesis that matches the right parenthesis at the end of `tokens`
+fn matching_left_par(tokens: &[Token]) -> Option<usize> {
+    let mut depth = 0;
+    for (idx, token) in tokens.iter().enumerate().rev() {
+        match token.kind {
+            RightPar => depth += 1,
+            LeftPar => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn type_of_object(ent: EntRef) -> Option<TypeEnt> {
+    match ent.kind() {
+        AnyEntKind::Object(object) => Some(object.subtype.type_mark()),
+        AnyEntKind::ObjectAlias { type_mark, .. } => Some(*type_mark),
+        AnyEntKind::ElementDeclaration(subtype) => Some(subtype.type_mark()),
+        _ => None,
+    }
+}
+
+/// The object named `symbol` that is declared last before the cursor,
+/// or a port or generic of the entity of the architecture at the cursor
+fn declared_object<'a>(
+    root: &'a DesignRoot,
+    source: &Source,
+    cursor: Position,
+    symbol: &Symbol,
+) -> Option<EntRef<'a>> {
+    let designator = Designator::Identifier(symbol.clone());
+    declarations_before(root, source, cursor)
+        .into_iter()
+        .filter(|ent| ent.designator() == &designator && type_of_object(ent).is_some())
+        .max_by_key(|ent| ent.decl_pos().map(|pos| pos.start()))
+}
+
+/// The declarations before the cursor within the regions that enclose it,
+/// together with the ports and generics of the entity of the architecture at the cursor
+fn declarations_before<'a>(
+    root: &'a DesignRoot,
+    source: &Source,
+    cursor: Position,
+) -> Vec<EntRef<'a>> {
+    let mut searcher = DeclarationsBefore {
+        root,
+        cursor,
+        declarations: Vec::new(),
+    };
+    let _ = root.search_source(source, &mut searcher);
+    searcher.declarations
+}
+
+struct DeclarationsBefore<'a> {
+    root: &'a DesignRoot,
+    cursor: Position,
+    declarations: Vec<EntRef<'a>>,
+}
+
+impl<'a> Searcher for DeclarationsBefore<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        let Some(ent) = decl.ent_id().map(|id| self.root.get_ent(id)) else {
+            return NotFinished;
+        };
+        if let FoundDeclaration::Architecture(body) = decl {
+            if body.span.pos(ctx).contains(self.cursor) {
+                if let AnyEntKind::Design(Design::Architecture(entity)) = ent.kind() {
+                    if let Design::Entity(_, region) = entity.kind() {
+                        self.declarations
+                            .extend(region.entities.values().filter_map(|ent| match ent {
+                                NamedEntities::Single(ent) => Some(*ent),
+                                NamedEntities::Overloaded(_) => None,
+                            }));
+                    }
+                }
+            }
+        } else if ent.decl_pos().is_some_and(|pos| pos.start() < self.cursor)
+            // Only the declarations of the enclosing regions are visible
+            && ent
+                .parent
+                .is_some_and(|parent| parent.src_span.pos(ctx).contains(self.cursor))
+        {
+            self.declarations.push(ent);
+        }
+        NotFinished
+    }
+}
+
+/// Lists the architectures of the entity of a direct instantiation such as `entity work.foo(`.
+/// The library `work` is any library that `source` is mapped to.
+fn list_architectures<'a>(
+    root: &'a DesignRoot,
+    source: &Source,
+    library: &Symbol,
+    entity: &Symbol,
+) -> Vec<CompletionItem<'a>> {
+    let mut libraries: HashSet<&Symbol> = HashSet::new();
+    if *library == root.symbol_utf8("work") {
+        libraries.extend(
+            root.units_by_source(source)
+                .map(|unit| unit.unit_id().library_name()),
+        );
+    } else {
+        libraries.insert(library);
+    }
+
+    root.libraries()
+        .filter(|lib| libraries.contains(lib.name()))
+        .flat_map(|lib| lib.secondary_units(entity))
+        .filter_map(|unit| unit.unit.get().and_then(|unit| unit.ent_id()))
+        .map(|id| root.get_ent(id))
+        .filter(|ent| matches!(ent.kind(), AnyEntKind::Design(Design::Architecture(..))))
+        .map(CompletionItem::Simple)
+        .collect()
+}
+
+/// Whether the innermost unclosed parenthesis before the cursor is that of a map aspect,
+/// i.e. the cursor is at a formal rather than within an actual such as `f(`
+fn is_formal_of_map_aspect(tokens: &[Token]) -> bool {
+    let mut depth = 0;
+    for (idx, token) in tokens.iter().enumerate().rev() {
+        match token.kind {
+            RightPar => depth += 1,
+            LeftPar if depth == 0 => {
+                return idx > 0 && tokens[idx - 1].kind == Map;
+            }
+            LeftPar => depth -= 1,
+            _ => {}
         }
-        _ => {
-            let mut searcher = CompletionSearcher::new(cursor, root);
-            let _ = root.search_source(source, &mut searcher);
-            searcher.completions
+    }
+    false
+}
+
+/// The declarations that are visible at the cursor together with the snippets
+fn list_visible_completions<'a>(
+    root: &'a DesignRoot,
+    snippets: &'a [Snippet],
+    with_templates: bool,
+    tokens: &[Token],
+    source: &Source,
+    cursor: Position,
+) -> Vec<CompletionItem<'a>> {
+    let mut searcher = CompletionSearcher::new(cursor, root);
+    let _ = root.search_source(source, &mut searcher);
+    let mut completions = searcher.completions;
+    if (with_templates || !snippets.is_empty())
+        && !matches!(
+            tokens[..],
+            [.., kind!(Dot)] | [.., kind!(Dot), kind!(Identifier)]
+        )
+    {
+        let context = snippet_context(root, source, cursor);
+        completions.extend(
+            snippets
+                .iter()
+                .filter(|snippet| snippet.is_offered_in(context))
+                .map(CompletionItem::Snippet),
+        );
+        if with_templates {
+            // The snippets of the config replace the templates of the same prefix
+            completions.extend(
+                templates()
+                    .iter()
+                    .filter(|template| {
+                        template.is_offered_in(context)
+                            && !snippets
+                                .iter()
+                                .any(|snippet| snippet.prefix == template.prefix)
+                    })
+                    .map(CompletionItem::Template),
+            );
         }
     }
+    completions
 }
 
 #[cfg(test)]
@@ -522,6 +1104,7 @@ mod test {
     use super::*;
     use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
     use crate::completion::tokenize_input;
+    use crate::snippet::SnippetContext;
     use crate::syntax::test::{assert_eq_unordered, Code};
     use assert_matches::assert_matches;
 
@@ -724,6 +1307,279 @@ mod test {
         );
     }
 
+    #[test]
+    pub fn completes_visible_names_within_actuals() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity child is
+  port (a : in natural; b : out natural);
+end entity;
+
+entity ent is
+end entity;
+
+architecture arch of ent is
+  function inc(value : natural) return natural is
+  begin
+    return value + 1;
+  end function;
+
+  signal sig : natural;
+begin
+  inst: entity work.child
+    port map (a => inc(sig), b => sig);
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let sig = root
+            .search_reference(code.source(), code.s1("sig :").start())
+            .unwrap();
+        let options = list_completion_options(&root, code.source(), code.s1("=> inc(").end());
+        assert!(options.contains(&CompletionItem::Simple(sig)));
+        assert!(!options
+            .iter()
+            .any(|option| matches!(option, CompletionItem::Formal(_))));
+    }
+
+    #[test]
+    pub fn completes_architectures_of_instantiated_entity() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity child is
+end entity;
+
+architecture rtl of child is
+begin
+end architecture;
+
+architecture sim of child is
+begin
+end architecture;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+begin
+  inst: entity work.child(rtl);
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let rtl = root
+            .search_reference(code.source(), code.s1("rtl").start())
+            .unwrap();
+        let sim = root
+            .search_reference(code.source(), code.s1("sim").start())
+            .unwrap();
+        let options = list_completion_options(&root, code.source(), code.s1("child(").end());
+        assert_eq_unordered(
+            &options,
+            &[CompletionItem::Simple(rtl), CompletionItem::Simple(sim)],
+        );
+    }
+
+    #[test]
+    pub fn completes_elements_of_records() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+package pkg is
+  type inner_t is record
+    valid : bit;
+    data : natural;
+  end record;
+
+  type outer_t is record
+    inner : inner_t;
+    count : natural;
+  end record;
+
+  type outer_array_t is array (0 to 1) of outer_t;
+end package;
+
+use work.pkg.all;
+
+entity ent is
+  port (p : in outer_t);
+end entity;
+
+architecture a of ent is
+  signal arr : outer_array_t;
+  alias al : inner_t is arr(0).inner;
+  signal n : natural;
+begin
+  n <= p.inner.data;
+  n <= arr(1).count;
+  n <= al.data;
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let elements_at = |substr: &str| -> Vec<String> {
+            list_completion_options(&root, code.source(), code.s1(substr).end())
+                .into_iter()
+                .map(|option| match option {
+                    CompletionItem::Simple(ent) => ent.designator().to_string(),
+                    _ => panic!("Expected a record element"),
+                })
+                .collect()
+        };
+        assert_eq!(elements_at("p."), vec!["inner", "count"]);
+        assert_eq!(elements_at("p.inner."), vec!["valid", "data"]);
+        assert_eq!(elements_at("arr(1)."), vec!["inner", "count"]);
+        assert_eq!(elements_at("al."), vec!["valid", "data"]);
+    }
+
+    #[test]
+    pub fn completes_aggregate_of_record_target() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture a of ent is
+  type pair_t is record
+    valid : bit;
+    data : natural;
+  end record;
+  type pair_array_t is array (0 to 1) of pair_t;
+
+  constant init : pair_t := ('0', 0);
+  signal pairs : pair_array_t;
+  signal b : boolean;
+begin
+  pairs(0) <= init;
+  b <= pairs(1).data <= 3;
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let aggregate_at = |substr: &str| -> Option<Vec<String>> {
+            match list_completion_options(&root, code.source(), code.s1(substr).end()).first() {
+                Some(CompletionItem::RecordAggregate(typ, elements)) => {
+                    assert_eq!(typ.designator().to_string(), "pair_t");
+                    Some(
+                        elements
+                            .iter()
+                            .map(|elem| elem.designator().to_string())
+                            .collect(),
+                    )
+                }
+                _ => None,
+            }
+        };
+        let elements = Some(vec!["valid".to_owned(), "data".to_owned()]);
+        assert_eq!(aggregate_at("pair_t :="), elements);
+        assert_eq!(aggregate_at("pairs(0) <="), elements);
+        assert_eq!(aggregate_at("b <="), None);
+        assert_eq!(aggregate_at("pairs(1).data <="), None);
+    }
+
+    #[test]
+    pub fn completes_uncovered_literals_of_case_statement() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture a of ent is
+  type state_t is (idle, busy, done, failed);
+  signal state : state_t;
+begin
+  process
+  begin
+    case state is
+      when idle =>
+        null;
+      when busy | done =>
+        null;
+      when others =>
+        null;
+    end case;
+    wait;
+  end process;
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let literals_at = |cursor: Position| -> Vec<String> {
+            list_completion_options(&root, code.source(), cursor)
+                .into_iter()
+                .map(|option| match option {
+                    CompletionItem::Simple(ent) => ent.designator().to_string(),
+                    _ => panic!("Expected an enumeration literal"),
+                })
+                .collect()
+        };
+        assert_eq!(literals_at(code.s1("busy |").end()), vec!["done", "failed"]);
+        assert_eq!(
+            literals_at(code.s1("when others").s1("when").end()),
+            vec!["failed"]
+        );
+    }
+
+    #[test]
+    pub fn completes_attributes_after_tick() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture a of ent is
+  attribute keep : boolean;
+  signal data : bit_vector(7 downto 0);
+  constant max : natural := natural'high;
+begin
+  process
+  begin
+    wait until data'event;
+  end process;
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let attributes_at = |cursor: Position| -> Vec<String> {
+            list_completion_options(&root, code.source(), cursor)
+                .into_iter()
+                .map(|option| match option {
+                    CompletionItem::Attribute(attribute) => attribute.to_string(),
+                    CompletionItem::Simple(ent) => ent.designator().to_string(),
+                    _ => panic!("Expected an attribute"),
+                })
+                .collect()
+        };
+        let of_signal = attributes_at(code.s1("data'").end());
+        for attribute in ["event", "length", "range", "subtype", "keep"] {
+            assert!(of_signal.iter().any(|name| name == attribute));
+        }
+        let of_type = attributes_at(code.s1("natural'").end());
+        for attribute in ["high", "succ", "image", "keep"] {
+            assert!(of_type.iter().any(|name| name == attribute));
+        }
+        for attribute in ["event", "length", "subtype"] {
+            assert!(!of_type.iter().any(|name| name == attribute));
+        }
+    }
+
     #[test]
     pub fn complete_in_generic_map() {
         let mut input = LibraryBuilder::new();
@@ -980,4 +1836,97 @@ end arch;
             &[CompletionItem::Simple(ent1), CompletionItem::Simple(ent2)],
         )
     }
+
+    #[test]
+    pub fn completes_snippets_of_the_context() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libA",
+            "\
+entity my_ent is
+end my_ent;
+
+architecture arch of my_ent is
+begin
+end arch;
+        ",
+        );
+        let (root, diag) = builder.get_analyzed_root();
+        check_no_diagnostics(&diag);
+
+        let snippet = |name: &str, context: SnippetContext| Snippet {
+            name: name.to_owned(),
+            prefix: name.to_owned(),
+            body: format!("{name} $0"),
+            description: None,
+            context,
+        };
+        let snippets = vec![
+            snippet("header", SnippetContext::Any),
+            snippet("reg", SnippetContext::ArchitectureDeclarations),
+            snippet("clocked", SnippetContext::Concurrent),
+        ];
+        let cursor = code.s1("begin").end();
+        let options =
+            list_completion_options_with_snippets(&root, &snippets, false, code.source(), cursor);
+        assert_eq!(
+            options,
+            vec![
+                CompletionItem::Snippet(&snippets[0]),
+                CompletionItem::Snippet(&snippets[2]),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn completes_templates_unless_replaced_by_snippets() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libA",
+            "\
+entity my_ent is
+end my_ent;
+
+architecture arch of my_ent is
+begin
+end arch;
+        ",
+        );
+        let (root, diag) = builder.get_analyzed_root();
+        check_no_diagnostics(&diag);
+
+        let prefixes = |options: Vec<CompletionItem>| -> Vec<String> {
+            options
+                .into_iter()
+                .filter_map(|option| match option {
+                    CompletionItem::Snippet(snippet) => Some(format!("snippet {}", snippet.prefix)),
+                    CompletionItem::Template(template) => Some(template.prefix.clone()),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let cursor = code.s1("begin").end();
+        let options =
+            list_completion_options_with_snippets(&root, &[], true, code.source(), cursor);
+        assert_eq!(prefixes(options), vec!["process", "clocked", "generate"]);
+
+        let snippets = vec![Snippet {
+            name: "clocked".to_owned(),
+            prefix: "clocked".to_owned(),
+            body: "process (clk_i) $0".to_owned(),
+            description: None,
+            context: SnippetContext::Concurrent,
+        }];
+        let options =
+            list_completion_options_with_snippets(&root, &snippets, true, code.source(), cursor);
+        assert_eq!(
+            prefixes(options),
+            vec!["snippet clocked", "process", "generate"]
+        );
+
+        let options =
+            list_completion_options_with_snippets(&root, &[], true, code.source(), code.end());
+        assert_eq!(prefixes(options), vec!["entity", "architecture"]);
+    }
 }
@@ -16,8 +16,12 @@ use fnv::FnvHashMap;
 use subst::VariableMap;
 use toml::{Table, Value};
 
-use crate::data::error_codes::ErrorCode;
+use crate::cross_check::GhdlCrossCheck;
 use crate::data::*;
+use crate::external_checker::ExternalChecker;
+use crate::ip_core::{is_ip_file_list, qip_file_names};
+use crate::message_catalog::MessageCatalog;
+use crate::snippet::Snippet;
 use crate::standard::VHDLStandard;
 
 #[derive(Clone, PartialEq, Eq, Default, Debug)]
@@ -27,13 +31,143 @@ pub struct Config {
     standard: VHDLStandard,
     // Defines the severity that diagnostics are displayed with
     severities: SeverityMap,
+    // Clock and reset conventions of generated processes
+    register_conventions: RegisterConventions,
+    // How deprecated declarations are marked
+    deprecation: Deprecation,
+    // How code between synthesis translate_off and translate_on pragmas is treated
+    synthesis_pragmas: SynthesisPragmas,
+    // External tools whose diagnostics are published together with the own diagnostics
+    external_checkers: Vec<ExternalChecker>,
+    // Compare the diagnostics with the analysis and elaboration of a top-level entity by GHDL
+    ghdl_cross_check: Option<GhdlCrossCheck>,
+    // Libraries that are enabled by name with the standard_libraries key, by preset name
+    presets: FnvHashMap<String, Preset>,
+    // The presets used by the project together with the directory of an external preset
+    standard_libraries: Vec<(String, Option<PathBuf>)>,
+    // The libraries of the presets used by the project
+    preset_libraries: Vec<LibraryConfig>,
+    // Named selections of libraries and files, by name
+    targets: FnvHashMap<String, Target>,
+    // The name of the active target
+    target: Option<String>,
+    // The locale of the messages, overriding the locale of the client
+    locale: Option<String>,
+    // The files of the message catalogs, by locale
+    message_catalogs: FnvHashMap<String, PathBuf>,
+    // The libraries of the project as selected by the active target
+    target_libraries: Option<Vec<LibraryConfig>>,
+    // Completion snippets of the users
+    snippets: Vec<Snippet>,
+}
+
+/// A named selection of the libraries and files of the project with a top-level entity,
+/// such as the files of a simulation or of one board of a design with board variants
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Target {
+    /// The top-level entity as `library.entity`
+    pub top: Option<String>,
+    // The libraries of the project that are part of the target, all libraries if not given
+    libraries: Option<Vec<String>>,
+    // Files that are only part of the target, by library
+    files: Vec<(String, Vec<String>)>,
+    // Patterns of files that are not part of the target
+    exclude: Vec<String>,
+}
+
+/// Libraries that are installed together with the standard libraries
+/// or that are part of an external installation
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+struct Preset {
+    libraries: FnvHashMap<String, LibraryConfig>,
+    /// The environment variable with the directory of an external installation.
+    /// File names of external presets are relative to that directory.
+    environment: Option<String>,
+    /// The lowest standard that the libraries can be analyzed with
+    standard: Option<VHDLStandard>,
+}
+
+/// The clock and reset of the clocked processes of a project
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RegisterConventions {
+    pub clock: String,
+    pub reset: String,
+    pub reset_active_low: bool,
+    pub async_reset: bool,
+    pub falling_edge: bool,
+}
+
+impl Default for RegisterConventions {
+    fn default() -> Self {
+        RegisterConventions {
+            clock: "clk".to_owned(),
+            reset: "rst".to_owned(),
+            reset_active_low: false,
+            async_reset: false,
+            falling_edge: false,
+        }
+    }
+}
+
+/// The markers of deprecated declarations, whose uses are reported with the `deprecated` diagnostic
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Deprecation {
+    /// A user-defined attribute that is specified for deprecated declarations, e.g.
+    /// `attribute deprecated of old_fn : function is "Use new_fn";`
+    pub attribute: String,
+    /// Text within a comment before or after a deprecated declaration
+    pub comment_marker: String,
+}
+
+impl Default for Deprecation {
+    fn default() -> Self {
+        Deprecation {
+            attribute: "deprecated".to_owned(),
+            comment_marker: "@deprecated".to_owned(),
+        }
+    }
+}
+
+/// The treatment of code within regions such as
+/// ```vhdl
+/// -- synthesis translate_off
+/// -- synthesis translate_on
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum SynthesisPragmas {
+    /// The code is analyzed like any other code
+    #[default]
+    Analyze,
+    /// The code is not analyzed and marked as excluded from synthesis
+    Exclude,
+    /// The code is analyzed and marked as excluded from synthesis
+    Mark,
+}
+
+impl TryFrom<&str> for SynthesisPragmas {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "analyze" => Ok(SynthesisPragmas::Analyze),
+            "exclude" => Ok(SynthesisPragmas::Exclude),
+            "mark" => Ok(SynthesisPragmas::Mark),
+            _ => Err(format!(
+                "'{value}' is not a valid value of synthesis_pragmas"
+            )),
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Default, Debug)]
 pub struct LibraryConfig {
     name: String,
     patterns: Vec<String>,
+    // Patterns of files that are excluded by the active target
+    excluded: Vec<String>,
     pub(crate) is_third_party: bool,
+    // Patterns of files that replace the files of the same name from the given standard on
+    standard_patterns: Vec<(VHDLStandard, Vec<String>)>,
 }
 
 impl LibraryConfig {
@@ -41,8 +175,47 @@ impl LibraryConfig {
     /// Only include files that exists
     /// Files that do not exist produce a warning message
     pub fn file_names(&self, messages: &mut dyn MessageHandler) -> Vec<PathBuf> {
+        let result = Self::match_patterns(&self.patterns, messages);
+        self.finish_file_names(result, messages)
+    }
+
+    /// Return a vector of file names for the VHDL standard
+    /// The files of the newest standard-specific patterns that apply to the standard
+    /// replace the files with the same file name, such as the declarations of
+    /// the std packages that were extended by a newer standard
+    pub fn file_names_for_standard(
+        &self,
+        standard: VHDLStandard,
+        messages: &mut dyn MessageHandler,
+    ) -> Vec<PathBuf> {
+        let mut result = Self::match_patterns(&self.patterns, messages);
+        let mut standard_patterns: Vec<_> = self
+            .standard_patterns
+            .iter()
+            .filter(|(pattern_standard, _)| *pattern_standard <= standard)
+            .collect();
+        standard_patterns.sort_by_key(|(pattern_standard, _)| *pattern_standard);
+
+        for (_, patterns) in standard_patterns {
+            for file_path in Self::match_patterns(patterns, messages) {
+                if let Some(existing) = result
+                    .iter_mut()
+                    .find(|existing| existing.file_name() == file_path.file_name())
+                {
+                    *existing = file_path;
+                } else {
+                    result.push(file_path);
+                }
+            }
+        }
+        self.finish_file_names(result, messages)
+    }
+
+    /// The files that match the patterns
+    /// Files that do not exist produce a warning message
+    fn match_patterns(patterns: &[String], messages: &mut dyn MessageHandler) -> Vec<PathBuf> {
         let mut result = Vec::new();
-        for pattern in self.patterns.iter() {
+        for pattern in patterns.iter() {
             let stripped_pattern = if cfg!(windows) {
                 pattern.strip_prefix("\\\\?\\").unwrap_or(pattern.as_str())
             } else {
@@ -86,7 +259,45 @@ impl LibraryConfig {
                 }
             }
         }
-        Self::remove_duplicates(result)
+        result
+    }
+
+    /// Remove the excluded files and expand the file lists of IP cores
+    fn finish_file_names(
+        &self,
+        mut result: Vec<PathBuf>,
+        messages: &mut dyn MessageHandler,
+    ) -> Vec<PathBuf> {
+        if !self.excluded.is_empty() {
+            let excluded: Vec<_> = self
+                .excluded
+                .iter()
+                .filter_map(|pattern| glob::Pattern::new(pattern).ok())
+                .collect();
+            result.retain(|file_path| {
+                !excluded
+                    .iter()
+                    .any(|pattern| pattern.matches_path(file_path))
+            });
+        }
+        Self::remove_duplicates(Self::expand_ip_file_lists(result, messages))
+    }
+
+    /// Replace the file lists of IP cores by the files they list
+    fn expand_ip_file_lists(
+        file_names: Vec<PathBuf>,
+        messages: &mut dyn MessageHandler,
+    ) -> Vec<PathBuf> {
+        file_names
+            .into_iter()
+            .flat_map(|file_name| {
+                if is_ip_file_list(&file_name) {
+                    qip_file_names(&file_name, messages)
+                } else {
+                    vec![file_name]
+                }
+            })
+            .collect()
     }
 
     /// Remove duplicate file names from the result
@@ -111,12 +322,8 @@ impl LibraryConfig {
 impl Config {
     pub fn from_str(string: &str, parent: &Path) -> Result<Config, String> {
         let config = string.parse::<Value>().map_err(|err| err.to_string())?;
-        let mut libraries = FnvHashMap::default();
-
         let standard = if let Some(std) = config.get("standard") {
-            let std_str = std.as_str().ok_or("standard must be a string")?;
-            VHDLStandard::try_from(std_str)
-                .map_err(|_| format!("Unsupported standard '{std_str}'"))?
+            Self::read_standard(std)?
         } else {
             VHDLStandard::default()
         };
@@ -127,6 +334,218 @@ impl Config {
             .as_table()
             .ok_or("libraries must be a table")?;
 
+        let libraries = Self::read_libraries(libs, parent)?;
+
+        let presets = if let Some(presets) = config.get("presets") {
+            Self::read_presets(presets.as_table().ok_or("presets must be a table")?, parent)?
+        } else {
+            FnvHashMap::default()
+        };
+
+        let mut standard_libraries = Vec::new();
+        match config.get("standard_libraries") {
+            Some(Value::Array(names)) => {
+                for name in names {
+                    let name = name
+                        .as_str()
+                        .ok_or_else(|| format!("not a string {name}"))?;
+                    standard_libraries.push((name.to_owned(), None));
+                }
+            }
+            // The directories of external presets
+            Some(Value::Table(directories)) => {
+                for (name, directory) in directories {
+                    let directory = directory
+                        .as_str()
+                        .ok_or_else(|| format!("not a string {directory}"))?;
+                    let directory = substitute_environment_variables(directory, &subst::Env)?;
+                    standard_libraries.push((name.to_owned(), Some(parent.join(directory))));
+                }
+            }
+            Some(_) => return Err("standard_libraries must be an array or a table".to_owned()),
+            None => {}
+        }
+
+        let severities = if let Some(lint) = config.get("lint") {
+            Self::read_severity_overwrites(lint.as_table().ok_or("lint must be a table")?)?
+        } else {
+            SeverityMap::default()
+        };
+
+        let register_conventions = if let Some(registers) = config.get("registers") {
+            Self::read_register_conventions(
+                registers.as_table().ok_or("registers must be a table")?,
+            )?
+        } else {
+            RegisterConventions::default()
+        };
+
+        let deprecation = if let Some(deprecation) = config.get("deprecation") {
+            Self::read_deprecation(
+                deprecation
+                    .as_table()
+                    .ok_or("deprecation must be a table")?,
+            )?
+        } else {
+            Deprecation::default()
+        };
+
+        let synthesis_pragmas = if let Some(pragmas) = config.get("synthesis_pragmas") {
+            SynthesisPragmas::try_from(
+                pragmas
+                    .as_str()
+                    .ok_or("synthesis_pragmas must be a string")?,
+            )?
+        } else {
+            SynthesisPragmas::default()
+        };
+
+        let mut external_checkers = Vec::new();
+        if let Some(checkers) = config.get("checkers") {
+            let checkers = checkers.as_table().ok_or("checkers must be a table")?;
+            for (name, checker) in checkers {
+                let checker = checker
+                    .as_table()
+                    .ok_or_else(|| format!("checkers.{name} must be a table"))?;
+                external_checkers.push(ExternalChecker::from_table(name, checker, parent)?);
+            }
+        }
+
+        let mut snippets = Vec::new();
+        if let Some(table) = config.get("snippets") {
+            let table = table.as_table().ok_or("snippets must be a table")?;
+            for (name, snippet) in table {
+                let snippet = snippet
+                    .as_table()
+                    .ok_or_else(|| format!("snippets.{name} must be a table"))?;
+                snippets.push(Snippet::from_table(name, snippet)?);
+            }
+        }
+
+        let targets = if let Some(targets) = config.get("targets") {
+            Self::read_targets(targets.as_table().ok_or("targets must be a table")?, parent)?
+        } else {
+            FnvHashMap::default()
+        };
+        let target = if let Some(target) = config.get("target") {
+            let target = target.as_str().ok_or("target must be a string")?;
+            if !targets.contains_key(target) {
+                return Err(format!("No target named '{target}'"));
+            }
+            Some(target.to_owned())
+        } else {
+            None
+        };
+
+        let locale = if let Some(locale) = config.get("locale") {
+            Some(locale.as_str().ok_or("locale must be a string")?.to_owned())
+        } else {
+            None
+        };
+        let mut message_catalogs = FnvHashMap::default();
+        if let Some(catalogs) = config.get("message_catalogs") {
+            let catalogs = catalogs
+                .as_table()
+                .ok_or("message_catalogs must be a table")?;
+            for (locale, file_name) in catalogs {
+                let file_name = file_name
+                    .as_str()
+                    .ok_or_else(|| format!("message_catalogs.{locale} must be a string"))?;
+                let file_name = substitute_environment_variables(file_name, &subst::Env)?;
+                message_catalogs.insert(locale.to_owned(), parent.join(file_name));
+            }
+        }
+
+        let ghdl_cross_check = if let Some(cross_check) = config.get("ghdl_cross_check") {
+            Some(GhdlCrossCheck::from_table(
+                cross_check
+                    .as_table()
+                    .ok_or("ghdl_cross_check must be a table")?,
+                parent,
+            )?)
+        } else {
+            None
+        };
+
+        let mut config = Config {
+            libraries,
+            severities,
+            standard,
+            register_conventions,
+            deprecation,
+            synthesis_pragmas,
+            external_checkers,
+            ghdl_cross_check,
+            presets,
+            standard_libraries,
+            preset_libraries: Vec::new(),
+            targets,
+            target,
+            target_libraries: None,
+            locale,
+            message_catalogs,
+            snippets,
+        };
+        config.update_preset_libraries();
+        config.update_target_libraries();
+        Ok(config)
+    }
+
+    fn read_standard(value: &Value) -> Result<VHDLStandard, String> {
+        let std_str = value.as_str().ok_or("standard must be a string")?;
+        VHDLStandard::try_from(std_str).map_err(|_| format!("Unsupported standard '{std_str}'"))
+    }
+
+    fn read_presets(table: &Table, parent: &Path) -> Result<FnvHashMap<String, Preset>, String> {
+        let mut presets = FnvHashMap::default();
+
+        for (name, preset) in table {
+            let preset = preset
+                .as_table()
+                .ok_or_else(|| format!("preset {name} must be a table"))?;
+            let environment = match preset.get("environment") {
+                Some(environment) => Some(
+                    environment
+                        .as_str()
+                        .ok_or_else(|| format!("environment of preset {name} must be a string"))?
+                        .to_owned(),
+                ),
+                None => None,
+            };
+            let standard = match preset.get("standard") {
+                Some(standard) => Some(Self::read_standard(standard)?),
+                None => None,
+            };
+            let libs = preset
+                .get("libraries")
+                .ok_or_else(|| format!("missing field libraries for preset {name}"))?
+                .as_table()
+                .ok_or_else(|| format!("libraries of preset {name} must be a table"))?;
+            // The directory of an external preset is only known when it is used
+            let libraries = if environment.is_some() {
+                Self::read_libraries(libs, Path::new(""))?
+            } else {
+                Self::read_libraries(libs, parent)?
+            };
+
+            presets.insert(
+                name.to_owned(),
+                Preset {
+                    libraries,
+                    environment,
+                    standard,
+                },
+            );
+        }
+        Ok(presets)
+    }
+
+    fn read_libraries(
+        libs: &Table,
+        parent: &Path,
+    ) -> Result<FnvHashMap<String, LibraryConfig>, String> {
+        let mut libraries = FnvHashMap::default();
+
         for (name, lib) in libs.iter() {
             if name.to_lowercase() == "work" {
                 return Err(format!(
@@ -141,21 +560,7 @@ impl Config {
                 .as_array()
                 .ok_or_else(|| format!("files for library {name} is not array"))?;
 
-            let mut patterns = Vec::new();
-            for file in file_arr.iter() {
-                let file = file
-                    .as_str()
-                    .ok_or_else(|| format!("not a string {file}"))?;
-
-                let file = substitute_environment_variables(file, &subst::Env)?;
-
-                let path = parent.join(file);
-                let path = path
-                    .to_str()
-                    .ok_or_else(|| format!("Could not convert {path:?} to string"))?
-                    .to_owned();
-                patterns.push(path);
-            }
+            let patterns = Self::read_patterns(file_arr, parent)?;
 
             let mut is_third_party = false;
             if let Some(opt) = lib.get("is_third_party") {
@@ -168,44 +573,188 @@ impl Config {
                 }
             }
 
+            let mut standard_patterns = Vec::new();
+            if let Some(standard_files) = lib.get("standard_files") {
+                let standard_files = standard_files
+                    .as_table()
+                    .ok_or_else(|| format!("standard_files for library {name} is not a table"))?;
+                for (standard, file_arr) in standard_files.iter() {
+                    let standard = VHDLStandard::try_from(standard.as_str())
+                        .map_err(|_| format!("Unsupported standard '{standard}'"))?;
+                    let file_arr = file_arr.as_array().ok_or_else(|| {
+                        format!("standard_files for library {name} is not a table of arrays")
+                    })?;
+                    standard_patterns.push((standard, Self::read_patterns(file_arr, parent)?));
+                }
+            }
+
             libraries.insert(
                 name.to_owned(),
                 LibraryConfig {
                     name: name.to_owned(),
                     patterns,
+                    excluded: Vec::new(),
                     is_third_party,
+                    standard_patterns,
                 },
             );
         }
 
-        let severities = if let Some(lint) = config.get("lint") {
-            Self::read_severity_overwrites(lint.as_table().ok_or("lint must be a table")?)?
-        } else {
-            SeverityMap::default()
-        };
+        Ok(libraries)
+    }
 
-        Ok(Config {
-            libraries,
-            severities,
-            standard,
-        })
+    /// File names or glob patterns relative to the parent folder
+    fn read_patterns(file_arr: &[Value], parent: &Path) -> Result<Vec<String>, String> {
+        let mut patterns = Vec::new();
+        for file in file_arr.iter() {
+            let file = file
+                .as_str()
+                .ok_or_else(|| format!("not a string {file}"))?;
+
+            let file = substitute_environment_variables(file, &subst::Env)?;
+
+            let path = parent.join(file);
+            let path = path
+                .to_str()
+                .ok_or_else(|| format!("Could not convert {path:?} to string"))?
+                .to_owned();
+            patterns.push(path);
+        }
+        Ok(patterns)
+    }
+
+    fn read_targets(table: &Table, parent: &Path) -> Result<FnvHashMap<String, Target>, String> {
+        let mut targets = FnvHashMap::default();
+        for (name, target) in table.iter() {
+            let target = target
+                .as_table()
+                .ok_or_else(|| format!("targets.{name} must be a table"))?;
+            let top = match target.get("top") {
+                Some(top) => Some(
+                    top.as_str()
+                        .ok_or_else(|| format!("targets.{name}.top must be a string"))?
+                        .to_owned(),
+                ),
+                None => None,
+            };
+            let libraries = match target.get("libraries") {
+                Some(libraries) => Some(
+                    libraries
+                        .as_array()
+                        .and_then(|libraries| {
+                            libraries
+                                .iter()
+                                .map(|library| library.as_str().map(str::to_owned))
+                                .collect::<Option<Vec<_>>>()
+                        })
+                        .ok_or_else(|| {
+                            format!("targets.{name}.libraries must be an array of strings")
+                        })?,
+                ),
+                None => None,
+            };
+            let mut files = Vec::new();
+            if let Some(libraries) = target.get("files") {
+                let libraries = libraries
+                    .as_table()
+                    .ok_or_else(|| format!("targets.{name}.files must be a table"))?;
+                for (library, file_arr) in libraries.iter() {
+                    let file_arr = file_arr
+                        .as_array()
+                        .ok_or_else(|| format!("targets.{name}.files.{library} is not array"))?;
+                    files.push((library.to_owned(), Self::read_patterns(file_arr, parent)?));
+                }
+            }
+            let exclude = match target.get("exclude") {
+                Some(exclude) => Self::read_patterns(
+                    exclude
+                        .as_array()
+                        .ok_or_else(|| format!("targets.{name}.exclude is not array"))?,
+                    parent,
+                )?,
+                None => Vec::new(),
+            };
+            targets.insert(
+                name.to_owned(),
+                Target {
+                    top,
+                    libraries,
+                    files,
+                    exclude,
+                },
+            );
+        }
+        Ok(targets)
+    }
+
+    fn read_register_conventions(table: &Table) -> Result<RegisterConventions, String> {
+        let mut conventions = RegisterConventions::default();
+
+        for (key, value) in table {
+            let value = value
+                .as_str()
+                .ok_or_else(|| format!("registers.{key} must be a string"))?;
+            let invalid = || format!("'{value}' is not a valid value of registers.{key}");
+            match key.as_str() {
+                "clock" => conventions.clock = value.to_owned(),
+                "reset" => conventions.reset = value.to_owned(),
+                "reset_active" => {
+                    conventions.reset_active_low = match value {
+                        "high" => false,
+                        "low" => true,
+                        _ => return Err(invalid()),
+                    }
+                }
+                "reset_style" => {
+                    conventions.async_reset = match value {
+                        "sync" => false,
+                        "async" => true,
+                        _ => return Err(invalid()),
+                    }
+                }
+                "clock_edge" => {
+                    conventions.falling_edge = match value {
+                        "rising" => false,
+                        "falling" => true,
+                        _ => return Err(invalid()),
+                    }
+                }
+                _ => return Err(format!("'{key}' is not a valid key of registers")),
+            }
+        }
+        Ok(conventions)
+    }
+
+    fn read_deprecation(table: &Table) -> Result<Deprecation, String> {
+        let mut deprecation = Deprecation::default();
+
+        for (key, value) in table {
+            let value = value
+                .as_str()
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| format!("deprecation.{key} must be a non-empty string"))?;
+            match key.as_str() {
+                "attribute" => deprecation.attribute = value.to_owned(),
+                "comment_marker" => deprecation.comment_marker = value.to_owned(),
+                _ => return Err(format!("'{key}' is not a valid key of deprecation")),
+            }
+        }
+        Ok(deprecation)
     }
 
     fn read_severity_overwrites(severity_overwrites: &Table) -> Result<SeverityMap, String> {
         let mut severities = SeverityMap::default();
 
         for (name, severity) in severity_overwrites {
-            let error_code = ErrorCode::try_from(name.as_str())
-                .map_err(|_| format!("'{name}' is not a valid error code"))?;
             match severity {
                 Value::String(severity) => {
                     let severity = Severity::try_from(severity.as_str())
                         .map_err(|_| format!("'{severity}' is not a valid severity level"))?;
-                    severities[error_code] = Some(severity);
+                    severities.set(name, Some(severity))?;
                 }
                 Value::Boolean(should_show) => {
                     if !should_show {
-                        severities[error_code] = None
+                        severities.set(name, None)?;
                     }
                 }
                 _ => return Err("severity must be a string or boolean".to_string()),
@@ -225,11 +774,178 @@ impl Config {
     }
 
     pub fn get_library(&self, name: &str) -> Option<&LibraryConfig> {
-        self.libraries.get(name)
+        self.iter_libraries().find(|library| library.name == name)
     }
 
+    /// The libraries of the project followed by the libraries of the standard library presets
+    /// When a target is active, the project libraries are those selected by the target
     pub fn iter_libraries(&self) -> impl Iterator<Item = &LibraryConfig> {
-        self.libraries.values()
+        let libraries: Box<dyn Iterator<Item = &LibraryConfig>> = match self.target_libraries {
+            Some(ref libraries) => Box::new(libraries.iter()),
+            None => Box::new(self.libraries.values()),
+        };
+        libraries.chain(self.preset_libraries.iter())
+    }
+
+    /// The names of the targets, sorted by name
+    pub fn targets(&self) -> Vec<&str> {
+        let mut names: Vec<_> = self.targets.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// The name of the active target
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    pub fn active_target(&self) -> Option<&Target> {
+        self.targets.get(self.target.as_ref()?)
+    }
+
+    /// Activate the target with the given name, or use all libraries of the project
+    pub fn select_target(&mut self, name: Option<&str>) -> Result<(), String> {
+        if let Some(name) = name {
+            if !self.targets.contains_key(name) {
+                return Err(format!("No target named '{name}'"));
+            }
+        }
+        self.target = name.map(str::to_owned);
+        self.update_target_libraries();
+        Ok(())
+    }
+
+    /// The message catalog of the locale of the config, or else of the locale of the client,
+    /// e.g. `de-CH`, which falls back to the catalog of its language `de`.
+    /// The English messages are used for a locale without a catalog.
+    pub fn message_catalog(&self, client_locale: Option<&str>) -> Result<MessageCatalog, String> {
+        let Some(locale) = self.locale.as_deref().or(client_locale) else {
+            return Ok(MessageCatalog::default());
+        };
+        let language = locale.split(['-', '_']).next().unwrap_or(locale);
+        match self
+            .message_catalogs
+            .get(locale)
+            .or_else(|| self.message_catalogs.get(language))
+        {
+            Some(file_name) => MessageCatalog::read_file(locale, file_name),
+            None => Ok(MessageCatalog::default()),
+        }
+    }
+
+    /// Select the libraries and files of the active target
+    fn update_target_libraries(&mut self) {
+        let Some(target) = self.active_target() else {
+            self.target_libraries = None;
+            return;
+        };
+        let mut libraries: Vec<LibraryConfig> = self
+            .libraries
+            .values()
+            .filter(|library| {
+                target.libraries.as_ref().is_none_or(|names| {
+                    names
+                        .iter()
+                        .any(|name| name.eq_ignore_ascii_case(&library.name))
+                })
+            })
+            .cloned()
+            .collect();
+        for (name, patterns) in target.files.iter() {
+            match libraries
+                .iter_mut()
+                .find(|library| library.name.eq_ignore_ascii_case(name))
+            {
+                Some(library) => library.patterns.extend(patterns.iter().cloned()),
+                None => libraries.push(LibraryConfig {
+                    name: name.clone(),
+                    patterns: patterns.clone(),
+                    ..Default::default()
+                }),
+            }
+        }
+        for library in libraries.iter_mut() {
+            library.excluded = target.exclude.clone();
+        }
+        self.target_libraries = Some(libraries);
+    }
+
+    /// The directory of the files of a preset used by the project.
+    /// Returns `Some(None)` for presets that are installed together with the standard libraries.
+    fn preset_directory(
+        &self,
+        preset: &Preset,
+        directory: &Option<PathBuf>,
+    ) -> Option<Option<PathBuf>> {
+        match preset.environment {
+            Some(ref environment) => Some(Some(
+                directory
+                    .clone()
+                    .or_else(|| env::var_os(environment).map(PathBuf::from))?,
+            )),
+            None => Some(None),
+        }
+    }
+
+    /// Resolve the libraries of the presets used by the project.
+    /// Libraries of the project take precedence over libraries of a preset with the same name.
+    fn update_preset_libraries(&mut self) {
+        let mut preset_libraries = Vec::new();
+        for (name, directory) in self.standard_libraries.iter() {
+            let Some(preset) = self.presets.get(name) else {
+                continue;
+            };
+            let Some(directory) = self.preset_directory(preset, directory) else {
+                continue;
+            };
+            for library in preset.libraries.values() {
+                if self
+                    .libraries
+                    .keys()
+                    .any(|name| name.eq_ignore_ascii_case(&library.name))
+                {
+                    continue;
+                }
+                let mut library = library.clone();
+                library.is_third_party = true;
+                if let Some(ref directory) = directory {
+                    for pattern in library.patterns.iter_mut() {
+                        *pattern = directory
+                            .join(pattern.as_str())
+                            .to_string_lossy()
+                            .into_owned();
+                    }
+                }
+                preset_libraries.push(library);
+            }
+        }
+        self.preset_libraries = preset_libraries;
+    }
+
+    /// Report the presets used by the project that cannot be loaded
+    pub fn check_standard_libraries(&self, messages: &mut dyn MessageHandler) {
+        for (name, directory) in self.standard_libraries.iter() {
+            let Some(preset) = self.presets.get(name) else {
+                messages.push(Message::warning(format!(
+                    "Standard library '{name}' is not installed"
+                )));
+                continue;
+            };
+            if self.preset_directory(preset, directory).is_none() {
+                messages.push(Message::warning(format!(
+                    "Standard library '{name}' was not found. Set the environment variable {} or its directory in standard_libraries",
+                    preset.environment.as_deref().unwrap_or_default()
+                )));
+            }
+            if let Some(standard) = preset.standard {
+                if self.standard < standard {
+                    messages.push(Message::warning(format!(
+                        "Standard library '{name}' requires VHDL-{}",
+                        standard.as_ref()
+                    )));
+                }
+            }
+        }
     }
 
     /// Append another config to self
@@ -237,7 +953,7 @@ impl Config {
     /// In case of conflict the appended config takes precedence
     pub fn append(&mut self, config: &Config, messages: &mut dyn MessageHandler) {
         self.standard = config.standard;
-        for library in config.iter_libraries() {
+        for library in config.libraries.values() {
             if let Some(parent_library) = self.libraries.get_mut(&library.name) {
                 *parent_library = library.clone();
 
@@ -250,6 +966,64 @@ impl Config {
             }
         }
         self.severities = config.severities;
+        self.register_conventions = config.register_conventions.clone();
+        self.deprecation = config.deprecation.clone();
+        self.synthesis_pragmas = config.synthesis_pragmas;
+        for checker in config.external_checkers.iter() {
+            if let Some(existing) = self
+                .external_checkers
+                .iter_mut()
+                .find(|existing| existing.name == checker.name)
+            {
+                *existing = checker.clone();
+            } else {
+                self.external_checkers.push(checker.clone());
+            }
+        }
+        if config.ghdl_cross_check.is_some() {
+            self.ghdl_cross_check = config.ghdl_cross_check.clone();
+        }
+        for (name, target) in config.targets.iter() {
+            self.targets.insert(name.clone(), target.clone());
+        }
+        if config.target.is_some() {
+            self.target = config.target.clone();
+        }
+        if config.locale.is_some() {
+            self.locale = config.locale.clone();
+        }
+        for (locale, file_name) in config.message_catalogs.iter() {
+            self.message_catalogs
+                .insert(locale.clone(), file_name.clone());
+        }
+        for (name, preset) in config.presets.iter() {
+            self.presets.insert(name.clone(), preset.clone());
+        }
+        for snippet in config.snippets.iter() {
+            if let Some(existing) = self
+                .snippets
+                .iter_mut()
+                .find(|existing| existing.name == snippet.name)
+            {
+                *existing = snippet.clone();
+            } else {
+                self.snippets.push(snippet.clone());
+            }
+        }
+        for (name, directory) in config.standard_libraries.iter() {
+            if let Some(existing) = self
+                .standard_libraries
+                .iter_mut()
+                .find(|(existing, _)| existing == name)
+            {
+                existing.1 = directory.clone().or(existing.1.take());
+            } else {
+                self.standard_libraries
+                    .push((name.clone(), directory.clone()));
+            }
+        }
+        self.update_preset_libraries();
+        self.update_target_libraries();
     }
 
     /// Load configuration file from installation folder
@@ -352,6 +1126,31 @@ impl Config {
         &self.severities
     }
 
+    pub fn register_conventions(&self) -> &RegisterConventions {
+        &self.register_conventions
+    }
+
+    pub fn deprecation(&self) -> &Deprecation {
+        &self.deprecation
+    }
+
+    pub fn synthesis_pragmas(&self) -> SynthesisPragmas {
+        self.synthesis_pragmas
+    }
+
+    pub fn external_checkers(&self) -> &[ExternalChecker] {
+        &self.external_checkers
+    }
+
+    /// The completion snippets of the users
+    pub fn snippets(&self) -> &[Snippet] {
+        &self.snippets
+    }
+
+    pub fn ghdl_cross_check(&self) -> Option<&GhdlCrossCheck> {
+        self.ghdl_cross_check.as_ref()
+    }
+
     /// The VHDL standard to use if no more specific config is present.
     /// By default, VHDL 2008 is assumed
     pub fn standard(&self) -> VHDLStandard {
@@ -507,6 +1306,8 @@ duplicate = false
 
         let mut expected_map = SeverityMap::default();
         expected_map[ErrorCode::Unused] = Some(Severity::Error);
+        expected_map[ErrorCode::UnusedPort] = Some(Severity::Error);
+        expected_map[ErrorCode::UndrivenPort] = Some(Severity::Error);
         expected_map[ErrorCode::Duplicate] = None;
         assert_eq!(config.severities, expected_map)
     }
@@ -571,6 +1372,357 @@ lib3.files = [
         assert_eq!(merged_config, expected_config);
     }
 
+    #[test]
+    fn test_standard_libraries() {
+        let installed = Config::from_str(
+            "
+[libraries]
+std.files = ['std/*.vhd']
+
+[presets.xilinx_unisim]
+libraries.unisim.files = ['unisim/*.vhd']
+
+[presets.osvvm]
+environment = 'VHDL_LS_TEST_OSVVM_DIR'
+standard = '2008'
+libraries.osvvm.files = ['osvvm/*.vhd']
+",
+            Path::new("installed"),
+        )
+        .unwrap();
+        let project = Config::from_str(
+            "
+standard_libraries = ['xilinx_unisim', 'unknown']
+
+[libraries]
+lib.files = ['lib/*.vhd']
+",
+            Path::new("project"),
+        )
+        .unwrap();
+
+        let mut config = installed.clone();
+        config.append(&project, &mut Vec::new());
+        let mut libraries: Vec<&str> = config.iter_libraries().map(|lib| lib.name()).collect();
+        libraries.sort_unstable();
+        assert_eq!(libraries, &["lib", "std", "unisim"]);
+
+        let unisim = config.get_library("unisim").unwrap();
+        assert!(unisim.is_third_party);
+        assert_eq!(
+            unisim.patterns,
+            vec![Path::new("installed")
+                .join("unisim/*.vhd")
+                .to_str()
+                .unwrap()
+                .to_owned()]
+        );
+        let mut messages = Vec::new();
+        config.check_standard_libraries(&mut messages);
+        assert_eq!(
+            messages,
+            vec![Message::warning(
+                "Standard library 'unknown' is not installed"
+            )]
+        );
+
+        // External presets are not loaded until their directory is known
+        let mut config = installed.clone();
+        config.append(
+            &Config::from_str(
+                "
+standard_libraries = ['osvvm']
+
+[libraries]
+",
+                Path::new("project"),
+            )
+            .unwrap(),
+            &mut Vec::new(),
+        );
+        assert!(config.get_library("osvvm").is_none());
+        let mut messages = Vec::new();
+        config.check_standard_libraries(&mut messages);
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].message.contains("VHDL_LS_TEST_OSVVM_DIR"));
+
+        let mut config = installed;
+        config.append(
+            &Config::from_str(
+                "
+standard = '1993'
+
+[libraries]
+
+[standard_libraries]
+osvvm = 'third_party/OsvvmLibraries'
+",
+                Path::new("project"),
+            )
+            .unwrap(),
+            &mut Vec::new(),
+        );
+        let osvvm = config.get_library("osvvm").unwrap();
+        assert!(osvvm.is_third_party);
+        assert_eq!(
+            osvvm.patterns,
+            vec![Path::new("project")
+                .join("third_party/OsvvmLibraries")
+                .join("osvvm/*.vhd")
+                .to_str()
+                .unwrap()
+                .to_owned()]
+        );
+        let mut messages = Vec::new();
+        config.check_standard_libraries(&mut messages);
+        assert_eq!(
+            messages,
+            vec![Message::warning(
+                "Standard library 'osvvm' requires VHDL-2008"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_snippets() {
+        let parent = Path::new("parent_folder");
+        let mut config = Config::from_str(
+            "
+[libraries]
+
+[snippets.register]
+prefix = 'reg'
+body = 'signal ${1:name}_r : std_logic;'
+context = 'architecture_declarations'
+",
+            parent,
+        )
+        .unwrap();
+        let user_config = Config::from_str(
+            "
+[libraries]
+
+[snippets.register]
+prefix = 'reg'
+body = ['signal ${1:name}_q : std_logic;']
+",
+            parent,
+        )
+        .unwrap();
+        let mut messages = Vec::new();
+        config.append(&user_config, &mut messages);
+
+        let names: Vec<_> = config
+            .snippets()
+            .iter()
+            .map(|snippet| (snippet.prefix.as_str(), snippet.body.as_str()))
+            .collect();
+        assert_eq!(names, vec![("reg", "signal ${1:name}_q : std_logic;")]);
+        assert_eq!(
+            Config::from_str("[libraries]\n[snippets]\nreg = 'signal'", parent),
+            Err("snippets.reg must be a table".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_register_conventions() {
+        let parent = Path::new("parent_folder");
+        let config = Config::from_str(
+            "
+[libraries]
+
+[registers]
+clock = 'clk_i'
+reset = 'rst_n'
+reset_active = 'low'
+reset_style = 'async'
+",
+            parent,
+        )
+        .unwrap();
+        assert_eq!(
+            config.register_conventions(),
+            &RegisterConventions {
+                clock: "clk_i".to_owned(),
+                reset: "rst_n".to_owned(),
+                reset_active_low: true,
+                async_reset: true,
+                falling_edge: false,
+            }
+        );
+
+        assert_eq!(
+            Config::from_str(
+                "
+[libraries]
+
+[registers]
+reset_style = 'asynchronous'
+",
+                parent,
+            ),
+            Err("'asynchronous' is not a valid value of registers.reset_style".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_deprecation() {
+        let parent = Path::new("parent_folder");
+        let config = Config::from_str(
+            "
+[libraries]
+
+[deprecation]
+comment_marker = 'OBSOLETE'
+",
+            parent,
+        )
+        .unwrap();
+        assert_eq!(
+            config.deprecation(),
+            &Deprecation {
+                attribute: "deprecated".to_owned(),
+                comment_marker: "OBSOLETE".to_owned(),
+            }
+        );
+
+        assert_eq!(
+            Config::from_str(
+                "
+[libraries]
+
+[deprecation]
+marker = 'OBSOLETE'
+",
+                parent,
+            ),
+            Err("'marker' is not a valid key of deprecation".to_owned())
+        );
+    }
+
+    #[test]
+    fn targets_select_libraries_and_files() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let parent = tempdir.path();
+        std::fs::create_dir(parent.join("board_a")).unwrap();
+        std::fs::create_dir(parent.join("board_b")).unwrap();
+        let common = touch(parent, "common.vhd");
+        let board_a = touch(parent, "board_a/pins.vhd");
+        touch(parent, "board_b/pins.vhd");
+        let tb = touch(parent, "tb.vhd");
+
+        let mut config = Config::from_str(
+            "
+target = 'fpga_a'
+
+[libraries]
+lib.files = ['*.vhd', '*/*.vhd']
+tb.files = ['tb.vhd']
+
+[targets.fpga_a]
+top = 'lib.top'
+libraries = ['lib']
+exclude = ['board_b/*.vhd', 'tb.vhd']
+
+[targets.sim]
+files.tb = ['board_a/pins.vhd']
+",
+            parent,
+        )
+        .unwrap();
+        assert_eq!(config.targets(), vec!["fpga_a", "sim"]);
+        assert_eq!(config.target(), Some("fpga_a"));
+        assert_eq!(
+            config.active_target().unwrap().top.as_deref(),
+            Some("lib.top")
+        );
+
+        let mut messages = vec![];
+        let libraries: Vec<&str> = config.iter_libraries().map(|lib| lib.name()).collect();
+        assert_eq!(libraries, &["lib"]);
+        let mut files = config.get_library("lib").unwrap().file_names(&mut messages);
+        files.sort();
+        assert_files_eq(&files, &[board_a.clone(), common]);
+
+        config.select_target(Some("sim")).unwrap();
+        let tb_files = config.get_library("tb").unwrap().file_names(&mut messages);
+        assert_files_eq(&tb_files, &[tb, board_a]);
+        assert_eq!(messages, vec![]);
+
+        assert_eq!(
+            config.select_target(Some("fpga_c")),
+            Err("No target named 'fpga_c'".to_owned())
+        );
+        config.select_target(None).unwrap();
+        assert_eq!(config.iter_libraries().count(), 2);
+    }
+
+    #[test]
+    fn message_catalog_of_locale() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let parent = tempdir.path();
+        std::fs::write(
+            parent.join("de.toml"),
+            "\"Unused declaration of {name}\" = \"Unbenutzte Deklaration von {name}\"",
+        )
+        .unwrap();
+
+        let config = Config::from_str(
+            "
+[libraries]
+
+[message_catalogs]
+de = 'de.toml'
+",
+            parent,
+        )
+        .unwrap();
+        let catalog = config.message_catalog(Some("de-CH")).unwrap();
+        assert_eq!(catalog.locale(), "de-CH");
+        assert_eq!(
+            catalog.translate("Unused declaration of signal 's'"),
+            "Unbenutzte Deklaration von signal 's'"
+        );
+        assert_eq!(config.message_catalog(Some("fr")).unwrap().locale(), "en");
+        assert_eq!(config.message_catalog(None).unwrap().locale(), "en");
+
+        // The locale of the config takes precedence over the locale of the client
+        let mut merged = config.clone();
+        merged.append(
+            &Config::from_str("locale = 'de'\n[libraries]", parent).unwrap(),
+            &mut Vec::new(),
+        );
+        assert_eq!(merged.message_catalog(Some("fr")).unwrap().locale(), "de");
+    }
+
+    #[test]
+    fn test_synthesis_pragmas() {
+        let parent = Path::new("parent_folder");
+        let config = Config::from_str("[libraries]", parent).unwrap();
+        assert_eq!(config.synthesis_pragmas(), SynthesisPragmas::Analyze);
+
+        let config = Config::from_str(
+            "
+synthesis_pragmas = 'exclude'
+[libraries]
+",
+            parent,
+        )
+        .unwrap();
+        assert_eq!(config.synthesis_pragmas(), SynthesisPragmas::Exclude);
+
+        assert_eq!(
+            Config::from_str(
+                "
+synthesis_pragmas = 'ignore'
+[libraries]
+",
+                parent,
+            ),
+            Err("'ignore' is not a valid value of synthesis_pragmas".to_owned())
+        );
+    }
+
     #[test]
     fn test_warning_on_missing_file() {
         let parent = Path::new("parent_folder");
@@ -646,6 +1798,39 @@ lib.files = [
         assert_eq!(messages, vec![]);
     }
 
+    #[test]
+    fn test_standard_files_replace_files_of_same_name() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let parent = tempdir.path();
+        std::fs::create_dir(parent.join("2019")).unwrap();
+        let config = Config::from_str(
+            "
+[libraries]
+std.files = ['*.vhd']
+std.standard_files.2019 = ['2019/*.vhd']
+",
+            parent,
+        )
+        .unwrap();
+
+        let env = touch(parent, "env.vhd");
+        let textio = touch(parent, "textio.vhd");
+        let env2019 = touch(&parent.join("2019"), "env.vhd");
+
+        let library = config.get_library("std").unwrap();
+        let mut messages = vec![];
+        assert_files_eq(
+            &library.file_names_for_standard(VHDLStandard::VHDL2008, &mut messages),
+            &[env.clone(), textio.clone()],
+        );
+        assert_files_eq(
+            &library.file_names_for_standard(VHDLStandard::VHDL2019, &mut messages),
+            &[env2019, textio.clone()],
+        );
+        assert_files_eq(&library.file_names(&mut messages), &[env, textio]);
+        assert_eq!(messages, vec![]);
+    }
+
     #[test]
     fn test_warning_on_emtpy_glob_pattern() {
         let parent = Path::new("parent_folder");
@@ -0,0 +1,339 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Cross-checking the analysis against the analysis and elaboration of GHDL
+
+use crate::data::{Diagnostic, Severity};
+use crate::external_checker::{ExternalChecker, ExternalDiagnostic};
+use crate::hierarchy::Instance;
+use crate::standard::VHDLStandard;
+use fnv::FnvHashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use toml::Table;
+
+/// The name of the cross-check diagnostics
+pub const CROSS_CHECK_SOURCE: &str = "ghdl cross-check";
+
+/// Numbers the work directories of cross-checks that run at the same time
+static NEXT_WORK_DIR: AtomicU64 = AtomicU64::new(0);
+
+/// The `ghdl_cross_check` table of the config
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GhdlCrossCheck {
+    /// The top-level entity as `library.entity`
+    pub top: String,
+    /// The GHDL executable
+    ghdl: String,
+    /// Run the cross-check whenever a file is saved, otherwise only on demand
+    pub run_on_save: bool,
+    directory: PathBuf,
+}
+
+impl GhdlCrossCheck {
+    pub(crate) fn from_table(table: &Table, parent: &Path) -> Result<GhdlCrossCheck, String> {
+        let string = |key: &str| {
+            table
+                .get(key)
+                .map(|value| {
+                    value
+                        .as_str()
+                        .ok_or_else(|| format!("ghdl_cross_check.{key} must be a string"))
+                })
+                .transpose()
+        };
+        let top = string("top")?.ok_or("ghdl_cross_check.top is missing")?;
+        if top.split_once('.').is_none() {
+            return Err(format!(
+                "Expected ghdl_cross_check.top as library.entity, got '{top}'"
+            ));
+        }
+        let run_on_save = match string("run")? {
+            Some("save") => true,
+            None | Some("manual") => false,
+            Some(value) => {
+                return Err(format!(
+                    "'{value}' is not a valid value of ghdl_cross_check.run"
+                ))
+            }
+        };
+        Ok(GhdlCrossCheck {
+            top: top.to_owned(),
+            ghdl: string("ghdl")?.unwrap_or("ghdl").to_owned(),
+            run_on_save,
+            directory: parent.to_owned(),
+        })
+    }
+
+    /// Import the files of the libraries into a temporary work directory and
+    /// let GHDL analyze and elaborate the top-level entity.
+    /// Returns the diagnostics that GHDL reports.
+    pub(crate) fn run_ghdl(
+        &self,
+        standard: VHDLStandard,
+        libraries: &[(String, Vec<PathBuf>)],
+    ) -> Result<Vec<ExternalDiagnostic>, String> {
+        let (library, entity) = self.top.split_once('.').unwrap_or_default();
+        let work_dir = std::env::temp_dir().join(format!(
+            "vhdl_ls_ghdl_{}_{}",
+            std::process::id(),
+            NEXT_WORK_DIR.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&work_dir)
+            .map_err(|err| format!("Failed to create the GHDL work directory: {err}"))?;
+        let std_option = match standard {
+            VHDLStandard::VHDL1993 => "--std=93",
+            VHDLStandard::VHDL2008 => "--std=08",
+            VHDLStandard::VHDL2019 => "--std=19",
+        };
+        let work_dir_option = format!("--workdir={}", work_dir.to_string_lossy());
+        let ghdl = |command: &str, library: &str, args: Vec<String>| {
+            let mut command = vec![
+                self.ghdl.clone(),
+                command.to_owned(),
+                std_option.to_owned(),
+                work_dir_option.clone(),
+                format!("-P{}", work_dir.to_string_lossy()),
+                format!("--work={library}"),
+            ];
+            command.extend(args);
+            ExternalChecker::new("ghdl", command, &self.directory).run(None)
+        };
+
+        let mut commands: Vec<(&str, &str, Vec<String>)> = libraries
+            .iter()
+            .filter(|(_, files)| !files.is_empty())
+            .map(|(name, files)| {
+                let files = files
+                    .iter()
+                    .map(|file| file.to_string_lossy().into_owned())
+                    .collect();
+                ("-i", name.as_str(), files)
+            })
+            .collect();
+        commands.push(("-m", library, vec![entity.to_owned()]));
+
+        let result = commands
+            .into_iter()
+            .map(|(command, library, args)| ghdl(command, library, args))
+            .collect::<Result<Vec<_>, _>>();
+        let _ = std::fs::remove_dir_all(&work_dir);
+        Ok(result?.into_iter().flatten().collect())
+    }
+}
+
+/// A cross-check of the project with GHDL.
+/// It does not refer to the project so that it can run on another thread.
+#[derive(Clone, Debug)]
+pub struct GhdlCrossCheckRun {
+    cross_check: GhdlCrossCheck,
+    standard: VHDLStandard,
+    /// The files of each library
+    libraries: Vec<(String, Vec<PathBuf>)>,
+    /// The errors of vhdl_lang
+    errors: Vec<Diagnostic>,
+    /// The files of the instance hierarchy of the top-level entity
+    hierarchy_files: FnvHashSet<PathBuf>,
+}
+
+impl GhdlCrossCheckRun {
+    pub(crate) fn new(
+        cross_check: GhdlCrossCheck,
+        standard: VHDLStandard,
+        libraries: Vec<(String, Vec<PathBuf>)>,
+        errors: Vec<Diagnostic>,
+        hierarchy_files: FnvHashSet<PathBuf>,
+    ) -> Self {
+        GhdlCrossCheckRun {
+            cross_check,
+            standard,
+            libraries,
+            errors,
+            hierarchy_files,
+        }
+    }
+
+    /// Let GHDL analyze and elaborate the top-level entity.
+    /// Returns the errors that only one of GHDL and vhdl_lang finds.
+    pub fn run(&self) -> Result<Vec<ExternalDiagnostic>, String> {
+        let ghdl = self.cross_check.run_ghdl(self.standard, &self.libraries)?;
+        Ok(discrepancies(&ghdl, &self.errors, &self.hierarchy_files))
+    }
+}
+
+/// The files of the architectures of the instance hierarchy
+pub(crate) fn hierarchy_files(instance: &Instance, files: &mut FnvHashSet<PathBuf>) {
+    files.insert(instance.pos.source.file_name().to_owned());
+    if let Some(ref pos) = instance.architecture_pos {
+        files.insert(pos.source.file_name().to_owned());
+    }
+    for instance in instance.instances.iter() {
+        hierarchy_files(instance, files);
+    }
+}
+
+/// Diagnostics for the errors that GHDL reports on lines without errors of vhdl_lang
+/// and for the errors of vhdl_lang on lines without errors of GHDL.
+/// The errors of vhdl_lang are only compared within the files of the hierarchy when GHDL
+/// succeeds, and within the files that GHDL reports errors for otherwise,
+/// since GHDL stops at the first unit with errors.
+pub(crate) fn discrepancies(
+    ghdl: &[ExternalDiagnostic],
+    errors: &[Diagnostic],
+    hierarchy_files: &FnvHashSet<PathBuf>,
+) -> Vec<ExternalDiagnostic> {
+    let ghdl: Vec<_> = ghdl
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .collect();
+    let mut result = Vec::new();
+    for diagnostic in ghdl.iter() {
+        let found = errors.iter().any(|error| {
+            error.pos.source.file_name() == diagnostic.file
+                && error.pos.start().line == diagnostic.pos.line
+        });
+        if !found {
+            result.push(ExternalDiagnostic {
+                source: CROSS_CHECK_SOURCE.to_owned(),
+                file: diagnostic.file.clone(),
+                pos: diagnostic.pos,
+                severity: Severity::Warning,
+                message: format!(
+                    "GHDL reports an error that vhdl_ls does not report: {}",
+                    diagnostic.message
+                ),
+            });
+        }
+    }
+
+    let checked_files: FnvHashSet<&Path> = if ghdl.is_empty() {
+        hierarchy_files.iter().map(PathBuf::as_path).collect()
+    } else {
+        ghdl.iter()
+            .map(|diagnostic| diagnostic.file.as_path())
+            .collect()
+    };
+    for error in errors {
+        let file = error.pos.source.file_name();
+        if !checked_files.contains(file) {
+            continue;
+        }
+        let found = ghdl.iter().any(|diagnostic| {
+            diagnostic.file == file && diagnostic.pos.line == error.pos.start().line
+        });
+        if !found {
+            result.push(ExternalDiagnostic {
+                source: CROSS_CHECK_SOURCE.to_owned(),
+                file: file.to_owned(),
+                pos: error.pos.start(),
+                severity: Severity::Warning,
+                message: format!(
+                    "vhdl_ls reports an error that GHDL does not report: {}",
+                    error.message
+                ),
+            });
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Position;
+    use crate::syntax::test::Code;
+
+    fn ghdl_error(file: &Path, line: u32, message: &str) -> ExternalDiagnostic {
+        ExternalDiagnostic {
+            source: "ghdl".to_owned(),
+            file: file.to_owned(),
+            pos: Position::new(line, 0),
+            severity: Severity::Error,
+            message: message.to_owned(),
+        }
+    }
+
+    #[test]
+    fn reports_errors_of_only_one_tool() {
+        let code = Code::new(
+            "\
+entity top is
+end entity;
+
+architecture rtl of top is
+  signal s : bit;
+begin
+  s <= '1';
+end architecture;
+",
+        );
+        let file = code.source().file_name();
+        let errors = vec![
+            Diagnostic::syntax_error(code.s1("signal s"), "vhdl_lang error"),
+            Diagnostic::syntax_error(code.s1("s <= '1'"), "common error"),
+        ];
+        let ghdl = vec![
+            ghdl_error(file, 6, "common error"),
+            ghdl_error(file, 1, "ghdl error"),
+        ];
+        let hierarchy = FnvHashSet::default();
+
+        assert_eq!(
+            discrepancies(&ghdl, &errors, &hierarchy)
+                .into_iter()
+                .map(|diagnostic| (diagnostic.pos.line, diagnostic.message))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    1,
+                    "GHDL reports an error that vhdl_ls does not report: ghdl error".to_owned()
+                ),
+                (
+                    4,
+                    "vhdl_ls reports an error that GHDL does not report: vhdl_lang error"
+                        .to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn compares_errors_of_hierarchy_when_ghdl_succeeds() {
+        let code = Code::new("entity top is\nend entity;");
+        let other = Code::new("entity other is\nend entity;");
+        let errors = vec![
+            Diagnostic::syntax_error(code.s1("top"), "in hierarchy"),
+            Diagnostic::syntax_error(other.s1("other"), "not in hierarchy"),
+        ];
+        let mut hierarchy = FnvHashSet::default();
+        hierarchy.insert(code.source().file_name().to_owned());
+
+        let result = discrepancies(&[], &errors, &hierarchy);
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result[0].message,
+            "vhdl_ls reports an error that GHDL does not report: in hierarchy"
+        );
+    }
+
+    #[test]
+    fn reads_config() {
+        let table = "top = 'lib.top'".parse::<Table>().unwrap();
+        let cross_check = GhdlCrossCheck::from_table(&table, Path::new("")).unwrap();
+        assert_eq!(cross_check.top, "lib.top");
+        assert!(!cross_check.run_on_save);
+
+        let table = "top = 'lib.top'\nrun = 'save'".parse::<Table>().unwrap();
+        let cross_check = GhdlCrossCheck::from_table(&table, Path::new("")).unwrap();
+        assert!(cross_check.run_on_save);
+
+        let table = "top = 'top'".parse::<Table>().unwrap();
+        assert_eq!(
+            GhdlCrossCheck::from_table(&table, Path::new("")),
+            Err("Expected ghdl_cross_check.top as library.entity, got 'top'".to_owned())
+        );
+    }
+}
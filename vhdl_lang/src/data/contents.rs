@@ -48,13 +48,19 @@ impl Contents {
         Position { line, character }
     }
 
-    #[cfg(test)]
     pub fn range(&self) -> Range {
         Range::new(self.start(), self.end())
     }
 
     #[cfg(test)]
     pub fn crop(&self, range: Range) -> Contents {
+        Contents {
+            lines: split_lines(&self.text(range)),
+        }
+    }
+
+    /// The text within the range
+    pub fn text(&self, range: Range) -> String {
         let mut reader = ContentReader::new(self);
         reader.seek_pos(range.start);
 
@@ -62,12 +68,11 @@ impl Contents {
         while reader.pos() < range.end {
             if let Some(chr) = reader.pop_char() {
                 result.push(chr);
+            } else {
+                break;
             }
         }
-
-        Contents {
-            lines: split_lines(&result),
-        }
+        result
     }
 
     pub fn num_lines(&self) -> usize {
@@ -324,7 +329,6 @@ impl<'a> ContentReader<'a> {
         self.state.pos()
     }
 
-    #[cfg(test)]
     pub fn seek_pos(&mut self, pos: Position) {
         self.state = ReaderState {
             pos: Position {
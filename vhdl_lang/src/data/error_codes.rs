@@ -392,6 +392,26 @@ pub enum ErrorCode {
     /// A declaration that is unused
     Unused,
 
+    /// A port that the architectures of its entity never use.
+    /// Ports of entities without architecture are not reported
+    UnusedPort,
+
+    /// A port of mode `out`, `inout` or `buffer` that the architectures of its entity use
+    /// but never drive
+    ///
+    /// # Example
+    /// ```vhdl
+    /// entity counter is
+    ///   port (count : buffer natural);
+    /// end entity;
+    ///
+    /// architecture rtl of counter is
+    /// begin
+    ///   assert count < 10;
+    /// end architecture;
+    /// ```
+    UndrivenPort,
+
     /// The declaration
     /// ```vhdl
     /// library work;
@@ -410,6 +430,26 @@ pub enum ErrorCode {
     /// ```
     UnassociatedContext,
 
+    /// A use of a declaration that is marked as deprecated
+    /// by an attribute or a comment of the `deprecation` table of the config
+    ///
+    /// # Example
+    /// ```vhdl
+    /// attribute deprecated : string;
+    /// attribute deprecated of old_fn : function is "Use new_fn";
+    /// ```
+    Deprecated,
+
+    /// Code that is excluded from synthesis by a pragma
+    ///
+    /// # Example
+    /// ```vhdl
+    /// -- synthesis translate_off
+    /// assert false report "Only in simulation";
+    /// -- synthesis translate_on
+    /// ```
+    ExcludedFromSynthesis,
+
     // Misc
     /// An internal error that signifies that some precondition within vhdl_lang wasn't met.
     /// If an error with this error code occurs,
@@ -494,7 +534,11 @@ impl Default for SeverityMap {
             | InvalidCall => Some(Error),
             Unused
             | UnnecessaryWorkLibrary
-            | UnassociatedContext => Some(Warning),
+            | UnassociatedContext
+            | UndrivenPort
+            | Deprecated => Some(Warning),
+            UnusedPort => Some(Warning),
+            ExcludedFromSynthesis => Some(Hint),
             Internal => Some(Error),
             Related => Some(Hint)
         };
@@ -516,6 +560,51 @@ impl IndexMut<ErrorCode> for SeverityMap {
     }
 }
 
+impl SeverityMap {
+    /// Set the severity of the diagnostics of an error code or of a class of diagnostics,
+    /// see `DIAGNOSTIC_CLASSES`. `None` hides the diagnostics.
+    pub fn set(&mut self, name: &str, severity: Option<Severity>) -> Result<(), String> {
+        if let Some((_, error_codes)) = DIAGNOSTIC_CLASSES.iter().find(|(class, _)| *class == name)
+        {
+            for error_code in error_codes.iter() {
+                self[*error_code] = severity;
+            }
+            return Ok(());
+        }
+        let error_code =
+            ErrorCode::try_from(name).map_err(|_| format!("'{name}' is not a valid error code"))?;
+        self[error_code] = severity;
+        Ok(())
+    }
+}
+
+/// Classes of diagnostics whose severity can be set together, by name
+pub const DIAGNOSTIC_CLASSES: &[(&str, &[ErrorCode])] = &[
+    (
+        "missing_bodies",
+        &[
+            ErrorCode::MissingProtectedBodyType,
+            ErrorCode::MissingDeferredDeclaration,
+            ErrorCode::MissingFullTypeDeclaration,
+        ],
+    ),
+    (
+        "unused",
+        &[
+            ErrorCode::Unused,
+            ErrorCode::UnusedPort,
+            ErrorCode::UndrivenPort,
+        ],
+    ),
+    (
+        "style",
+        &[
+            ErrorCode::UnnecessaryWorkLibrary,
+            ErrorCode::UnassociatedContext,
+        ],
+    ),
+];
+
 impl ErrorCode {
     pub fn as_str(&self) -> &str {
         self.into()
@@ -542,6 +631,21 @@ fn serialize_from_string() {
     );
 }
 
+#[test]
+fn set_severity_of_class() {
+    let mut severities = SeverityMap::default();
+    severities.set("missing_bodies", None).unwrap();
+    severities
+        .set("type_mismatch", Some(Severity::Warning))
+        .unwrap();
+    assert_eq!(severities[ErrorCode::MissingDeferredDeclaration], None);
+    assert_eq!(severities[ErrorCode::TypeMismatch], Some(Severity::Warning));
+    assert_eq!(
+        severities.set("missing", None),
+        Err("'missing' is not a valid error code".to_owned())
+    );
+}
+
 #[test]
 fn serialize_to_string() {
     assert_eq!(ErrorCode::VoidReturn.as_str(), "void_return");
@@ -566,6 +670,14 @@ impl Diagnostic {
         )
     }
 
+    pub fn excluded_from_synthesis(item: impl AsRef<SrcPos>) -> Diagnostic {
+        Self::new(
+            item,
+            "Excluded from synthesis",
+            ErrorCode::ExcludedFromSynthesis,
+        )
+    }
+
     pub fn internal(item: impl AsRef<SrcPos>, msg: impl Into<String>) -> Diagnostic {
         Self::new(item, msg, ErrorCode::Internal)
     }
@@ -0,0 +1,162 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! The comments that document a declaration
+
+use crate::analysis::DesignRoot;
+use crate::syntax::{Comment, TokenAccess};
+use crate::{EntRef, HasTokenSpan};
+
+/// The comments that the tokenizer attached to the first and last token of a declaration
+pub(crate) struct DeclarationComments<'a> {
+    /// The comments on the lines before the declaration
+    pub leading: &'a [Comment],
+    /// The comment after the end of the declaration on the same line
+    pub trailing: Option<&'a Comment>,
+    /// The line where the declaration starts
+    pub start_line: u32,
+}
+
+pub(crate) fn declaration_comments<'a>(
+    root: &'a DesignRoot,
+    ent: EntRef<'a>,
+) -> Option<DeclarationComments<'a>> {
+    let decl_pos = ent.decl_pos()?;
+    for unit in root.units_by_source(&decl_pos.source) {
+        let Some(data) = unit.unit.get() else {
+            continue;
+        };
+        let ctx: &dyn TokenAccess = &unit.tokens;
+        if !data.data().get_pos(ctx).contains(decl_pos.start()) {
+            continue;
+        }
+        let first = ctx.get_token(ent.src_span.start_token);
+        let leading = first
+            .comments
+            .as_ref()
+            .map(|comments| comments.leading.as_slice())
+            .unwrap_or_default();
+        let trailing = ctx
+            .get_token(ent.src_span.end_token)
+            .comments
+            .as_ref()
+            .and_then(|comments| comments.trailing.as_ref());
+        return Some(DeclarationComments {
+            leading,
+            trailing,
+            start_line: first.pos.start().line,
+        });
+    }
+    None
+}
+
+/// The block of comment lines immediately before the declaration of `ent` as Markdown.
+/// Doxygen style `--!` comments are treated the same as plain `--` comments.
+pub(crate) fn documentation(root: &DesignRoot, ent: EntRef) -> Option<String> {
+    let DeclarationComments {
+        leading: comments,
+        start_line: mut line,
+        ..
+    } = declaration_comments(root, ent)?;
+
+    // Only the comments that end on the line right before the declaration
+    // and the comments on the lines directly above them belong to it
+    let mut start = comments.len();
+    for comment in comments.iter().rev() {
+        if comment.range.end.line + 1 != line {
+            break;
+        }
+        line = comment.range.start.line;
+        start -= 1;
+    }
+
+    let lines: Vec<&str> = comments[start..]
+        .iter()
+        .flat_map(|comment| comment.value.lines())
+        .map(|line| {
+            let line = line.strip_prefix('!').unwrap_or(line);
+            line.strip_prefix(' ').unwrap_or(line).trim_end()
+        })
+        // Banners such as `-- ------` are not part of the text
+        .filter(|line| line.is_empty() || !line.chars().all(|chr| chr == '-'))
+        .collect();
+
+    let text = lines.join("\n");
+    let text = text.trim_matches('\n');
+    (!text.is_empty()).then(|| text.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+
+    #[test]
+    fn documentation_of_declarations() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+-- Not attached to the package
+
+--! Utilities of the design
+--!
+--! See the *manual*
+package pkg is
+  -- The width of the bus
+  -- in bits
+  constant width : natural := 8;
+
+  -- ---------------------
+  -- The states of the FSM
+  -- ---------------------
+  type state_t is (idle, busy);
+
+  constant undocumented : natural := 0; -- A trailing comment
+end package;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let documentation_of = |name: &str| {
+            let ent = root
+                .search_reference(code.source(), code.s1(name).start())
+                .unwrap();
+            documentation(&root, ent)
+        };
+        assert_eq!(
+            documentation_of("pkg"),
+            Some("Utilities of the design\n\nSee the *manual*".to_owned())
+        );
+        assert_eq!(
+            documentation_of("width :"),
+            Some("The width of the bus\nin bits".to_owned())
+        );
+        assert_eq!(
+            documentation_of("state_t"),
+            Some("The states of the FSM".to_owned())
+        );
+        assert_eq!(documentation_of("undocumented"), None);
+    }
+
+    #[test]
+    fn comments_separated_by_blank_line_are_not_documentation() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.in_declarative_region(
+            "
+  -- Unrelated comment
+
+  signal data : bit;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let ent = root
+            .search_reference(code.source(), code.s1("data").start())
+            .unwrap();
+        assert_eq!(documentation(&root, ent), None);
+    }
+}
@@ -0,0 +1,346 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! The generics and ports of entities for documentation generators
+
+use crate::analysis::DesignRoot;
+use crate::ast::{
+    AnyDesignUnit, AnyPrimaryUnit, EntityDeclaration, InterfaceDeclaration, ModeIndication,
+};
+use crate::data::{SrcPos, Symbol};
+use crate::syntax::{Kind, Token, TokenAccess};
+use serde_json::{json, Map, Value};
+
+/// The interface of an entity
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct EntityInterface {
+    pub library: String,
+    pub name: String,
+    pub pos: SrcPos,
+    /// The comments on the lines directly before the entity
+    pub description: Option<String>,
+    pub generics: Vec<InterfaceElement>,
+    pub ports: Vec<InterfaceElement>,
+}
+
+/// A generic or port of an entity
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct InterfaceElement {
+    pub name: String,
+    /// The mode of a port, `view` for a port with a mode view
+    pub direction: Option<String>,
+    pub typ: String,
+    pub default_value: Option<String>,
+    /// The comments on the lines directly before the element,
+    /// or the comment at the end of its line
+    pub description: Option<String>,
+}
+
+impl EntityInterface {
+    /// The interface as a JSON object in the style of TerosHDL
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.json_value()).unwrap_or_default()
+    }
+
+    fn json_value(&self) -> Value {
+        json!({
+            "library": self.library,
+            "name": self.name,
+            "file": self.pos.source.file_name().to_string_lossy(),
+            "line": self.pos.start().line + 1,
+            "description": self.description,
+            "generics": self.generics.iter().map(InterfaceElement::json_value).collect::<Vec<_>>(),
+            "ports": self.ports.iter().map(InterfaceElement::json_value).collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl InterfaceElement {
+    fn json_value(&self) -> Value {
+        let mut element = Map::new();
+        element.insert("name".to_owned(), json!(self.name));
+        // Generics have no direction
+        if let Some(ref direction) = self.direction {
+            element.insert("direction".to_owned(), json!(direction));
+        }
+        element.insert("type".to_owned(), json!(self.typ));
+        element.insert("default_value".to_owned(), json!(self.default_value));
+        element.insert("description".to_owned(), json!(self.description));
+        Value::Object(element)
+    }
+}
+
+/// The interfaces as a JSON array
+pub fn interfaces_to_json(interfaces: &[EntityInterface]) -> String {
+    let interfaces: Vec<Value> = interfaces.iter().map(EntityInterface::json_value).collect();
+    serde_json::to_string_pretty(&interfaces).unwrap_or_default()
+}
+
+/// The interfaces of the entities of the libraries, in the order of the source files
+pub fn entity_interfaces(root: &DesignRoot, libraries: &[Symbol]) -> Vec<EntityInterface> {
+    let mut result = Vec::new();
+    for library_name in libraries {
+        let Some(library) = root.get_lib(library_name) else {
+            continue;
+        };
+        for unit in library.primary_units() {
+            let Some(data) = unit.unit.get() else {
+                continue;
+            };
+            if let AnyDesignUnit::Primary(AnyPrimaryUnit::Entity(entity)) = data.data() {
+                result.push(entity_interface(library_name, entity, &unit.tokens));
+            }
+        }
+    }
+    result.sort_by(|interface1, interface2| {
+        (interface1.pos.source.file_name(), interface1.pos.start())
+            .cmp(&(interface2.pos.source.file_name(), interface2.pos.start()))
+    });
+    result
+}
+
+fn entity_interface(
+    library: &Symbol,
+    entity: &EntityDeclaration,
+    tokens: &[Token],
+) -> EntityInterface {
+    let ident = tokens.get_token(entity.ident.tree.token);
+    // The comments precede the `entity` keyword
+    let description = token_index(tokens, ident)
+        .and_then(|idx| idx.checked_sub(1))
+        .and_then(|idx| leading_doc(&tokens[idx]));
+
+    EntityInterface {
+        library: library.name_utf8(),
+        name: entity.ident.tree.item.name_utf8(),
+        pos: ident.pos.clone(),
+        description,
+        generics: interface_elements(entity.generic_clause.as_deref(), tokens, false),
+        ports: interface_elements(entity.port_clause.as_deref(), tokens, true),
+    }
+}
+
+fn interface_elements(
+    declarations: Option<&[InterfaceDeclaration]>,
+    tokens: &[Token],
+    is_port: bool,
+) -> Vec<InterfaceElement> {
+    let mut result = Vec::new();
+    for declaration in declarations.unwrap_or_default() {
+        let (ident, direction, typ, default_value) = match declaration {
+            InterfaceDeclaration::Object(object) => match object.mode {
+                ModeIndication::Simple(ref mode) => (
+                    &object.ident.tree,
+                    is_port.then(|| mode.mode.unwrap_or_default().to_string()),
+                    mode.subtype_indication.to_string(),
+                    mode.expression.as_ref().map(|expr| expr.item.to_string()),
+                ),
+                ModeIndication::View(ref view) => (
+                    &object.ident.tree,
+                    Some("view".to_owned()),
+                    view.to_string(),
+                    None,
+                ),
+            },
+            InterfaceDeclaration::Type(ident) => (&ident.tree, None, "type".to_owned(), None),
+            _ => continue,
+        };
+        let ident_token = tokens.get_token(ident.token);
+        result.push(InterfaceElement {
+            name: ident.item.name_utf8(),
+            direction,
+            typ,
+            default_value,
+            description: leading_doc(ident_token).or_else(|| trailing_doc(tokens, ident_token)),
+        });
+    }
+    result
+}
+
+fn token_index(tokens: &[Token], token: &Token) -> Option<usize> {
+    tokens
+        .iter()
+        .position(|candidate| std::ptr::eq(candidate, token))
+}
+
+/// The comments on the lines directly before the token
+fn leading_doc(token: &Token) -> Option<String> {
+    let comments = &token.comments.as_ref()?.leading;
+    let mut line = token.pos.start().line;
+    let mut lines = Vec::new();
+    for comment in comments.iter().rev() {
+        if comment.range.end.line + 1 != line {
+            break;
+        }
+        line = comment.range.start.line;
+        lines.push(doc_text(&comment.value));
+    }
+    lines.reverse();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// The comment at the end of the line of an interface declaration,
+/// i.e. after its semicolon or after the last declaration of the list
+fn trailing_doc(tokens: &[Token], ident: &Token) -> Option<String> {
+    let start = token_index(tokens, ident)?;
+    let mut depth = 0;
+    let mut last = start;
+    for (idx, token) in tokens.iter().enumerate().skip(start) {
+        match token.kind {
+            Kind::LeftPar => depth += 1,
+            Kind::RightPar if depth == 0 => break,
+            Kind::RightPar => depth -= 1,
+            Kind::SemiColon if depth == 0 => {
+                last = idx;
+                break;
+            }
+            _ => {}
+        }
+        last = idx;
+    }
+    let comment = tokens[last].comments.as_ref()?.trailing.as_ref()?;
+    Some(doc_text(&comment.value))
+}
+
+/// The text of a comment without markers such as `--!`
+fn doc_text(comment: &str) -> String {
+    comment.trim_start_matches(['-', '!']).trim().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+
+    #[test]
+    fn extracts_generics_and_ports_with_comments() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "\
+-- Copyright notice
+
+--! A FIFO with a configurable depth
+library ieee;
+use ieee.std_logic_1164.all;
+
+--! The FIFO
+entity fifo is
+  generic (
+    --! The width of the data
+    width : natural := 8;
+    depth : positive -- The number of words
+  );
+  port (
+    clk : in std_logic; -- The clock
+    data : in std_logic_vector(width - 1 downto 0);
+    full : out boolean := false --! High when full
+  );
+end entity;
+",
+        );
+        builder.add_std_logic_1164();
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let interfaces = entity_interfaces(&root, &[root.symbol_utf8("libname")]);
+        assert_eq!(interfaces.len(), 1);
+        let fifo = &interfaces[0];
+        assert_eq!(fifo.name, "fifo");
+        assert_eq!(fifo.description.as_deref(), Some("The FIFO"));
+        assert_eq!(
+            fifo.generics,
+            vec![
+                InterfaceElement {
+                    name: "width".to_owned(),
+                    direction: None,
+                    typ: "natural".to_owned(),
+                    default_value: Some("8".to_owned()),
+                    description: Some("The width of the data".to_owned()),
+                },
+                InterfaceElement {
+                    name: "depth".to_owned(),
+                    direction: None,
+                    typ: "positive".to_owned(),
+                    default_value: None,
+                    description: Some("The number of words".to_owned()),
+                },
+            ]
+        );
+        assert_eq!(
+            fifo.ports
+                .iter()
+                .map(|port| (
+                    port.name.as_str(),
+                    port.direction.as_deref(),
+                    port.typ.as_str(),
+                    port.description.as_deref()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("clk", Some("in"), "std_logic", Some("The clock")),
+                (
+                    "data",
+                    Some("in"),
+                    "std_logic_vector(width - 1 downto 0)",
+                    None
+                ),
+                ("full", Some("out"), "boolean", Some("High when full")),
+            ]
+        );
+    }
+
+    #[test]
+    fn interfaces_as_json() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity buf is
+  port (
+    a : in bit; -- The \"input\"
+    y : out bit
+  );
+end entity;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let interfaces = entity_interfaces(&root, &[root.symbol_utf8("libname")]);
+        let file = code.source().file_name().to_string_lossy();
+        let json: Value = serde_json::from_str(&interfaces_to_json(&interfaces)).unwrap();
+        assert_eq!(
+            json,
+            json!([
+                {
+                    "library": "libname",
+                    "name": "buf",
+                    "file": file,
+                    "line": 1,
+                    "description": null,
+                    "generics": [],
+                    "ports": [
+                        {
+                            "name": "a",
+                            "direction": "in",
+                            "type": "bit",
+                            "default_value": null,
+                            "description": "The \"input\""
+                        },
+                        {
+                            "name": "y",
+                            "direction": "out",
+                            "type": "bit",
+                            "default_value": null,
+                            "description": null
+                        }
+                    ]
+                }
+            ])
+        );
+    }
+}
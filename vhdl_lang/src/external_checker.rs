@@ -0,0 +1,507 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! External tools, such as `ghdl -s` or a synthesis lint, that check the design
+//! and report diagnostics in their output
+
+use crate::data::{Position, Severity};
+use crate::ip_core::json_integer;
+use regex::Regex;
+use serde_json::Deserializer;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use toml::{Table, Value};
+
+/// Matches output such as `src/top.vhd:12:5: error: no declaration for "x"`
+pub const DEFAULT_CHECKER_PATTERN: &str = r"^(?P<file>(?:[A-Za-z]:)?[^:]+):(?P<line>\d+):(?:(?P<column>\d+):)?\s*(?:(?P<severity>[A-Za-z]+)\s*:)?\s*(?P<message>.+)$";
+
+/// The time a checker may run before it is killed, in seconds
+pub const DEFAULT_CHECKER_TIMEOUT: u64 = 30;
+
+/// A checker of the `checkers` table of the config
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ExternalChecker {
+    pub name: String,
+    /// The program and its arguments.
+    /// `${file}` and `${library}` are replaced by the checked file and its library.
+    command: Vec<String>,
+    /// Run the checker whenever a file is saved, otherwise only on demand
+    pub run_on_save: bool,
+    format: OutputFormat,
+    /// The checker is killed when it runs for longer than this
+    timeout: Duration,
+    /// The working directory, i.e. the directory of the config file.
+    /// Relative file names of the output are relative to it.
+    directory: PathBuf,
+}
+
+#[derive(Clone, Debug)]
+enum OutputFormat {
+    /// Each line that matches the pattern is a diagnostic.
+    /// The pattern has the named groups `file`, `line`, `message`
+    /// and optionally `column` and `severity`.
+    Regex(Regex),
+    /// A JSON array of diagnostics or a JSON object per diagnostic.
+    /// The fields are the keys of the file, line, column, severity and message
+    /// within the objects, nested keys are separated by dots.
+    Json([String; 5]),
+}
+
+impl PartialEq for OutputFormat {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (OutputFormat::Regex(regex), OutputFormat::Regex(other)) => {
+                regex.as_str() == other.as_str()
+            }
+            (OutputFormat::Json(fields), OutputFormat::Json(other)) => fields == other,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for OutputFormat {}
+
+/// The runs of an external checker on the files of a project.
+/// It does not refer to the project so that it can run on another thread.
+#[derive(Clone, Debug)]
+pub struct ExternalCheckerRun {
+    pub checker: ExternalChecker,
+    /// The files and their libraries that a checker of a single file checks
+    files: Vec<(PathBuf, String)>,
+}
+
+/// A diagnostic of an external checker
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ExternalDiagnostic {
+    /// The name of the checker
+    pub source: String,
+    pub file: PathBuf,
+    pub pos: Position,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl ExternalChecker {
+    /// A checker that runs the command in the directory and parses its output
+    /// with the default pattern
+    pub(crate) fn new(name: &str, command: Vec<String>, directory: &Path) -> ExternalChecker {
+        ExternalChecker {
+            name: name.to_owned(),
+            command,
+            run_on_save: false,
+            format: OutputFormat::Regex(
+                Regex::new(DEFAULT_CHECKER_PATTERN).expect("The default pattern is valid"),
+            ),
+            timeout: Duration::from_secs(DEFAULT_CHECKER_TIMEOUT),
+            directory: directory.to_owned(),
+        }
+    }
+
+    pub(crate) fn from_table(
+        name: &str,
+        table: &Table,
+        parent: &Path,
+    ) -> Result<ExternalChecker, String> {
+        let command = table
+            .get("command")
+            .and_then(Value::as_array)
+            .and_then(|command| {
+                command
+                    .iter()
+                    .map(|arg| arg.as_str().map(str::to_owned))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .filter(|command| !command.is_empty())
+            .ok_or_else(|| format!("checkers.{name}.command must be an array of strings"))?;
+
+        let run_on_save = match string_field(table, name, "run")? {
+            Some("save") => true,
+            None | Some("manual") => false,
+            Some(value) => {
+                return Err(format!(
+                    "'{value}' is not a valid value of checkers.{name}.run"
+                ))
+            }
+        };
+
+        let format = match string_field(table, name, "format")? {
+            None | Some("regex") => {
+                let pattern =
+                    string_field(table, name, "pattern")?.unwrap_or(DEFAULT_CHECKER_PATTERN);
+                OutputFormat::Regex(
+                    Regex::new(pattern)
+                        .map_err(|err| format!("Invalid pattern of checkers.{name}: {err}"))?,
+                )
+            }
+            Some("json") => {
+                let mut fields =
+                    ["file", "line", "column", "severity", "message"].map(str::to_owned);
+                if let Some(keys) = table.get("fields") {
+                    let keys = keys
+                        .as_table()
+                        .ok_or_else(|| format!("checkers.{name}.fields must be a table"))?;
+                    for (key, value) in keys {
+                        let Some(idx) = ["file", "line", "column", "severity", "message"]
+                            .iter()
+                            .position(|field| field == key)
+                        else {
+                            return Err(format!("'{key}' is not a valid field of checkers.{name}"));
+                        };
+                        fields[idx] = value
+                            .as_str()
+                            .ok_or_else(|| {
+                                format!("checkers.{name}.fields.{key} must be a string")
+                            })?
+                            .to_owned();
+                    }
+                }
+                OutputFormat::Json(fields)
+            }
+            Some(value) => {
+                return Err(format!(
+                    "'{value}' is not a valid value of checkers.{name}.format"
+                ))
+            }
+        };
+
+        let timeout = match table.get("timeout") {
+            None => DEFAULT_CHECKER_TIMEOUT,
+            Some(value) => value
+                .as_integer()
+                .and_then(|value| u64::try_from(value).ok())
+                .filter(|value| *value > 0)
+                .ok_or_else(|| {
+                    format!("checkers.{name}.timeout must be a positive number of seconds")
+                })?,
+        };
+
+        Ok(ExternalChecker {
+            name: name.to_owned(),
+            command,
+            run_on_save,
+            format,
+            timeout: Duration::from_secs(timeout),
+            directory: parent.to_owned(),
+        })
+    }
+
+    /// True if the checker checks a single file, i.e. the command refers to `${file}`.
+    /// Other checkers check the whole project.
+    pub fn is_per_file(&self) -> bool {
+        self.command.iter().any(|arg| arg.contains("${file}"))
+    }
+
+    /// Run the checker on a file of a library or on the whole project
+    pub fn run(&self, file: Option<(&Path, &str)>) -> Result<Vec<ExternalDiagnostic>, String> {
+        let command: Vec<String> = self
+            .command
+            .iter()
+            .map(|arg| match file {
+                Some((file_name, library)) => arg
+                    .replace("${file}", &file_name.to_string_lossy())
+                    .replace("${library}", library),
+                None => arg.clone(),
+            })
+            .collect();
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| format!("Checker '{}' has no command", self.name))?;
+
+        let mut child = Command::new(program)
+            .args(args)
+            .current_dir(&self.directory)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("Failed to run checker '{}': {err}", self.name))?;
+        // The pipes are read while waiting so that the checker does not block on a full pipe
+        let stdout = read_to_end(child.stdout.take());
+        let stderr = read_to_end(child.stderr.take());
+
+        let deadline = Instant::now() + self.timeout;
+        // Checkers usually exit with an error when they find problems
+        loop {
+            match child.try_wait() {
+                Ok(Some(_)) => break,
+                Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+                Ok(None) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(format!(
+                        "Checker '{}' did not finish within {} seconds",
+                        self.name,
+                        self.timeout.as_secs()
+                    ));
+                }
+                Err(err) => return Err(format!("Failed to run checker '{}': {err}", self.name)),
+            }
+        }
+
+        let mut text = String::from_utf8_lossy(&stdout.join().unwrap_or_default()).into_owned();
+        text.push('\n');
+        text.push_str(&String::from_utf8_lossy(&stderr.join().unwrap_or_default()));
+        Ok(self.parse_output(&text))
+    }
+
+    /// The diagnostics of the output of the checker
+    pub fn parse_output(&self, output: &str) -> Vec<ExternalDiagnostic> {
+        match self.format {
+            OutputFormat::Regex(ref regex) => output
+                .lines()
+                .filter_map(|line| {
+                    let captures = regex.captures(line.trim_end())?;
+                    let group = |name: &str| captures.name(name).map(|group| group.as_str());
+                    self.diagnostic(
+                        group("file")?,
+                        group("line")?.parse().ok()?,
+                        group("column").and_then(|column| column.parse().ok()),
+                        group("severity"),
+                        group("message")?,
+                    )
+                })
+                .collect(),
+            OutputFormat::Json(ref fields) => {
+                let mut objects = Vec::new();
+                for value in Deserializer::from_str(output).into_iter::<serde_json::Value>() {
+                    match value {
+                        Ok(serde_json::Value::Array(elements)) => objects.extend(elements),
+                        Ok(value) => objects.push(value),
+                        Err(_) => break,
+                    }
+                }
+                let [file, line, column, severity, message] = fields;
+                objects
+                    .iter()
+                    .filter_map(|object| {
+                        let field = |path: &str| {
+                            path.split('.')
+                                .try_fold(object, |value, key| value.get(key))
+                        };
+                        let text = |path: &str| field(path)?.as_str();
+                        self.diagnostic(
+                            text(file)?,
+                            json_integer(field(line)?)?.try_into().ok()?,
+                            field(column)
+                                .and_then(json_integer)
+                                .and_then(|column| column.try_into().ok()),
+                            text(severity),
+                            text(message)?,
+                        )
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// A diagnostic at the one-based line and column
+    fn diagnostic(
+        &self,
+        file: &str,
+        line: u32,
+        column: Option<u32>,
+        severity: Option<&str>,
+        message: &str,
+    ) -> Option<ExternalDiagnostic> {
+        let mut message = message.trim().to_owned();
+        let severity = match severity.map(str::to_lowercase).as_deref() {
+            None | Some("error" | "fatal" | "failure") => Severity::Error,
+            Some("warning" | "warn") => Severity::Warning,
+            Some("info" | "information" | "note") => Severity::Info,
+            Some("hint") => Severity::Hint,
+            // Not a severity but the start of the message
+            Some(_) => {
+                message = format!("{}: {message}", severity.unwrap_or_default());
+                Severity::Error
+            }
+        };
+        Some(ExternalDiagnostic {
+            source: self.name.clone(),
+            file: self.directory.join(file.trim()),
+            pos: Position::new(
+                line.saturating_sub(1),
+                column.unwrap_or(1).saturating_sub(1),
+            ),
+            severity,
+            message,
+        })
+    }
+}
+
+impl ExternalCheckerRun {
+    /// Runs of the checker on the files, or a single run on the whole project
+    /// for a checker that does not check a single file
+    pub(crate) fn new(checker: ExternalChecker, files: Vec<(PathBuf, String)>) -> Self {
+        ExternalCheckerRun { checker, files }
+    }
+
+    /// Run the checker. Returns its diagnostics and the errors of the runs that failed.
+    pub fn run(&self) -> (Vec<ExternalDiagnostic>, Vec<String>) {
+        let results = if self.checker.is_per_file() {
+            self.files
+                .iter()
+                .map(|(file_name, library)| self.checker.run(Some((file_name, library))))
+                .collect()
+        } else {
+            vec![self.checker.run(None)]
+        };
+        let mut diagnostics = Vec::new();
+        let mut errors = Vec::new();
+        for result in results {
+            match result {
+                Ok(result) => diagnostics.extend(result),
+                Err(err) => errors.push(err),
+            }
+        }
+        (diagnostics, errors)
+    }
+}
+
+/// Read the pipe on a separate thread
+fn read_to_end(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        bytes
+    })
+}
+
+fn string_field<'a>(table: &'a Table, name: &str, key: &str) -> Result<Option<&'a str>, String> {
+    table
+        .get(key)
+        .map(|value| {
+            value
+                .as_str()
+                .ok_or_else(|| format!("checkers.{name}.{key} must be a string"))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checker(table: &str) -> ExternalChecker {
+        ExternalChecker::from_table(
+            "ghdl",
+            &table.parse::<Table>().unwrap(),
+            Path::new("project"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_output_with_default_pattern() {
+        let checker = checker("command = ['ghdl', '-s', '${file}']");
+        assert!(!checker.run_on_save);
+        assert!(checker.is_per_file());
+        assert_eq!(
+            checker.parse_output(
+                "\
+src/top.vhd:12:5: error: no declaration for \"x\"
+src/top.vhd:3:1:warning: unused signal
+src/top.vhd:7:1: Expected: ';'
+ghdl: compilation error
+"
+            ),
+            vec![
+                ExternalDiagnostic {
+                    source: "ghdl".to_owned(),
+                    file: Path::new("project").join("src/top.vhd"),
+                    pos: Position::new(11, 4),
+                    severity: Severity::Error,
+                    message: "no declaration for \"x\"".to_owned(),
+                },
+                ExternalDiagnostic {
+                    source: "ghdl".to_owned(),
+                    file: Path::new("project").join("src/top.vhd"),
+                    pos: Position::new(2, 0),
+                    severity: Severity::Warning,
+                    message: "unused signal".to_owned(),
+                },
+                ExternalDiagnostic {
+                    source: "ghdl".to_owned(),
+                    file: Path::new("project").join("src/top.vhd"),
+                    pos: Position::new(6, 0),
+                    severity: Severity::Error,
+                    message: "Expected: ';'".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_json_output() {
+        let checker = checker(
+            "
+command = ['lint', '--json']
+run = 'save'
+format = 'json'
+fields = { file = 'location.path', line = 'location.line', severity = 'level' }
+",
+        );
+        assert!(checker.run_on_save);
+        assert!(!checker.is_per_file());
+        assert_eq!(
+            checker.parse_output(
+                r#"[
+  {"location": {"path": "a.vhd", "line": 2}, "level": "info", "message": "Consider a constant"}
+]"#
+            ),
+            vec![ExternalDiagnostic {
+                source: "ghdl".to_owned(),
+                file: Path::new("project").join("a.vhd"),
+                pos: Position::new(1, 0),
+                severity: Severity::Info,
+                message: "Consider a constant".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_checkers() {
+        let parse = |table: &str| {
+            ExternalChecker::from_table("lint", &table.parse::<Table>().unwrap(), Path::new(""))
+        };
+        assert_eq!(
+            parse("command = []"),
+            Err("checkers.lint.command must be an array of strings".to_owned())
+        );
+        assert_eq!(
+            parse("command = ['lint']\nrun = 'always'"),
+            Err("'always' is not a valid value of checkers.lint.run".to_owned())
+        );
+        assert!(parse("command = ['lint']\npattern = '('").is_err());
+        assert_eq!(
+            parse("command = ['lint']\ntimeout = 0"),
+            Err("checkers.lint.timeout must be a positive number of seconds".to_owned())
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn kills_checker_after_timeout() {
+        let checker = ExternalChecker::from_table(
+            "sleep",
+            &"command = ['sleep', '10']\ntimeout = 1"
+                .parse::<Table>()
+                .unwrap(),
+            Path::new("."),
+        )
+        .unwrap();
+        let start = Instant::now();
+        assert_eq!(
+            checker.run(None),
+            Err("Checker 'sleep' did not finish within 1 seconds".to_owned())
+        );
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,204 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! The regions of a source file that an editor can fold
+
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::data::{Range, Source};
+use crate::syntax::{Comment, TokenAccess};
+use crate::HasTokenSpan;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum FoldingRangeKind {
+    /// A design unit, subprogram, type or statement
+    Region,
+    /// Consecutive comment lines
+    Comment,
+}
+
+/// A range of lines where all lines but the first can be hidden
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct FoldingRange {
+    /// The zero-based line where the region starts
+    pub start_line: u32,
+    /// The zero-based line where the region ends
+    pub end_line: u32,
+    pub kind: FoldingRangeKind,
+}
+
+pub(crate) fn folding_ranges(root: &DesignRoot, source: &Source) -> Vec<FoldingRange> {
+    let mut searcher = FoldingRangeSearcher::default();
+    let _ = root.search_source(source, &mut searcher);
+    let mut ranges = searcher.ranges;
+
+    for unit in root.units_by_source(source) {
+        for token in unit.tokens.iter() {
+            if let Some(comments) = &token.comments {
+                add_comment_blocks(&mut ranges, &comments.leading);
+            }
+        }
+    }
+
+    // The same unit is found through every library that a file is mapped to
+    ranges.sort();
+    ranges.dedup();
+    ranges
+}
+
+/// Add a range for each block of comments on consecutive lines
+fn add_comment_blocks(ranges: &mut Vec<FoldingRange>, comments: &[Comment]) {
+    let mut block: Option<Range> = None;
+    for comment in comments {
+        block = match block {
+            Some(range) if comment.range.start.line <= range.end.line + 1 => {
+                Some(Range::new(range.start, comment.range.end))
+            }
+            Some(range) => {
+                add_range(ranges, range, FoldingRangeKind::Comment);
+                Some(comment.range)
+            }
+            None => Some(comment.range),
+        };
+    }
+    if let Some(range) = block {
+        add_range(ranges, range, FoldingRangeKind::Comment);
+    }
+}
+
+fn add_range(ranges: &mut Vec<FoldingRange>, range: Range, kind: FoldingRangeKind) {
+    if range.end.line > range.start.line {
+        ranges.push(FoldingRange {
+            start_line: range.start.line,
+            end_line: range.end.line,
+            kind,
+        });
+    }
+}
+
+#[derive(Default)]
+struct FoldingRangeSearcher {
+    ranges: Vec<FoldingRange>,
+}
+
+impl Searcher for FoldingRangeSearcher {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        let span = match decl {
+            FoundDeclaration::Entity(unit) => unit.span,
+            FoundDeclaration::Architecture(unit) => unit.span,
+            FoundDeclaration::Package(unit) => unit.span,
+            FoundDeclaration::PackageBody(unit) => unit.span,
+            FoundDeclaration::Configuration(unit) => unit.span,
+            FoundDeclaration::Context(unit) => unit.span,
+            FoundDeclaration::Subprogram(body) => body.span,
+            FoundDeclaration::Component(component) => component.span,
+            FoundDeclaration::Type(typ) => typ.span,
+            FoundDeclaration::ConcurrentStatement(statement) => statement.span(),
+            FoundDeclaration::SequentialStatement(statement) => statement.span(),
+            _ => return NotFinished,
+        };
+        add_range(
+            &mut self.ranges,
+            span.pos(ctx).range(),
+            FoldingRangeKind::Region,
+        );
+        NotFinished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::syntax::test::Code;
+
+    fn region(code: &Code, start: &str, end: &str) -> FoldingRange {
+        FoldingRange {
+            start_line: code.s1(start).start().line,
+            end_line: code.s1(end).start().line,
+            kind: FoldingRangeKind::Region,
+        }
+    }
+
+    #[test]
+    fn folds_design_units_subprograms_and_statements() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture a of ent is
+  function f(value : natural) return natural is
+  begin
+    return value;
+  end function;
+
+  signal s : natural;
+begin
+  main: process
+  begin
+    if s = 0 then
+      s <= f(1);
+    end if;
+    wait;
+  end process;
+
+  gen: for i in 0 to 1 generate
+    s <= i;
+  end generate;
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        assert_eq!(
+            folding_ranges(&root, code.source()),
+            vec![
+                region(&code, "entity ent", "end entity"),
+                region(&code, "architecture a", "end architecture"),
+                region(&code, "function f", "end function"),
+                region(&code, "main:", "end process"),
+                region(&code, "if s", "end if"),
+                region(&code, "gen:", "end generate"),
+            ]
+        );
+    }
+
+    #[test]
+    fn folds_consecutive_comment_lines() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+-- The first line
+-- The second line
+entity ent is
+  -- A single line
+
+  -- Another block
+  -- of two lines
+end entity;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let comment = |start: &str, end: &str| FoldingRange {
+            start_line: code.s1(start).start().line,
+            end_line: code.s1(end).start().line,
+            kind: FoldingRangeKind::Comment,
+        };
+        assert_eq!(
+            folding_ranges(&root, code.source()),
+            vec![
+                comment("-- The first", "-- The second"),
+                region(&code, "entity ent", "end entity"),
+                comment("-- Another", "-- of two"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,1320 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! A formatter that keeps the layout of the code but re-indents every line
+//! according to the nesting of the design units, declarations and statements.
+//! Continuation lines are indented one level deeper than the line they continue
+//! and the lines within parentheses one level deeper than the opening parenthesis.
+//!
+//! # Design
+//!
+//! The formatter re-indents the lines of the source instead of printing the AST.
+//! Printing the AST would need a printer for every node and would have to re-create
+//! the line breaks and the placement of the comments that the author chose.
+//! Re-indenting only needs the position of each node, so the line breaks, comments and
+//! spacing within a line are kept unless a setting asks to change them.
+//!
+//! Formatting a file takes two passes:
+//!
+//! - [`indentation`] walks the design file once and computes a `Layout`: the indentation
+//!   level of each line together with what the settings need to know about it, e.g. the
+//!   lists that can be wrapped, the keywords and the lines that must be kept verbatim,
+//!   such as the continuation lines of block comments and the regions between
+//!   [`FMT_OFF_COMMENT`] and [`FMT_ON_COMMENT`].
+//! - [`VHDLFormatter`] pushes each line of the source with its level to a `Buffer`,
+//!   which indents it according to the [`FormatterConfig`] and wraps or aligns it.
+//!   Only the lines that differ from the source become [`TextEdit`]s, so that the other
+//!   lines are left alone, e.g. by the range formatting of an editor.
+//!
+//! Only files without syntax errors are formatted, since the layout comes from the AST.
+//! The formatter does not add, remove or reorder tokens, except when it sorts and deduplicates
+//! context clauses, and changes only the case of keywords. [`verify`] checks this and that
+//! formatting the formatted code again changes nothing.
+//! [`config`] reads the settings from the nearest [`FORMATTER_CONFIG_FILE_NAME`] file.
+
+mod buffer;
+mod config;
+mod context_clauses;
+mod indentation;
+mod verify;
+
+use crate::ast::DesignFile;
+use crate::data::{Position, Range, Source, SrcPos};
+use crate::refactor::TextEdit;
+use buffer::Buffer;
+pub use config::FORMATTER_CONFIG_FILE_NAME;
+use context_clauses::sort_clauses;
+use indentation::{Alignment, Layout};
+
+/// The formatter keeps the lines from a comment that contains this text up to and including
+/// a comment that contains [`FMT_ON_COMMENT`] as they are, e.g. for hand-aligned tables.
+/// Whitespace and case are ignored, i.e. `-- vhdl_lang: fmt off` is also recognized.
+pub const FMT_OFF_COMMENT: &str = "vhdl_lang:fmtoff";
+pub const FMT_ON_COMMENT: &str = "vhdl_lang:fmton";
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FormatterConfig {
+    /// The number of spaces of an indentation level when not using tabs
+    pub indent_width: usize,
+    /// Indent with one tab per level instead of spaces
+    pub use_tabs: bool,
+    /// The column at which long lines are wrapped, either after their logical operators
+    /// and the `else` of conditional expressions or by putting the elements of their
+    /// port maps, association lists and other parenthesized lists on separate lines
+    pub max_line_length: Option<usize>,
+    /// Align the colons and `:=` of the signal, constant, variable, port and generic
+    /// declarations on adjacent lines
+    pub align_declarations: bool,
+    /// Align the `=>` of the associations of port maps and generic maps
+    /// and of the case alternatives on adjacent lines
+    pub align_arrows: bool,
+    /// Split the comments on their own lines that do not fit within `max_line_length`
+    /// into several comments
+    pub reflow_comments: bool,
+    pub keyword_case: KeywordCase,
+    /// Join the lines of a port map, generic map or the arguments of a call that spans
+    /// several lines into a single line when it fits within `max_line_length`
+    pub compact_lists: bool,
+    /// Align the comments at the end of adjacent lines to the same column
+    pub align_trailing_comments: bool,
+    /// Sort the library clauses, context references and use clauses of each context clause
+    /// and remove the duplicates
+    pub sort_context_clauses: bool,
+    /// The number of consecutive blank lines to keep, e.g. between groups of declarations.
+    /// The blank lines beyond it are removed. All blank lines are kept by default
+    pub max_blank_lines: Option<usize>,
+}
+
+/// The case of the reserved words such as `entity` and `and`
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum KeywordCase {
+    /// Keep the case of the code
+    #[default]
+    Preserve,
+    Lower,
+    Upper,
+}
+
+impl Default for FormatterConfig {
+    fn default() -> Self {
+        FormatterConfig {
+            indent_width: 4,
+            use_tabs: false,
+            max_line_length: None,
+            align_declarations: false,
+            align_arrows: false,
+            reflow_comments: false,
+            keyword_case: KeywordCase::Preserve,
+            compact_lists: false,
+            align_trailing_comments: false,
+            sort_context_clauses: false,
+            max_blank_lines: None,
+        }
+    }
+}
+
+/// The lines from `first` up to and including `last`, starting at zero
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LineRange {
+    pub first: u32,
+    pub last: u32,
+}
+
+impl TryFrom<&str> for LineRange {
+    type Error = String;
+
+    /// Parse the one-based lines of the form `first:last`
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let invalid = || format!("'{value}' is not a valid line range");
+        let (first, last) = value.split_once(':').ok_or_else(invalid)?;
+        match (first.trim().parse::<u32>(), last.trim().parse::<u32>()) {
+            (Ok(first @ 1..), Ok(last)) if first <= last => Ok(LineRange {
+                first: first - 1,
+                last: last - 1,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+pub struct VHDLFormatter {
+    config: FormatterConfig,
+}
+
+impl VHDLFormatter {
+    pub fn new(config: FormatterConfig) -> VHDLFormatter {
+        VHDLFormatter { config }
+    }
+
+    /// The edits that format `source`, given the design file that was parsed from it
+    /// without syntax errors. Only the lines that change are edited.
+    pub fn format_design_file(&self, source: &Source, design_file: &DesignFile) -> Vec<TextEdit> {
+        self.format_lines(source, &Layout::of_design_file(design_file))
+    }
+
+    /// The edits that format the lines of the smallest design unit, declaration or statement
+    /// that encloses `range`
+    pub fn format_range(
+        &self,
+        source: &Source,
+        design_file: &DesignFile,
+        range: Range,
+    ) -> Vec<TextEdit> {
+        let mut last_line = range.end.line;
+        if range.end.character == 0 && last_line > range.start.line {
+            // A selection of whole lines ends at the start of the following line
+            last_line -= 1;
+        }
+        let lines = LineRange {
+            first: range.start.line,
+            last: last_line,
+        };
+        self.format_ranges(source, design_file, &[lines])
+    }
+
+    /// The edits that format the lines of the smallest design units, declarations or
+    /// statements that enclose the line ranges, e.g. the lines that were changed
+    pub fn format_ranges(
+        &self,
+        source: &Source,
+        design_file: &DesignFile,
+        ranges: &[LineRange],
+    ) -> Vec<TextEdit> {
+        let layout = Layout::of_design_file(design_file);
+        let ranges: Vec<(u32, u32)> = ranges
+            .iter()
+            .map(|range| layout.enclosing_lines(range.first, range.last))
+            .collect();
+        self.format_lines(source, &layout)
+            .into_iter()
+            .filter(|edit| {
+                let line = edit.pos.start().line;
+                ranges
+                    .iter()
+                    .any(|(first, last)| (*first..=*last).contains(&line))
+            })
+            .collect()
+    }
+
+    /// The edits that re-indent the code when `typed` is typed before the cursor:
+    /// the statement or declaration that ends with a `;`, the block that is closed
+    /// by an `end` and the line before a newline.
+    /// The line of the cursor is not changed after a newline, to keep its indentation
+    /// by the editor.
+    pub fn format_on_type(
+        &self,
+        source: &Source,
+        design_file: &DesignFile,
+        cursor: Position,
+        typed: char,
+    ) -> Vec<TextEdit> {
+        match typed {
+            ';' => self.format_range(source, design_file, Range::new(cursor, cursor)),
+            'd' if ends_with_end(source, cursor) => {
+                self.format_range(source, design_file, Range::new(cursor, cursor))
+            }
+            '\n' if cursor.line > 0 => {
+                let previous_line = Position::new(cursor.line - 1, 0);
+                self.format_range(
+                    source,
+                    design_file,
+                    Range::new(previous_line, previous_line),
+                )
+                .into_iter()
+                .filter(|edit| edit.pos.start().line != cursor.line)
+                .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn format_lines(&self, source: &Source, layout: &Layout) -> Vec<TextEdit> {
+        let contents = source.contents();
+        let original: Vec<&str> = (0..contents.num_lines())
+            .filter_map(|lineno| contents.get_line(lineno))
+            .map(|line| line.trim_end_matches(['\r', '\n']))
+            .collect();
+
+        let mut buffer = Buffer::new(&self.config);
+        // The blank lines in excess of the maximum number of consecutive blank lines
+        // and the lines that are joined with a line before them
+        let mut removed = vec![false; original.len()];
+        let mut blank_lines = 0;
+        let mut joined_until = None;
+        for (lineno, line) in original.iter().enumerate() {
+            blank_lines = if line.trim().is_empty() {
+                blank_lines + 1
+            } else {
+                0
+            };
+            let lineno = lineno as u32;
+            if joined_until.is_some_and(|last| lineno <= last) {
+                removed[lineno as usize] = true;
+                buffer.push_blank();
+            } else if layout.is_unformatted(lineno) {
+                buffer.push_verbatim(line);
+            } else if layout.is_verbatim(lineno) {
+                buffer.push_verbatim(line.trim_end());
+            } else if line.trim().is_empty() {
+                removed[lineno as usize] = self
+                    .config
+                    .max_blank_lines
+                    .is_some_and(|max_blank_lines| blank_lines > max_blank_lines);
+                buffer.push_blank();
+            } else if line.trim_start().starts_with("--") {
+                match layout.level(lineno) {
+                    Some(level) => buffer.push_comment(level, line.trim()),
+                    None if layout.is_after_last_token(lineno) => {
+                        buffer.push_comment(0, line.trim())
+                    }
+                    None => buffer.push_verbatim(line.trim_end()),
+                }
+            } else if let Some(level) = layout.level(lineno) {
+                if let Some((last, text)) = self.joined_list(layout, &original, &buffer, lineno) {
+                    joined_until = Some(last);
+                    buffer.push_indented(level, &text, &[], &[]);
+                    continue;
+                }
+                let indentation = line.chars().count() - line.trim_start().chars().count();
+                let text =
+                    self.with_keyword_case(line.trim(), &layout.keywords(lineno, indentation));
+                buffer.push_indented(
+                    level,
+                    &text,
+                    &layout.lists(lineno, indentation),
+                    &layout.breaks(lineno, indentation),
+                );
+            } else {
+                buffer.push_verbatim(line.trim_end());
+            }
+        }
+
+        // The adjacent lines of the same kind at the same level
+        let alignment = |lineno: usize| {
+            if removed[lineno] {
+                return None;
+            }
+            let lineno = lineno as u32;
+            let alignment = layout.alignment(lineno)?;
+            let is_enabled = match alignment {
+                Alignment::Declaration => self.config.align_declarations,
+                Alignment::Arrow => self.config.align_arrows,
+            };
+            is_enabled.then(|| (alignment, layout.level(lineno)))
+        };
+        let mut start = 0;
+        while start < original.len() {
+            let Some(key) = alignment(start) else {
+                start += 1;
+                continue;
+            };
+            let mut end = start + 1;
+            while end < original.len() && alignment(end) == Some(key) {
+                end += 1;
+            }
+            buffer.align(start..end, key.0);
+            start = end;
+        }
+
+        if self.config.align_trailing_comments {
+            // The number of characters of the comment at the end of the line
+            let comment_length = |lineno: usize| {
+                if removed[lineno] {
+                    return None;
+                }
+                let offset = layout.trailing_comment(lineno as u32)?;
+                Some(original[lineno].trim_end().chars().count() - offset)
+            };
+            let mut start = 0;
+            while start < original.len() {
+                let mut lengths = Vec::new();
+                while let Some(length) = comment_length(start + lengths.len()) {
+                    lengths.push(length);
+                    if start + lengths.len() == original.len() {
+                        break;
+                    }
+                }
+                if lengths.len() > 1 {
+                    buffer.align_comments(start..start + lengths.len(), &lengths);
+                }
+                start += lengths.len().max(1);
+            }
+        }
+
+        let mut lines = buffer.into_lines();
+        if self.config.sort_context_clauses {
+            for lineno in sort_clauses(&mut lines, &layout.context_clauses()) {
+                removed[lineno as usize] = true;
+            }
+        }
+        line_edits(source, contents.end(), &original, lines, &removed)
+    }
+
+    /// The edits that sort the library clauses, context references and use clauses of each
+    /// context clause and remove the duplicates, without formatting the code
+    pub fn sort_context_clauses(&self, source: &Source, design_file: &DesignFile) -> Vec<TextEdit> {
+        let contents = source.contents();
+        let original: Vec<&str> = (0..contents.num_lines())
+            .filter_map(|lineno| contents.get_line(lineno))
+            .map(|line| line.trim_end_matches(['\r', '\n']))
+            .collect();
+        let mut lines: Vec<String> = original.iter().map(|line| line.to_string()).collect();
+        let mut removed = vec![false; original.len()];
+        let layout = Layout::of_design_file(design_file);
+        for lineno in sort_clauses(&mut lines, &layout.context_clauses()) {
+            removed[lineno as usize] = true;
+        }
+        line_edits(source, contents.end(), &original, lines, &removed)
+    }
+
+    /// The last line of the outermost multi-line association list that opens on the `first`
+    /// line and fits on a single line, with the text of the lines joined into that line
+    fn joined_list(
+        &self,
+        layout: &Layout,
+        original: &[&str],
+        buffer: &Buffer,
+        first: u32,
+    ) -> Option<(u32, String)> {
+        if !self.config.compact_lists || self.config.max_line_length.is_none() {
+            return None;
+        }
+        let level = layout.level(first)?;
+        layout.multi_line_lists(first).into_iter().find_map(|last| {
+            let mut text = String::new();
+            for lineno in first..=last {
+                if layout.is_unformatted(lineno)
+                    || layout.is_verbatim(lineno)
+                    || layout.level(lineno).is_none()
+                {
+                    return None;
+                }
+                let line = original[lineno as usize];
+                let indentation = line.chars().count() - line.trim_start().chars().count();
+                let part =
+                    self.with_keyword_case(line.trim(), &layout.keywords(lineno, indentation));
+                if !(text.is_empty() || text.ends_with('(') || part.starts_with(')')) {
+                    text.push(' ');
+                }
+                text.push_str(&part);
+            }
+            buffer
+                .fits(&[(level, text.clone())])
+                .then_some((last, text))
+        })
+    }
+
+    fn with_keyword_case(&self, text: &str, keywords: &[std::ops::Range<usize>]) -> String {
+        let convert = match self.config.keyword_case {
+            KeywordCase::Preserve => return text.to_owned(),
+            KeywordCase::Lower => char::to_ascii_lowercase,
+            KeywordCase::Upper => char::to_ascii_uppercase,
+        };
+        let mut chars: Vec<char> = text.chars().collect();
+        for keyword in keywords.iter() {
+            for chr in chars[keyword.clone()].iter_mut() {
+                *chr = convert(chr);
+            }
+        }
+        chars.into_iter().collect()
+    }
+}
+
+/// The edits that replace the `original` lines that differ from the new `lines`
+/// and that remove the `removed` lines, where `end` is the end of the source
+fn line_edits(
+    source: &Source,
+    end: Position,
+    original: &[&str],
+    lines: Vec<String>,
+    removed: &[bool],
+) -> Vec<TextEdit> {
+    let mut edits = Vec::new();
+    for (lineno, (line, formatted)) in original.iter().zip(lines).enumerate() {
+        if removed[lineno] {
+            let lineno = lineno as u32;
+            let range = Range::new(
+                Position::new(lineno, 0),
+                Position::new(lineno + 1, 0).min(end),
+            );
+            edits.push(TextEdit::replace(SrcPos::new(source.clone(), range), ""));
+            continue;
+        }
+        if *line == formatted {
+            continue;
+        }
+        let lineno = lineno as u32;
+        let range = Range::new(
+            Position::new(lineno, 0),
+            Position::new(lineno, line.chars().count() as u32),
+        );
+        edits.push(TextEdit::replace(
+            SrcPos::new(source.clone(), range),
+            formatted,
+        ));
+    }
+    edits
+}
+
+/// Whether the `end` keyword is right before the cursor
+fn ends_with_end(source: &Source, cursor: Position) -> bool {
+    let contents = source.contents();
+    let Some(line) = contents.get_line(cursor.line as usize) else {
+        return false;
+    };
+    let before: Vec<char> = line.chars().take(cursor.character as usize).collect();
+    let Some(start) = before.len().checked_sub(3) else {
+        return false;
+    };
+    let word: String = before[start..].iter().collect();
+    word.eq_ignore_ascii_case("end")
+        && !before[..start]
+            .last()
+            .is_some_and(|chr| chr.is_ascii_alphanumeric() || *chr == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::refactor::apply_edits;
+    use crate::syntax::test::Code;
+
+    fn format_with(config: FormatterConfig, code: &str) -> String {
+        let code = Code::new(code);
+        let edits =
+            VHDLFormatter::new(config).format_design_file(code.source(), &code.design_file());
+        apply_edits(code.source(), &edits)
+    }
+
+    #[test]
+    fn indents_declarations_and_statements() {
+        let formatted = format_with(
+            FormatterConfig::default(),
+            "\
+entity ent is
+port (
+clk : in bit;
+q : out bit
+);
+end entity;
+
+architecture a of ent is
+  signal state : natural;
+begin
+main: process (clk)
+begin
+if clk = '1' then
+case state is
+when 0 =>
+state <= 1;
+when others =>
+state <= 0;
+end case;
+else
+null;
+end if;
+end process;
+
+inst: entity work.child
+port map (
+d => clk,
+q => q
+);
+end architecture;
+",
+        );
+        assert_eq!(
+            formatted,
+            "\
+entity ent is
+    port (
+        clk : in bit;
+        q : out bit
+    );
+end entity;
+
+architecture a of ent is
+    signal state : natural;
+begin
+    main: process (clk)
+    begin
+        if clk = '1' then
+            case state is
+                when 0 =>
+                    state <= 1;
+                when others =>
+                    state <= 0;
+            end case;
+        else
+            null;
+        end if;
+    end process;
+
+    inst: entity work.child
+        port map (
+            d => clk,
+            q => q
+        );
+end architecture;
+"
+        );
+    }
+
+    #[test]
+    fn indents_with_configured_width_or_tabs() {
+        let code = "\
+package body pkg is
+  function f(x : bit) return bit is
+    variable y : bit;
+  begin
+    -- Invert
+    y := not x;
+    return y;
+  end function;
+end package body;
+";
+        let two_spaces = FormatterConfig {
+            indent_width: 2,
+            ..Default::default()
+        };
+        assert_eq!(format_with(two_spaces, code), code);
+
+        let tabs = FormatterConfig {
+            use_tabs: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_with(tabs, code),
+            "\
+package body pkg is
+\tfunction f(x : bit) return bit is
+\t\tvariable y : bit;
+\tbegin
+\t\t-- Invert
+\t\ty := not x;
+\t\treturn y;
+\tend function;
+end package body;
+"
+        );
+    }
+
+    #[test]
+    fn indents_configurations() {
+        let formatted = format_with(
+            FormatterConfig::default(),
+            "\
+configuration cfg of ent is
+for a
+for inst : child
+use entity work.child(rtl);
+end for;
+end for;
+end configuration;
+",
+        );
+        assert_eq!(
+            formatted,
+            "\
+configuration cfg of ent is
+    for a
+        for inst : child
+            use entity work.child(rtl);
+        end for;
+    end for;
+end configuration;
+"
+        );
+    }
+
+    #[test]
+    fn wraps_lists_of_long_lines() {
+        let config = FormatterConfig {
+            max_line_length: Some(50),
+            ..Default::default()
+        };
+        let formatted = format_with(
+            config,
+            "\
+architecture a of ent is
+begin
+  inst: entity work.child port map (d => data(0), q => res); -- Child
+  res <= f(a, b);
+end architecture;
+",
+        );
+        assert_eq!(
+            formatted,
+            "\
+architecture a of ent is
+begin
+    inst: entity work.child port map (
+        d => data(0),
+        q => res
+    ); -- Child
+    res <= f(a, b);
+end architecture;
+"
+        );
+    }
+
+    #[test]
+    fn aligns_declarations() {
+        let config = FormatterConfig {
+            indent_width: 2,
+            align_declarations: true,
+            ..Default::default()
+        };
+        let formatted = format_with(
+            config,
+            "\
+entity ent is
+  generic (
+    width : natural := 8;
+    reset_value : bit := '0'
+  );
+  port (
+    clk : in bit;
+    data_out : out bit_vector(width - 1 downto 0)
+  );
+end entity;
+
+architecture a of ent is
+  signal counter : natural;
+  signal valid : bit := '0';
+
+  constant max : natural := 10;
+begin
+end architecture;
+
+package pkg is
+  constant a : natural := 1;
+  constant bb : natural := 22;
+  -- Unaligned
+  constant ccc : bit := '1';
+end package;
+",
+        );
+        assert_eq!(
+            formatted,
+            "\
+entity ent is
+  generic (
+    width       : natural := 8;
+    reset_value : bit     := '0'
+  );
+  port (
+    clk      : in bit;
+    data_out : out bit_vector(width - 1 downto 0)
+  );
+end entity;
+
+architecture a of ent is
+  signal counter : natural;
+  signal valid   : bit := '0';
+
+  constant max : natural := 10;
+begin
+end architecture;
+
+package pkg is
+  constant a  : natural := 1;
+  constant bb : natural := 22;
+  -- Unaligned
+  constant ccc : bit := '1';
+end package;
+"
+        );
+    }
+
+    #[test]
+    fn aligns_arrows() {
+        let config = FormatterConfig {
+            indent_width: 2,
+            align_arrows: true,
+            ..Default::default()
+        };
+        let formatted = format_with(
+            config,
+            "\
+architecture a of ent is
+begin
+  inst: entity work.child
+    generic map (width => 8)
+    port map (
+      clk => clk,
+      data_in => data(0),
+      q => open
+    );
+
+  process (state)
+  begin
+    case state is
+      when idle => next_state <= run;
+      when others => next_state <= idle;
+    end case;
+  end process;
+end architecture;
+",
+        );
+        assert_eq!(
+            formatted,
+            "\
+architecture a of ent is
+begin
+  inst: entity work.child
+    generic map (width => 8)
+    port map (
+      clk     => clk,
+      data_in => data(0),
+      q       => open
+    );
+
+  process (state)
+  begin
+    case state is
+      when idle   => next_state <= run;
+      when others => next_state <= idle;
+    end case;
+  end process;
+end architecture;
+"
+        );
+    }
+
+    #[test]
+    fn reindents_comments() {
+        let formatted = format_with(
+            FormatterConfig::default(),
+            "\
+-- Header
+library ieee;
+
+architecture a of ent is
+-- The state
+signal state : natural; -- Trailing
+begin
+process
+begin
+wait;
+-- After the last statement
+end process;
+end architecture;
+
+configuration cfg of ent is
+for a
+-- Default binding
+end for;
+end configuration;
+  -- Footer
+",
+        );
+        assert_eq!(
+            formatted,
+            "\
+-- Header
+library ieee;
+
+architecture a of ent is
+    -- The state
+    signal state : natural; -- Trailing
+begin
+    process
+    begin
+        wait;
+        -- After the last statement
+    end process;
+end architecture;
+
+configuration cfg of ent is
+    for a
+        -- Default binding
+    end for;
+end configuration;
+-- Footer
+"
+        );
+    }
+
+    #[test]
+    fn reflows_long_comments() {
+        let config = FormatterConfig {
+            max_line_length: Some(30),
+            reflow_comments: true,
+            ..Default::default()
+        };
+        let formatted = format_with(
+            config,
+            "\
+package pkg is
+  -- The width of the data bus in bits
+  constant width : natural := 8;
+  -------------------------------------
+end package;
+",
+        );
+        assert_eq!(
+            formatted,
+            "\
+package pkg is
+    -- The width of the data
+    -- bus in bits
+    constant width : natural := 8;
+    -------------------------------------
+end package;
+"
+        );
+    }
+
+    #[test]
+    fn keeps_regions_where_formatting_is_off() {
+        let code = "\
+package pkg is
+constant a : natural := 1;
+  -- vhdl_lang: fmt off
+  constant table : integer_vector := (
+      1,   2,   3,
+     10,  20,  30
+  );  
+  -- vhdl_lang: fmt on
+constant b : natural := 2;
+end package;
+";
+        assert_eq!(
+            format_with(FormatterConfig::default(), code),
+            "\
+package pkg is
+    constant a : natural := 1;
+  -- vhdl_lang: fmt off
+  constant table : integer_vector := (
+      1,   2,   3,
+     10,  20,  30
+  );  
+  -- vhdl_lang: fmt on
+    constant b : natural := 2;
+end package;
+"
+        );
+    }
+
+    #[test]
+    fn formats_enclosing_statement_of_range() {
+        let code = Code::new(
+            "\
+architecture a of ent is
+begin
+process
+begin
+wait;
+end process;
+process
+begin
+wait;
+end process;
+end architecture;
+",
+        );
+        // From the `begin` to the `wait` of the second process
+        let range = Range::new(code.s("begin", 3).start(), code.s("wait", 2).end());
+        let edits = VHDLFormatter::new(FormatterConfig::default()).format_range(
+            code.source(),
+            &code.design_file(),
+            range,
+        );
+        assert_eq!(
+            apply_edits(code.source(), &edits),
+            "\
+architecture a of ent is
+begin
+process
+begin
+wait;
+end process;
+    process
+    begin
+        wait;
+    end process;
+end architecture;
+"
+        );
+    }
+
+    #[test]
+    fn formats_line_ranges() {
+        let code = Code::new(
+            "\
+architecture a of ent is
+begin
+process
+begin
+wait;
+end process;
+x <= y;
+y <= z;
+process
+begin
+wait;
+end process;
+end architecture;
+",
+        );
+        // The `wait` of the first process and the second assignment, with one-based lines
+        let ranges = [
+            LineRange::try_from("5:5").unwrap(),
+            LineRange::try_from("8:8").unwrap(),
+        ];
+        let edits = VHDLFormatter::new(FormatterConfig::default()).format_ranges(
+            code.source(),
+            &code.design_file(),
+            &ranges,
+        );
+        assert_eq!(
+            apply_edits(code.source(), &edits),
+            "\
+architecture a of ent is
+begin
+process
+begin
+        wait;
+end process;
+x <= y;
+    y <= z;
+process
+begin
+wait;
+end process;
+end architecture;
+"
+        );
+    }
+
+    #[test]
+    fn parses_line_ranges() {
+        assert_eq!(
+            LineRange::try_from("3:10"),
+            Ok(LineRange { first: 2, last: 9 })
+        );
+        for value in ["0:1", "5:4", "5", "a:b"] {
+            assert_eq!(
+                LineRange::try_from(value),
+                Err(format!("'{value}' is not a valid line range"))
+            );
+        }
+    }
+
+    #[test]
+    fn formats_on_type() {
+        let text = "\
+architecture a of ent is
+begin
+process
+begin
+wait;
+end process;
+end architecture;
+";
+        let code = Code::new(text);
+        let formatter = VHDLFormatter::new(FormatterConfig::default());
+        let on_type = |cursor: Position, typed: char| {
+            let edits = formatter.format_on_type(code.source(), &code.design_file(), cursor, typed);
+            apply_edits(code.source(), &edits)
+        };
+
+        assert_eq!(
+            on_type(code.s1("wait;").end(), ';'),
+            text.replace("\nwait;", "\n        wait;")
+        );
+        assert_eq!(
+            on_type(code.s1("end process").s1("end").end(), 'd'),
+            "\
+architecture a of ent is
+begin
+    process
+    begin
+        wait;
+    end process;
+end architecture;
+"
+        );
+        assert_eq!(on_type(code.s1("wait").end(), 'd'), text);
+    }
+
+    #[test]
+    fn changes_case_of_keywords() {
+        let code = "\
+Entity Ent IS
+    Port (Clk : IN bit);
+eNd ENTITY Ent;
+
+architecture RTL of Ent is
+    signal Busy : Boolean := FALSE; -- Signal BUSY
+begin
+    Busy <= TRUE AND (Clk = '1') when \"Entity\" = \"Entity\" Else FALSE;
+END architecture;
+";
+        let with_case = |keyword_case| {
+            let config = FormatterConfig {
+                keyword_case,
+                ..Default::default()
+            };
+            format_with(config, code)
+        };
+
+        assert_eq!(with_case(KeywordCase::Preserve), code);
+        assert_eq!(
+            with_case(KeywordCase::Lower),
+            "\
+entity Ent is
+    port (Clk : in bit);
+end entity Ent;
+
+architecture RTL of Ent is
+    signal Busy : Boolean := FALSE; -- Signal BUSY
+begin
+    Busy <= TRUE and (Clk = '1') when \"Entity\" = \"Entity\" else FALSE;
+end architecture;
+"
+        );
+        assert_eq!(
+            with_case(KeywordCase::Upper),
+            "\
+ENTITY Ent IS
+    PORT (Clk : IN bit);
+END ENTITY Ent;
+
+ARCHITECTURE RTL OF Ent IS
+    SIGNAL Busy : Boolean := FALSE; -- Signal BUSY
+BEGIN
+    Busy <= TRUE AND (Clk = '1') WHEN \"Entity\" = \"Entity\" ELSE FALSE;
+END ARCHITECTURE;
+"
+        );
+    }
+
+    #[test]
+    fn limits_consecutive_blank_lines() {
+        let code = "\
+package pkg is
+    constant a : natural := 0;
+
+
+
+    constant b : natural := 1;
+
+    -- vhdl_lang: fmt off
+
+
+    -- vhdl_lang: fmt on
+end package;
+";
+        assert_eq!(format_with(FormatterConfig::default(), code), code);
+        let config = FormatterConfig {
+            max_blank_lines: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            format_with(config, code),
+            "\
+package pkg is
+    constant a : natural := 0;
+
+    constant b : natural := 1;
+
+    -- vhdl_lang: fmt off
+
+
+    -- vhdl_lang: fmt on
+end package;
+"
+        );
+        let config = FormatterConfig {
+            max_blank_lines: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(
+            format_with(config, code),
+            "\
+package pkg is
+    constant a : natural := 0;
+    constant b : natural := 1;
+    -- vhdl_lang: fmt off
+
+
+    -- vhdl_lang: fmt on
+end package;
+"
+        );
+    }
+
+    #[test]
+    fn joins_short_association_lists() {
+        let code = "\
+entity ent is
+    port (
+        clk : in bit
+    );
+end entity;
+
+architecture a of ent is
+begin
+    inst: entity work.child
+        port map (
+            clk => clk,
+            q => q
+        );
+    long_inst: entity work.child
+        port map (
+            clk => some_long_signal_name,
+            q => another_long_signal_name
+        );
+    x <= f(
+        a,
+        b);
+end architecture;
+";
+        let config = FormatterConfig {
+            max_line_length: Some(60),
+            ..Default::default()
+        };
+        assert_eq!(format_with(config.clone(), code), code);
+        let config = FormatterConfig {
+            compact_lists: true,
+            ..config
+        };
+        assert_eq!(
+            format_with(config, code),
+            "\
+entity ent is
+    port (
+        clk : in bit
+    );
+end entity;
+
+architecture a of ent is
+begin
+    inst: entity work.child
+        port map (clk => clk, q => q);
+    long_inst: entity work.child
+        port map (
+            clk => some_long_signal_name,
+            q => another_long_signal_name
+        );
+    x <= f(a, b);
+end architecture;
+"
+        );
+    }
+
+    #[test]
+    fn sorts_context_clauses() {
+        let code = "\
+-- Header
+
+use ieee.numeric_std.all;
+-- Logic types
+use ieee.std_logic_1164.all;
+library ieee;
+use IEEE.numeric_std.all;
+
+entity ent is
+end entity;
+
+context work.ctx;
+library ieee; use ieee.std_logic_1164.all;
+library work_lib;
+entity other is
+end entity;
+";
+        let sorted = "\
+-- Header
+
+library ieee;
+use ieee.numeric_std.all;
+-- Logic types
+use ieee.std_logic_1164.all;
+
+entity ent is
+end entity;
+
+context work.ctx;
+library ieee; use ieee.std_logic_1164.all;
+library work_lib;
+entity other is
+end entity;
+";
+        assert_eq!(format_with(FormatterConfig::default(), code), code);
+        let config = FormatterConfig {
+            sort_context_clauses: true,
+            ..Default::default()
+        };
+        assert_eq!(format_with(config.clone(), code), sorted);
+
+        let code = Code::new(code);
+        let edits =
+            VHDLFormatter::new(config).sort_context_clauses(code.source(), &code.design_file());
+        assert_eq!(apply_edits(code.source(), &edits), sorted);
+    }
+
+    #[test]
+    fn wraps_long_expressions_after_operators() {
+        let code = "\
+architecture a of ent is
+begin
+    ready <= valid_in and not busy and enable_signal and start_pulse;
+    y <= first_value when select_a = '1' else second_value when select_b = '1' else third;
+    z <= a and b;
+end architecture;
+";
+        let config = FormatterConfig {
+            max_line_length: Some(50),
+            ..Default::default()
+        };
+        assert_eq!(
+            format_with(config, code),
+            "\
+architecture a of ent is
+begin
+    ready <= valid_in and not busy and
+        enable_signal and start_pulse;
+    y <= first_value when select_a = '1' else
+        second_value when select_b = '1' else
+        third;
+    z <= a and b;
+end architecture;
+"
+        );
+    }
+
+    #[test]
+    fn aligns_trailing_comments() {
+        let code = "\
+architecture a of ent is
+    signal a : bit; -- first
+    signal long_name : bit_vector(0 to 7);   -- second
+
+    constant c : natural := 0;  -- alone
+begin
+end architecture;
+";
+        assert_eq!(format_with(FormatterConfig::default(), code), code);
+        let config = FormatterConfig {
+            align_trailing_comments: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_with(config, code),
+            "\
+architecture a of ent is
+    signal a : bit;                        -- first
+    signal long_name : bit_vector(0 to 7); -- second
+
+    constant c : natural := 0;  -- alone
+begin
+end architecture;
+"
+        );
+    }
+}
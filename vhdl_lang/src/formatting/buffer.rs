@@ -0,0 +1,240 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+use super::indentation::{Alignment, List};
+use super::FormatterConfig;
+use crate::refactor::{align_arrows, align_declarations};
+
+/// The formatted lines of a source file, one for each line of the original file.
+/// A line that is wrapped contains several lines.
+pub(crate) struct Buffer<'a> {
+    config: &'a FormatterConfig,
+    lines: Vec<String>,
+}
+
+impl<'a> Buffer<'a> {
+    pub fn new(config: &'a FormatterConfig) -> Buffer<'a> {
+        Buffer {
+            config,
+            lines: Vec::new(),
+        }
+    }
+
+    /// The leading whitespace of a line at the indentation `level`
+    pub fn indentation(&self, level: usize) -> String {
+        if self.config.use_tabs {
+            "\t".repeat(level)
+        } else {
+            " ".repeat(level * self.config.indent_width)
+        }
+    }
+
+    /// The column after the last character of a line, where a tab is as wide as a level
+    fn width(&self, level: usize, text: &str) -> usize {
+        level * self.config.indent_width + text.chars().count()
+    }
+
+    /// Whether the lines at their levels fit within the maximum line length
+    pub fn fits(&self, lines: &[(usize, String)]) -> bool {
+        let Some(max_line_length) = self.config.max_line_length else {
+            return true;
+        };
+        lines
+            .iter()
+            .all(|(level, text)| self.width(*level, text) <= max_line_length)
+    }
+
+    /// Push a line that is kept compact when it fits within the maximum line length.
+    /// Otherwise it is broken after the operators at the character offsets `breaks`
+    /// when that makes it fit, or else its lists are expanded to one element per line,
+    /// the longest list first, until it fits or there are no more lists to expand.
+    pub fn push_indented(&mut self, level: usize, text: &str, lists: &[List], breaks: &[usize]) {
+        let mut lines = vec![(level, text.to_owned())];
+        if !self.fits(&lines) && !breaks.is_empty() {
+            let broken = self.break_after_operators(level, text, breaks);
+            if self.fits(&broken) {
+                lines = broken;
+            }
+        }
+        let mut by_length: Vec<&List> = lists.iter().collect();
+        by_length.sort_by_key(|list| std::cmp::Reverse(list.close - list.open));
+        let mut expanded = Vec::new();
+        for list in by_length {
+            if self.fits(&lines) {
+                break;
+            }
+            expanded.push(list);
+            lines = expand(level, text, &expanded);
+        }
+
+        let lines: Vec<String> = lines
+            .into_iter()
+            .map(|(level, text)| self.indentation(level) + &text)
+            .collect();
+        self.lines.push(lines.join("\n"));
+    }
+
+    /// Break a line after the operators, with as many operands on each line as fit
+    /// and the continuation lines one level deeper than the line
+    fn break_after_operators(
+        &self,
+        level: usize,
+        text: &str,
+        breaks: &[usize],
+    ) -> Vec<(usize, String)> {
+        let Some(max_line_length) = self.config.max_line_length else {
+            return vec![(level, text.to_owned())];
+        };
+        let chars: Vec<char> = text.chars().collect();
+        let mut segments = Vec::new();
+        let mut start = 0;
+        for end in breaks.iter().copied().chain(std::iter::once(chars.len())) {
+            let segment: String = chars[start..end].iter().collect();
+            segments.push(segment.trim().to_owned());
+            start = end;
+        }
+
+        let mut lines: Vec<(usize, String)> = Vec::new();
+        for segment in segments.into_iter().filter(|segment| !segment.is_empty()) {
+            match lines.last_mut() {
+                Some((line_level, line))
+                    if self.width(*line_level, line) + 1 + segment.chars().count()
+                        <= max_line_length =>
+                {
+                    line.push(' ');
+                    line.push_str(&segment);
+                }
+                Some(_) => lines.push((level + 1, segment)),
+                None => lines.push((level, segment)),
+            }
+        }
+        lines
+    }
+
+    /// Push a comment on its own line. When comments are reflowed, a comment that does not fit
+    /// within the maximum line length is split between its words into several comments.
+    pub fn push_comment(&mut self, level: usize, text: &str) {
+        let max_line_length = match self.config.max_line_length {
+            Some(max_line_length) if self.config.reflow_comments => max_line_length,
+            _ => return self.push_indented(level, text, &[], &[]),
+        };
+        if self.width(level, text) <= max_line_length {
+            return self.push_indented(level, text, &[], &[]);
+        }
+
+        let words = text.trim_start_matches('-');
+        let dashes = &text[..text.len() - words.len()];
+        let mut lines = Vec::new();
+        let mut current = dashes.to_owned();
+        for word in words.split_whitespace() {
+            if current.len() > dashes.len()
+                && self.width(level, &current) + 1 + word.chars().count() > max_line_length
+            {
+                lines.push(std::mem::replace(&mut current, dashes.to_owned()));
+            }
+            current.push(' ');
+            current.push_str(word);
+        }
+        lines.push(current);
+
+        let lines: Vec<String> = lines
+            .into_iter()
+            .map(|line| self.indentation(level) + &line)
+            .collect();
+        self.lines.push(lines.join("\n"));
+    }
+
+    /// Align lines that are pushed, unless some of them are wrapped
+    pub fn align(&mut self, lines: std::ops::Range<usize>, alignment: Alignment) {
+        let lines = &mut self.lines[lines];
+        if lines.iter().any(|line| line.contains('\n')) {
+            return;
+        }
+        match alignment {
+            Alignment::Declaration => align_declarations(lines),
+            Alignment::Arrow => align_arrows(lines),
+        }
+    }
+
+    /// Align the comments at the end of lines that are pushed, where `lengths` are the number
+    /// of characters of the comments, unless some of the lines are wrapped
+    pub fn align_comments(&mut self, lines: std::ops::Range<usize>, lengths: &[usize]) {
+        let tab_width = self.config.indent_width;
+        let width = |code: &str| -> usize {
+            code.chars()
+                .map(|chr| if chr == '\t' { tab_width } else { 1 })
+                .sum()
+        };
+        let lines = &mut self.lines[lines];
+        if lines.iter().any(|line| line.contains('\n')) {
+            return;
+        }
+
+        let split: Vec<(String, String)> = lines
+            .iter()
+            .zip(lengths)
+            .map(|(line, length)| {
+                let chars: Vec<char> = line.chars().collect();
+                let at = chars.len().saturating_sub(*length);
+                let code: String = chars[..at].iter().collect();
+                (code.trim_end().to_owned(), chars[at..].iter().collect())
+            })
+            .collect();
+        let Some(column) = split.iter().map(|(code, _)| width(code) + 1).max() else {
+            return;
+        };
+        for (line, (code, comment)) in lines.iter_mut().zip(split) {
+            let padding = " ".repeat(column - width(&code));
+            *line = format!("{code}{padding}{comment}");
+        }
+    }
+
+    pub fn push_verbatim(&mut self, text: &str) {
+        self.lines.push(text.to_owned());
+    }
+
+    pub fn push_blank(&mut self) {
+        self.lines.push(String::new());
+    }
+
+    pub fn into_lines(self) -> Vec<String> {
+        self.lines
+    }
+}
+
+/// Split a line into the lines before, within and after the expanded lists,
+/// with the elements of the lists one level deeper than the line
+fn expand(level: usize, text: &str, lists: &[&List]) -> Vec<(usize, String)> {
+    let chars: Vec<char> = text.chars().collect();
+    let slice = |start: usize, end: usize| -> String {
+        chars[start..end]
+            .iter()
+            .collect::<String>()
+            .trim()
+            .to_owned()
+    };
+
+    let mut lists = lists.to_vec();
+    lists.sort_by_key(|list| list.open);
+
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for list in lists {
+        lines.push((level, slice(start, list.open + 1)));
+        let mut element_start = list.open + 1;
+        for separator in list.separators.iter() {
+            lines.push((level + 1, slice(element_start, separator + 1)));
+            element_start = separator + 1;
+        }
+        let last_element = slice(element_start, list.close);
+        if !last_element.is_empty() {
+            lines.push((level + 1, last_element));
+        }
+        start = list.close;
+    }
+    lines.push((level, slice(start, chars.len())));
+    lines
+}
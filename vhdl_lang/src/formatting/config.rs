@@ -0,0 +1,177 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+use super::{FormatterConfig, KeywordCase};
+use std::path::{Path, PathBuf};
+use toml::Table;
+
+/// The name of the file that configures the formatting of the files in its directory
+/// and the directories below it
+pub const FORMATTER_CONFIG_FILE_NAME: &str = ".vhdl_lang_fmt.toml";
+
+impl FormatterConfig {
+    /// Overwrite the settings that are given by the contents of a formatter configuration
+    /// file and keep the others, e.g. those of the editor
+    pub fn update_from_str(&mut self, string: &str) -> Result<(), String> {
+        let table = string.parse::<Table>().map_err(|err| err.to_string())?;
+
+        for (key, value) in table.iter() {
+            let as_bool = || {
+                value
+                    .as_bool()
+                    .ok_or_else(|| format!("{key} must be a boolean"))
+            };
+            let as_positive = || {
+                value
+                    .as_integer()
+                    .filter(|value| *value > 0)
+                    .map(|value| value as usize)
+                    .ok_or_else(|| format!("{key} must be a positive integer"))
+            };
+            match key.as_str() {
+                "indent_width" => self.indent_width = as_positive()?,
+                "use_tabs" => self.use_tabs = as_bool()?,
+                "max_line_length" => self.max_line_length = Some(as_positive()?),
+                "align_declarations" => self.align_declarations = as_bool()?,
+                "align_arrows" => self.align_arrows = as_bool()?,
+                "reflow_comments" => self.reflow_comments = as_bool()?,
+                "compact_lists" => self.compact_lists = as_bool()?,
+                "align_trailing_comments" => self.align_trailing_comments = as_bool()?,
+                "sort_context_clauses" => self.sort_context_clauses = as_bool()?,
+                "max_blank_lines" => {
+                    let value = value
+                        .as_integer()
+                        .filter(|value| *value >= 0)
+                        .ok_or_else(|| format!("{key} must be a non-negative integer"))?;
+                    self.max_blank_lines = Some(value as usize);
+                }
+                "keyword_case" => {
+                    let value = value
+                        .as_str()
+                        .ok_or_else(|| format!("{key} must be a string"))?;
+                    self.keyword_case = match value {
+                        "preserve" => KeywordCase::Preserve,
+                        "lower" => KeywordCase::Lower,
+                        "upper" => KeywordCase::Upper,
+                        _ => return Err(format!("'{value}' is not a valid value of {key}")),
+                    }
+                }
+                _ => {
+                    return Err(format!(
+                        "'{key}' is not a valid key of {FORMATTER_CONFIG_FILE_NAME}"
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Update the settings from the formatter configuration file that is nearest to
+    /// `file_name`, found in its directory or in one of the directories above it.
+    /// Returns the name of the configuration file that was read, if any.
+    pub fn update_from_nearest_file(
+        &mut self,
+        file_name: &Path,
+    ) -> Result<Option<PathBuf>, String> {
+        let Some(config_file) = find_config_file(file_name) else {
+            return Ok(None);
+        };
+        let contents = std::fs::read_to_string(&config_file)
+            .map_err(|err| format!("Failed to read {}: {err}", config_file.to_string_lossy()))?;
+        self.update_from_str(&contents)
+            .map_err(|err| format!("Error in {}: {err}", config_file.to_string_lossy()))?;
+        Ok(Some(config_file))
+    }
+}
+
+fn find_config_file(file_name: &Path) -> Option<PathBuf> {
+    file_name
+        .ancestors()
+        .skip(1)
+        .map(|directory| directory.join(FORMATTER_CONFIG_FILE_NAME))
+        .find(|config_file| config_file.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn updates_settings_of_config_file() {
+        let mut config = FormatterConfig {
+            indent_width: 2,
+            ..Default::default()
+        };
+        config
+            .update_from_str(
+                "
+use_tabs = true
+max_line_length = 100
+keyword_case = 'upper'
+max_blank_lines = 0
+",
+            )
+            .unwrap();
+        assert_eq!(
+            config,
+            FormatterConfig {
+                indent_width: 2,
+                use_tabs: true,
+                max_line_length: Some(100),
+                keyword_case: KeywordCase::Upper,
+                max_blank_lines: Some(0),
+                ..Default::default()
+            }
+        );
+
+        assert_eq!(
+            config.update_from_str("keyword_case = 'title'"),
+            Err("'title' is not a valid value of keyword_case".to_owned())
+        );
+        assert_eq!(
+            config.update_from_str("indent_width = 0"),
+            Err("indent_width must be a positive integer".to_owned())
+        );
+        assert_eq!(
+            config.update_from_str("indent = 2"),
+            Err("'indent' is not a valid key of .vhdl_lang_fmt.toml".to_owned())
+        );
+    }
+
+    #[test]
+    fn reads_nearest_config_file() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = tempdir.path();
+        let nested = root.join("rtl").join("core");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(root.join(FORMATTER_CONFIG_FILE_NAME), "indent_width = 2").unwrap();
+        std::fs::write(
+            root.join("rtl").join(FORMATTER_CONFIG_FILE_NAME),
+            "align_arrows = true",
+        )
+        .unwrap();
+
+        let mut config = FormatterConfig::default();
+        assert_eq!(
+            config.update_from_nearest_file(&nested.join("core.vhd")),
+            Ok(Some(root.join("rtl").join(FORMATTER_CONFIG_FILE_NAME)))
+        );
+        assert_eq!(
+            config,
+            FormatterConfig {
+                align_arrows: true,
+                ..Default::default()
+            }
+        );
+
+        let mut config = FormatterConfig::default();
+        assert_eq!(
+            config.update_from_nearest_file(&root.join("top.vhd")),
+            Ok(Some(root.join(FORMATTER_CONFIG_FILE_NAME)))
+        );
+        assert_eq!(config.indent_width, 2);
+    }
+}
@@ -0,0 +1,108 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Sort the library clauses, context references and use clauses of context clauses
+
+use crate::ast::{ContextClause, ContextItem};
+use crate::syntax::{Token, TokenAccess};
+use crate::HasTokenSpan;
+use fnv::FnvHashMap;
+
+/// A context item on a line of its own, together with the comment lines directly before it
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct ClauseLines {
+    /// The first comment line, or the line of the item when it has no comments
+    first: u32,
+    line: u32,
+    /// Library clauses are sorted before context references, that are sorted before use clauses,
+    /// since a context reference or use clause may refer to a library of a library clause
+    rank: usize,
+}
+
+impl ClauseLines {
+    pub fn lines(&self) -> std::ops::RangeInclusive<u32> {
+        self.first..=self.line
+    }
+}
+
+/// The context items of a context clause that are on lines of their own
+pub(crate) fn clause_lines(tokens: &[Token], items: &ContextClause) -> Vec<ClauseLines> {
+    let mut tokens_per_line: FnvHashMap<u32, usize> = FnvHashMap::default();
+    for token in tokens.iter() {
+        *tokens_per_line.entry(token.pos.start().line).or_default() += 1;
+    }
+
+    let mut clauses = Vec::new();
+    for item in items.iter() {
+        let span = item.span();
+        let line = tokens.get_pos(span.start_token).start().line;
+        let item_tokens = tokens.get_token_slice(span.start_token, span.end_token);
+        if tokens.get_pos(span.end_token).end().line != line
+            || tokens_per_line.get(&line) != Some(&item_tokens.len())
+        {
+            continue;
+        }
+
+        let mut first = line;
+        if let Some(ref comments) = tokens.get_token(span.start_token).comments {
+            for comment in comments.leading.iter().rev() {
+                if comment.range.end.line + 1 != first {
+                    break;
+                }
+                first = comment.range.start.line;
+            }
+        }
+        let rank = match item {
+            ContextItem::Library(_) => 0,
+            ContextItem::Context(_) => 1,
+            ContextItem::Use(_) => 2,
+        };
+        clauses.push(ClauseLines { first, line, rank });
+    }
+    clauses
+}
+
+/// Sort the clauses of each run of clauses on consecutive lines by their kind and text
+/// and remove the clauses that are duplicates of another clause, including their comments.
+/// Returns the lines that are left empty at the end of the runs, that are to be removed.
+pub(crate) fn sort_clauses(lines: &mut [String], clauses: &[ClauseLines]) -> Vec<u32> {
+    let mut removed = Vec::new();
+    let mut start = 0;
+    while start < clauses.len() {
+        let mut end = start + 1;
+        while end < clauses.len() && clauses[end].first == clauses[end - 1].line + 1 {
+            end += 1;
+        }
+        let run = &clauses[start..end];
+        start = end;
+
+        let text = |clause: &ClauseLines| -> Vec<String> {
+            (clause.first..=clause.line)
+                .map(|lineno| lines[lineno as usize].clone())
+                .collect()
+        };
+        let normalized = |text: &[String]| -> Vec<String> {
+            text.iter().map(|line| line.trim().to_lowercase()).collect()
+        };
+        let mut sorted: Vec<(usize, Vec<String>)> = run
+            .iter()
+            .map(|clause| (clause.rank, text(clause)))
+            .collect();
+        sorted
+            .sort_by_cached_key(|(rank, text)| (*rank, text.last().unwrap().trim().to_lowercase()));
+        sorted.dedup_by(|(_, text), (_, previous)| normalized(text) == normalized(previous));
+
+        let first_line = run[0].first as usize;
+        let last_line = run[run.len() - 1].line as usize;
+        let mut lineno = first_line;
+        for line in sorted.into_iter().flat_map(|(_, text)| text) {
+            lines[lineno] = line;
+            lineno += 1;
+        }
+        removed.extend(lineno as u32..=last_line as u32);
+    }
+    removed
+}
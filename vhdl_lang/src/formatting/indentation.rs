@@ -0,0 +1,716 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! The indentation level of each line of a design file, from the nesting of
+//! its design units, declarations and statements
+
+use super::context_clauses::{clause_lines, ClauseLines};
+use super::{FMT_OFF_COMMENT, FMT_ON_COMMENT};
+use crate::ast::token_range::WithTokenSpan;
+use crate::ast::{
+    AnyDesignUnit, AnyPrimaryUnit, AnySecondaryUnit, Choice, ConcurrentStatement, ContextClause,
+    Declaration, DesignFile, GenerateBody, InterfaceDeclaration, LabeledConcurrentStatement,
+    LabeledSequentialStatement, MapAspect, SequentialStatement, TypeDeclaration, TypeDefinition,
+};
+use crate::data::Position;
+use crate::syntax::{kind_str, Kind, Token, TokenAccess, Value};
+use crate::{HasTokenSpan, TokenId, TokenSpan};
+use fnv::{FnvHashMap, FnvHashSet};
+
+/// Reserved words, including the word operators such as `and`, are tokens without a value
+fn is_keyword(token: &Token) -> bool {
+    matches!(token.value, Value::None)
+        && kind_str(token.kind).starts_with(|chr: char| chr.is_ascii_alphabetic())
+}
+
+/// How a line is aligned with the adjacent lines
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Alignment {
+    /// The `:` and `:=` of an object or interface declaration
+    Declaration,
+    /// The `=>` of an association or a case alternative
+    Arrow,
+}
+
+/// A design unit, declaration, statement or case alternative
+struct Item {
+    start: Position,
+    end: Position,
+    depth: usize,
+    /// Whether the item has a body that is closed by `end`.
+    /// The lines of such an item that start with `begin`, `else`, `elsif` or `end`
+    /// are indented as the item rather than as a continuation line.
+    block: bool,
+}
+
+struct Items<'a> {
+    ctx: &'a dyn TokenAccess,
+    items: Vec<Item>,
+    aligned_lines: FnvHashMap<u32, Alignment>,
+}
+
+impl<'a> Items<'a> {
+    fn push(&mut self, start: TokenId, end: TokenId, depth: usize, block: bool) {
+        self.items.push(Item {
+            start: self.ctx.get_pos(start).start(),
+            end: self.ctx.get_pos(end).end(),
+            depth,
+            block,
+        });
+    }
+
+    /// A construct that fits on a single line is aligned with the adjacent lines
+    fn push_aligned_line(&mut self, start: Position, end: Position, alignment: Alignment) {
+        if start.line == end.line {
+            self.aligned_lines.insert(start.line, alignment);
+        }
+    }
+
+    fn push_aligned_span(&mut self, span: TokenSpan, alignment: Alignment) {
+        let start = self.ctx.get_pos(span.start_token).start();
+        let end = self.ctx.get_pos(span.end_token).end();
+        self.push_aligned_line(start, end, alignment);
+    }
+
+    fn map_aspect(&mut self, map: &MapAspect) {
+        for element in map.list.items.iter() {
+            let start = element
+                .formal
+                .as_ref()
+                .map(|formal| formal.span.start_token)
+                .unwrap_or(element.actual.span.start_token);
+            let span = TokenSpan::new(start, element.actual.span.end_token);
+            self.push_aligned_span(span, Alignment::Arrow);
+        }
+    }
+
+    fn interface_list(&mut self, list: &[InterfaceDeclaration]) {
+        for decl in list.iter() {
+            if matches!(decl, InterfaceDeclaration::Object(_)) {
+                self.push_aligned_span(decl.span(), Alignment::Declaration);
+            }
+        }
+    }
+
+    fn design_unit(&mut self, unit: &AnyDesignUnit) {
+        let span = unit.span();
+        self.push(span.start_token, span.end_token, 0, true);
+
+        if let AnyDesignUnit::Primary(AnyPrimaryUnit::Context(context)) = unit {
+            self.context_clause(&context.items, 1);
+            return;
+        }
+        self.context_clause(unit.context_clause(), 0);
+
+        match unit {
+            AnyDesignUnit::Primary(AnyPrimaryUnit::Entity(entity)) => {
+                for list in [&entity.generic_clause, &entity.port_clause]
+                    .into_iter()
+                    .flatten()
+                {
+                    self.interface_list(list);
+                }
+                self.declarations(&entity.decl, 1);
+                self.concurrent_statements(&entity.statements, 1);
+            }
+            AnyDesignUnit::Primary(AnyPrimaryUnit::Package(package)) => {
+                self.declarations(&package.decl, 1);
+            }
+            AnyDesignUnit::Primary(AnyPrimaryUnit::Configuration(_)) => {
+                self.configuration(span);
+            }
+            AnyDesignUnit::Primary(AnyPrimaryUnit::PackageInstance(_))
+            | AnyDesignUnit::Primary(AnyPrimaryUnit::Context(_)) => {}
+            AnyDesignUnit::Secondary(AnySecondaryUnit::Architecture(architecture)) => {
+                self.declarations(&architecture.decl, 1);
+                self.concurrent_statements(&architecture.statements, 1);
+            }
+            AnyDesignUnit::Secondary(AnySecondaryUnit::PackageBody(body)) => {
+                self.declarations(&body.decl, 1);
+            }
+        }
+    }
+
+    fn context_clause(&mut self, items: &ContextClause, depth: usize) {
+        for item in items.iter() {
+            let span = item.span();
+            self.push(span.start_token, span.end_token, depth, false);
+        }
+    }
+
+    fn declarations(&mut self, declarations: &[WithTokenSpan<Declaration>], depth: usize) {
+        for decl in declarations.iter() {
+            let span = decl.span;
+            match decl.item {
+                Declaration::SubprogramBody(ref body) => {
+                    self.push(span.start_token, span.end_token, depth, true);
+                    self.declarations(&body.declarations, depth + 1);
+                    self.sequential_statements(&body.statements, depth + 1);
+                }
+                Declaration::Type(TypeDeclaration {
+                    def: TypeDefinition::ProtectedBody(ref body),
+                    ..
+                }) => {
+                    self.push(span.start_token, span.end_token, depth, true);
+                    self.declarations(&body.decl, depth + 1);
+                }
+                Declaration::Component(ref component) => {
+                    self.push(span.start_token, span.end_token, depth, true);
+                    self.interface_list(&component.generic_list);
+                    self.interface_list(&component.port_list);
+                }
+                Declaration::Type(_) | Declaration::View(_) => {
+                    self.push(span.start_token, span.end_token, depth, true);
+                }
+                Declaration::Object(_) => {
+                    self.push(span.start_token, span.end_token, depth, false);
+                    self.push_aligned_span(span, Alignment::Declaration);
+                }
+                Declaration::Package(ref instance) => {
+                    self.push(span.start_token, span.end_token, depth, false);
+                    if let Some(ref map) = instance.generic_map {
+                        self.map_aspect(map);
+                    }
+                }
+                _ => self.push(span.start_token, span.end_token, depth, false),
+            }
+        }
+    }
+
+    fn concurrent_statements(&mut self, statements: &[LabeledConcurrentStatement], depth: usize) {
+        for stmt in statements.iter() {
+            let span = stmt.statement.span;
+            let start = stmt
+                .label
+                .tree
+                .as_ref()
+                .map(|label| label.token)
+                .unwrap_or(span.start_token);
+            match stmt.statement.item {
+                ConcurrentStatement::Block(ref block) => {
+                    self.push(start, span.end_token, depth, true);
+                    for map in [&block.header.generic_map, &block.header.port_map]
+                        .into_iter()
+                        .flatten()
+                    {
+                        self.map_aspect(map);
+                    }
+                    self.declarations(&block.decl, depth + 1);
+                    self.concurrent_statements(&block.statements, depth + 1);
+                }
+                ConcurrentStatement::Process(ref process) => {
+                    self.push(start, span.end_token, depth, true);
+                    self.declarations(&process.decl, depth + 1);
+                    self.sequential_statements(&process.statements, depth + 1);
+                }
+                ConcurrentStatement::ForGenerate(ref generate) => {
+                    self.push(start, span.end_token, depth, true);
+                    self.generate_body(&generate.body, depth + 1);
+                }
+                ConcurrentStatement::IfGenerate(ref generate) => {
+                    self.push(start, span.end_token, depth, true);
+                    for conditional in generate.conds.conditionals.iter() {
+                        self.generate_body(&conditional.item, depth + 1);
+                    }
+                    if let Some(ref body) = generate.conds.else_item {
+                        self.generate_body(body, depth + 1);
+                    }
+                }
+                ConcurrentStatement::CaseGenerate(ref generate) => {
+                    self.push(start, span.end_token, depth, true);
+                    for alternative in generate.sels.alternatives.iter() {
+                        let last = alternative
+                            .item
+                            .statements
+                            .last()
+                            .map(|stmt| stmt.statement.span.end_token);
+                        self.alternative(span, &alternative.choices, last, depth + 1);
+                        self.generate_body(&alternative.item, depth + 2);
+                    }
+                }
+                ConcurrentStatement::Instance(ref instance) => {
+                    self.push(start, span.end_token, depth, false);
+                    for map in [&instance.generic_map, &instance.port_map]
+                        .into_iter()
+                        .flatten()
+                    {
+                        self.map_aspect(map);
+                    }
+                }
+                _ => self.push(start, span.end_token, depth, false),
+            }
+        }
+    }
+
+    fn generate_body(&mut self, body: &GenerateBody, depth: usize) {
+        if let Some(ref decl) = body.decl {
+            self.declarations(decl, depth);
+        }
+        self.concurrent_statements(&body.statements, depth);
+    }
+
+    fn sequential_statements(&mut self, statements: &[LabeledSequentialStatement], depth: usize) {
+        for stmt in statements.iter() {
+            let span = stmt.statement.span;
+            let start = stmt
+                .label
+                .tree
+                .as_ref()
+                .map(|label| label.token)
+                .unwrap_or(span.start_token);
+            match stmt.statement.item {
+                SequentialStatement::If(ref ifstmt) => {
+                    self.push(start, span.end_token, depth, true);
+                    for conditional in ifstmt.conds.conditionals.iter() {
+                        self.sequential_statements(&conditional.item, depth + 1);
+                    }
+                    if let Some(ref statements) = ifstmt.conds.else_item {
+                        self.sequential_statements(statements, depth + 1);
+                    }
+                }
+                SequentialStatement::Case(ref case) => {
+                    self.push(start, span.end_token, depth, true);
+                    for alternative in case.alternatives.iter() {
+                        let last = alternative
+                            .item
+                            .last()
+                            .map(|stmt| stmt.statement.span.end_token);
+                        self.alternative(span, &alternative.choices, last, depth + 1);
+                        self.sequential_statements(&alternative.item, depth + 2);
+                    }
+                }
+                SequentialStatement::Loop(ref loopstmt) => {
+                    self.push(start, span.end_token, depth, true);
+                    self.sequential_statements(&loopstmt.statements, depth + 1);
+                }
+                _ => self.push(start, span.end_token, depth, false),
+            }
+        }
+    }
+
+    /// A case alternative from its `when` to its last statement
+    fn alternative(
+        &mut self,
+        case_span: TokenSpan,
+        choices: &[WithTokenSpan<Choice>],
+        last: Option<TokenId>,
+        depth: usize,
+    ) {
+        let (Some(first), Some(last_choice)) = (choices.first(), choices.last()) else {
+            return;
+        };
+        let start = self
+            .ctx
+            .get_token_slice(case_span.start_token, first.span.start_token)
+            .iter()
+            .rev()
+            .find(|token| token.kind == Kind::When)
+            .map(|token| token.pos.start())
+            .unwrap_or_else(|| self.ctx.get_pos(first.span.start_token).start());
+        let choices_end = self.ctx.get_pos(last_choice.span.end_token).end();
+        self.push_aligned_line(start, choices_end, Alignment::Arrow);
+        let end = self
+            .ctx
+            .get_pos(last.unwrap_or(last_choice.span.end_token))
+            .end();
+        self.items.push(Item {
+            start,
+            end,
+            depth,
+            block: false,
+        });
+    }
+
+    /// The block and component configurations from `for` to `end for;`
+    fn configuration(&mut self, span: TokenSpan) {
+        let tokens = self.ctx.get_token_slice(span.start_token, span.end_token);
+        let mut open = Vec::new();
+        for (idx, token) in tokens.iter().enumerate() {
+            if token.kind != Kind::For {
+                continue;
+            }
+            if idx == 0 || tokens[idx - 1].kind != Kind::End {
+                open.push(token.pos.start());
+            } else if let Some(start) = open.pop() {
+                let end = tokens
+                    .get(idx + 1)
+                    .filter(|token| token.kind == Kind::SemiColon)
+                    .unwrap_or(token);
+                self.items.push(Item {
+                    start,
+                    end: end.pos.end(),
+                    depth: open.len() + 1,
+                    block: true,
+                });
+            }
+        }
+    }
+
+    /// The level of a line that starts with `token` and is not within parentheses
+    fn level_of(&self, token: &Token) -> usize {
+        let start = token.pos.start();
+        // The innermost item, which is the last one of the deepest items
+        let Some(item) = self
+            .items
+            .iter()
+            .filter(|item| item.start <= start && start < item.end)
+            .max_by_key(|item| item.depth)
+        else {
+            return 0;
+        };
+        let closes_body = item.block
+            && matches!(
+                token.kind,
+                Kind::Begin | Kind::Else | Kind::Elsif | Kind::End
+            );
+        if item.start == start || closes_body {
+            item.depth
+        } else {
+            item.depth + 1
+        }
+    }
+}
+
+/// The indentation of the lines of a design file
+pub(crate) struct Layout {
+    /// The indentation level of the lines that start with a token or a comment
+    levels: FnvHashMap<u32, usize>,
+    /// The continuation lines of multi-line comments, that are kept as they are
+    verbatim: FnvHashSet<u32>,
+    /// The outermost lists of each line that open and close on the line
+    lists: FnvHashMap<u32, Vec<List>>,
+    /// The lines that are aligned with the adjacent lines of the same kind and level
+    aligned_lines: FnvHashMap<u32, Alignment>,
+    /// The line of the last token of the design file
+    last_line: Option<u32>,
+    /// The first and last lines of the regions between the comments that turn formatting
+    /// off and on again, without a last line when formatting is not turned on again
+    unformatted: Vec<(u32, Option<u32>)>,
+    /// The start and end of the design units, declarations, statements and case alternatives
+    spans: Vec<(Position, Position)>,
+    /// The character offsets of the keywords of each line
+    keywords: FnvHashMap<u32, Vec<std::ops::Range<usize>>>,
+    /// The first and last lines of the association lists of maps and calls that span several
+    /// lines and contain no comments, i.e. the lists that can be joined into a single line
+    multi_line_lists: Vec<(u32, u32)>,
+    /// The library clauses, context references and use clauses on lines of their own
+    context_clauses: Vec<ClauseLines>,
+    /// The character offsets after the logical operators and the `else` of conditional
+    /// expressions of each line that are not within a list that opens on the line
+    breaks: FnvHashMap<u32, Vec<usize>>,
+    /// The character offset of the comment at the end of each line after code
+    trailing_comments: FnvHashMap<u32, usize>,
+}
+
+/// A parenthesized list, by the character offsets of its parentheses
+/// and of the commas and semicolons between its elements
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct List {
+    pub open: usize,
+    pub close: usize,
+    pub separators: Vec<usize>,
+}
+
+impl List {
+    fn shifted_left(&self, offset: usize) -> List {
+        List {
+            open: self.open - offset,
+            close: self.close - offset,
+            separators: self.separators.iter().map(|sep| sep - offset).collect(),
+        }
+    }
+}
+
+/// An open parenthesis
+struct Paren {
+    /// The level of the line of the parenthesis
+    level: usize,
+    start: Position,
+    separators: Vec<usize>,
+    /// Whether the parenthesis follows `map` or a name, as the list of a map or a call
+    is_association_list: bool,
+    /// Whether the list contains a comment or a declaration with a `:`
+    is_joinable: bool,
+}
+
+impl Layout {
+    pub fn of_design_file(design_file: &DesignFile) -> Layout {
+        let mut layout = Layout {
+            levels: FnvHashMap::default(),
+            verbatim: FnvHashSet::default(),
+            lists: FnvHashMap::default(),
+            aligned_lines: FnvHashMap::default(),
+            last_line: None,
+            unformatted: Vec::new(),
+            spans: Vec::new(),
+            keywords: FnvHashMap::default(),
+            multi_line_lists: Vec::new(),
+            context_clauses: Vec::new(),
+            breaks: FnvHashMap::default(),
+            trailing_comments: FnvHashMap::default(),
+        };
+
+        let mut previous_line = None;
+        let mut formatting_off = None;
+        for (tokens, unit) in design_file.design_units.iter() {
+            let mut items = Items {
+                ctx: tokens,
+                items: Vec::new(),
+                aligned_lines: FnvHashMap::default(),
+            };
+            items.design_unit(unit);
+            let context_clause = match unit {
+                AnyDesignUnit::Primary(AnyPrimaryUnit::Context(context)) => &context.items,
+                _ => unit.context_clause(),
+            };
+            layout
+                .context_clauses
+                .extend(clause_lines(tokens, context_clause));
+            layout.aligned_lines.extend(items.aligned_lines.drain());
+
+            let mut parens: Vec<Paren> = Vec::new();
+            let mut level = 0;
+            let mut previous_kind = None;
+            // The number of open parentheses at the start of the line
+            let mut line_depth = 0;
+            for token in tokens.iter() {
+                let line = token.pos.start().line;
+                if previous_line != Some(line) {
+                    line_depth = parens.len();
+                    level = match parens.last() {
+                        Some(outer) if token.kind == Kind::RightPar => outer.level,
+                        Some(outer) => outer.level + 1,
+                        None => items.level_of(token),
+                    };
+                    layout.levels.insert(line, level);
+                }
+
+                if let Some(ref comments) = token.comments {
+                    // Comments on their own lines are indented as the token that follows them,
+                    // except that the comments before an `end` belong to the body it closes
+                    let comment_level = if token.kind == Kind::End {
+                        level + 1
+                    } else {
+                        level
+                    };
+                    for comment in comments.leading.iter() {
+                        let comment_line = comment.range.start.line;
+                        if comment_line != line
+                            && previous_line.is_none_or(|previous| comment_line > previous)
+                        {
+                            layout.levels.insert(comment_line, comment_level);
+                        }
+                    }
+                    if let Some(ref comment) = comments.trailing {
+                        if comment.range.start.line == comment.range.end.line {
+                            layout.trailing_comments.insert(
+                                comment.range.start.line,
+                                comment.range.start.character as usize,
+                            );
+                        }
+                    }
+                    for comment in comments.leading.iter().chain(comments.trailing.iter()) {
+                        layout
+                            .verbatim
+                            .extend(comment.range.start.line + 1..=comment.range.end.line);
+
+                        let directive = comment
+                            .value
+                            .replace(char::is_whitespace, "")
+                            .to_lowercase();
+                        if directive.contains(FMT_OFF_COMMENT) {
+                            formatting_off.get_or_insert(comment.range.start.line);
+                        } else if directive.contains(FMT_ON_COMMENT) {
+                            if let Some(start) = formatting_off.take() {
+                                layout
+                                    .unformatted
+                                    .push((start, Some(comment.range.end.line)));
+                            }
+                        }
+                    }
+                }
+
+                if token.comments.is_some() || token.kind == Kind::Colon {
+                    for paren in parens.iter_mut() {
+                        paren.is_joinable = false;
+                    }
+                }
+
+                if is_keyword(token) {
+                    let start = token.pos.start().character as usize;
+                    let end = token.pos.end().character as usize;
+                    layout.keywords.entry(line).or_default().push(start..end);
+                }
+
+                if parens.len() == line_depth
+                    && matches!(
+                        token.kind,
+                        Kind::And
+                            | Kind::Or
+                            | Kind::Nand
+                            | Kind::Nor
+                            | Kind::Xor
+                            | Kind::Xnor
+                            | Kind::Else
+                    )
+                {
+                    let end = token.pos.end().character as usize;
+                    layout.breaks.entry(line).or_default().push(end);
+                }
+
+                match token.kind {
+                    Kind::LeftPar => parens.push(Paren {
+                        level,
+                        start: token.pos.start(),
+                        separators: Vec::new(),
+                        is_association_list: matches!(
+                            previous_kind,
+                            Some(Kind::Map | Kind::Identifier)
+                        ),
+                        is_joinable: token.comments.is_none(),
+                    }),
+                    Kind::RightPar => {
+                        if let Some(paren) = parens.pop() {
+                            let is_outermost =
+                                parens.last().is_none_or(|outer| outer.start.line != line);
+                            if paren.start.line == line && is_outermost {
+                                layout.lists.entry(line).or_default().push(List {
+                                    open: paren.start.character as usize,
+                                    close: token.pos.start().character as usize,
+                                    separators: paren.separators,
+                                });
+                            } else if paren.start.line < line
+                                && paren.is_association_list
+                                && paren.is_joinable
+                            {
+                                layout.multi_line_lists.push((paren.start.line, line));
+                            }
+                        }
+                    }
+                    Kind::Comma | Kind::SemiColon => {
+                        if let Some(paren) = parens.last_mut() {
+                            paren.separators.push(token.pos.start().character as usize);
+                        }
+                    }
+                    _ => {}
+                }
+                previous_line = Some(line);
+                previous_kind = Some(token.kind);
+            }
+            layout
+                .spans
+                .extend(items.items.iter().map(|item| (item.start, item.end)));
+        }
+        if let Some(start) = formatting_off {
+            layout.unformatted.push((start, None));
+        }
+        layout.last_line = previous_line;
+        layout
+    }
+
+    pub fn level(&self, line: u32) -> Option<usize> {
+        self.levels.get(&line).copied()
+    }
+
+    pub fn is_verbatim(&self, line: u32) -> bool {
+        self.verbatim.contains(&line)
+    }
+
+    /// Whether the line is after the last design unit, where comments are not part of any unit
+    pub fn is_after_last_token(&self, line: u32) -> bool {
+        self.last_line.is_none_or(|last_line| line > last_line)
+    }
+
+    pub fn alignment(&self, line: u32) -> Option<Alignment> {
+        if self.is_unformatted(line) {
+            return None;
+        }
+        self.aligned_lines.get(&line).copied()
+    }
+
+    /// The first and last line of the smallest design unit, declaration, statement or
+    /// case alternative that encloses the lines, or the lines themselves when none does
+    pub fn enclosing_lines(&self, first: u32, last: u32) -> (u32, u32) {
+        self.spans
+            .iter()
+            .filter(|(start, end)| start.line <= first && last <= end.line)
+            .min_by_key(|(start, end)| end.line - start.line)
+            .map(|(start, end)| (start.line, end.line))
+            .unwrap_or((first, last))
+    }
+
+    /// Whether the line is within a region where formatting is turned off
+    pub fn is_unformatted(&self, line: u32) -> bool {
+        self.unformatted
+            .iter()
+            .any(|(start, end)| *start <= line && end.is_none_or(|end| line <= end))
+    }
+
+    /// The context items on lines of their own, without those where formatting is turned off
+    pub fn context_clauses(&self) -> Vec<ClauseLines> {
+        self.context_clauses
+            .iter()
+            .filter(|clause| !clause.lines().any(|line| self.is_unformatted(line)))
+            .copied()
+            .collect()
+    }
+
+    /// The last lines of the multi-line association lists that open on the line,
+    /// the outermost list first
+    pub fn multi_line_lists(&self, line: u32) -> Vec<u32> {
+        let mut last_lines: Vec<u32> = self
+            .multi_line_lists
+            .iter()
+            .filter(|(first, _)| *first == line)
+            .map(|(_, last)| *last)
+            .collect();
+        last_lines.sort_by_key(|last| std::cmp::Reverse(*last));
+        last_lines
+    }
+
+    /// The character offset of the comment at the end of a line after code,
+    /// unless formatting is turned off for the line
+    pub fn trailing_comment(&self, line: u32) -> Option<usize> {
+        if self.is_unformatted(line) {
+            return None;
+        }
+        self.trailing_comments.get(&line).copied()
+    }
+
+    /// The operator breaks of a line relative to the line without its `indentation` characters
+    pub fn breaks(&self, line: u32, indentation: usize) -> Vec<usize> {
+        self.breaks
+            .get(&line)
+            .map(|breaks| breaks.iter().map(|end| end - indentation).collect())
+            .unwrap_or_default()
+    }
+
+    /// The keywords of a line relative to the line without its `indentation` characters
+    pub fn keywords(&self, line: u32, indentation: usize) -> Vec<std::ops::Range<usize>> {
+        self.keywords
+            .get(&line)
+            .map(|keywords| {
+                keywords
+                    .iter()
+                    .map(|keyword| keyword.start - indentation..keyword.end - indentation)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The lists of a line relative to the line without its `indentation` characters
+    pub fn lists(&self, line: u32, indentation: usize) -> Vec<List> {
+        self.lists
+            .get(&line)
+            .map(|lists| {
+                lists
+                    .iter()
+                    .map(|list| list.shifted_left(indentation))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
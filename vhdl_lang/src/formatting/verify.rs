@@ -0,0 +1,204 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Check that formatting keeps the meaning of the code and that formatting
+//! the formatted code again changes nothing
+
+use super::VHDLFormatter;
+use crate::ast::DesignFile;
+use crate::data::{Diagnostic, Source};
+use crate::refactor::{apply_edits, source_text};
+use crate::syntax::{Token, VHDLParser};
+
+impl VHDLFormatter {
+    /// Format `source`, parse the formatted code and format it again. Fails when the
+    /// formatted code has syntax errors, when its tokens differ from those of the original
+    /// code other than by the case of the keywords or when formatting it again changes it.
+    pub fn verify(&self, parser: &VHDLParser, source: &Source) -> Result<(), String> {
+        let file_name = source.file_name().display();
+        let design_file = parse(parser, source)
+            .map_err(|err| format!("Cannot format {file_name} because of syntax errors: {err}"))?;
+        let edits = self.format_design_file(source, &design_file);
+
+        let formatted = Source::inline(source.file_name(), &apply_edits(source, &edits));
+        let formatted_file = parse(parser, &formatted)
+            .map_err(|err| format!("The formatted code of {file_name} has syntax errors: {err}"))?;
+
+        let tokens = |design_file: &DesignFile| -> Vec<Token> {
+            design_file
+                .design_units
+                .iter()
+                .flat_map(|(tokens, _)| tokens.iter().cloned())
+                .collect()
+        };
+        compare_tokens(&tokens(&design_file), &tokens(&formatted_file))
+            .map_err(|err| format!("Formatting {file_name} {err}"))?;
+
+        if let Some(edit) = self.format_design_file(&formatted, &formatted_file).first() {
+            return Err(format!(
+                "Formatting {file_name} twice changes line {} of the formatted code",
+                edit.pos.start().line + 1
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Fails when the tokens differ other than by the case of the keywords
+fn compare_tokens(original_tokens: &[Token], formatted_tokens: &[Token]) -> Result<(), String> {
+    for (original, formatted) in original_tokens.iter().zip(formatted_tokens.iter()) {
+        if original.kind != formatted.kind || original.value != formatted.value {
+            return Err(format!(
+                "changes '{}' on line {} to '{}'",
+                source_text(&original.pos),
+                original.pos.start().line + 1,
+                source_text(&formatted.pos),
+            ));
+        }
+    }
+    if original_tokens.len() != formatted_tokens.len() {
+        return Err(format!(
+            "changes the number of tokens from {} to {}",
+            original_tokens.len(),
+            formatted_tokens.len()
+        ));
+    }
+    Ok(())
+}
+
+/// The design file of the source, or the message of its first syntax error
+fn parse(parser: &VHDLParser, source: &Source) -> Result<DesignFile, String> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let design_file = parser.parse_design_source(source, &mut diagnostics);
+    match diagnostics.into_iter().next() {
+        Some(diagnostic) => Err(diagnostic.message),
+        None => Ok(design_file),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formatting::{FormatterConfig, KeywordCase};
+    use crate::standard::VHDLStandard;
+    use std::path::Path;
+
+    const CODE: &str = "\
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity ent is
+generic (width : natural := 8);
+port (
+clk : in std_logic;
+data_in : in std_logic_vector(width - 1 downto 0);
+data_out : out std_logic_vector(width - 1 downto 0)
+);
+end entity;
+
+ARCHITECTURE rtl OF ent IS
+-- The registered data
+signal data : std_logic_vector(width - 1 downto 0);
+begin
+process (clk)
+begin
+if rising_edge(clk) then
+data <= data_in;
+end if;
+end process;
+data_out <= data when data(0) = '1' else (others => '0');
+end architecture;
+";
+
+    #[test]
+    fn formatting_is_stable_and_keeps_tokens() {
+        let parser = VHDLParser::new(VHDLStandard::default());
+        let source = Source::inline(Path::new("ent.vhd"), CODE);
+        let configs = [
+            FormatterConfig::default(),
+            FormatterConfig {
+                indent_width: 2,
+                align_declarations: true,
+                keyword_case: KeywordCase::Lower,
+                max_blank_lines: Some(0),
+                sort_context_clauses: true,
+                ..Default::default()
+            },
+            FormatterConfig {
+                use_tabs: true,
+                max_line_length: Some(50),
+                keyword_case: KeywordCase::Upper,
+                ..Default::default()
+            },
+        ];
+        for config in configs {
+            assert_eq!(VHDLFormatter::new(config).verify(&parser, &source), Ok(()));
+        }
+    }
+
+    #[test]
+    fn formatting_the_vhdl_libraries_is_stable_and_keeps_tokens() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+        let mut file_names = Vec::new();
+        for pattern in ["vhdl_libraries/**/*.vhd*", "vhdl_lang/tests/**/*.vhd*"] {
+            let pattern = root.join(pattern);
+            file_names.extend(glob::glob(&pattern.to_string_lossy()).unwrap().flatten());
+        }
+        assert!(!file_names.is_empty());
+
+        let parser = VHDLParser::new(VHDLStandard::default());
+        let formatter = VHDLFormatter::new(FormatterConfig::default());
+        let mut failures = Vec::new();
+        for file_name in file_names {
+            let source = Source::from_latin1_file(&file_name).unwrap();
+            match formatter.verify(&parser, &source) {
+                Ok(()) => {}
+                // Files that cannot be parsed are not formatted
+                Err(err) if err.starts_with("Cannot format") => {}
+                Err(err) => failures.push(err),
+            }
+        }
+        assert_eq!(failures, Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_changed_token_values() {
+        let parser = VHDLParser::new(VHDLStandard::default());
+        let tokens = |code: &str| -> Vec<Token> {
+            let source = Source::inline(Path::new("ent.vhd"), code);
+            parse(&parser, &source)
+                .unwrap()
+                .design_units
+                .into_iter()
+                .flat_map(|(tokens, _)| tokens)
+                .collect()
+        };
+        assert_eq!(
+            compare_tokens(
+                &tokens("entity ent is\nend entity;"),
+                &tokens("ENTITY ent IS\nEND ENTITY;")
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            compare_tokens(
+                &tokens("entity ent is\nend entity;"),
+                &tokens("entity other is\nend entity;")
+            ),
+            Err("changes 'ent' on line 1 to 'other'".to_owned())
+        );
+    }
+
+    #[test]
+    fn fails_for_syntax_errors() {
+        let parser = VHDLParser::new(VHDLStandard::default());
+        let source = Source::inline(Path::new("ent.vhd"), "entity ent is");
+        let result = VHDLFormatter::new(FormatterConfig::default()).verify(&parser, &source);
+        assert!(result
+            .unwrap_err()
+            .starts_with("Cannot format ent.vhd because of syntax errors"));
+    }
+}
@@ -0,0 +1,903 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! The instance hierarchy of a design, starting at its top-level entities
+
+use crate::analysis::{DesignRoot, LockedUnit};
+use crate::ast::search::{FindAllEnt, FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::ast::{
+    ActualPart, AnyDesignUnit, AnyPrimaryUnit, AnySecondaryUnit, ConcurrentStatement, Designator,
+    EntityDeclaration, HasUnitId, InstantiatedUnit, InstantiationStatement, InterfaceDeclaration,
+    LabeledConcurrentStatement, ModeIndication, Name,
+};
+use crate::data::{HasSource, HasSrcPos, Source, SrcPos, Symbol};
+use crate::named_entity::{AnyEntKind, Design, EntRef, HasEntityId};
+use crate::refactor::source_text;
+use crate::reference_kind::classify_references;
+use crate::syntax::TokenAccess;
+use crate::{EntityId, ReferenceKind};
+use fnv::FnvHashSet;
+use serde_json::{json, Map, Value};
+
+/// An instance of the elaborated design
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Instance {
+    /// The label of the instance, prefixed by the labels of the enclosing block and
+    /// generate statements. The name of the entity for a top-level instance.
+    pub name: String,
+    /// The instantiation statement or the entity declaration of a top-level instance
+    pub pos: SrcPos,
+    /// The instantiated component, if any
+    pub component: Option<String>,
+    /// The library of the bound entity
+    pub library: Option<String>,
+    /// The bound entity, `None` when no entity is bound to a component
+    pub entity: Option<String>,
+    /// The architecture of the bound entity
+    pub architecture: Option<String>,
+    pub architecture_pos: Option<SrcPos>,
+    /// The value of each generic of the entity, as associated by the generic map or the default
+    /// value of the generic. Generics of the parent instance are replaced by their value.
+    pub generics: Vec<(String, Option<String>)>,
+    pub instances: Vec<Instance>,
+}
+
+impl Instance {
+    /// The instance and its sub-instances as a JSON object
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.json_value()).unwrap_or_default()
+    }
+
+    fn json_value(&self) -> Value {
+        let location = |pos: &SrcPos| {
+            json!({
+                "file": pos.source.file_name().to_string_lossy(),
+                "line": pos.start().line + 1,
+            })
+        };
+        let generics: Map<String, Value> = self
+            .generics
+            .iter()
+            .map(|(name, value)| (name.clone(), json!(value)))
+            .collect();
+        json!({
+            "name": self.name,
+            "location": location(&self.pos),
+            "component": self.component,
+            "library": self.library,
+            "entity": self.entity,
+            "architecture": self.architecture,
+            "architecture_location": self.architecture_pos.as_ref().map(location),
+            "generics": generics,
+            "instances": self.instances.iter().map(Instance::json_value).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// The instances as a JSON array
+pub fn instances_to_json(instances: &[Instance]) -> String {
+    let instances: Vec<Value> = instances.iter().map(Instance::json_value).collect();
+    serde_json::to_string_pretty(&instances).unwrap_or_default()
+}
+
+/// The entities of the libraries that have an architecture
+/// and are not instantiated by any architecture of the design
+pub fn top_level_entities(root: &DesignRoot, libraries: &[Symbol]) -> Vec<(Symbol, Symbol)> {
+    let elaboration = Elaboration { root };
+    let mut instantiated = FnvHashSet::default();
+    for library in root.libraries() {
+        for unit in library.units() {
+            let Some(data) = unit.unit.get() else {
+                continue;
+            };
+            if let AnyDesignUnit::Secondary(AnySecondaryUnit::Architecture(arch)) = data.data() {
+                elaboration.instantiated_entities(
+                    library.name(),
+                    &arch.statements,
+                    &mut instantiated,
+                );
+            }
+        }
+    }
+
+    let mut tops = Vec::new();
+    for library_name in libraries {
+        let Some(library) = root.get_lib(library_name) else {
+            continue;
+        };
+        for unit in library.primary_units() {
+            let is_entity = unit.unit.get().is_some_and(|data| data.data().is_entity());
+            let name = unit.unit_id().primary_name();
+            let key = (library_name.clone(), name.clone());
+            if is_entity
+                && library.secondary_units(name).next().is_some()
+                && !instantiated.contains(&key)
+            {
+                tops.push((unit.pos().clone(), key));
+            }
+        }
+    }
+    tops.sort_by(|(pos1, _), (pos2, _)| {
+        (pos1.source.file_name(), pos1.start()).cmp(&(pos2.source.file_name(), pos2.start()))
+    });
+    tops.into_iter().map(|(_, key)| key).collect()
+}
+
+/// The instance hierarchy below the architecture of an entity.
+/// Returns `None` when the entity does not exist.
+pub fn instance_hierarchy(
+    root: &DesignRoot,
+    library: &Symbol,
+    entity: &Symbol,
+) -> Option<Instance> {
+    let elaboration = Elaboration { root };
+    let unit = root.get_lib(library)?.primary_unit(entity)?;
+    if !unit.unit.get()?.data().is_entity() {
+        return None;
+    }
+    Some(elaboration.instance(
+        entity.name_utf8(),
+        unit.pos().clone(),
+        None,
+        Some((library.clone(), entity.clone(), None)),
+        &[],
+        &mut Vec::new(),
+    ))
+}
+
+/// The entities that are bound to the instances of the statements, including the instances
+/// within block and generate statements
+pub(crate) fn instantiated_entities(
+    root: &DesignRoot,
+    library: &Symbol,
+    statements: &[LabeledConcurrentStatement],
+) -> FnvHashSet<(Symbol, Symbol)> {
+    let mut result = FnvHashSet::default();
+    Elaboration { root }.instantiated_entities(library, statements, &mut result);
+    result
+}
+
+/// An instantiation statement together with the architecture that contains it
+#[derive(Clone, Debug)]
+pub struct Instantiation<'a> {
+    /// The label of the instantiation statement
+    pub pos: SrcPos,
+    /// The architecture that contains the instantiation statement
+    pub parent: EntRef<'a>,
+    /// The instantiated entity, component or configuration.
+    /// A component is replaced by the entity of its default binding when it exists.
+    pub unit: EntRef<'a>,
+}
+
+/// The instantiations of an entity or component.
+/// The instantiations of the entity of an architecture are returned for an architecture.
+pub(crate) fn incoming_instances<'a>(
+    root: &'a DesignRoot,
+    ent: EntRef<'a>,
+) -> Vec<Instantiation<'a>> {
+    let unit = match ent.kind() {
+        AnyEntKind::Design(Design::Architecture(entity)) => entity.0,
+        _ => ent,
+    };
+    instantiations(root)
+        .into_iter()
+        .filter(|instantiation| instantiation.unit.id() == unit.id())
+        .collect()
+}
+
+/// The instantiations within an architecture, or within all architectures of an entity
+pub(crate) fn outgoing_instances<'a>(
+    root: &'a DesignRoot,
+    ent: EntRef<'a>,
+) -> Vec<Instantiation<'a>> {
+    instantiations(root)
+        .into_iter()
+        .filter(|instantiation| {
+            instantiation.parent.id() == ent.id()
+                || matches!(
+                    instantiation.parent.kind(),
+                    AnyEntKind::Design(Design::Architecture(entity)) if entity.id() == ent.id()
+                )
+        })
+        .collect()
+}
+
+/// The entities and components that are declared in `source` together with
+/// the names of the instantiated units of their instantiation statements.
+/// The instantiations of an entity include those of the components with the same name.
+pub(crate) fn instantiations_of_units<'a>(
+    root: &'a DesignRoot,
+    source: &Source,
+) -> Vec<(EntRef<'a>, Vec<SrcPos>)> {
+    let mut searcher = FindAllEnt::new(root, |ent| {
+        matches!(
+            ent.kind(),
+            AnyEntKind::Design(Design::Entity(..)) | AnyEntKind::Component(..)
+        )
+    });
+    let _ = root.search_source(source, &mut searcher);
+    let mut units = searcher.result;
+    units.sort_by_key(|unit| unit.decl_pos().map(|pos| pos.start()));
+    // The same unit is found through every library that a file is mapped to
+    units.dedup_by_key(|unit| unit.id());
+
+    units
+        .into_iter()
+        .map(|unit| {
+            let mut references = root.find_all_references(unit);
+            if matches!(unit.kind(), AnyEntKind::Design(Design::Entity(..))) {
+                for component in root.find_implementation(unit) {
+                    if matches!(component.kind(), AnyEntKind::Component(..)) {
+                        references.extend(root.find_all_references(component));
+                    }
+                }
+            }
+            let mut positions: Vec<SrcPos> = classify_references(root, references)
+                .into_iter()
+                .filter(|(_, kind)| *kind == ReferenceKind::Instantiation)
+                .map(|(pos, _)| pos)
+                .collect();
+            positions.sort_by(|pos1, pos2| {
+                (pos1.source.file_name(), pos1.start())
+                    .cmp(&(pos2.source.file_name(), pos2.start()))
+            });
+            positions.dedup();
+            (unit, positions)
+        })
+        .collect()
+}
+
+/// All instantiation statements of the design, ordered by file and position
+fn instantiations(root: &DesignRoot) -> Vec<Instantiation> {
+    let mut searcher = Instantiations {
+        root,
+        architecture: None,
+        found: FnvHashSet::default(),
+        instantiations: Vec::new(),
+    };
+    let _ = root.search(&mut searcher);
+
+    let mut instantiations = searcher.instantiations;
+    instantiations.sort_by(|inst1, inst2| {
+        (inst1.pos.source.file_name(), inst1.pos.start())
+            .cmp(&(inst2.pos.source.file_name(), inst2.pos.start()))
+    });
+    instantiations
+}
+
+struct Instantiations<'a> {
+    root: &'a DesignRoot,
+    /// The architecture of the statements that are searched
+    architecture: Option<EntRef<'a>>,
+    found: FnvHashSet<SrcPos>,
+    instantiations: Vec<Instantiation<'a>>,
+}
+
+impl<'a> Instantiations<'a> {
+    /// The entity of the default binding of a component
+    fn bound_unit(&self, unit: EntRef<'a>) -> EntRef<'a> {
+        if !matches!(unit.kind(), AnyEntKind::Component(..)) {
+            return unit;
+        }
+        self.root
+            .find_implementation(unit)
+            .into_iter()
+            .find(|ent| matches!(ent.kind(), AnyEntKind::Design(Design::Entity(..))))
+            .unwrap_or(unit)
+    }
+}
+
+impl<'a> Searcher for Instantiations<'a> {
+    fn search_decl(&mut self, _ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        let Some(ent) = decl.ent_id().map(|id| self.root.get_ent(id)) else {
+            return NotFinished;
+        };
+        match ent.kind() {
+            AnyEntKind::Design(Design::Architecture(..)) => self.architecture = Some(ent),
+            AnyEntKind::Design(..) => self.architecture = None,
+            _ => {}
+        }
+
+        let FoundDeclaration::ConcurrentStatement(stmt) = decl else {
+            return NotFinished;
+        };
+        let ConcurrentStatement::Instance(ref instance) = stmt.statement.item else {
+            return NotFinished;
+        };
+        let (Some(parent), Some(pos), Some(id)) = (
+            self.architecture,
+            ent.decl_pos(),
+            instance.entity_reference(),
+        ) else {
+            return NotFinished;
+        };
+        // The same statement is found through every library that a file is mapped to
+        if self.found.insert(pos.clone()) {
+            self.instantiations.push(Instantiation {
+                pos: pos.clone(),
+                parent,
+                unit: self.bound_unit(self.root.get_ent(id)),
+            });
+        }
+        NotFinished
+    }
+}
+
+/// An entity together with the architecture of a binding
+type Binding = (Symbol, Symbol, Option<Symbol>);
+
+struct Elaboration<'a> {
+    root: &'a DesignRoot,
+}
+
+impl<'a> Elaboration<'a> {
+    /// Elaborate the instance of an entity. The generic map is associated with the generics
+    /// of the entity, actuals that are generics of the parent are replaced by their value.
+    fn instance(
+        &self,
+        name: String,
+        pos: SrcPos,
+        generic_map: Option<(&InstantiationStatement, &dyn TokenAccess)>,
+        binding: Option<Binding>,
+        parent_generics: &[(String, Option<String>)],
+        stack: &mut Vec<(Symbol, Symbol)>,
+    ) -> Instance {
+        let mut instance = Instance {
+            name,
+            pos,
+            component: None,
+            library: None,
+            entity: None,
+            architecture: None,
+            architecture_pos: None,
+            generics: Vec::new(),
+            instances: Vec::new(),
+        };
+        let Some((library, entity, architecture)) = binding else {
+            return instance;
+        };
+        let Some(entity_unit) = self
+            .root
+            .get_lib(&library)
+            .and_then(|lib| lib.primary_unit(&entity))
+        else {
+            return instance;
+        };
+        let Some(entity_data) = entity_unit.unit.get() else {
+            return instance;
+        };
+        let AnyDesignUnit::Primary(AnyPrimaryUnit::Entity(entity_decl)) = entity_data.data() else {
+            return instance;
+        };
+        instance.library = Some(library.name_utf8());
+        instance.entity = Some(entity.name_utf8());
+        instance.generics = generic_values(entity_decl, &entity_unit.tokens, generic_map)
+            .into_iter()
+            .map(|(name, value)| {
+                let value = value.map(|value| {
+                    parent_generics
+                        .iter()
+                        .find(|(parent, _)| parent.eq_ignore_ascii_case(&value))
+                        .and_then(|(_, parent_value)| parent_value.clone())
+                        .unwrap_or(value)
+                });
+                (name, value)
+            })
+            .collect();
+
+        let Some(arch_unit) = self.architecture(&library, &entity, architecture.as_ref()) else {
+            return instance;
+        };
+        instance.architecture = arch_unit.unit_id().secondary_name().map(Symbol::name_utf8);
+        instance.architecture_pos = Some(arch_unit.pos().clone());
+
+        // Recursive instantiation cannot be elaborated without evaluating generate conditions
+        let key = (library.clone(), entity.clone());
+        if stack.contains(&key) {
+            return instance;
+        }
+        let Some(arch_data) = arch_unit.unit.get() else {
+            return instance;
+        };
+        if let AnyDesignUnit::Secondary(AnySecondaryUnit::Architecture(arch)) = arch_data.data() {
+            stack.push(key);
+            self.sub_instances(
+                &library,
+                &arch_unit.tokens,
+                &arch.statements,
+                "",
+                &instance.generics,
+                stack,
+                &mut instance.instances,
+            );
+            stack.pop();
+        }
+        instance
+            .instances
+            .sort_by_key(|instance| instance.pos.start());
+        instance
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn sub_instances(
+        &self,
+        library: &Symbol,
+        ctx: &dyn TokenAccess,
+        statements: &[LabeledConcurrentStatement],
+        prefix: &str,
+        generics: &[(String, Option<String>)],
+        stack: &mut Vec<(Symbol, Symbol)>,
+        result: &mut Vec<Instance>,
+    ) {
+        for (label, statements) in nested_statements(statements) {
+            let prefix = format!("{prefix}{label}.");
+            self.sub_instances(library, ctx, statements, &prefix, generics, stack, result);
+        }
+        for stmt in statements {
+            let Some((label, instance)) = instance_of(stmt) else {
+                continue;
+            };
+            let (component, binding) = self.binding(library, instance);
+            let mut sub_instance = self.instance(
+                format!("{prefix}{label}"),
+                stmt.statement.span.pos(ctx),
+                Some((instance, ctx)),
+                binding,
+                generics,
+                stack,
+            );
+            sub_instance.component = component;
+            result.push(sub_instance);
+        }
+    }
+
+    /// Add the entities that are instantiated by the statements
+    fn instantiated_entities(
+        &self,
+        library: &Symbol,
+        statements: &[LabeledConcurrentStatement],
+        result: &mut FnvHashSet<(Symbol, Symbol)>,
+    ) {
+        for (_, statements) in nested_statements(statements) {
+            self.instantiated_entities(library, statements, result);
+        }
+        for stmt in statements {
+            if let Some((_, instance)) = instance_of(stmt) {
+                if let (_, Some((library, entity, _))) = self.binding(library, instance) {
+                    result.insert((library, entity));
+                }
+            }
+        }
+    }
+
+    /// The instantiated component and the entity that is bound to an instance.
+    /// A component is bound to the entity of the same name in the library of the instance.
+    fn binding(
+        &self,
+        library: &Symbol,
+        instance: &InstantiationStatement,
+    ) -> (Option<String>, Option<Binding>) {
+        match instance.unit {
+            InstantiatedUnit::Entity(ref name, ref architecture) => {
+                let binding =
+                    self.library_unit(name.item.get_suffix_reference())
+                        .map(|(library, entity)| {
+                            let architecture =
+                                architecture.as_ref().map(|arch| arch.item.item.clone());
+                            (library, entity, architecture)
+                        });
+                (None, binding)
+            }
+            InstantiatedUnit::Component(ref name) => {
+                let Some((_, component)) = self.library_unit(name.item.get_suffix_reference())
+                else {
+                    return (None, None);
+                };
+                let is_entity = self
+                    .root
+                    .get_lib(library)
+                    .and_then(|lib| lib.primary_unit(&component))
+                    .and_then(|unit| unit.unit.get())
+                    .is_some_and(|data| data.data().is_entity());
+                let binding = is_entity.then(|| (library.clone(), component.clone(), None));
+                (Some(component.name_utf8()), binding)
+            }
+            InstantiatedUnit::Configuration(ref name) => {
+                let binding = self
+                    .library_unit(name.item.get_suffix_reference())
+                    .and_then(|(library, configuration)| {
+                        self.configured_entity(&library, &configuration)
+                    });
+                (None, binding)
+            }
+        }
+    }
+
+    /// The entity and architecture of a configuration declaration
+    fn configured_entity(&self, library: &Symbol, name: &Symbol) -> Option<Binding> {
+        let unit = self.root.get_lib(library)?.primary_unit(name)?;
+        let data = unit.unit.get()?;
+        let AnyDesignUnit::Primary(AnyPrimaryUnit::Configuration(config)) = data.data() else {
+            return None;
+        };
+        let (library, entity) =
+            self.library_unit(config.entity_name.item.get_suffix_reference())?;
+        let architecture = match config.block_config.block_spec.item {
+            Name::Designator(ref designator) => match designator.item {
+                Designator::Identifier(ref name) => Some(name.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        Some((library, entity, architecture))
+    }
+
+    /// The library and the name of a design unit
+    fn library_unit(&self, id: Option<EntityId>) -> Option<(Symbol, Symbol)> {
+        let ent = self.root.get_ent(id?);
+        let Designator::Identifier(ref name) = ent.designator() else {
+            return None;
+        };
+        Some((ent.library_name()?.clone(), name.clone()))
+    }
+
+    /// The architecture of an entity. When no architecture is given,
+    /// the last architecture of the entity in the source files is used.
+    fn architecture(
+        &self,
+        library: &Symbol,
+        entity: &'a Symbol,
+        name: Option<&Symbol>,
+    ) -> Option<&'a LockedUnit> {
+        let mut architectures = self.root.get_lib(library)?.secondary_units(entity);
+        match name {
+            Some(name) => architectures.find(|unit| unit.unit_id().secondary_name() == Some(name)),
+            None => architectures.max_by(|unit1, unit2| {
+                (unit1.source().file_name(), unit1.pos().start())
+                    .cmp(&(unit2.source().file_name(), unit2.pos().start()))
+            }),
+        }
+    }
+}
+
+/// The label and the instantiation of an instantiation statement
+pub(crate) fn instance_of(
+    stmt: &LabeledConcurrentStatement,
+) -> Option<(String, &InstantiationStatement)> {
+    let ConcurrentStatement::Instance(ref instance) = stmt.statement.item else {
+        return None;
+    };
+    let label = stmt.label.tree.as_ref()?.item.name_utf8();
+    Some((label, instance))
+}
+
+/// The statements of the block and generate statements together with their label
+pub(crate) fn nested_statements(
+    statements: &[LabeledConcurrentStatement],
+) -> Vec<(String, &[LabeledConcurrentStatement])> {
+    let mut result: Vec<(String, &[LabeledConcurrentStatement])> = Vec::new();
+    for stmt in statements {
+        let label = stmt
+            .label
+            .tree
+            .as_ref()
+            .map(|label| label.item.name_utf8())
+            .unwrap_or_default();
+        match stmt.statement.item {
+            ConcurrentStatement::Block(ref block) => result.push((label, &block.statements)),
+            ConcurrentStatement::ForGenerate(ref gen) => result.push((label, &gen.body.statements)),
+            ConcurrentStatement::IfGenerate(ref gen) => {
+                for cond in gen.conds.conditionals.iter() {
+                    result.push((label.clone(), &cond.item.statements));
+                }
+                if let Some(ref body) = gen.conds.else_item {
+                    result.push((label, &body.statements));
+                }
+            }
+            ConcurrentStatement::CaseGenerate(ref gen) => {
+                for alternative in gen.sels.alternatives.iter() {
+                    result.push((label.clone(), &alternative.item.statements));
+                }
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+/// The name of each object generic of the entity with the value of the
+/// generic map or the default value
+fn generic_values(
+    entity: &EntityDeclaration,
+    entity_ctx: &dyn TokenAccess,
+    generic_map: Option<(&InstantiationStatement, &dyn TokenAccess)>,
+) -> Vec<(String, Option<String>)> {
+    let mut generics: Vec<(String, Option<String>)> = entity
+        .generic_clause
+        .iter()
+        .flatten()
+        .filter_map(|generic| match generic {
+            InterfaceDeclaration::Object(object) => {
+                let default = match object.mode {
+                    ModeIndication::Simple(ref mode) => mode.expression.as_ref(),
+                    ModeIndication::View(_) => None,
+                };
+                Some((
+                    object.ident.tree.item.name_utf8(),
+                    default.map(|expr| source_text(&expr.span.pos(entity_ctx))),
+                ))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let Some((instance, ctx)) = generic_map else {
+        return generics;
+    };
+    let Some(ref map) = instance.generic_map else {
+        return generics;
+    };
+    for (i, element) in map.list.items.iter().enumerate() {
+        let ActualPart::Expression(_) = element.actual.item else {
+            continue;
+        };
+        let generic = match element.formal {
+            Some(ref formal) => {
+                let formal = source_text(&formal.span.pos(ctx));
+                generics
+                    .iter_mut()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(&formal))
+            }
+            None => generics.get_mut(i),
+        };
+        if let Some((_, value)) = generic {
+            *value = Some(source_text(&element.actual.span.pos(ctx)));
+        }
+    }
+    generics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+
+    /// The name, entity and generics of the instances, indented by their depth
+    fn outline(instance: &Instance, depth: usize, result: &mut Vec<String>) {
+        let generics = instance
+            .generics
+            .iter()
+            .map(|(name, value)| format!("{name}={}", value.as_deref().unwrap_or("?")))
+            .collect::<Vec<_>>()
+            .join(",");
+        result.push(format!(
+            "{}{}: {}({}) [{generics}]",
+            "  ".repeat(depth),
+            instance.name,
+            instance.entity.as_deref().unwrap_or("?"),
+            instance.architecture.as_deref().unwrap_or("?"),
+        ));
+        for sub_instance in instance.instances.iter() {
+            outline(sub_instance, depth + 1, result);
+        }
+    }
+
+    #[test]
+    fn elaborates_instance_hierarchy() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "\
+entity leaf is
+  generic (width : natural := 4; depth : natural := 2);
+end entity;
+
+architecture rtl of leaf is
+begin
+end architecture;
+
+entity mid is
+  generic (width : natural := 8);
+end entity;
+
+architecture rtl of mid is
+  component leaf is
+    generic (width : natural := 4; depth : natural := 2);
+  end component;
+begin
+  u_first : entity work.leaf generic map (width => width);
+  gen_lanes : for i in 0 to 1 generate
+    u_lane : leaf generic map (16);
+  end generate;
+end architecture;
+
+entity top is
+end entity;
+
+architecture struct of top is
+begin
+  u_mid : entity work.mid(rtl) generic map (width => 32);
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let library = root.symbol_utf8("libname");
+        let tops = top_level_entities(&root, std::slice::from_ref(&library));
+        assert_eq!(tops, vec![(library.clone(), root.symbol_utf8("top"))]);
+
+        let top = instance_hierarchy(&root, &library, &tops[0].1).unwrap();
+        let mut result = Vec::new();
+        outline(&top, 0, &mut result);
+        assert_eq!(
+            result,
+            vec![
+                "top: top(struct) []",
+                "  u_mid: mid(rtl) [width=32]",
+                "    u_first: leaf(rtl) [width=32,depth=2]",
+                "    gen_lanes.u_lane: leaf(rtl) [width=16,depth=2]",
+            ]
+        );
+        assert_eq!(
+            top.instances[0].instances[1].component.as_deref(),
+            Some("leaf")
+        );
+    }
+
+    #[test]
+    fn instance_hierarchy_as_json() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity top is
+  generic (name : string := \"ab\");
+end entity;
+
+architecture struct of top is
+  component black_box is
+  end component;
+begin
+  u_box : component black_box;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let library = root.symbol_utf8("libname");
+        let top = instance_hierarchy(&root, &library, &root.symbol_utf8("top")).unwrap();
+        let file = code.source().file_name().to_string_lossy();
+        let json: Value = serde_json::from_str(&instances_to_json(&[top])).unwrap();
+        assert_eq!(
+            json,
+            json!([
+                {
+                    "name": "top",
+                    "location": { "file": file, "line": 1 },
+                    "component": null,
+                    "library": "libname",
+                    "entity": "top",
+                    "architecture": "struct",
+                    "architecture_location": { "file": file, "line": 5 },
+                    "generics": { "name": "\"ab\"" },
+                    "instances": [
+                        {
+                            "name": "u_box",
+                            "location": { "file": file, "line": 9 },
+                            "component": "black_box",
+                            "library": null,
+                            "entity": null,
+                            "architecture": null,
+                            "architecture_location": null,
+                            "generics": {},
+                            "instances": []
+                        }
+                    ]
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn finds_incoming_and_outgoing_instances() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity leaf is
+end entity;
+
+architecture rtl of leaf is
+begin
+end architecture;
+
+entity top is
+end entity;
+
+architecture struct of top is
+  component leaf is
+  end component;
+begin
+  u_first : entity work.leaf;
+  gen_lanes : for i in 0 to 1 generate
+    u_lane : component leaf;
+  end generate;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let leaf = root
+            .search_reference(code.source(), code.s1("leaf").start())
+            .unwrap();
+        let top = root
+            .search_reference(code.source(), code.s1("top").start())
+            .unwrap();
+        let labels = |instantiations: Vec<Instantiation>| -> Vec<SrcPos> {
+            instantiations
+                .into_iter()
+                .map(|instantiation| {
+                    assert_eq!(instantiation.unit.id(), leaf.id());
+                    instantiation.pos
+                })
+                .collect()
+        };
+        let expected = vec![code.s1("u_first").pos(), code.s1("u_lane").pos()];
+
+        assert_eq!(labels(incoming_instances(&root, leaf)), expected);
+        assert_eq!(labels(outgoing_instances(&root, top)), expected);
+        assert_eq!(labels(outgoing_instances(&root, leaf)), vec![]);
+
+        let rtl = root
+            .search_reference(code.source(), code.s1("rtl").start())
+            .unwrap();
+        assert_eq!(labels(incoming_instances(&root, rtl)), expected);
+    }
+
+    #[test]
+    fn instantiations_of_entities_and_components_of_source() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity leaf is
+end entity;
+
+entity top is
+end entity;
+
+architecture struct of top is
+  component leaf is
+  end component;
+begin
+  u_first : entity work.leaf;
+  u_second : component leaf;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let summary: Vec<(String, Vec<SrcPos>)> = instantiations_of_units(&root, code.source())
+            .into_iter()
+            .map(|(unit, positions)| (unit.describe(), positions))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "entity 'leaf'".to_owned(),
+                    vec![code.s("leaf", 3).pos(), code.s("leaf", 4).pos()]
+                ),
+                ("entity 'top'".to_owned(), vec![]),
+                ("component 'leaf'".to_owned(), vec![code.s("leaf", 4).pos()]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,730 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Black-box entities of vendor IP cores.
+//!
+//! Xilinx `.xci` files describe an instance of an IP core. The ports of the generated core are read from
+//! the JSON format of recent Vivado versions, from the boundary description embedded in the older
+//! IP-XACT format or from an IP-XACT component and translated to an entity like a Verilog module.
+//! Intel `.qip` files list the files generated for a core and are replaced by those files when a library is loaded.
+
+use crate::data::{Message, MessageHandler, Position};
+use crate::standard::VHDLStandard;
+use crate::verilog::{entity_declarations, Direction, Identifier, Module, Port, Width};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// True if the file describes an IP core that is translated to a black-box entity
+pub(crate) fn is_ip_core_file(file_name: &Path) -> bool {
+    has_extension(file_name, "xci")
+}
+
+/// True if the file is a list of the files of an IP core
+pub(crate) fn is_ip_file_list(file_name: &Path) -> bool {
+    has_extension(file_name, "qip")
+}
+
+fn has_extension(file_name: &Path, extension: &str) -> bool {
+    file_name
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// The VHDL entity declaration of the IP core of an `.xci` file
+pub(crate) fn black_box_entities(text: &str, standard: VHDLStandard) -> String {
+    let modules = if text.trim_start().starts_with('{') {
+        serde_json::from_str(text)
+            .ok()
+            .and_then(|xci| xci_module(text, &xci))
+            .into_iter()
+            .collect()
+    } else {
+        xml_element(&mut Scanner::new(text))
+            .map(|root| ip_xact_modules(&root))
+            .unwrap_or_default()
+    };
+    entity_declarations(&modules, standard)
+}
+
+/// The HDL files listed in a `.qip` file, including the files of nested `.qip` files.
+/// Relative paths are relative to the directory of the `.qip` file.
+pub(crate) fn qip_file_names(file_name: &Path, messages: &mut dyn MessageHandler) -> Vec<PathBuf> {
+    let mut visited = Vec::new();
+    let mut result = Vec::new();
+    read_qip_file(file_name, &mut visited, &mut result, messages);
+    result
+}
+
+fn read_qip_file(
+    file_name: &Path,
+    visited: &mut Vec<PathBuf>,
+    result: &mut Vec<PathBuf>,
+    messages: &mut dyn MessageHandler,
+) {
+    if visited.iter().any(|name| name == file_name) {
+        return;
+    }
+    visited.push(file_name.to_owned());
+
+    let text = match std::fs::read_to_string(file_name) {
+        Ok(text) => text,
+        Err(err) => {
+            messages.push(Message::error(format!(
+                "Could not read {}: {err}",
+                file_name.display()
+            )));
+            return;
+        }
+    };
+    let directory = file_name.parent().unwrap_or(Path::new(""));
+
+    for line in text.lines() {
+        let Some((kind, path)) = qip_assignment(line, directory) else {
+            continue;
+        };
+        if !path.exists() {
+            messages.push(Message::warning(format!(
+                "File {} listed in {} does not exist",
+                path.display(),
+                file_name.display()
+            )));
+        } else if kind == "QIP_FILE" {
+            read_qip_file(&path, visited, result, messages);
+        } else {
+            result.push(path);
+        }
+    }
+}
+
+/// The kind and path of a file assignment such as
+/// `set_global_assignment -name VHDL_FILE [file join $::quartus(qip_path) "core.vhd"]`
+fn qip_assignment(line: &str, directory: &Path) -> Option<(String, PathBuf)> {
+    let words = tcl_words(line);
+    if words.first()? != "set_global_assignment" {
+        return None;
+    }
+    let name_idx = words.iter().position(|word| word == "-name")?;
+    let kind = words.get(name_idx + 1)?;
+    if ![
+        "VHDL_FILE",
+        "VERILOG_FILE",
+        "SYSTEMVERILOG_FILE",
+        "QIP_FILE",
+    ]
+    .contains(&kind.as_str())
+    {
+        return None;
+    }
+
+    // Paths are relative to the directory of the file
+    let qip_path = |word: &str| match word.strip_prefix("$::quartus(qip_path)") {
+        Some(rest) => PathBuf::from(rest.trim_start_matches('/')),
+        None => PathBuf::from(word),
+    };
+    let args = &words[name_idx + 2..];
+    let path = if args.len() >= 3 && args[0] == "[" && args[1] == "file" && args[2] == "join" {
+        args[3..]
+            .iter()
+            .take_while(|word| *word != "]")
+            .fold(PathBuf::new(), |path, word| path.join(qip_path(word)))
+    } else {
+        qip_path(args.first()?)
+    };
+    Some((kind.clone(), directory.join(path)))
+}
+
+/// Split a line of Tcl into words. Brackets are separate words.
+fn tcl_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(chr) = chars.next() {
+        match chr {
+            chr if chr.is_whitespace() => {}
+            '[' | ']' => words.push(chr.to_string()),
+            '"' | '{' => {
+                let close = if chr == '"' { '"' } else { '}' };
+                words.push(chars.by_ref().take_while(|chr| *chr != close).collect());
+            }
+            _ => {
+                let mut word = chr.to_string();
+                while let Some(chr) = chars.next_if(|chr| !chr.is_whitespace() && *chr != ']') {
+                    word.push(chr);
+                }
+                words.push(word);
+            }
+        }
+    }
+    words
+}
+
+/// A character iterator that keeps track of the position
+struct Scanner {
+    chars: Vec<char>,
+    idx: usize,
+    pos: Position,
+}
+
+impl Scanner {
+    fn new(text: &str) -> Scanner {
+        Scanner {
+            chars: text.chars().collect(),
+            idx: 0,
+            pos: Position::default(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.idx).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let chr = self.peek()?;
+        if chr == '\n' {
+            self.pos = Position::new(self.pos.line + 1, 0);
+        } else {
+            self.pos.character += 1;
+        }
+        self.idx += 1;
+        Some(chr)
+    }
+
+    fn starts_with(&self, pattern: &str) -> bool {
+        let mut idx = self.idx;
+        pattern.chars().all(|chr| {
+            idx += 1;
+            self.chars.get(idx - 1) == Some(&chr)
+        })
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|chr| chr.is_whitespace()) {
+            self.bump();
+        }
+    }
+
+    /// Advance to after the next occurrence of the pattern
+    fn skip_past(&mut self, pattern: &str) {
+        while !self.starts_with(pattern) && self.bump().is_some() {}
+        for _ in pattern.chars() {
+            self.bump();
+        }
+    }
+}
+
+/// A string of the file together with its position
+#[derive(Clone, Debug, PartialEq, Default)]
+struct Text {
+    value: String,
+    pos: Position,
+}
+
+impl Text {
+    fn ident(&self) -> Identifier {
+        Identifier {
+            name: self.value.trim().to_owned(),
+            pos: self.pos,
+        }
+    }
+}
+
+/// An integer that is given as a number or as a string
+pub(crate) fn json_integer(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(number) => number.as_i64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Finds the positions of the strings of a JSON text in the order they appear
+struct JsonLocator<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+impl<'a> JsonLocator<'a> {
+    fn new(text: &'a str) -> JsonLocator<'a> {
+        JsonLocator { text, offset: 0 }
+    }
+
+    /// The position of the first character after the quote of the next occurrence
+    /// of the string, or of the current location when the string is not found
+    fn next(&mut self, value: &str) -> Position {
+        let quoted = serde_json::to_string(value).unwrap_or_default();
+        if let Some(idx) = self.text[self.offset..].find(&quoted) {
+            self.offset += idx + 1;
+        }
+        let before = &self.text[..self.offset];
+        let line = before.matches('\n').count();
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        let character = before[line_start..].chars().count();
+        self.offset += quoted.len().saturating_sub(1);
+        Position::new(line as u32, character as u32)
+    }
+}
+
+/// An XML element without its attributes.
+/// Names are without the namespace prefix.
+#[derive(Clone, Debug, PartialEq, Default)]
+struct Element {
+    name: String,
+    text: Text,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// The first element with the name in the subtree of this element
+    fn descendant(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find_map(|child| {
+            (child.name == name)
+                .then_some(child)
+                .or_else(|| child.descendant(name))
+        })
+    }
+}
+
+/// Parse the root element of an XML document
+fn xml_element(scanner: &mut Scanner) -> Option<Element> {
+    loop {
+        scanner.skip_whitespace();
+        if scanner.starts_with("<!--") {
+            scanner.skip_past("-->");
+        } else if scanner.starts_with("<?") || scanner.starts_with("<!") {
+            scanner.skip_past(">");
+        } else {
+            break;
+        }
+    }
+    if scanner.bump()? != '<' {
+        return None;
+    }
+
+    let mut element = Element::default();
+    let mut name = String::new();
+    while let Some(chr) = scanner
+        .peek()
+        .filter(|chr| !chr.is_whitespace() && !"/>".contains(*chr))
+    {
+        name.push(chr);
+        scanner.bump();
+    }
+    element.name = name.rsplit(':').next().unwrap_or_default().to_owned();
+
+    // Skip the attributes
+    let mut previous = None;
+    loop {
+        match scanner.bump()? {
+            '>' => break,
+            quote @ ('"' | '\'') => while scanner.bump()? != quote {},
+            chr => {
+                previous = Some(chr);
+                continue;
+            }
+        }
+        previous = None;
+    }
+    if previous == Some('/') {
+        return Some(element);
+    }
+
+    element.text.pos = scanner.pos;
+    loop {
+        if scanner.starts_with("</") {
+            scanner.skip_past(">");
+            break;
+        } else if scanner.starts_with("<!--") {
+            scanner.skip_past("-->");
+        } else if scanner.starts_with("<![CDATA[") {
+            scanner.skip_past("<![CDATA[");
+            while !scanner.starts_with("]]>") {
+                element.text.value.push(scanner.bump()?);
+            }
+            scanner.skip_past("]]>");
+        } else if scanner.starts_with("<") {
+            element.children.push(xml_element(scanner)?);
+        } else {
+            if element.text.value.trim().is_empty() {
+                element.text.value.clear();
+                element.text.pos = scanner.pos;
+            }
+            let chr = scanner.bump()?;
+            if chr == '&' {
+                let mut reference = String::new();
+                while let Some(chr) = scanner.bump().filter(|chr| *chr != ';') {
+                    reference.push(chr);
+                }
+                element.text.value.push(xml_reference(&reference));
+            } else {
+                element.text.value.push(chr);
+            }
+        }
+    }
+    Some(element)
+}
+
+/// The character of an entity or character reference without `&` and `;`
+fn xml_reference(reference: &str) -> char {
+    match reference {
+        "lt" => '<',
+        "gt" => '>',
+        "amp" => '&',
+        "quot" => '"',
+        "apos" => '\'',
+        _ => reference
+            .strip_prefix("#x")
+            .map(|hex| u32::from_str_radix(hex, 16).ok())
+            .or_else(|| reference.strip_prefix('#').map(|dec| dec.parse().ok()))
+            .flatten()
+            .and_then(char::from_u32)
+            .unwrap_or(char::REPLACEMENT_CHARACTER),
+    }
+}
+
+/// The core of the JSON format of an `.xci` file.
+/// The names are placed at their position in the JSON text.
+fn xci_module(text: &str, xci: &Value) -> Option<Module> {
+    let instance = xci.get("ip_inst")?;
+    let name = instance.get("xci_name")?.as_str()?;
+    let mut locator = JsonLocator::new(text);
+    locator.next("xci_name");
+    let ident = Identifier {
+        name: name.trim().to_owned(),
+        pos: locator.next(name),
+    };
+    let ports = match instance.get("boundary") {
+        Some(boundary) => {
+            locator.next("boundary");
+            locator.next("ports");
+            boundary_ports(boundary, |name| locator.next(name))
+        }
+        None => Vec::new(),
+    };
+    Some(Module {
+        ident,
+        parameters: Vec::new(),
+        ports,
+        end: None,
+    })
+}
+
+/// The ports of a boundary description, each placed at the position given by `pos`
+fn boundary_ports(boundary: &Value, mut pos: impl FnMut(&str) -> Position) -> Vec<Port> {
+    let Some(Value::Object(ports)) = boundary.get("ports") else {
+        return Vec::new();
+    };
+    ports
+        .iter()
+        .map(|(name, port)| {
+            let ident = Identifier {
+                name: name.trim().to_owned(),
+                pos: pos(name),
+            };
+            // Each port is a list with a single object
+            let port = match port {
+                Value::Array(elements) => elements.first().unwrap_or(port),
+                _ => port,
+            };
+            let direction = port
+                .get("direction")
+                .and_then(Value::as_str)
+                .and_then(direction);
+            let bounds = port
+                .get("size_left")
+                .and_then(json_integer)
+                .zip(port.get("size_right").and_then(json_integer));
+            Port {
+                ident,
+                direction,
+                width: bounds.map_or(Width::Bit, |bounds| Width::Vector(Some(bounds))),
+            }
+        })
+        .collect()
+}
+
+fn direction(value: &str) -> Option<Direction> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "in" => Some(Direction::Input),
+        "out" => Some(Direction::Output),
+        "inout" => Some(Direction::Inout),
+        _ => None,
+    }
+}
+
+/// The cores of an IP-XACT document, i.e. an IP-XACT component or the component instances of an `.xci` file
+fn ip_xact_modules(root: &Element) -> Vec<Module> {
+    match root.name.as_str() {
+        "component" => ip_xact_component(root).into_iter().collect(),
+        "design" => root
+            .child("componentInstances")
+            .map(|instances| {
+                instances
+                    .children("componentInstance")
+                    .filter_map(xci_instance)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+fn ip_xact_component(component: &Element) -> Option<Module> {
+    let ports = component
+        .child("model")
+        .and_then(|model| model.child("ports"))
+        .map(|ports| ports.children("port").filter_map(ip_xact_port).collect())
+        .unwrap_or_default();
+    Some(Module {
+        ident: component.child("name")?.text.ident(),
+        parameters: Vec::new(),
+        ports,
+        end: None,
+    })
+}
+
+fn ip_xact_port(port: &Element) -> Option<Port> {
+    // Transactional ports have no wire
+    let wire = port.child("wire")?;
+    let vector = wire
+        .child("vector")
+        .or_else(|| wire.child("vectors")?.child("vector"));
+    let bound = |name: &str| vector?.child(name)?.text.value.trim().parse::<i64>().ok();
+    let width = match vector {
+        Some(_) => Width::Vector(bound("left").zip(bound("right"))),
+        None => Width::Bit,
+    };
+    Some(Port {
+        ident: port.child("name")?.text.ident(),
+        direction: direction(&wire.child("direction")?.text.value),
+        width,
+    })
+}
+
+/// An instance of an `.xci` file in the IP-XACT format.
+/// The ports are read from the boundary description in JSON and placed at the name of the instance.
+fn xci_instance(instance: &Element) -> Option<Module> {
+    let ident = instance.child("instanceName")?.text.ident();
+    let ports = instance
+        .descendant("boundaryDescriptionJSON")
+        .and_then(|boundary| serde_json::from_str::<Value>(&boundary.text.value).ok())
+        .map(|boundary| {
+            let boundary = boundary.get("boundary").unwrap_or(&boundary);
+            boundary_ports(boundary, |_| ident.pos)
+        })
+        .unwrap_or_default();
+    Some(Module {
+        ident,
+        parameters: Vec::new(),
+        ports,
+        end: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::data::NullMessages;
+
+    #[test]
+    fn reads_ports_of_json_xci() {
+        let xci = "\
+{
+  \"schema\": \"xilinx.com:schema:json_instance:1.0\",
+  \"ip_inst\": {
+    \"xci_name\": \"clk_wiz_0\",
+    \"component_reference\": \"xilinx.com:ip:clk_wiz:6.0\",
+    \"boundary\": {
+      \"ports\": {
+        \"clk_out1\": [ { \"direction\": \"out\" } ],
+        \"phase\": [ { \"direction\": \"in\", \"size_left\": \"7\", \"size_right\": \"0\" } ],
+        \"clk_in1\": [ { \"direction\": \"in\" } ]
+      }
+    }
+  }
+}
+";
+        let module = xci_module(xci, &serde_json::from_str(xci).unwrap()).unwrap();
+        assert_eq!(
+            module.ident,
+            Identifier {
+                name: "clk_wiz_0".to_owned(),
+                pos: Position::new(3, 17)
+            }
+        );
+        assert_eq!(
+            module
+                .ports
+                .iter()
+                .map(|port| (port.ident.name.as_str(), port.direction, port.width))
+                .collect::<Vec<_>>(),
+            vec![
+                ("clk_out1", Some(Direction::Output), Width::Bit),
+                ("phase", Some(Direction::Input), Width::Vector(Some((7, 0)))),
+                ("clk_in1", Some(Direction::Input), Width::Bit),
+            ]
+        );
+        assert_eq!(module.ports[1].ident.pos, Position::new(8, 9));
+    }
+
+    #[test]
+    fn reads_ports_of_ip_xact() {
+        let component = "\
+<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<spirit:component xmlns:spirit=\"http://www.spiritconsortium.org/XMLSchema/SPIRIT/1685-2009\">
+  <spirit:vendor>xilinx.com</spirit:vendor>
+  <spirit:name>fifo_gen</spirit:name>
+  <spirit:model>
+    <spirit:ports>
+      <spirit:port>
+        <spirit:name>din</spirit:name>
+        <spirit:wire>
+          <spirit:direction>in</spirit:direction>
+          <spirit:vector>
+            <spirit:left spirit:format=\"long\">31</spirit:left>
+            <spirit:right spirit:format=\"long\">0</spirit:right>
+          </spirit:vector>
+        </spirit:wire>
+      </spirit:port>
+      <!-- <spirit:port/> -->
+      <spirit:port>
+        <spirit:name>full</spirit:name>
+        <spirit:wire>
+          <spirit:direction>out</spirit:direction>
+        </spirit:wire>
+      </spirit:port>
+    </spirit:ports>
+  </spirit:model>
+</spirit:component>
+";
+        let modules = ip_xact_modules(&xml_element(&mut Scanner::new(component)).unwrap());
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0].ident.name, "fifo_gen");
+        assert_eq!(modules[0].ident.pos, Position::new(3, 15));
+        assert_eq!(
+            modules[0]
+                .ports
+                .iter()
+                .map(|port| (port.ident.name.as_str(), port.direction, port.width))
+                .collect::<Vec<_>>(),
+            vec![
+                ("din", Some(Direction::Input), Width::Vector(Some((31, 0)))),
+                ("full", Some(Direction::Output), Width::Bit),
+            ]
+        );
+
+        let xci = "\
+<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<spirit:design xmlns:spirit=\"http://www.spiritconsortium.org/XMLSchema/SPIRIT/1685-2009\">
+  <spirit:componentInstances>
+    <spirit:componentInstance>
+      <spirit:instanceName>ila_0</spirit:instanceName>
+      <spirit:vendorExtensions>
+        <xilinx:componentInstanceExtensions>
+          <xilinx:boundaryDescriptionJSON>{&quot;boundary&quot;:{&quot;ports&quot;:{&quot;clk&quot;:[{&quot;direction&quot;:&quot;in&quot;}]}}}</xilinx:boundaryDescriptionJSON>
+        </xilinx:componentInstanceExtensions>
+      </spirit:vendorExtensions>
+    </spirit:componentInstance>
+  </spirit:componentInstances>
+</spirit:design>
+";
+        let modules = ip_xact_modules(&xml_element(&mut Scanner::new(xci)).unwrap());
+        assert_eq!(
+            modules,
+            vec![Module {
+                ident: Identifier {
+                    name: "ila_0".to_owned(),
+                    pos: Position::new(4, 27)
+                },
+                parameters: Vec::new(),
+                ports: vec![Port {
+                    ident: Identifier {
+                        name: "clk".to_owned(),
+                        pos: Position::new(4, 27)
+                    },
+                    direction: Some(Direction::Input),
+                    width: Width::Bit,
+                }],
+                end: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn instantiation_of_ip_core_is_checked() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            &black_box_entities(
+                "{\"ip_inst\": {\"xci_name\": \"dcm\", \"boundary\": {\"ports\": {\
+                 \"clk_in\": [{\"direction\": \"in\"}], \
+                 \"clk_out\": [{\"direction\": \"out\", \"size_left\": \"1\", \"size_right\": \"0\"}]}}}}",
+                VHDLStandard::VHDL2008,
+            ),
+        );
+        builder.code(
+            "libname",
+            "\
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity top is
+end entity;
+
+architecture a of top is
+  signal clk : std_logic;
+  signal clks : std_logic_vector(1 downto 0);
+begin
+  inst: entity work.dcm port map (clk_in => clk, clk_out => clks);
+end architecture;
+",
+        );
+        builder.add_std_logic_1164();
+        let (_, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+    }
+
+    #[test]
+    fn lists_files_of_qip() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = tempdir.path();
+        std::fs::create_dir(root.join("pll")).unwrap();
+        for name in ["pll.vhd", "pll/pll_0002.v"] {
+            std::fs::write(root.join(name), "").unwrap();
+        }
+        std::fs::write(
+            root.join("pll.qip"),
+            "\
+set_global_assignment -name IP_TOOL_NAME \"altera_pll\"
+set_global_assignment -library \"pll\" -name VHDL_FILE [file join $::quartus(qip_path) \"pll.vhd\"]
+set_global_assignment -library \"pll\" -name VERILOG_FILE [file join $::quartus(qip_path) \"pll/pll_0002.v\"]
+set_global_assignment -name QIP_FILE [file join $::quartus(qip_path) \"pll/pll_0002.qip\"]
+",
+        )
+        .unwrap();
+
+        let mut messages = Vec::new();
+        assert_eq!(
+            qip_file_names(&root.join("pll.qip"), &mut messages),
+            vec![root.join("pll.vhd"), root.join("pll").join("pll_0002.v")]
+        );
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].message.contains("pll_0002.qip"));
+
+        assert_eq!(
+            qip_file_names(&root.join("missing.qip"), &mut NullMessages),
+            Vec::<PathBuf>::new()
+        );
+    }
+}
@@ -13,32 +13,70 @@ extern crate self as vhdl_lang;
 pub mod ast;
 #[macro_use]
 mod analysis;
+mod call_hierarchy;
 mod config;
+mod cross_check;
 mod data;
+mod documentation;
+mod entity_interface;
+mod external_checker;
+mod folding_range;
+mod formatting;
+mod hierarchy;
+mod ip_core;
+mod linked_editing;
 mod lint;
+mod message_catalog;
 mod named_entity;
 mod project;
+mod refactor;
 mod syntax;
+mod verilog;
 
 mod completion;
+mod reference_kind;
+mod semantic_tokens;
+mod signature_help;
+mod snippet;
 mod standard;
+mod statistics;
 
-pub use crate::config::Config;
+pub use crate::config::{Config, Deprecation, RegisterConventions, SynthesisPragmas, Target};
+pub use crate::cross_check::{GhdlCrossCheck, GhdlCrossCheckRun, CROSS_CHECK_SOURCE};
 pub use crate::data::{
-    Diagnostic, Latin1String, Message, MessageHandler, MessagePrinter, MessageType,
+    Diagnostic, ErrorCode, Latin1String, Message, MessageHandler, MessagePrinter, MessageType,
     NullDiagnostics, NullMessages, Position, Range, Severity, SeverityMap, Source, SrcPos,
 };
 
 pub use crate::analysis::EntHierarchy;
+pub use crate::call_hierarchy::Calls;
+pub use crate::entity_interface::{interfaces_to_json, EntityInterface, InterfaceElement};
+pub use crate::external_checker::{ExternalChecker, ExternalCheckerRun, ExternalDiagnostic};
+pub use crate::folding_range::{FoldingRange, FoldingRangeKind};
+pub use crate::formatting::{
+    FormatterConfig, KeywordCase, LineRange, VHDLFormatter, FMT_OFF_COMMENT, FMT_ON_COMMENT,
+    FORMATTER_CONFIG_FILE_NAME,
+};
+pub use crate::hierarchy::{instances_to_json, Instance, Instantiation};
+pub use crate::message_catalog::{MessageCatalog, DEFAULT_LOCALE};
 pub use crate::named_entity::{
     AnyEnt, AnyEntKind, Concurrent, Design, EntRef, EntityId, HasEntityId, InterfaceEnt, Object,
     Overloaded, Reference, Related, Sequential, Type,
 };
 
 pub use crate::project::{Project, SourceFile};
+pub use crate::refactor::{
+    align_lines, apply_edits, unified_diff, CodeAction, EntityStub, FixRule, MovedUnit, Testbench,
+    TextEdit, ALLOW_UNUSED_COMMENT, DEFAULT_FILE_NAME_SCHEME,
+};
 pub use crate::syntax::{
     kind_str, HasTokenSpan, ParserResult, Token, TokenAccess, TokenId, TokenSpan, VHDLParser,
 };
 
 pub use completion::{list_completion_options, CompletionItem};
+pub use reference_kind::ReferenceKind;
+pub use semantic_tokens::{SemanticToken, SemanticTokenKind};
+pub use signature_help::{CallSignature, SignatureHelp};
+pub use snippet::{Snippet, SnippetContext};
 pub use standard::VHDLStandard;
+pub use statistics::{DesignStatistics, EntityStatistics, LibraryStatistics};
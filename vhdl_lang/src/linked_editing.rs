@@ -0,0 +1,119 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! The name of a declaration and the repeated name after its `end`,
+//! which an editor can change simultaneously
+
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchResult, SearchState, Searcher};
+use crate::data::{Position, Source, SrcPos};
+use crate::named_entity::HasEntityId;
+use crate::syntax::TokenAccess;
+
+/// The identifier and the end label of the declaration at the cursor.
+/// Declarations without an end label have no linked ranges.
+pub(crate) fn linked_editing_ranges(
+    root: &DesignRoot,
+    source: &Source,
+    cursor: Position,
+) -> Option<Vec<SrcPos>> {
+    let mut searcher = LinkedEditingSearcher {
+        root,
+        cursor,
+        ranges: None,
+    };
+    let _ = root.search_source(source, &mut searcher);
+    searcher.ranges
+}
+
+struct LinkedEditingSearcher<'a> {
+    root: &'a DesignRoot,
+    cursor: Position,
+    ranges: Option<Vec<SrcPos>>,
+}
+
+impl<'a> Searcher for LinkedEditingSearcher<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        let end_pos = match decl {
+            FoundDeclaration::ConcurrentStatement(statement) => {
+                statement.statement.item.end_label_pos().cloned()
+            }
+            FoundDeclaration::SequentialStatement(statement) => {
+                statement.statement.item.end_label_pos().cloned()
+            }
+            _ => decl.end_ident_pos().map(|id| ctx.get_pos(id).clone()),
+        };
+        let Some(end_pos) = end_pos else {
+            return NotFinished;
+        };
+        let Some(decl_pos) = decl
+            .ent_id()
+            .and_then(|id| self.root.get_ent(id).decl_pos())
+        else {
+            return NotFinished;
+        };
+
+        let ranges = [decl_pos.clone(), end_pos];
+        if ranges
+            .iter()
+            .any(|pos| pos.start() <= self.cursor && self.cursor <= pos.end())
+        {
+            self.ranges = Some(ranges.into());
+            return SearchState::Finished(SearchResult::Found);
+        }
+        NotFinished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+
+    #[test]
+    fn links_names_of_declarations_and_end_labels() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity top is
+end entity top;
+
+architecture rtl of top is
+  signal s : bit;
+begin
+  main: process
+  begin
+    s <= '1';
+    wait;
+  end process main;
+
+  s <= '0';
+end architecture rtl;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let ranges_at = |name: &str, occurrence: usize| {
+            linked_editing_ranges(&root, code.source(), code.s(name, occurrence).start())
+        };
+
+        let entity = Some(vec![code.s("top", 1).pos(), code.s("top", 2).pos()]);
+        assert_eq!(ranges_at("top", 1), entity);
+        assert_eq!(ranges_at("top", 2), entity);
+        assert_eq!(
+            ranges_at("rtl", 2),
+            Some(vec![code.s("rtl", 1).pos(), code.s("rtl", 2).pos()])
+        );
+        assert_eq!(
+            ranges_at("main", 1),
+            Some(vec![code.s("main", 1).pos(), code.s("main", 2).pos()])
+        );
+        // The entity name of the architecture is a reference
+        assert_eq!(ranges_at("top", 3), None);
+        assert_eq!(ranges_at("s <= '0'", 1), None);
+    }
+}
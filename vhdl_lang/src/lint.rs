@@ -5,3 +5,4 @@
 // Copyright (c) 2022, Olof Kraigher olof.kraigher@gmail.com
 
 pub mod dead_code;
+pub mod deprecated;
@@ -10,11 +10,13 @@ use crate::analysis::LockedUnit;
 use crate::ast::search::Search;
 use crate::ast::search::SearchState;
 use crate::ast::search::Searcher;
+use crate::ast::Mode;
 use crate::ast::UnitId;
 use crate::data::error_codes::ErrorCode;
 use crate::data::DiagnosticHandler;
 use crate::data::Symbol;
-use crate::named_entity::{HasEntityId, Reference, Related};
+use crate::named_entity::{HasEntityId, InterfaceMode, ObjectInterface, Reference, Related};
+use crate::reference_kind::{classify_references, ReferenceKind};
 use crate::syntax::TokenAccess;
 use crate::AnyEntKind;
 use crate::Config;
@@ -31,6 +33,8 @@ struct DeadCodeSearcher<'a> {
     root: &'a DesignRoot,
     references: FnvHashSet<EntRef<'a>>,
     declarations: FnvHashSet<EntRef<'a>>,
+    // The references to output ports, to find the ports that are never driven
+    output_port_references: Vec<(SrcPos, EntRef<'a>)>,
 }
 
 impl<'a> DeadCodeSearcher<'a> {
@@ -39,6 +43,7 @@ impl<'a> DeadCodeSearcher<'a> {
             root,
             references: Default::default(),
             declarations: Default::default(),
+            output_port_references: Default::default(),
         }
     }
 }
@@ -47,12 +52,15 @@ impl<'a> Searcher for DeadCodeSearcher<'a> {
     fn search_pos_with_ref(
         &mut self,
         _ctx: &dyn TokenAccess,
-        _: &SrcPos,
+        pos: &SrcPos,
         reference: &Reference,
     ) -> SearchState {
         if let Some(id) = reference.get() {
             let ent = self.root.get_ent(id);
             self.references.insert(ent);
+            if is_output_port(ent) {
+                self.output_port_references.push((pos.clone(), ent));
+            }
 
             if let Related::DeclaredBy(other) = ent.related {
                 self.references.insert(other);
@@ -93,6 +101,30 @@ fn is_interface(ent: EntRef) -> bool {
         )
 }
 
+fn is_port(ent: EntRef) -> bool {
+    matches!(
+        ent.kind(),
+        AnyEntKind::Object(o) if matches!(o.iface, Some(ObjectInterface::Port(..))))
+}
+
+/// A port of mode `out`, `inout` or `buffer` of an entity
+fn is_output_port(ent: EntRef) -> bool {
+    let AnyEntKind::Object(object) = ent.kind() else {
+        return false;
+    };
+    let is_output = matches!(
+        object.mode(),
+        Some(InterfaceMode::Simple(
+            Mode::Out | Mode::InOut | Mode::Buffer
+        ))
+    );
+    let is_entity_port = matches!(object.iface, Some(ObjectInterface::Port(..)))
+        && ent
+            .parent
+            .is_some_and(|parent| matches!(parent.kind(), AnyEntKind::Design(Design::Entity(..))));
+    is_output && is_entity_port
+}
+
 fn can_be_locally_unused(ent: EntRef) -> bool {
     if let Related::DeclaredBy(related) = ent.related {
         if !can_be_locally_unused(related) {
@@ -163,7 +195,7 @@ fn can_be_locally_unused(ent: EntRef) -> bool {
 }
 
 /// Find *local* unused declarations
-fn find_unused_declarations<'a>(
+pub(crate) fn find_unused_declarations<'a>(
     root: &'a DesignRoot,
     lib: &Library,
     primary_unit_name: &Symbol,
@@ -195,6 +227,60 @@ fn find_unused_declarations<'a>(
         .collect()
 }
 
+/// Find the output ports of an entity that are used but that none of its architectures assign,
+/// associate with a driving formal or declare an alias of.
+/// Output ports that are not used at all are unused declarations.
+pub(crate) fn find_undriven_ports<'a>(
+    root: &'a DesignRoot,
+    lib: &Library,
+    primary_unit_name: &Symbol,
+) -> FnvHashSet<EntRef<'a>> {
+    let mut searcher = DeadCodeSearcher::new(root);
+
+    if let Some(unit) = lib.primary_unit(primary_unit_name) {
+        search_unit(unit, &mut searcher);
+    }
+
+    // The ports of an entity without architecture are driven elsewhere, e.g. by a black box
+    let mut has_architecture = false;
+    for unit in lib.secondary_units(primary_unit_name) {
+        search_unit(unit, &mut searcher);
+        has_architecture = true;
+    }
+    if !has_architecture {
+        return FnvHashSet::default();
+    }
+
+    let kinds = classify_references(
+        root,
+        searcher
+            .output_port_references
+            .iter()
+            .map(|(pos, _)| pos.clone())
+            .collect(),
+    );
+    let mut driven: FnvHashSet<EntRef> = searcher
+        .output_port_references
+        .iter()
+        .zip(kinds)
+        .filter(|(_, (_, kind))| *kind == ReferenceKind::Write)
+        .map(|((_, ent), _)| *ent)
+        .collect();
+    for ent in searcher.declarations.iter() {
+        if let AnyEntKind::ObjectAlias { base_object, .. } = ent.kind() {
+            driven.insert(base_object.ent);
+        }
+    }
+
+    searcher
+        .declarations
+        .into_iter()
+        .filter(|ent| {
+            is_output_port(ent) && searcher.references.contains(ent) && !driven.contains(ent)
+        })
+        .collect()
+}
+
 /// Use a struct to keep state of units that do not need to be re-scanned
 #[derive(Default)]
 pub(crate) struct UnusedDeclarationsLinter {
@@ -231,15 +317,36 @@ impl UnusedDeclarationsLinter {
 
             if let Some(library) = root.get_lib(unit.library_name()) {
                 self.diagnostics.entry(key).or_insert_with(|| {
+                    let undriven = find_undriven_ports(root, library, unit.primary_name())
+                        .into_iter()
+                        .filter_map(|ent| {
+                            Some(Diagnostic::new(
+                                ent.decl_pos()?,
+                                format!("Port '{}' is never driven", ent.designator()),
+                                ErrorCode::UndrivenPort,
+                            ))
+                        });
+                    // The ports of an entity without architecture are used elsewhere
+                    let has_architecture = library
+                        .secondary_units(unit.primary_name())
+                        .next()
+                        .is_some();
                     find_unused_declarations(root, library, unit.primary_name())
                         .into_iter()
+                        .filter(|ent| has_architecture || !is_port(ent))
                         .filter_map(|ent| {
+                            let code = if is_port(ent) {
+                                ErrorCode::UnusedPort
+                            } else {
+                                ErrorCode::Unused
+                            };
                             Some(Diagnostic::new(
                                 ent.decl_pos()?,
                                 format!("Unused declaration of {}", ent.describe()),
-                                ErrorCode::Unused,
+                                code,
                             ))
                         })
+                        .chain(undriven)
                         .collect_vec()
                 });
             }
@@ -259,8 +366,12 @@ impl UnusedDeclarationsLinter {
 mod tests {
     use super::*;
     use crate::analysis::tests::LibraryBuilder;
+    use crate::ast::HasUnitId;
+    use crate::data::error_codes::SeverityMap;
+    use crate::data::Severity;
     use crate::syntax::test::check_no_diagnostics;
     use crate::syntax::test::Code;
+    use std::path::Path;
 
     fn get_ent(root: &DesignRoot, code: Code) -> EntRef {
         root.search_reference(code.source(), code.start()).unwrap()
@@ -352,6 +463,183 @@ end architecture;",
         )
     }
 
+    #[test]
+    fn unused_ports_have_their_own_error_code() {
+        let mut builder = LibraryBuilder::new();
+
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+  port (
+    unused_port : in boolean
+  );
+end entity;
+
+architecture a of ent is
+  signal unused_signal : boolean;
+begin
+end architecture;",
+        );
+
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let lib = root.get_lib(&root.symbol_utf8("libname")).unwrap();
+        let unit_id = lib
+            .primary_unit(&root.symbol_utf8("ent"))
+            .unwrap()
+            .unit_id()
+            .clone();
+        let config = Config::from_str("[libraries]\nlibname.files = []", Path::new("")).unwrap();
+
+        let mut diagnostics = Vec::new();
+        UnusedDeclarationsLinter::default().lint(&root, &config, &[unit_id], &mut diagnostics);
+        diagnostics.sort_by_key(|diagnostic| diagnostic.pos.start());
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|diagnostic| (diagnostic.pos.clone(), diagnostic.code))
+                .collect_vec(),
+            vec![
+                (code.s1("unused_port").pos(), ErrorCode::UnusedPort),
+                (code.s1("unused_signal").pos(), ErrorCode::Unused),
+            ]
+        );
+    }
+
+    #[test]
+    fn unused_ports_are_warnings_by_default() {
+        let mut builder = LibraryBuilder::new();
+
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+  port (
+    unread : in boolean;
+    unassigned : out boolean
+  );
+end entity;
+
+architecture a of ent is
+begin
+end architecture;
+
+entity black_box is
+  port (
+    unused : in boolean
+  );
+end entity;",
+        );
+
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let lib = root.get_lib(&root.symbol_utf8("libname")).unwrap();
+        let unit_ids = ["ent", "black_box"]
+            .iter()
+            .map(|name| {
+                lib.primary_unit(&root.symbol_utf8(name))
+                    .unwrap()
+                    .unit_id()
+                    .clone()
+            })
+            .collect_vec();
+        let config = Config::from_str("[libraries]\nlibname.files = []", Path::new("")).unwrap();
+
+        let mut diagnostics = Vec::new();
+        UnusedDeclarationsLinter::default().lint(&root, &config, &unit_ids, &mut diagnostics);
+        diagnostics.sort_by_key(|diagnostic| diagnostic.pos.start());
+        let severities = SeverityMap::default();
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|diagnostic| (diagnostic.pos.clone(), severities[diagnostic.code]))
+                .collect_vec(),
+            vec![
+                (code.s1("unread").pos(), Some(Severity::Warning)),
+                (code.s1("unassigned").pos(), Some(Severity::Warning)),
+            ]
+        );
+    }
+
+    #[test]
+    fn unused_generics() {
+        let mut builder = LibraryBuilder::new();
+
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+  generic (
+    used : natural;
+    unused : natural
+  );
+end entity;
+
+architecture a of ent is
+  signal s : natural := used;
+begin
+   s <= s + 1;
+end architecture;",
+        );
+
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let lib = root.get_lib(&root.symbol_utf8("libname")).unwrap();
+
+        check_unused(
+            find_unused_declarations(&root, lib, &root.symbol_utf8("ent")),
+            FnvHashSet::from_iter(vec![get_ent(&root, code.s1("unused"))]),
+        )
+    }
+
+    #[test]
+    fn output_ports_that_are_used_but_never_driven() {
+        let mut builder = LibraryBuilder::new();
+
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+  port (
+    a : in bit;
+    assigned : out bit;
+    read_only : out bit;
+    unused : out bit;
+    by_procedure : out bit;
+    by_alias : out bit
+  );
+end entity;
+
+architecture a of ent is
+  procedure drive(signal o : out bit) is
+  begin
+    o <= '1';
+  end procedure;
+
+  alias alias_of_port : bit is by_alias;
+begin
+  assigned <= a;
+  assert read_only = '0';
+  drive(by_procedure);
+  alias_of_port <= '0';
+end architecture;",
+        );
+
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let lib = root.get_lib(&root.symbol_utf8("libname")).unwrap();
+
+        check_unused(
+            find_undriven_ports(&root, lib, &root.symbol_utf8("ent")),
+            FnvHashSet::from_iter(vec![get_ent(&root, code.s1("read_only"))]),
+        )
+    }
+
     /// Since the focus of the unused declaration lint is local declarations
     /// we have to assume that a package header declaration could be used somewhere else.
     #[test]
@@ -0,0 +1,257 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+use crate::analysis::DesignRoot;
+use crate::analysis::Library;
+use crate::analysis::LockedUnit;
+use crate::ast::search::Search;
+use crate::ast::search::SearchState;
+use crate::ast::search::Searcher;
+use crate::ast::UnitId;
+use crate::config::Deprecation;
+use crate::data::error_codes::ErrorCode;
+use crate::data::DiagnosticHandler;
+use crate::data::Symbol;
+use crate::documentation::declaration_comments;
+use crate::named_entity::Reference;
+use crate::syntax::TokenAccess;
+use crate::Config;
+use crate::Diagnostic;
+use crate::EntRef;
+use crate::EntityId;
+use crate::SrcPos;
+use fnv::FnvHashMap;
+
+struct ReferenceSearcher<'a> {
+    root: &'a DesignRoot,
+    references: Vec<(SrcPos, EntRef<'a>)>,
+}
+
+impl<'a> Searcher for ReferenceSearcher<'a> {
+    fn search_pos_with_ref(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        reference: &Reference,
+    ) -> SearchState {
+        if let Some(id) = reference.get() {
+            self.references.push((pos.clone(), self.root.get_ent(id)));
+        }
+        SearchState::NotFinished
+    }
+}
+
+fn search_unit(unit: &LockedUnit, searcher: &mut impl Searcher) {
+    let _ = unit.unit.expect_analyzed().search(&unit.tokens, searcher);
+}
+
+/// How a declaration is marked as deprecated
+enum Marker {
+    /// The position of the designator of the attribute specification
+    Attribute(SrcPos),
+    Comment,
+}
+
+fn deprecation_marker(root: &DesignRoot, deprecation: &Deprecation, ent: EntRef) -> Option<Marker> {
+    if let Some((pos, _)) = ent
+        .attrs
+        .iter()
+        .find(|(name, _)| {
+            name.name_utf8()
+                .eq_ignore_ascii_case(&deprecation.attribute)
+        })
+        .map(|(_, attr)| attr)
+    {
+        return Some(Marker::Attribute(pos.clone()));
+    }
+
+    let comments = declaration_comments(root, ent)?;
+    let marker = deprecation.comment_marker.to_lowercase();
+    let is_marked = comments
+        .leading
+        .iter()
+        .chain(comments.trailing)
+        .any(|comment| comment.value.to_lowercase().contains(&marker));
+    is_marked.then_some(Marker::Comment)
+}
+
+/// Whether the declaration of `ent` is marked as deprecated
+pub(crate) fn is_deprecated(root: &DesignRoot, deprecation: &Deprecation, ent: EntRef) -> bool {
+    deprecation_marker(root, deprecation, ent).is_some()
+}
+
+/// Find the uses of deprecated declarations within the units of a primary unit
+pub(crate) fn find_deprecated_uses(
+    root: &DesignRoot,
+    deprecation: &Deprecation,
+    lib: &Library,
+    primary_unit_name: &Symbol,
+) -> Vec<Diagnostic> {
+    let mut searcher = ReferenceSearcher {
+        root,
+        references: Vec::new(),
+    };
+
+    if let Some(unit) = lib.primary_unit(primary_unit_name) {
+        search_unit(unit, &mut searcher);
+    }
+
+    for unit in lib.secondary_units(primary_unit_name) {
+        search_unit(unit, &mut searcher);
+    }
+
+    let mut markers: FnvHashMap<EntityId, Option<Marker>> = FnvHashMap::default();
+    let mut diagnostics = Vec::new();
+    for (pos, ent) in searcher.references {
+        let marker = markers
+            .entry(ent.id())
+            .or_insert_with(|| deprecation_marker(root, deprecation, ent));
+        match marker {
+            None => continue,
+            // The designator of the attribute specification is not a use
+            Some(Marker::Attribute(spec_pos)) if *spec_pos == pos => continue,
+            Some(_) => {}
+        }
+        if ent.decl_pos() == Some(&pos) {
+            continue;
+        }
+        diagnostics.push(Diagnostic::new(
+            pos,
+            format!("Use of deprecated {}", ent.describe()),
+            ErrorCode::Deprecated,
+        ));
+    }
+    diagnostics
+}
+
+/// Use a struct to keep state of units that do not need to be re-scanned
+#[derive(Default)]
+pub(crate) struct DeprecatedUsesLinter {
+    // library name, primary name
+    diagnostics: FnvHashMap<(Symbol, Symbol), Vec<Diagnostic>>,
+}
+
+impl DeprecatedUsesLinter {
+    pub fn lint(
+        &mut self,
+        root: &DesignRoot,
+        config: &Config,
+        analyzed_units: &[UnitId],
+        diagnostics: &mut dyn DiagnosticHandler,
+    ) {
+        // Prune diagnostics that need to be re-computed
+        for unit in analyzed_units {
+            let key = (unit.library_name().clone(), unit.primary_name().clone());
+            self.diagnostics.remove(&key);
+        }
+
+        // Prune diagnostics for units that no longer exist
+        self.diagnostics.retain(|(library_name, primary_name), _| {
+            if let Some(library) = root.get_lib(library_name) {
+                if library.primary_unit(primary_name).is_some() {
+                    return true;
+                }
+            }
+            false
+        });
+
+        for unit in analyzed_units {
+            let key = (unit.library_name().clone(), unit.primary_name().clone());
+
+            if let Some(library) = root.get_lib(unit.library_name()) {
+                self.diagnostics.entry(key).or_insert_with(|| {
+                    find_deprecated_uses(root, config.deprecation(), library, unit.primary_name())
+                });
+            }
+        }
+
+        for ((library_name, _), unit_diagnostics) in self.diagnostics.iter() {
+            if let Some(library_config) = config.get_library(&library_name.name_utf8()) {
+                if !library_config.is_third_party {
+                    diagnostics.append(unit_diagnostics.iter().cloned());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+    use crate::ast::HasUnitId;
+    use crate::syntax::test::check_no_diagnostics;
+
+    fn deprecated_uses(builder: LibraryBuilder) -> Vec<Diagnostic> {
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+        let library = root.get_lib(&root.symbol_utf8("libname")).unwrap();
+        let mut diagnostics = Vec::new();
+        for unit in library.primary_units() {
+            diagnostics.extend(find_deprecated_uses(
+                &root,
+                &Deprecation::default(),
+                library,
+                unit.unit_id().primary_name(),
+            ));
+        }
+        diagnostics
+    }
+
+    #[test]
+    fn uses_of_declaration_with_deprecated_attribute() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+package pkg is
+  attribute deprecated : string;
+  constant old_width : natural := 8;
+  attribute deprecated of old_width : constant is \"Use width\";
+  constant width : natural := old_width;
+end package;",
+        );
+        assert_eq!(
+            deprecated_uses(builder),
+            vec![Diagnostic::new(
+                code.s("old_width", 3),
+                "Use of deprecated constant 'old_width'",
+                ErrorCode::Deprecated,
+            )]
+        );
+    }
+
+    #[test]
+    fn uses_of_declaration_with_deprecated_comment() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+package pkg is
+  -- @deprecated Use new_fn
+  function old_fn return natural;
+  constant width : natural := 8; -- @Deprecated
+  constant depth : natural := 16;
+end package;
+
+package body pkg is
+  function old_fn return natural is
+  begin
+    return width + depth;
+  end function;
+end package body;",
+        );
+        let diagnostics = deprecated_uses(builder);
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic::new(
+                code.s("width", 2),
+                "Use of deprecated constant 'width'",
+                ErrorCode::Deprecated,
+            )]
+        );
+    }
+}
@@ -4,15 +4,24 @@
 //
 // Copyright (c) 2018, Olof Kraigher olof.kraigher@gmail.com
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use itertools::Itertools;
-use std::path::Path;
-use vhdl_lang::{Config, Diagnostic, MessagePrinter, Project, Severity, SeverityMap};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use vhdl_lang::{
+    apply_edits, instances_to_json, interfaces_to_json, unified_diff, Config, Diagnostic, FixRule,
+    FormatterConfig, LineRange, MessagePrinter, Position, Project, ReferenceKind, Severity,
+    SeverityMap, Source, TextEdit, VHDLFormatter, VHDLParser, VHDLStandard,
+};
 
 /// Run vhdl analysis
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// The number of threads to use. By default, the maximum is selected based on process cores
     #[arg(short = 'p', long)]
     num_threads: Option<usize>,
@@ -23,12 +32,118 @@ struct Args {
     libraries: Option<String>,
 
     /// Config file in TOML format containing libraries and settings
-    #[arg(short, long)]
-    config: String,
+    #[arg(short, long, required = true)]
+    config: Option<String>,
+
+    /// Apply safe automatic fixes to the source files of all libraries that are not
+    /// third party libraries. By default, unused signals, variables, constants and use clauses
+    /// are removed, missing end labels are added and trailing whitespace is removed
+    #[arg(long)]
+    fix: bool,
+
+    /// The comma separated fix rules to apply instead of the default rules:
+    /// unused, end_labels, whitespace and named_association
+    #[arg(long, value_delimiter = ',', requires = "fix")]
+    fix_rules: Vec<String>,
+
+    /// Print the fixes as a diff instead of writing the source files
+    #[arg(long, requires = "fix")]
+    diff: bool,
+
+    /// Print the instance hierarchy of the design as JSON instead of the diagnostics
+    #[arg(long)]
+    hierarchy: bool,
+
+    /// The top-level entity of the instance hierarchy as library.entity.
+    /// By default, the top-level entity of the target is used, or every entity
+    /// that is not instantiated is a top-level entity
+    #[arg(long, requires = "hierarchy")]
+    top: Option<String>,
+
+    /// The target of the config whose libraries and files are analyzed,
+    /// instead of the target selected by the config
+    #[arg(long)]
+    target: Option<String>,
+
+    /// Print the generics and ports of all entities as JSON instead of the diagnostics
+    #[arg(long, conflicts_with = "hierarchy")]
+    interfaces: bool,
+
+    /// Print statistics of the design instead of the diagnostics: the design units,
+    /// lines of code and processes of each library, the instances of each entity
+    /// and the deepest instance hierarchy
+    #[arg(long, conflicts_with_all = ["hierarchy", "interfaces"])]
+    statistics: bool,
+
+    /// Print the statistics as JSON instead of as tables
+    #[arg(long, requires = "statistics")]
+    json: bool,
+
+    /// Print the references of the declaration at FILE:LINE:COLUMN instead of the diagnostics,
+    /// each with its kind: declaration, write, port_association, instantiation or read.
+    /// The line and column start at 1
+    #[arg(
+        long,
+        value_name = "FILE:LINE:COLUMN",
+        conflicts_with_all = ["hierarchy", "interfaces", "statistics"]
+    )]
+    references: Option<String>,
+
+    /// The comma separated kinds of the references to print instead of all references,
+    /// e.g. write to find the statements that drive a signal
+    #[arg(long, value_delimiter = ',', requires = "references")]
+    reference_kinds: Vec<String>,
+
+    /// Create a testbench of the entity given as library.entity instead of printing
+    /// the diagnostics. The testbench is written next to the file of the entity and
+    /// drives the clock and reset of the registers config
+    #[arg(
+        long,
+        value_name = "ENTITY",
+        conflicts_with_all = ["hierarchy", "interfaces", "statistics", "references"]
+    )]
+    testbench: Option<String>,
+
+    /// Add the runner_cfg generic and the test runner of VUnit to the testbench
+    #[arg(long, requires = "testbench")]
+    vunit: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Re-indent VHDL files in place, or stdin to stdout when no files are given.
+    /// The style is read from the nearest .vhdl_lang_fmt.toml file
+    Format(FormatArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct FormatArgs {
+    /// The files to format
+    files: Vec<PathBuf>,
+
+    /// Print a diff of the files that are not formatted instead of writing them
+    /// and exit with a non-zero status if there are any
+    #[arg(long)]
+    check: bool,
+
+    /// Check that formatting the files keeps their tokens and that formatting them
+    /// again changes nothing, without writing them
+    #[arg(long, conflicts_with = "check", requires = "files")]
+    verify: bool,
+
+    /// Only format the lines from FIRST to LAST, starting at 1, and the declarations
+    /// and statements that enclose them. May be given several times, e.g. for the
+    /// changed lines of a diff
+    #[arg(long, value_name = "FIRST:LAST", conflicts_with = "verify")]
+    lines: Vec<String>,
 }
 
 fn main() {
     let args = Args::parse();
+    if let Some(Command::Format(ref format_args)) = args.command {
+        std::process::exit(format(format_args));
+    }
+
     rayon::ThreadPoolBuilder::new()
         .num_threads(args.num_threads.unwrap_or(0))
         .build_global()
@@ -38,15 +153,101 @@ fn main() {
     let mut msg_printer = MessagePrinter::default();
     config.load_external_config(&mut msg_printer, args.libraries.clone());
     config.append(
-        &Config::read_file_path(Path::new(&args.config)).expect("Failed to read config file"),
+        &Config::read_file_path(Path::new(args.config.as_deref().unwrap_or_default()))
+            .expect("Failed to read config file"),
         &mut msg_printer,
     );
+    if let Some(ref target) = args.target {
+        if let Err(err) = config.select_target(Some(target)) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    }
 
     let severity_map = *config.severities();
+    let message_catalog = config.message_catalog(None).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        Default::default()
+    });
     let mut project = Project::from_config(config, &mut msg_printer);
     project.enable_unused_declaration_detection();
     let diagnostics = project.analyse();
 
+    if args.hierarchy {
+        match project.instance_hierarchy(args.top.as_deref()) {
+            Ok(instances) => {
+                println!("{}", instances_to_json(&instances));
+                std::process::exit(0);
+            }
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if args.interfaces {
+        println!("{}", interfaces_to_json(&project.entity_interfaces()));
+        std::process::exit(0);
+    }
+
+    if args.statistics {
+        let statistics = project.design_statistics();
+        if args.json {
+            println!("{}", statistics.to_json());
+        } else {
+            print!("{}", statistics.to_table());
+        }
+        std::process::exit(0);
+    }
+
+    if let Some(ref location) = args.references {
+        match print_references(&project, location, &args.reference_kinds) {
+            Ok(()) => std::process::exit(0),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(ref entity) = args.testbench {
+        match write_testbench(&project, entity, args.vunit) {
+            Ok(()) => std::process::exit(0),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if args.fix {
+        let rules = if args.fix_rules.is_empty() {
+            FixRule::ALL
+                .into_iter()
+                .filter(FixRule::is_default)
+                .collect()
+        } else {
+            match args
+                .fix_rules
+                .iter()
+                .map(|rule| FixRule::try_from(rule.as_str()))
+                .collect::<Result<Vec<_>, _>>()
+            {
+                Ok(rules) => rules,
+                Err(err) => {
+                    eprintln!("{err}");
+                    std::process::exit(1);
+                }
+            }
+        };
+        apply_fixes(&project, &rules, args.diff);
+    }
+
+    let diagnostics: Vec<_> = diagnostics
+        .into_iter()
+        .map(|diagnostic| message_catalog.translate_diagnostic(diagnostic))
+        .collect();
     show_diagnostics(&diagnostics, &severity_map);
 
     if diagnostics
@@ -59,6 +260,187 @@ fn main() {
     }
 }
 
+fn apply_fixes(project: &Project, rules: &[FixRule], diff: bool) {
+    for (source, edits) in project.safe_fixes(rules) {
+        let file_name = source.file_name();
+        if diff {
+            print!("{}", unified_diff(&source, &edits));
+            continue;
+        }
+        match std::fs::write(file_name, apply_edits(&source, &edits)) {
+            Ok(()) => println!("Applied {} fixes to {}", edits.len(), file_name.display()),
+            Err(err) => println!("Failed to write {}: {err}", file_name.display()),
+        }
+    }
+}
+
+/// Format the files or stdin and return the exit status
+fn format(args: &FormatArgs) -> i32 {
+    let parser = VHDLParser::new(VHDLStandard::default());
+    let ranges = match args
+        .lines
+        .iter()
+        .map(|lines| LineRange::try_from(lines.as_str()))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(ranges) => ranges,
+        Err(err) => {
+            eprintln!("{err}");
+            return 1;
+        }
+    };
+    if args.files.is_empty() {
+        let mut contents = String::new();
+        if let Err(err) = std::io::stdin().read_to_string(&mut contents) {
+            eprintln!("Failed to read stdin: {err}");
+            return 1;
+        }
+        // The configuration of stdin is found from the current directory
+        let file_name = std::env::current_dir().unwrap_or_default().join("<stdin>");
+        let source = Source::inline(&file_name, &contents);
+        return match format_source(&parser, &source, &ranges) {
+            Ok(edits) if args.check => {
+                print!("{}", unified_diff(&source, &edits));
+                i32::from(!edits.is_empty())
+            }
+            Ok(edits) => {
+                print!("{}", apply_edits(&source, &edits));
+                0
+            }
+            Err(err) => {
+                eprintln!("{err}");
+                print!("{contents}");
+                1
+            }
+        };
+    }
+
+    let mut status = 0;
+    for file_name in args.files.iter() {
+        let source = match Source::from_latin1_file(file_name) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("Failed to read {}: {err}", file_name.display());
+                status = 1;
+                continue;
+            }
+        };
+        if args.verify {
+            match formatter(&source).and_then(|formatter| formatter.verify(&parser, &source)) {
+                Ok(()) => println!("Verified {}", file_name.display()),
+                Err(err) => {
+                    eprintln!("{err}");
+                    status = 1;
+                }
+            }
+            continue;
+        }
+        let edits = match format_source(&parser, &source, &ranges) {
+            Ok(edits) => edits,
+            Err(err) => {
+                eprintln!("{err}");
+                status = 1;
+                continue;
+            }
+        };
+        if edits.is_empty() {
+            continue;
+        }
+        if args.check {
+            print!("{}", unified_diff(&source, &edits));
+            status = 1;
+            continue;
+        }
+        match std::fs::write(file_name, apply_edits(&source, &edits)) {
+            Ok(()) => println!("Formatted {}", file_name.display()),
+            Err(err) => {
+                eprintln!("Failed to write {}: {err}", file_name.display());
+                status = 1;
+            }
+        }
+    }
+    status
+}
+
+/// The formatter with the style of the nearest formatter configuration file of the source
+fn formatter(source: &Source) -> Result<VHDLFormatter, String> {
+    let mut config = FormatterConfig::default();
+    config.update_from_nearest_file(source.file_name())?;
+    Ok(VHDLFormatter::new(config))
+}
+
+/// The edits that format the source, or only the line ranges when there are any
+fn format_source(
+    parser: &VHDLParser,
+    source: &Source,
+    ranges: &[LineRange],
+) -> Result<Vec<TextEdit>, String> {
+    let file_name = source.file_name();
+    let formatter = formatter(source)?;
+
+    let mut diagnostics = Vec::new();
+    let design_file = parser.parse_design_source(source, &mut diagnostics);
+    if !diagnostics.is_empty() {
+        return Err(format!(
+            "Cannot format {} because of syntax errors",
+            file_name.display()
+        ));
+    }
+    if ranges.is_empty() {
+        Ok(formatter.format_design_file(source, &design_file))
+    } else {
+        Ok(formatter.format_ranges(source, &design_file, ranges))
+    }
+}
+
+fn print_references(project: &Project, location: &str, kinds: &[String]) -> Result<(), String> {
+    let kinds = kinds
+        .iter()
+        .map(|kind| ReferenceKind::try_from(kind.as_str()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut parts = location.rsplitn(3, ':');
+    let (Some(column), Some(line), Some(file_name)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(format!("'{location}' is not of the form FILE:LINE:COLUMN"));
+    };
+    let (Ok(line @ 1..), Ok(column @ 1..)) = (line.parse::<u32>(), column.parse::<u32>()) else {
+        return Err(format!("'{location}' is not of the form FILE:LINE:COLUMN"));
+    };
+    let source = project
+        .get_source(Path::new(file_name))
+        .ok_or_else(|| format!("'{file_name}' is not a file of the project"))?;
+    let ent = project
+        .find_declaration(&source, Position::new(line - 1, column - 1))
+        .ok_or_else(|| format!("No declaration found at {location}"))?;
+
+    for (pos, kind) in project.find_all_references_with_kind(ent) {
+        if kinds.is_empty() || kinds.contains(&kind) {
+            let start = pos.start();
+            println!(
+                "{}:{}:{}: {}",
+                pos.file_name().display(),
+                start.line + 1,
+                start.character + 1,
+                kind.as_str()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn write_testbench(project: &Project, entity: &str, vunit: bool) -> Result<(), String> {
+    let testbench = project.testbench(entity, vunit)?;
+    let file_name = &testbench.file_name;
+    if file_name.exists() {
+        return Err(format!("{} already exists", file_name.display()));
+    }
+    std::fs::write(file_name, testbench.text)
+        .map_err(|err| format!("Failed to write {}: {err}", file_name.display()))?;
+    println!("Created {}", file_name.display());
+    Ok(())
+}
+
 fn show_diagnostics(diagnostics: &[Diagnostic], severity_map: &SeverityMap) {
     let diagnostics = diagnostics
         .iter()
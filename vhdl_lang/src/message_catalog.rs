@@ -0,0 +1,173 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Translations of the English diagnostic messages
+
+use crate::data::Diagnostic;
+use regex::{Captures, Regex};
+use std::borrow::Cow;
+use std::path::Path;
+use toml::Table;
+
+/// The locale of the messages of vhdl_lang
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// A catalog of translated messages.
+/// Each entry maps an English message template to its translation.
+/// Templates contain placeholders such as `{name}` that match any text,
+/// the translation refers to the matched text with the same placeholder.
+#[derive(Clone, Debug)]
+pub struct MessageCatalog {
+    locale: String,
+    entries: Vec<(Regex, String)>,
+}
+
+impl Default for MessageCatalog {
+    fn default() -> Self {
+        MessageCatalog {
+            locale: DEFAULT_LOCALE.to_owned(),
+            entries: Vec::new(),
+        }
+    }
+}
+
+fn placeholder() -> Regex {
+    Regex::new(r"\{(\w+)\}").unwrap()
+}
+
+impl MessageCatalog {
+    /// Read the catalog of a locale from a TOML table of templates and translations, e.g.
+    /// `"No declaration of '{name}'" = "Keine Deklaration von '{name}'"`
+    pub fn from_str(locale: &str, string: &str) -> Result<MessageCatalog, String> {
+        let table = string.parse::<Table>().map_err(|err| err.to_string())?;
+        let placeholder = placeholder();
+        let mut entries = Vec::new();
+        for (template, translation) in table.iter() {
+            let translation = translation
+                .as_str()
+                .ok_or_else(|| format!("The translation of '{template}' must be a string"))?;
+
+            let mut pattern = String::from("^");
+            let mut start = 0;
+            for captures in placeholder.captures_iter(template) {
+                let (Some(whole), Some(name)) = (captures.get(0), captures.get(1)) else {
+                    continue;
+                };
+                pattern.push_str(&regex::escape(&template[start..whole.start()]));
+                pattern.push_str(&format!("(?P<{}>.*?)", name.as_str()));
+                start = whole.end();
+            }
+            pattern.push_str(&regex::escape(&template[start..]));
+            pattern.push('$');
+            let regex = Regex::new(&pattern)
+                .map_err(|err| format!("Invalid message template '{template}': {err}"))?;
+            entries.push((regex, translation.to_owned()));
+        }
+        // The templates of the table are sorted by name,
+        // longer templates are tried first since they are more specific
+        entries.sort_by_key(|(regex, _)| std::cmp::Reverse(regex.as_str().len()));
+        Ok(MessageCatalog {
+            locale: locale.to_owned(),
+            entries,
+        })
+    }
+
+    pub fn read_file(locale: &str, file_name: &Path) -> Result<MessageCatalog, String> {
+        let contents = std::fs::read_to_string(file_name).map_err(|err| {
+            format!(
+                "Failed to read the message catalog {}: {err}",
+                file_name.display()
+            )
+        })?;
+        MessageCatalog::from_str(locale, &contents).map_err(|err| {
+            format!(
+                "Error in the message catalog {}: {err}",
+                file_name.display()
+            )
+        })
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// The translation of the longest matching template, or the message itself
+    pub fn translate<'a>(&self, message: &'a str) -> Cow<'a, str> {
+        for (template, translation) in self.entries.iter() {
+            if let Some(values) = template.captures(message) {
+                let translated = placeholder().replace_all(translation, |captures: &Captures| {
+                    match values.name(&captures[1]) {
+                        Some(value) => value.as_str().to_owned(),
+                        None => captures[0].to_owned(),
+                    }
+                });
+                return Cow::Owned(translated.into_owned());
+            }
+        }
+        Cow::Borrowed(message)
+    }
+
+    /// Translate the message and the related messages of the diagnostic
+    pub fn translate_diagnostic(&self, mut diagnostic: Diagnostic) -> Diagnostic {
+        if self.entries.is_empty() {
+            return diagnostic;
+        }
+        diagnostic.message = self.translate(&diagnostic.message).into_owned();
+        for (_, message) in diagnostic.related.iter_mut() {
+            *message = self.translate(message).into_owned();
+        }
+        diagnostic
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates_messages_with_placeholders() {
+        let catalog = MessageCatalog::from_str(
+            "de",
+            r#"
+"No declaration of '{name}'" = "Keine Deklaration von '{name}'"
+"'{name}' is not a {what}" = "{what} erwartet, '{name}' gefunden"
+"#,
+        )
+        .unwrap();
+        assert_eq!(catalog.locale(), "de");
+        assert_eq!(
+            catalog.translate("No declaration of 'foo'"),
+            "Keine Deklaration von 'foo'"
+        );
+        assert_eq!(
+            catalog.translate("'foo' is not a type"),
+            "type erwartet, 'foo' gefunden"
+        );
+        assert_eq!(
+            catalog.translate("No declaration of 'foo' within library"),
+            "No declaration of 'foo' within library"
+        );
+    }
+
+    #[test]
+    fn english_is_the_default() {
+        let catalog = MessageCatalog::default();
+        assert_eq!(catalog.locale(), DEFAULT_LOCALE);
+        assert_eq!(
+            catalog.translate("Unused declaration"),
+            "Unused declaration"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_catalogs() {
+        assert_eq!(
+            MessageCatalog::from_str("de", "\"Unused\" = 1").unwrap_err(),
+            "The translation of 'Unused' must be a string"
+        );
+        assert!(MessageCatalog::from_str("de", "\"{a} and {a}\" = \"{a}\"").is_err());
+    }
+}
@@ -63,6 +63,23 @@ impl<'a> Visibility<'a> {
         self.visible.values().flatten().map(|entry| entry.1.entity)
     }
 
+    /// All potentially visible names, including those of the regions made visible with `.all`
+    pub fn names(&self) -> impl Iterator<Item = (&Designator, EntRef<'a>)> + '_ {
+        self.all_in_regions
+            .iter()
+            .flat_map(|visible_region| visible_region.region.entities.iter())
+            .map(|(designator, named_entities)| (designator, named_entities.first()))
+            .chain(
+                self.visible
+                    .iter()
+                    .flat_map(|(designator, visible_entities)| {
+                        visible_entities
+                            .values()
+                            .map(move |visible_entity| (designator, visible_entity.entity))
+                    }),
+            )
+    }
+
     pub fn add_context_visibility(
         &mut self,
         visible_pos: Option<&SrcPos>,
@@ -7,16 +7,43 @@
 use crate::analysis::DesignRoot;
 use crate::ast::search::Searcher;
 use crate::ast::DesignFile;
-use crate::completion::{list_completion_options, CompletionItem};
+use crate::call_hierarchy::{incoming_calls, is_subprogram, outgoing_calls, Calls};
+use crate::completion::{list_completion_options_with_snippets, CompletionItem};
 use crate::config::Config;
+use crate::cross_check::{hierarchy_files, GhdlCrossCheckRun};
+use crate::documentation::documentation;
+use crate::entity_interface::{entity_interfaces, EntityInterface};
+use crate::external_checker::ExternalCheckerRun;
+use crate::folding_range::{folding_ranges, FoldingRange};
+use crate::formatting::{FormatterConfig, VHDLFormatter};
+use crate::hierarchy::{
+    incoming_instances, instance_hierarchy, instantiations_of_units, outgoing_instances,
+    top_level_entities, Instance, Instantiation,
+};
+use crate::ip_core::is_ip_core_file;
+use crate::linked_editing::linked_editing_ranges;
 use crate::lint::dead_code::UnusedDeclarationsLinter;
-use crate::named_entity::{AnyEnt, EntRef};
+use crate::lint::deprecated::DeprecatedUsesLinter;
+use crate::named_entity::{AnyEnt, EntRef, Related};
+use crate::refactor::{
+    add_missing_end_labels, auto_imports, bundle_signals, check_rename, entity_stub,
+    extract_procedure, formal_name_hints, insert_registered_process, instantiate,
+    list_code_actions, match_declaration_case, migrate_to_numeric_std, move_unit,
+    move_unit_to_file, remove_unused_context_items, remove_unused_declarations, rename,
+    rename_matching, safe_fixes, testbench, update_component_declarations, update_instantiations,
+    CodeAction, EntityStub, FixRule, MovedUnit, Testbench, TextEdit,
+};
+use crate::reference_kind::{classify_references, ReferenceKind};
+use crate::semantic_tokens::{semantic_tokens, SemanticToken};
+use crate::signature_help::{signature_help, SignatureHelp};
 use crate::standard::VHDLStandard;
+use crate::statistics::{design_statistics, DesignStatistics};
 use crate::syntax::VHDLParser;
+use crate::verilog::is_verilog_file;
 use crate::{data::*, EntHierarchy, EntityId};
 use fnv::{FnvHashMap, FnvHashSet};
 use std::collections::hash_map::Entry;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use vhdl_lang::Token;
 
 pub struct Project {
@@ -26,6 +53,7 @@ pub struct Project {
     files: FnvHashMap<FilePath, SourceFile>,
     empty_libraries: FnvHashSet<Symbol>,
     lint: Option<UnusedDeclarationsLinter>,
+    deprecated_uses: DeprecatedUsesLinter,
 }
 
 impl Project {
@@ -37,6 +65,7 @@ impl Project {
             empty_libraries: FnvHashSet::default(),
             parser,
             lint: None,
+            deprecated_uses: DeprecatedUsesLinter::default(),
             config: Config::default(),
         }
     }
@@ -49,6 +78,7 @@ impl Project {
     /// Files referred by configuration are parsed into corresponding libraries.
     pub fn from_config(config: Config, messages: &mut dyn MessageHandler) -> Project {
         let mut project = Project::new(config.standard());
+        project.parser.synthesis_pragmas = config.synthesis_pragmas();
         let files = project.load_files_from_config(&config, messages);
         project.parse_and_add_files(files, messages);
         project.config = config;
@@ -56,10 +86,50 @@ impl Project {
     }
 
     /// Replace active project configuration.
-    /// The design state is reset, new files are added and parsed. Existing source files will be
+    /// New files are added and parsed. Existing source files will be
     /// kept and parsed from in-memory source (required for incremental document updates).
+    /// When the configuration only changes the files of the libraries, only the files whose
+    /// libraries have changed are analyzed again. Otherwise, the design state is reset.
     pub fn update_config(&mut self, config: Config, messages: &mut dyn MessageHandler) {
+        let library_names = |config: &Config| -> FnvHashSet<String> {
+            config
+                .iter_libraries()
+                .map(|library| library.name().to_owned())
+                .collect()
+        };
+        if config.standard() != self.config.standard()
+            || config.synthesis_pragmas() != self.config.synthesis_pragmas()
+            || library_names(&config) != library_names(&self.config)
+        {
+            self.reset_config(config, messages);
+            return;
+        }
+
+        let mut new_files = self.load_files_from_config(&config, messages);
+        for (file_name, source_file) in self.files.iter_mut() {
+            let library_names = new_files.remove(file_name).unwrap_or_default();
+            if library_names == source_file.library_names {
+                continue;
+            }
+            for library_name in source_file.library_names.iter() {
+                self.root
+                    .remove_source(library_name.clone(), &source_file.source);
+            }
+            source_file.parser_diagnostics.clear();
+            source_file.library_names = library_names;
+            source_file.design_file = self
+                .parser
+                .parse_design_source(&source_file.source, &mut source_file.parser_diagnostics);
+        }
+
+        self.config = config;
+        self.parse_and_add_files(new_files, messages);
+    }
+
+    /// Reset the design state and add all files of the configuration
+    fn reset_config(&mut self, config: Config, messages: &mut dyn MessageHandler) {
         self.parser = VHDLParser::new(config.standard());
+        self.parser.synthesis_pragmas = config.synthesis_pragmas();
         self.root = DesignRoot::new(self.parser.symbols.clone());
 
         // Reset library associations for known files,
@@ -97,13 +167,15 @@ impl Project {
         let mut files: FnvHashMap<FilePath, FnvHashSet<Symbol>> = FnvHashMap::default();
         self.empty_libraries.clear();
 
+        config.check_standard_libraries(messages);
+
         for library in config.iter_libraries() {
             let library_name =
                 Latin1String::from_utf8(library.name()).expect("Library name not latin-1 encoded");
             let library_name = self.parser.symbol(&library_name);
 
             let mut empty_library = true;
-            for file_name in library.file_names(messages) {
+            for file_name in library.file_names_for_standard(config.standard(), messages) {
                 empty_library = false;
 
                 match files.entry(FilePath::new(&file_name)) {
@@ -243,6 +315,8 @@ impl Project {
         if let Some(ref mut lint) = self.lint {
             lint.lint(&self.root, &self.config, &analyzed_units, &mut diagnostics);
         }
+        self.deprecated_uses
+            .lint(&self.root, &self.config, &analyzed_units, &mut diagnostics);
 
         diagnostics
     }
@@ -286,6 +360,38 @@ impl Project {
         self.root.document_symbols(library_name, source)
     }
 
+    /// The function or procedure at the cursor
+    pub fn find_subprogram(&self, source: &Source, cursor: Position) -> Option<EntRef> {
+        self.find_declaration(source, cursor)
+            .filter(|ent| is_subprogram(ent))
+    }
+
+    /// The subprograms, processes and design units that call the subprogram `ent`
+    pub fn incoming_calls<'a>(&'a self, ent: EntRef<'a>) -> Vec<Calls<'a>> {
+        incoming_calls(&self.root, ent)
+    }
+
+    /// The subprograms that are called by `ent`
+    pub fn outgoing_calls<'a>(&'a self, ent: EntRef<'a>) -> Vec<Calls<'a>> {
+        outgoing_calls(&self.root, ent)
+    }
+
+    /// The instantiations of the entity, architecture or component `ent`
+    pub fn incoming_instances<'a>(&'a self, ent: EntRef<'a>) -> Vec<Instantiation<'a>> {
+        incoming_instances(&self.root, ent)
+    }
+
+    /// The instantiations within the architecture `ent` or the architectures of the entity `ent`
+    pub fn outgoing_instances<'a>(&'a self, ent: EntRef<'a>) -> Vec<Instantiation<'a>> {
+        outgoing_instances(&self.root, ent)
+    }
+
+    /// The entities and components declared in `source` together with the names
+    /// of the instantiated units of their instantiation statements
+    pub fn instantiations_of_units(&self, source: &Source) -> Vec<(EntRef, Vec<SrcPos>)> {
+        instantiations_of_units(&self.root, source)
+    }
+
     pub fn find_implementation(&self, source: &Source, cursor: Position) -> Vec<EntRef> {
         if let Some(ent) = self.find_declaration(source, cursor) {
             self.root.find_implementation(ent)
@@ -299,6 +405,15 @@ impl Project {
         self.root.format_declaration(ent)
     }
 
+    /// The comments immediately before the declaration of `ent` as Markdown
+    pub fn documentation(&self, ent: &AnyEnt) -> Option<String> {
+        let ent = match ent.related {
+            Related::InstanceOf(ent) => ent,
+            _ => ent,
+        };
+        documentation(&self.root, ent)
+    }
+
     pub fn format_entity(&self, id: EntityId) -> Option<String> {
         let ent = self.root.get_ent(id);
         self.format_declaration(ent)
@@ -309,12 +424,73 @@ impl Project {
         self.root.find_all_references(ent)
     }
 
+    /// The references of `ent` together with how each reference uses it,
+    /// e.g. to find the statements that drive a signal
+    pub fn find_all_references_with_kind(&self, ent: &AnyEnt) -> Vec<(SrcPos, ReferenceKind)> {
+        classify_references(&self.root, self.root.find_all_references(ent))
+    }
+
+    /// The references of `ent` within `source` together with how each reference uses it
+    pub fn find_all_references_in_source_with_kind(
+        &self,
+        source: &Source,
+        ent: &AnyEnt,
+    ) -> Vec<(SrcPos, ReferenceKind)> {
+        classify_references(
+            &self.root,
+            self.root.find_all_references_in_source(source, ent),
+        )
+    }
+
+    /// The formal names of the positional associations of `source`,
+    /// as insertions before the actuals
+    pub fn formal_name_hints(&self, source: &Source) -> Vec<TextEdit> {
+        formal_name_hints(&self.root, source)
+    }
+
+    /// The identifiers of `source` classified by the declarations they refer to
+    pub fn semantic_tokens(&self, source: &Source) -> Vec<SemanticToken> {
+        semantic_tokens(&self.root, self.config.deprecation(), source)
+    }
+
+    /// The signatures of the subprogram call or the map aspect of the instantiation at the cursor
+    pub fn signature_help(&self, source: &Source, cursor: Position) -> Option<SignatureHelp> {
+        signature_help(&self.root, source, cursor)
+    }
+
+    /// The design units, subprograms, statements and comment blocks of `source`
+    /// that span several lines
+    pub fn folding_ranges(&self, source: &Source) -> Vec<FoldingRange> {
+        folding_ranges(&self.root, source)
+    }
+
+    /// The name of the declaration at the cursor and the name after its `end`
+    pub fn linked_editing_ranges(&self, source: &Source, cursor: Position) -> Option<Vec<SrcPos>> {
+        linked_editing_ranges(&self.root, source, cursor)
+    }
+
     pub fn find_all_references_in_source(&self, source: &Source, ent: &AnyEnt) -> Vec<SrcPos> {
         self.root.find_all_references_in_source(source, ent)
     }
 
     /// Get source positions that are not resolved to a declaration
     /// This is used for development to test where the language server is blind
+    /// The references of the source to design units that are declared in other files,
+    /// such as the packages of use clauses and the entities of instantiations
+    pub fn find_design_unit_references(&self, source: &Source) -> Vec<(SrcPos, EntRef)> {
+        let mut references: Vec<_> = self
+            .root
+            .find_design_unit_references(source)
+            .into_iter()
+            .filter(|(_, ent)| {
+                ent.decl_pos()
+                    .is_some_and(|decl_pos| decl_pos.source != *source)
+            })
+            .collect();
+        references.sort_by_key(|(pos, _)| pos.start());
+        references
+    }
+
     pub fn find_all_unresolved(&self) -> (usize, Vec<SrcPos>) {
         self.root.find_all_unresolved()
     }
@@ -328,12 +504,440 @@ impl Project {
         source: &Source,
         cursor: Position,
     ) -> Vec<CompletionItem> {
-        list_completion_options(&self.root, source, cursor)
+        list_completion_options_with_snippets(
+            &self.root,
+            self.config.snippets(),
+            true,
+            source,
+            cursor,
+        )
     }
 
     pub fn entity_id_from_raw(&self, raw: usize) -> Option<EntityId> {
         self.root.entity_id_from_raw(raw)
     }
+
+    /// Create or update component declarations in `package` that match the given entities.
+    /// Both the package and the entities are selected names, e.g. `lib.components`.
+    pub fn update_component_declarations(
+        &self,
+        package: &str,
+        entities: &[&str],
+    ) -> Result<Vec<TextEdit>, String> {
+        update_component_declarations(&self.root, package, entities)
+    }
+
+    /// Rewrite a source file from `std_logic_arith`, `std_logic_unsigned` and `std_logic_signed`
+    /// to `ieee.numeric_std`
+    pub fn migrate_to_numeric_std(&self, source: &Source) -> Vec<TextEdit> {
+        migrate_to_numeric_std(&self.root, source)
+    }
+
+    /// Rewrite all source files of a library to `ieee.numeric_std`
+    pub fn migrate_library_to_numeric_std(
+        &self,
+        library_name: &str,
+    ) -> Result<Vec<TextEdit>, String> {
+        let library_name = self.root.symbol_utf8(library_name);
+        if self.root.get_lib(&library_name).is_none() {
+            return Err(format!("No such library '{library_name}'"));
+        }
+
+        let mut edits = Vec::new();
+        for source_file in self.files.values() {
+            if source_file.library_names.contains(&library_name) {
+                edits.extend(migrate_to_numeric_std(&self.root, &source_file.source));
+            }
+        }
+        Ok(edits)
+    }
+
+    /// Check that `ent` can be renamed, i.e. that it is not declared
+    /// within the standard library or a third party library
+    pub fn check_rename(&self, ent: &AnyEnt) -> Result<(), String> {
+        check_rename(ent, &self.read_only_libraries())
+    }
+
+    /// Rename `ent` to `new_name` together with all its references
+    pub fn rename(&self, ent: &AnyEnt, new_name: &str) -> Result<Vec<TextEdit>, String> {
+        rename(&self.root, ent, new_name, &self.read_only_libraries())
+    }
+
+    /// Rename all declarations whose identifier matches the regular expression `pattern`
+    /// together with their references. The matches are replaced by `replacement`.
+    /// Declarations within the standard library and third party libraries are not renamed.
+    pub fn rename_matching(
+        &self,
+        pattern: &str,
+        replacement: &str,
+    ) -> Result<Vec<TextEdit>, String> {
+        rename_matching(
+            &self.root,
+            pattern,
+            replacement,
+            &self.read_only_libraries(),
+        )
+    }
+
+    /// The standard library and the third party libraries,
+    /// whose declarations are never renamed
+    fn read_only_libraries(&self) -> FnvHashSet<Symbol> {
+        let mut libraries: FnvHashSet<Symbol> = self
+            .config
+            .iter_libraries()
+            .filter(|library| library.is_third_party)
+            .map(|library| self.root.symbol_utf8(library.name()))
+            .collect();
+        libraries.insert(self.root.symbol_utf8("std"));
+        libraries
+    }
+
+    /// Move the architecture or package body at the cursor into a new file
+    /// named according to `file_name_scheme`
+    pub fn move_unit_to_file(
+        &self,
+        source: &Source,
+        cursor: Position,
+        file_name_scheme: &str,
+    ) -> Option<MovedUnit> {
+        move_unit_to_file(&self.root, source, cursor, file_name_scheme)
+    }
+
+    /// Move the design unit at the cursor, together with its context clause, to `file_name`
+    pub fn move_unit(
+        &self,
+        source: &Source,
+        cursor: Position,
+        file_name: &Path,
+    ) -> Result<MovedUnit, String> {
+        move_unit(&self.root, source, cursor, file_name)
+    }
+
+    /// Create the missing entity of the instantiation at the cursor in a new file
+    pub fn entity_stub(&self, source: &Source, cursor: Position) -> Option<EntityStub> {
+        entity_stub(&self.root, source, cursor)
+    }
+
+    /// Move the statements of a process within the range into a new procedure of the process
+    /// and call it instead
+    pub fn extract_procedure(
+        &self,
+        source: &Source,
+        range: Range,
+        name: &str,
+    ) -> Option<CodeAction> {
+        extract_procedure(&self.root, source, range, name)
+    }
+
+    /// Repeat the name after `end` of every entity, architecture, process, generate and loop
+    /// within the source file that lacks an end label
+    pub fn add_missing_end_labels(&self, source: &Source) -> Vec<TextEdit> {
+        add_missing_end_labels(&self.root, source)
+    }
+
+    /// Write every reference within the source file with the same case as the name
+    /// of its declaration
+    pub fn match_declaration_case(&self, source: &Source) -> Vec<TextEdit> {
+        match_declaration_case(&self.root, source)
+    }
+
+    /// Replace the signals declared on the lines of `range` with a signal of a record type
+    /// that is declared in `package`, a selected name such as `lib.pkg`
+    pub fn bundle_signals(
+        &self,
+        source: &Source,
+        range: Range,
+        package: &str,
+    ) -> Result<Vec<TextEdit>, String> {
+        bundle_signals(&self.root, source, range, package)
+    }
+
+    /// Make the associations of every instantiation of an entity match its generics and ports,
+    /// renaming, removing and adding associations. The entity is a selected name such as `lib.ent`
+    pub fn update_instantiations(&self, entity: &str) -> Result<Vec<TextEdit>, String> {
+        update_instantiations(&self.root, entity)
+    }
+
+    /// Insert a clocked process at the cursor that uses the clock and reset of the config
+    pub fn insert_registered_process(&self, source: &Source, cursor: Position) -> TextEdit {
+        insert_registered_process(source, cursor, self.config.register_conventions())
+    }
+
+    /// Insert an instantiation at the cursor that associates every generic and port with
+    /// a same-named signal. The entity is given as `lib.ent` or a component as `lib.pkg.comp`
+    pub fn instantiate(
+        &self,
+        source: &Source,
+        cursor: Position,
+        name: &str,
+    ) -> Result<TextEdit, String> {
+        instantiate(&self.root, source, cursor, name)
+    }
+
+    /// Create a testbench of an entity given as `lib.ent` that drives the clock and reset
+    /// of the config, optionally with the test runner of VUnit
+    pub fn testbench(&self, entity: &str, vunit: bool) -> Result<Testbench, String> {
+        testbench(
+            &self.root,
+            entity,
+            self.config.register_conventions(),
+            vunit,
+        )
+    }
+
+    /// Remove the unused signals, variables, constants and use clauses of a source file
+    pub fn remove_unused_declarations(&self, source: &Source) -> Vec<TextEdit> {
+        remove_unused_declarations(&self.root, source)
+    }
+
+    /// Remove the use clauses and library clauses that are not used
+    pub fn remove_unused_context_items(&self, source: &Source) -> Vec<TextEdit> {
+        remove_unused_context_items(&self.root, source)
+    }
+
+    /// Re-indent the lines of a source file.
+    /// Files with syntax errors as well as Verilog files and IP cores are not formatted.
+    pub fn format_source(&self, source: &Source, config: FormatterConfig) -> Option<Vec<TextEdit>> {
+        let design_file = self.parse_for_formatting(source)?;
+        Some(VHDLFormatter::new(config).format_design_file(source, &design_file))
+    }
+
+    /// Re-indent the lines of the smallest design unit, declaration or statement
+    /// that encloses `range`
+    pub fn format_source_range(
+        &self,
+        source: &Source,
+        range: Range,
+        config: FormatterConfig,
+    ) -> Option<Vec<TextEdit>> {
+        let design_file = self.parse_for_formatting(source)?;
+        Some(VHDLFormatter::new(config).format_range(source, &design_file, range))
+    }
+
+    /// Re-indent the code that is completed by typing `typed` before the cursor
+    pub fn format_on_type(
+        &self,
+        source: &Source,
+        cursor: Position,
+        typed: char,
+        config: FormatterConfig,
+    ) -> Option<Vec<TextEdit>> {
+        let design_file = self.parse_for_formatting(source)?;
+        Some(VHDLFormatter::new(config).format_on_type(source, &design_file, cursor, typed))
+    }
+
+    /// Sort the library clauses, context references and use clauses of the context clauses
+    /// of a source file and remove the duplicates
+    pub fn sort_context_clauses(&self, source: &Source) -> Option<Vec<TextEdit>> {
+        let design_file = self.parse_for_formatting(source)?;
+        Some(
+            VHDLFormatter::new(FormatterConfig::default())
+                .sort_context_clauses(source, &design_file),
+        )
+    }
+
+    fn parse_for_formatting(&self, source: &Source) -> Option<DesignFile> {
+        let file_name = source.file_name();
+        if is_verilog_file(file_name) || is_ip_core_file(file_name) {
+            return None;
+        }
+        let mut diagnostics = Vec::new();
+        let design_file = self.parser.parse_design_source(source, &mut diagnostics);
+        diagnostics.is_empty().then_some(design_file)
+    }
+
+    /// The fixes of the rules for every VHDL source file that is mapped to
+    /// at least one library that is not a third party library
+    pub fn safe_fixes(&self, rules: &[FixRule]) -> Vec<(Source, Vec<TextEdit>)> {
+        let third_party: FnvHashSet<Symbol> = self
+            .config
+            .iter_libraries()
+            .filter(|library| library.is_third_party)
+            .map(|library| self.root.symbol_utf8(library.name()))
+            .collect();
+
+        let mut fixes: Vec<_> = self
+            .files
+            .values()
+            .filter(|source_file| {
+                let file_name = source_file.source.file_name();
+                !is_verilog_file(file_name)
+                    && !is_ip_core_file(file_name)
+                    && source_file
+                        .library_names
+                        .iter()
+                        .any(|library_name| !third_party.contains(library_name))
+            })
+            .filter_map(|source_file| {
+                let edits = safe_fixes(&self.root, &source_file.source, rules);
+                (!edits.is_empty()).then(|| (source_file.source.clone(), edits))
+            })
+            .collect();
+        fixes.sort_by(|(source1, _), (source2, _)| source1.file_name().cmp(source2.file_name()));
+        fixes
+    }
+
+    /// The instance hierarchy below the top-level entity given as `library.entity`,
+    /// or below the top-level entity of the active target.
+    /// Without a top-level entity, the hierarchies below all entities that are not instantiated
+    /// are returned, except for the entities of third party libraries.
+    pub fn instance_hierarchy(&self, top: Option<&str>) -> Result<Vec<Instance>, String> {
+        let top = top.or_else(|| self.config.active_target()?.top.as_deref());
+        let tops = if let Some(top) = top {
+            let (library, entity) = top.split_once('.').ok_or_else(|| {
+                format!("Expected the top-level entity as library.entity, got '{top}'")
+            })?;
+            vec![(
+                self.root.symbol_utf8(library),
+                self.root.symbol_utf8(entity),
+            )]
+        } else {
+            top_level_entities(&self.root, &self.project_libraries())
+        };
+
+        tops.iter()
+            .map(|(library, entity)| {
+                instance_hierarchy(&self.root, library, entity)
+                    .ok_or_else(|| format!("No entity '{entity}' in library '{library}'"))
+            })
+            .collect()
+    }
+
+    /// The generics and ports of the entities of all libraries except third party libraries
+    pub fn entity_interfaces(&self) -> Vec<EntityInterface> {
+        entity_interfaces(&self.root, &self.project_libraries())
+    }
+
+    /// The statistics of the libraries and entities of all libraries except third party libraries
+    pub fn design_statistics(&self) -> DesignStatistics {
+        design_statistics(&self.root, &self.project_libraries())
+    }
+
+    /// The runs of the external checkers of the config.
+    /// Checkers of a single file check the given file, or all files of the project
+    /// except for the files of third party libraries when no file is given.
+    /// When `on_save` is set, only the checkers that run when a file is saved are run.
+    pub fn external_checker_runs(
+        &self,
+        file_name: Option<&Path>,
+        on_save: bool,
+    ) -> Vec<ExternalCheckerRun> {
+        let files: Vec<(&Path, Symbol)> = if let Some(file_name) = file_name {
+            self.files
+                .get(&FilePath::new(file_name))
+                .and_then(|file| {
+                    let library = self.library_mapping_of(&file.source).into_iter().next()?;
+                    Some((file_name, library))
+                })
+                .into_iter()
+                .collect()
+        } else {
+            let libraries = self.project_libraries();
+            let mut files: Vec<_> = self
+                .files
+                .values()
+                .filter_map(|file| {
+                    let library = self
+                        .library_mapping_of(&file.source)
+                        .into_iter()
+                        .find(|library| libraries.contains(library))?;
+                    Some((file.source.file_name(), library))
+                })
+                .collect();
+            files.sort_by_key(|(file, _)| *file);
+            files
+        };
+
+        let files: Vec<(PathBuf, String)> = files
+            .into_iter()
+            .map(|(file_name, library)| (file_name.to_owned(), library.name_utf8()))
+            .collect();
+        self.config
+            .external_checkers()
+            .iter()
+            .filter(|checker| !on_save || checker.run_on_save)
+            .map(|checker| ExternalCheckerRun::new(checker.clone(), files.clone()))
+            .collect()
+    }
+
+    /// The cross-check of the top-level entity of the `ghdl_cross_check` config with GHDL.
+    /// Returns `None` when no cross-check is configured, or when `on_save` is set
+    /// and the cross-check only runs on demand.
+    pub fn ghdl_cross_check_run(
+        &mut self,
+        on_save: bool,
+    ) -> Option<Result<GhdlCrossCheckRun, String>> {
+        let cross_check = self.config.ghdl_cross_check()?.clone();
+        if on_save && !cross_check.run_on_save {
+            return None;
+        }
+
+        let diagnostics = self.analyse();
+        let errors: Vec<_> = diagnostics
+            .into_iter()
+            .filter(|diagnostic| self.config.severities()[diagnostic.code] == Some(Severity::Error))
+            .collect();
+        let result = self
+            .instance_hierarchy(Some(&cross_check.top))
+            .map(|hierarchy| {
+                let mut files = FnvHashSet::default();
+                hierarchy
+                    .iter()
+                    .for_each(|instance| hierarchy_files(instance, &mut files));
+
+                let mut libraries: FnvHashMap<String, Vec<PathBuf>> = FnvHashMap::default();
+                for file in self.files.values() {
+                    let file_name = file.source.file_name();
+                    if is_verilog_file(file_name) || is_ip_core_file(file_name) {
+                        continue;
+                    }
+                    for library in file.library_names.iter() {
+                        libraries
+                            .entry(library.name_utf8())
+                            .or_default()
+                            .push(file_name.to_owned());
+                    }
+                }
+                let mut libraries: Vec<_> = libraries.into_iter().collect();
+                libraries.sort();
+                libraries.iter_mut().for_each(|(_, files)| files.sort());
+
+                GhdlCrossCheckRun::new(cross_check, self.parser.standard, libraries, errors, files)
+            });
+        Some(result)
+    }
+
+    /// The names of the libraries of the project that are not third party libraries, sorted by name
+    pub fn project_library_names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self
+            .config
+            .iter_libraries()
+            .filter(|library| !library.is_third_party)
+            .map(|library| library.name().to_owned())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// The libraries of the project that are not third party libraries
+    fn project_libraries(&self) -> Vec<Symbol> {
+        self.config
+            .iter_libraries()
+            .filter(|library| !library.is_third_party)
+            .map(|library| self.root.symbol_utf8(library.name()))
+            .collect()
+    }
+
+    /// Code actions that rewrite the source at the cursor position
+    pub fn code_actions(&self, source: &Source, cursor: Position) -> Vec<CodeAction> {
+        list_code_actions(&self.root, self.parser.standard, source, cursor)
+    }
+
+    /// Quick fixes that add the use clause of the only package that declares an unresolved name
+    pub fn auto_imports(&self, source: &Source) -> Vec<(SrcPos, CodeAction)> {
+        auto_imports(&self.root, source)
+    }
 }
 
 /// Multiply cloneable value by cloning
@@ -657,4 +1261,145 @@ lib.files = ['file2.vhd']
         assert_eq!(diagnostics[0].pos.source, source2); // No such library
         assert_eq!(diagnostics[1].pos.source, source2); // No declaration
     }
+
+    #[test]
+    fn config_update_reanalyzes_files_with_changed_libraries() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(tempdir.path()).unwrap();
+        std::fs::write(
+            root.join("pkg.vhd"),
+            "
+package pkg is
+end package;
+        ",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("user.vhd"),
+            "
+library other;
+use other.pkg.all;
+
+entity user is
+end entity;
+        ",
+        )
+        .unwrap();
+
+        let config1 = Config::from_str(
+            "
+[libraries]
+lib.files = ['pkg.vhd', 'user.vhd']
+other.files = []
+        ",
+            &root,
+        )
+        .unwrap();
+        let config2 = Config::from_str(
+            "
+[libraries]
+lib.files = ['user.vhd']
+other.files = ['pkg.vhd']
+        ",
+            &root,
+        )
+        .unwrap();
+
+        let mut messages = Vec::new();
+        let mut project = Project::from_config(config1, &mut messages);
+        assert_ne!(project.analyse(), vec![]);
+
+        project.update_config(config2, &mut messages);
+        assert_eq!(messages, vec![]);
+        assert_eq!(project.analyse(), vec![]);
+    }
+
+    #[test]
+    fn code_excluded_from_synthesis_is_not_analyzed() {
+        let root = tempfile::tempdir().unwrap();
+        let vhdl_file_path = root.path().join("file.vhd");
+        std::fs::write(
+            &vhdl_file_path,
+            "
+entity ent is
+end entity;
+
+architecture a of ent is
+begin
+  -- synthesis translate_off
+  assert missing report \"Only in simulation\";
+  -- synthesis translate_on
+end architecture;
+        ",
+        )
+        .unwrap();
+
+        let config_str = "
+synthesis_pragmas = 'exclude'
+[libraries]
+lib.files = ['file.vhd']
+        ";
+        let config = Config::from_str(config_str, root.path()).unwrap();
+        let mut messages = Vec::new();
+        let mut project = Project::from_config(config, &mut messages);
+        assert_eq!(messages, vec![]);
+
+        let diagnostics = project.analyse();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, ErrorCode::ExcludedFromSynthesis);
+    }
+
+    #[test]
+    fn design_unit_references_to_other_files() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(
+            root.path().join("pkg.vhd"),
+            "
+package pkg is
+end package;
+
+entity sub is
+end entity;
+",
+        )
+        .unwrap();
+        let top_path = root.path().join("top.vhd");
+        std::fs::write(
+            &top_path,
+            "
+use work.pkg.all;
+
+entity top is
+end entity;
+
+architecture a of top is
+begin
+  inst: entity work.sub;
+end architecture;
+",
+        )
+        .unwrap();
+
+        let config_str = "
+[libraries]
+lib.files = ['pkg.vhd', 'top.vhd']
+        ";
+        let config = Config::from_str(config_str, root.path()).unwrap();
+        let mut messages = Vec::new();
+        let mut project = Project::from_config(config, &mut messages);
+        assert_eq!(messages, vec![]);
+        project.analyse();
+
+        let source = project.get_source(&top_path).unwrap();
+        let references: Vec<_> = project
+            .find_design_unit_references(&source)
+            .into_iter()
+            .map(|(pos, ent)| (pos.start().line, ent.designator().to_string()))
+            .collect();
+        // The reference of the architecture to the entity of the same file is not included
+        assert_eq!(
+            references,
+            vec![(1, "pkg".to_owned()), (8, "sub".to_owned())]
+        );
+    }
 }
@@ -0,0 +1,230 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Source transformations that produce text edits rather than diagnostics.
+//! The edits are not applied by `vhdl_lang`; it is up to the client
+//! (typically the language server) to apply them to the documents.
+
+mod align;
+mod bundle_signals;
+mod case_arms;
+mod component_declaration;
+mod declare_signal;
+mod did_you_mean;
+mod end_labels;
+mod entity_instantiation;
+mod entity_stub;
+mod extract_procedure;
+mod fixes;
+mod identifier_case;
+mod instantiation;
+mod missing_associations;
+mod modernize;
+mod move_unit;
+mod named_association;
+mod numeric_std;
+mod package_body;
+mod registered_process;
+mod rename;
+mod rename_matching;
+mod sensitivity_list;
+mod signal_to_variable;
+mod testbench;
+mod unused;
+mod update_instantiations;
+mod use_clauses;
+
+use crate::analysis::{DesignRoot, LockedUnit};
+use crate::data::{Position, Range, Source, SrcPos};
+use crate::standard::VHDLStandard;
+
+pub use align::align_lines;
+pub(crate) use align::{align_arrows, align_declarations};
+pub use bundle_signals::bundle_signals;
+pub(crate) use case_arms::enum_literals;
+pub use component_declaration::update_component_declarations;
+pub use did_you_mean::auto_imports;
+pub use end_labels::add_missing_end_labels;
+pub use entity_stub::{entity_stub, EntityStub};
+pub use extract_procedure::extract_procedure;
+pub use fixes::{safe_fixes, unified_diff, FixRule};
+pub use identifier_case::match_declaration_case;
+pub use instantiation::instantiate;
+pub use move_unit::{move_unit, move_unit_to_file, MovedUnit, DEFAULT_FILE_NAME_SCHEME};
+pub use named_association::formal_name_hints;
+pub use numeric_std::migrate_to_numeric_std;
+pub use registered_process::insert_registered_process;
+pub use rename::{check_rename, rename};
+pub use rename_matching::rename_matching;
+pub use testbench::{testbench, Testbench};
+pub use unused::{remove_unused_declarations, ALLOW_UNUSED_COMMENT};
+pub use update_instantiations::update_instantiations;
+pub use use_clauses::remove_unused_context_items;
+
+/// A single replacement of the text at `pos` with `new_text`.
+/// An insertion is represented by an empty range.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TextEdit {
+    pub pos: SrcPos,
+    pub new_text: String,
+}
+
+impl TextEdit {
+    pub fn replace(pos: SrcPos, new_text: impl Into<String>) -> TextEdit {
+        TextEdit {
+            pos,
+            new_text: new_text.into(),
+        }
+    }
+
+    pub fn insert(pos: &SrcPos, new_text: impl Into<String>) -> TextEdit {
+        TextEdit {
+            pos: SrcPos::new(pos.source.clone(), Range::new(pos.start(), pos.start())),
+            new_text: new_text.into(),
+        }
+    }
+}
+
+/// A named set of edits that can be offered to the user, e.g. as a code action
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct CodeAction {
+    pub title: String,
+    pub edits: Vec<TextEdit>,
+}
+
+/// List the code actions that are applicable at the cursor position
+pub fn list_code_actions(
+    root: &DesignRoot,
+    standard: VHDLStandard,
+    source: &Source,
+    cursor: Position,
+) -> Vec<CodeAction> {
+    let mut actions = did_you_mean::list_code_actions(root, source, cursor);
+    actions.extend(declare_signal::list_code_actions(root, source, cursor));
+    actions.extend(component_declaration::list_code_actions(
+        root, source, cursor,
+    ));
+    actions.extend(missing_associations::list_code_actions(
+        root, source, cursor,
+    ));
+    actions.extend(entity_instantiation::list_code_actions(
+        root, source, cursor,
+    ));
+    actions.extend(numeric_std::list_code_actions(root, source, cursor));
+    actions.extend(use_clauses::list_code_actions(root, source, cursor));
+    actions.extend(end_labels::list_code_actions(root, source, cursor));
+    actions.extend(identifier_case::list_code_actions(root, source, cursor));
+    actions.extend(case_arms::list_code_actions(root, source, cursor));
+    actions.extend(package_body::list_code_actions(root, source, cursor));
+    actions.extend(named_association::list_code_actions(root, source, cursor));
+    actions.extend(signal_to_variable::list_code_actions(root, source, cursor));
+    actions.extend(sensitivity_list::list_code_actions(root, source, cursor));
+    if standard >= VHDLStandard::VHDL2008 {
+        actions.extend(modernize::list_code_actions(root, source, cursor));
+    }
+    actions
+}
+
+/// Apply `edits` to the contents of `source` and return the new contents.
+/// The edits must not overlap.
+pub fn apply_edits(source: &Source, edits: &[TextEdit]) -> String {
+    let contents = source.contents();
+    let mut edits: Vec<&TextEdit> = edits.iter().collect();
+    edits.sort_by_key(|edit| edit.pos.start());
+
+    let mut text = String::new();
+    let mut start = contents.start();
+    for edit in edits {
+        text.push_str(&contents.text(Range::new(start, edit.pos.start())));
+        text.push_str(&edit.new_text);
+        start = edit.pos.end();
+    }
+    text.push_str(&contents.text(Range::new(start, contents.end())));
+    text
+}
+
+/// Find a primary unit given a selected name such as `lib.name`.
+fn find_primary_unit<'a>(root: &'a DesignRoot, name: &str) -> Result<&'a LockedUnit, String> {
+    let Some((library_name, primary_name)) = name.split_once('.') else {
+        return Err(format!(
+            "Expected a selected name of the form 'library.unit', got '{name}'"
+        ));
+    };
+
+    let library = root
+        .get_lib(&root.symbol_utf8(library_name.trim()))
+        .ok_or_else(|| format!("No such library '{}'", library_name.trim()))?;
+
+    library
+        .primary_unit(&root.symbol_utf8(primary_name.trim()))
+        .ok_or_else(|| {
+            format!(
+                "No primary unit '{}' within library '{}'",
+                primary_name.trim(),
+                library_name.trim()
+            )
+        })
+}
+
+/// Indent all lines but the first by `indent`
+fn indent_lines(text: &str, indent: &str) -> String {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 || line.is_empty() {
+                line.to_owned()
+            } else {
+                format!("{indent}{line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The source text at `pos`
+pub(crate) fn source_text(pos: &SrcPos) -> String {
+    pos.source.contents().text(pos.range())
+}
+
+/// The leading whitespace of the line where `pos` starts
+fn line_indent(pos: &SrcPos) -> String {
+    pos.source
+        .contents()
+        .get_line(pos.start().line as usize)
+        .map(|line| {
+            line.chars()
+                .take_while(|chr| *chr == ' ' || *chr == '\t')
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Extend `pos` to the whole lines it occupies when there is nothing else on these lines
+fn whole_lines(pos: &SrcPos) -> SrcPos {
+    let contents = pos.source.contents();
+    let start = pos.start();
+    let end = pos.end();
+
+    let nothing_before = contents.get_line(start.line as usize).is_some_and(|line| {
+        line.chars()
+            .take(start.character as usize)
+            .all(char::is_whitespace)
+    });
+    let nothing_after = contents.get_line(end.line as usize).is_some_and(|line| {
+        line.chars()
+            .skip(end.character as usize)
+            .all(char::is_whitespace)
+    });
+
+    if nothing_before && nothing_after {
+        SrcPos::new(
+            pos.source.clone(),
+            Range::new(Position::new(start.line, 0), Position::new(end.line + 1, 0)),
+        )
+    } else {
+        pos.clone()
+    }
+}
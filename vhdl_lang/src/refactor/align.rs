@@ -0,0 +1,200 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Column alignment of `:`, `:=` and `=>` within a range of lines
+
+use super::TextEdit;
+use crate::data::{Position, Range, Source, SrcPos};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Delimiter {
+    Colon,
+    Assignment,
+    Arrow,
+}
+
+impl Delimiter {
+    fn text(self) -> &'static str {
+        match self {
+            Delimiter::Colon => ":",
+            Delimiter::Assignment => ":=",
+            Delimiter::Arrow => "=>",
+        }
+    }
+}
+
+/// Align the first `:`, `:=` and `=>` of the lines within `range` to the same column.
+/// Only lines that contain the delimiter are changed and the rest of the file is left as is.
+pub fn align_lines(source: &Source, range: Range) -> Vec<TextEdit> {
+    let contents = source.contents();
+    let mut last_line = range.end.line;
+    if range.end.character == 0 && last_line > range.start.line {
+        // A selection of whole lines ends at the start of the following line
+        last_line -= 1;
+    }
+
+    let original: Vec<(u32, String)> = (range.start.line..=last_line)
+        .filter_map(|line| {
+            let text = contents.get_line(line as usize)?;
+            Some((line, text.trim_end_matches(['\r', '\n']).to_owned()))
+        })
+        .collect();
+
+    let mut lines: Vec<String> = original.iter().map(|(_, text)| text.clone()).collect();
+    for delimiter in [Delimiter::Colon, Delimiter::Assignment, Delimiter::Arrow] {
+        align(&mut lines, delimiter);
+    }
+
+    original
+        .into_iter()
+        .zip(lines)
+        .filter(|((_, old), new)| old != new)
+        .map(|((line, old), new)| {
+            let end = Position::new(line, old.chars().count() as u32);
+            let pos = SrcPos::new(source.clone(), Range::new(Position::new(line, 0), end));
+            TextEdit::replace(pos, new)
+        })
+        .collect()
+}
+
+/// Align the first `:` and `:=` of the lines, such as the lines of adjacent declarations
+pub(crate) fn align_declarations(lines: &mut [String]) {
+    for delimiter in [Delimiter::Colon, Delimiter::Assignment] {
+        align(lines, delimiter);
+    }
+}
+
+/// Align the first `=>` of the lines, such as the lines of adjacent associations
+pub(crate) fn align_arrows(lines: &mut [String]) {
+    align(lines, Delimiter::Arrow);
+}
+
+fn align(lines: &mut [String], delimiter: Delimiter) {
+    // The text before the delimiter and the byte index of the delimiter.
+    // Lines that start with the delimiter are not aligned.
+    let found: Vec<Option<(String, usize)>> = lines
+        .iter()
+        .map(|line| {
+            let idx = find_delimiter(line, delimiter)?;
+            let before = line[..idx].trim_end();
+            (!before.trim_start().is_empty()).then(|| (before.to_owned(), idx))
+        })
+        .collect();
+
+    if found.iter().flatten().count() < 2 {
+        return;
+    }
+    let Some(column) = found
+        .iter()
+        .flatten()
+        .map(|(before, _)| before.chars().count() + 1)
+        .max()
+    else {
+        return;
+    };
+
+    for (line, found) in lines.iter_mut().zip(found) {
+        if let Some((before, idx)) = found {
+            let padding = " ".repeat(column - before.chars().count());
+            *line = format!("{before}{padding}{}", &line[idx..]);
+        }
+    }
+}
+
+/// The byte index of the first occurrence of `delimiter` outside of literals and comments
+fn find_delimiter(line: &str, delimiter: Delimiter) -> Option<usize> {
+    let bytes = line.as_bytes();
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        let rest = &bytes[idx..];
+        if rest.starts_with(b"--") {
+            return None;
+        } else if rest[0] == b'"' {
+            idx += rest[1..].iter().position(|byte| *byte == b'"')? + 1;
+        } else if rest[0] == b'\'' && rest.get(2) == Some(&b'\'') {
+            // Character literal such as ':'
+            idx += 2;
+        } else if rest.starts_with(b":=") {
+            match delimiter {
+                Delimiter::Assignment => return Some(idx),
+                // A variable assignment rather than a declaration
+                Delimiter::Colon => return None,
+                Delimiter::Arrow => idx += 1,
+            }
+        } else if rest.starts_with(delimiter.text().as_bytes()) {
+            return Some(idx);
+        }
+        idx += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::refactor::apply_edits;
+    use crate::syntax::test::Code;
+
+    fn aligned(code: &Code, range: Range) -> String {
+        let edits = align_lines(code.source(), range);
+        apply_edits(code.source(), &edits)
+    }
+
+    #[test]
+    fn aligns_colons_and_assignments() {
+        let code = Code::new(
+            "\
+signal clk : std_logic;
+signal data_valid : std_logic := '0';
+constant width : natural:= 8;
+",
+        );
+        assert_eq!(
+            aligned(&code, code.pos().range()),
+            "\
+signal clk        : std_logic;
+signal data_valid : std_logic := '0';
+constant width    : natural   := 8;
+"
+        );
+    }
+
+    #[test]
+    fn aligns_arrows_within_selection_only() {
+        let code = Code::new(
+            "\
+  port map (
+    clk => clk,
+    data_valid => valid,
+    rst => rst -- a => b
+  );
+",
+        );
+        let selection = Range::new(Position::new(1, 0), Position::new(3, 0));
+        assert_eq!(
+            aligned(&code, selection),
+            "\
+  port map (
+    clk        => clk,
+    data_valid => valid,
+    rst => rst -- a => b
+  );
+"
+        );
+    }
+
+    #[test]
+    fn ignores_delimiters_in_literals_and_assignments() {
+        let code = Code::new(
+            "\
+    x := \":\";
+    c <= ':';
+",
+        );
+        assert_eq!(align_lines(code.source(), code.pos().range()), vec![]);
+    }
+}
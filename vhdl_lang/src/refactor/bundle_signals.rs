@@ -0,0 +1,494 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Replace a group of signals with a single signal of a new record type
+
+use super::component_declaration::{insert_before_end, package_end};
+use super::use_clauses::uses_all_of;
+use super::{find_primary_unit, source_text, whole_lines, TextEdit};
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::ast::{
+    AnyDesignUnit, AnyPrimaryUnit, AnySecondaryUnit, Declaration, Designator, HasUnitId,
+    ObjectClass,
+};
+use crate::data::{Range, Source, SrcPos};
+use crate::named_entity::{AnyEntKind, Design, EntRef, HasEntityId};
+use crate::syntax::{Kind, TokenAccess};
+use crate::HasTokenSpan;
+
+/// Replace the signals declared on the lines of `range` with one signal of a new record type.
+///
+/// The names of the signals must share a prefix ending with `_`, such as `rx_valid` and
+/// `rx_data`. The prefix without the `_` is the name of the new signal, `rx`, and the rest of
+/// the names are the fields of the record type `rx_t`. The record type is declared at the end of
+/// `package`, given as a selected name such as `lib.pkg`, and references such as `rx_valid`
+/// are rewritten to `rx.valid`.
+///
+/// The subtype indications of the signals are copied as they are,
+/// so their types must be visible within the package as well.
+pub fn bundle_signals(
+    root: &DesignRoot,
+    source: &Source,
+    range: Range,
+    package: &str,
+) -> Result<Vec<TextEdit>, String> {
+    let pkg_unit = find_primary_unit(root, package)?;
+    let Some(pkg_guard) = pkg_unit.unit.get() else {
+        return Err(format!("'{package}' has not been analyzed"));
+    };
+    let AnyDesignUnit::Primary(AnyPrimaryUnit::Package(pkg)) = pkg_guard.data() else {
+        return Err(format!("'{package}' is not a package"));
+    };
+    let Some((end_pos, indent)) = package_end(&pkg_unit.tokens, pkg) else {
+        return Err(format!("Could not find the end of package '{package}'"));
+    };
+
+    let mut last_line = range.end.line;
+    if range.end.character == 0 && last_line > range.start.line {
+        // A selection of whole lines ends at the start of the following line
+        last_line -= 1;
+    }
+    let mut searcher = SignalDeclarations {
+        root,
+        source,
+        lines: (range.start.line, last_line),
+        signals: Vec::new(),
+        declared: Vec::new(),
+    };
+    let _ = root.search_source(source, &mut searcher);
+    let signals = searcher.signals;
+
+    if signals.len() < 2 {
+        return Err("Expected the declarations of at least two signals".to_owned());
+    }
+    let Some(architecture) = signals[0]
+        .ent
+        .parent
+        .filter(|parent| matches!(parent.kind(), AnyEntKind::Design(Design::Architecture(..))))
+    else {
+        return Err("Only signals declared in an architecture can be bundled".to_owned());
+    };
+    for signal in signals.iter() {
+        if signal.ent.parent.map(|parent| parent.id()) != Some(architecture.id()) {
+            return Err("The signals must be declared in the same architecture".to_owned());
+        }
+        if let AnyEntKind::Object(object) = signal.ent.kind() {
+            let type_mark = object.subtype.type_mark();
+            if type_mark
+                .parent
+                .is_some_and(|parent| parent.id() == architecture.id())
+            {
+                return Err(format!(
+                    "The type of '{}' is declared within the architecture",
+                    signal.ent.designator()
+                ));
+            }
+        }
+    }
+
+    let names: Vec<String> = signals
+        .iter()
+        .map(|signal| signal.ent.designator().to_string())
+        .collect();
+    let prefix_len = common_prefix_len(&names);
+    if prefix_len < 2 {
+        return Err("The signals must have a common prefix such as 'rx_'".to_owned());
+    }
+    let name = &names[0][..prefix_len - 1];
+    let type_name = format!("{name}_t");
+    let fields: Vec<&str> = names.iter().map(|name| &name[prefix_len..]).collect();
+    if let Some(field) = fields
+        .iter()
+        .find(|field| !field.starts_with(|chr: char| chr.is_ascii_alphabetic()))
+    {
+        return Err(format!("'{field}' is not a valid name of a record field"));
+    }
+
+    if is_declared(&searcher.declared, architecture, name, root) {
+        return Err(format!("'{name}' is already declared"));
+    }
+    if pkg.decl.iter().any(|decl| {
+        matches!(decl.item, Declaration::Type(ref typ)
+            if typ.ident.tree.item.name_utf8().eq_ignore_ascii_case(&type_name))
+    }) {
+        return Err(format!("'{type_name}' is already declared in '{package}'"));
+    }
+
+    let defaults: Vec<&String> = signals
+        .iter()
+        .filter_map(|signal| signal.default.as_ref())
+        .collect();
+    let default = if defaults.is_empty() {
+        String::new()
+    } else if defaults.len() == signals.len() {
+        let elements: Vec<String> = fields
+            .iter()
+            .zip(defaults)
+            .map(|(field, default)| format!("{field} => {default}"))
+            .collect();
+        format!(" := ({})", elements.join(", "))
+    } else {
+        return Err("Either all or none of the signals must have a default value".to_owned());
+    };
+
+    let width = fields.iter().map(|field| field.len()).max().unwrap_or(0);
+    let mut record = format!("\n{indent}type {type_name} is record\n");
+    for (field, signal) in fields.iter().zip(signals.iter()) {
+        record.push_str(&format!(
+            "{indent}{indent}{field:width$} : {};\n",
+            signal.subtype
+        ));
+    }
+    record.push_str(&format!("{indent}end record;\n"));
+    let mut edits = vec![insert_before_end(&end_pos, record)];
+
+    // Each identifier of `signal a, b : bit;` shares the same declaration
+    let mut declarations: Vec<&SrcPos> = Vec::new();
+    for signal in signals.iter() {
+        if declarations.contains(&&signal.decl_pos) {
+            continue;
+        }
+        let edit = if declarations.is_empty() {
+            let text = format!("signal {name} : {type_name}{default};");
+            TextEdit::replace(signal.decl_pos.clone(), text)
+        } else {
+            TextEdit::replace(whole_lines(&signal.decl_pos), "")
+        };
+        declarations.push(&signal.decl_pos);
+        edits.push(edit);
+    }
+
+    for (signal, field) in signals.iter().zip(fields.iter()) {
+        for pos in root.find_all_references(signal.ent) {
+            if Some(&pos) == signal.ent.decl_pos() {
+                continue;
+            }
+            let edit = TextEdit::replace(pos, format!("{name}.{field}"));
+            // A source file mapped to several libraries is searched once per library
+            if !edits.contains(&edit) {
+                edits.push(edit);
+            }
+        }
+    }
+
+    // Make the record type visible within the architecture
+    for unit in root.units_by_source(source) {
+        let Some(data) = unit.unit.get() else {
+            continue;
+        };
+        let design_unit = data.data();
+        let AnyDesignUnit::Secondary(AnySecondaryUnit::Architecture(arch)) = design_unit else {
+            continue;
+        };
+        if arch.ident.decl.get() != Some(architecture.id()) {
+            continue;
+        }
+        let Some(package) = pkg.ident.decl.get() else {
+            break;
+        };
+        let mut is_used = uses_all_of(root, &unit.tokens, design_unit.context_clause(), package);
+        // The context clause of the entity applies to the architecture as well
+        if let Some(entity_unit) = root
+            .get_lib(unit.unit_id().library_name())
+            .and_then(|library| library.primary_unit(&arch.entity_name.item.item))
+        {
+            if let Some(entity) = entity_unit.unit.get() {
+                let context_clause = entity.data().context_clause();
+                is_used |= uses_all_of(root, &entity_unit.tokens, context_clause, package);
+            }
+        }
+        if !is_used {
+            let pkg_id = pkg_unit.unit_id();
+            let (library, pkg_name) = (pkg_id.library_name(), pkg_id.primary_name());
+            let text = if library == unit.unit_id().library_name() {
+                format!("use work.{pkg_name}.all;\n")
+            } else {
+                format!("library {library};\nuse {library}.{pkg_name}.all;\n")
+            };
+            edits.push(TextEdit::insert(&design_unit.get_pos(&unit.tokens), text));
+        }
+        break;
+    }
+
+    Ok(edits)
+}
+
+/// The length of the longest common prefix of the names that ends with `_`, ignoring case
+fn common_prefix_len(names: &[String]) -> usize {
+    let Some((first, others)) = names.split_first() else {
+        return 0;
+    };
+    let common = others
+        .iter()
+        .map(|name| {
+            first
+                .bytes()
+                .zip(name.bytes())
+                .take_while(|(left, right)| left.eq_ignore_ascii_case(right))
+                .count()
+        })
+        .min()
+        .unwrap_or(0);
+    first[..common].rfind('_').map_or(0, |idx| idx + 1)
+}
+
+/// True if `name` is declared within the architecture or the entity it belongs to
+fn is_declared(declared: &[EntRef], architecture: EntRef, name: &str, root: &DesignRoot) -> bool {
+    let designator = Designator::Identifier(root.symbol_utf8(name));
+    let in_architecture = declared.iter().any(|ent| {
+        ent.parent
+            .is_some_and(|parent| parent.id() == architecture.id())
+            && *ent.designator() == designator
+    });
+    let in_entity = match architecture.kind() {
+        AnyEntKind::Design(Design::Architecture(entity)) => match entity.kind() {
+            Design::Entity(_, region) => region.lookup_immediate(&designator).is_some(),
+            _ => false,
+        },
+        _ => false,
+    };
+    in_architecture || in_entity
+}
+
+struct BundledSignal<'a> {
+    ent: EntRef<'a>,
+    /// The position of the whole declaration
+    decl_pos: SrcPos,
+    subtype: String,
+    default: Option<String>,
+}
+
+struct SignalDeclarations<'a> {
+    root: &'a DesignRoot,
+    source: &'a Source,
+    /// The first and last line of the selection
+    lines: (u32, u32),
+    signals: Vec<BundledSignal<'a>>,
+    /// Every declaration of the source file
+    declared: Vec<EntRef<'a>>,
+}
+
+impl<'a> Searcher for SignalDeclarations<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        let Some(ent) = decl.ent_id().map(|id| self.root.get_ent(id)) else {
+            return NotFinished;
+        };
+        // A source file mapped to several libraries is searched once per library
+        if !self.declared.iter().any(|other| other.id() == ent.id()) {
+            self.declared.push(ent);
+        }
+
+        let FoundDeclaration::Object(object) = decl else {
+            return NotFinished;
+        };
+        let decl_pos = ent.src_span.pos(ctx);
+        let (first_line, last_line) = self.lines;
+        if object.class != ObjectClass::Signal
+            || decl_pos.source != *self.source
+            || decl_pos.start().line < first_line
+            || decl_pos.end().line > last_line
+            || self
+                .signals
+                .iter()
+                .any(|signal| signal.ent.id() == ent.id())
+        {
+            return NotFinished;
+        }
+
+        // The subtype indication is found between `:` and either `:=` or `;`
+        let tokens = ctx.get_token_slice(ent.src_span.start_token, ent.src_span.end_token);
+        let Some(colon) = tokens.iter().position(|token| token.kind == Kind::Colon) else {
+            return NotFinished;
+        };
+        let end = tokens
+            .iter()
+            .position(|token| token.kind == Kind::ColonEq)
+            .unwrap_or(tokens.len() - 1);
+        if end <= colon + 1 {
+            return NotFinished;
+        }
+
+        self.signals.push(BundledSignal {
+            ent,
+            decl_pos,
+            subtype: source_text(&tokens[colon + 1].pos.combine(&tokens[end - 1].pos)),
+            default: object
+                .expression
+                .as_ref()
+                .map(|expr| source_text(&expr.pos(ctx))),
+        });
+        NotFinished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::data::Position;
+    use crate::refactor::apply_edits;
+    use crate::syntax::test::Code;
+
+    fn apply(code: &Code, edits: &[TextEdit]) -> String {
+        let edits: Vec<TextEdit> = edits
+            .iter()
+            .filter(|edit| edit.pos.source == *code.source())
+            .cloned()
+            .collect();
+        apply_edits(code.source(), &edits)
+    }
+
+    #[test]
+    fn bundles_signals_into_record() {
+        let mut builder = LibraryBuilder::new();
+        let pkg = builder.code(
+            "libname",
+            "\
+package types_pkg is
+end package;
+",
+        );
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal rx_valid : bit;
+  signal rx_data : bit_vector(7 downto 0);
+  signal other : bit;
+begin
+  rx_valid <= '1';
+  rx_data <= (others => '0');
+  other <= rx_valid;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let selection = Range::new(Position::new(4, 0), Position::new(6, 0));
+        let edits = bundle_signals(&root, code.source(), selection, "libname.types_pkg").unwrap();
+        assert_eq!(
+            apply(&pkg, &edits),
+            "\
+package types_pkg is
+
+  type rx_t is record
+    valid : bit;
+    data  : bit_vector(7 downto 0);
+  end record;
+end package;
+"
+        );
+        assert_eq!(
+            apply(&code, &edits),
+            "\
+entity ent is
+end entity;
+
+use work.types_pkg.all;
+architecture a of ent is
+  signal rx : rx_t;
+  signal other : bit;
+begin
+  rx.valid <= '1';
+  rx.data <= (others => '0');
+  other <= rx.valid;
+end architecture;
+"
+        );
+    }
+
+    #[test]
+    fn bundles_default_values_into_aggregate() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+package pkg is
+  constant width : natural := 8;
+end package;
+
+use work.pkg.all;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal rx_count, rx_total : natural := 0;
+begin
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let selection = code.s1("signal rx_count").pos().range();
+        let edits = bundle_signals(&root, code.source(), selection, "libname.pkg").unwrap();
+        assert_eq!(
+            apply(&code, &edits),
+            "\
+package pkg is
+  constant width : natural := 8;
+
+  type rx_t is record
+    count : natural;
+    total : natural;
+  end record;
+end package;
+
+use work.pkg.all;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal rx : rx_t := (count => 0, total => 0);
+begin
+end architecture;
+"
+        );
+    }
+
+    #[test]
+    fn requires_common_prefix() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "\
+package pkg is
+end package;
+",
+        );
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal valid : bit;
+  signal data : bit;
+begin
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let selection = code
+            .s1("signal valid")
+            .pos()
+            .combine(&code.s1("signal data").pos());
+        assert_eq!(
+            bundle_signals(&root, code.source(), selection.range(), "libname.pkg"),
+            Err("The signals must have a common prefix such as 'rx_'".to_owned())
+        );
+    }
+}
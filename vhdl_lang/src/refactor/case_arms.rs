@@ -0,0 +1,441 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Complete the alternatives of a case statement over an enumeration type
+
+use super::component_declaration::insert_before_end;
+use super::{line_indent, CodeAction};
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::ast::{
+    CaseStatement, Choice, Designator, Expression, Literal, Name, SequentialStatement,
+};
+use crate::data::{Position, Source, SrcPos};
+use crate::named_entity::{AnyEntKind, EntRef, EntityId, OverloadedEnt, Type, TypeEnt};
+use crate::syntax::{Kind, TokenAccess};
+use crate::TokenSpan;
+
+pub fn list_code_actions(root: &DesignRoot, source: &Source, cursor: Position) -> Vec<CodeAction> {
+    let mut searcher = CaseAtCursor {
+        root,
+        source,
+        cursor,
+        found: None,
+    };
+    let _ = root.search_source(source, &mut searcher);
+    let Some(case) = searcher.found else {
+        return Vec::new();
+    };
+
+    let missing: Vec<&Designator> = enum_literals(root, case.typ)
+        .into_iter()
+        .map(|literal| literal.designator())
+        .filter(|literal| !case.covered.contains(literal))
+        .collect();
+    if missing.is_empty() {
+        return Vec::new();
+    }
+
+    let mut text: String = missing
+        .iter()
+        .map(|literal| format!("{}when {literal} =>\n", case.indent))
+        .collect();
+    if !case.has_others {
+        text.push_str(&format!("{}when others =>\n", case.indent));
+    }
+
+    vec![CodeAction {
+        title: format!(
+            "Add missing case alternatives of '{}'",
+            case.typ.designator()
+        ),
+        edits: vec![insert_before_end(&case.insert_pos, text)],
+    }]
+}
+
+/// The innermost case statement at the cursor
+struct CaseArms<'a> {
+    typ: TypeEnt<'a>,
+    /// The literals that already have an alternative
+    covered: Vec<Designator>,
+    has_others: bool,
+    /// The `when` of the `others` alternative or the `end` of the case statement
+    insert_pos: SrcPos,
+    indent: String,
+}
+
+struct CaseAtCursor<'a> {
+    root: &'a DesignRoot,
+    source: &'a Source,
+    cursor: Position,
+    found: Option<CaseArms<'a>>,
+}
+
+impl<'a> CaseAtCursor<'a> {
+    fn case_arms(
+        &self,
+        ctx: &dyn TokenAccess,
+        span: TokenSpan,
+        case: &CaseStatement,
+    ) -> Option<CaseArms<'a>> {
+        let mut covered = Vec::new();
+        let mut literal_type = None;
+        let mut others = None;
+        for choice in case.alternatives.iter().flat_map(|alt| alt.choices.iter()) {
+            match choice.item {
+                Choice::Others => others = Some(choice.span.start_token),
+                Choice::Expression(Expression::Literal(Literal::Character(chr))) => {
+                    covered.push(Designator::Character(chr));
+                }
+                Choice::Expression(Expression::Name(ref name)) => {
+                    let Name::Designator(ref designator) = name.as_ref() else {
+                        return None;
+                    };
+                    let literal = self.root.get_ent(designator.reference.get()?);
+                    literal_type = OverloadedEnt::from_any(literal)?.return_type();
+                    covered.push(literal.designator().clone());
+                }
+                // Ranges and other expressions are not enumerated
+                _ => return None,
+            }
+        }
+
+        let typ = match literal_type {
+            Some(typ) => typ,
+            None => self.expression_type(&case.expression.item)?,
+        }
+        .base_type();
+        if !matches!(typ.kind(), Type::Enum(_)) {
+            return None;
+        }
+
+        let tokens = ctx.get_token_slice(span.start_token, span.end_token);
+        let insert_pos = match others {
+            Some(others) => {
+                let idx = tokens
+                    .iter()
+                    .position(|token| token.pos == *ctx.get_pos(others))?;
+                tokens.get(idx.checked_sub(1)?)?.pos.clone()
+            }
+            None => tokens
+                .iter()
+                .rev()
+                .find(|token| token.kind == Kind::End)?
+                .pos
+                .clone(),
+        };
+        let indent = match case.alternatives.first() {
+            Some(alternative) => line_indent(&alternative.choices.first()?.pos(ctx)),
+            None => format!("{}  ", line_indent(&span.pos(ctx))),
+        };
+
+        Some(CaseArms {
+            typ,
+            covered,
+            has_others: others.is_some(),
+            insert_pos,
+            indent,
+        })
+    }
+
+    /// The type of a case expression that names an object, a record element,
+    /// an array element or the result of a function call
+    fn expression_type(&self, expr: &Expression) -> Option<TypeEnt<'a>> {
+        let Expression::Name(ref name) = expr else {
+            return None;
+        };
+        self.name_type(name)
+    }
+
+    fn name_type(&self, name: &Name) -> Option<TypeEnt<'a>> {
+        if let Name::CallOrIndexed(ref fcall) = name {
+            let function = fcall
+                .name
+                .item
+                .get_suffix_reference()
+                .and_then(|id| OverloadedEnt::from_any(self.root.get_ent(id)));
+            if let Some(function) = function {
+                return function.return_type();
+            }
+            return match self.name_type(&fcall.name.item)?.base_type().kind() {
+                Type::Array { elem_type, .. } => Some(*elem_type),
+                _ => None,
+            };
+        }
+
+        let ent = self.root.get_ent(name.get_suffix_reference()?);
+        match ent.kind() {
+            AnyEntKind::Object(object) => Some(object.subtype.type_mark()),
+            AnyEntKind::ElementDeclaration(subtype) | AnyEntKind::DeferredConstant(subtype) => {
+                Some(subtype.type_mark())
+            }
+            AnyEntKind::ObjectAlias { type_mark, .. } => Some(*type_mark),
+            AnyEntKind::Overloaded(_) => OverloadedEnt::from_any(ent)?.return_type(),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Searcher for CaseAtCursor<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        if let FoundDeclaration::SequentialStatement(stmt) = decl {
+            if let SequentialStatement::Case(ref case) = stmt.statement.item {
+                let pos = stmt.statement.span.pos(ctx);
+                // Enclosing statements are found before the statements within them
+                if pos.source == *self.source && pos.contains(self.cursor) {
+                    self.found = self.case_arms(ctx, stmt.statement.span, case);
+                }
+            }
+        }
+        NotFinished
+    }
+}
+
+/// The literals of an enumeration type in the order of declaration
+pub(crate) fn enum_literals<'a>(root: &'a DesignRoot, typ: TypeEnt) -> Vec<EntRef<'a>> {
+    let Some(source) = typ.decl_pos().map(|pos| pos.source.clone()) else {
+        return Vec::new();
+    };
+    let mut searcher = EnumLiterals {
+        root,
+        typ: typ.id(),
+        literals: Vec::new(),
+    };
+    let _ = root.search_source(&source, &mut searcher);
+    searcher.literals
+}
+
+struct EnumLiterals<'a> {
+    root: &'a DesignRoot,
+    typ: EntityId,
+    literals: Vec<EntRef<'a>>,
+}
+
+impl<'a> Searcher for EnumLiterals<'a> {
+    fn search_decl(&mut self, _ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        if let FoundDeclaration::EnumerationLiteral(_, literal) = decl {
+            let Some(literal) = literal
+                .decl
+                .get()
+                .and_then(|id| OverloadedEnt::from_any(self.root.get_ent(id)))
+            else {
+                return NotFinished;
+            };
+            let is_of_type = literal
+                .return_type()
+                .is_some_and(|typ| typ.id() == self.typ);
+
+            // A source file mapped to several libraries is searched once per library
+            if is_of_type
+                && !self
+                    .literals
+                    .iter()
+                    .any(|other| other.designator() == literal.designator())
+            {
+                self.literals.push(literal.into());
+            }
+        }
+        NotFinished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::refactor::{apply_edits, TextEdit};
+
+    #[test]
+    fn adds_missing_alternatives_and_others() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture a of ent is
+  type state_t is (idle, busy, done);
+  signal state : state_t;
+begin
+  process
+  begin
+    case state is
+      when busy =>
+        state <= done;
+    end case;
+    wait;
+  end process;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let actions = list_code_actions(&root, code.source(), code.s1("case state").start());
+        assert_eq!(actions.len(), 1);
+        assert_eq!(
+            actions[0].title,
+            "Add missing case alternatives of 'state_t'"
+        );
+        assert_eq!(
+            apply_edits(code.source(), &actions[0].edits),
+            "\
+entity ent is
+end entity;
+
+architecture a of ent is
+  type state_t is (idle, busy, done);
+  signal state : state_t;
+begin
+  process
+  begin
+    case state is
+      when busy =>
+        state <= done;
+      when idle =>
+      when done =>
+      when others =>
+    end case;
+    wait;
+  end process;
+end architecture;
+"
+        );
+    }
+
+    #[test]
+    fn inserts_before_existing_others() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture a of ent is
+  type dir_t is ('l', 'r', 'u', 'd');
+  signal dir : dir_t;
+begin
+  process
+  begin
+    case dir is
+      when 'u' | 'd' =>
+        null;
+      when others =>
+        null;
+    end case;
+    wait;
+  end process;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let actions = list_code_actions(&root, code.source(), code.s1("null").start());
+        assert_eq!(
+            actions[0].edits,
+            vec![TextEdit::insert(
+                &code.s1("      when others").pos(),
+                "      when 'l' =>\n      when 'r' =>\n"
+            )]
+        );
+    }
+
+    #[test]
+    fn adds_alternatives_of_record_and_array_elements_and_function_results() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture a of ent is
+  type mode_t is (read, write);
+  type mode_arr_t is array (natural range <>) of mode_t;
+  type request_t is record
+    mode : mode_t;
+    addr : natural;
+  end record;
+  signal req : request_t;
+  signal modes : mode_arr_t(0 to 3);
+
+  function next_mode(mode : mode_t) return mode_t is
+  begin
+    return mode;
+  end function;
+begin
+  process
+  begin
+    case req.mode is
+      when others => null;
+    end case;
+    case modes(0) is
+      when others => null;
+    end case;
+    case next_mode(req.mode) is
+      when others => null;
+    end case;
+    wait;
+  end process;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        for cursor in ["case req.mode", "case modes(0)", "case next_mode"] {
+            let actions = list_code_actions(&root, code.source(), code.s1(cursor).start());
+            assert_eq!(actions.len(), 1, "{cursor}");
+            assert_eq!(
+                actions[0].title,
+                "Add missing case alternatives of 'mode_t'"
+            );
+            assert_eq!(
+                actions[0].edits[0].new_text, "      when read =>\n      when write =>\n",
+                "{cursor}"
+            );
+        }
+    }
+
+    #[test]
+    fn no_action_for_complete_or_non_enumeration_case() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal b : boolean;
+  signal n : natural;
+begin
+  process
+  begin
+    case b is
+      when true => null;
+      when false => null;
+    end case;
+    case n is
+      when 0 => null;
+      when others => null;
+    end case;
+    wait;
+  end process;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        for cursor in [code.s1("case b").start(), code.s1("case n").start()] {
+            assert_eq!(list_code_actions(&root, code.source(), cursor), vec![]);
+        }
+    }
+}
@@ -0,0 +1,403 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Component declarations that match the interface of an entity
+
+use super::declare_signal::declare;
+use super::did_you_mean::is_identifier;
+use super::{find_primary_unit, indent_lines, line_indent, source_text, CodeAction, TextEdit};
+use crate::analysis::{DesignRoot, LockedUnit};
+use crate::ast::{
+    AnyDesignUnit, AnyPrimaryUnit, AnySecondaryUnit, ArchitectureBody, ComponentDeclaration,
+    Declaration, Designator, EntityDeclaration, HasIdent, HasUnitId, PackageDeclaration,
+};
+use crate::data::{ErrorCode, Position, Range, Source, SrcPos};
+use crate::named_entity::{AnyEntKind, Design};
+use crate::syntax::{Kind, TokenAccess};
+use crate::HasTokenSpan;
+
+const DEFAULT_INDENT: &str = "  ";
+
+/// Create or update the component declarations of the given entities
+/// within a package, such that the components match the entity interfaces.
+///
+/// Both the package and the entities are given as selected names, i.e. `lib.name`.
+/// Components already declared in the package are replaced in place,
+/// other components are inserted at the end of the package.
+pub fn update_component_declarations(
+    root: &DesignRoot,
+    package: &str,
+    entities: &[&str],
+) -> Result<Vec<TextEdit>, String> {
+    let pkg_unit = find_primary_unit(root, package)?;
+    let Some(pkg_guard) = pkg_unit.unit.get() else {
+        return Err(format!("'{package}' has not been analyzed"));
+    };
+    let AnyDesignUnit::Primary(AnyPrimaryUnit::Package(pkg)) = pkg_guard.data() else {
+        return Err(format!("'{package}' is not a package"));
+    };
+    let tokens = &pkg_unit.tokens;
+
+    let Some((end_pos, indent)) = package_end(tokens, pkg) else {
+        return Err(format!("Could not find the end of package '{package}'"));
+    };
+
+    let mut edits = Vec::new();
+    let mut inserted = Vec::new();
+
+    for name in entities {
+        let ent_unit = find_primary_unit(root, name)?;
+        let Some(ent_guard) = ent_unit.unit.get() else {
+            return Err(format!("'{name}' has not been analyzed"));
+        };
+        let AnyDesignUnit::Primary(AnyPrimaryUnit::Entity(entity)) = ent_guard.data() else {
+            return Err(format!("'{name}' is not an entity"));
+        };
+
+        let entity_name = entity.name();
+        let text = component_from_entity(entity).to_string();
+
+        let existing = pkg.decl.iter().find_map(|decl| match &decl.item {
+            Declaration::Component(component) if component.ident.tree.item == *entity_name => {
+                Some(decl.span.pos(tokens))
+            }
+            _ => None,
+        });
+
+        if let Some(pos) = existing {
+            let indent = line_indent(&pos);
+            edits.push(TextEdit::replace(pos, indent_lines(&text, &indent)));
+        } else {
+            inserted.push(format!("{indent}{}\n", indent_lines(&text, &indent)));
+        }
+    }
+
+    if !inserted.is_empty() {
+        edits.push(insert_before_end(
+            &end_pos,
+            format!("\n{}", inserted.join("\n")),
+        ));
+    }
+
+    Ok(edits)
+}
+
+/// Declare the component of the entity at the cursor within the architecture
+/// that instantiates it, e.g. at `entity work.child` or at the unresolved
+/// component name of `inst: child port map (...)`
+pub fn list_code_actions(root: &DesignRoot, source: &Source, cursor: Position) -> Vec<CodeAction> {
+    let mut actions = Vec::new();
+
+    for unit in root.units_by_source(source) {
+        let Some(data) = unit.unit.get() else {
+            continue;
+        };
+        let AnyDesignUnit::Secondary(AnySecondaryUnit::Architecture(arch)) = data.data() else {
+            continue;
+        };
+        let tokens: &dyn TokenAccess = &unit.tokens;
+        let begin = tokens.get_pos(arch.begin_token);
+        if cursor < begin.end() || !arch.get_pos(tokens).contains(cursor) {
+            continue;
+        }
+
+        let unresolved = data.result().diagnostics.iter().find_map(|diagnostic| {
+            let name = source_text(&diagnostic.pos);
+            (diagnostic.code == ErrorCode::Unresolved
+                && diagnostic.pos.source == *source
+                && diagnostic.pos.contains(cursor)
+                && is_identifier(&name))
+            .then(|| {
+                (
+                    unit.unit_id().library_name().clone(),
+                    root.symbol_utf8(&name),
+                )
+            })
+        });
+        let entity_name = unresolved.or_else(|| {
+            let (_, ent) = root.item_at_cursor(source, cursor)?;
+            let AnyEntKind::Design(Design::Entity(..)) = ent.kind() else {
+                return None;
+            };
+            let Designator::Identifier(name) = ent.designator() else {
+                return None;
+            };
+            Some((ent.library_name()?.clone(), name.clone()))
+        });
+        let Some((library_name, name)) = entity_name else {
+            continue;
+        };
+        let Some(entity_unit) = root
+            .get_lib(&library_name)
+            .and_then(|library| library.primary_unit(&name))
+        else {
+            continue;
+        };
+
+        let is_declared = arch.decl.iter().any(|decl| {
+            matches!(&decl.item, Declaration::Component(component)
+                if component.ident.tree.item == name)
+        });
+        if is_declared {
+            continue;
+        }
+        let Some(action) = declare_component(tokens, arch, begin, entity_unit) else {
+            continue;
+        };
+        // A source file mapped to several libraries contains the same units once per library
+        if !actions.contains(&action) {
+            actions.push(action);
+        }
+    }
+    actions
+}
+
+fn declare_component(
+    tokens: &dyn TokenAccess,
+    arch: &ArchitectureBody,
+    begin: &SrcPos,
+    entity_unit: &LockedUnit,
+) -> Option<CodeAction> {
+    let data = entity_unit.unit.get()?;
+    let AnyDesignUnit::Primary(AnyPrimaryUnit::Entity(entity)) = data.data() else {
+        return None;
+    };
+    let text = component_from_entity(entity).to_string();
+
+    Some(CodeAction {
+        title: format!(
+            "Declare component '{}' in architecture '{}'",
+            entity.name(),
+            arch.name()
+        ),
+        edits: vec![declare(tokens, arch, begin, &text)],
+    })
+}
+
+/// The position of the `end` of a package and the indentation of its declarations
+pub(super) fn package_end(
+    tokens: &dyn TokenAccess,
+    pkg: &PackageDeclaration,
+) -> Option<(SrcPos, String)> {
+    let end_token = tokens
+        .get_token_slice(pkg.get_start_token(), pkg.get_end_token())
+        .iter()
+        .rev()
+        .find(|token| token.kind == Kind::End)?;
+
+    // Keep the indentation of the existing declarations
+    let indent = pkg
+        .decl
+        .first()
+        .map(|decl| line_indent(&decl.span.pos(tokens)))
+        .unwrap_or_else(|| DEFAULT_INDENT.to_owned());
+    Some((end_token.pos.clone(), indent))
+}
+
+/// Insert `text` before the `end` at `end_pos`, on the lines above it when it starts a line
+pub(super) fn insert_before_end(end_pos: &SrcPos, text: String) -> TextEdit {
+    if is_first_on_line(end_pos) {
+        let line_start = Position::new(end_pos.start().line, 0);
+        TextEdit::insert(
+            &SrcPos::new(end_pos.source.clone(), Range::new(line_start, line_start)),
+            text,
+        )
+    } else {
+        TextEdit::insert(end_pos, text)
+    }
+}
+
+fn component_from_entity(entity: &EntityDeclaration) -> ComponentDeclaration {
+    ComponentDeclaration {
+        span: entity.span,
+        ident: entity.ident.clone(),
+        generic_list: entity.generic_clause.clone().unwrap_or_default(),
+        port_list: entity.port_clause.clone().unwrap_or_default(),
+        end_ident_pos: None,
+    }
+}
+
+fn is_first_on_line(pos: &SrcPos) -> bool {
+    pos.source
+        .contents()
+        .get_line(pos.start().line as usize)
+        .map(|line| {
+            line.chars()
+                .take(pos.start().character as usize)
+                .all(|chr| chr == ' ' || chr == '\t')
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+
+    #[test]
+    fn inserts_missing_component_at_end_of_package() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+entity ent is
+  generic (
+    width : natural
+  );
+  port (
+    clk : in bit;
+    data : out bit_vector(width - 1 downto 0)
+  );
+end entity;",
+        );
+        let pkg = builder.code(
+            "libname",
+            "
+package components is
+  constant c : natural := 0;
+end package;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let edits =
+            update_component_declarations(&root, "libname.components", &["libname.ent"]).unwrap();
+        let end_line = pkg.s1("end package").start().line;
+        assert_eq!(
+            edits,
+            vec![TextEdit::insert(
+                &SrcPos::new(
+                    pkg.source().clone(),
+                    Range::new(Position::new(end_line, 0), Position::new(end_line, 0))
+                ),
+                "
+  component ent
+    generic (
+      width : natural
+    );
+    port (
+      clk : in bit;
+      data : out bit_vector(width - 1 downto 0)
+    );
+  end component;
+"
+            )]
+        );
+    }
+
+    #[test]
+    fn replaces_existing_component() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+entity ent is
+  port (
+    clk : in bit;
+    rst : in bit
+  );
+end entity;",
+        );
+        let pkg = builder.code(
+            "libname",
+            "
+package components is
+    component ent is
+        port (clk : in bit);
+    end component;
+end package;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let edits =
+            update_component_declarations(&root, "libname.components", &["libname.ent"]).unwrap();
+        assert_eq!(
+            edits,
+            vec![TextEdit::replace(
+                pkg.s1("component ent is
+        port (clk : in bit);
+    end component;")
+                    .pos(),
+                "component ent
+      port (
+        clk : in bit;
+        rst : in bit
+      );
+    end component;"
+            )]
+        );
+    }
+
+    #[test]
+    fn declares_component_in_architecture() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity child is
+  generic (width : natural);
+  port (clk : in bit);
+end entity;
+
+entity top is
+end entity;
+
+architecture rtl of top is
+  signal clk : bit;
+begin
+  inst: entity work.child generic map (width => 8) port map (clk => clk);
+  comp: child generic map (width => 8) port map (clk => clk);
+end architecture;",
+        );
+        let (root, _) = builder.get_analyzed_root();
+
+        let begin_line = code.s1("begin").start().line;
+        let expected = vec![CodeAction {
+            title: "Declare component 'child' in architecture 'rtl'".to_owned(),
+            edits: vec![TextEdit::insert(
+                &SrcPos::new(
+                    code.source().clone(),
+                    Range::new(Position::new(begin_line, 0), Position::new(begin_line, 0)),
+                ),
+                "  component child
+    generic (
+      width : natural
+    );
+    port (
+      clk : in bit
+    );
+  end component;
+",
+            )],
+        }];
+        let cursor = code.s1("work.child").s1("child").start();
+        assert_eq!(list_code_actions(&root, code.source(), cursor), expected);
+        let cursor = code.s1("comp: child").s1("child").start();
+        assert_eq!(list_code_actions(&root, code.source(), cursor), expected);
+    }
+
+    #[test]
+    fn errors_on_non_package() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+entity ent is
+end entity;",
+        );
+        let (root, _) = builder.get_analyzed_root();
+
+        assert_eq!(
+            update_component_declarations(&root, "libname.ent", &["libname.ent"]),
+            Err("'libname.ent' is not a package".to_owned())
+        );
+        assert_eq!(
+            update_component_declarations(&root, "libname.missing", &["libname.ent"]),
+            Err("No primary unit 'missing' within library 'libname'".to_owned())
+        );
+    }
+}
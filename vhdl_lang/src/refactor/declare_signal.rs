@@ -0,0 +1,228 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Declare a signal of an architecture that is used but not declared
+
+use super::did_you_mean::is_identifier;
+use super::{indent_lines, line_indent, source_text, CodeAction, TextEdit};
+use crate::analysis::DesignRoot;
+use crate::ast::{AnyDesignUnit, AnySecondaryUnit, ArchitectureBody};
+use crate::data::{ErrorCode, Position, Range, Source, SrcPos};
+use crate::named_entity::{AnyEntKind, EntRef};
+use crate::syntax::{Kind, Token, TokenAccess};
+use crate::HasTokenSpan;
+
+/// The type of the signal when the context of its use does not tell
+const DEFAULT_SUBTYPE: &str = "bit";
+
+pub fn list_code_actions(root: &DesignRoot, source: &Source, cursor: Position) -> Vec<CodeAction> {
+    let mut actions = Vec::new();
+
+    for unit in root.units_by_source(source) {
+        let Some(data) = unit.unit.get() else {
+            continue;
+        };
+        let AnyDesignUnit::Secondary(AnySecondaryUnit::Architecture(arch)) = data.data() else {
+            continue;
+        };
+        let tokens: &dyn TokenAccess = &unit.tokens;
+        let begin = tokens.get_pos(arch.begin_token);
+
+        for diagnostic in data.result().diagnostics.iter() {
+            if diagnostic.code != ErrorCode::Unresolved
+                || diagnostic.pos.source != *source
+                || !diagnostic.pos.contains(cursor)
+                || diagnostic.pos.start() < begin.end()
+            {
+                continue;
+            }
+            let name = source_text(&diagnostic.pos);
+            if !is_identifier(&name) {
+                continue;
+            }
+            let idx = unit
+                .tokens
+                .iter()
+                .position(|token| token.pos == diagnostic.pos);
+            // The name of an instantiated component
+            if idx.is_some_and(|idx| {
+                unit.tokens
+                    .get(idx + 1)
+                    .is_some_and(|token| matches!(token.kind, Kind::Generic | Kind::Port))
+            }) {
+                continue;
+            }
+            let subtype = idx
+                .and_then(|idx| infer_subtype(root, &unit.tokens, idx))
+                .unwrap_or_else(|| DEFAULT_SUBTYPE.to_owned());
+
+            let action = CodeAction {
+                title: format!("Declare signal '{name}'"),
+                edits: vec![declare(
+                    tokens,
+                    arch,
+                    begin,
+                    &format!("signal {name} : {subtype};"),
+                )],
+            };
+            // A source file mapped to several libraries contains the same units once per library
+            if !actions.contains(&action) {
+                actions.push(action);
+            }
+        }
+    }
+    actions
+}
+
+/// Insert `declaration` on lines of its own after the last declaration of the architecture
+pub(super) fn declare(
+    tokens: &dyn TokenAccess,
+    arch: &ArchitectureBody,
+    begin: &SrcPos,
+    declaration: &str,
+) -> TextEdit {
+    let indent = match arch.decl.last() {
+        Some(decl) => line_indent(&decl.span.pos(tokens)),
+        None => format!("{}  ", line_indent(&arch.get_pos(tokens))),
+    };
+    let declaration = indent_lines(declaration, &indent);
+    if line_indent(begin).len() == begin.start().character as usize {
+        let line_start = Position::new(begin.start().line, 0);
+        TextEdit::insert(
+            &SrcPos::new(begin.source.clone(), Range::new(line_start, line_start)),
+            format!("{indent}{declaration}\n"),
+        )
+    } else {
+        TextEdit::insert(
+            begin,
+            format!("\n{indent}{declaration}\n{}", line_indent(begin)),
+        )
+    }
+}
+
+/// The subtype of the object on the other side of an assignment or association
+/// of the name at token `idx`, e.g. `target <= name;`, `name <= value;` or `formal => name`
+fn infer_subtype(root: &DesignRoot, tokens: &[Token], idx: usize) -> Option<String> {
+    let before = &tokens[..idx];
+    let after = &tokens[idx + 1..];
+    let other = match (before, after) {
+        ([.., other, arrow], [end, ..])
+            if matches!(arrow.kind, Kind::LTE | Kind::ColonEq)
+                && matches!(end.kind, Kind::SemiColon | Kind::When | Kind::After) =>
+        {
+            other
+        }
+        ([.., other, arrow], [end, ..])
+            if arrow.kind == Kind::RightArrow
+                && matches!(end.kind, Kind::Comma | Kind::RightPar) =>
+        {
+            other
+        }
+        ([.., end], [arrow, other, semi, ..])
+            if matches!(
+                end.kind,
+                Kind::SemiColon | Kind::Begin | Kind::Then | Kind::Else
+            ) && matches!(arrow.kind, Kind::LTE | Kind::ColonEq)
+                && semi.kind == Kind::SemiColon =>
+        {
+            other
+        }
+        _ => return None,
+    };
+    if other.kind != Kind::Identifier {
+        return None;
+    }
+    let ent = root.search_reference(&other.pos.source, other.pos.start())?;
+    subtype_indication(root, ent)
+}
+
+/// The text of the subtype indication of the declaration of an object,
+/// e.g. `bit_vector(7 downto 0)` of `data : in bit_vector(7 downto 0) := (others => '0')`
+fn subtype_indication(root: &DesignRoot, ent: EntRef) -> Option<String> {
+    let AnyEntKind::Object(object) = ent.kind() else {
+        return None;
+    };
+    let decl_pos = ent.decl_pos()?;
+    let text = root.units_by_source(&decl_pos.source).find_map(|unit| {
+        let data = unit.unit.get()?;
+        let tokens: &dyn TokenAccess = &unit.tokens;
+        if !data.data().get_pos(tokens).contains(decl_pos.start()) {
+            return None;
+        }
+        let declaration = tokens.get_token_slice(ent.src_span.start_token, ent.src_span.end_token);
+        let colon = declaration
+            .iter()
+            .position(|token| token.kind == Kind::Colon)?;
+        let subtype = declaration[colon + 1..]
+            .iter()
+            .skip_while(|token| {
+                matches!(
+                    token.kind,
+                    Kind::In | Kind::Out | Kind::InOut | Kind::Buffer | Kind::Linkage
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut depth = 0;
+        let mut end = None;
+        for token in subtype.iter() {
+            match token.kind {
+                Kind::LeftPar => depth += 1,
+                Kind::RightPar if depth == 0 => break,
+                Kind::RightPar => depth -= 1,
+                Kind::ColonEq | Kind::SemiColon | Kind::Bus | Kind::Register if depth == 0 => break,
+                _ => {}
+            }
+            end = Some(token);
+        }
+        let pos = subtype.first()?.pos.combine(&end?.pos);
+        Some(source_text(&pos))
+    });
+    text.or_else(|| Some(object.subtype.type_mark().designator().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+    use crate::refactor::apply_edits;
+
+    #[test]
+    fn declares_signal_with_subtype_of_assignment_target() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+  port (data : out bit_vector(7 downto 0));
+end entity;
+
+architecture a of ent is
+  signal valid : boolean;
+begin
+  data <= buffered;
+  flag <= valid;
+  other <= '1';
+end architecture;",
+        );
+        let (root, _) = builder.get_analyzed_root();
+
+        let declared = |name: &str| {
+            let actions = list_code_actions(&root, code.source(), code.s1(name).start());
+            assert_eq!(actions.len(), 1);
+            apply_edits(code.source(), &actions[0].edits)
+        };
+        assert!(declared("buffered").contains(
+            "  signal valid : boolean;\n  signal buffered : bit_vector(7 downto 0);\nbegin"
+        ));
+        assert!(declared("flag").contains("  signal flag : boolean;\nbegin"));
+        assert!(declared("other").contains("  signal other : bit;\nbegin"));
+        assert_eq!(
+            list_code_actions(&root, code.source(), code.s1("data <=").start()),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,372 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Quick fixes for names that could not be resolved: use a similar name that is visible
+//! or make the name visible with a use clause of the package that declares it,
+//! or with a library clause of the library with that name
+
+use super::{line_indent, source_text, CodeAction, TextEdit};
+use crate::analysis::{DesignRoot, LockedUnit};
+use crate::ast::{AnyDesignUnit, AnyPrimaryUnit, ContextItem, Designator, HasUnitId};
+use crate::data::{ErrorCode, Position, Range, Source, SrcPos, Symbol};
+use crate::named_entity::{AnyEntKind, Design};
+use crate::HasTokenSpan;
+
+pub fn list_code_actions(root: &DesignRoot, source: &Source, cursor: Position) -> Vec<CodeAction> {
+    let mut actions = Vec::new();
+
+    for unit in root.units_by_source(source) {
+        let Some(data) = unit.unit.get() else {
+            continue;
+        };
+        for diagnostic in data.result().diagnostics.iter() {
+            if diagnostic.code != ErrorCode::Unresolved
+                || diagnostic.pos.source != *source
+                || !diagnostic.pos.contains(cursor)
+            {
+                continue;
+            }
+            let name = source_text(&diagnostic.pos);
+            if !is_identifier(&name) {
+                continue;
+            }
+
+            // The similar names that analysis suggests as related information
+            for (pos, message) in diagnostic.related.iter() {
+                if !message.starts_with("Did you mean") {
+                    continue;
+                }
+                let similar = source_text(pos);
+                let action = CodeAction {
+                    title: format!("Change to '{similar}'"),
+                    edits: vec![TextEdit::replace(diagnostic.pos.clone(), similar)],
+                };
+                if !actions.contains(&action) {
+                    actions.push(action);
+                }
+            }
+
+            if let Some(action) = add_library_clause(root, unit, data.data(), &name) {
+                if !actions.contains(&action) {
+                    actions.push(action);
+                }
+            }
+
+            // A name that only one package declares is fixed by `auto_imports`
+            let imports = import_actions(root, unit, data.data(), &name);
+            if imports.len() > 1 {
+                for action in imports {
+                    if !actions.contains(&action) {
+                        actions.push(action);
+                    }
+                }
+            }
+        }
+    }
+    actions
+}
+
+/// The quick fixes that make an unresolved name within `source` visible
+/// when exactly one package of the design declares it, together with the name
+pub fn auto_imports(root: &DesignRoot, source: &Source) -> Vec<(SrcPos, CodeAction)> {
+    let mut fixes = Vec::new();
+
+    for unit in root.units_by_source(source) {
+        let Some(data) = unit.unit.get() else {
+            continue;
+        };
+        for diagnostic in data.result().diagnostics.iter() {
+            if diagnostic.code != ErrorCode::Unresolved || diagnostic.pos.source != *source {
+                continue;
+            }
+            let name = source_text(&diagnostic.pos);
+            if !is_identifier(&name) {
+                continue;
+            }
+            let mut imports = import_actions(root, unit, data.data(), &name);
+            if imports.len() != 1 {
+                continue;
+            }
+            let fix = (diagnostic.pos.clone(), imports.remove(0));
+            // A source file mapped to several libraries contains the same units once per library
+            if !fixes.contains(&fix) {
+                fixes.push(fix);
+            }
+        }
+    }
+    fixes
+}
+
+/// Use clauses of the packages that declare `name`
+fn import_actions(
+    root: &DesignRoot,
+    unit: &LockedUnit,
+    design_unit: &AnyDesignUnit,
+    name: &str,
+) -> Vec<CodeAction> {
+    packages_declaring(root, &root.symbol_utf8(name))
+        .into_iter()
+        .filter_map(|(library, package)| use_package(unit, design_unit, &library, &package))
+        .collect()
+}
+
+pub(super) fn is_identifier(name: &str) -> bool {
+    name.starts_with(|chr: char| chr.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|chr| chr.is_ascii_alphanumeric() || chr == '_')
+}
+
+/// The library and name of the packages that declare `name`
+fn packages_declaring(root: &DesignRoot, name: &Symbol) -> Vec<(Symbol, Symbol)> {
+    let designator = Designator::Identifier(name.clone());
+    let mut packages = Vec::new();
+    for library in root.libraries() {
+        for unit in library.primary_units() {
+            let Some(data) = unit.unit.get() else {
+                continue;
+            };
+            let AnyDesignUnit::Primary(AnyPrimaryUnit::Package(package)) = data.data() else {
+                continue;
+            };
+            let Some(id) = package.ident.decl.get() else {
+                continue;
+            };
+            if let AnyEntKind::Design(Design::Package(_, region)) = root.get_ent(id).kind() {
+                if region.lookup_immediate(&designator).is_some() {
+                    packages.push((
+                        library.name().clone(),
+                        unit.unit_id().primary_name().clone(),
+                    ));
+                }
+            }
+        }
+    }
+    packages.sort_by_key(|(library, package)| (library.name_utf8(), package.name_utf8()));
+    packages
+}
+
+/// Insert a library clause before the use clauses of the design unit
+/// when `name` is the name of a library of the design
+fn add_library_clause(
+    root: &DesignRoot,
+    unit: &LockedUnit,
+    design_unit: &AnyDesignUnit,
+    name: &str,
+) -> Option<CodeAction> {
+    let library = root.get_lib(&root.symbol_utf8(name))?;
+    let pos = design_unit
+        .context_clause()
+        .iter()
+        .find(|item| matches!(item, ContextItem::Use(_)))
+        .map(|item| item.get_pos(&unit.tokens))
+        .unwrap_or_else(|| design_unit.get_pos(&unit.tokens));
+
+    Some(CodeAction {
+        title: format!("Add 'library {}'", library.name()),
+        edits: vec![TextEdit::insert(
+            &pos,
+            format!("library {};\n{}", library.name(), line_indent(&pos)),
+        )],
+    })
+}
+
+/// Insert a use clause of all declarations of the package after the context clause
+/// of the design unit, together with a library clause when the library is not yet visible
+fn use_package(
+    unit: &LockedUnit,
+    design_unit: &AnyDesignUnit,
+    library: &Symbol,
+    package: &Symbol,
+) -> Option<CodeAction> {
+    let unit_id = unit.unit_id();
+    if unit_id.primary_name() == package && unit_id.library_name() == library {
+        return None;
+    }
+    let prefix = if unit_id.library_name() == library {
+        format!("work.{package}")
+    } else {
+        format!("{library}.{package}")
+    };
+    let has_library_clause = unit_id.library_name() == library
+        || library.name_utf8() == "std"
+        || design_unit.context_clause().iter().any(|item| {
+            matches!(item, ContextItem::Library(clause)
+                if clause.name_list.items.iter().any(|name| name.item.item == *library))
+        });
+    let text = if has_library_clause {
+        format!("use {prefix}.all;\n")
+    } else {
+        format!("library {library};\nuse {prefix}.all;\n")
+    };
+
+    // Comments between the context clause and the design unit document the unit,
+    // so the use clause is added on the line after the last context item
+    let unit_pos = design_unit.get_pos(&unit.tokens);
+    let pos = match design_unit.context_clause().last() {
+        Some(item) if item.get_pos(&unit.tokens).end().line < unit_pos.start().line => {
+            let next_line = Position::new(item.get_pos(&unit.tokens).end().line + 1, 0);
+            SrcPos::new(unit_pos.source.clone(), Range::new(next_line, next_line))
+        }
+        _ => unit_pos,
+    };
+
+    Some(CodeAction {
+        title: format!("Add 'use {prefix}.all'"),
+        edits: vec![TextEdit::insert(&pos, text)],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+
+    #[test]
+    fn changes_to_similar_name() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal counter : natural;
+begin
+  counter <= countr + 1;
+end architecture;",
+        );
+        let (root, _) = builder.get_analyzed_root();
+        let actions = list_code_actions(&root, code.source(), code.s1("countr").start());
+        assert_eq!(
+            actions,
+            vec![CodeAction {
+                title: "Change to 'counter'".to_owned(),
+                edits: vec![TextEdit::replace(code.s1("countr").pos(), "counter")],
+            }]
+        );
+    }
+
+    #[test]
+    fn uses_package_that_declares_name() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "\
+package pkg is
+  constant depth : natural := 8;
+end package;",
+        );
+        builder.code(
+            "otherlib",
+            "\
+package other_pkg is
+  constant depth : natural := 16;
+end package;",
+        );
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal mem : bit_vector(0 to depth - 1);
+begin
+end architecture;",
+        );
+        let (root, _) = builder.get_analyzed_root();
+        let actions = list_code_actions(&root, code.source(), code.s1("depth").start());
+        let insert_pos = code.s1("architecture").pos();
+        assert_eq!(
+            actions,
+            vec![
+                CodeAction {
+                    title: "Add 'use work.pkg.all'".to_owned(),
+                    edits: vec![TextEdit::insert(&insert_pos, "use work.pkg.all;\n")],
+                },
+                CodeAction {
+                    title: "Add 'use otherlib.other_pkg.all'".to_owned(),
+                    edits: vec![TextEdit::insert(
+                        &insert_pos,
+                        "library otherlib;\nuse otherlib.other_pkg.all;\n"
+                    )],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn auto_imports_unique_package_after_context_clause() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "\
+package pkg is
+  constant depth : natural := 8;
+end package;",
+        );
+        let code = builder.code(
+            "libname",
+            "\
+use std.textio.all;
+
+-- The top level
+entity ent is
+  generic (size : natural := depth);
+end entity;",
+        );
+        let (root, _) = builder.get_analyzed_root();
+
+        let line = code.s1("use std").start().line + 1;
+        let insert_pos = SrcPos::new(
+            code.source().clone(),
+            Range::new(Position::new(line, 0), Position::new(line, 0)),
+        );
+        let fix = CodeAction {
+            title: "Add 'use work.pkg.all'".to_owned(),
+            edits: vec![TextEdit::insert(&insert_pos, "use work.pkg.all;\n")],
+        };
+        assert_eq!(
+            auto_imports(&root, code.source()),
+            vec![(code.s1("depth").pos(), fix.clone())]
+        );
+        assert!(!list_code_actions(&root, code.source(), code.s1("depth").start()).contains(&fix));
+    }
+
+    #[test]
+    fn adds_library_clause_before_use_clauses() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "otherlib",
+            "\
+package other_pkg is
+  constant depth : natural := 16;
+end package;",
+        );
+        let code = builder.code(
+            "libname",
+            "\
+use otherlib.other_pkg.all;
+
+entity ent is
+end entity;",
+        );
+        let (root, _) = builder.get_analyzed_root();
+        let actions = list_code_actions(&root, code.source(), code.s1("otherlib").start());
+        assert_eq!(
+            actions,
+            vec![CodeAction {
+                title: "Add 'library otherlib'".to_owned(),
+                edits: vec![TextEdit::insert(
+                    &code.s1("use").pos(),
+                    "library otherlib;\n"
+                )],
+            }]
+        );
+    }
+}
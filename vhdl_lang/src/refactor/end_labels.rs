@@ -0,0 +1,335 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Repeat the name of design units, subprograms, components, record and protected types
+//! and labeled statements after `end`
+
+use super::{CodeAction, TextEdit};
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::ast::{
+    ConcurrentStatement, SequentialStatement, SubprogramDesignator, SubprogramSpecification,
+    TypeDefinition,
+};
+use crate::data::{Position, Source, SrcPos, Symbol};
+use crate::syntax::{Kind, TokenAccess};
+use crate::TokenSpan;
+
+pub fn list_code_actions(root: &DesignRoot, source: &Source, cursor: Position) -> Vec<CodeAction> {
+    let missing = find_missing_end_labels(root, source);
+
+    // Enclosing constructs are found before the constructs within them
+    let Some(innermost) = missing
+        .iter()
+        .rev()
+        .find(|label| label.pos.contains(cursor))
+    else {
+        return Vec::new();
+    };
+
+    let mut actions = vec![CodeAction {
+        title: format!("Add end label '{}'", innermost.name),
+        edits: vec![innermost.edit.clone()],
+    }];
+    if missing.len() > 1 {
+        actions.push(CodeAction {
+            title: "Add all missing end labels".to_owned(),
+            edits: missing.into_iter().map(|label| label.edit).collect(),
+        });
+    }
+    actions
+}
+
+/// Add the end label of every design unit, subprogram body, component, record type,
+/// protected type and labeled compound statement within the source file that lacks one
+pub fn add_missing_end_labels(root: &DesignRoot, source: &Source) -> Vec<TextEdit> {
+    find_missing_end_labels(root, source)
+        .into_iter()
+        .map(|label| label.edit)
+        .collect()
+}
+
+struct MissingEndLabel {
+    /// The position of the construct that lacks the end label
+    pos: SrcPos,
+    name: String,
+    edit: TextEdit,
+}
+
+fn find_missing_end_labels(root: &DesignRoot, source: &Source) -> Vec<MissingEndLabel> {
+    let mut searcher = EndLabelSearcher {
+        source,
+        missing: Vec::new(),
+    };
+    let _ = root.search_source(source, &mut searcher);
+    searcher.missing
+}
+
+struct EndLabelSearcher<'a> {
+    source: &'a Source,
+    missing: Vec<MissingEndLabel>,
+}
+
+impl<'a> EndLabelSearcher<'a> {
+    /// Insert the name just before the final semicolon of `span`
+    fn add(&mut self, ctx: &dyn TokenAccess, span: TokenSpan, name: &Symbol) {
+        let pos = span.pos(ctx);
+        if pos.source != *self.source || ctx.get_token(span.end_token).kind != Kind::SemiColon {
+            return;
+        }
+        let edit = TextEdit::insert(ctx.get_pos(span.end_token), format!(" {name}"));
+
+        // A source file mapped to several libraries is searched once per library
+        if !self.missing.iter().any(|label| label.edit == edit) {
+            self.missing.push(MissingEndLabel {
+                pos,
+                name: name.to_string(),
+                edit,
+            });
+        }
+    }
+}
+
+impl<'a> Searcher for EndLabelSearcher<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        match decl {
+            FoundDeclaration::Entity(entity) if entity.end_ident_pos.is_none() => {
+                self.add(ctx, entity.span, &entity.ident.tree.item);
+            }
+            FoundDeclaration::Architecture(arch) if arch.end_ident_pos.is_none() => {
+                self.add(ctx, arch.span, &arch.ident.tree.item);
+            }
+            FoundDeclaration::Package(package) if package.end_ident_pos.is_none() => {
+                self.add(ctx, package.span, &package.ident.tree.item);
+            }
+            FoundDeclaration::PackageBody(body) if body.end_ident_pos.is_none() => {
+                self.add(ctx, body.span, &body.ident.tree.item);
+            }
+            FoundDeclaration::Configuration(config) if config.end_ident_pos.is_none() => {
+                self.add(ctx, config.span, &config.ident.tree.item);
+            }
+            FoundDeclaration::Context(context) if context.end_ident_pos.is_none() => {
+                self.add(ctx, context.span, &context.ident.tree.item);
+            }
+            FoundDeclaration::Component(component) if component.end_ident_pos.is_none() => {
+                self.add(ctx, component.span, &component.ident.tree.item);
+            }
+            FoundDeclaration::Subprogram(body) if body.end_ident_pos.is_none() => {
+                let designator = match body.specification {
+                    SubprogramSpecification::Procedure(ref procedure) => &procedure.designator,
+                    SubprogramSpecification::Function(ref function) => &function.designator,
+                };
+                // Operator symbols are not repeated as they would need to be quoted
+                if let SubprogramDesignator::Identifier(ref name) = designator.tree.item {
+                    self.add(ctx, body.span, name);
+                }
+            }
+            FoundDeclaration::Type(type_decl) if type_decl.end_ident_pos.is_none() => {
+                if matches!(
+                    type_decl.def,
+                    TypeDefinition::Record(_)
+                        | TypeDefinition::Protected(_)
+                        | TypeDefinition::ProtectedBody(_)
+                        | TypeDefinition::Physical(_)
+                ) {
+                    self.add(ctx, type_decl.span, &type_decl.ident.tree.item);
+                }
+            }
+            FoundDeclaration::ConcurrentStatement(stmt) => {
+                if let Some(ref label) = stmt.label.tree {
+                    let end_label_pos = match stmt.statement.item {
+                        ConcurrentStatement::Process(ref process) => &process.end_label_pos,
+                        ConcurrentStatement::Block(ref block) => &block.end_label_pos,
+                        ConcurrentStatement::ForGenerate(ref gen) => &gen.end_label_pos,
+                        ConcurrentStatement::IfGenerate(ref gen) => &gen.end_label_pos,
+                        ConcurrentStatement::CaseGenerate(ref gen) => &gen.end_label_pos,
+                        _ => return NotFinished,
+                    };
+                    if end_label_pos.is_none() {
+                        self.add(ctx, stmt.statement.span, &label.item);
+                    }
+                }
+            }
+            FoundDeclaration::SequentialStatement(stmt) => {
+                if let Some(ref label) = stmt.label.tree {
+                    let end_label_pos = match stmt.statement.item {
+                        SequentialStatement::Loop(ref loop_stmt) => &loop_stmt.end_label_pos,
+                        SequentialStatement::If(ref if_stmt) => &if_stmt.end_label_pos,
+                        SequentialStatement::Case(ref case_stmt) => &case_stmt.end_label_pos,
+                        _ => return NotFinished,
+                    };
+                    if end_label_pos.is_none() {
+                        self.add(ctx, stmt.statement.span, &label.item);
+                    }
+                }
+            }
+            _ => {}
+        }
+        NotFinished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+
+    #[test]
+    fn adds_end_label_of_innermost_construct() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture a of ent is
+begin
+  main: process
+  begin
+    wait;
+  end process;
+end architecture a;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let end_entity = TextEdit::insert(&code.s1("end entity;").s1(";").pos(), " ent");
+        let end_process = TextEdit::insert(&code.s1("end process;").s1(";").pos(), " main");
+        assert_eq!(
+            list_code_actions(&root, code.source(), code.s1("wait").start()),
+            vec![
+                CodeAction {
+                    title: "Add end label 'main'".to_owned(),
+                    edits: vec![end_process.clone()],
+                },
+                CodeAction {
+                    title: "Add all missing end labels".to_owned(),
+                    edits: vec![end_entity, end_process],
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn adds_end_labels_of_generates_and_loops() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end ent;
+
+architecture a of ent is
+begin
+  gen: for i in 0 to 1 generate
+  end generate;
+
+  process
+  begin
+    outer: loop
+      inner: loop
+      end loop inner;
+    end loop;
+  end process;
+end;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        assert_eq!(
+            add_missing_end_labels(&root, code.source()),
+            vec![
+                TextEdit::insert(&code.s1("end;").s1(";").pos(), " a"),
+                TextEdit::insert(&code.s1("end generate;").s1(";").pos(), " gen"),
+                TextEdit::insert(&code.s1("end loop;").s1(";").pos(), " outer"),
+            ]
+        );
+    }
+
+    #[test]
+    fn adds_end_labels_of_units_and_declarations() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+package pkg is
+  type rec_t is record
+    field : natural;
+  end record;
+
+  component comp is
+  end component;
+
+  function fn return natural;
+  function \"+\"(left, right : rec_t) return rec_t;
+end package;
+
+package body pkg is
+  function fn return natural is
+  begin
+    check: if true then
+      return 0;
+    end if;
+    return 1;
+  end function;
+
+  function \"+\"(left, right : rec_t) return rec_t is
+  begin
+    return left;
+  end function;
+end package body;
+
+context ctx is
+  library libname;
+end context;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        assert_eq!(
+            add_missing_end_labels(&root, code.source()),
+            vec![
+                TextEdit::insert(&code.s1("end package;").s1(";").pos(), " pkg"),
+                TextEdit::insert(&code.s1("end record;").s1(";").pos(), " rec_t"),
+                TextEdit::insert(&code.s1("end component;").s1(";").pos(), " comp"),
+                TextEdit::insert(&code.s1("end package body;").s1(";").pos(), " pkg"),
+                TextEdit::insert(&code.s1("end function;").s1(";").pos(), " fn"),
+                TextEdit::insert(&code.s1("end if;").s1(";").pos(), " check"),
+                TextEdit::insert(&code.s1("end context;").s1(";").pos(), " ctx"),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_action_for_unlabeled_statements() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity ent;
+
+architecture a of ent is
+begin
+  process
+  begin
+    wait;
+  end process;
+end architecture a;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+        assert_eq!(
+            list_code_actions(&root, code.source(), code.s1("wait").start()),
+            vec![]
+        );
+    }
+}
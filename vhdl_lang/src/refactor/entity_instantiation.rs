@@ -0,0 +1,222 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Rewrite a component instantiation into a direct instantiation of the entity
+//! that the component is bound to by default
+
+use super::{whole_lines, CodeAction, TextEdit};
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, Search, SearchState, Searcher};
+use crate::ast::{
+    AnyDesignUnit, AnyKind, AnySecondaryUnit, ConcurrentStatement, Designator, HasUnitId,
+    InstantiatedUnit, PrimaryKind,
+};
+use crate::data::{Position, Source, SrcPos};
+use crate::named_entity::EntityId;
+use crate::syntax::{Kind, TokenAccess};
+use crate::HasTokenSpan;
+
+/// Replace the component name of the instantiation at the cursor with the entity of the same name
+/// in the library of the architecture, e.g. `inst: child port map (...)` with
+/// `inst: entity work.child(rtl) port map (...)`. The architecture is only named when the entity
+/// has exactly one. If the component is declared in the architecture and only instantiated at
+/// the cursor, removing the component declaration is offered as well.
+pub fn list_code_actions(root: &DesignRoot, source: &Source, cursor: Position) -> Vec<CodeAction> {
+    let mut actions = Vec::new();
+
+    for unit in root.units_by_source(source) {
+        let Some(data) = unit.unit.get() else {
+            continue;
+        };
+        let AnyDesignUnit::Secondary(AnySecondaryUnit::Architecture(_)) = data.data() else {
+            continue;
+        };
+        let mut searcher = ComponentInstance {
+            source,
+            cursor,
+            instance: None,
+        };
+        let _ = data.data().search(&unit.tokens, &mut searcher);
+        let Some((component, name_pos)) = searcher.instance else {
+            continue;
+        };
+
+        let component = root.get_ent(component);
+        let Designator::Identifier(name) = component.designator() else {
+            continue;
+        };
+        let Some(library) = root.get_lib(unit.unit_id().library_name()) else {
+            continue;
+        };
+        if !library
+            .primary_unit(name)
+            .is_some_and(|entity| entity.kind() == AnyKind::Primary(PrimaryKind::Entity))
+        {
+            continue;
+        }
+        let architectures: Vec<_> = library
+            .secondary_units(name)
+            .filter_map(|unit| unit.unit_id().secondary_name())
+            .collect();
+        let entity = match architectures[..] {
+            [architecture] => format!("work.{name}({architecture})"),
+            _ => format!("work.{name}"),
+        };
+
+        let instantiate = TextEdit::replace(name_pos.clone(), format!("entity {entity}"));
+        let action = CodeAction {
+            title: format!("Instantiate entity '{entity}' directly"),
+            edits: vec![instantiate.clone()],
+        };
+        // A source file mapped to several libraries contains the same units once per library
+        if actions.contains(&action) {
+            continue;
+        }
+        actions.push(action);
+
+        // The component must be declared within the architecture such that
+        // all other references to it are found within the source file
+        let Some(decl_pos) = component.decl_pos() else {
+            continue;
+        };
+        let tokens: &dyn TokenAccess = &unit.tokens;
+        if decl_pos.source != *source || !data.data().get_pos(tokens).contains(decl_pos.start()) {
+            continue;
+        }
+        let declaration = component.src_span.pos(tokens);
+        let is_used_elsewhere = root
+            .find_all_references_in_source(source, component)
+            .iter()
+            .any(|pos| !declaration.contains(pos.start()) && !name_pos.contains(pos.start()));
+        if !is_used_elsewhere {
+            actions.push(CodeAction {
+                title: format!(
+                    "Instantiate entity '{entity}' directly and remove component '{name}'"
+                ),
+                edits: vec![
+                    TextEdit::replace(whole_lines(&declaration), ""),
+                    instantiate,
+                ],
+            });
+        }
+    }
+    actions
+}
+
+struct ComponentInstance<'a> {
+    source: &'a Source,
+    cursor: Position,
+    /// The component of the instantiation at the cursor and the position of its name,
+    /// including the `component` keyword
+    instance: Option<(EntityId, SrcPos)>,
+}
+
+impl<'a> Searcher for ComponentInstance<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        let FoundDeclaration::ConcurrentStatement(stmt) = decl else {
+            return NotFinished;
+        };
+        let ConcurrentStatement::Instance(ref instance) = stmt.statement.item else {
+            return NotFinished;
+        };
+        let InstantiatedUnit::Component(ref name) = instance.unit else {
+            return NotFinished;
+        };
+        let pos = stmt.statement.span.pos(ctx);
+        // The cursor may also be on the label of the instance
+        let labeled_pos = match stmt.label.tree {
+            Some(ref label) => ctx.get_pos(label.token).combine(&pos),
+            None => pos,
+        };
+        if labeled_pos.source != *self.source || !labeled_pos.contains(self.cursor) {
+            return NotFinished;
+        }
+        let Some(id) = instance.entity_reference() else {
+            return NotFinished;
+        };
+
+        let mut name_pos = name.pos(ctx);
+        if let [.., keyword, _] =
+            ctx.get_token_slice(stmt.statement.span.start_token, name.span.start_token)
+        {
+            if keyword.kind == Kind::Component {
+                name_pos = keyword.pos.combine(&name_pos);
+            }
+        }
+        self.instance = Some((id, name_pos));
+        NotFinished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::refactor::apply_edits;
+
+    #[test]
+    fn instantiates_bound_entity_directly() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "\
+entity child is
+  port (clk : in bit);
+end entity;
+
+architecture rtl of child is
+begin
+end architecture;",
+        );
+        let code = builder.code(
+            "libname",
+            "\
+entity top is
+end entity;
+
+architecture rtl of top is
+  signal clk : bit;
+
+  component child is
+    port (clk : in bit);
+  end component;
+begin
+  inst: component child
+    port map (clk => clk);
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let actions = list_code_actions(&root, code.source(), code.s1("inst").start());
+        assert_eq!(
+            actions
+                .iter()
+                .map(|action| action.title.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "Instantiate entity 'work.child(rtl)' directly",
+                "Instantiate entity 'work.child(rtl)' directly and remove component 'child'",
+            ]
+        );
+        assert!(apply_edits(code.source(), &actions[0].edits)
+            .contains("  inst: entity work.child(rtl)\n    port map (clk => clk);"));
+        assert_eq!(
+            apply_edits(code.source(), &actions[1].edits),
+            "\
+entity top is
+end entity;
+
+architecture rtl of top is
+  signal clk : bit;
+
+begin
+  inst: entity work.child(rtl)
+    port map (clk => clk);
+end architecture;"
+        );
+    }
+}
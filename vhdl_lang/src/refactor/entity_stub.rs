@@ -0,0 +1,506 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Create the entity of an instantiation or component declaration that refers to an entity
+//! that does not exist yet
+
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::ast::{
+    AbstractLiteral, ActualPart, ComponentDeclaration, ConcurrentStatement, Designator,
+    EntityDeclaration, Expression, InstantiatedUnit, InstantiationStatement, Literal, MapAspect,
+    Mode, Name,
+};
+use crate::data::{Position, Source, Symbol};
+use crate::named_entity::{
+    AnyEntKind, Design, InterfaceMode, ObjectInterface, OverloadedEnt, TypeEnt,
+};
+use crate::syntax::TokenAccess;
+use std::path::PathBuf;
+
+/// An entity inferred from an instantiation or component declaration, to be written to a new file
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct EntityStub {
+    pub name: String,
+    /// The file to create, in the same directory as the file of the instantiation or component
+    pub file_name: PathBuf,
+    /// The text of the new file, i.e. the entity together with its context clause
+    pub text: String,
+}
+
+/// Create an entity for the direct entity instantiation at the cursor when the entity
+/// does not exist. The generics and ports are named after the formals of the association
+/// lists and typed after the actuals. Ports are of mode `in` unless the actual is a port
+/// of mode `out`, `inout` or `buffer`.
+/// Returns `None` when an association is positional or the type of an actual is unknown.
+///
+/// At a component declaration, an entity with the generics and ports of the component
+/// and an empty architecture is created when the library of the component has no such entity.
+pub fn entity_stub(root: &DesignRoot, source: &Source, cursor: Position) -> Option<EntityStub> {
+    let mut searcher = InstanceAtCursor {
+        root,
+        source,
+        cursor,
+        library: None,
+        stub: None,
+    };
+    let _ = root.search_source(source, &mut searcher);
+    searcher.stub
+}
+
+struct InstanceAtCursor<'a> {
+    root: &'a DesignRoot,
+    source: &'a Source,
+    cursor: Position,
+    /// The library of the architecture that is searched
+    library: Option<Symbol>,
+    stub: Option<EntityStub>,
+}
+
+impl<'a> Searcher for InstanceAtCursor<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        match decl {
+            FoundDeclaration::Architecture(arch) => {
+                self.library = arch
+                    .ident
+                    .decl
+                    .get()
+                    .and_then(|id| self.root.get_ent(id).library_name().cloned());
+            }
+            FoundDeclaration::Component(component) => {
+                let pos = component.span.pos(ctx);
+                if pos.source == *self.source && pos.contains(self.cursor) {
+                    self.stub = self
+                        .stub
+                        .take()
+                        .or_else(|| stub_of_component(self.root, self.source, component));
+                }
+            }
+            FoundDeclaration::ConcurrentStatement(stmt) => {
+                let pos = stmt.statement.span.pos(ctx);
+                if pos.source != *self.source || !pos.contains(self.cursor) {
+                    return NotFinished;
+                }
+                if let ConcurrentStatement::Instance(ref instance) = stmt.statement.item {
+                    if let Some(library) = self.library.as_ref() {
+                        self.stub = self.stub.take().or_else(|| {
+                            stub_of_instance(self.root, self.source, library, instance)
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+        NotFinished
+    }
+}
+
+/// A generic or port of the new entity
+struct Interface<'a> {
+    name: String,
+    mode: Option<Mode>,
+    typ: TypeEnt<'a>,
+}
+
+fn stub_of_instance(
+    root: &DesignRoot,
+    source: &Source,
+    library: &Symbol,
+    instance: &InstantiationStatement,
+) -> Option<EntityStub> {
+    let InstantiatedUnit::Entity(ref name, _) = instance.unit else {
+        return None;
+    };
+    let suffix = match name.item {
+        Name::Designator(ref designator) => designator,
+        Name::Selected(ref prefix, ref suffix) => {
+            // Only entities of the library of the instantiation can be created next to it
+            let prefix = root.get_ent(prefix.item.get_suffix_reference()?);
+            if prefix.library_name() != Some(library) {
+                return None;
+            }
+            &suffix.item
+        }
+        _ => return None,
+    };
+    if suffix.reference.is_defined() {
+        return None;
+    }
+    let Designator::Identifier(ref entity_name) = suffix.item else {
+        return None;
+    };
+    let entity_name = entity_name.name_utf8();
+
+    let generics = interfaces(root, instance.generic_map.as_ref(), false)?;
+    let ports = interfaces(root, instance.port_map.as_ref(), true)?;
+
+    let mut text = context_clause(
+        library,
+        generics.iter().chain(ports.iter()).map(|iface| iface.typ),
+    );
+    text.push_str(&format!("entity {entity_name} is\n"));
+    text.push_str(&interface_list("generic", &generics));
+    text.push_str(&interface_list("port", &ports));
+    text.push_str("end entity;\n");
+
+    Some(EntityStub {
+        file_name: stub_file_name(source, &entity_name),
+        name: entity_name,
+        text,
+    })
+}
+
+fn stub_of_component(
+    root: &DesignRoot,
+    source: &Source,
+    component: &ComponentDeclaration,
+) -> Option<EntityStub> {
+    let ent = root.get_ent(component.ident.decl.get()?);
+    let AnyEntKind::Component(region) = ent.kind() else {
+        return None;
+    };
+    let library = ent.library_name()?;
+    if root
+        .get_lib(library)?
+        .primary_unit(&component.ident.tree.item)
+        .is_some()
+    {
+        return None;
+    }
+    let entity_name = component.ident.tree.item.name_utf8();
+
+    let (ports, generics) = region.ports_and_generics();
+    let mut text = context_clause(
+        library,
+        generics
+            .iter()
+            .chain(ports.iter())
+            .map(|iface| iface.type_mark()),
+    );
+    text.push_str(&entity_from_component(component).to_string());
+    text.push_str(&format!(
+        "\n\narchitecture rtl of {entity_name} is\nbegin\nend architecture;\n"
+    ));
+
+    Some(EntityStub {
+        file_name: stub_file_name(source, &entity_name),
+        name: entity_name,
+        text,
+    })
+}
+
+fn entity_from_component(component: &ComponentDeclaration) -> EntityDeclaration {
+    EntityDeclaration {
+        span: component.span,
+        context_clause: Vec::new(),
+        ident: component.ident.clone(),
+        generic_clause: Some(component.generic_list.clone()),
+        port_clause: Some(component.port_list.clone()),
+        decl: Vec::new(),
+        statements: Vec::new(),
+        end_ident_pos: None,
+    }
+}
+
+/// The new file is named after the entity, next to `source` and with the same extension
+fn stub_file_name(source: &Source, entity_name: &str) -> PathBuf {
+    let path = source.file_name();
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("vhd");
+    path.with_file_name(format!("{entity_name}.{ext}"))
+}
+
+/// The library and use clauses of the packages that declare the types, followed by a blank line
+fn context_clause<'a>(library: &Symbol, types: impl Iterator<Item = TypeEnt<'a>>) -> String {
+    let mut packages: Vec<(String, String)> = Vec::new();
+    for typ in types {
+        if let Some(package) = package_of(library, typ) {
+            if !packages.contains(&package) {
+                packages.push(package);
+            }
+        }
+    }
+
+    let mut text = String::new();
+    let mut libraries: Vec<&str> = Vec::new();
+    for (library_name, _) in packages.iter() {
+        if !["work", "std"].contains(&library_name.as_str())
+            && !libraries.contains(&library_name.as_str())
+        {
+            libraries.push(library_name);
+            text.push_str(&format!("library {library_name};\n"));
+        }
+    }
+    for (library_name, package_name) in packages.iter() {
+        text.push_str(&format!("use {library_name}.{package_name}.all;\n"));
+    }
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text
+}
+
+fn interfaces<'a>(
+    root: &'a DesignRoot,
+    map: Option<&MapAspect>,
+    is_port: bool,
+) -> Option<Vec<Interface<'a>>> {
+    let Some(map) = map else {
+        return Some(Vec::new());
+    };
+
+    map.list
+        .items
+        .iter()
+        .map(|assoc| {
+            let Name::Designator(ref formal) = assoc.formal.as_ref()?.item else {
+                return None;
+            };
+            let ActualPart::Expression(ref actual) = assoc.actual.item else {
+                return None;
+            };
+            let (typ, mode) = actual_type(root, actual)?;
+            Some(Interface {
+                name: formal.item.to_string(),
+                mode: is_port.then_some(mode),
+                typ,
+            })
+        })
+        .collect()
+}
+
+/// The type of an actual and the mode of a port that is associated with it
+fn actual_type<'a>(root: &'a DesignRoot, actual: &Expression) -> Option<(TypeEnt<'a>, Mode)> {
+    let standard_type = |name: &str| {
+        let AnyEntKind::Design(Design::Package(_, region)) = root.standard_package()?.kind() else {
+            return None;
+        };
+        let ent = region.lookup_immediate(&Designator::Identifier(root.symbol_utf8(name)))?;
+        TypeEnt::from_any(ent.first())
+    };
+
+    match actual {
+        Expression::Name(name) => {
+            let ent = root.get_ent(name.get_suffix_reference()?);
+            match ent.kind() {
+                AnyEntKind::Object(object) => {
+                    let mode = match object.iface {
+                        Some(ObjectInterface::Port(InterfaceMode::Simple(mode))) => mode,
+                        _ => Mode::In,
+                    };
+                    Some((object.subtype.type_mark(), mode))
+                }
+                AnyEntKind::Overloaded(_) => {
+                    Some((OverloadedEnt::from_any(ent)?.return_type()?, Mode::In))
+                }
+                _ => None,
+            }
+        }
+        Expression::Literal(literal) => {
+            let typ = match literal {
+                Literal::AbstractLiteral(AbstractLiteral::Integer(_)) => "integer",
+                Literal::AbstractLiteral(AbstractLiteral::Real(_)) => "real",
+                Literal::String(_) => "string",
+                _ => return None,
+            };
+            Some((standard_type(typ)?, Mode::In))
+        }
+        _ => None,
+    }
+}
+
+/// The library and package that declare a type, unless the type is from `std.standard`
+fn package_of(library: &Symbol, typ: TypeEnt) -> Option<(String, String)> {
+    let package = typ.parent?;
+    if !matches!(package.kind(), AnyEntKind::Design(Design::Package(..))) {
+        return None;
+    }
+    let package_library = package.library_name()?;
+    let package_name = package.designator().to_string();
+    if package_library.name_utf8() == "std" && package_name == "standard" {
+        return None;
+    }
+
+    let library_name = if package_library == library {
+        "work".to_owned()
+    } else {
+        package_library.name_utf8()
+    };
+    Some((library_name, package_name))
+}
+
+fn interface_list(keyword: &str, interfaces: &[Interface]) -> String {
+    if interfaces.is_empty() {
+        return String::new();
+    }
+    let elements: Vec<String> = interfaces
+        .iter()
+        .map(|iface| {
+            let mode = match iface.mode {
+                Some(Mode::In) => "in ",
+                Some(Mode::Out) => "out ",
+                Some(Mode::InOut) => "inout ",
+                Some(Mode::Buffer) => "buffer ",
+                Some(Mode::Linkage) => "linkage ",
+                None => "",
+            };
+            format!("    {} : {mode}{}", iface.name, iface.typ.designator())
+        })
+        .collect();
+    format!("  {keyword} (\n{}\n  );\n", elements.join(";\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+
+    #[test]
+    fn infers_generics_and_ports_from_associations() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity top is
+  port (
+    clk : in std_logic;
+    dout : out std_logic_vector(7 downto 0)
+  );
+end entity;
+
+architecture a of top is
+  constant depth : natural := 16;
+begin
+  inst: entity work.fifo
+    generic map (
+      depth => depth,
+      name => \"rx\"
+    )
+    port map (
+      clk => clk,
+      data => dout
+    );
+end architecture;
+",
+        );
+        builder.add_std_logic_1164();
+        let (root, _) = builder.get_analyzed_root();
+
+        let stub = entity_stub(&root, code.source(), code.s1("work.fifo").start()).unwrap();
+        assert_eq!(stub.name, "fifo");
+        assert_eq!(
+            stub.file_name,
+            code.source().file_name().with_file_name("fifo.vhd")
+        );
+        assert_eq!(
+            stub.text,
+            "\
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity fifo is
+  generic (
+    depth : NATURAL;
+    name : STRING
+  );
+  port (
+    clk : in STD_LOGIC;
+    data : out STD_LOGIC_VECTOR
+  );
+end entity;
+"
+        );
+    }
+
+    #[test]
+    fn no_stub_for_existing_entity_or_positional_association() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity existing is
+  port (x : in bit);
+end entity;
+
+entity top is
+end entity;
+
+architecture a of top is
+  signal s : bit;
+begin
+  inst0: entity work.existing port map (x => s);
+  inst1: entity work.missing port map (s);
+end architecture;
+",
+        );
+        let (root, _) = builder.get_analyzed_root();
+
+        for cursor in [code.s1("inst0").start(), code.s1("inst1").start()] {
+            assert_eq!(entity_stub(&root, code.source(), cursor), None);
+        }
+    }
+
+    #[test]
+    fn creates_entity_and_architecture_from_component() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity top is
+end entity;
+
+architecture a of top is
+  component fifo is
+    generic (depth : natural := 16);
+    port (clk : in std_logic; data : out std_logic_vector(7 downto 0));
+  end component;
+
+  component top is
+  end component;
+begin
+end architecture;
+",
+        );
+        builder.add_std_logic_1164();
+        let (root, _) = builder.get_analyzed_root();
+
+        let stub = entity_stub(&root, code.source(), code.s1("fifo").start()).unwrap();
+        assert_eq!(stub.name, "fifo");
+        assert_eq!(
+            stub.text,
+            "\
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity fifo is
+  generic (
+    depth : natural := 16
+  );
+  port (
+    clk : in std_logic;
+    data : out std_logic_vector(7 downto 0)
+  );
+end entity;
+
+architecture rtl of fifo is
+begin
+end architecture;
+"
+        );
+        assert_eq!(
+            entity_stub(&root, code.source(), code.s1("component top").start()),
+            None
+        );
+    }
+}
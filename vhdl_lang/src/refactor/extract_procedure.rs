@@ -0,0 +1,411 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Extract sequential statements of a process into a procedure of the process
+
+use super::signal_to_variable::{declare_before_begin, encloses, find_begin};
+use super::{indent_lines, line_indent, source_text, CodeAction, TextEdit};
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::ast::{ConcurrentStatement, ObjectClass, SequentialStatement};
+use crate::data::{Range, Source, SrcPos};
+use crate::named_entity::{AnyEntKind, EntityId, Reference};
+use crate::reference_kind::{classify_references, ReferenceKind};
+use crate::syntax::TokenAccess;
+
+/// Replace the statements of a process within `range` with a call of a new procedure
+/// named `name` that is declared in the process and contains the statements.
+///
+/// The signals and variables that the statements use, as well as the parameters of
+/// enclosing loops, are passed as parameters. Signals are of mode `in`, `out` or `inout`
+/// depending on whether the statements read or assign them. Assigned variables are of
+/// mode `inout` since the statements need not assign them on every path.
+/// Returns `None` unless the range covers a sequence of whole statements that neither
+/// return nor exit or skip a loop that is not part of the statements.
+pub fn extract_procedure(
+    root: &DesignRoot,
+    source: &Source,
+    range: Range,
+    name: &str,
+) -> Option<CodeAction> {
+    let selection = SrcPos::new(source.clone(), range);
+    let mut searcher = SelectionSearcher {
+        selection: &selection,
+        process: None,
+        statements: Vec::new(),
+        references: Vec::new(),
+    };
+    let _ = root.search_source(source, &mut searcher);
+    let process = searcher.process?;
+    if selection.start() < process.begin.end() {
+        return None;
+    }
+
+    // The selected statements that are not part of another selected statement
+    let statements: Vec<&Statement> = searcher
+        .statements
+        .iter()
+        .filter(|stmt| {
+            !searcher
+                .statements
+                .iter()
+                .any(|other| other.pos != stmt.pos && encloses(&other.pos, &stmt.pos))
+        })
+        .collect();
+    let first = statements.iter().min_by_key(|stmt| stmt.pos.start())?;
+    let last = statements.iter().max_by_key(|stmt| stmt.pos.end())?;
+    // Keywords such as `else` or `end if` between the statements belong to an enclosing
+    // statement, i.e. the statements are not a sequence of the same statement list
+    for unit in root.units_by_source(source) {
+        if unit.tokens.iter().any(|token| {
+            encloses(&selection, &token.pos)
+                && !statements
+                    .iter()
+                    .any(|stmt| encloses(&stmt.pos, &token.pos))
+        }) {
+            return None;
+        }
+    }
+    for stmt in searcher.statements.iter() {
+        match stmt.kind {
+            StatementKind::Return => return None,
+            StatementKind::Jump
+                if !searcher.statements.iter().any(|other| {
+                    other.kind == StatementKind::Loop && encloses(&other.pos, &stmt.pos)
+                }) =>
+            {
+                return None
+            }
+            _ => {}
+        }
+    }
+
+    let references: Vec<(SrcPos, EntityId)> = searcher
+        .references
+        .into_iter()
+        .filter(|(pos, _)| statements.iter().any(|stmt| encloses(&stmt.pos, pos)))
+        .collect();
+    let kinds = classify_references(
+        root,
+        references.iter().map(|(pos, _)| pos.clone()).collect(),
+    );
+    let mut parameters: Vec<Parameter> = Vec::new();
+    for ((_, id), (_, kind)) in references.iter().zip(kinds) {
+        let ent = root.get_ent(*id);
+        if ent
+            .decl_pos()
+            .is_some_and(|decl_pos| encloses(&selection, decl_pos))
+        {
+            continue;
+        }
+        let (class, typ) = match ent.kind() {
+            AnyEntKind::Object(object)
+                if matches!(object.class, ObjectClass::Signal | ObjectClass::Variable) =>
+            {
+                (
+                    object.class,
+                    object.subtype.type_mark().designator().to_string(),
+                )
+            }
+            AnyEntKind::LoopParameter(typ) => (
+                ObjectClass::Constant,
+                typ.as_ref()?.designator().to_string(),
+            ),
+            _ => continue,
+        };
+        let idx = match parameters.iter().position(|parameter| parameter.id == *id) {
+            Some(idx) => idx,
+            None => {
+                parameters.push(Parameter {
+                    id: *id,
+                    name: ent.designator().to_string(),
+                    class,
+                    typ,
+                    is_read: false,
+                    is_written: false,
+                });
+                parameters.len() - 1
+            }
+        };
+        if kind == ReferenceKind::Write {
+            parameters[idx].is_written = true;
+        } else {
+            parameters[idx].is_read = true;
+        }
+    }
+
+    let statements_pos = first.pos.combine(&last.pos);
+    let indent = line_indent(&first.pos);
+    let body: Vec<String> = source_text(&statements_pos)
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| match line.strip_prefix(indent.as_str()) {
+            _ if line.trim().is_empty() => String::new(),
+            _ if idx == 0 => format!("  {line}"),
+            Some(line) => format!("  {line}"),
+            None => line.to_owned(),
+        })
+        .collect();
+
+    let (header, call) = if parameters.is_empty() {
+        (format!("procedure {name} is"), format!("{name};"))
+    } else {
+        let declarations: Vec<String> = parameters.iter().map(Parameter::declaration).collect();
+        let actuals: Vec<&str> = parameters
+            .iter()
+            .map(|parameter| parameter.name.as_str())
+            .collect();
+        (
+            format!("procedure {name}({}) is", declarations.join("; ")),
+            format!("{name}({});", actuals.join(", ")),
+        )
+    };
+    let procedure = format!("{header}\nbegin\n{}\nend procedure;", body.join("\n"));
+
+    Some(CodeAction {
+        title: format!("Extract procedure '{name}'"),
+        edits: vec![
+            declare_before_begin(
+                &process.begin,
+                &process.decl_indent,
+                &indent_lines(&procedure, &process.decl_indent),
+            ),
+            TextEdit::replace(statements_pos, call),
+        ],
+    })
+}
+
+struct Parameter {
+    id: EntityId,
+    name: String,
+    class: ObjectClass,
+    typ: String,
+    is_read: bool,
+    is_written: bool,
+}
+
+impl Parameter {
+    fn declaration(&self) -> String {
+        let Parameter { name, typ, .. } = self;
+        match (self.class, self.is_read, self.is_written) {
+            (ObjectClass::Signal, _, false) => format!("signal {name} : in {typ}"),
+            (ObjectClass::Signal, false, true) => format!("signal {name} : out {typ}"),
+            (ObjectClass::Signal, true, true) => format!("signal {name} : inout {typ}"),
+            (ObjectClass::Variable, _, true) => format!("variable {name} : inout {typ}"),
+            _ => format!("{name} : in {typ}"),
+        }
+    }
+}
+
+struct Process {
+    begin: SrcPos,
+    /// The indentation of a new declaration within the process
+    decl_indent: String,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum StatementKind {
+    Loop,
+    /// A `next` or `exit` statement
+    Jump,
+    Return,
+    Other,
+}
+
+struct Statement {
+    /// The statement including its label
+    pos: SrcPos,
+    kind: StatementKind,
+}
+
+struct SelectionSearcher<'a> {
+    selection: &'a SrcPos,
+    /// The process that contains the selection
+    process: Option<Process>,
+    /// The statements within the selection
+    statements: Vec<Statement>,
+    /// The references within the selection and what they refer to
+    references: Vec<(SrcPos, EntityId)>,
+}
+
+impl<'a> Searcher for SelectionSearcher<'a> {
+    fn search_pos_with_ref(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        reference: &Reference,
+    ) -> SearchState {
+        let Some(id) = reference.get() else {
+            return NotFinished;
+        };
+        // A source file mapped to several libraries is searched once per library
+        if encloses(self.selection, pos) && !self.references.iter().any(|(other, _)| other == pos) {
+            self.references.push((pos.clone(), id));
+        }
+        NotFinished
+    }
+
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        match decl {
+            FoundDeclaration::ConcurrentStatement(stmt) if self.process.is_none() => {
+                let ConcurrentStatement::Process(ref process) = stmt.statement.item else {
+                    return NotFinished;
+                };
+                let pos = stmt.statement.span.pos(ctx);
+                if !encloses(&pos, self.selection) {
+                    return NotFinished;
+                }
+                if let Some(begin) = find_begin(ctx, stmt.statement.span, process) {
+                    let decl_indent = match process.decl.last() {
+                        Some(decl) => line_indent(&decl.span.pos(ctx)),
+                        None => format!("{}  ", line_indent(&pos)),
+                    };
+                    self.process = Some(Process { begin, decl_indent });
+                }
+            }
+            FoundDeclaration::SequentialStatement(stmt) => {
+                let pos = stmt.statement.span.pos(ctx);
+                let pos = match stmt.label.tree {
+                    Some(ref label) => label.pos(ctx).combine(&pos),
+                    None => pos,
+                };
+                if !encloses(self.selection, &pos)
+                    || self.statements.iter().any(|other| other.pos == pos)
+                {
+                    return NotFinished;
+                }
+                let kind = match stmt.statement.item {
+                    SequentialStatement::Loop(..) => StatementKind::Loop,
+                    SequentialStatement::Next(..) | SequentialStatement::Exit(..) => {
+                        StatementKind::Jump
+                    }
+                    SequentialStatement::Return(..) => StatementKind::Return,
+                    _ => StatementKind::Other,
+                };
+                self.statements.push(Statement { pos, kind });
+            }
+            _ => {}
+        }
+        NotFinished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::refactor::apply_edits;
+
+    #[test]
+    fn extracts_statements_with_parameters() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+  port (
+    clk : in bit;
+    d : in bit_vector(7 downto 0);
+    q : out bit_vector(7 downto 0)
+  );
+end entity;
+
+architecture a of ent is
+begin
+  process (clk)
+    variable count : natural := 0;
+  begin
+    if clk = '1' then
+      for i in d'range loop
+        q(i) <= d(i);
+
+        count := count + 1;
+      end loop;
+    end if;
+  end process;
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let range = code
+            .s1("q(i) <= d(i);\n\n        count := count + 1;")
+            .pos()
+            .range();
+        let action = extract_procedure(&root, code.source(), range, "copy").unwrap();
+        assert_eq!(action.title, "Extract procedure 'copy'");
+        assert_eq!(
+            apply_edits(code.source(), &action.edits),
+            "\
+entity ent is
+  port (
+    clk : in bit;
+    d : in bit_vector(7 downto 0);
+    q : out bit_vector(7 downto 0)
+  );
+end entity;
+
+architecture a of ent is
+begin
+  process (clk)
+    variable count : natural := 0;
+    procedure copy(signal q : out BIT_VECTOR; i : in INTEGER; \
+signal d : in BIT_VECTOR; variable count : inout NATURAL) is
+    begin
+      q(i) <= d(i);
+
+      count := count + 1;
+    end procedure;
+  begin
+    if clk = '1' then
+      for i in d'range loop
+        copy(q, i, d, count);
+      end loop;
+    end if;
+  end process;
+end architecture;"
+        );
+    }
+
+    #[test]
+    fn no_extraction_of_partial_statements_or_jumps() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture a of ent is
+begin
+  process
+    variable v : natural;
+  begin
+    for i in 0 to 7 loop
+      if i = 3 then
+        v := 0;
+      else
+        next;
+      end if;
+    end loop;
+    wait;
+  end process;
+end architecture;",
+        );
+        let (root, _) = builder.get_analyzed_root();
+
+        for selection in ["v := 0;\n      else", "next;", "if i = 3 then"] {
+            let range = code.s1(selection).pos().range();
+            assert_eq!(
+                extract_procedure(&root, code.source(), range, "proc"),
+                None,
+                "{selection}"
+            );
+        }
+        let range = code.s1("v := 0;").pos().range();
+        assert!(extract_procedure(&root, code.source(), range, "proc").is_some());
+    }
+}
@@ -0,0 +1,244 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Fixes that are safe to apply to all files without review, e.g. by `vhdl_lang --fix`
+
+use super::end_labels::add_missing_end_labels;
+use super::named_association::name_instance_associations;
+use super::unused::remove_unused_declarations;
+use super::TextEdit;
+use crate::analysis::DesignRoot;
+use crate::data::{Position, Range, Source, SrcPos};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FixRule {
+    /// Remove unused signals, variables, constants and use clauses
+    Unused,
+    /// Add the missing end labels
+    EndLabels,
+    /// Remove whitespace at the end of lines
+    Whitespace,
+    /// Convert positional associations of instances into named associations
+    NamedAssociation,
+}
+
+impl FixRule {
+    pub const ALL: [FixRule; 4] = [
+        FixRule::Unused,
+        FixRule::EndLabels,
+        FixRule::Whitespace,
+        FixRule::NamedAssociation,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FixRule::Unused => "unused",
+            FixRule::EndLabels => "end_labels",
+            FixRule::Whitespace => "whitespace",
+            FixRule::NamedAssociation => "named_association",
+        }
+    }
+
+    /// The rules applied when no rules are selected.
+    /// Named associations change the style of the code and must be selected explicitly.
+    pub fn is_default(&self) -> bool {
+        !matches!(self, FixRule::NamedAssociation)
+    }
+}
+
+impl TryFrom<&str> for FixRule {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        FixRule::ALL
+            .into_iter()
+            .find(|rule| rule.name() == value)
+            .ok_or_else(|| {
+                format!(
+                    "'{value}' is not a valid fix rule, expected one of {}",
+                    FixRule::ALL.map(|rule| rule.name()).join(", ")
+                )
+            })
+    }
+}
+
+/// The edits of the rules for the source file.
+/// An edit that overlaps the edit of an earlier rule is dropped.
+pub fn safe_fixes(root: &DesignRoot, source: &Source, rules: &[FixRule]) -> Vec<TextEdit> {
+    let mut edits: Vec<TextEdit> = Vec::new();
+    for rule in rules {
+        let rule_edits = match rule {
+            FixRule::Unused => remove_unused_declarations(root, source),
+            FixRule::EndLabels => add_missing_end_labels(root, source),
+            FixRule::Whitespace => remove_trailing_whitespace(source),
+            FixRule::NamedAssociation => name_instance_associations(root, source),
+        };
+        for edit in rule_edits {
+            if !edits.iter().any(|other| overlaps(&edit.pos, &other.pos)) {
+                edits.push(edit);
+            }
+        }
+    }
+    edits.sort_by_key(|edit| edit.pos.start());
+    edits
+}
+
+/// True if the ranges overlap or are insertions at the same position
+fn overlaps(pos: &SrcPos, other: &SrcPos) -> bool {
+    pos.start() < other.end() && other.start() < pos.end() || pos.start() == other.start()
+}
+
+fn remove_trailing_whitespace(source: &Source) -> Vec<TextEdit> {
+    let contents = source.contents();
+    (0..contents.num_lines())
+        .filter_map(|lineno| {
+            let line = contents.get_line(lineno)?;
+            let line = line.trim_end_matches(['\r', '\n']);
+            let trimmed = line.trim_end_matches([' ', '\t']);
+            if trimmed.len() == line.len() {
+                return None;
+            }
+            let column = |text: &str| text.chars().map(char::len_utf16).sum::<usize>() as u32;
+            Some(TextEdit::replace(
+                SrcPos::new(
+                    source.clone(),
+                    Range::new(
+                        Position::new(lineno as u32, column(trimmed)),
+                        Position::new(lineno as u32, column(line)),
+                    ),
+                ),
+                "",
+            ))
+        })
+        .collect()
+}
+
+/// The edits of a source file as a unified diff of the changed lines
+pub fn unified_diff(source: &Source, edits: &[TextEdit]) -> String {
+    let contents = source.contents();
+    let mut edits: Vec<&TextEdit> = edits.iter().collect();
+    edits.sort_by_key(|edit| edit.pos.start());
+
+    let file_name = source.file_name().to_string_lossy();
+    let mut diff = format!("--- {file_name}\n+++ {file_name}\n");
+    // The number of lines added by the previous hunks
+    let mut offset: i64 = 0;
+    let mut idx = 0;
+    while idx < edits.len() {
+        // Edits on the same lines form a hunk
+        let first_line = edits[idx].pos.start().line;
+        let mut last_line = edits[idx].pos.end().line;
+        let mut end = idx + 1;
+        while end < edits.len() && edits[end].pos.start().line <= last_line {
+            last_line = last_line.max(edits[end].pos.end().line);
+            end += 1;
+        }
+        let hunk = Range::new(
+            Position::new(first_line, 0),
+            Position::new(last_line + 1, 0).min(contents.end()),
+        );
+
+        let old_text = contents.text(hunk);
+        let mut new_text = String::new();
+        let mut start = hunk.start;
+        for edit in edits[idx..end].iter() {
+            new_text.push_str(&contents.text(Range::new(start, edit.pos.start())));
+            new_text.push_str(&edit.new_text);
+            start = edit.pos.end();
+        }
+        new_text.push_str(&contents.text(Range::new(start, hunk.end)));
+
+        let old_lines = old_text.lines().count() as i64;
+        let new_lines = new_text.lines().count() as i64;
+        diff.push_str(&format!(
+            "@@ -{},{old_lines} +{},{new_lines} @@\n",
+            first_line + 1,
+            first_line as i64 + 1 + offset,
+        ));
+        for line in old_text.lines() {
+            diff.push_str(&format!("-{line}\n"));
+        }
+        for line in new_text.lines() {
+            diff.push_str(&format!("+{line}\n"));
+        }
+        offset += new_lines - old_lines;
+        idx = end;
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::refactor::apply_edits;
+    use crate::syntax::test::Code;
+
+    #[test]
+    fn applies_selected_rules() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal unused : bit;
+begin
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let edits = safe_fixes(
+            &root,
+            code.source(),
+            &[FixRule::Whitespace, FixRule::EndLabels],
+        );
+        assert_eq!(
+            apply_edits(code.source(), &edits),
+            "\
+entity ent is
+end entity ent;
+
+architecture a of ent is
+  signal unused : bit;
+begin
+end architecture a;
+"
+        );
+    }
+
+    #[test]
+    fn parses_rule_names() {
+        assert_eq!(FixRule::try_from("end_labels"), Ok(FixRule::EndLabels));
+        assert_eq!(
+            FixRule::try_from("labels"),
+            Err("'labels' is not a valid fix rule, expected one of unused, end_labels, whitespace, named_association".to_owned())
+        );
+    }
+
+    #[test]
+    fn diff_of_edits() {
+        let code = Code::new("entity ent is \t\nend entity;\n");
+        let edits = remove_trailing_whitespace(code.source());
+        assert_eq!(
+            unified_diff(code.source(), &edits),
+            format!(
+                "\
+--- {file_name}
++++ {file_name}
+@@ -1,1 +1,1 @@
+-entity ent is \t
++entity ent is
+",
+                file_name = code.source().file_name().to_string_lossy()
+            )
+        );
+    }
+}
@@ -0,0 +1,173 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Write references with the same case as the name of the declaration, e.g. `Clk` as `clk`
+
+use super::{source_text, CodeAction, TextEdit};
+use crate::analysis::DesignRoot;
+use crate::ast::search::{NotFinished, SearchState, Searcher};
+use crate::data::{Position, Source, SrcPos};
+use crate::named_entity::Reference;
+use crate::syntax::TokenAccess;
+
+pub fn list_code_actions(root: &DesignRoot, source: &Source, cursor: Position) -> Vec<CodeAction> {
+    let edits = match_declaration_case(root, source);
+    let Some(edit) = edits.iter().find(|edit| edit.pos.contains(cursor)) else {
+        return Vec::new();
+    };
+
+    let mut actions = vec![CodeAction {
+        title: format!("Write '{}' as declared", edit.new_text),
+        edits: vec![edit.clone()],
+    }];
+    if edits.len() > 1 {
+        actions.push(CodeAction {
+            title: "Write all identifiers as declared".to_owned(),
+            edits,
+        });
+    }
+    actions
+}
+
+/// Change the case of every reference within the source file whose name only differs
+/// in case from the name of its declaration
+pub fn match_declaration_case(root: &DesignRoot, source: &Source) -> Vec<TextEdit> {
+    let mut searcher = CaseSearcher {
+        root,
+        source,
+        edits: Vec::new(),
+    };
+    let _ = root.search_source(source, &mut searcher);
+    searcher.edits
+}
+
+struct CaseSearcher<'a> {
+    root: &'a DesignRoot,
+    source: &'a Source,
+    edits: Vec<TextEdit>,
+}
+
+impl<'a> Searcher for CaseSearcher<'a> {
+    fn search_pos_with_ref(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        reference: &Reference,
+    ) -> SearchState {
+        let Some(id) = reference.get() else {
+            return NotFinished;
+        };
+        let ent = self.root.get_ent(id);
+        let Some(decl_pos) = ent.decl_pos() else {
+            return NotFinished;
+        };
+        if pos.source != *self.source || pos == decl_pos {
+            return NotFinished;
+        }
+        // The standard libraries are declared in upper case but are commonly written in lower case
+        if ent
+            .library_name()
+            .is_some_and(|library| ["std", "ieee"].contains(&library.name_utf8().as_str()))
+        {
+            return NotFinished;
+        }
+
+        let name = source_text(pos);
+        let declared = source_text(decl_pos);
+        // Extended identifiers are case sensitive and operator symbols have no case to match
+        let is_basic_identifier = name.starts_with(|c: char| c.is_ascii_alphabetic());
+        if is_basic_identifier && name != declared && name.eq_ignore_ascii_case(&declared) {
+            let edit = TextEdit::replace(pos.clone(), declared);
+            // A source file mapped to several libraries is searched once per library
+            if !self.edits.contains(&edit) {
+                self.edits.push(edit);
+            }
+        }
+        NotFinished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+
+    #[test]
+    fn matches_case_of_declarations() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+  port (
+    clk : in bit;
+    Data_In : in bit
+  );
+end entity;
+
+architecture a of ent is
+  signal q : bit;
+begin
+  process (CLK)
+  begin
+    if Clk = '1' then
+      Q <= data_in;
+    end if;
+  end process;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        assert_eq!(
+            match_declaration_case(&root, code.source()),
+            vec![
+                TextEdit::replace(code.s1("CLK").pos(), "clk"),
+                TextEdit::replace(code.s1("Clk").pos(), "clk"),
+                TextEdit::replace(code.s1("Q <=").s1("Q").pos(), "q"),
+                TextEdit::replace(code.s1("data_in;").s1("data_in").pos(), "Data_In"),
+            ]
+        );
+    }
+
+    #[test]
+    fn offers_action_for_reference_at_cursor() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+package pkg is
+  constant Width : natural := 8;
+  constant depth : natural := WIDTH;
+  constant size : natural := width * depth;
+end package;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let first = TextEdit::replace(code.s1("WIDTH").pos(), "Width");
+        let second = TextEdit::replace(code.s1("width *").s1("width").pos(), "Width");
+        assert_eq!(
+            list_code_actions(&root, code.source(), code.s1("WIDTH").start()),
+            vec![
+                CodeAction {
+                    title: "Write 'Width' as declared".to_owned(),
+                    edits: vec![first.clone()],
+                },
+                CodeAction {
+                    title: "Write all identifiers as declared".to_owned(),
+                    edits: vec![first, second],
+                }
+            ]
+        );
+        assert_eq!(
+            list_code_actions(&root, code.source(), code.s1("depth;").start()),
+            vec![]
+        );
+    }
+}
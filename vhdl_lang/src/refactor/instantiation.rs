@@ -0,0 +1,179 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Template of an instantiation of an entity or component
+
+use super::align::align_arrows;
+use super::{find_primary_unit, indent_lines, line_indent, TextEdit};
+use crate::analysis::DesignRoot;
+use crate::ast::{AnyDesignUnit, AnyPrimaryUnit, Designator, HasUnitId};
+use crate::data::{Position, Range, Source, SrcPos};
+use crate::named_entity::{AnyEntKind, Design, Region};
+
+/// Insert an instantiation at the cursor that associates every generic and port
+/// with a signal of the same name.
+///
+/// An entity is given as a selected name such as `lib.ent` and a component
+/// of a package as `lib.pkg.comp`. Lines after the first are indented like the line of the cursor.
+pub fn instantiate(
+    root: &DesignRoot,
+    source: &Source,
+    cursor: Position,
+    name: &str,
+) -> Result<TextEdit, String> {
+    let (unit_name, component) = match name.splitn(3, '.').collect::<Vec<_>>()[..] {
+        [library, package, component] => (format!("{library}.{package}"), Some(component.trim())),
+        _ => (name.to_owned(), None),
+    };
+    let unit = find_primary_unit(root, &unit_name)?;
+    let Some(data) = unit.unit.get() else {
+        return Err(format!("'{unit_name}' has not been analyzed"));
+    };
+    let id = match (data.data(), component) {
+        (AnyDesignUnit::Primary(AnyPrimaryUnit::Entity(entity)), None) => entity.ident.decl.get(),
+        (AnyDesignUnit::Primary(AnyPrimaryUnit::Package(package)), Some(_)) => {
+            package.ident.decl.get()
+        }
+        (_, None) => return Err(format!("'{name}' is not an entity")),
+        (_, Some(_)) => return Err(format!("'{unit_name}' is not a package")),
+    };
+    let Some(id) = id else {
+        return Err(format!("'{unit_name}' has not been analyzed"));
+    };
+
+    let text = match (root.get_ent(id).kind(), component) {
+        (AnyEntKind::Design(Design::Entity(_, region)), None) => {
+            let library_name = unit.unit_id().library_name();
+            // Entities of the library of the source file are referred to through `work`
+            let library = if root
+                .units_by_source(source)
+                .any(|unit| unit.unit_id().library_name() == library_name)
+            {
+                "work".to_owned()
+            } else {
+                library_name.to_string()
+            };
+            let entity_name = unit.unit_id().primary_name();
+            instantiation(
+                &format!("{entity_name}_inst: entity {library}.{entity_name}"),
+                region,
+            )
+        }
+        (AnyEntKind::Design(Design::Package(_, region)), Some(component)) => {
+            let designator = Designator::Identifier(root.symbol_utf8(component));
+            let ent = region
+                .lookup_immediate(&designator)
+                .map(|ents| ents.first())
+                .ok_or_else(|| format!("No component '{component}' within '{unit_name}'"))?;
+            let AnyEntKind::Component(region) = ent.kind() else {
+                return Err(format!("'{name}' is not a component"));
+            };
+            let component_name = ent.designator();
+            instantiation(&format!("{component_name}_inst: {component_name}"), region)
+        }
+        _ => return Err(format!("'{unit_name}' has not been analyzed")),
+    };
+
+    let pos = SrcPos::new(source.clone(), Range::new(cursor, cursor));
+    Ok(TextEdit::insert(
+        &pos,
+        indent_lines(&text, &line_indent(&pos)),
+    ))
+}
+
+/// The instantiation `{header}` followed by the map aspects of the generics and ports of `region`
+pub(super) fn instantiation(header: &str, region: &Region) -> String {
+    let (ports, generics) = region.ports_and_generics();
+    let mut text = header.to_owned();
+
+    for (aspect, formals) in [("generic", generics), ("port", ports)] {
+        if formals.is_empty() {
+            continue;
+        }
+        let last = formals.len() - 1;
+        let mut lines: Vec<String> = formals
+            .iter()
+            .enumerate()
+            .map(|(idx, formal)| {
+                let separator = if idx == last { "" } else { "," };
+                format!("    {0} => {0}{separator}", formal.designator())
+            })
+            .collect();
+        align_arrows(&mut lines);
+        text.push_str(&format!("\n  {aspect} map (\n{}\n  )", lines.join("\n")));
+    }
+    text.push(';');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::refactor::apply_edits;
+
+    #[test]
+    fn instantiates_entities_and_components() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "\
+entity child is
+  generic (width : natural);
+  port (clk : in bit; data : out bit_vector(width - 1 downto 0));
+end entity;
+
+package pkg is
+  component comp is
+    port (enable : in bit);
+  end component;
+end package;",
+        );
+        let code = builder.code(
+            "libname",
+            "\
+entity top is
+end entity;
+
+architecture rtl of top is
+begin
+  \n\
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let cursor = code.s1("begin\n  ").end();
+        let edit = instantiate(&root, code.source(), cursor, "libname.child").unwrap();
+        assert_eq!(
+            apply_edits(code.source(), &[edit]),
+            "\
+entity top is
+end entity;
+
+architecture rtl of top is
+begin
+  child_inst: entity work.child
+    generic map (
+      width => width
+    )
+    port map (
+      clk  => clk,
+      data => data
+    );
+end architecture;"
+        );
+
+        let edit = instantiate(&root, code.source(), cursor, "libname.pkg.comp").unwrap();
+        assert!(apply_edits(code.source(), &[edit]).contains(
+            "  comp_inst: comp\n    port map (\n      enable => enable\n    );\nend architecture;"
+        ));
+        assert_eq!(
+            instantiate(&root, code.source(), cursor, "libname.pkg"),
+            Err("'libname.pkg' is not an entity".to_owned())
+        );
+    }
+}
@@ -0,0 +1,336 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Associate the generics and ports that an instantiation lacks with same-named actuals
+
+use super::align::align_arrows;
+use super::signal_to_variable::base_reference;
+use super::{line_indent, CodeAction, TextEdit};
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::ast::{ConcurrentStatement, InstantiatedUnit, MapAspect};
+use crate::data::{ErrorCode, Position, Range, Source, SrcPos};
+use crate::named_entity::{AnyEntKind, Design, InterfaceEnt};
+use crate::syntax::{TokenAccess, TokenId};
+
+pub fn list_code_actions(root: &DesignRoot, source: &Source, cursor: Position) -> Vec<CodeAction> {
+    // The instantiated unit and the declaration of the formal of each missing association
+    let mut unassociated = Vec::new();
+    for unit in root.units_by_source(source) {
+        let Some(data) = unit.unit.get() else {
+            continue;
+        };
+        for diagnostic in data.result().diagnostics.iter() {
+            if diagnostic.code != ErrorCode::Unassociated || diagnostic.pos.source != *source {
+                continue;
+            }
+            if let Some((decl_pos, _)) = diagnostic.related.first() {
+                unassociated.push((diagnostic.pos.clone(), decl_pos.clone()));
+            }
+        }
+    }
+    if unassociated.is_empty() {
+        return Vec::new();
+    }
+
+    let mut searcher = InstanceSearcher {
+        root,
+        source,
+        cursor,
+        unassociated,
+        action: None,
+    };
+    let _ = root.search_source(source, &mut searcher);
+    searcher.action.into_iter().collect()
+}
+
+struct InstanceSearcher<'a> {
+    root: &'a DesignRoot,
+    source: &'a Source,
+    cursor: Position,
+    unassociated: Vec<(SrcPos, SrcPos)>,
+    action: Option<CodeAction>,
+}
+
+impl<'a> Searcher for InstanceSearcher<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        let FoundDeclaration::ConcurrentStatement(stmt) = decl else {
+            return NotFinished;
+        };
+        let ConcurrentStatement::Instance(ref instance) = stmt.statement.item else {
+            return NotFinished;
+        };
+        let pos = stmt.statement.span.pos(ctx);
+        // The cursor may also be on the label of the instance
+        let labeled_pos = match stmt.label.tree {
+            Some(ref label) => ctx.get_pos(label.token).combine(&pos),
+            None => pos.clone(),
+        };
+        if pos.source != *self.source || !labeled_pos.contains(self.cursor) {
+            return NotFinished;
+        }
+        let unit_pos = match instance.unit {
+            InstantiatedUnit::Component(ref name)
+            | InstantiatedUnit::Entity(ref name, _)
+            | InstantiatedUnit::Configuration(ref name) => name.pos(ctx),
+        };
+        let Some(id) = instance.entity_reference() else {
+            return NotFinished;
+        };
+        let region = match self.root.get_ent(id).kind() {
+            AnyEntKind::Design(Design::Entity(_, region)) | AnyEntKind::Component(region) => region,
+            _ => return NotFinished,
+        };
+        let (ports, generics) = region.ports_and_generics();
+        let is_missing = |formal: &InterfaceEnt| {
+            self.unassociated.iter().any(|(instance_pos, decl_pos)| {
+                *instance_pos == unit_pos && formal.decl_pos() == Some(decl_pos)
+            })
+        };
+        let missing_generics: Vec<_> = generics.iter().copied().filter(is_missing).collect();
+        let missing_ports: Vec<_> = ports.iter().copied().filter(is_missing).collect();
+        if missing_generics.is_empty() && missing_ports.is_empty() {
+            return NotFinished;
+        }
+
+        let indent = line_indent(&pos);
+        let end = stmt.statement.span.end_token;
+        let mut edits = Vec::new();
+        match instance.generic_map {
+            Some(ref map_aspect) => {
+                let associated = associated_formals(map_aspect, &generics);
+                edits.extend(associate(
+                    ctx,
+                    map_aspect,
+                    &associated,
+                    &generics,
+                    &missing_generics,
+                ))
+            }
+            None => edits.extend(add_map_aspect(
+                ctx,
+                stmt.statement.span.start_token,
+                instance
+                    .port_map
+                    .as_ref()
+                    .map_or(end, |port_map| port_map.start),
+                "generic",
+                &missing_generics,
+                &indent,
+            )),
+        }
+        match instance.port_map {
+            Some(ref map_aspect) => {
+                let associated = associated_formals(map_aspect, &ports);
+                edits.extend(associate(
+                    ctx,
+                    map_aspect,
+                    &associated,
+                    &ports,
+                    &missing_ports,
+                ))
+            }
+            None => edits.extend(add_map_aspect(
+                ctx,
+                stmt.statement.span.start_token,
+                end,
+                "port",
+                &missing_ports,
+                &indent,
+            )),
+        }
+
+        let names: Vec<String> = missing_generics
+            .iter()
+            .chain(missing_ports.iter())
+            .map(|formal| formal.designator().to_string())
+            .collect();
+        self.action = Some(CodeAction {
+            title: format!("Associate '{}'", names.join(", ")),
+            edits,
+        });
+        NotFinished
+    }
+}
+
+fn association(formal: &InterfaceEnt) -> String {
+    format!("{0} => {0}", formal.designator())
+}
+
+/// The index of the formal of each association of a map aspect.
+/// Positional associations precede named associations
+/// so the position of the actual is the index of the formal.
+fn associated_formals(map_aspect: &MapAspect, formals: &[InterfaceEnt]) -> Vec<Option<usize>> {
+    map_aspect
+        .list
+        .items
+        .iter()
+        .enumerate()
+        .map(|(idx, elem)| match elem.formal {
+            Some(ref formal) => {
+                let id = base_reference(&formal.item)?;
+                formals.iter().position(|formal| formal.id() == id)
+            }
+            None => Some(idx),
+        })
+        .collect()
+}
+
+/// Insert the missing associations into an existing map aspect, after the association
+/// of the preceding formal such that the associations stay in the order of the declarations.
+/// `associated` is the index of the formal of each association.
+pub(super) fn associate(
+    ctx: &dyn TokenAccess,
+    map_aspect: &MapAspect,
+    associated: &[Option<usize>],
+    formals: &[InterfaceEnt],
+    missing: &[InterfaceEnt],
+) -> Vec<TextEdit> {
+    let elements = &map_aspect.list.items;
+    let Some(first) = elements.first() else {
+        return Vec::new();
+    };
+    let first_pos = first
+        .formal
+        .as_ref()
+        .map_or_else(|| first.actual.pos(ctx), |formal| formal.pos(ctx));
+    // Associations on lines of their own are kept that way
+    let separator = if first_pos.start().line == ctx.get_pos(map_aspect.start).start().line {
+        ", ".to_owned()
+    } else {
+        format!(",\n{}", line_indent(&first_pos))
+    };
+
+    let mut edits: Vec<TextEdit> = Vec::new();
+    for formal in missing {
+        let Some(idx) = formals.iter().position(|other| other.id() == formal.id()) else {
+            continue;
+        };
+        let preceding = associated
+            .iter()
+            .enumerate()
+            .filter_map(|(elem, associated)| Some((elem, (*associated)?)))
+            .filter(|(_, associated)| *associated < idx)
+            .max_by_key(|(_, associated)| *associated)
+            .map(|(elem, _)| elem);
+
+        let edit = match preceding {
+            Some(elem) => TextEdit::insert(
+                &end_of(&elements[elem].actual.pos(ctx)),
+                format!("{separator}{}", association(formal)),
+            ),
+            None => TextEdit::insert(&first_pos, format!("{}{separator}", association(formal))),
+        };
+        // Formals between the same associations are inserted together
+        match edits.last_mut() {
+            Some(last) if last.pos == edit.pos => last.new_text.push_str(&edit.new_text),
+            _ => edits.push(edit),
+        }
+    }
+    edits
+}
+
+/// Insert a map aspect with the missing associations before the `anchor` token,
+/// e.g. the `port` of the port map or the `;` of the instantiation
+pub(super) fn add_map_aspect(
+    ctx: &dyn TokenAccess,
+    start: TokenId,
+    anchor: TokenId,
+    aspect: &str,
+    missing: &[InterfaceEnt],
+    indent: &str,
+) -> Option<TextEdit> {
+    if missing.is_empty() {
+        return None;
+    }
+    let tokens = ctx.get_token_slice(start, anchor);
+    let [.., preceding, _] = tokens else {
+        return None;
+    };
+
+    let last = missing.len() - 1;
+    let mut lines: Vec<String> = missing
+        .iter()
+        .enumerate()
+        .map(|(idx, formal)| {
+            let separator = if idx == last { "" } else { "," };
+            format!("{indent}    {}{separator}", association(formal))
+        })
+        .collect();
+    align_arrows(&mut lines);
+    Some(TextEdit::insert(
+        &end_of(&preceding.pos),
+        format!(
+            "\n{indent}  {aspect} map (\n{}\n{indent}  )",
+            lines.join("\n")
+        ),
+    ))
+}
+
+fn end_of(pos: &SrcPos) -> SrcPos {
+    SrcPos::new(pos.source.clone(), Range::new(pos.end(), pos.end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+    use crate::refactor::apply_edits;
+
+    #[test]
+    fn associates_missing_generics_and_ports() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity child is
+  generic (width : natural; depth : natural := 4);
+  port (clk, rst : in bit; valid : in bit; data : out bit);
+end entity;
+
+entity top is
+end entity;
+
+architecture rtl of top is
+  signal clk, rst, valid, data : bit;
+  constant width : natural := 8;
+begin
+  inst: entity work.child
+    port map (
+      clk => clk,
+      data => data
+    );
+
+  other: entity work.child
+    generic map (width => 8)
+    port map (rst => rst, valid => valid);
+end architecture;",
+        );
+        let (root, _) = builder.get_analyzed_root();
+
+        let actions = list_code_actions(&root, code.source(), code.s1("inst").start());
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].title, "Associate 'width, rst, valid'");
+        assert!(apply_edits(code.source(), &actions[0].edits).contains(
+            "\
+  inst: entity work.child
+    generic map (
+      width => width
+    )
+    port map (
+      clk => clk,
+      rst => rst,
+      valid => valid,
+      data => data
+    );"
+        ));
+
+        let actions = list_code_actions(&root, code.source(), code.s1("other").start());
+        assert_eq!(actions.len(), 1);
+        assert!(apply_edits(code.source(), &actions[0].edits)
+            .contains("    port map (clk => clk, rst => rst, valid => valid);"));
+    }
+}
@@ -0,0 +1,755 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Rewrites of legacy constructs into their VHDL-2008 equivalents
+
+use super::{line_indent, source_text, CodeAction, TextEdit};
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::ast::{
+    ActualPart, AssignmentRightHand, ConcurrentStatement, Expression, IfStatement,
+    LabeledSequentialStatement, Literal, Name, Operator, ProcessStatement, SensitivityList,
+    SequentialStatement, Waveform,
+};
+use crate::data::{Position, Source, SrcPos};
+use crate::named_entity::{ObjectEnt, OverloadedEnt, Reference, TypeEnt};
+use crate::syntax::{Kind, TokenAccess, Value};
+use crate::TokenSpan;
+
+pub fn list_code_actions(root: &DesignRoot, source: &Source, cursor: Position) -> Vec<CodeAction> {
+    let mut searcher = ModernizeSearcher {
+        root,
+        source,
+        cursor,
+        actions: Vec::new(),
+    };
+    let _ = root.search_source(source, &mut searcher);
+    searcher.actions
+}
+
+struct ModernizeSearcher<'a> {
+    root: &'a DesignRoot,
+    source: &'a Source,
+    cursor: Position,
+    actions: Vec<CodeAction>,
+}
+
+impl<'a> ModernizeSearcher<'a> {
+    fn is_at_cursor(&self, pos: &SrcPos) -> bool {
+        pos.source == *self.source && pos.contains(self.cursor)
+    }
+
+    fn add(&mut self, action: Option<CodeAction>) {
+        // A source file mapped to several libraries is searched once per library
+        if let Some(action) = action {
+            if !self.actions.contains(&action) {
+                self.actions.push(action);
+            }
+        }
+    }
+}
+
+impl<'a> Searcher for ModernizeSearcher<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        match decl {
+            FoundDeclaration::ConcurrentStatement(stmt) => {
+                if let ConcurrentStatement::Process(ref process) = stmt.statement.item {
+                    if self.is_at_cursor(&stmt.statement.span.pos(ctx)) {
+                        self.add(sensitivity_list_to_all(ctx, stmt.statement.span, process));
+                    }
+                }
+            }
+            FoundDeclaration::SequentialStatement(stmt) => {
+                if let SequentialStatement::If(ref ifstmt) = stmt.statement.item {
+                    let pos = stmt.statement.span.pos(ctx);
+                    if self.is_at_cursor(&pos) {
+                        self.add(if_to_conditional_assignment(ctx, &pos, ifstmt));
+                        if stmt.label.tree.is_none() {
+                            self.add(if_to_matching_case(ctx, &pos, ifstmt));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        NotFinished
+    }
+
+    fn search_expression(
+        &mut self,
+        ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        expr: &Expression,
+    ) -> SearchState {
+        if self.is_at_cursor(pos) {
+            self.add(remove_vector_conversion(self.root, ctx, pos, expr));
+        }
+        NotFinished
+    }
+}
+
+/// Replace the sensitivity list of a combinational process with `all`.
+/// Clocked processes are left as is since `all` would make them sensitive
+/// to every signal that is read.
+fn sensitivity_list_to_all(
+    ctx: &dyn TokenAccess,
+    span: TokenSpan,
+    process: &ProcessStatement,
+) -> Option<CodeAction> {
+    let Some(SensitivityList::Names(names)) = &process.sensitivity_list else {
+        return None;
+    };
+    let pos = names.first()?.pos(ctx).combine(&names.last()?.pos(ctx));
+
+    if is_clocked(ctx, span) {
+        return None;
+    }
+
+    Some(CodeAction {
+        title: "Replace sensitivity list with 'all'".to_owned(),
+        edits: vec![TextEdit::replace(pos, "all")],
+    })
+}
+
+/// True if the tokens contain a clock edge detection such as
+/// `rising_edge(clk)` or `clk'event`
+pub(super) fn is_clocked(ctx: &dyn TokenAccess, span: TokenSpan) -> bool {
+    let tokens = ctx.get_token_slice(span.start_token, span.end_token);
+    tokens.iter().enumerate().any(|(idx, token)| {
+        let Value::Identifier(ref sym) = token.value else {
+            return false;
+        };
+        let name = sym.name_utf8();
+        if name.eq_ignore_ascii_case("rising_edge") || name.eq_ignore_ascii_case("falling_edge") {
+            true
+        } else {
+            name.eq_ignore_ascii_case("event") && idx > 0 && tokens[idx - 1].kind == Kind::Tick
+        }
+    })
+}
+
+/// Rewrite an if statement where every branch consists of a single assignment
+/// to the same signal into a conditional signal assignment, i.e.
+///
+/// ```vhdl
+/// if sel = '1' then
+///   q <= a;
+/// else
+///   q <= b;
+/// end if;
+/// ```
+///
+/// becomes `q <= a when sel = '1' else b;`.
+/// Nested if statements in the else branch are flattened.
+fn if_to_conditional_assignment(
+    ctx: &dyn TokenAccess,
+    pos: &SrcPos,
+    ifstmt: &IfStatement,
+) -> Option<CodeAction> {
+    let mut target = None;
+    let mut arms = Vec::new();
+    let mut else_arm = None;
+    collect_arms(ctx, ifstmt, &mut target, &mut arms, &mut else_arm)?;
+    let target = target?;
+
+    let indent = format!("{}{}", line_indent(pos), " ".repeat(target.len() + 4));
+    let mut text = format!("{target} <= ");
+    for (idx, (waveform, condition)) in arms.iter().enumerate() {
+        if idx > 0 {
+            text.push_str(" else\n");
+            text.push_str(&indent);
+        }
+        text.push_str(&format!("{waveform} when {condition}"));
+    }
+    if let Some(waveform) = else_arm {
+        text.push_str(" else\n");
+        text.push_str(&indent);
+        text.push_str(&waveform);
+    }
+    text.push(';');
+
+    Some(CodeAction {
+        title: "Convert to conditional signal assignment".to_owned(),
+        edits: vec![TextEdit::replace(pos.clone(), text)],
+    })
+}
+
+/// Collect the `(waveform, condition)` arms of an if statement.
+/// Returns `None` if the if statement cannot be expressed as a conditional signal assignment.
+fn collect_arms(
+    ctx: &dyn TokenAccess,
+    ifstmt: &IfStatement,
+    target: &mut Option<String>,
+    arms: &mut Vec<(String, String)>,
+    else_arm: &mut Option<String>,
+) -> Option<()> {
+    for cond in ifstmt.conds.conditionals.iter() {
+        let waveform = single_assignment(ctx, &cond.item, target)?;
+        arms.push((waveform, source_text(&cond.condition.pos(ctx))));
+    }
+
+    if let Some(ref else_item) = ifstmt.conds.else_item {
+        if let [LabeledSequentialStatement { label, statement }] = else_item.as_slice() {
+            if let (None, SequentialStatement::If(ref nested)) = (&label.tree, &statement.item) {
+                return collect_arms(ctx, nested, target, arms, else_arm);
+            }
+        }
+        *else_arm = Some(single_assignment(ctx, else_item, target)?);
+    }
+    Some(())
+}
+
+/// The waveform of a branch that only contains a simple signal assignment to `target`
+fn single_assignment(
+    ctx: &dyn TokenAccess,
+    statements: &[LabeledSequentialStatement],
+    target: &mut Option<String>,
+) -> Option<String> {
+    let [stmt] = statements else {
+        return None;
+    };
+    if stmt.label.tree.is_some() {
+        return None;
+    }
+    let SequentialStatement::SignalAssignment(ref assignment) = stmt.statement.item else {
+        return None;
+    };
+    if assignment.delay_mechanism.is_some() {
+        return None;
+    }
+    let AssignmentRightHand::Simple(Waveform::Elements(ref elements)) = assignment.rhs else {
+        return None;
+    };
+    let [element] = elements.as_slice() else {
+        return None;
+    };
+    if element.after.is_some() {
+        return None;
+    }
+
+    let assignment_target = source_text(&assignment.target.pos(ctx));
+    match target {
+        Some(target) if !target.eq_ignore_ascii_case(&assignment_target) => return None,
+        Some(_) => {}
+        None => *target = Some(assignment_target),
+    }
+
+    Some(source_text(&element.value.pos(ctx)))
+}
+
+/// Rewrite an if statement where every condition matches the same expression against
+/// a literal, i.e. `sel ?= "1-"` or `std_match(sel, "1-")`, into a matching case statement.
+///
+/// ```vhdl
+/// if sel ?= "1-" then
+///   q <= a;
+/// elsif sel ?= "01" then
+///   q <= b;
+/// end if;
+/// ```
+///
+/// becomes
+///
+/// ```vhdl
+/// case? sel is
+///   when "1-" =>
+///     q <= a;
+///   when "01" =>
+///     q <= b;
+///   when others =>
+///     null;
+/// end case?;
+/// ```
+///
+/// The branches of an if statement are tried in order while a matching case statement
+/// requires that no value matches more than one choice, so overlapping choices are rejected.
+fn if_to_matching_case(
+    ctx: &dyn TokenAccess,
+    pos: &SrcPos,
+    ifstmt: &IfStatement,
+) -> Option<CodeAction> {
+    let mut selector = None;
+    let mut arms = Vec::new();
+    let mut else_item = None;
+    collect_matching_arms(ctx, ifstmt, &mut selector, &mut arms, &mut else_item)?;
+    let selector = selector?;
+    if arms.len() < 2 {
+        return None;
+    }
+
+    let choices: Vec<String> = arms.iter().map(|(choice, _)| choice.clone()).collect();
+    for (idx, choice) in choices.iter().enumerate() {
+        if choices[..idx]
+            .iter()
+            .any(|other| choices_overlap(choice, other))
+        {
+            return None;
+        }
+    }
+
+    let indent = line_indent(pos);
+    let mut text = format!("case? {selector} is\n");
+    for (choice, statements) in arms {
+        text.push_str(&format!("{indent}  when {choice} =>\n"));
+        text.push_str(&branch_text(ctx, statements, &format!("{indent}    ")));
+    }
+    text.push_str(&format!("{indent}  when others =>\n"));
+    text.push_str(&branch_text(
+        ctx,
+        else_item.unwrap_or_default(),
+        &format!("{indent}    "),
+    ));
+    text.push_str(&format!("{indent}end case?;"));
+
+    Some(CodeAction {
+        title: "Convert to matching case statement".to_owned(),
+        edits: vec![TextEdit::replace(pos.clone(), text)],
+    })
+}
+
+/// Collect the `(choice, statements)` arms of an if statement.
+/// Returns `None` if a condition does not match the selector against a literal.
+fn collect_matching_arms<'a>(
+    ctx: &dyn TokenAccess,
+    ifstmt: &'a IfStatement,
+    selector: &mut Option<String>,
+    arms: &mut Vec<(String, &'a [LabeledSequentialStatement])>,
+    else_item: &mut Option<&'a [LabeledSequentialStatement]>,
+) -> Option<()> {
+    for cond in ifstmt.conds.conditionals.iter() {
+        let (lhs, choice) = matching_condition(ctx, &cond.condition.item)?;
+        let lhs_text = source_text(&lhs);
+        match selector {
+            Some(selector) if !selector.eq_ignore_ascii_case(&lhs_text) => return None,
+            Some(_) => {}
+            None => *selector = Some(lhs_text),
+        }
+        arms.push((source_text(&choice), cond.item.as_slice()));
+    }
+
+    if let Some(ref statements) = ifstmt.conds.else_item {
+        if let [LabeledSequentialStatement { label, statement }] = statements.as_slice() {
+            if let (None, SequentialStatement::If(ref nested)) = (&label.tree, &statement.item) {
+                return collect_matching_arms(ctx, nested, selector, arms, else_item);
+            }
+        }
+        *else_item = Some(statements.as_slice());
+    }
+    Some(())
+}
+
+/// The position of the selector and of the choice of a condition such as
+/// `sel ?= "1-"` or `std_match(sel, "1-")`
+fn matching_condition(ctx: &dyn TokenAccess, condition: &Expression) -> Option<(SrcPos, SrcPos)> {
+    match condition {
+        Expression::Binary(op, lhs, rhs) if op.item.item == Operator::QueEQ => {
+            is_choice_literal(&rhs.item).then(|| (lhs.pos(ctx), rhs.pos(ctx)))
+        }
+        Expression::Name(name) => {
+            let Name::CallOrIndexed(ref call) = name.as_ref() else {
+                return None;
+            };
+            let Name::Designator(ref designator) = call.name.item else {
+                return None;
+            };
+            if !designator
+                .item
+                .to_string()
+                .eq_ignore_ascii_case("std_match")
+            {
+                return None;
+            }
+            let [lhs, rhs] = call.parameters.as_slice() else {
+                return None;
+            };
+            if lhs.formal.is_some() || rhs.formal.is_some() {
+                return None;
+            }
+            let ActualPart::Expression(ref choice) = rhs.actual.item else {
+                return None;
+            };
+            is_choice_literal(choice).then(|| (lhs.actual.pos(ctx), rhs.actual.pos(ctx)))
+        }
+        _ => None,
+    }
+}
+
+fn is_choice_literal(expr: &Expression) -> bool {
+    matches!(
+        expr,
+        Expression::Literal(Literal::String(_) | Literal::Character(_))
+    )
+}
+
+/// True if some value matches both choices, `-` matches any value
+fn choices_overlap(choice: &str, other: &str) -> bool {
+    choice.len() == other.len()
+        && choice
+            .chars()
+            .zip(other.chars())
+            .all(|(c1, c2)| c1 == c2 || c1 == '-' || c2 == '-')
+}
+
+/// The statements of a branch on separate lines indented by `indent`
+fn branch_text(
+    ctx: &dyn TokenAccess,
+    statements: &[LabeledSequentialStatement],
+    indent: &str,
+) -> String {
+    if statements.is_empty() {
+        return format!("{indent}null;\n");
+    }
+
+    let mut text = String::new();
+    for stmt in statements {
+        let mut pos = stmt.statement.span.pos(ctx);
+        if let Some(ref label) = stmt.label.tree {
+            pos = label.pos(ctx).combine(&pos);
+        }
+        let old_indent = line_indent(&pos);
+        for (idx, line) in source_text(&pos).lines().enumerate() {
+            let line = if idx == 0 {
+                line
+            } else {
+                line.strip_prefix(old_indent.as_str()).unwrap_or(line)
+            };
+            text.push_str(indent);
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// Remove a conversion to `std_logic_vector` or `std_ulogic_vector` of an operand that
+/// already has that type. Since VHDL-2008 `std_logic_vector` is a resolved subtype of
+/// `std_ulogic_vector` so conversions between them are no longer necessary.
+fn remove_vector_conversion(
+    root: &DesignRoot,
+    ctx: &dyn TokenAccess,
+    pos: &SrcPos,
+    expr: &Expression,
+) -> Option<CodeAction> {
+    let Expression::Name(name) = expr else {
+        return None;
+    };
+    let Name::CallOrIndexed(ref call) = name.as_ref() else {
+        return None;
+    };
+    let target = TypeEnt::from_any(root.get_ent(name_reference(&call.name.item)?.get()?))?;
+    if !is_logic_vector(target) {
+        return None;
+    }
+
+    let [param] = call.parameters.as_slice() else {
+        return None;
+    };
+    if param.formal.is_some() {
+        return None;
+    }
+    let ActualPart::Expression(Expression::Name(ref operand)) = param.actual.item else {
+        return None;
+    };
+    let operand_type = name_type(root, operand)?;
+    if operand_type.base_type() != target.base_type() {
+        return None;
+    }
+
+    Some(CodeAction {
+        title: format!(
+            "Remove redundant conversion to {}",
+            source_text(&call.name.pos(ctx))
+        ),
+        edits: vec![TextEdit::replace(
+            pos.clone(),
+            source_text(&param.actual.pos(ctx)),
+        )],
+    })
+}
+
+fn name_reference(name: &Name) -> Option<&Reference> {
+    match name {
+        Name::Designator(designator) => Some(&designator.reference),
+        Name::Selected(_, designator) => Some(&designator.item.reference),
+        _ => None,
+    }
+}
+
+/// The type of an object name or of a function call
+fn name_type<'a>(root: &'a DesignRoot, name: &Name) -> Option<TypeEnt<'a>> {
+    match name {
+        Name::Designator(..) | Name::Selected(..) => {
+            ObjectEnt::from_any(root.get_ent(name_reference(name)?.get()?))
+                .map(|object| object.type_mark())
+        }
+        Name::CallOrIndexed(call) => {
+            OverloadedEnt::from_any(root.get_ent(name_reference(&call.name.item)?.get()?))?
+                .return_type()
+        }
+        _ => None,
+    }
+}
+
+fn is_logic_vector(typ: TypeEnt) -> bool {
+    matches!(
+        typ.designator().to_string().to_ascii_lowercase().as_str(),
+        "std_logic_vector" | "std_ulogic_vector"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::syntax::test::Code;
+
+    fn actions_at(builder: LibraryBuilder, code: &Code, cursor: Position) -> Vec<CodeAction> {
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+        list_code_actions(&root, code.source(), cursor)
+    }
+
+    #[test]
+    fn replaces_sensitivity_list_of_combinational_process() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal a, b, c : bit;
+begin
+  process (a, b)
+  begin
+    c <= a and b;
+  end process;
+end architecture;
+",
+        );
+        let actions = actions_at(builder, &code, code.s1("c <=").start());
+        assert_eq!(
+            actions,
+            vec![CodeAction {
+                title: "Replace sensitivity list with 'all'".to_owned(),
+                edits: vec![TextEdit::replace(code.s1("a, b)").s1("a, b").pos(), "all")],
+            }]
+        );
+    }
+
+    #[test]
+    fn keeps_sensitivity_list_of_clocked_process() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal clk, d, q : bit;
+begin
+  process (clk)
+  begin
+    if clk'event and clk = '1' then
+      q <= d;
+    end if;
+  end process;
+end architecture;
+",
+        );
+        let actions = actions_at(builder, &code, code.s1("process (clk)").start());
+        assert_eq!(actions, vec![]);
+    }
+
+    #[test]
+    fn converts_if_chain_to_conditional_assignment() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal a, b, c, sel1, sel2, q : bit;
+begin
+  process (all)
+  begin
+    if sel1 = '1' then
+      q <= a;
+    else
+      if sel2 = '1' then
+        q <= b;
+      else
+        q <= c;
+      end if;
+    end if;
+  end process;
+end architecture;
+",
+        );
+        let actions = actions_at(builder, &code, code.s1("if sel1").start());
+        assert_eq!(
+            actions,
+            vec![CodeAction {
+                title: "Convert to conditional signal assignment".to_owned(),
+                edits: vec![TextEdit::replace(
+                    code.s1_to_end("if sel1").s_from_start("end if;", 2).pos(),
+                    "q <= a when sel1 = '1' else
+         b when sel2 = '1' else
+         c;"
+                )],
+            }]
+        );
+    }
+
+    #[test]
+    fn does_not_convert_assignments_to_different_targets() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal a, b, sel, q1, q2 : bit;
+begin
+  process (all)
+  begin
+    if sel = '1' then
+      q1 <= a;
+    else
+      q2 <= b;
+    end if;
+  end process;
+end architecture;
+",
+        );
+        let actions = actions_at(builder, &code, code.s1("if sel").start());
+        assert_eq!(actions, vec![]);
+    }
+
+    fn matching_case_actions(builder: LibraryBuilder, code: &Code) -> Vec<CodeAction> {
+        actions_at(builder, code, code.s1("if sel").start())
+            .into_iter()
+            .filter(|action| action.title == "Convert to matching case statement")
+            .collect()
+    }
+
+    #[test]
+    fn converts_matching_if_chain_to_matching_case() {
+        let mut builder = LibraryBuilder::new();
+        builder.add_std_logic_1164();
+        let code = builder.code(
+            "libname",
+            "
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal sel : std_ulogic_vector(1 downto 0);
+  signal a, b, q : std_ulogic;
+begin
+  process (all)
+  begin
+    if sel ?= \"1-\" then
+      q <= a;
+    elsif sel ?= \"01\" then
+      q <= b;
+    else
+      q <= '0';
+    end if;
+  end process;
+end architecture;
+",
+        );
+        assert_eq!(
+            matching_case_actions(builder, &code),
+            vec![CodeAction {
+                title: "Convert to matching case statement".to_owned(),
+                edits: vec![TextEdit::replace(
+                    code.s1_to_end("if sel").s_from_start("end if;", 1).pos(),
+                    "case? sel is
+      when \"1-\" =>
+        q <= a;
+      when \"01\" =>
+        q <= b;
+      when others =>
+        q <= '0';
+    end case?;"
+                )],
+            }]
+        );
+    }
+
+    #[test]
+    fn does_not_convert_overlapping_choices_to_matching_case() {
+        let mut builder = LibraryBuilder::new();
+        builder.add_std_logic_1164();
+        let code = builder.code(
+            "libname",
+            "
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal sel : std_ulogic_vector(1 downto 0);
+  signal a, b, q : std_ulogic;
+begin
+  process (all)
+  begin
+    if sel ?= \"1-\" then
+      q <= a;
+    elsif sel ?= \"-1\" then
+      q <= b;
+    end if;
+  end process;
+end architecture;
+",
+        );
+        assert_eq!(matching_case_actions(builder, &code), vec![]);
+    }
+
+    #[test]
+    fn removes_redundant_vector_conversion() {
+        let mut builder = LibraryBuilder::new();
+        builder.add_std_logic_1164();
+        let code = builder.code(
+            "libname",
+            "
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal u : std_ulogic_vector(1 downto 0);
+  signal l : std_logic_vector(1 downto 0);
+begin
+  l <= std_logic_vector(u);
+end architecture;
+",
+        );
+        let actions = actions_at(builder, &code, code.s1("(u)").end());
+        assert_eq!(
+            actions,
+            vec![CodeAction {
+                title: "Remove redundant conversion to std_logic_vector".to_owned(),
+                edits: vec![TextEdit::replace(code.s1("std_logic_vector(u)").pos(), "u")],
+            }]
+        );
+    }
+}
@@ -0,0 +1,375 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Move a design unit together with its context clause to another file
+
+use super::{source_text, whole_lines, TextEdit};
+use crate::analysis::DesignRoot;
+use crate::ast::{AnyDesignUnit, AnyKind, AnySecondaryUnit, HasUnitId};
+use crate::data::{Position, Range, Source, SrcPos};
+use crate::HasTokenSpan;
+use std::path::{Path, PathBuf};
+
+/// The default scheme for the name of the new file.
+/// `{primary}` is replaced by the name of the entity or package,
+/// `{secondary}` by the name of the architecture or `body` for a package body
+/// and `{ext}` by the extension of the original file.
+pub const DEFAULT_FILE_NAME_SCHEME: &str = "{primary}_{secondary}.{ext}";
+
+/// A design unit that is moved to another file
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct MovedUnit {
+    /// A description of the unit such as `architecture 'rtl'`
+    pub description: String,
+    /// The file that the unit is moved to
+    pub file_name: PathBuf,
+    /// The text of the unit together with its context clause
+    pub text: String,
+    /// Removes the unit and its context clause from the original file
+    pub removal: TextEdit,
+}
+
+impl MovedUnit {
+    /// Append the unit to the end of `target`, separated by an empty line
+    pub fn append_to(&self, target: &Source) -> TextEdit {
+        let contents = target.contents();
+        let (end, separator) = match contents.get_line(contents.num_lines().saturating_sub(1)) {
+            None => (contents.end(), ""),
+            Some(line) if line.ends_with('\n') => {
+                (Position::new(contents.num_lines() as u32, 0), "\n")
+            }
+            Some(_) => (contents.end(), "\n\n"),
+        };
+        TextEdit::replace(
+            SrcPos::new(target.clone(), Range::new(end, end)),
+            format!("{separator}{}", self.text),
+        )
+    }
+}
+
+/// Move the architecture or package body at the cursor into a new file named
+/// according to `file_name_scheme`.
+/// Returns `None` if the cursor is not within a secondary unit
+/// or if the unit is the only design unit of the file.
+pub fn move_unit_to_file(
+    root: &DesignRoot,
+    source: &Source,
+    cursor: Position,
+    file_name_scheme: &str,
+) -> Option<MovedUnit> {
+    let (unit, num_units) = unit_at_cursor(root, source, cursor);
+    let unit = unit?;
+    if num_units < 2 || !matches!(unit.kind, AnyKind::Secondary(_)) {
+        return None;
+    }
+
+    let file_name = new_file_name(source, file_name_scheme, &unit.primary, &unit.secondary);
+    if file_name == source.file_name() {
+        return None;
+    }
+    Some(unit.moved_to(file_name))
+}
+
+/// Move the design unit at the cursor, together with its context clause, to `file_name`.
+/// The unit is appended to the file when it already exists.
+pub fn move_unit(
+    root: &DesignRoot,
+    source: &Source,
+    cursor: Position,
+    file_name: &Path,
+) -> Result<MovedUnit, String> {
+    let Some(unit) = unit_at_cursor(root, source, cursor).0 else {
+        return Err("No design unit at the cursor".to_owned());
+    };
+    if file_name == source.file_name() {
+        return Err(format!(
+            "The {} is already in {}",
+            unit.description,
+            file_name.to_string_lossy()
+        ));
+    }
+    Ok(unit.moved_to(file_name.to_owned()))
+}
+
+struct FoundUnit {
+    /// The unit including its context clause
+    pos: SrcPos,
+    kind: AnyKind,
+    primary: String,
+    /// The name of the architecture or `body` for a package body
+    secondary: String,
+    description: String,
+}
+
+impl FoundUnit {
+    fn moved_to(self, file_name: PathBuf) -> MovedUnit {
+        MovedUnit {
+            description: self.description,
+            file_name,
+            text: format!("{}\n", source_text(&self.pos)),
+            removal: TextEdit::replace(whole_lines(&self.pos), ""),
+        }
+    }
+}
+
+/// The design unit at the cursor and the number of design units of the file
+fn unit_at_cursor(
+    root: &DesignRoot,
+    source: &Source,
+    cursor: Position,
+) -> (Option<FoundUnit>, usize) {
+    // A file mapped to several libraries contains the same units once per library
+    let mut unit_positions: Vec<SrcPos> = Vec::new();
+    let mut found = None;
+
+    for unit in root.units_by_source(source) {
+        let Some(data) = unit.unit.get() else {
+            continue;
+        };
+        let design_unit = data.data();
+        let pos = design_unit.get_pos(&unit.tokens);
+        if unit_positions.contains(&pos) {
+            continue;
+        }
+        unit_positions.push(pos.clone());
+
+        let pos = match design_unit.context_clause().first() {
+            Some(item) => item.get_pos(&unit.tokens).combine(&pos),
+            None => pos,
+        };
+        if found.is_some() || !pos.contains(cursor) {
+            continue;
+        }
+        let kind = unit.kind();
+        let (primary, secondary, name) = match design_unit {
+            AnyDesignUnit::Primary(_) => {
+                let name = unit.unit_id().primary_name().name_utf8();
+                (name.clone(), String::new(), name)
+            }
+            AnyDesignUnit::Secondary(AnySecondaryUnit::Architecture(arch)) => {
+                let name = arch.ident.tree.item.name_utf8();
+                (arch.entity_name.item.item.name_utf8(), name.clone(), name)
+            }
+            AnyDesignUnit::Secondary(AnySecondaryUnit::PackageBody(body)) => {
+                let name = body.ident.tree.item.name_utf8();
+                (name.clone(), "body".to_owned(), name)
+            }
+        };
+        found = Some(FoundUnit {
+            pos,
+            kind,
+            primary,
+            secondary,
+            description: format!("{} '{name}'", kind.describe()),
+        });
+    }
+
+    (found, unit_positions.len())
+}
+
+fn new_file_name(source: &Source, scheme: &str, primary: &str, secondary: &str) -> PathBuf {
+    let path = source.file_name();
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("vhd");
+    let name = scheme
+        .replace("{primary}", primary)
+        .replace("{secondary}", secondary)
+        .replace("{ext}", ext);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::refactor::apply_edits;
+
+    #[test]
+    fn moves_architecture_with_context_clause() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+library ieee;
+use ieee.std_logic_1164.all;
+
+architecture rtl of ent is
+  signal s : std_logic;
+begin
+end architecture;
+",
+        );
+        builder.add_std_logic_1164();
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let moved = move_unit_to_file(
+            &root,
+            code.source(),
+            code.s1("signal s").start(),
+            DEFAULT_FILE_NAME_SCHEME,
+        )
+        .unwrap();
+        assert_eq!(moved.description, "architecture 'rtl'");
+        assert_eq!(moved.file_name, PathBuf::from("ent_rtl.vhd"));
+        assert_eq!(
+            moved.text,
+            "\
+library ieee;
+use ieee.std_logic_1164.all;
+
+architecture rtl of ent is
+  signal s : std_logic;
+begin
+end architecture;
+"
+        );
+        assert_eq!(
+            moved.removal,
+            TextEdit::replace(
+                whole_lines(
+                    &code
+                        .s1("library ieee;")
+                        .pos()
+                        .combine(&code.s1("end architecture;").pos())
+                ),
+                ""
+            )
+        );
+    }
+
+    #[test]
+    fn moves_package_body_with_custom_scheme() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+package pkg is
+end package;
+
+package body pkg is
+end package body;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let moved = move_unit_to_file(
+            &root,
+            code.source(),
+            code.s1("package body").start(),
+            "{primary}-{secondary}.vhdl",
+        )
+        .unwrap();
+        assert_eq!(moved.description, "package body 'pkg'");
+        assert_eq!(moved.file_name, PathBuf::from("pkg-body.vhdl"));
+        assert_eq!(moved.text, "package body pkg is\nend package body;\n");
+    }
+
+    #[test]
+    fn does_not_move_primary_or_only_unit() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture rtl of ent is
+begin
+end architecture;
+",
+        );
+        let single = builder.code(
+            "libname",
+            "\
+architecture other of ent is
+begin
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        for (code, cursor) in [
+            (&code, code.s1("entity ent").start()),
+            (&single, single.s1("architecture").start()),
+        ] {
+            assert_eq!(
+                move_unit_to_file(&root, code.source(), cursor, DEFAULT_FILE_NAME_SCHEME),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn moves_entity_to_existing_file() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity ent is
+  port (clk : in std_logic);
+end entity;
+
+architecture rtl of ent is
+begin
+end architecture;
+",
+        );
+        let target = builder.code("libname", "package pkg is\nend package;");
+        builder.add_std_logic_1164();
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let moved = move_unit(
+            &root,
+            code.source(),
+            code.s1("port").start(),
+            target.source().file_name(),
+        )
+        .unwrap();
+        assert_eq!(moved.description, "entity 'ent'");
+        assert_eq!(
+            apply_edits(code.source(), std::slice::from_ref(&moved.removal)),
+            "\narchitecture rtl of ent is\nbegin\nend architecture;\n"
+        );
+        assert_eq!(
+            apply_edits(target.source(), &[moved.append_to(target.source())]),
+            "\
+package pkg is
+end package;
+
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity ent is
+  port (clk : in std_logic);
+end entity;
+"
+        );
+        assert_eq!(
+            move_unit(
+                &root,
+                code.source(),
+                code.s1("port").start(),
+                code.source().file_name()
+            ),
+            Err(format!(
+                "The entity 'ent' is already in {}",
+                code.source().file_name().to_string_lossy()
+            ))
+        );
+    }
+}
@@ -0,0 +1,445 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Rewrite positional associations of port maps, generic maps and subprogram calls
+//! into named associations
+
+use super::{CodeAction, TextEdit};
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::ast::{
+    AssociationElement, CallOrIndexed, ConcurrentStatement, Expression, InstantiationStatement,
+    MapAspect, Name, SequentialStatement,
+};
+use crate::data::{Position, Source, SrcPos};
+use crate::named_entity::{AnyEntKind, Design, InterfaceEnt, OverloadedEnt};
+use crate::syntax::TokenAccess;
+
+pub fn list_code_actions(root: &DesignRoot, source: &Source, cursor: Position) -> Vec<CodeAction> {
+    let mut searcher = NamedAssociationSearcher {
+        root,
+        source,
+        cursor,
+        edits: None,
+    };
+    let _ = root.search_source(source, &mut searcher);
+
+    searcher
+        .edits
+        .map(|edits| {
+            vec![CodeAction {
+                title: "Convert to named association".to_owned(),
+                edits,
+            }]
+        })
+        .unwrap_or_default()
+}
+
+/// Convert the positional associations of the generic maps and port maps of all instances
+/// within the source file into named associations
+pub fn name_instance_associations(root: &DesignRoot, source: &Source) -> Vec<TextEdit> {
+    let mut searcher = InstanceSearcher {
+        root,
+        edits: Vec::new(),
+    };
+    let _ = root.search_source(source, &mut searcher);
+    searcher.edits
+}
+
+struct InstanceSearcher<'a> {
+    root: &'a DesignRoot,
+    edits: Vec<TextEdit>,
+}
+
+impl<'a> Searcher for InstanceSearcher<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        if let FoundDeclaration::ConcurrentStatement(stmt) = decl {
+            if let ConcurrentStatement::Instance(ref instance) = stmt.statement.item {
+                self.edits.extend(instance_edits(self.root, ctx, instance));
+            }
+        }
+        NotFinished
+    }
+}
+
+/// The names of the formals of all positional associations within the source file,
+/// as the insertions that would convert them into named associations.
+/// This includes the calls of functions and procedures.
+pub fn formal_name_hints(root: &DesignRoot, source: &Source) -> Vec<TextEdit> {
+    let mut searcher = HintSearcher {
+        root,
+        edits: Vec::new(),
+    };
+    let _ = root.search_source(source, &mut searcher);
+
+    let mut edits = searcher.edits;
+    edits.sort_by_key(|edit| edit.pos.start());
+    // The same association is found through every library that a file is mapped to
+    edits.dedup();
+    edits
+}
+
+struct HintSearcher<'a> {
+    root: &'a DesignRoot,
+    edits: Vec<TextEdit>,
+}
+
+impl<'a> Searcher for HintSearcher<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        match decl {
+            FoundDeclaration::ConcurrentStatement(stmt) => match stmt.statement.item {
+                ConcurrentStatement::Instance(ref instance) => {
+                    self.edits.extend(instance_edits(self.root, ctx, instance));
+                }
+                ConcurrentStatement::ProcedureCall(ref pcall) => {
+                    self.edits
+                        .extend(call_edits(self.root, ctx, &pcall.call.item));
+                }
+                _ => {}
+            },
+            FoundDeclaration::SequentialStatement(stmt) => {
+                if let SequentialStatement::ProcedureCall(ref pcall) = stmt.statement.item {
+                    self.edits.extend(call_edits(self.root, ctx, &pcall.item));
+                }
+            }
+            _ => {}
+        }
+        NotFinished
+    }
+
+    fn search_expression(
+        &mut self,
+        ctx: &dyn TokenAccess,
+        _pos: &SrcPos,
+        expr: &Expression,
+    ) -> SearchState {
+        if let Expression::Name(ref name) = expr {
+            if let Name::CallOrIndexed(ref call) = name.as_ref() {
+                self.edits.extend(call_edits(self.root, ctx, call));
+            }
+        }
+        NotFinished
+    }
+}
+
+struct NamedAssociationSearcher<'a> {
+    root: &'a DesignRoot,
+    source: &'a Source,
+    cursor: Position,
+    /// The edits of the innermost association list at the cursor
+    edits: Option<Vec<TextEdit>>,
+}
+
+impl<'a> NamedAssociationSearcher<'a> {
+    fn is_at_cursor(&self, pos: &SrcPos) -> bool {
+        pos.source == *self.source && pos.contains(self.cursor)
+    }
+
+    fn offer(&mut self, edits: Vec<TextEdit>) {
+        if !edits.is_empty() {
+            self.edits = Some(edits);
+        }
+    }
+}
+
+impl<'a> Searcher for NamedAssociationSearcher<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        match decl {
+            FoundDeclaration::ConcurrentStatement(stmt)
+                if self.is_at_cursor(&stmt.statement.span.pos(ctx)) =>
+            {
+                match stmt.statement.item {
+                    ConcurrentStatement::Instance(ref instance) => {
+                        self.offer(instance_edits(self.root, ctx, instance));
+                    }
+                    ConcurrentStatement::ProcedureCall(ref pcall) => {
+                        self.offer(call_edits(self.root, ctx, &pcall.call.item));
+                    }
+                    _ => {}
+                }
+            }
+            FoundDeclaration::SequentialStatement(stmt)
+                if self.is_at_cursor(&stmt.statement.span.pos(ctx)) =>
+            {
+                if let SequentialStatement::ProcedureCall(ref pcall) = stmt.statement.item {
+                    self.offer(call_edits(self.root, ctx, &pcall.item));
+                }
+            }
+            _ => {}
+        }
+        NotFinished
+    }
+
+    fn search_expression(
+        &mut self,
+        ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        expr: &Expression,
+    ) -> SearchState {
+        if let Expression::Name(ref name) = expr {
+            if let Name::CallOrIndexed(ref call) = name.as_ref() {
+                if self.is_at_cursor(pos) {
+                    self.offer(call_edits(self.root, ctx, call));
+                }
+            }
+        }
+        NotFinished
+    }
+}
+
+fn instance_edits(
+    root: &DesignRoot,
+    ctx: &dyn TokenAccess,
+    instance: &InstantiationStatement,
+) -> Vec<TextEdit> {
+    let Some(id) = instance.entity_reference() else {
+        return Vec::new();
+    };
+    let region = match root.get_ent(id).kind() {
+        AnyEntKind::Design(Design::Entity(_, region)) | AnyEntKind::Component(region) => region,
+        _ => return Vec::new(),
+    };
+    let (ports, generics) = region.ports_and_generics();
+
+    let mut edits = map_aspect_edits(ctx, instance.generic_map.as_ref(), &generics);
+    edits.extend(map_aspect_edits(ctx, instance.port_map.as_ref(), &ports));
+    edits
+}
+
+fn map_aspect_edits(
+    ctx: &dyn TokenAccess,
+    map_aspect: Option<&MapAspect>,
+    formals: &[InterfaceEnt],
+) -> Vec<TextEdit> {
+    let Some(map_aspect) = map_aspect else {
+        return Vec::new();
+    };
+    named_edits(ctx, &map_aspect.list.items, |idx| formals.get(idx).copied())
+}
+
+fn call_edits(root: &DesignRoot, ctx: &dyn TokenAccess, call: &CallOrIndexed) -> Vec<TextEdit> {
+    // Indexed names and type conversions do not reference a subprogram
+    let Some(subprogram) = call
+        .name
+        .item
+        .get_suffix_reference()
+        .and_then(|id| OverloadedEnt::from_any(root.get_ent(id)))
+    else {
+        return Vec::new();
+    };
+    named_edits(ctx, &call.parameters, |idx| subprogram.formals().nth(idx))
+}
+
+/// Insert the name of the formal before each positional actual.
+/// Positional associations always precede named associations
+/// so the position of the actual is the index of the formal.
+fn named_edits<'a>(
+    ctx: &dyn TokenAccess,
+    elements: &[AssociationElement],
+    formal: impl Fn(usize) -> Option<InterfaceEnt<'a>>,
+) -> Vec<TextEdit> {
+    elements
+        .iter()
+        .enumerate()
+        .take_while(|(_, elem)| elem.formal.is_none())
+        .filter_map(|(idx, elem)| {
+            let formal = formal(idx)?;
+            Some(TextEdit::insert(
+                &elem.actual.pos(ctx),
+                format!("{} => ", formal.designator()),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::syntax::test::Code;
+
+    fn actions_at(builder: LibraryBuilder, code: &Code, cursor: Position) -> Vec<CodeAction> {
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+        list_code_actions(&root, code.source(), cursor)
+    }
+
+    #[test]
+    fn converts_positional_port_and_generic_maps() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity sub is
+  generic (width : natural);
+  port (clk : in bit; data : out bit_vector(width - 1 downto 0));
+end entity;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal clk : bit;
+  signal data : bit_vector(7 downto 0);
+begin
+  inst: entity work.sub
+    generic map (8)
+    port map (clk, data => data);
+end architecture;
+",
+        );
+        let actions = actions_at(builder, &code, code.s1("entity work.sub").start());
+        assert_eq!(
+            actions,
+            vec![CodeAction {
+                title: "Convert to named association".to_owned(),
+                edits: vec![
+                    TextEdit::insert(&code.s1("(8)").s1("8").pos(), "width => "),
+                    TextEdit::insert(&code.s1("(clk,").s1("clk").pos(), "clk => "),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn converts_positional_subprogram_call() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture a of ent is
+  function add(left, right : natural) return natural is
+  begin
+    return left + right;
+  end function;
+
+  constant c : natural := add(1, 2);
+begin
+end architecture;
+",
+        );
+        let actions = actions_at(builder, &code, code.s1("add(1, 2)").start());
+        assert_eq!(
+            actions,
+            vec![CodeAction {
+                title: "Convert to named association".to_owned(),
+                edits: vec![
+                    TextEdit::insert(&code.s1("1, 2").s1("1").pos(), "left => "),
+                    TextEdit::insert(&code.s1("1, 2").s1("2").pos(), "right => "),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn names_associations_of_all_instances() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity sub is
+  port (a : in bit; b : out bit);
+end entity;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal x, y : bit;
+begin
+  inst1: entity work.sub port map (x, y);
+  inst2: entity work.sub port map (y, b => open);
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+        assert_eq!(
+            name_instance_associations(&root, code.source()),
+            vec![
+                TextEdit::insert(&code.s1("(x, y)").s1("x").pos(), "a => "),
+                TextEdit::insert(&code.s1("(x, y)").s1("y").pos(), "b => "),
+                TextEdit::insert(&code.s1("(y, b").s1("y").pos(), "a => "),
+            ]
+        );
+    }
+
+    #[test]
+    fn hints_formals_of_instances_and_calls() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity sub is
+  port (a : in natural; b : out natural);
+end entity;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  function add(left, right : natural) return natural is
+  begin
+    return left + right;
+  end function;
+
+  signal x, y : natural;
+begin
+  inst: entity work.sub port map (add(x, 1), y);
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+        assert_eq!(
+            formal_name_hints(&root, code.source()),
+            vec![
+                TextEdit::insert(&code.s1("add(x, 1)").pos(), "a => "),
+                TextEdit::insert(&code.s1("x, 1").s1("x").pos(), "left => "),
+                TextEdit::insert(&code.s1("x, 1").s1("1").pos(), "right => "),
+                TextEdit::insert(&code.s1(", y)").s1("y").pos(), "b => "),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_action_for_named_association_or_indexed_name() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture a of ent is
+  function add(left, right : natural) return natural is
+  begin
+    return left + right;
+  end function;
+
+  type arr_t is array (natural range <>) of natural;
+  constant arr : arr_t(0 to 1) := (0, 1);
+  constant c1 : natural := add(left => 1, right => 2);
+  constant c2 : natural := arr(1);
+begin
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+        assert_eq!(
+            list_code_actions(&root, code.source(), code.s1("add(left").start()),
+            vec![]
+        );
+        assert_eq!(
+            list_code_actions(&root, code.source(), code.s1("arr(1)").start()),
+            vec![]
+        );
+    }
+}
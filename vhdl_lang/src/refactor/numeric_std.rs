@@ -0,0 +1,587 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Migration from the Synopsys `std_logic_arith`, `std_logic_unsigned` and `std_logic_signed`
+//! packages to `ieee.numeric_std`.
+//!
+//! The use clauses of the legacy packages are replaced and conversions are inserted
+//! wherever an operator or function of a legacy package was selected by the analysis.
+
+use super::{source_text, whole_lines, CodeAction, TextEdit};
+use crate::analysis::DesignRoot;
+use crate::ast::search::{Finished, NotFinished, NotFound, SearchState, Searcher};
+use crate::ast::token_range::WithTokenSpan;
+use crate::ast::{ActualPart, CallOrIndexed, ContextItem, Expression, Name, UseClause};
+use crate::data::{Position, Range, Source, SrcPos};
+use crate::named_entity::{AnyEnt, AnyEntKind, Design, OverloadedEnt, Reference, TypeEnt};
+use crate::syntax::TokenAccess;
+use crate::HasTokenSpan;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Legacy {
+    Arith,
+    Unsigned,
+    Signed,
+}
+
+impl Legacy {
+    fn of_package(ent: &AnyEnt) -> Option<Legacy> {
+        if !matches!(ent.kind(), AnyEntKind::Design(Design::Package(..))) {
+            return None;
+        }
+        if !ent
+            .library_name()
+            .is_some_and(|name| name.name_utf8().eq_ignore_ascii_case("ieee"))
+        {
+            return None;
+        }
+        match ent.designator().to_string().to_ascii_lowercase().as_str() {
+            "std_logic_arith" => Some(Legacy::Arith),
+            "std_logic_unsigned" => Some(Legacy::Unsigned),
+            "std_logic_signed" => Some(Legacy::Signed),
+            _ => None,
+        }
+    }
+
+    /// The `numeric_std` type that a `std_logic_vector` is interpreted as
+    fn numeric_type(self) -> &'static str {
+        match self {
+            Legacy::Arith | Legacy::Unsigned => "unsigned",
+            Legacy::Signed => "signed",
+        }
+    }
+}
+
+/// Rewrite a source file to use `ieee.numeric_std` instead of the legacy arithmetic packages
+pub fn migrate_to_numeric_std(root: &DesignRoot, source: &Source) -> Vec<TextEdit> {
+    let mut edits = use_clause_edits(root, source);
+
+    let mut searcher = MigrationSearcher {
+        root,
+        edits: Vec::new(),
+    };
+    let _ = root.search_source(source, &mut searcher);
+
+    for edit in searcher.edits {
+        // A source file mapped to several libraries is searched once per library
+        if !edits.contains(&edit) {
+            edits.push(edit);
+        }
+    }
+    edits.sort_by_key(|edit| edit.pos.start());
+    edits
+}
+
+/// Offer the migration when the cursor is on a use clause of a legacy package
+pub fn list_code_actions(root: &DesignRoot, source: &Source, cursor: Position) -> Vec<CodeAction> {
+    let on_legacy_use_clause = legacy_use_clauses(root, source)
+        .iter()
+        .any(|(clause_pos, ..)| clause_pos.contains(cursor));
+
+    if on_legacy_use_clause {
+        vec![CodeAction {
+            title: "Migrate file to numeric_std".to_owned(),
+            edits: migrate_to_numeric_std(root, source),
+        }]
+    } else {
+        Vec::new()
+    }
+}
+
+/// The first use clause of a legacy package within each context clause is replaced
+/// by a use clause of `numeric_std`, the others are removed.
+fn use_clause_edits(root: &DesignRoot, source: &Source) -> Vec<TextEdit> {
+    let mut edits = Vec::new();
+    for (clause_pos, name_pos, replace) in legacy_use_clauses(root, source) {
+        let edit = if replace {
+            TextEdit::replace(name_pos, "ieee.numeric_std.all")
+        } else {
+            TextEdit::replace(whole_lines(&clause_pos), "")
+        };
+        if !edits.contains(&edit) {
+            edits.push(edit);
+        }
+    }
+    edits
+}
+
+/// The position of each use clause of a legacy package together with the position of the name
+/// and whether the clause shall be replaced by a use clause of `numeric_std`
+fn legacy_use_clauses(root: &DesignRoot, source: &Source) -> Vec<(SrcPos, SrcPos, bool)> {
+    let mut clauses = Vec::new();
+    for unit in root.units_by_source(source) {
+        let Some(data) = unit.unit.get() else {
+            continue;
+        };
+        let tokens = &unit.tokens;
+        let context_clause = data.data().context_clause();
+
+        let mut has_numeric_std = context_clause.iter().any(|item| match item {
+            ContextItem::Use(use_clause) => use_clause.name_list.items.iter().any(|name| {
+                source_text(&name.pos(tokens))
+                    .replace(char::is_whitespace, "")
+                    .eq_ignore_ascii_case("ieee.numeric_std.all")
+            }),
+            _ => false,
+        });
+
+        for item in context_clause.iter() {
+            let ContextItem::Use(use_clause) = item else {
+                continue;
+            };
+            let Some(name) = legacy_use_clause(root, use_clause) else {
+                continue;
+            };
+            clauses.push((item.get_pos(tokens), name.pos(tokens), !has_numeric_std));
+            has_numeric_std = true;
+        }
+    }
+    clauses
+}
+
+/// The name of a use clause such as `use ieee.std_logic_unsigned.all`
+fn legacy_use_clause<'a>(
+    root: &DesignRoot,
+    use_clause: &'a UseClause,
+) -> Option<&'a WithTokenSpan<Name>> {
+    let [name] = use_clause.name_list.items.as_slice() else {
+        return None;
+    };
+    let Name::SelectedAll(ref prefix) = name.item else {
+        return None;
+    };
+    let Name::Selected(_, ref package) = prefix.item else {
+        return None;
+    };
+    let ent = root.get_ent(package.item.reference.get()?);
+    Legacy::of_package(ent)?;
+    Some(name)
+}
+
+struct MigrationSearcher<'a> {
+    root: &'a DesignRoot,
+    edits: Vec<TextEdit>,
+}
+
+impl<'a> Searcher for MigrationSearcher<'a> {
+    fn search_expression(
+        &mut self,
+        ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        expr: &Expression,
+    ) -> SearchState {
+        let rewriter = Rewriter {
+            root: self.root,
+            ctx,
+        };
+        if let Some(rewritten) = rewriter.rewrite(pos, expr) {
+            self.edits
+                .push(TextEdit::replace(pos.clone(), rewritten.text));
+            // The sub-expressions are part of the rewritten text
+            Finished(NotFound)
+        } else {
+            NotFinished
+        }
+    }
+}
+
+struct Rewritten {
+    text: String,
+    /// The `numeric_std` expression before it was converted back to `std_logic_vector`
+    numeric: Option<String>,
+}
+
+impl Rewritten {
+    fn new(text: String) -> Rewritten {
+        Rewritten {
+            text,
+            numeric: None,
+        }
+    }
+
+    fn std_logic_vector(numeric: String) -> Rewritten {
+        Rewritten {
+            text: format!("std_logic_vector({numeric})"),
+            numeric: Some(numeric),
+        }
+    }
+}
+
+struct Rewriter<'a> {
+    root: &'a DesignRoot,
+    ctx: &'a dyn TokenAccess,
+}
+
+impl<'a> Rewriter<'a> {
+    /// The rewritten expression or `None` when the expression does not depend on
+    /// the legacy packages
+    fn rewrite(&self, pos: &SrcPos, expr: &Expression) -> Option<Rewritten> {
+        match expr {
+            Expression::Binary(op, left, right) => {
+                self.rewrite_operation(pos, &op.item.reference, &[left.as_ref(), right.as_ref()])
+            }
+            Expression::Unary(op, operand) => {
+                self.rewrite_operation(pos, &op.item.reference, &[operand.as_ref()])
+            }
+            Expression::Name(name) => match name.as_ref() {
+                Name::CallOrIndexed(call) => self.rewrite_call(pos, call),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn rewrite_operation(
+        &self,
+        pos: &SrcPos,
+        reference: &Reference,
+        operands: &[&WithTokenSpan<Expression>],
+    ) -> Option<Rewritten> {
+        let function = self.legacy_function(reference);
+        let mut changed = false;
+        let mut replacements = Vec::with_capacity(operands.len());
+
+        for (idx, operand) in operands.iter().enumerate() {
+            let operand_pos = operand.pos(self.ctx);
+            let rewritten = self.rewrite(&operand_pos, &operand.item);
+            let is_vector_formal = function.is_some_and(|(_, function)| {
+                function
+                    .formals()
+                    .nth(idx)
+                    .is_some_and(|formal| is_std_logic_vector(formal.base_type()))
+            });
+
+            let text = match (rewritten, function) {
+                (Some(rewritten), Some((legacy, _))) if is_vector_formal => {
+                    match rewritten.numeric {
+                        Some(numeric) => numeric,
+                        None => format!("{}({})", legacy.numeric_type(), rewritten.text),
+                    }
+                }
+                (Some(rewritten), _) => rewritten.text,
+                (None, Some((legacy, _))) if is_vector_formal => {
+                    format!("{}({})", legacy.numeric_type(), source_text(&operand_pos))
+                }
+                (None, _) => continue,
+            };
+            changed = true;
+            replacements.push((operand_pos, text));
+        }
+
+        let returns_vector = function
+            .is_some_and(|(_, function)| function.return_type().is_some_and(is_std_logic_vector));
+        if !changed && !returns_vector {
+            return None;
+        }
+
+        let text = splice(pos, replacements);
+        Some(if returns_vector {
+            Rewritten::std_logic_vector(text)
+        } else {
+            Rewritten::new(text)
+        })
+    }
+
+    fn rewrite_call(&self, pos: &SrcPos, call: &CallOrIndexed) -> Option<Rewritten> {
+        let mut changed = false;
+        let mut positional = true;
+        let mut arguments = Vec::with_capacity(call.parameters.len());
+
+        for param in call.parameters.iter() {
+            let ActualPart::Expression(ref expr) = param.actual.item else {
+                positional = false;
+                continue;
+            };
+            positional &= param.formal.is_none();
+            let actual_pos = param.actual.pos(self.ctx);
+            let rewritten = self.rewrite(&actual_pos, expr);
+            changed |= rewritten.is_some();
+            arguments.push((actual_pos, rewritten));
+        }
+
+        if positional {
+            if let Some((legacy, function)) = name_reference(&call.name.item)
+                .and_then(|reference| self.legacy_function(reference))
+            {
+                let texts: Vec<String> = arguments
+                    .iter()
+                    .map(|(pos, rewritten)| match rewritten {
+                        Some(rewritten) => rewritten.text.clone(),
+                        None => source_text(pos),
+                    })
+                    .collect();
+                if let Some(converted) = convert_function(legacy, function, &texts) {
+                    return Some(converted);
+                }
+            }
+        }
+
+        if !changed {
+            return None;
+        }
+
+        let replacements = arguments
+            .into_iter()
+            .filter_map(|(pos, rewritten)| Some((pos, rewritten?.text)))
+            .collect();
+        Some(Rewritten::new(splice(pos, replacements)))
+    }
+
+    /// The subprogram referenced by `reference` if it is declared in a legacy package
+    fn legacy_function(&self, reference: &Reference) -> Option<(Legacy, OverloadedEnt<'a>)> {
+        let ent = self.root.get_ent(reference.get()?);
+        let legacy = Legacy::of_package(ent.parent?)?;
+        Some((legacy, OverloadedEnt::from_any(ent)?))
+    }
+}
+
+/// Replace a conversion function of the legacy packages by its `numeric_std` equivalent
+fn convert_function(
+    legacy: Legacy,
+    function: OverloadedEnt,
+    arguments: &[String],
+) -> Option<Rewritten> {
+    let name = function.designator().to_string().to_ascii_lowercase();
+    let arg_type = type_name(function.formals().nth(0)?.base_type());
+    let numeric_type = legacy.numeric_type();
+
+    let rewritten = match (name.as_str(), arguments) {
+        ("conv_integer", [arg]) => match arg_type.as_str() {
+            "unsigned" | "signed" => Rewritten::new(format!("to_integer({arg})")),
+            _ if is_std_logic_vector(function.formals().nth(0)?.base_type()) => {
+                Rewritten::new(format!("to_integer({numeric_type}({arg}))"))
+            }
+            _ => return None,
+        },
+        ("conv_std_logic_vector", [arg, size]) => match arg_type.as_str() {
+            "integer" => Rewritten::std_logic_vector(format!("to_unsigned({arg}, {size})")),
+            "unsigned" | "signed" => Rewritten::std_logic_vector(format!("resize({arg}, {size})")),
+            _ => return None,
+        },
+        ("conv_unsigned", [arg, size]) => match arg_type.as_str() {
+            "integer" => Rewritten::new(format!("to_unsigned({arg}, {size})")),
+            "unsigned" => Rewritten::new(format!("resize({arg}, {size})")),
+            "signed" => Rewritten::new(format!("unsigned(resize({arg}, {size}))")),
+            _ => return None,
+        },
+        ("conv_signed", [arg, size]) => match arg_type.as_str() {
+            "integer" => Rewritten::new(format!("to_signed({arg}, {size})")),
+            "signed" => Rewritten::new(format!("resize({arg}, {size})")),
+            "unsigned" => Rewritten::new(format!("signed(resize({arg}, {size}))")),
+            _ => return None,
+        },
+        ("ext", [arg, size]) => {
+            Rewritten::std_logic_vector(format!("resize(unsigned({arg}), {size})"))
+        }
+        ("sxt", [arg, size]) => {
+            Rewritten::std_logic_vector(format!("resize(signed({arg}), {size})"))
+        }
+        _ => return None,
+    };
+    Some(rewritten)
+}
+
+fn name_reference(name: &Name) -> Option<&Reference> {
+    match name {
+        Name::Designator(designator) => Some(&designator.reference),
+        Name::Selected(_, designator) => Some(&designator.item.reference),
+        _ => None,
+    }
+}
+
+fn type_name(typ: TypeEnt) -> String {
+    typ.base_type()
+        .designator()
+        .to_string()
+        .to_ascii_lowercase()
+}
+
+fn is_std_logic_vector(typ: TypeEnt) -> bool {
+    matches!(
+        type_name(typ).as_str(),
+        "std_logic_vector" | "std_ulogic_vector"
+    )
+}
+
+/// The text at `pos` where the text at each replaced position has been substituted.
+/// The replaced positions must be ordered and lie within `pos`.
+fn splice(pos: &SrcPos, replacements: Vec<(SrcPos, String)>) -> String {
+    let contents = pos.source.contents();
+    let mut text = String::new();
+    let mut start = pos.start();
+
+    for (replaced, new_text) in replacements {
+        text.push_str(&contents.text(Range::new(start, replaced.start())));
+        text.push_str(&new_text);
+        start = replaced.end();
+    }
+    text.push_str(&contents.text(Range::new(start, pos.end())));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+
+    fn add_legacy_packages(builder: &mut LibraryBuilder) {
+        builder.add_std_logic_1164();
+        builder.code(
+            "ieee",
+            "
+library ieee;
+use ieee.std_logic_1164.all;
+
+package std_logic_arith is
+  type unsigned is array (natural range <>) of std_logic;
+  type signed is array (natural range <>) of std_logic;
+
+  function conv_unsigned(arg : integer; size : integer) return unsigned;
+  function conv_std_logic_vector(arg : integer; size : integer) return std_logic_vector;
+end package;
+
+library ieee;
+use ieee.std_logic_1164.all;
+
+package std_logic_unsigned is
+  function \"+\"(l : std_logic_vector; r : std_logic_vector) return std_logic_vector;
+  function \"+\"(l : std_logic_vector; r : integer) return std_logic_vector;
+  function conv_integer(arg : std_logic_vector) return integer;
+end package;
+",
+        );
+    }
+
+    #[test]
+    fn converts_std_logic_unsigned_operators() {
+        let mut builder = LibraryBuilder::new();
+        add_legacy_packages(&mut builder);
+        let code = builder.code(
+            "libname",
+            "
+library ieee;
+use ieee.std_logic_1164.all;
+use ieee.std_logic_unsigned.all;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal a, b, q : std_logic_vector(7 downto 0);
+  signal i : integer;
+begin
+  q <= a + b + 1;
+  i <= conv_integer(a);
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        assert_eq!(
+            migrate_to_numeric_std(&root, code.source()),
+            vec![
+                TextEdit::replace(
+                    code.s1("ieee.std_logic_unsigned.all").pos(),
+                    "ieee.numeric_std.all"
+                ),
+                TextEdit::replace(
+                    code.s1("a + b + 1").pos(),
+                    "std_logic_vector(unsigned(a) + unsigned(b) + 1)"
+                ),
+                TextEdit::replace(code.s1("conv_integer(a)").pos(), "to_integer(unsigned(a))"),
+            ]
+        );
+    }
+
+    #[test]
+    fn converts_std_logic_arith_functions() {
+        let mut builder = LibraryBuilder::new();
+        add_legacy_packages(&mut builder);
+        let code = builder.code(
+            "libname",
+            "
+library ieee;
+use ieee.std_logic_1164.all;
+use ieee.std_logic_arith.all;
+use ieee.std_logic_unsigned.all;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal cnt : unsigned(7 downto 0);
+  signal q : std_logic_vector(7 downto 0);
+begin
+  cnt <= conv_unsigned(3, 8);
+  q <= conv_std_logic_vector(5, 8);
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let removed_line = code.s1("use ieee.std_logic_unsigned.all;").start().line;
+        assert_eq!(
+            migrate_to_numeric_std(&root, code.source()),
+            vec![
+                TextEdit::replace(
+                    code.s1("ieee.std_logic_arith.all").pos(),
+                    "ieee.numeric_std.all"
+                ),
+                TextEdit::replace(
+                    SrcPos::new(
+                        code.source().clone(),
+                        Range::new(
+                            Position::new(removed_line, 0),
+                            Position::new(removed_line + 1, 0)
+                        )
+                    ),
+                    ""
+                ),
+                TextEdit::replace(code.s1("conv_unsigned(3, 8)").pos(), "to_unsigned(3, 8)"),
+                TextEdit::replace(
+                    code.s1("conv_std_logic_vector(5, 8)").pos(),
+                    "std_logic_vector(to_unsigned(5, 8))"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn offers_migration_on_legacy_use_clause() {
+        let mut builder = LibraryBuilder::new();
+        add_legacy_packages(&mut builder);
+        let code = builder.code(
+            "libname",
+            "
+library ieee;
+use ieee.std_logic_1164.all;
+use ieee.std_logic_unsigned.all;
+
+entity ent is
+end entity;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        assert_eq!(
+            list_code_actions(&root, code.source(), code.s1("std_logic_unsigned").start()),
+            vec![CodeAction {
+                title: "Migrate file to numeric_std".to_owned(),
+                edits: vec![TextEdit::replace(
+                    code.s1("ieee.std_logic_unsigned.all").pos(),
+                    "ieee.numeric_std.all"
+                )],
+            }]
+        );
+        assert_eq!(
+            list_code_actions(&root, code.source(), code.s1("std_logic_1164").start()),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,240 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Create the package body of a package that declares subprograms or deferred constants
+
+use super::{line_indent, source_text, CodeAction, TextEdit};
+use crate::analysis::DesignRoot;
+use crate::ast::{
+    AnyDesignUnit, AnyPrimaryUnit, Declaration, HasUnitId, ObjectClass, PackageDeclaration,
+    SubprogramSpecification,
+};
+use crate::data::{Position, Range, Source, SrcPos};
+use crate::named_entity::{AnyEntKind, Type, TypeEnt};
+use crate::syntax::TokenAccess;
+use crate::HasTokenSpan;
+
+pub fn list_code_actions(root: &DesignRoot, source: &Source, cursor: Position) -> Vec<CodeAction> {
+    for unit in root.units_by_source(source) {
+        let Some(data) = unit.unit.get() else {
+            continue;
+        };
+        let design_unit = data.data();
+        let AnyDesignUnit::Primary(AnyPrimaryUnit::Package(package)) = design_unit else {
+            continue;
+        };
+        let pos = design_unit.get_pos(&unit.tokens);
+        if !pos.contains(cursor) {
+            continue;
+        }
+
+        let unit_id = unit.unit_id();
+        let has_body = root.get_lib(unit_id.library_name()).is_some_and(|library| {
+            library
+                .secondary_units(unit_id.primary_name())
+                .next()
+                .is_some()
+        });
+        if has_body {
+            return Vec::new();
+        }
+
+        return package_body(root, &unit.tokens, package, &pos)
+            .map(|edit| CodeAction {
+                title: format!(
+                    "Create package body of '{}'",
+                    package.ident.tree.item.name_utf8()
+                ),
+                edits: vec![edit],
+            })
+            .into_iter()
+            .collect();
+    }
+    Vec::new()
+}
+
+/// Insert a package body after the package with an empty body of every subprogram
+/// and a placeholder value of every deferred constant
+fn package_body(
+    root: &DesignRoot,
+    ctx: &dyn TokenAccess,
+    package: &PackageDeclaration,
+    package_pos: &SrcPos,
+) -> Option<TextEdit> {
+    let mut items: Vec<String> = Vec::new();
+    let mut deferred: Vec<SrcPos> = Vec::new();
+
+    for decl in package.decl.iter() {
+        let pos = decl.span.pos(ctx);
+        let indent = line_indent(&pos);
+        match decl.item {
+            Declaration::SubprogramDeclaration(ref subprogram) => {
+                let spec = &subprogram.specification;
+                let kind = match spec {
+                    SubprogramSpecification::Procedure(_) => "procedure",
+                    SubprogramSpecification::Function(_) => "function",
+                };
+                items.push(format!(
+                    "{indent}{} is\n{indent}begin\n{indent}end {kind};",
+                    source_text(&spec.get_pos(ctx))
+                ));
+            }
+            Declaration::Object(ref object)
+                if object.class == ObjectClass::Constant && object.expression.is_none() =>
+            {
+                // Each identifier of `constant a, b : natural;` is a separate declaration
+                if deferred.contains(&pos) {
+                    continue;
+                }
+                deferred.push(pos.clone());
+
+                let AnyEntKind::DeferredConstant(subtype) =
+                    root.get_ent(object.ident.decl.get()?).kind()
+                else {
+                    continue;
+                };
+                let text = source_text(&pos);
+                let text = text.trim_end().trim_end_matches(';').trim_end();
+                items.push(format!(
+                    "{indent}{text} := {};",
+                    placeholder(subtype.type_mark())
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    if items.is_empty() {
+        return None;
+    }
+
+    let indent = line_indent(package_pos);
+    let name = package.ident.tree.item.name_utf8();
+    let end = package_pos.end();
+    Some(TextEdit::insert(
+        &SrcPos::new(package_pos.source.clone(), Range::new(end, end)),
+        format!(
+            "\n\n{indent}package body {name} is\n{}\n{indent}end package body;",
+            items.join("\n\n")
+        ),
+    ))
+}
+
+/// A value of the type to be replaced by the actual value of the constant
+fn placeholder(typ: TypeEnt) -> String {
+    match typ.base_type().kind() {
+        Type::Array { elem_type, .. } => format!("(others => {})", placeholder(*elem_type)),
+        Type::Record(region) => {
+            let elements: Vec<String> = region
+                .elems
+                .iter()
+                .map(|elem| format!("{} => {}", elem.designator(), placeholder(elem.type_mark())))
+                .collect();
+            format!("({})", elements.join(", "))
+        }
+        Type::Access(_) => "null".to_owned(),
+        _ => format!("{}'left", typ.designator()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::refactor::apply_edits;
+
+    #[test]
+    fn creates_package_body() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+package pkg is
+  type rec_t is record
+    valid : boolean;
+    data : bit_vector(7 downto 0);
+  end record;
+  constant width, depth : natural;
+  constant init : rec_t;
+  constant ready : boolean := true;
+
+  function max(a, b : integer)
+    return integer;
+  procedure reset(signal s : out rec_t);
+end package;
+",
+        );
+        // The missing full declarations of the deferred constants are reported
+        let (root, _) = builder.get_analyzed_root();
+
+        let actions = list_code_actions(&root, code.source(), code.s1("package pkg").start());
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].title, "Create package body of 'pkg'");
+        assert_eq!(
+            apply_edits(code.source(), &actions[0].edits),
+            "\
+package pkg is
+  type rec_t is record
+    valid : boolean;
+    data : bit_vector(7 downto 0);
+  end record;
+  constant width, depth : natural;
+  constant init : rec_t;
+  constant ready : boolean := true;
+
+  function max(a, b : integer)
+    return integer;
+  procedure reset(signal s : out rec_t);
+end package;
+
+package body pkg is
+  constant width, depth : natural := NATURAL'left;
+
+  constant init : rec_t := (valid => BOOLEAN'left, data => (others => BIT'left));
+
+  function max(a, b : integer)
+    return integer is
+  begin
+  end function;
+
+  procedure reset(signal s : out rec_t) is
+  begin
+  end procedure;
+end package body;
+"
+        );
+    }
+
+    #[test]
+    fn no_action_when_body_exists_or_is_not_needed() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+package with_body is
+  constant c : natural;
+end package;
+
+package body with_body is
+  constant c : natural := 0;
+end package body;
+
+package without_subprograms is
+  constant c : natural := 0;
+end package;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        for cursor in [
+            code.s1("with_body is").start(),
+            code.s1("without_subprograms").start(),
+        ] {
+            assert_eq!(list_code_actions(&root, code.source(), cursor), vec![]);
+        }
+    }
+}
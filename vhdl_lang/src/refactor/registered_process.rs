@@ -0,0 +1,117 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Template of a clocked process with the clock and reset of the project
+
+use super::{indent_lines, line_indent, TextEdit};
+use crate::config::RegisterConventions;
+use crate::data::{Position, Range, Source, SrcPos};
+
+/// Insert a clocked process at the cursor.
+/// Lines after the first are indented like the line of the cursor.
+pub fn insert_registered_process(
+    source: &Source,
+    cursor: Position,
+    conventions: &RegisterConventions,
+) -> TextEdit {
+    let pos = SrcPos::new(source.clone(), Range::new(cursor, cursor));
+    let text = indent_lines(&registered_process(conventions), &line_indent(&pos));
+    TextEdit::insert(&pos, text)
+}
+
+fn registered_process(conventions: &RegisterConventions) -> String {
+    let RegisterConventions {
+        clock,
+        reset,
+        reset_active_low,
+        async_reset,
+        falling_edge,
+    } = conventions;
+    let edge = if *falling_edge {
+        "falling_edge"
+    } else {
+        "rising_edge"
+    };
+    let active = if *reset_active_low { '0' } else { '1' };
+
+    if *async_reset {
+        format!(
+            "\
+process ({clock}, {reset})
+begin
+  if {reset} = '{active}' then
+  elsif {edge}({clock}) then
+  end if;
+end process;"
+        )
+    } else {
+        format!(
+            "\
+process ({clock})
+begin
+  if {edge}({clock}) then
+    if {reset} = '{active}' then
+    else
+    end if;
+  end if;
+end process;"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::refactor::apply_edits;
+    use crate::syntax::test::Code;
+
+    #[test]
+    fn inserts_synchronous_reset_process_by_default() {
+        let code = Code::new("architecture a of ent is\nbegin\n  \nend architecture;\n");
+        let edit = insert_registered_process(
+            code.source(),
+            Position::new(2, 2),
+            &RegisterConventions::default(),
+        );
+        assert_eq!(
+            apply_edits(code.source(), &[edit]),
+            "\
+architecture a of ent is
+begin
+  process (clk)
+  begin
+    if rising_edge(clk) then
+      if rst = '1' then
+      else
+      end if;
+    end if;
+  end process;
+end architecture;
+"
+        );
+    }
+
+    #[test]
+    fn asynchronous_active_low_reset() {
+        let conventions = RegisterConventions {
+            clock: "clk_i".to_owned(),
+            reset: "rst_n".to_owned(),
+            reset_active_low: true,
+            async_reset: true,
+            falling_edge: true,
+        };
+        assert_eq!(
+            registered_process(&conventions),
+            "\
+process (clk_i, rst_n)
+begin
+  if rst_n = '0' then
+  elsif falling_edge(clk_i) then
+  end if;
+end process;"
+        );
+    }
+}
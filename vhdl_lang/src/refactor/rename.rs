@@ -0,0 +1,201 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+use super::TextEdit;
+use crate::analysis::DesignRoot;
+use crate::ast::Designator;
+use crate::data::{ContentReader, Source, Symbol};
+use crate::syntax::{Kind, Tokenizer};
+use crate::{AnyEntKind, EntRef};
+use fnv::FnvHashSet;
+use std::path::Path;
+
+/// Check that `ent` can be renamed without breaking code that cannot be changed.
+/// Declarations within `read_only_libraries` such as the standard library
+/// are never renamed.
+pub fn check_rename(ent: EntRef, read_only_libraries: &FnvHashSet<Symbol>) -> Result<(), String> {
+    let Designator::Identifier(_) = ent.designator() else {
+        // It does not make sense to rename operator symbols and character literals
+        // Also they have different representations that would not be handled consistently
+        // Such as function "+"(arg1, arg2 : integer) but used as foo + bar
+        return Err(format!("Cannot rename {}", ent.describe()));
+    };
+    if matches!(ent.kind(), AnyEntKind::Library) {
+        return Err(format!(
+            "Cannot rename {}, libraries are defined by the configuration",
+            ent.describe()
+        ));
+    }
+    if ent.decl_pos().is_none() {
+        return Err(format!("Cannot rename implicit {}", ent.describe()));
+    }
+    match ent.library_name() {
+        Some(library_name) if read_only_libraries.contains(library_name) => Err(format!(
+            "Cannot rename {} declared in the read-only library {library_name}",
+            ent.describe()
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Rename `ent` to `new_name` in all files.
+/// This includes the `end` labels of the declaration as well as references
+/// from other design units such as the architecture names of configurations.
+pub fn rename(
+    root: &DesignRoot,
+    ent: EntRef,
+    new_name: &str,
+    read_only_libraries: &FnvHashSet<Symbol>,
+) -> Result<Vec<TextEdit>, String> {
+    check_rename(ent, read_only_libraries)?;
+    if !is_identifier(root, new_name) {
+        return Err(format!("'{new_name}' is not a valid identifier"));
+    }
+
+    // The same position is found through every library that a file is mapped to
+    let mut renamed = FnvHashSet::default();
+    Ok(root
+        .find_all_references(ent)
+        .into_iter()
+        .filter(|pos| renamed.insert(pos.clone()))
+        .map(|pos| TextEdit::replace(pos, new_name))
+        .collect())
+}
+
+/// Whether `name` is a single basic or extended identifier that is not a reserved word
+fn is_identifier(root: &DesignRoot, name: &str) -> bool {
+    // The tokenizer is lenient about the underscores of basic identifiers
+    if !name.starts_with('\\') && (name.contains("__") || name.ends_with('_')) {
+        return false;
+    }
+    let source = Source::inline(Path::new("<rename>"), name);
+    let contents = source.contents();
+    let mut tokenizer = Tokenizer::new(root.symbols(), &source, ContentReader::new(&contents));
+    matches!(tokenizer.pop(), Ok(Some(token)) if token.kind == Kind::Identifier)
+        && matches!(tokenizer.pop(), Ok(None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::syntax::test::assert_eq_unordered;
+
+    #[test]
+    fn renames_end_labels_and_configuration_references() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity foo is
+end entity foo;
+
+architecture rtl of foo is
+begin
+end architecture rtl;
+
+configuration cfg of foo is
+  for rtl
+  end for;
+end configuration cfg;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let ent = root
+            .search_reference(code.source(), code.s1("rtl").start())
+            .unwrap();
+        assert_eq_unordered(
+            &rename(&root, ent, "behav", &FnvHashSet::default()).unwrap(),
+            &[
+                TextEdit::replace(code.s("rtl", 1).pos(), "behav"),
+                TextEdit::replace(code.s("rtl", 2).pos(), "behav"),
+                TextEdit::replace(code.s("rtl", 3).pos(), "behav"),
+            ],
+        );
+
+        let ent = root
+            .search_reference(code.source(), code.s1("foo").start())
+            .unwrap();
+        assert_eq_unordered(
+            &rename(&root, ent, "top", &FnvHashSet::default()).unwrap(),
+            &[
+                TextEdit::replace(code.s("foo", 1).pos(), "top"),
+                TextEdit::replace(code.s("foo", 2).pos(), "top"),
+                TextEdit::replace(code.s("foo", 3).pos(), "top"),
+                TextEdit::replace(code.s("foo", 4).pos(), "top"),
+            ],
+        );
+    }
+
+    #[test]
+    fn error_on_invalid_new_name() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+package pkg is
+  constant c : natural := 0;
+end package;",
+        );
+        let (root, _) = builder.get_analyzed_root();
+        let ent = root
+            .search_reference(code.source(), code.s1("c :").start())
+            .unwrap();
+
+        for new_name in ["signal", "1abc", "a b", "a__b", "a_", ""] {
+            assert_eq!(
+                rename(&root, ent, new_name, &FnvHashSet::default()),
+                Err(format!("'{new_name}' is not a valid identifier"))
+            );
+        }
+        assert!(rename(&root, ent, "\\my name\\", &FnvHashSet::default()).is_ok());
+    }
+
+    #[test]
+    fn cannot_rename_within_read_only_libraries() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "vendor",
+            "
+package pkg is
+  constant c_vendor : natural := 0;
+end package;",
+        );
+        let code = builder.code(
+            "libname",
+            "
+library vendor;
+use vendor.pkg.all;
+
+package local is
+  constant c_local : natural := c_vendor;
+end package;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let read_only = FnvHashSet::from_iter([root.symbol_utf8("vendor")]);
+        let ent = root
+            .search_reference(code.source(), code.s1("c_vendor").start())
+            .unwrap();
+        assert_eq!(
+            check_rename(ent, &read_only),
+            Err(
+                "Cannot rename constant 'c_vendor' declared in the read-only library vendor"
+                    .to_owned()
+            )
+        );
+        let ent = root
+            .search_reference(code.source(), code.s1("vendor").start())
+            .unwrap();
+        assert!(check_rename(ent, &read_only).is_err());
+        let ent = root
+            .search_reference(code.source(), code.s1("c_local").start())
+            .unwrap();
+        assert_eq!(check_rename(ent, &read_only), Ok(()));
+    }
+}
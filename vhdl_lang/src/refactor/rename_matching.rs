@@ -0,0 +1,204 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+use super::TextEdit;
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::ast::Designator;
+use crate::data::{SrcPos, Symbol};
+use crate::named_entity::{AnyEntKind, EntRef, HasEntityId, Reference};
+use crate::syntax::TokenAccess;
+use fnv::FnvHashSet;
+use regex::Regex;
+
+/// Rename every named entity whose identifier matches `pattern`.
+/// The new name is created by replacing the matches of `pattern` with `replacement`,
+/// which may refer to capture groups such as `$1`.
+///
+/// Declarations within `excluded_libraries` are never renamed.
+pub fn rename_matching(
+    root: &DesignRoot,
+    pattern: &str,
+    replacement: &str,
+    excluded_libraries: &FnvHashSet<Symbol>,
+) -> Result<Vec<TextEdit>, String> {
+    let pattern = Regex::new(pattern).map_err(|err| format!("Invalid pattern: {err}"))?;
+
+    let mut searcher = RenameMatching {
+        root,
+        pattern: &pattern,
+        replacement,
+        excluded_libraries,
+        renamed: FnvHashSet::default(),
+        edits: Vec::new(),
+    };
+    let _ = root.search(&mut searcher);
+    Ok(searcher.edits)
+}
+
+struct RenameMatching<'a> {
+    root: &'a DesignRoot,
+    pattern: &'a Regex,
+    replacement: &'a str,
+    excluded_libraries: &'a FnvHashSet<Symbol>,
+    renamed: FnvHashSet<SrcPos>,
+    edits: Vec<TextEdit>,
+}
+
+impl<'a> RenameMatching<'a> {
+    fn new_name(&self, ent: EntRef) -> Option<String> {
+        if matches!(ent.kind(), AnyEntKind::Library) {
+            return None;
+        }
+        let Designator::Identifier(ref symbol) = ent.designator() else {
+            return None;
+        };
+        if self.excluded_libraries.contains(ent.library_name()?) {
+            return None;
+        }
+
+        let name = symbol.name_utf8();
+        if !self.pattern.is_match(&name) {
+            return None;
+        }
+        let new_name = self.pattern.replace_all(&name, self.replacement);
+        if new_name.is_empty() || new_name == name {
+            None
+        } else {
+            Some(new_name.into_owned())
+        }
+    }
+
+    fn rename(&mut self, pos: &SrcPos, new_name: &str) {
+        // The same position is found through every library that a file is mapped to
+        if self.renamed.insert(pos.clone()) {
+            self.edits.push(TextEdit::replace(pos.clone(), new_name));
+        }
+    }
+}
+
+impl<'a> Searcher for RenameMatching<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        if let Some(id) = decl.ent_id() {
+            let ent = self.root.get_ent(id);
+            if let Some(new_name) = self.new_name(ent) {
+                if let Some(decl_pos) = ent.decl_pos() {
+                    self.rename(decl_pos, &new_name);
+                }
+                if let Some(end_ident) = decl.end_ident_pos() {
+                    self.rename(ctx.get_pos(end_ident), &new_name);
+                }
+            }
+        }
+        NotFinished
+    }
+
+    fn search_pos_with_ref(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        reference: &Reference,
+    ) -> SearchState {
+        if let Some(id) = reference.get() {
+            if let Some(new_name) = self.new_name(self.root.get_ent(id)) {
+                self.rename(pos, &new_name);
+            }
+        }
+        NotFinished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::syntax::test::assert_eq_unordered;
+
+    #[test]
+    fn renames_declarations_and_references_matching_pattern() {
+        let mut builder = LibraryBuilder::new();
+        let pkg = builder.code(
+            "libname",
+            "
+package pkg is
+  constant s_width : natural := 8;
+  constant other : natural := s_width;
+end package pkg;",
+        );
+        let code = builder.code(
+            "libname",
+            "
+use work.pkg.all;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal s_data : bit_vector(s_width - 1 downto 0);
+begin
+  s_data <= (others => '0');
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let edits = rename_matching(&root, "^s_", "sig_", &FnvHashSet::default()).unwrap();
+        assert_eq_unordered(
+            &edits,
+            &[
+                TextEdit::replace(pkg.s("s_width", 1).pos(), "sig_width"),
+                TextEdit::replace(pkg.s("s_width", 2).pos(), "sig_width"),
+                TextEdit::replace(code.s1("s_width").pos(), "sig_width"),
+                TextEdit::replace(code.s("s_data", 1).pos(), "sig_data"),
+                TextEdit::replace(code.s("s_data", 2).pos(), "sig_data"),
+            ],
+        );
+    }
+
+    #[test]
+    fn does_not_rename_within_excluded_libraries() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "vendor",
+            "
+package pkg is
+  constant c_vendor : natural := 0;
+end package;",
+        );
+        let code = builder.code(
+            "libname",
+            "
+library vendor;
+use vendor.pkg.all;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  constant c_local : natural := c_vendor;
+begin
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let excluded = FnvHashSet::from_iter([root.symbol_utf8("vendor")]);
+        let edits = rename_matching(&root, "^c_(.*)$", "${1}_c", &excluded).unwrap();
+        assert_eq!(
+            edits,
+            vec![TextEdit::replace(code.s1("c_local").pos(), "local_c")]
+        );
+    }
+
+    #[test]
+    fn error_on_invalid_pattern() {
+        let builder = LibraryBuilder::new();
+        let (root, _) = builder.get_analyzed_root();
+        assert!(rename_matching(&root, "(", "x", &FnvHashSet::default())
+            .unwrap_err()
+            .starts_with("Invalid pattern"));
+    }
+}
@@ -0,0 +1,190 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Complete the sensitivity list of a combinational process with the signals that it reads
+
+use super::modernize::is_clocked;
+use super::signal_to_variable::{base_reference, encloses, find_begin};
+use super::{source_text, CodeAction, TextEdit};
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::ast::{ConcurrentStatement, ObjectClass, SensitivityList, SequentialStatement, Target};
+use crate::data::{Position, Range, Source, SrcPos};
+use crate::named_entity::{AnyEntKind, EntityId, Reference};
+use crate::syntax::TokenAccess;
+
+pub fn list_code_actions(root: &DesignRoot, source: &Source, cursor: Position) -> Vec<CodeAction> {
+    let mut searcher = ReadSearcher {
+        root,
+        source,
+        cursor,
+        process: None,
+        reads: Vec::new(),
+        targets: Vec::new(),
+    };
+    let _ = root.search_source(source, &mut searcher);
+    let Some(process) = searcher.process.as_ref() else {
+        return Vec::new();
+    };
+
+    let mut missing: Vec<(EntityId, String)> = Vec::new();
+    for (pos, id) in searcher.reads.iter() {
+        let is_write = searcher
+            .targets
+            .iter()
+            .any(|(target, base)| base == id && encloses(target, pos));
+        if encloses(&process.body, pos)
+            && !is_write
+            && !process.sensitive_to.contains(id)
+            && !missing.iter().any(|(other, _)| other == id)
+        {
+            missing.push((*id, source_text(pos)));
+        }
+    }
+    if missing.is_empty() {
+        return Vec::new();
+    }
+
+    let names: Vec<String> = missing.into_iter().map(|(_, name)| name).collect();
+    let end = process.last_name.end();
+    vec![CodeAction {
+        title: format!("Add '{}' to sensitivity list", names.join(", ")),
+        edits: vec![TextEdit::insert(
+            &SrcPos::new(source.clone(), Range::new(end, end)),
+            format!(", {}", names.join(", ")),
+        )],
+    }]
+}
+
+/// A combinational process with a list of signals at the cursor
+struct Process {
+    /// The signals of the sensitivity list
+    sensitive_to: Vec<EntityId>,
+    last_name: SrcPos,
+    /// From `begin` to the end of the process
+    body: SrcPos,
+}
+
+struct ReadSearcher<'a> {
+    root: &'a DesignRoot,
+    source: &'a Source,
+    cursor: Position,
+    process: Option<Process>,
+    /// The references to signals in the order of the source file
+    reads: Vec<(SrcPos, EntityId)>,
+    /// The targets of signal assignments and the signal that they assign
+    targets: Vec<(SrcPos, EntityId)>,
+}
+
+impl<'a> Searcher for ReadSearcher<'a> {
+    fn search_pos_with_ref(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        reference: &Reference,
+    ) -> SearchState {
+        if let Some(id) = reference.get() {
+            if let AnyEntKind::Object(object) = self.root.get_ent(id).kind() {
+                if object.class == ObjectClass::Signal {
+                    self.reads.push((pos.clone(), id));
+                }
+            }
+        }
+        NotFinished
+    }
+
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        match decl {
+            FoundDeclaration::ConcurrentStatement(stmt) if self.process.is_none() => {
+                let ConcurrentStatement::Process(ref process) = stmt.statement.item else {
+                    return NotFinished;
+                };
+                let Some(SensitivityList::Names(ref names)) = process.sensitivity_list else {
+                    return NotFinished;
+                };
+                let span = stmt.statement.span;
+                let pos = span.pos(ctx);
+                if pos.source != *self.source || !pos.contains(self.cursor) || is_clocked(ctx, span)
+                {
+                    return NotFinished;
+                }
+                let (Some(last), Some(begin)) = (names.last(), find_begin(ctx, span, process))
+                else {
+                    return NotFinished;
+                };
+                self.process = Some(Process {
+                    sensitive_to: names
+                        .iter()
+                        .filter_map(|name| base_reference(&name.item))
+                        .collect(),
+                    last_name: last.pos(ctx),
+                    body: begin.combine(ctx.get_pos(span.end_token)),
+                });
+            }
+            FoundDeclaration::SequentialStatement(stmt) => {
+                if let SequentialStatement::SignalAssignment(ref assign) = stmt.statement.item {
+                    if let Target::Name(ref name) = assign.target.item {
+                        if let Some(id) = base_reference(name) {
+                            self.targets.push((assign.target.span.pos(ctx), id));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        NotFinished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+
+    #[test]
+    fn adds_signals_read_by_combinational_process() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture rtl of ent is
+  signal sel, a, b, q, r : bit;
+  signal vec : bit_vector(1 downto 0);
+begin
+  mux: process (sel)
+  begin
+    if sel = '1' then
+      q <= a;
+    else
+      q <= b;
+    end if;
+    vec(0) <= a;
+  end process;
+
+  complete: process (a, b)
+  begin
+    r <= a and b;
+  end process;
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let cursor = code.s1("process (sel)").start();
+        assert_eq!(
+            list_code_actions(&root, code.source(), cursor),
+            vec![CodeAction {
+                title: "Add 'a, b' to sensitivity list".to_owned(),
+                edits: vec![TextEdit::insert(&code.s1("(sel)").s1(")").pos(), ", a, b")],
+            }]
+        );
+        let cursor = code.s1("process (a, b)").start();
+        assert_eq!(list_code_actions(&root, code.source(), cursor), vec![]);
+    }
+}
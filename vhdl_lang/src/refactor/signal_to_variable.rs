@@ -0,0 +1,455 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Convert a signal that is only used within a single process into a variable of that process
+
+use super::{line_indent, source_text, whole_lines, CodeAction, TextEdit};
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::ast::{
+    AssignmentRightHand, AttributeDesignator, CallOrIndexed, ConcurrentStatement, Expression, Name,
+    ObjectClass, ProcessStatement, SequentialStatement, SignalAssignment, Target, Waveform,
+};
+use crate::data::{Position, Range, Source, SrcPos};
+use crate::named_entity::{AnyEntKind, Concurrent, Design, EntRef, EntityId, OverloadedEnt};
+use crate::syntax::{Kind, TokenAccess};
+use crate::TokenSpan;
+
+pub fn list_code_actions(root: &DesignRoot, source: &Source, cursor: Position) -> Vec<CodeAction> {
+    root.item_at_cursor(source, cursor)
+        .and_then(|(_, ent)| signal_to_variable(root, source, ent))
+        .into_iter()
+        .collect()
+}
+
+/// Move the declaration of `signal` into the process that uses it and turn its
+/// assignments into variable assignments.
+/// Returns `None` unless the transformation preserves the behavior of the process.
+fn signal_to_variable(root: &DesignRoot, source: &Source, signal: EntRef) -> Option<CodeAction> {
+    if !is_local_signal(signal) {
+        return None;
+    }
+
+    let mut usage = SignalUsage {
+        root,
+        signal: signal.id(),
+        declarations: Vec::new(),
+        processes: Vec::new(),
+        writes: Vec::new(),
+        loops: Vec::new(),
+        restricted: Vec::new(),
+    };
+    let _ = root.search_source(source, &mut usage);
+
+    let (declaration, _) = usage
+        .declarations
+        .iter()
+        .find(|(_, id)| *id == signal.id())?;
+    // Each identifier of `signal a, b : bit;` shares the same declaration
+    if usage
+        .declarations
+        .iter()
+        .any(|(pos, id)| pos == declaration && *id != signal.id())
+    {
+        return None;
+    }
+
+    let references: Vec<SrcPos> = root
+        .find_all_references(signal)
+        .into_iter()
+        .filter(|pos| Some(pos) != signal.decl_pos())
+        .collect();
+    // References in the sensitivity list or the declarative part are outside of the body
+    let process = usage.processes.iter().find(|process| {
+        !references.is_empty() && references.iter().all(|pos| encloses(&process.body, pos))
+    })?;
+
+    let writes: Vec<&Write> = usage
+        .writes
+        .iter()
+        .filter(|write| encloses(&process.body, &write.statement))
+        .collect();
+    let first_write = writes
+        .iter()
+        .map(|write| &write.statement)
+        .min_by_key(|pos| pos.start())?;
+
+    for read in references
+        .iter()
+        .filter(|pos| !writes.iter().any(|write| encloses(&write.target, pos)))
+    {
+        // A signal keeps its old value until the process suspends whereas a variable is
+        // updated immediately, so the value may only be read before it is assigned
+        if read.start() > first_write.end()
+            || usage.restricted.iter().any(|pos| encloses(pos, read))
+            || usage.loops.iter().any(|pos| {
+                encloses(pos, read) && writes.iter().any(|write| encloses(pos, &write.statement))
+            })
+        {
+            return None;
+        }
+    }
+
+    let text = source_text(declaration);
+    if !text.get(..6)?.eq_ignore_ascii_case("signal") {
+        return None;
+    }
+    let variable = format!("variable{}", &text[6..]);
+
+    let mut edits = vec![
+        TextEdit::replace(whole_lines(declaration), ""),
+        declare_before_begin(&process.begin, &process.decl_indent, &variable),
+    ];
+    for write in writes {
+        let edit = TextEdit::replace(write.arrow.clone()?, ":=");
+        // A source file mapped to several libraries is searched once per library
+        if !edits.contains(&edit) {
+            edits.push(edit);
+        }
+    }
+    edits.sort_by_key(|edit| edit.pos.start());
+
+    let title = match process.label {
+        Some(ref label) => format!(
+            "Convert signal '{}' to a variable of process '{label}'",
+            signal.designator()
+        ),
+        None => format!(
+            "Convert signal '{}' to a process variable",
+            signal.designator()
+        ),
+    };
+    Some(CodeAction { title, edits })
+}
+
+/// A signal declared within an architecture, block or generate statement
+fn is_local_signal(ent: EntRef) -> bool {
+    let AnyEntKind::Object(object) = ent.kind() else {
+        return false;
+    };
+    object.class == ObjectClass::Signal
+        && object.iface.is_none()
+        && ent.parent.is_some_and(|parent| {
+            matches!(
+                parent.kind(),
+                AnyEntKind::Design(Design::Architecture(..))
+                    | AnyEntKind::Concurrent(Some(Concurrent::Block | Concurrent::Generate))
+            )
+        })
+}
+
+pub(super) fn encloses(outer: &SrcPos, inner: &SrcPos) -> bool {
+    outer.source == inner.source && outer.start() <= inner.start() && inner.end() <= outer.end()
+}
+
+struct Process {
+    label: Option<String>,
+    begin: SrcPos,
+    /// From `begin` to the end of the process
+    body: SrcPos,
+    /// The indentation of a new declaration within the process
+    decl_indent: String,
+}
+
+struct Write {
+    statement: SrcPos,
+    target: SrcPos,
+    /// The `<=` of a plain assignment, `None` if the assignment has no variable counterpart
+    arrow: Option<SrcPos>,
+}
+
+struct SignalUsage<'a> {
+    root: &'a DesignRoot,
+    signal: EntityId,
+    /// The declaration of every signal of the source file
+    declarations: Vec<(SrcPos, EntityId)>,
+    processes: Vec<Process>,
+    /// The signal assignments to the signal
+    writes: Vec<Write>,
+    loops: Vec<SrcPos>,
+    /// Where the signal may only be used as a signal, such as in a wait statement
+    restricted: Vec<SrcPos>,
+}
+
+impl<'a> SignalUsage<'a> {
+    /// True if the subprogram that is called has a formal of class signal
+    fn has_signal_formal(&self, call: &CallOrIndexed) -> bool {
+        call.name
+            .item
+            .get_suffix_reference()
+            .and_then(|id| OverloadedEnt::from_any(self.root.get_ent(id)))
+            .is_some_and(|subprogram| subprogram.formals().iter().any(|formal| formal.is_signal()))
+    }
+}
+
+impl<'a> Searcher for SignalUsage<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        match decl {
+            FoundDeclaration::Object(object) if object.class == ObjectClass::Signal => {
+                if let Some(id) = object.ident.decl.get() {
+                    let pos = self.root.get_ent(id).src_span.pos(ctx);
+                    self.declarations.push((pos, id));
+                }
+            }
+            FoundDeclaration::ConcurrentStatement(stmt) => {
+                if let ConcurrentStatement::Process(ref process) = stmt.statement.item {
+                    if let Some(begin) = find_begin(ctx, stmt.statement.span, process) {
+                        let pos = stmt.statement.span.pos(ctx);
+                        let decl_indent = match process.decl.last() {
+                            Some(decl) => line_indent(&decl.span.pos(ctx)),
+                            None => format!("{}  ", line_indent(&pos)),
+                        };
+                        self.processes.push(Process {
+                            label: stmt.label.tree.as_ref().map(|label| label.item.to_string()),
+                            body: begin.combine(ctx.get_pos(stmt.statement.span.end_token)),
+                            begin,
+                            decl_indent,
+                        });
+                    }
+                }
+            }
+            FoundDeclaration::SequentialStatement(stmt) => {
+                let pos = stmt.statement.span.pos(ctx);
+                match stmt.statement.item {
+                    SequentialStatement::SignalAssignment(ref assign) => {
+                        if let Target::Name(ref name) = assign.target.item {
+                            if base_reference(name) == Some(self.signal) {
+                                self.writes.push(Write {
+                                    statement: pos,
+                                    target: assign.target.span.pos(ctx),
+                                    arrow: assignment_arrow(ctx, assign),
+                                });
+                            }
+                        }
+                    }
+                    SequentialStatement::SignalForceAssignment(..)
+                    | SequentialStatement::SignalReleaseAssignment(..)
+                    | SequentialStatement::Wait(..) => self.restricted.push(pos),
+                    SequentialStatement::ProcedureCall(ref call)
+                        if self.has_signal_formal(&call.item) =>
+                    {
+                        self.restricted.push(pos)
+                    }
+                    SequentialStatement::Loop(..) => self.loops.push(pos),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        NotFinished
+    }
+
+    fn search_expression(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        expr: &Expression,
+    ) -> SearchState {
+        if let Expression::Name(ref name) = expr {
+            let restricted = match name.as_ref() {
+                Name::Attribute(attr) => matches!(attr.attr.item, AttributeDesignator::Signal(_)),
+                Name::CallOrIndexed(call) => self.has_signal_formal(call),
+                _ => false,
+            };
+            if restricted {
+                self.restricted.push(pos.clone());
+            }
+        }
+        NotFinished
+    }
+}
+
+/// The position of the `begin` keyword of a process
+pub(super) fn find_begin(
+    ctx: &dyn TokenAccess,
+    span: TokenSpan,
+    process: &ProcessStatement,
+) -> Option<SrcPos> {
+    // Subprogram bodies within the declarative part have a `begin` of their own
+    let start = process
+        .decl
+        .last()
+        .map_or(span.start_token, |decl| decl.span.end_token);
+    ctx.get_token_slice(start, span.end_token)
+        .iter()
+        .find(|token| token.kind == Kind::Begin)
+        .map(|token| token.pos.clone())
+}
+
+/// Insert `declaration` on lines of its own before the `begin` of a process.
+/// Lines of the declaration after the first must already be indented.
+pub(super) fn declare_before_begin(begin: &SrcPos, indent: &str, declaration: &str) -> TextEdit {
+    if line_indent(begin).len() == begin.start().character as usize {
+        let line_start = Position::new(begin.start().line, 0);
+        TextEdit::insert(
+            &SrcPos::new(begin.source.clone(), Range::new(line_start, line_start)),
+            format!("{indent}{declaration}\n"),
+        )
+    } else {
+        TextEdit::insert(
+            begin,
+            format!("\n{indent}{declaration}\n{}", line_indent(begin)),
+        )
+    }
+}
+
+/// The `<=` of an assignment of a single waveform element without delay
+fn assignment_arrow(ctx: &dyn TokenAccess, assign: &SignalAssignment) -> Option<SrcPos> {
+    let AssignmentRightHand::Simple(Waveform::Elements(ref elements)) = assign.rhs else {
+        return None;
+    };
+    let [ref element] = elements[..] else {
+        return None;
+    };
+    if assign.delay_mechanism.is_some() || element.after.is_some() {
+        return None;
+    }
+    let tokens = ctx.get_token_slice(assign.target.span.end_token, element.value.span.start_token);
+    match tokens {
+        [_, arrow, _] if arrow.kind == Kind::LTE => Some(arrow.pos.clone()),
+        _ => None,
+    }
+}
+
+/// The object that is named by `s`, `s(0)`, `s(1 downto 0)` or `s.field`
+pub(super) fn base_reference(name: &Name) -> Option<EntityId> {
+    match name {
+        Name::Designator(designator) => designator.reference.get(),
+        Name::Selected(prefix, _) | Name::Slice(prefix, _) => base_reference(&prefix.item),
+        Name::CallOrIndexed(call) => base_reference(&call.name.item),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::refactor::apply_edits;
+
+    #[test]
+    fn converts_signal_of_clocked_process() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+  port (clk : in bit; q : out natural);
+end entity;
+
+architecture a of ent is
+  signal count : natural := 0;
+begin
+  main: process (clk)
+  begin
+    if clk = '1' then
+      q <= count;
+      count <= count + 1;
+    end if;
+  end process;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let actions = list_code_actions(&root, code.source(), code.s1("count").start());
+        assert_eq!(actions.len(), 1);
+        assert_eq!(
+            actions[0].title,
+            "Convert signal 'count' to a variable of process 'main'"
+        );
+        assert_eq!(
+            apply_edits(code.source(), &actions[0].edits),
+            "\
+entity ent is
+  port (clk : in bit; q : out natural);
+end entity;
+
+architecture a of ent is
+begin
+  main: process (clk)
+    variable count : natural := 0;
+  begin
+    if clk = '1' then
+      q <= count;
+      count := count + 1;
+    end if;
+  end process;
+end architecture;
+"
+        );
+    }
+
+    #[test]
+    fn no_action_when_read_after_write() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+  port (d : in bit; q : out bit);
+end entity;
+
+architecture a of ent is
+  signal tmp : bit;
+begin
+  process (d)
+  begin
+    tmp <= d;
+    q <= tmp;
+  end process;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+        assert_eq!(
+            list_code_actions(&root, code.source(), code.s1("tmp").start()),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn no_action_for_signal_used_by_several_processes_or_attributes() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+  port (clk : in bit; q : out bit);
+end entity;
+
+architecture a of ent is
+  signal common : bit;
+  signal toggle : bit;
+begin
+  process (clk)
+  begin
+    q <= common;
+    if toggle'event then
+      toggle <= not toggle;
+    end if;
+  end process;
+
+  process
+  begin
+    common <= '1';
+    wait;
+  end process;
+end architecture;
+",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+        for name in ["common", "toggle"] {
+            assert_eq!(
+                list_code_actions(&root, code.source(), code.s1(name).start()),
+                vec![]
+            );
+        }
+    }
+}
@@ -0,0 +1,301 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Testbench of an entity that instantiates it and drives its clock and reset
+
+use super::align::align_declarations;
+use super::instantiation::instantiation;
+use super::{find_primary_unit, indent_lines, source_text};
+use crate::analysis::DesignRoot;
+use crate::ast::{AnyDesignUnit, AnyPrimaryUnit, InterfaceDeclaration, Mode, ModeIndication};
+use crate::config::RegisterConventions;
+use crate::data::HasSource;
+use crate::named_entity::{AnyEntKind, Design};
+use crate::syntax::TokenAccess;
+use crate::HasTokenSpan;
+use std::path::PathBuf;
+
+/// The number of clock cycles that the reset is active after the start of the simulation
+const RESET_CYCLES: usize = 10;
+
+/// A testbench of an entity, to be written to a new file
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Testbench {
+    /// The name of the testbench entity, i.e. the name of the entity with the suffix `_tb`
+    pub name: String,
+    /// The file to create, in the same directory as the file of the entity
+    pub file_name: PathBuf,
+    /// The file of the entity, the testbench belongs to the same library
+    pub entity_file_name: PathBuf,
+    /// The text of the new file, i.e. the testbench entity and its architecture
+    pub text: String,
+}
+
+/// Create a testbench of an entity given as a selected name such as `lib.ent`.
+///
+/// The generics of the entity become generics of the testbench and every port is associated
+/// with a signal of the same name. When the entity has the clock or reset port of `conventions`,
+/// the clock is toggled and the reset is released after a few clock cycles.
+/// With `vunit`, the testbench has the `runner_cfg` generic and the test runner of VUnit.
+pub fn testbench(
+    root: &DesignRoot,
+    entity: &str,
+    conventions: &RegisterConventions,
+    vunit: bool,
+) -> Result<Testbench, String> {
+    let unit = find_primary_unit(root, entity)?;
+    let Some(data) = unit.unit.get() else {
+        return Err(format!("'{entity}' has not been analyzed"));
+    };
+    let AnyDesignUnit::Primary(AnyPrimaryUnit::Entity(entity_decl)) = data.data() else {
+        return Err(format!("'{entity}' is not an entity"));
+    };
+    let Some(id) = entity_decl.ident.decl.get() else {
+        return Err(format!("'{entity}' has not been analyzed"));
+    };
+    let AnyEntKind::Design(Design::Entity(_, region)) = root.get_ent(id).kind() else {
+        return Err(format!("'{entity}' is not an entity"));
+    };
+    let tokens: &dyn TokenAccess = &unit.tokens;
+    let entity_name = entity_decl.ident.tree.item.name_utf8();
+    let name = format!("{entity_name}_tb");
+
+    let mut text = String::new();
+    // The types of the generics and ports are visible through the context clause of the entity
+    if let (Some(first), Some(last)) = (
+        entity_decl.context_clause.first(),
+        entity_decl.context_clause.last(),
+    ) {
+        text.push_str(&source_text(
+            &first.get_pos(tokens).combine(&last.get_pos(tokens)),
+        ));
+        text.push('\n');
+    }
+    if vunit {
+        text.push_str("library vunit_lib;\ncontext vunit_lib.vunit_context;\n");
+    }
+    if !text.is_empty() {
+        text.push('\n');
+    }
+
+    let mut generics: Vec<String> = entity_decl
+        .generic_clause
+        .iter()
+        .flatten()
+        .map(|generic| generic.to_string())
+        .collect();
+    if vunit {
+        generics.push("runner_cfg : string".to_owned());
+    }
+    text.push_str(&format!("entity {name} is\n"));
+    if !generics.is_empty() {
+        text.push_str(&format!(
+            "  generic (\n    {}\n  );\n",
+            generics.join(";\n    ")
+        ));
+    }
+    text.push_str("end entity;\n\n");
+
+    let (active, inactive) = if conventions.reset_active_low {
+        ('0', '1')
+    } else {
+        ('1', '0')
+    };
+    let mut clock = None;
+    let mut reset = None;
+    let mut signals = Vec::new();
+    for port in entity_decl.port_clause.iter().flatten() {
+        let InterfaceDeclaration::Object(ref object) = port else {
+            continue;
+        };
+        // The subtype of a port with a mode view is not known without its view
+        let ModeIndication::Simple(ref mode) = object.mode else {
+            continue;
+        };
+        let port_name = object.ident.tree.item.name_utf8();
+        let is_input = matches!(mode.mode, None | Some(Mode::In));
+        let initial = if is_input && port_name.eq_ignore_ascii_case(&conventions.clock) {
+            clock = Some(port_name.clone());
+            " := '0'".to_owned()
+        } else if is_input && port_name.eq_ignore_ascii_case(&conventions.reset) {
+            reset = Some(port_name.clone());
+            format!(" := '{active}'")
+        } else {
+            match mode.expression {
+                Some(ref expression) => format!(" := {expression}"),
+                None => String::new(),
+            }
+        };
+        signals.push(format!(
+            "signal {port_name} : {}{initial};",
+            mode.subtype_indication
+        ));
+    }
+    align_declarations(&mut signals);
+
+    text.push_str(&format!("architecture tb of {name} is\n"));
+    if let Some(ref clock) = clock {
+        text.push_str(&format!("  constant {clock}_period : time := 10 ns;\n\n"));
+    }
+    for signal in signals.iter() {
+        text.push_str(&format!("  {signal}\n"));
+    }
+    text.push_str("begin\n");
+    let dut = instantiation(&format!("dut: entity work.{entity_name}"), region);
+    text.push_str(&format!("  {}\n", indent_lines(&dut, "  ")));
+
+    if let Some(ref clock) = clock {
+        text.push_str(&format!(
+            "\n  {clock} <= not {clock} after {clock}_period / 2;\n"
+        ));
+    }
+    text.push_str("\n  main: process\n  begin\n");
+    if vunit {
+        text.push_str("    test_runner_setup(runner, runner_cfg);\n");
+    }
+    if let Some(ref reset) = reset {
+        let delay = match clock {
+            Some(ref clock) => format!("{RESET_CYCLES} * {clock}_period"),
+            None => "100 ns".to_owned(),
+        };
+        text.push_str(&format!(
+            "    wait for {delay};\n    {reset} <= '{inactive}';\n"
+        ));
+    }
+    text.push('\n');
+    if vunit {
+        text.push_str("    test_runner_cleanup(runner);\n");
+    } else {
+        text.push_str("    wait;\n");
+    }
+    text.push_str("  end process;\nend architecture;\n");
+
+    let entity_file_name = unit.source().file_name().to_owned();
+    let ext = entity_file_name
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("vhd");
+    Ok(Testbench {
+        file_name: entity_file_name.with_file_name(format!("{name}.{ext}")),
+        entity_file_name,
+        name,
+        text,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+
+    #[test]
+    fn creates_vunit_testbench_with_clock_and_reset() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity fifo is
+  generic (depth : natural := 16);
+  port (
+    clk : in std_logic;
+    rst : in std_logic;
+    data : out std_logic_vector(7 downto 0)
+  );
+end entity;
+",
+        );
+        builder.add_std_logic_1164();
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let tb = testbench(&root, "libname.fifo", &RegisterConventions::default(), true).unwrap();
+        assert_eq!(tb.name, "fifo_tb");
+        assert_eq!(
+            tb.file_name,
+            code.source().file_name().with_file_name("fifo_tb.vhd")
+        );
+        assert_eq!(
+            tb.text,
+            "\
+library ieee;
+use ieee.std_logic_1164.all;
+library vunit_lib;
+context vunit_lib.vunit_context;
+
+entity fifo_tb is
+  generic (
+    depth : natural := 16;
+    runner_cfg : string
+  );
+end entity;
+
+architecture tb of fifo_tb is
+  constant clk_period : time := 10 ns;
+
+  signal clk  : std_logic := '0';
+  signal rst  : std_logic := '1';
+  signal data : std_logic_vector(7 downto 0);
+begin
+  dut: entity work.fifo
+    generic map (
+      depth => depth
+    )
+    port map (
+      clk  => clk,
+      rst  => rst,
+      data => data
+    );
+
+  clk <= not clk after clk_period / 2;
+
+  main: process
+  begin
+    test_runner_setup(runner, runner_cfg);
+    wait for 10 * clk_period;
+    rst <= '0';
+
+    test_runner_cleanup(runner);
+  end process;
+end architecture;
+"
+        );
+    }
+
+    #[test]
+    fn testbench_without_clock_and_reset() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "\
+entity adder is
+  port (a, b : in natural; sum : out natural);
+end entity;
+
+package pkg is
+end package;",
+        );
+        let (root, _) = builder.get_analyzed_root();
+
+        let conventions = RegisterConventions::default();
+        let tb = testbench(&root, "libname.adder", &conventions, false).unwrap();
+        assert!(tb.text.starts_with("entity adder_tb is\nend entity;\n"));
+        assert!(tb.text.contains(
+            "\
+  main: process
+  begin
+
+    wait;
+  end process;"
+        ));
+        assert_eq!(
+            testbench(&root, "libname.pkg", &conventions, false),
+            Err("'libname.pkg' is not an entity".to_owned())
+        );
+    }
+}
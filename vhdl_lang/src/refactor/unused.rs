@@ -0,0 +1,237 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Removal of unused signals, variables, constants and use clauses
+
+use super::use_clauses::unused_use_clauses;
+use super::{whole_lines, TextEdit};
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::ast::{HasUnitId, ObjectClass};
+use crate::data::{Source, SrcPos};
+use crate::lint::dead_code::find_unused_declarations;
+use crate::named_entity::EntityId;
+use crate::syntax::TokenAccess;
+use crate::TokenSpan;
+use fnv::FnvHashSet;
+
+/// A declaration is kept if a comment on the declaration contains this text.
+/// Whitespace and case are ignored, i.e. `-- VHDL_LS: allow(unused)` is also recognized.
+pub const ALLOW_UNUSED_COMMENT: &str = "vhdl_ls:allow(unused)";
+
+/// Remove the unused signals, variables, constants and use clauses of a source file.
+/// A declaration of several identifiers is only removed when all of them are unused.
+pub fn remove_unused_declarations(root: &DesignRoot, source: &Source) -> Vec<TextEdit> {
+    let primary_units: FnvHashSet<_> = root
+        .units_by_source(source)
+        .map(|unit| {
+            let unit_id = unit.unit_id();
+            (
+                unit_id.library_name().clone(),
+                unit_id.primary_name().clone(),
+            )
+        })
+        .collect();
+
+    let mut unused = FnvHashSet::default();
+    for (library_name, primary_name) in primary_units.iter() {
+        if let Some(library) = root.get_lib(library_name) {
+            unused.extend(
+                find_unused_declarations(root, library, primary_name)
+                    .into_iter()
+                    .map(|ent| ent.id()),
+            );
+        }
+    }
+
+    let mut searcher = UnusedObjects {
+        root,
+        source,
+        unused: &unused,
+        declarations: Vec::new(),
+    };
+    let _ = root.search_source(source, &mut searcher);
+
+    let mut removed: Vec<SrcPos> = searcher
+        .declarations
+        .into_iter()
+        .filter_map(|(pos, all_unused)| all_unused.then_some(pos))
+        .collect();
+    removed.extend(unused_use_clauses(root, source));
+
+    let mut edits: Vec<TextEdit> = removed
+        .iter()
+        .map(|pos| TextEdit::replace(whole_lines(pos), ""))
+        .collect();
+    edits.sort_by_key(|edit| edit.pos.start());
+    edits
+}
+
+struct UnusedObjects<'a> {
+    root: &'a DesignRoot,
+    source: &'a Source,
+    unused: &'a FnvHashSet<EntityId>,
+    /// The position of each object declaration and whether all of its identifiers are unused
+    declarations: Vec<(SrcPos, bool)>,
+}
+
+impl<'a> Searcher for UnusedObjects<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        let FoundDeclaration::Object(object) = decl else {
+            return NotFinished;
+        };
+        if !matches!(
+            object.class,
+            ObjectClass::Signal | ObjectClass::Variable | ObjectClass::Constant
+        ) {
+            return NotFinished;
+        }
+        let Some(id) = object.ident.decl.get() else {
+            return NotFinished;
+        };
+        let span = self.root.get_ent(id).src_span;
+
+        let pos = span.pos(ctx);
+        if pos.source != *self.source {
+            return NotFinished;
+        }
+        let is_unused = self.unused.contains(&id) && !allows_unused(ctx, span);
+
+        // Each identifier of `signal a, b : bit;` is a separate declaration with the same span
+        match self
+            .declarations
+            .iter_mut()
+            .find(|(other, _)| *other == pos)
+        {
+            Some((_, all_unused)) => *all_unused &= is_unused,
+            None => self.declarations.push((pos, is_unused)),
+        }
+        NotFinished
+    }
+}
+
+/// True if a comment before or after the declaration suppresses the removal
+pub(super) fn allows_unused(ctx: &dyn TokenAccess, span: TokenSpan) -> bool {
+    let leading = ctx
+        .get_token(span.start_token)
+        .comments
+        .iter()
+        .flat_map(|comments| comments.leading.iter());
+    let trailing = ctx
+        .get_token(span.end_token)
+        .comments
+        .iter()
+        .flat_map(|comments| comments.trailing.iter());
+
+    leading.chain(trailing).any(|comment| {
+        comment
+            .value
+            .replace(char::is_whitespace, "")
+            .to_lowercase()
+            .contains(ALLOW_UNUSED_COMMENT)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+
+    #[test]
+    fn removes_unused_objects() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal unused_sig : bit;
+  signal used_sig, unused_other : bit;
+  constant unused_const : natural := 0;
+begin
+  process
+    variable unused_var : natural;
+  begin
+    used_sig <= '1';
+    wait;
+  end process;
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let removed = |substr: &str| TextEdit::replace(whole_lines(&code.s1(substr).pos()), "");
+        assert_eq!(
+            remove_unused_declarations(&root, code.source()),
+            vec![
+                removed("signal unused_sig : bit;"),
+                removed("constant unused_const : natural := 0;"),
+                removed("variable unused_var : natural;"),
+            ]
+        );
+    }
+
+    #[test]
+    fn removes_unused_use_clauses() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+package pkg is
+  constant c1 : natural := 1;
+  constant c2 : natural := 2;
+end package;",
+        );
+        let code = builder.code(
+            "libname",
+            "
+use work.pkg.c1;
+use work.pkg.c2;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal s : bit_vector(c1 downto 0);
+begin
+  s <= (others => '0');
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        assert_eq!(
+            remove_unused_declarations(&root, code.source()),
+            vec![TextEdit::replace(
+                whole_lines(&code.s1("use work.pkg.c2;").pos()),
+                ""
+            )]
+        );
+    }
+
+    #[test]
+    fn keeps_declarations_with_allow_unused_comment() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+entity ent is
+end entity;
+
+architecture a of ent is
+  -- vhdl_ls: allow(unused)
+  signal debug1 : bit;
+  signal debug2 : bit; -- VHDL_LS: allow(unused)
+begin
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+        assert_eq!(remove_unused_declarations(&root, code.source()), vec![]);
+    }
+}
@@ -0,0 +1,295 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Update the associations of the instantiations of an entity after its generics or ports changed
+
+use super::missing_associations::{add_map_aspect, associate};
+use super::{find_primary_unit, line_indent, TextEdit};
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::ast::token_range::WithTokenSpan;
+use crate::ast::{AnyDesignUnit, AnyPrimaryUnit, ConcurrentStatement, MapAspect, Name};
+use crate::data::{Range, SrcPos};
+use crate::named_entity::{AnyEntKind, Design, EntityId, InterfaceEnt};
+use crate::syntax::TokenAccess;
+
+/// Make the named associations of every instantiation of an entity match its generics and ports.
+/// The entity is given as a selected name such as `lib.ent`.
+///
+/// - An association of a formal that no longer exists is renamed to the formal that is
+///   declared at its position if that formal is not associated, otherwise it is removed
+/// - Generics and ports without default that are not associated are associated with
+///   same-named actuals, like output ports they may be left open otherwise
+///
+/// Positional associations are left as they are.
+pub fn update_instantiations(root: &DesignRoot, entity: &str) -> Result<Vec<TextEdit>, String> {
+    let unit = find_primary_unit(root, entity)?;
+    let Some(data) = unit.unit.get() else {
+        return Err(format!("'{entity}' has not been analyzed"));
+    };
+    let AnyDesignUnit::Primary(AnyPrimaryUnit::Entity(entity_decl)) = data.data() else {
+        return Err(format!("'{entity}' is not an entity"));
+    };
+    let Some(id) = entity_decl.ident.decl.get() else {
+        return Err(format!("'{entity}' has not been analyzed"));
+    };
+    let AnyEntKind::Design(Design::Entity(_, region)) = root.get_ent(id).kind() else {
+        return Err(format!("'{entity}' is not an entity"));
+    };
+    let (ports, generics) = region.ports_and_generics();
+
+    let mut searcher = InstanceSearcher {
+        id,
+        generics: &generics,
+        ports: &ports,
+        edits: Vec::new(),
+    };
+    let _ = root.search(&mut searcher);
+    Ok(searcher.edits)
+}
+
+struct InstanceSearcher<'e, 'a> {
+    id: EntityId,
+    generics: &'e [InterfaceEnt<'a>],
+    ports: &'e [InterfaceEnt<'a>],
+    edits: Vec<TextEdit>,
+}
+
+impl<'e, 'a> Searcher for InstanceSearcher<'e, 'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        let FoundDeclaration::ConcurrentStatement(stmt) = decl else {
+            return NotFinished;
+        };
+        let ConcurrentStatement::Instance(ref instance) = stmt.statement.item else {
+            return NotFinished;
+        };
+        if instance.entity_reference() != Some(self.id) {
+            return NotFinished;
+        }
+
+        let span = stmt.statement.span;
+        let indent = line_indent(&span.pos(ctx));
+        let mut edits = Vec::new();
+        match instance.generic_map {
+            Some(ref map_aspect) => edits.extend(update_map_aspect(ctx, map_aspect, self.generics)),
+            None => edits.extend(add_map_aspect(
+                ctx,
+                span.start_token,
+                instance
+                    .port_map
+                    .as_ref()
+                    .map_or(span.end_token, |port_map| port_map.start),
+                "generic",
+                &required(self.generics, &[]),
+                &indent,
+            )),
+        }
+        match instance.port_map {
+            Some(ref map_aspect) => edits.extend(update_map_aspect(ctx, map_aspect, self.ports)),
+            None => edits.extend(add_map_aspect(
+                ctx,
+                span.start_token,
+                span.end_token,
+                "port",
+                &required(self.ports, &[]),
+                &indent,
+            )),
+        }
+
+        for edit in edits {
+            // A source file mapped to several libraries contains the same units once per library
+            if !self.edits.contains(&edit) {
+                self.edits.push(edit);
+            }
+        }
+        NotFinished
+    }
+}
+
+/// The formals that must be associated but are not
+fn required<'a>(formals: &[InterfaceEnt<'a>], associated: &[bool]) -> Vec<InterfaceEnt<'a>> {
+    formals
+        .iter()
+        .enumerate()
+        .filter(|(idx, formal)| {
+            !associated.get(*idx).copied().unwrap_or_default()
+                && !formal.has_default()
+                && !formal.is_out_or_inout_signal()
+        })
+        .map(|(_, formal)| *formal)
+        .collect()
+}
+
+fn update_map_aspect(
+    ctx: &dyn TokenAccess,
+    map_aspect: &MapAspect,
+    formals: &[InterfaceEnt],
+) -> Vec<TextEdit> {
+    let elements = &map_aspect.list.items;
+    // The index of the formal of each association
+    let mut formal_of: Vec<Option<usize>> = vec![None; elements.len()];
+    // The associations of formals that do not exist and the position of their base name
+    let mut stale: Vec<(usize, SrcPos)> = Vec::new();
+
+    for (idx, elem) in elements.iter().enumerate() {
+        let Some(ref formal) = elem.formal else {
+            // Positional associations precede named associations
+            formal_of[idx] = Some(idx);
+            continue;
+        };
+        let base = base_name(formal);
+        let Name::Designator(ref designator) = base.item else {
+            continue;
+        };
+        match formals
+            .iter()
+            .position(|formal| *formal.designator() == designator.item)
+        {
+            Some(formal) => formal_of[idx] = Some(formal),
+            None => stale.push((idx, base.pos(ctx))),
+        }
+    }
+
+    let mut renames = Vec::new();
+    let mut removed = Vec::new();
+    for (idx, pos) in stale {
+        if idx < formals.len() && !formal_of.contains(&Some(idx)) {
+            formal_of[idx] = Some(idx);
+            renames.push(TextEdit::replace(
+                pos,
+                formals[idx].designator().to_string(),
+            ));
+        } else {
+            removed.push(idx);
+        }
+    }
+
+    let associated: Vec<bool> = (0..formals.len())
+        .map(|idx| formal_of.contains(&Some(idx)))
+        .collect();
+    // Insertions come before the replacements and removals that start at the same position
+    let mut edits = associate(
+        ctx,
+        map_aspect,
+        &formal_of,
+        formals,
+        &required(formals, &associated),
+    );
+    edits.extend(renames);
+
+    // Consecutive associations are removed together with the separators between them
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for idx in removed {
+        match runs.last_mut() {
+            Some((_, last)) if *last + 1 == idx => *last = idx,
+            _ => runs.push((idx, idx)),
+        }
+    }
+    let start = |idx: usize| {
+        let elem = &elements[idx];
+        elem.formal
+            .as_ref()
+            .map_or_else(|| elem.actual.pos(ctx), |formal| formal.pos(ctx))
+            .start()
+    };
+    let end = |idx: usize| elements[idx].actual.pos(ctx).end();
+    let source = &ctx.get_pos(map_aspect.start).source;
+    for (first, last) in runs {
+        let range = if last + 1 < elements.len() {
+            Range::new(start(first), start(last + 1))
+        } else if first > 0 {
+            Range::new(end(first - 1), end(last))
+        } else {
+            Range::new(start(first), end(last))
+        };
+        edits.push(TextEdit::replace(SrcPos::new(source.clone(), range), ""));
+    }
+    edits
+}
+
+/// The name of the formal of a partial association such as `data(0)` or `rec.field`
+fn base_name(name: &WithTokenSpan<Name>) -> &WithTokenSpan<Name> {
+    match &name.item {
+        Name::Selected(prefix, _) | Name::Slice(prefix, _) => base_name(prefix),
+        Name::CallOrIndexed(call) => base_name(&call.name),
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+    use crate::refactor::apply_edits;
+
+    #[test]
+    fn updates_associations_to_changed_interface() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "\
+entity child is
+  generic (width : natural);
+  port (clk, rst : in bit; enable : in bit; valid : in bit; data : out bit);
+end entity;",
+        );
+        let code = builder.code(
+            "libname",
+            "\
+entity top is
+end entity;
+
+architecture rtl of top is
+  signal clk, rst, en, valid, data, unused : bit;
+  constant width : natural := 8;
+begin
+  inst: entity work.child
+    port map (
+      clk => clk,
+      rst => rst,
+      en => en,
+      data => data,
+      legacy => unused
+    );
+
+  positional: entity work.child
+    generic map (8)
+    port map (clk, rst, en, valid);
+end architecture;",
+        );
+        let (root, _) = builder.get_analyzed_root();
+
+        let edits = update_instantiations(&root, "libname.child").unwrap();
+        assert_eq!(
+            apply_edits(code.source(), &edits),
+            "\
+entity top is
+end entity;
+
+architecture rtl of top is
+  signal clk, rst, en, valid, data, unused : bit;
+  constant width : natural := 8;
+begin
+  inst: entity work.child
+    generic map (
+      width => width
+    )
+    port map (
+      clk => clk,
+      rst => rst,
+      enable => en,
+      valid => valid,
+      data => data
+    );
+
+  positional: entity work.child
+    generic map (8)
+    port map (clk, rst, en, valid);
+end architecture;"
+        );
+        assert_eq!(update_instantiations(&root, "libname.top"), Ok(Vec::new()));
+    }
+}
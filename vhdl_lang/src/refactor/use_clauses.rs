@@ -0,0 +1,632 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Merge, collapse and expand the use clauses of a context clause
+//! and remove the clauses that are not used
+
+use super::unused::allows_unused;
+use super::{source_text, whole_lines, CodeAction, TextEdit};
+use crate::analysis::{DesignRoot, LockedUnit};
+use crate::ast::search::{NotFinished, Search, SearchState, Searcher};
+use crate::ast::{AnyDesignUnit, ContextClause, ContextItem, Designator, HasUnitId, Name};
+use crate::data::{Position, Source, SrcPos};
+use crate::named_entity::{AnyEntKind, EntRef, EntityId, Reference, Related};
+use crate::syntax::TokenAccess;
+use crate::HasTokenSpan;
+
+pub fn list_code_actions(root: &DesignRoot, source: &Source, cursor: Position) -> Vec<CodeAction> {
+    let mut actions = Vec::new();
+
+    for unit in root.units_by_source(source) {
+        let Some(data) = unit.unit.get() else {
+            continue;
+        };
+        let tokens = &unit.tokens;
+        let context_clause = data.data().context_clause();
+
+        let Some(current) = context_clause
+            .iter()
+            .find(|item| item.get_pos(tokens).contains(cursor))
+            .and_then(|item| package_use(root, tokens, item))
+        else {
+            continue;
+        };
+        let same_package: Vec<PackageUse> = context_clause
+            .iter()
+            .filter_map(|item| package_use(root, tokens, item))
+            .filter(|package_use| package_use.package == current.package)
+            .collect();
+
+        let mut unit_actions = vec![
+            merge_use_clauses(&same_package),
+            collapse_use_clauses(&same_package),
+        ];
+        if current.is_all() {
+            let context_pos = context_clause_pos(tokens, context_clause);
+            let used = used_declarations(
+                root,
+                unit,
+                data.data(),
+                current.package,
+                context_pos.as_ref(),
+            );
+            unit_actions.push(expand_use_all(&current, &used));
+        }
+
+        // A source file mapped to several libraries contains the same units once per library
+        for action in unit_actions.into_iter().flatten() {
+            if !actions.contains(&action) {
+                actions.push(action);
+            }
+        }
+    }
+    actions
+}
+
+/// The use clauses of the context clauses within `source` that do not make
+/// any referenced declaration visible and are not marked as allowed to be unused
+pub(super) fn unused_use_clauses(root: &DesignRoot, source: &Source) -> Vec<SrcPos> {
+    let mut unused = Vec::new();
+
+    for unit in root.units_by_source(source) {
+        let Some(data) = unit.unit.get() else {
+            continue;
+        };
+        let tokens = &unit.tokens;
+        let context_clause = data.data().context_clause();
+        let context_pos = context_clause_pos(tokens, context_clause);
+
+        for item in context_clause.iter() {
+            let Some(package_use) = package_use(root, tokens, item) else {
+                continue;
+            };
+            let used = used_declarations(
+                root,
+                unit,
+                data.data(),
+                package_use.package,
+                context_pos.as_ref(),
+            );
+            let is_used = package_use.suffixes.iter().any(|suffix| match suffix {
+                None => !used.is_empty(),
+                Some(suffix) => used
+                    .iter()
+                    .any(|ent| ent.designator().to_string().eq_ignore_ascii_case(suffix)),
+            });
+
+            // A source file mapped to several libraries contains the same units once per library
+            if !is_used && !allows_unused(tokens, item.span()) && !unused.contains(&package_use.pos)
+            {
+                unused.push(package_use.pos);
+            }
+        }
+    }
+    unused
+}
+
+/// Remove the use clauses that do not make any referenced declaration visible and the
+/// library clauses of libraries that neither the remaining use clauses nor the unit refer to
+pub fn remove_unused_context_items(root: &DesignRoot, source: &Source) -> Vec<TextEdit> {
+    let unused_uses = unused_use_clauses(root, source);
+    let mut removed = unused_uses.clone();
+
+    for unit in root.units_by_source(source) {
+        let Some(data) = unit.unit.get() else {
+            continue;
+        };
+        let tokens = &unit.tokens;
+        for item in data.data().context_clause().iter() {
+            let ContextItem::Library(clause) = item else {
+                continue;
+            };
+            let pos = item.get_pos(tokens);
+            if removed.contains(&pos) || allows_unused(tokens, item.span()) {
+                continue;
+            }
+            let is_used = clause.name_list.items.iter().any(|name| {
+                let Some(library) = name.reference.get() else {
+                    // Keep the clauses of unknown libraries
+                    return true;
+                };
+                let mut searcher = References {
+                    target: library,
+                    positions: Vec::new(),
+                };
+                search_with_secondary_units(root, unit, data.data(), &mut searcher);
+                searcher.positions.iter().any(|reference| {
+                    !std::iter::once(&pos)
+                        .chain(unused_uses.iter())
+                        .any(|clause| {
+                            clause.source == reference.source && clause.contains(reference.start())
+                        })
+                })
+            });
+            if !is_used {
+                removed.push(pos);
+            }
+        }
+    }
+
+    let mut edits: Vec<TextEdit> = removed
+        .iter()
+        .map(|pos| TextEdit::replace(whole_lines(pos), ""))
+        .collect();
+    edits.sort_by_key(|edit| edit.pos.start());
+    edits
+}
+
+/// The references to a single named entity
+struct References {
+    target: EntityId,
+    positions: Vec<SrcPos>,
+}
+
+impl Searcher for References {
+    fn search_pos_with_ref(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        reference: &Reference,
+    ) -> SearchState {
+        if reference.get() == Some(self.target) {
+            self.positions.push(pos.clone());
+        }
+        NotFinished
+    }
+}
+
+/// True if the context clause makes all declarations of `package` visible
+pub(super) fn uses_all_of(
+    root: &DesignRoot,
+    tokens: &dyn TokenAccess,
+    context_clause: &ContextClause,
+    package: EntityId,
+) -> bool {
+    context_clause
+        .iter()
+        .filter_map(|item| package_use(root, tokens, item))
+        .any(|package_use| package_use.package == package && package_use.is_all())
+}
+
+fn context_clause_pos(tokens: &dyn TokenAccess, context_clause: &ContextClause) -> Option<SrcPos> {
+    let first = context_clause.first()?.get_pos(tokens);
+    let last = context_clause.last()?.get_pos(tokens);
+    Some(first.combine(&last))
+}
+
+/// A use clause where all names are declared by the same package,
+/// such as `use ieee.numeric_std.unsigned, ieee.numeric_std.signed;`
+struct PackageUse {
+    pos: SrcPos,
+    package: EntityId,
+    /// The name of the package, e.g. `ieee.numeric_std`
+    prefix: String,
+    /// The selected declarations, `None` for `.all`
+    suffixes: Vec<Option<String>>,
+}
+
+impl PackageUse {
+    fn is_all(&self) -> bool {
+        self.suffixes.iter().all(Option::is_none)
+    }
+
+    fn selected_names(&self) -> impl Iterator<Item = String> + '_ {
+        self.suffixes
+            .iter()
+            .flatten()
+            .map(|suffix| format!("{}.{suffix}", self.prefix))
+    }
+}
+
+fn package_use(
+    root: &DesignRoot,
+    tokens: &dyn TokenAccess,
+    item: &ContextItem,
+) -> Option<PackageUse> {
+    let ContextItem::Use(use_clause) = item else {
+        return None;
+    };
+
+    let mut package_use: Option<PackageUse> = None;
+    for name in use_clause.name_list.items.iter() {
+        let (prefix, suffix) = match name.item {
+            Name::SelectedAll(ref prefix) => (prefix, None),
+            Name::Selected(ref prefix, ref suffix) => (prefix, Some(suffix.item.item.to_string())),
+            _ => return None,
+        };
+        let package = prefix.item.get_suffix_reference()?;
+        if !matches!(root.get_ent(package).kind(), AnyEntKind::Design(_)) {
+            return None;
+        }
+
+        match package_use {
+            Some(ref mut package_use) if package_use.package == package => {
+                package_use.suffixes.push(suffix);
+            }
+            Some(_) => return None,
+            None => {
+                package_use = Some(PackageUse {
+                    pos: item.get_pos(tokens),
+                    package,
+                    prefix: source_text(&prefix.pos(tokens)),
+                    suffixes: vec![suffix],
+                })
+            }
+        }
+    }
+    package_use
+}
+
+/// Replace the first use clause with `text` and remove the others
+fn replace_use_clauses(package_uses: &[PackageUse], text: String) -> Vec<TextEdit> {
+    let mut edits = Vec::new();
+    if let Some((first, rest)) = package_uses.split_first() {
+        edits.push(TextEdit::replace(first.pos.clone(), text));
+        edits.extend(
+            rest.iter()
+                .map(|package_use| TextEdit::replace(whole_lines(&package_use.pos), "")),
+        );
+    }
+    edits
+}
+
+/// Merge use clauses of individual declarations of the same package into a single use clause
+fn merge_use_clauses(package_uses: &[PackageUse]) -> Option<CodeAction> {
+    if package_uses.len() < 2 || package_uses.iter().any(PackageUse::is_all) {
+        return None;
+    }
+    let mut names: Vec<String> = Vec::new();
+    for name in package_uses.iter().flat_map(PackageUse::selected_names) {
+        if !names.iter().any(|other| other.eq_ignore_ascii_case(&name)) {
+            names.push(name);
+        }
+    }
+
+    Some(CodeAction {
+        title: format!("Merge use clauses of '{}'", package_uses[0].prefix),
+        edits: replace_use_clauses(package_uses, format!("use {};", names.join(", "))),
+    })
+}
+
+/// Replace the use clauses of individual declarations by a single `.all` use clause
+fn collapse_use_clauses(package_uses: &[PackageUse]) -> Option<CodeAction> {
+    if package_uses.iter().all(PackageUse::is_all) {
+        return None;
+    }
+    let prefix = &package_uses.first()?.prefix;
+
+    Some(CodeAction {
+        title: format!("Use all declarations of '{prefix}'"),
+        edits: replace_use_clauses(package_uses, format!("use {prefix}.all;")),
+    })
+}
+
+/// Replace a `.all` use clause by the declarations that are actually referenced
+fn expand_use_all(package_use: &PackageUse, used: &[EntRef]) -> Option<CodeAction> {
+    if used.is_empty() {
+        return None;
+    }
+    let prefix = &package_use.prefix;
+    // Overloaded subprograms are all made visible by a single name
+    let mut designators: Vec<&Designator> = Vec::new();
+    for ent in used {
+        if !designators.contains(&ent.designator()) {
+            designators.push(ent.designator());
+        }
+    }
+    let names: Vec<String> = designators
+        .iter()
+        .map(|designator| format!("{prefix}.{designator}"))
+        .collect();
+
+    Some(CodeAction {
+        title: format!("Use only the referenced declarations of '{prefix}'"),
+        edits: vec![TextEdit::replace(
+            package_use.pos.clone(),
+            format!("use {};", names.join(", ")),
+        )],
+    })
+}
+
+/// The declarations of `package` that are referenced within `unit`.
+/// The declarations of a primary unit are also visible within its secondary units
+/// so these are searched as well.
+fn used_declarations<'a>(
+    root: &'a DesignRoot,
+    unit: &LockedUnit,
+    design_unit: &AnyDesignUnit,
+    package: EntityId,
+    context_pos: Option<&SrcPos>,
+) -> Vec<EntRef<'a>> {
+    let mut searcher = UsedDeclarations {
+        root,
+        package,
+        context_pos,
+        used: Vec::new(),
+    };
+    search_with_secondary_units(root, unit, design_unit, &mut searcher);
+    searcher.used
+}
+
+/// Search `design_unit` and, for a primary unit, its secondary units
+fn search_with_secondary_units(
+    root: &DesignRoot,
+    unit: &LockedUnit,
+    design_unit: &AnyDesignUnit,
+    searcher: &mut impl Searcher,
+) {
+    let _ = design_unit.search(&unit.tokens, searcher);
+
+    let unit_id = unit.unit_id();
+    if unit_id.secondary_name().is_none() {
+        if let Some(library) = root.get_lib(unit_id.library_name()) {
+            for secondary in library.secondary_units(unit_id.primary_name()) {
+                if let Some(data) = secondary.unit.get() {
+                    let _ = data.data().search(&secondary.tokens, searcher);
+                }
+            }
+        }
+    }
+}
+
+struct UsedDeclarations<'a, 'b> {
+    root: &'a DesignRoot,
+    package: EntityId,
+    /// References within the context clause itself do not count as a use
+    context_pos: Option<&'b SrcPos>,
+    used: Vec<EntRef<'a>>,
+}
+
+impl<'a, 'b> UsedDeclarations<'a, 'b> {
+    /// The declaration of the package that makes `ent` visible
+    fn package_declaration(&self, ent: EntRef<'a>) -> Option<EntRef<'a>> {
+        let mut ent = ent.declaration();
+        loop {
+            if let Related::ImplicitOf(owner) = ent.related {
+                ent = owner;
+                continue;
+            }
+            let parent = ent.parent?;
+            if parent.id() == self.package {
+                return Some(ent);
+            }
+            ent = parent;
+        }
+    }
+}
+
+impl<'a, 'b> Searcher for UsedDeclarations<'a, 'b> {
+    fn search_pos_with_ref(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        reference: &Reference,
+    ) -> SearchState {
+        let in_context_clause = self.context_pos.is_some_and(|context_pos| {
+            context_pos.source == pos.source && context_pos.contains(pos.start())
+        });
+        if let Some(id) = reference.get() {
+            if !in_context_clause {
+                if let Some(ent) = self.package_declaration(self.root.get_ent(id)) {
+                    if !self.used.iter().any(|used| used.id() == ent.id()) {
+                        self.used.push(ent);
+                    }
+                }
+            }
+        }
+        NotFinished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::syntax::test::Code;
+
+    fn actions_at(builder: LibraryBuilder, code: &Code, cursor: Position) -> Vec<CodeAction> {
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+        list_code_actions(&root, code.source(), cursor)
+    }
+
+    fn add_pkg(builder: &mut LibraryBuilder) {
+        builder.code(
+            "libname",
+            "
+package pkg is
+  type state_t is (idle, busy);
+  constant c1 : natural := 1;
+  constant c2 : natural := 2;
+  constant c3 : natural := 3;
+end package;",
+        );
+    }
+
+    #[test]
+    fn merges_and_collapses_use_clauses_of_same_package() {
+        let mut builder = LibraryBuilder::new();
+        add_pkg(&mut builder);
+        let code = builder.code(
+            "libname",
+            "
+use work.pkg.c1;
+use work.pkg.c2;
+
+entity ent is
+end entity;",
+        );
+        let actions = actions_at(builder, &code, code.s1("work.pkg.c1").start());
+        let removal = whole_lines(&code.s1("use work.pkg.c2;").pos());
+        assert_eq!(
+            actions,
+            vec![
+                CodeAction {
+                    title: "Merge use clauses of 'work.pkg'".to_owned(),
+                    edits: vec![
+                        TextEdit::replace(
+                            code.s1("use work.pkg.c1;").pos(),
+                            "use work.pkg.c1, work.pkg.c2;"
+                        ),
+                        TextEdit::replace(removal.clone(), ""),
+                    ],
+                },
+                CodeAction {
+                    title: "Use all declarations of 'work.pkg'".to_owned(),
+                    edits: vec![
+                        TextEdit::replace(code.s1("use work.pkg.c1;").pos(), "use work.pkg.all;"),
+                        TextEdit::replace(removal, ""),
+                    ],
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn expands_use_all_into_referenced_declarations() {
+        let mut builder = LibraryBuilder::new();
+        add_pkg(&mut builder);
+        let code = builder.code(
+            "libname",
+            "
+use work.pkg.all;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal state : state_t := idle;
+  constant c : natural := c2 + c1;
+begin
+end architecture;",
+        );
+        let actions = actions_at(builder, &code, code.s1("work.pkg.all").start());
+        assert_eq!(
+            actions,
+            vec![CodeAction {
+                title: "Use only the referenced declarations of 'work.pkg'".to_owned(),
+                edits: vec![TextEdit::replace(
+                    code.s1("use work.pkg.all;").pos(),
+                    "use work.pkg.state_t, work.pkg.c2, work.pkg.c1;"
+                )],
+            }]
+        );
+    }
+
+    #[test]
+    fn expands_use_all_into_one_name_per_overloaded_subprogram() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+package pkg is
+  function f(arg : natural) return natural;
+  function f(arg : boolean) return natural;
+end package;
+
+package body pkg is
+  function f(arg : natural) return natural is
+  begin
+    return arg;
+  end function;
+
+  function f(arg : boolean) return natural is
+  begin
+    return 0;
+  end function;
+end package body;",
+        );
+        let code = builder.code(
+            "libname",
+            "
+use work.pkg.all;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  constant c : natural := f(1) + f(true);
+begin
+end architecture;",
+        );
+        let actions = actions_at(builder, &code, code.s1("work.pkg.all").start());
+        assert_eq!(
+            actions,
+            vec![CodeAction {
+                title: "Use only the referenced declarations of 'work.pkg'".to_owned(),
+                edits: vec![TextEdit::replace(
+                    code.s1("use work.pkg.all;").pos(),
+                    "use work.pkg.f;"
+                )],
+            }]
+        );
+    }
+
+    #[test]
+    fn no_action_for_use_clauses_of_libraries() {
+        let mut builder = LibraryBuilder::new();
+        add_pkg(&mut builder);
+        let code = builder.code(
+            "otherlib",
+            "
+library libname;
+use libname.all;
+
+entity ent is
+end entity;",
+        );
+        let actions = actions_at(builder, &code, code.s1("libname.all").start());
+        assert_eq!(actions, vec![]);
+    }
+
+    #[test]
+    fn removes_unused_use_and_library_clauses() {
+        let mut builder = LibraryBuilder::new();
+        add_pkg(&mut builder);
+        builder.code(
+            "otherlib",
+            "
+package other_pkg is
+  constant c4 : natural := 4;
+end package;",
+        );
+        builder.code(
+            "thirdlib",
+            "
+package third_pkg is
+end package;",
+        );
+        let code = builder.code(
+            "libname",
+            "
+library otherlib;
+library thirdlib;
+use otherlib.other_pkg.all;
+use work.pkg.c1;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal s : bit_vector(c1 downto 0);
+begin
+  s <= (others => '0');
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let removed = |substr: &str| TextEdit::replace(whole_lines(&code.s1(substr).pos()), "");
+        assert_eq!(
+            remove_unused_context_items(&root, code.source()),
+            vec![
+                removed("library otherlib;"),
+                removed("library thirdlib;"),
+                removed("use otherlib.other_pkg.all;"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,446 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! The kind of usage of each reference, e.g. to find the statements that drive a signal
+
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::ast::token_range::WithTokenSpan;
+use crate::ast::{
+    ActualPart, AssociationElement, CallOrIndexed, ConcurrentStatement, Expression,
+    InstantiatedUnit, InstantiationStatement, Mode, Name, SequentialStatement, Target,
+};
+use crate::data::{Source, SrcPos};
+use crate::named_entity::{
+    AnyEntKind, Design, EntRef, EntityId, HasEntityId, InterfaceMode, OverloadedEnt,
+};
+use crate::syntax::TokenAccess;
+use fnv::FnvHashSet;
+
+/// How a reference uses the declaration it refers to
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ReferenceKind {
+    /// The declaration itself, including the end label and the full declaration
+    /// of a deferred constant or a subprogram body
+    Declaration,
+    /// The target of a signal or variable assignment, or the actual of
+    /// a port or parameter of mode `out`, `inout` or `buffer`
+    Write,
+    /// A formal or an actual of a port map that is not driven by the port
+    PortAssociation,
+    /// The entity, component or configuration of an instantiation
+    Instantiation,
+    /// Any other reference, including the actuals of subprogram calls
+    Read,
+}
+
+impl ReferenceKind {
+    pub const ALL: [ReferenceKind; 5] = [
+        ReferenceKind::Declaration,
+        ReferenceKind::Write,
+        ReferenceKind::PortAssociation,
+        ReferenceKind::Instantiation,
+        ReferenceKind::Read,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReferenceKind::Declaration => "declaration",
+            ReferenceKind::Write => "write",
+            ReferenceKind::PortAssociation => "port_association",
+            ReferenceKind::Instantiation => "instantiation",
+            ReferenceKind::Read => "read",
+        }
+    }
+}
+
+impl TryFrom<&str> for ReferenceKind {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        ReferenceKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == value)
+            .ok_or_else(|| format!("'{value}' is not a valid reference kind"))
+    }
+}
+
+/// Classify the references by the statements and declarations of their source files
+pub(crate) fn classify_references(
+    root: &DesignRoot,
+    references: Vec<SrcPos>,
+) -> Vec<(SrcPos, ReferenceKind)> {
+    let sources: FnvHashSet<Source> = references.iter().map(|pos| pos.source.clone()).collect();
+    let mut usages = Usages {
+        root,
+        declarations: FnvHashSet::default(),
+        writes: Vec::new(),
+        port_associations: Vec::new(),
+        instantiations: Vec::new(),
+    };
+    for source in sources.iter() {
+        let _ = root.search_source(source, &mut usages);
+    }
+
+    references
+        .into_iter()
+        .map(|pos| {
+            let encloses_pos = |outer: &SrcPos| encloses(outer, &pos);
+            let kind = if usages.declarations.contains(&pos) {
+                ReferenceKind::Declaration
+            } else if usages.instantiations.iter().any(encloses_pos) {
+                ReferenceKind::Instantiation
+            } else if usages.writes.iter().any(encloses_pos) {
+                ReferenceKind::Write
+            } else if usages.port_associations.iter().any(encloses_pos) {
+                ReferenceKind::PortAssociation
+            } else {
+                ReferenceKind::Read
+            };
+            (pos, kind)
+        })
+        .collect()
+}
+
+fn encloses(outer: &SrcPos, inner: &SrcPos) -> bool {
+    outer.source == inner.source && outer.start() <= inner.start() && inner.end() <= outer.end()
+}
+
+struct Usages<'a> {
+    root: &'a DesignRoot,
+    /// The identifiers and end labels of all declarations
+    declarations: FnvHashSet<SrcPos>,
+    /// The names that are assigned, without the indexes and ranges of the target
+    writes: Vec<SrcPos>,
+    port_associations: Vec<SrcPos>,
+    /// The names of the instantiated units
+    instantiations: Vec<SrcPos>,
+}
+
+impl<'a> Usages<'a> {
+    fn add_target(&mut self, ctx: &dyn TokenAccess, target: &WithTokenSpan<Target>) {
+        match target.item {
+            Target::Name(ref name) => self.add_assigned_name(ctx, name, target.span.pos(ctx)),
+            Target::Aggregate(_) => self.writes.push(target.span.pos(ctx)),
+        }
+    }
+
+    /// The prefixes of an indexed name or slice are assigned, but not the indexes
+    fn add_assigned_name(&mut self, ctx: &dyn TokenAccess, name: &Name, pos: SrcPos) {
+        match name {
+            Name::Selected(prefix, suffix) => {
+                self.writes.push(suffix.pos(ctx).clone());
+                self.add_assigned_name(ctx, &prefix.item, prefix.span.pos(ctx));
+            }
+            Name::Slice(prefix, _) => {
+                self.add_assigned_name(ctx, &prefix.item, prefix.span.pos(ctx))
+            }
+            Name::CallOrIndexed(call) => {
+                self.add_assigned_name(ctx, &call.name.item, call.name.span.pos(ctx))
+            }
+            _ => self.writes.push(pos),
+        }
+    }
+
+    /// The actuals that are associated with formals of mode `out`, `inout` or `buffer`
+    /// are driven by the instance or the procedure.
+    /// Positional associations take the formal at the same index.
+    fn add_driven_actuals(
+        &mut self,
+        ctx: &dyn TokenAccess,
+        elements: &[AssociationElement],
+        formal_at: impl Fn(usize) -> Option<EntRef<'a>>,
+    ) {
+        for (idx, element) in elements.iter().enumerate() {
+            let formal = match element.formal {
+                Some(ref formal) => formal_reference(&formal.item).map(|id| self.root.get_ent(id)),
+                None => formal_at(idx),
+            };
+            if !formal.is_some_and(is_driving_formal) {
+                continue;
+            }
+            if let ActualPart::Expression(Expression::Name(ref name)) = element.actual.item {
+                self.add_assigned_name(ctx, name, element.actual.span.pos(ctx));
+            }
+        }
+    }
+
+    fn add_instance(&mut self, ctx: &dyn TokenAccess, instance: &InstantiationStatement) {
+        let name = match instance.unit {
+            InstantiatedUnit::Component(ref name)
+            | InstantiatedUnit::Entity(ref name, _)
+            | InstantiatedUnit::Configuration(ref name) => name,
+        };
+        self.instantiations.push(name.span.pos(ctx));
+
+        let Some(ref port_map) = instance.port_map else {
+            return;
+        };
+        for association in port_map.list.items.iter() {
+            if let Some(ref formal) = association.formal {
+                self.port_associations.push(formal.span.pos(ctx));
+            }
+            self.port_associations
+                .push(association.actual.span.pos(ctx));
+        }
+
+        let ports = instance
+            .entity_reference()
+            .and_then(|id| match self.root.get_ent(id).kind() {
+                AnyEntKind::Design(Design::Entity(_, region)) | AnyEntKind::Component(region) => {
+                    Some(region.ports_and_generics().0)
+                }
+                _ => None,
+            })
+            .unwrap_or_default();
+        self.add_driven_actuals(ctx, &port_map.list.items, |idx| {
+            ports.get(idx).map(|port| port.inner())
+        });
+    }
+
+    fn add_procedure_call(&mut self, ctx: &dyn TokenAccess, call: &CallOrIndexed) {
+        let Some(procedure) = call
+            .name
+            .item
+            .get_suffix_reference()
+            .and_then(|id| OverloadedEnt::from_any(self.root.get_ent(id)))
+        else {
+            return;
+        };
+        self.add_driven_actuals(ctx, &call.parameters, |idx| {
+            procedure.formals().nth(idx).map(|formal| formal.inner())
+        });
+    }
+}
+
+/// The port or parameter of a formal part such as `q` or `q(0)`
+fn formal_reference(name: &Name) -> Option<EntityId> {
+    match name {
+        Name::CallOrIndexed(call) => formal_reference(&call.name.item),
+        Name::Slice(prefix, _) => formal_reference(&prefix.item),
+        _ => name.get_suffix_reference(),
+    }
+}
+
+fn is_driving_formal(ent: EntRef) -> bool {
+    matches!(
+        ent.kind(),
+        AnyEntKind::Object(object) if matches!(
+            object.mode(),
+            Some(InterfaceMode::Simple(Mode::Out | Mode::InOut | Mode::Buffer))
+        )
+    )
+}
+
+impl<'a> Searcher for Usages<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        if let Some(id) = decl.ent_id() {
+            if let Some(decl_pos) = self.root.get_ent(id).decl_pos() {
+                self.declarations.insert(decl_pos.clone());
+            }
+            if let Some(pos) = decl.end_ident_pos() {
+                self.declarations.insert(ctx.get_pos(pos).clone());
+            }
+        }
+
+        match decl {
+            FoundDeclaration::ConcurrentStatement(stmt) => match stmt.statement.item {
+                ConcurrentStatement::Assignment(ref assign) => self.add_target(ctx, &assign.target),
+                ConcurrentStatement::Instance(ref instance) => self.add_instance(ctx, instance),
+                ConcurrentStatement::ProcedureCall(ref pcall) => {
+                    self.add_procedure_call(ctx, &pcall.call.item)
+                }
+                _ => {}
+            },
+            FoundDeclaration::SequentialStatement(stmt) => match stmt.statement.item {
+                SequentialStatement::VariableAssignment(ref assign) => {
+                    self.add_target(ctx, &assign.target)
+                }
+                SequentialStatement::SignalAssignment(ref assign) => {
+                    self.add_target(ctx, &assign.target)
+                }
+                SequentialStatement::SignalForceAssignment(ref assign) => {
+                    self.add_target(ctx, &assign.target)
+                }
+                SequentialStatement::SignalReleaseAssignment(ref assign) => {
+                    self.add_target(ctx, &assign.target)
+                }
+                SequentialStatement::ProcedureCall(ref pcall) => {
+                    self.add_procedure_call(ctx, &pcall.item)
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        NotFinished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+
+    #[test]
+    fn classifies_references_of_signal() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity child is
+  port (
+    d : in bit_vector(0 to 1);
+    q : out bit
+  );
+end entity;
+
+architecture a of child is
+begin
+  q <= d(0);
+end architecture;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal data : bit_vector(0 to 1);
+  signal idx : natural;
+  signal res : bit;
+begin
+  data(idx) <= '1';
+  res <= data(1);
+
+  inst: entity work.child
+    port map (
+      d => data,
+      q => res
+    );
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let data = root
+            .search_reference(code.source(), code.s1("data").start())
+            .unwrap();
+        let references = classify_references(&root, root.find_all_references(data));
+        assert_eq!(
+            references,
+            vec![
+                (code.s("data", 1).pos(), ReferenceKind::Declaration),
+                (code.s("data", 2).pos(), ReferenceKind::Write),
+                (code.s("data", 3).pos(), ReferenceKind::Read),
+                (code.s("data", 4).pos(), ReferenceKind::PortAssociation),
+            ]
+        );
+
+        let res = root
+            .search_reference(code.source(), code.s1("res").start())
+            .unwrap();
+        let references = classify_references(&root, root.find_all_references(res));
+        assert_eq!(
+            references,
+            vec![
+                (code.s("res", 1).pos(), ReferenceKind::Declaration),
+                (code.s("res", 2).pos(), ReferenceKind::Write),
+                (code.s("res", 3).pos(), ReferenceKind::Write),
+            ]
+        );
+
+        let idx = root
+            .search_reference(code.source(), code.s1("idx").start())
+            .unwrap();
+        let references = classify_references(&root, root.find_all_references(idx));
+        assert_eq!(
+            references,
+            vec![
+                (code.s("idx", 1).pos(), ReferenceKind::Declaration),
+                (code.s("idx", 2).pos(), ReferenceKind::Read),
+            ]
+        );
+
+        let child = root
+            .search_reference(code.source(), code.s1("child").start())
+            .unwrap();
+        let kinds: Vec<_> = classify_references(&root, root.find_all_references(child))
+            .into_iter()
+            .map(|(_, kind)| kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ReferenceKind::Declaration,
+                ReferenceKind::Read,
+                ReferenceKind::Instantiation
+            ]
+        );
+    }
+
+    #[test]
+    fn classifies_actuals_of_out_parameters_as_writes() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture a of ent is
+  procedure update(value : in natural; variable target : out natural) is
+  begin
+    target := value;
+  end procedure;
+begin
+  process
+    variable src, dst : natural;
+  begin
+    update(src, dst);
+    update(target => dst, value => src);
+    wait;
+  end process;
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let kinds_of = |name: &str| -> Vec<ReferenceKind> {
+            let ent = root
+                .search_reference(code.source(), code.s1(name).start())
+                .unwrap();
+            classify_references(&root, root.find_all_references(ent))
+                .into_iter()
+                .map(|(_, kind)| kind)
+                .collect()
+        };
+        assert_eq!(
+            kinds_of("dst"),
+            vec![
+                ReferenceKind::Declaration,
+                ReferenceKind::Write,
+                ReferenceKind::Write
+            ]
+        );
+        assert_eq!(
+            kinds_of("src"),
+            vec![
+                ReferenceKind::Declaration,
+                ReferenceKind::Read,
+                ReferenceKind::Read
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_reference_kind() {
+        for kind in ReferenceKind::ALL {
+            assert_eq!(ReferenceKind::try_from(kind.as_str()), Ok(kind));
+        }
+        assert_eq!(
+            ReferenceKind::try_from("driver"),
+            Err("'driver' is not a valid reference kind".to_owned())
+        );
+    }
+}
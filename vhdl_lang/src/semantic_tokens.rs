@@ -0,0 +1,357 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! The classification of the identifiers of a source file, e.g. for semantic highlighting
+
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::ast::{ExternalObjectClass, Mode, ObjectClass};
+use crate::config::Deprecation;
+use crate::data::{Source, SrcPos};
+use crate::lint::deprecated::is_deprecated;
+use crate::named_entity::{
+    AnyEntKind, Design, EntRef, EntityId, HasEntityId, InterfaceMode, Object, ObjectInterface,
+    Overloaded, Reference,
+};
+use crate::syntax::TokenAccess;
+use fnv::FnvHashMap;
+
+/// What an identifier refers to
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SemanticTokenKind {
+    Library,
+    Entity,
+    Architecture,
+    Configuration,
+    /// A package, package body or package instance
+    Package,
+    Context,
+    Component,
+    Type,
+    Signal,
+    Variable,
+    /// A constant, deferred constant or loop parameter
+    Constant,
+    File,
+    /// A function or procedure
+    Subprogram,
+    EnumMember,
+    /// A unit of a physical type
+    Unit,
+    RecordElement,
+    Generic,
+    Port,
+    Parameter,
+    /// The label of a concurrent or sequential statement
+    Label,
+    Attribute,
+    View,
+}
+
+impl SemanticTokenKind {
+    pub const ALL: [SemanticTokenKind; 22] = [
+        SemanticTokenKind::Library,
+        SemanticTokenKind::Entity,
+        SemanticTokenKind::Architecture,
+        SemanticTokenKind::Configuration,
+        SemanticTokenKind::Package,
+        SemanticTokenKind::Context,
+        SemanticTokenKind::Component,
+        SemanticTokenKind::Type,
+        SemanticTokenKind::Signal,
+        SemanticTokenKind::Variable,
+        SemanticTokenKind::Constant,
+        SemanticTokenKind::File,
+        SemanticTokenKind::Subprogram,
+        SemanticTokenKind::EnumMember,
+        SemanticTokenKind::Unit,
+        SemanticTokenKind::RecordElement,
+        SemanticTokenKind::Generic,
+        SemanticTokenKind::Port,
+        SemanticTokenKind::Parameter,
+        SemanticTokenKind::Label,
+        SemanticTokenKind::Attribute,
+        SemanticTokenKind::View,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SemanticTokenKind::Library => "library",
+            SemanticTokenKind::Entity => "entity",
+            SemanticTokenKind::Architecture => "architecture",
+            SemanticTokenKind::Configuration => "configuration",
+            SemanticTokenKind::Package => "package",
+            SemanticTokenKind::Context => "context",
+            SemanticTokenKind::Component => "component",
+            SemanticTokenKind::Type => "type",
+            SemanticTokenKind::Signal => "signal",
+            SemanticTokenKind::Variable => "variable",
+            SemanticTokenKind::Constant => "constant",
+            SemanticTokenKind::File => "file",
+            SemanticTokenKind::Subprogram => "subprogram",
+            SemanticTokenKind::EnumMember => "enumMember",
+            SemanticTokenKind::Unit => "unit",
+            SemanticTokenKind::RecordElement => "recordElement",
+            SemanticTokenKind::Generic => "generic",
+            SemanticTokenKind::Port => "port",
+            SemanticTokenKind::Parameter => "parameter",
+            SemanticTokenKind::Label => "label",
+            SemanticTokenKind::Attribute => "attribute",
+            SemanticTokenKind::View => "view",
+        }
+    }
+
+    /// The kind of `ent` and whether it can only be read
+    fn of(ent: EntRef) -> (SemanticTokenKind, bool) {
+        let ent = ent.as_actual();
+        match ent.kind() {
+            AnyEntKind::ExternalAlias { class, .. } => match class {
+                ExternalObjectClass::Constant => (SemanticTokenKind::Constant, true),
+                ExternalObjectClass::Signal => (SemanticTokenKind::Signal, false),
+                ExternalObjectClass::Variable => (SemanticTokenKind::Variable, false),
+            },
+            AnyEntKind::ObjectAlias { .. } => unreachable!("Aliases are stripped"),
+            AnyEntKind::File(..) | AnyEntKind::InterfaceFile(..) => {
+                (SemanticTokenKind::File, false)
+            }
+            AnyEntKind::Component(..) => (SemanticTokenKind::Component, false),
+            AnyEntKind::Attribute(..) => (SemanticTokenKind::Attribute, false),
+            AnyEntKind::Overloaded(Overloaded::EnumLiteral(..)) => {
+                (SemanticTokenKind::EnumMember, true)
+            }
+            AnyEntKind::Overloaded(..) => (SemanticTokenKind::Subprogram, false),
+            AnyEntKind::Type(..) => (SemanticTokenKind::Type, false),
+            AnyEntKind::ElementDeclaration(..) => (SemanticTokenKind::RecordElement, false),
+            AnyEntKind::Concurrent(..) | AnyEntKind::Sequential(..) => {
+                (SemanticTokenKind::Label, false)
+            }
+            AnyEntKind::Object(object) => object_kind(object),
+            AnyEntKind::LoopParameter(..) | AnyEntKind::DeferredConstant(..) => {
+                (SemanticTokenKind::Constant, true)
+            }
+            AnyEntKind::PhysicalLiteral(..) => (SemanticTokenKind::Unit, true),
+            AnyEntKind::Library => (SemanticTokenKind::Library, false),
+            AnyEntKind::Design(design) => match design {
+                Design::Entity(..) => (SemanticTokenKind::Entity, false),
+                Design::Architecture(..) => (SemanticTokenKind::Architecture, false),
+                Design::Configuration => (SemanticTokenKind::Configuration, false),
+                Design::Package(..)
+                | Design::PackageBody
+                | Design::UninstPackage(..)
+                | Design::PackageInstance(..)
+                | Design::InterfacePackageInstance(..) => (SemanticTokenKind::Package, false),
+                Design::Context(..) => (SemanticTokenKind::Context, false),
+            },
+            AnyEntKind::View(..) => (SemanticTokenKind::View, false),
+        }
+    }
+}
+
+fn object_kind(object: &Object) -> (SemanticTokenKind, bool) {
+    let is_input = |mode: &InterfaceMode| matches!(mode, InterfaceMode::Simple(Mode::In));
+    match object.iface {
+        Some(ObjectInterface::Generic) => (SemanticTokenKind::Generic, true),
+        Some(ObjectInterface::Port(ref mode)) => (SemanticTokenKind::Port, is_input(mode)),
+        Some(ObjectInterface::Parameter(ref mode)) => {
+            (SemanticTokenKind::Parameter, is_input(mode))
+        }
+        None => match object.class {
+            ObjectClass::Signal => (SemanticTokenKind::Signal, false),
+            ObjectClass::Constant => (SemanticTokenKind::Constant, true),
+            ObjectClass::Variable | ObjectClass::SharedVariable => {
+                (SemanticTokenKind::Variable, false)
+            }
+        },
+    }
+}
+
+/// An identifier of a source file together with what it refers to
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SemanticToken {
+    pub pos: SrcPos,
+    pub kind: SemanticTokenKind,
+    /// The identifier or `end` label of the declaration itself
+    pub is_declaration: bool,
+    /// A constant, generic or input that cannot be assigned
+    pub is_readonly: bool,
+    /// The declaration is marked as deprecated
+    pub is_deprecated: bool,
+}
+
+/// The identifiers of `source` that refer to a declaration, ordered by their position
+pub(crate) fn semantic_tokens(
+    root: &DesignRoot,
+    deprecation: &Deprecation,
+    source: &Source,
+) -> Vec<SemanticToken> {
+    let mut searcher = SemanticTokens {
+        root,
+        deprecation,
+        source,
+        deprecated: FnvHashMap::default(),
+        tokens: Vec::new(),
+    };
+    let _ = root.search_source(source, &mut searcher);
+
+    let mut tokens = searcher.tokens;
+    tokens.sort_by_key(|token| (token.pos.start(), !token.is_declaration));
+    // The same position is found through every library that a file is mapped to
+    tokens.dedup_by(|token, previous| token.pos.start() == previous.pos.start());
+    tokens
+}
+
+struct SemanticTokens<'a> {
+    root: &'a DesignRoot,
+    deprecation: &'a Deprecation,
+    source: &'a Source,
+    deprecated: FnvHashMap<EntityId, bool>,
+    tokens: Vec<SemanticToken>,
+}
+
+impl<'a> SemanticTokens<'a> {
+    fn add(&mut self, pos: &SrcPos, ent: EntRef, is_declaration: bool) {
+        if pos.source != *self.source {
+            return;
+        }
+        let (kind, is_readonly) = SemanticTokenKind::of(ent);
+        let is_deprecated = *self
+            .deprecated
+            .entry(ent.id())
+            .or_insert_with(|| is_deprecated(self.root, self.deprecation, ent));
+        self.tokens.push(SemanticToken {
+            pos: pos.clone(),
+            kind,
+            is_declaration,
+            is_readonly,
+            is_deprecated,
+        });
+    }
+}
+
+impl<'a> Searcher for SemanticTokens<'a> {
+    fn search_decl(&mut self, ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        if let Some(id) = decl.ent_id() {
+            let ent = self.root.get_ent(id);
+            if let Some(decl_pos) = ent.decl_pos() {
+                self.add(decl_pos, ent, true);
+            }
+            if let Some(pos) = decl.end_ident_pos() {
+                self.add(ctx.get_pos(pos), ent, true);
+            }
+        }
+        NotFinished
+    }
+
+    fn search_pos_with_ref(
+        &mut self,
+        _ctx: &dyn TokenAccess,
+        pos: &SrcPos,
+        reference: &Reference,
+    ) -> SearchState {
+        if let Some(id) = reference.get() {
+            self.add(pos, self.root.get_ent(id), false);
+        }
+        NotFinished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+    use crate::syntax::test::Code;
+
+    fn token_at<'a>(
+        tokens: &'a [SemanticToken],
+        code: &Code,
+        name: &str,
+        occurence: usize,
+    ) -> &'a SemanticToken {
+        let start = code.s(name, occurence).start();
+        tokens
+            .iter()
+            .find(|token| token.pos.start() == start)
+            .unwrap()
+    }
+
+    #[test]
+    fn classifies_identifiers() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity top is
+  generic (width : natural);
+  port (clk : in bit; q : out bit);
+end entity;
+
+architecture a of top is
+  signal s : bit;
+begin
+  main: process (clk)
+    variable v : bit;
+  begin
+    v := s;
+    q <= v;
+  end process;
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let tokens = semantic_tokens(&root, &Deprecation::default(), code.source());
+        let kind = |name, occurence| {
+            let token = token_at(&tokens, &code, name, occurence);
+            (token.kind, token.is_declaration, token.is_readonly)
+        };
+        assert_eq!(kind("top", 1), (SemanticTokenKind::Entity, true, false));
+        assert_eq!(kind("top", 2), (SemanticTokenKind::Entity, false, false));
+        assert_eq!(kind("width", 1), (SemanticTokenKind::Generic, true, true));
+        assert_eq!(kind("natural", 1), (SemanticTokenKind::Type, false, false));
+        assert_eq!(kind("clk", 1), (SemanticTokenKind::Port, true, true));
+        assert_eq!(kind("clk", 2), (SemanticTokenKind::Port, false, true));
+        assert_eq!(kind("q :", 1), (SemanticTokenKind::Port, true, false));
+        assert_eq!(
+            kind("a of", 1),
+            (SemanticTokenKind::Architecture, true, false)
+        );
+        assert_eq!(kind("s :", 1), (SemanticTokenKind::Signal, true, false));
+        assert_eq!(kind("main", 1), (SemanticTokenKind::Label, true, false));
+        assert_eq!(kind("v :", 1), (SemanticTokenKind::Variable, true, false));
+        assert_eq!(kind("v;", 1), (SemanticTokenKind::Variable, false, false));
+
+        let mut positions: Vec<_> = tokens.iter().map(|token| token.pos.start()).collect();
+        positions.dedup();
+        assert_eq!(positions.len(), tokens.len());
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn marks_deprecated_declarations() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+package pkg is
+  -- @deprecated Use new_value
+  constant old_value : natural := 0;
+  constant new_value : natural := old_value;
+end package;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let deprecated: Vec<_> = semantic_tokens(&root, &Deprecation::default(), code.source())
+            .into_iter()
+            .filter(|token| token.is_deprecated)
+            .map(|token| token.pos)
+            .collect();
+        assert_eq!(
+            deprecated,
+            vec![code.s("old_value", 1).pos(), code.s("old_value", 2).pos()]
+        );
+    }
+}
@@ -0,0 +1,406 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! The signatures of the subprogram call or instantiation at the cursor
+//!
+//! The code is typically incomplete while a call is written, so the enclosing
+//! parenthesis is found from the tokens before the cursor rather than from the AST.
+
+use crate::analysis::DesignRoot;
+use crate::ast::search::{FoundDeclaration, NotFinished, SearchState, Searcher};
+use crate::ast::Designator;
+use crate::completion::tokenize_input;
+use crate::data::{Position, Source, Symbol};
+use crate::named_entity::{AnyEntKind, Design, EntRef, HasEntityId, InterfaceEnt, OverloadedEnt};
+use crate::syntax::Kind::*;
+use crate::syntax::{Token, TokenAccess, Value};
+use fnv::FnvHashSet;
+use std::fmt::Write;
+
+/// A subprogram, or the generics or ports of an entity or component
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CallSignature {
+    /// E.g. `inc(value : natural) return natural` or `ent port map (clk : bit)`
+    pub label: String,
+    /// The byte offsets of each formal within the label
+    pub parameters: Vec<(usize, usize)>,
+    /// The formal of the association at the cursor, if the signature has one
+    pub active_parameter: Option<usize>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SignatureHelp {
+    /// The subprogram that is called is the first of its overloads
+    pub signatures: Vec<CallSignature>,
+    pub active_signature: usize,
+}
+
+/// The association at the cursor within the parenthesis of a call or map aspect
+struct Association<'t> {
+    /// The index of the left parenthesis
+    open: usize,
+    /// The number of preceding associations
+    position: usize,
+    /// The formal of a named association
+    formal: Option<&'t Symbol>,
+}
+
+pub(crate) fn signature_help(
+    root: &DesignRoot,
+    source: &Source,
+    cursor: Position,
+) -> Option<SignatureHelp> {
+    let tokens = tokenize_input(root.symbols(), source, cursor);
+    let association = association_at(&tokens)?;
+    let before = &tokens[..association.open];
+
+    let signatures: Vec<CallSignature> = match before {
+        [unit @ .., aspect @ Token {
+            kind: Generic | Port,
+            ..
+        }, Token { kind: Map, .. }] => {
+            let is_port = aspect.kind == Port;
+            let name = instantiated_unit(unit)?;
+            candidates(root, source, name, |ent| {
+                matches!(
+                    ent.kind(),
+                    AnyEntKind::Design(Design::Entity(..)) | AnyEntKind::Component(..)
+                )
+            })?
+            .into_iter()
+            .filter_map(|ent| map_aspect_signature(ent, is_port, &association))
+            .collect()
+        }
+        [.., name @ Token {
+            kind: Identifier, ..
+        }] => candidates(root, source, name, |ent| {
+            OverloadedEnt::from_any(ent).is_some_and(|ent| !ent.formals().is_empty())
+        })?
+        .into_iter()
+        .filter_map(|ent| subprogram_signature(OverloadedEnt::from_any(ent)?, &association))
+        .collect(),
+        _ => return None,
+    };
+
+    if signatures.is_empty() {
+        return None;
+    }
+    Some(SignatureHelp {
+        // Prefer the called subprogram unless the cursor is beyond its formals
+        active_signature: signatures
+            .iter()
+            .position(|signature| signature.active_parameter.is_some())
+            .unwrap_or(0),
+        signatures,
+    })
+}
+
+/// The innermost unclosed parenthesis before the cursor
+fn association_at(tokens: &[Token]) -> Option<Association> {
+    let mut depth = 0;
+    let mut position = 0;
+    // The first token of the association at the cursor
+    let mut current = None;
+    for (idx, token) in tokens.iter().enumerate().rev() {
+        match token.kind {
+            RightPar => depth += 1,
+            LeftPar if depth == 0 => {
+                let formal = match &tokens[current.unwrap_or(idx + 1)..] {
+                    [Token {
+                        value: Value::Identifier(formal),
+                        ..
+                    }, Token {
+                        kind: RightArrow, ..
+                    }, ..] => Some(formal),
+                    _ => None,
+                };
+                return Some(Association {
+                    open: idx,
+                    position,
+                    formal,
+                });
+            }
+            LeftPar => depth -= 1,
+            Comma if depth == 0 => {
+                current.get_or_insert(idx + 1);
+                position += 1;
+            }
+            // Associations never contain these tokens outside of parenthesis
+            SemiColon | Begin | Is | Then | Loop | Generate => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The name of the entity or component before the map aspects of an instantiation,
+/// skipping the architecture name and a preceding generic map
+fn instantiated_unit(tokens: &[Token]) -> Option<&Token> {
+    let mut depth = 0;
+    for token in tokens.iter().rev() {
+        match token.kind {
+            RightPar => depth += 1,
+            LeftPar => depth -= 1,
+            _ if depth > 0 => {}
+            Generic | Map => {}
+            Identifier => return Some(token),
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// The declarations that `name` may refer to.
+/// The declaration that the name is resolved to comes first, but while the call
+/// is written the name is typically not resolved and all matching declarations
+/// of the design are candidates.
+fn candidates<'a>(
+    root: &'a DesignRoot,
+    source: &Source,
+    name: &Token,
+    is_candidate: impl Fn(EntRef<'a>) -> bool,
+) -> Option<Vec<EntRef<'a>>> {
+    let Value::Identifier(symbol) = &name.value else {
+        return None;
+    };
+
+    let mut candidates = Vec::new();
+    if let Some(ent) = root.search_reference(source, name.pos.start()) {
+        let ent = ent.declaration();
+        if !is_candidate(ent) {
+            // E.g. an indexed name or a type conversion
+            return None;
+        }
+        candidates.push(ent);
+    }
+
+    let mut searcher = DeclarationSearcher {
+        root,
+        symbol,
+        found: Vec::new(),
+    };
+    let _ = root.search_source(source, &mut searcher);
+
+    let mut seen: FnvHashSet<_> = candidates.iter().map(|ent| ent.id()).collect();
+    candidates.extend(
+        searcher
+            .found
+            .into_iter()
+            .chain(root.public_symbols().filter(|ent| has_name(ent, symbol)))
+            .map(|ent| ent.declaration())
+            .filter(|ent| is_candidate(ent) && seen.insert(ent.id())),
+    );
+    Some(candidates)
+}
+
+fn has_name(ent: EntRef, symbol: &Symbol) -> bool {
+    matches!(ent.designator(), Designator::Identifier(name) if name == symbol)
+}
+
+/// Collects the declarations of a source file with a certain name
+struct DeclarationSearcher<'a, 's> {
+    root: &'a DesignRoot,
+    symbol: &'s Symbol,
+    found: Vec<EntRef<'a>>,
+}
+
+impl<'a, 's> Searcher for DeclarationSearcher<'a, 's> {
+    fn search_decl(&mut self, _ctx: &dyn TokenAccess, decl: FoundDeclaration) -> SearchState {
+        if let Some(id) = decl.ent_id() {
+            let ent = self.root.get_ent(id);
+            if has_name(ent, self.symbol) {
+                self.found.push(ent);
+            }
+        }
+        NotFinished
+    }
+}
+
+fn subprogram_signature(
+    subprogram: OverloadedEnt,
+    association: &Association,
+) -> Option<CallSignature> {
+    let mut signature = signature_of(
+        subprogram.designator().to_string(),
+        subprogram.formals().iter(),
+        association,
+    )?;
+    if let Some(return_type) = subprogram.return_type() {
+        write!(signature.label, " return {}", return_type.designator()).unwrap();
+    }
+    Some(signature)
+}
+
+fn map_aspect_signature(
+    ent: EntRef,
+    is_port: bool,
+    association: &Association,
+) -> Option<CallSignature> {
+    let region = match ent.kind() {
+        AnyEntKind::Design(Design::Entity(_, region)) | AnyEntKind::Component(region) => region,
+        _ => return None,
+    };
+    let (ports, generics) = region.ports_and_generics();
+    let (formals, aspect) = if is_port {
+        (ports, "port map")
+    } else {
+        (generics, "generic map")
+    };
+    signature_of(
+        format!("{} {aspect} ", ent.designator()),
+        formals.into_iter(),
+        association,
+    )
+}
+
+/// The signature `{prefix}(formal : type; ...)`.
+/// An empty formal list has no signature
+fn signature_of<'a>(
+    prefix: String,
+    formals: impl Iterator<Item = InterfaceEnt<'a>>,
+    association: &Association,
+) -> Option<CallSignature> {
+    let mut label = prefix;
+    let mut parameters = Vec::new();
+    let mut active_parameter = None;
+
+    label.push('(');
+    for (idx, formal) in formals.enumerate() {
+        if idx > 0 {
+            label.push_str("; ");
+        }
+        let start = label.len();
+        write!(
+            label,
+            "{} : {}",
+            formal.designator(),
+            formal.type_mark().designator()
+        )
+        .unwrap();
+        parameters.push((start, label.len()));
+
+        let is_active = match association.formal {
+            Some(name) => has_name(formal.inner(), name),
+            None => idx == association.position,
+        };
+        if is_active {
+            active_parameter = Some(idx);
+        }
+    }
+    label.push(')');
+
+    if parameters.is_empty() {
+        return None;
+    }
+    Some(CallSignature {
+        label,
+        parameters,
+        active_parameter,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+
+    fn labels(help: &SignatureHelp) -> Vec<&str> {
+        help.signatures
+            .iter()
+            .map(|signature| signature.label.as_str())
+            .collect()
+    }
+
+    fn active_formal(help: &SignatureHelp) -> Option<&str> {
+        let signature = &help.signatures[help.active_signature];
+        let (start, end) = signature.parameters[signature.active_parameter?];
+        Some(&signature.label[start..end])
+    }
+
+    #[test]
+    fn signatures_of_overloaded_subprograms() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "
+package pkg is
+  function add(a : natural) return natural;
+  function add(a : natural; b : boolean) return natural;
+  procedure add(signal target : out natural);
+end package;
+
+use work.pkg.all;
+
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal s : natural;
+begin
+  s <= add(1, true);
+  s <= add(b => false, a => 0);
+end architecture;",
+        );
+        let (root, _) = builder.get_analyzed_root();
+
+        let cursor = code.s1("1, true").start();
+        let help = signature_help(&root, code.source(), cursor).unwrap();
+        assert_eq!(
+            labels(&help),
+            vec![
+                "add(a : NATURAL; b : BOOLEAN) return NATURAL",
+                "add(a : NATURAL) return NATURAL",
+                "add(target : NATURAL)",
+            ]
+        );
+        assert_eq!(active_formal(&help), Some("a : NATURAL"));
+
+        let cursor = code.s1("true").start();
+        let help = signature_help(&root, code.source(), cursor).unwrap();
+        assert_eq!(active_formal(&help), Some("b : BOOLEAN"));
+
+        let cursor = code.s1("a => 0").end();
+        let help = signature_help(&root, code.source(), cursor).unwrap();
+        assert_eq!(active_formal(&help), Some("a : NATURAL"));
+
+        assert_eq!(
+            signature_help(&root, code.source(), code.s1("s <= add").start()),
+            None
+        );
+    }
+
+    #[test]
+    fn signatures_of_incomplete_instantiation() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "
+entity child is
+  generic (width : natural);
+  port (clk : bit; data : bit_vector(width - 1 downto 0));
+end entity;",
+        );
+        // The snippet is being written and cannot be analyzed
+        let code = builder.snippet(
+            "
+architecture a of ent is
+begin
+  inst: entity work.child generic map (8) port map (clk, ",
+        );
+        let (root, _) = builder.get_analyzed_root();
+
+        let help = signature_help(&root, code.source(), code.end()).unwrap();
+        assert_eq!(
+            labels(&help),
+            vec!["child port map (clk : BIT; data : BIT_VECTOR)"]
+        );
+        assert_eq!(active_formal(&help), Some("data : BIT_VECTOR"));
+
+        let help = signature_help(&root, code.source(), code.s1("(8").end()).unwrap();
+        assert_eq!(labels(&help), vec!["child generic map (width : NATURAL)"]);
+        assert_eq!(active_formal(&help), Some("width : NATURAL"));
+    }
+}
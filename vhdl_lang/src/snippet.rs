@@ -0,0 +1,466 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Completion snippets of the `snippets` table of the config,
+//! such as the boilerplate of a team, that are offered together with the other completions
+
+use crate::analysis::DesignRoot;
+use crate::ast::{
+    AnyDesignUnit, AnyPrimaryUnit, AnySecondaryUnit, ConcurrentStatement,
+    LabeledConcurrentStatement, ProcessStatement,
+};
+use crate::data::{Position, Source};
+use crate::hierarchy::nested_statements;
+use crate::syntax::{Kind, TokenAccess};
+use crate::HasTokenSpan;
+use std::sync::OnceLock;
+use toml::{Table, Value};
+
+/// A snippet of the `snippets` table of the config
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Snippet {
+    pub name: String,
+    /// The word that is completed to the snippet
+    pub prefix: String,
+    /// The text in the snippet syntax of LSP, i.e. with placeholders such as `${1:name}`
+    pub body: String,
+    pub description: Option<String>,
+    /// Where the snippet is offered
+    pub context: SnippetContext,
+}
+
+/// The part of the code where a snippet is offered
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum SnippetContext {
+    /// Everywhere
+    #[default]
+    Any,
+    /// Outside of design units, e.g. for a new entity
+    DesignFile,
+    /// The declarative part of an architecture
+    ArchitectureDeclarations,
+    /// The statement part of an architecture, including blocks and generate statements
+    Concurrent,
+    /// The statement part of a process
+    Sequential,
+    /// The declarative part of a package or a package body
+    PackageDeclarations,
+}
+
+impl TryFrom<&str> for SnippetContext {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        use SnippetContext::*;
+        Ok(match value {
+            "any" => Any,
+            "design_file" => DesignFile,
+            "architecture_declarations" => ArchitectureDeclarations,
+            "concurrent" => Concurrent,
+            "sequential" => Sequential,
+            "package_declarations" => PackageDeclarations,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl Snippet {
+    pub(crate) fn from_table(name: &str, table: &Table) -> Result<Snippet, String> {
+        let prefix = table
+            .get("prefix")
+            .and_then(Value::as_str)
+            .filter(|prefix| !prefix.is_empty())
+            .ok_or_else(|| format!("snippets.{name}.prefix must be a string"))?;
+
+        // The body is a string or an array of lines
+        let body = match table.get("body") {
+            Some(Value::String(body)) => body.clone(),
+            Some(Value::Array(lines)) => lines
+                .iter()
+                .map(Value::as_str)
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| {
+                    format!("snippets.{name}.body must be a string or an array of strings")
+                })?
+                .join("\n"),
+            _ => {
+                return Err(format!(
+                    "snippets.{name}.body must be a string or an array of strings"
+                ))
+            }
+        };
+
+        let description = match table.get("description") {
+            None => None,
+            Some(description) => Some(
+                description
+                    .as_str()
+                    .ok_or_else(|| format!("snippets.{name}.description must be a string"))?
+                    .to_owned(),
+            ),
+        };
+
+        let context = match table.get("context") {
+            None => SnippetContext::Any,
+            Some(context) => {
+                let context = context
+                    .as_str()
+                    .ok_or_else(|| format!("snippets.{name}.context must be a string"))?;
+                SnippetContext::try_from(context).map_err(|_| {
+                    format!("'{context}' is not a valid value of snippets.{name}.context")
+                })?
+            }
+        };
+
+        Ok(Snippet {
+            name: name.to_owned(),
+            prefix: prefix.to_owned(),
+            body,
+            description,
+            context,
+        })
+    }
+
+    /// Whether the snippet is offered in the context of the cursor
+    pub(crate) fn is_offered_in(&self, context: Option<SnippetContext>) -> bool {
+        self.context == SnippetContext::Any || Some(self.context) == context
+    }
+
+    /// The body without the placeholders and tab stops of the snippet syntax,
+    /// for clients that do not support snippets
+    pub fn plain_text(&self) -> String {
+        let mut result = String::with_capacity(self.body.len());
+        let mut chars = self.body.chars().peekable();
+        while let Some(chr) = chars.next() {
+            match chr {
+                '\\' => {
+                    if let Some(escaped) = chars.next() {
+                        result.push(escaped);
+                    }
+                }
+                '$' if chars.peek().is_some_and(char::is_ascii_digit) => {
+                    while chars.next_if(char::is_ascii_digit).is_some() {}
+                }
+                '$' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    // `${1}`, `${1:default}` and `${1|first,second|}`
+                    while chars.next_if(char::is_ascii_digit).is_some() {}
+                    match chars.next() {
+                        Some(':') => {
+                            for chr in chars.by_ref() {
+                                if chr == '}' {
+                                    break;
+                                }
+                                result.push(chr);
+                            }
+                        }
+                        Some('|') => {
+                            for chr in chars.by_ref() {
+                                if chr == ',' || chr == '|' {
+                                    break;
+                                }
+                                result.push(chr);
+                            }
+                            for chr in chars.by_ref() {
+                                if chr == '}' {
+                                    break;
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                chr => result.push(chr),
+            }
+        }
+        result
+    }
+}
+
+/// The built-in templates of common constructs, which are offered like the snippets
+/// of the config unless a snippet of the config has the same prefix
+pub(crate) fn templates() -> &'static [Snippet] {
+    static TEMPLATES: OnceLock<Vec<Snippet>> = OnceLock::new();
+    TEMPLATES.get_or_init(|| {
+        use SnippetContext::*;
+        let template = |prefix: &str, description: &str, context, body: &[&str]| Snippet {
+            name: prefix.to_owned(),
+            prefix: prefix.to_owned(),
+            body: body.join("\n"),
+            description: Some(description.to_owned()),
+            context,
+        };
+        let component = [
+            "component ${1:name} is",
+            "  port (",
+            "    $0",
+            "  );",
+            "end component;",
+        ];
+        vec![
+            template(
+                "entity",
+                "Entity declaration",
+                DesignFile,
+                &[
+                    "entity ${1:name} is",
+                    "  port (",
+                    "    $0",
+                    "  );",
+                    "end entity;",
+                ],
+            ),
+            template(
+                "architecture",
+                "Architecture body",
+                DesignFile,
+                &[
+                    "architecture ${1:rtl} of ${2:name} is",
+                    "begin",
+                    "  $0",
+                    "end architecture;",
+                ],
+            ),
+            template(
+                "component",
+                "Component declaration",
+                ArchitectureDeclarations,
+                &component,
+            ),
+            template(
+                "component",
+                "Component declaration",
+                PackageDeclarations,
+                &component,
+            ),
+            template(
+                "process",
+                "Process with a sensitivity list",
+                Concurrent,
+                &["process (${1:all})", "begin", "  $0", "end process;"],
+            ),
+            template(
+                "clocked",
+                "Process that is triggered by the rising edge of a clock",
+                Concurrent,
+                &[
+                    "process (${1:clk})",
+                    "begin",
+                    "  if rising_edge(${1:clk}) then",
+                    "    $0",
+                    "  end if;",
+                    "end process;",
+                ],
+            ),
+            template(
+                "generate",
+                "For generate statement",
+                Concurrent,
+                &[
+                    "${1:gen}: for ${2:i} in ${3:0} to ${4:7} generate",
+                    "  $0",
+                    "end generate;",
+                ],
+            ),
+            template(
+                "case",
+                "Case statement",
+                Sequential,
+                &[
+                    "case ${1:expression} is",
+                    "  when ${2:choice} =>",
+                    "    $0",
+                    "  when others =>",
+                    "    null;",
+                    "end case;",
+                ],
+            ),
+        ]
+    })
+}
+
+/// The context of the cursor for snippets.
+/// `None` when the cursor is within a part of a design unit without a snippet context,
+/// such as within a statement.
+pub(crate) fn snippet_context(
+    root: &DesignRoot,
+    source: &Source,
+    cursor: Position,
+) -> Option<SnippetContext> {
+    for unit in root.units_by_source(source) {
+        let Some(data) = unit.unit.get() else {
+            continue;
+        };
+        let ctx: &dyn TokenAccess = &unit.tokens;
+        if !data.data().get_pos(ctx).contains(cursor) {
+            continue;
+        }
+        return match data.data() {
+            AnyDesignUnit::Secondary(AnySecondaryUnit::Architecture(arch)) => {
+                if cursor <= ctx.get_pos(arch.begin_token).start() {
+                    Some(SnippetContext::ArchitectureDeclarations)
+                } else {
+                    concurrent_context(&arch.statements, ctx, cursor)
+                }
+            }
+            AnyDesignUnit::Primary(AnyPrimaryUnit::Package(_))
+            | AnyDesignUnit::Secondary(AnySecondaryUnit::PackageBody(_)) => {
+                Some(SnippetContext::PackageDeclarations)
+            }
+            _ => None,
+        };
+    }
+    Some(SnippetContext::DesignFile)
+}
+
+fn concurrent_context(
+    statements: &[LabeledConcurrentStatement],
+    ctx: &dyn TokenAccess,
+    cursor: Position,
+) -> Option<SnippetContext> {
+    let Some(stmt) = statements
+        .iter()
+        .find(|stmt| stmt.statement.pos(ctx).contains(cursor))
+    else {
+        return Some(SnippetContext::Concurrent);
+    };
+    match stmt.statement.item {
+        ConcurrentStatement::Process(ref process) => process_context(process, stmt, ctx, cursor),
+        ConcurrentStatement::Block(_)
+        | ConcurrentStatement::ForGenerate(_)
+        | ConcurrentStatement::IfGenerate(_)
+        | ConcurrentStatement::CaseGenerate(_) => {
+            for (_, statements) in nested_statements(std::slice::from_ref(stmt)) {
+                if statements
+                    .iter()
+                    .any(|stmt| stmt.statement.pos(ctx).contains(cursor))
+                {
+                    return concurrent_context(statements, ctx, cursor);
+                }
+            }
+            Some(SnippetContext::Concurrent)
+        }
+        _ => None,
+    }
+}
+
+/// The statement part of a process starts after the `begin` that follows the declarations
+fn process_context(
+    process: &ProcessStatement,
+    stmt: &LabeledConcurrentStatement,
+    ctx: &dyn TokenAccess,
+    cursor: Position,
+) -> Option<SnippetContext> {
+    let span = stmt.statement.span;
+    let start = process
+        .decl
+        .last()
+        .map(|decl| decl.span.end_token)
+        .unwrap_or(span.start_token);
+    let begin = ctx
+        .get_token_slice(start, span.end_token)
+        .iter()
+        .find(|token| token.kind == Kind::Begin)?;
+    (cursor >= begin.pos.end()).then_some(SnippetContext::Sequential)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::LibraryBuilder;
+
+    fn snippet(toml: &str) -> Result<Snippet, String> {
+        Snippet::from_table("name", &toml.parse::<Table>().unwrap())
+    }
+
+    #[test]
+    fn reads_snippet_from_table() {
+        assert_eq!(
+            snippet(
+                "
+prefix = 'reg'
+body = ['signal ${1:name} : ${2:std_logic};', '$0']
+description = 'A register'
+context = 'architecture_declarations'
+"
+            ),
+            Ok(Snippet {
+                name: "name".to_owned(),
+                prefix: "reg".to_owned(),
+                body: "signal ${1:name} : ${2:std_logic};\n$0".to_owned(),
+                description: Some("A register".to_owned()),
+                context: SnippetContext::ArchitectureDeclarations,
+            })
+        );
+        assert_eq!(
+            snippet("prefix = 'reg'\nbody = 'x'\ncontext = 'entity'"),
+            Err("'entity' is not a valid value of snippets.name.context".to_owned())
+        );
+        assert_eq!(
+            snippet("prefix = 'reg'"),
+            Err("snippets.name.body must be a string or an array of strings".to_owned())
+        );
+    }
+
+    #[test]
+    fn plain_text_without_placeholders() {
+        let mut snippet = snippet("prefix = 'reg'\nbody = ''").unwrap();
+        snippet.body =
+            "${1:name}: process(${2|clk,clk_i|})\\$ \\}\nbegin\n  $0\nend process;".to_owned();
+        assert_eq!(
+            snippet.plain_text(),
+            "name: process(clk)$ }\nbegin\n  \nend process;"
+        );
+    }
+
+    #[test]
+    fn context_of_cursor() {
+        let mut builder = LibraryBuilder::new();
+        let code = builder.code(
+            "libname",
+            "\
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal sig : bit;
+begin
+  sig <= '1';
+
+  main: process
+    variable var : bit;
+  begin
+    var := sig;
+    wait;
+  end process;
+end architecture;
+
+package pkg is
+  constant c : bit := '0';
+end package;
+",
+        );
+        let (root, _) = builder.get_analyzed_root();
+        let context = |substr: &str| snippet_context(&root, code.source(), code.s1(substr).start());
+
+        assert_eq!(
+            context("signal sig"),
+            Some(SnippetContext::ArchitectureDeclarations)
+        );
+        assert_eq!(context("main"), Some(SnippetContext::Concurrent));
+        assert_eq!(context("var :="), Some(SnippetContext::Sequential));
+        assert_eq!(context("variable"), None);
+        assert_eq!(context("sig <="), None);
+        assert_eq!(
+            context("constant"),
+            Some(SnippetContext::PackageDeclarations)
+        );
+        assert_eq!(
+            snippet_context(&root, code.source(), code.end()),
+            Some(SnippetContext::DesignFile)
+        );
+    }
+}
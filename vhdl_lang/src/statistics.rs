@@ -0,0 +1,491 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Statistics of the analyzed design for reports and dashboards
+
+use crate::analysis::DesignRoot;
+use crate::ast::{
+    AnyDesignUnit, AnyPrimaryUnit, AnySecondaryUnit, ConcurrentStatement, HasUnitId,
+    LabeledConcurrentStatement,
+};
+use crate::data::{HasSource, Source, Symbol};
+use crate::hierarchy::{
+    instance_hierarchy, instance_of, instantiated_entities, nested_statements, top_level_entities,
+    Instance,
+};
+use fnv::FnvHashSet;
+use serde_json::json;
+
+/// The statistics of the libraries and entities of a design
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct DesignStatistics {
+    pub libraries: Vec<LibraryStatistics>,
+    pub entities: Vec<EntityStatistics>,
+    /// The names of the instances from a top-level entity down to the deepest instance
+    pub deepest_hierarchy: Vec<String>,
+}
+
+/// The number of design units, lines of code and processes of a library
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct LibraryStatistics {
+    pub name: String,
+    pub files: usize,
+    /// The lines that are neither blank nor only a comment
+    pub lines: usize,
+    pub entities: usize,
+    pub architectures: usize,
+    pub packages: usize,
+    pub package_bodies: usize,
+    pub configurations: usize,
+    pub contexts: usize,
+    pub processes: usize,
+}
+
+/// The instantiations of the architectures of an entity
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct EntityStatistics {
+    pub library: String,
+    pub name: String,
+    /// The number of instantiation statements
+    pub instances: usize,
+    /// The number of different entities that are instantiated
+    pub instantiated_entities: usize,
+}
+
+impl DesignStatistics {
+    /// The statistics as a JSON object
+    pub fn to_json(&self) -> String {
+        let libraries: Vec<_> = self
+            .libraries
+            .iter()
+            .map(|library| {
+                json!({
+                    "name": library.name,
+                    "files": library.files,
+                    "lines": library.lines,
+                    "entities": library.entities,
+                    "architectures": library.architectures,
+                    "packages": library.packages,
+                    "package_bodies": library.package_bodies,
+                    "configurations": library.configurations,
+                    "contexts": library.contexts,
+                    "processes": library.processes,
+                })
+            })
+            .collect();
+        let entities: Vec<_> = self
+            .entities
+            .iter()
+            .map(|entity| {
+                json!({
+                    "library": entity.library,
+                    "name": entity.name,
+                    "instances": entity.instances,
+                    "instantiated_entities": entity.instantiated_entities,
+                })
+            })
+            .collect();
+        serde_json::to_string_pretty(&json!({
+            "libraries": libraries,
+            "entities": entities,
+            "hierarchy_depth": self.deepest_hierarchy.len(),
+            "deepest_hierarchy": self.deepest_hierarchy,
+        }))
+        .unwrap_or_default()
+    }
+
+    /// The statistics as tables for the terminal
+    pub fn to_table(&self) -> String {
+        let mut rows: Vec<Vec<String>> = self
+            .libraries
+            .iter()
+            .map(|library| {
+                vec![
+                    library.name.clone(),
+                    library.files.to_string(),
+                    library.lines.to_string(),
+                    library.entities.to_string(),
+                    library.architectures.to_string(),
+                    library.packages.to_string(),
+                    library.package_bodies.to_string(),
+                    library.configurations.to_string(),
+                    library.contexts.to_string(),
+                    library.processes.to_string(),
+                ]
+            })
+            .collect();
+        let total = |count: fn(&LibraryStatistics) -> usize| {
+            self.libraries.iter().map(count).sum::<usize>().to_string()
+        };
+        rows.push(vec![
+            "Total".to_owned(),
+            total(|library| library.files),
+            total(|library| library.lines),
+            total(|library| library.entities),
+            total(|library| library.architectures),
+            total(|library| library.packages),
+            total(|library| library.package_bodies),
+            total(|library| library.configurations),
+            total(|library| library.contexts),
+            total(|library| library.processes),
+        ]);
+        let mut result = table(
+            &[
+                "Library",
+                "Files",
+                "Lines",
+                "Entities",
+                "Architectures",
+                "Packages",
+                "Package bodies",
+                "Configurations",
+                "Contexts",
+                "Processes",
+            ],
+            &rows,
+        );
+
+        let rows: Vec<Vec<String>> = self
+            .entities
+            .iter()
+            .map(|entity| {
+                vec![
+                    format!("{}.{}", entity.library, entity.name),
+                    entity.instances.to_string(),
+                    entity.instantiated_entities.to_string(),
+                ]
+            })
+            .collect();
+        result.push('\n');
+        result.push_str(&table(
+            &["Entity", "Instances", "Instantiated entities"],
+            &rows,
+        ));
+
+        result.push('\n');
+        result.push_str(&format!(
+            "Hierarchy depth: {}\n",
+            self.deepest_hierarchy.len()
+        ));
+        if !self.deepest_hierarchy.is_empty() {
+            result.push_str(&format!(
+                "Deepest hierarchy: {}\n",
+                self.deepest_hierarchy.join(" / ")
+            ));
+        }
+        result
+    }
+}
+
+/// A table with the first column aligned to the left and the other columns to the right
+fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            rows.iter()
+                .map(|row| row[i].len())
+                .chain(std::iter::once(header.len()))
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    let line = |cells: Vec<&str>| {
+        let cells: Vec<String> = cells
+            .into_iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                if i == 0 {
+                    format!("{cell:<width$}", width = widths[i])
+                } else {
+                    format!("{cell:>width$}", width = widths[i])
+                }
+            })
+            .collect();
+        format!("{}\n", cells.join("  ").trim_end())
+    };
+
+    let mut result = line(headers.to_vec());
+    for row in rows {
+        result.push_str(&line(row.iter().map(String::as_str).collect()));
+    }
+    result
+}
+
+/// The statistics of the libraries and of their entities
+pub fn design_statistics(root: &DesignRoot, libraries: &[Symbol]) -> DesignStatistics {
+    let mut statistics = DesignStatistics::default();
+    for library_name in libraries {
+        let Some(library) = root.get_lib(library_name) else {
+            continue;
+        };
+        let mut library_statistics = LibraryStatistics {
+            name: library_name.name_utf8(),
+            ..Default::default()
+        };
+        let mut sources = FnvHashSet::default();
+
+        for unit in library.units() {
+            sources.insert(unit.source().clone());
+            let Some(data) = unit.unit.get() else {
+                continue;
+            };
+            match data.data() {
+                AnyDesignUnit::Primary(primary) => match primary {
+                    AnyPrimaryUnit::Entity(entity) => {
+                        library_statistics.entities += 1;
+                        library_statistics.processes += count_processes(&entity.statements);
+                    }
+                    AnyPrimaryUnit::Package(_) | AnyPrimaryUnit::PackageInstance(_) => {
+                        library_statistics.packages += 1
+                    }
+                    AnyPrimaryUnit::Configuration(_) => library_statistics.configurations += 1,
+                    AnyPrimaryUnit::Context(_) => library_statistics.contexts += 1,
+                },
+                AnyDesignUnit::Secondary(secondary) => match secondary {
+                    AnySecondaryUnit::Architecture(arch) => {
+                        library_statistics.architectures += 1;
+                        library_statistics.processes += count_processes(&arch.statements);
+                    }
+                    AnySecondaryUnit::PackageBody(_) => library_statistics.package_bodies += 1,
+                },
+            }
+        }
+        library_statistics.files = sources.len();
+        library_statistics.lines = sources.iter().map(lines_of_code).sum();
+        statistics.libraries.push(library_statistics);
+
+        for unit in library.primary_units() {
+            let is_entity = unit.unit.get().is_some_and(|data| data.data().is_entity());
+            if !is_entity {
+                continue;
+            }
+            let name = unit.unit_id().primary_name();
+            let mut entity_statistics = EntityStatistics {
+                library: library_name.name_utf8(),
+                name: name.name_utf8(),
+                instances: 0,
+                instantiated_entities: 0,
+            };
+            let mut entities = FnvHashSet::default();
+            for arch_unit in library.secondary_units(name) {
+                let Some(data) = arch_unit.unit.get() else {
+                    continue;
+                };
+                if let AnyDesignUnit::Secondary(AnySecondaryUnit::Architecture(arch)) = data.data()
+                {
+                    entity_statistics.instances += count_instances(&arch.statements);
+                    entities.extend(instantiated_entities(root, library_name, &arch.statements));
+                }
+            }
+            entity_statistics.instantiated_entities = entities.len();
+            statistics.entities.push(entity_statistics);
+        }
+    }
+    statistics
+        .libraries
+        .sort_by(|lib1, lib2| lib1.name.cmp(&lib2.name));
+    statistics
+        .entities
+        .sort_by(|ent1, ent2| (&ent1.library, &ent1.name).cmp(&(&ent2.library, &ent2.name)));
+
+    for (library, entity) in top_level_entities(root, libraries) {
+        if let Some(instance) = instance_hierarchy(root, &library, &entity) {
+            let path = deepest_path(&instance);
+            if path.len() > statistics.deepest_hierarchy.len() {
+                statistics.deepest_hierarchy = path;
+            }
+        }
+    }
+    statistics
+}
+
+/// The names of the instances on the longest path from the instance down
+fn deepest_path(instance: &Instance) -> Vec<String> {
+    let mut path = instance
+        .instances
+        .iter()
+        .map(deepest_path)
+        .fold(Vec::new(), |deepest, path| {
+            if path.len() > deepest.len() {
+                path
+            } else {
+                deepest
+            }
+        });
+    path.insert(0, instance.name.clone());
+    path
+}
+
+fn count_processes(statements: &[LabeledConcurrentStatement]) -> usize {
+    let processes = statements
+        .iter()
+        .filter(|stmt| matches!(stmt.statement.item, ConcurrentStatement::Process(_)))
+        .count();
+    processes
+        + nested_statements(statements)
+            .into_iter()
+            .map(|(_, statements)| count_processes(statements))
+            .sum::<usize>()
+}
+
+fn count_instances(statements: &[LabeledConcurrentStatement]) -> usize {
+    let instances = statements
+        .iter()
+        .filter(|stmt| instance_of(stmt).is_some())
+        .count();
+    instances
+        + nested_statements(statements)
+            .into_iter()
+            .map(|(_, statements)| count_instances(statements))
+            .sum::<usize>()
+}
+
+/// The number of lines that are neither blank nor only a comment
+fn lines_of_code(source: &Source) -> usize {
+    let contents = source.contents();
+    (0..contents.num_lines())
+        .filter_map(|lineno| contents.get_line(lineno))
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("--"))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+
+    #[test]
+    fn counts_units_lines_and_processes() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "\
+-- A package
+package pkg is
+  constant width : natural := 8;
+end package;
+
+package body pkg is
+end package body;
+
+entity leaf is
+end entity;
+
+architecture rtl of leaf is
+begin
+  main: process
+  begin
+    wait;
+  end process;
+
+  gen: if true generate
+    other: process
+    begin
+      wait;
+    end process;
+  end generate;
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let statistics = design_statistics(&root, &[root.symbol_utf8("libname")]);
+        assert_eq!(
+            statistics.libraries,
+            vec![LibraryStatistics {
+                name: "libname".to_owned(),
+                files: 1,
+                lines: 20,
+                entities: 1,
+                architectures: 1,
+                packages: 1,
+                package_bodies: 1,
+                configurations: 0,
+                contexts: 0,
+                processes: 2,
+            }]
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&statistics.to_json()).unwrap();
+        assert_eq!(json["libraries"][0]["name"], "libname");
+        assert_eq!(json["libraries"][0]["processes"], 2);
+    }
+
+    #[test]
+    fn counts_instances_and_hierarchy_depth() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            "\
+entity leaf is
+end entity;
+
+architecture rtl of leaf is
+begin
+end architecture;
+
+entity mid is
+end entity;
+
+architecture rtl of mid is
+begin
+  leaf_a: entity work.leaf;
+  leaf_b: entity work.leaf;
+end architecture;
+
+entity top is
+end entity;
+
+architecture rtl of top is
+begin
+  mid_inst: entity work.mid;
+  gen: for i in 0 to 1 generate
+    leaf_inst: entity work.leaf;
+  end generate;
+end architecture;",
+        );
+        let (root, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+
+        let statistics = design_statistics(&root, &[root.symbol_utf8("libname")]);
+        let fan_out: Vec<_> = statistics
+            .entities
+            .iter()
+            .map(|entity| {
+                (
+                    entity.name.as_str(),
+                    entity.instances,
+                    entity.instantiated_entities,
+                )
+            })
+            .collect();
+        assert_eq!(fan_out, vec![("leaf", 0, 0), ("mid", 2, 1), ("top", 2, 2)]);
+        assert_eq!(
+            statistics.deepest_hierarchy,
+            vec!["top".to_owned(), "mid_inst".to_owned(), "leaf_a".to_owned()]
+        );
+    }
+
+    #[test]
+    fn aligns_columns_of_table() {
+        assert_eq!(
+            table(
+                &["Entity", "Instances"],
+                &[
+                    vec!["lib.top".to_owned(), "12".to_owned()],
+                    vec!["lib.a".to_owned(), "3".to_owned()],
+                ]
+            ),
+            "\
+Entity   Instances
+lib.top         12
+lib.a            3
+"
+        );
+    }
+}
@@ -28,7 +28,7 @@ fn parse_entity_aspect(ctx: &mut ParsingContext<'_>) -> ParseResult<EntityAspect
                 if ctx.stream.skip_if_kind(LeftPar) {
                     let ident = ctx.stream.expect_ident()?;
                     ctx.stream.expect_kind(RightPar)?;
-                    Some(ident)
+                    Some(WithRef::new(ident))
                 } else {
                     None
                 }
@@ -803,7 +803,7 @@ end configuration cfg;
             code.with_stream(parse_entity_aspect),
             EntityAspect::Entity(
                 code.s1("lib.foo.name").name(),
-                Some(code.s1("arch").ident())
+                Some(WithRef::new(code.s1("arch").ident()))
             )
         );
     }
@@ -838,7 +838,7 @@ end configuration cfg;
                 bind_ind: BindingIndication {
                     entity_aspect: Some(EntityAspect::Entity(
                         code.s1("work.foo").name(),
-                        Some(code.s1("rtl").ident())
+                        Some(WithRef::new(code.s1("rtl").ident()))
                     )),
                     generic_map: None,
                     port_map: None
@@ -863,7 +863,7 @@ end configuration cfg;
                 bind_ind: BindingIndication {
                     entity_aspect: Some(EntityAspect::Entity(
                         code.s1("work.foo").name(),
-                        Some(code.s1("rtl").ident())
+                        Some(WithRef::new(code.s1("rtl").ident()))
                     )),
                     generic_map: None,
                     port_map: None
@@ -890,7 +890,7 @@ end configuration cfg;
                 bind_ind: BindingIndication {
                     entity_aspect: Some(EntityAspect::Entity(
                         code.s1("work.foo").name(),
-                        Some(code.s1("rtl").ident())
+                        Some(WithRef::new(code.s1("rtl").ident()))
                     )),
                     generic_map: None,
                     port_map: None
@@ -158,6 +158,7 @@ pub fn parse_design_file(ctx: &mut ParsingContext<'_>) -> ParseResult<DesignFile
     let mut design_units = vec![];
 
     while let Some(token) = ctx.stream.peek() {
+        let unit_start = ctx.stream.state();
         try_init_token_kind!(
             token,
             Library => {
@@ -165,7 +166,7 @@ pub fn parse_design_file(ctx: &mut ParsingContext<'_>) -> ParseResult<DesignFile
                     Ok(library) => {
                         context_clause.push(ContextItem::Library(library));
                     },
-                    Err(diagnostic) => ctx.diagnostics.push(diagnostic),
+                    Err(diagnostic) => push_unless_protected(ctx, unit_start, diagnostic),
                 }
             },
             Use => {
@@ -173,7 +174,7 @@ pub fn parse_design_file(ctx: &mut ParsingContext<'_>) -> ParseResult<DesignFile
                     Ok(use_clause) => {
                         context_clause.push(ContextItem::Use(use_clause.item));
                     },
-                    Err(diagnostic) => ctx.diagnostics.push(diagnostic),
+                    Err(diagnostic) => push_unless_protected(ctx, unit_start, diagnostic),
                 }
             },
             Context => match parse_context(ctx) {
@@ -196,7 +197,7 @@ pub fn parse_design_file(ctx: &mut ParsingContext<'_>) -> ParseResult<DesignFile
                 Ok(DeclarationOrReference::Reference(context_ref)) => {
                     context_clause.push(ContextItem::Context(context_ref));
                 }
-                Err(diagnostic) => ctx.diagnostics.push(diagnostic),
+                Err(diagnostic) => push_unless_protected(ctx, unit_start, diagnostic),
             },
             Entity => match parse_entity_declaration(ctx) {
                 Ok(mut entity) => {
@@ -204,7 +205,7 @@ pub fn parse_design_file(ctx: &mut ParsingContext<'_>) -> ParseResult<DesignFile
                     entity.context_clause = take_context_clause(&mut context_clause);
                     design_units.push((tokens, AnyDesignUnit::Primary(AnyPrimaryUnit::Entity(entity))));
                 }
-                Err(diagnostic) => ctx.diagnostics.push(diagnostic),
+                Err(diagnostic) => push_unless_protected(ctx, unit_start, diagnostic),
             },
 
             Architecture => match parse_architecture_body(ctx) {
@@ -213,7 +214,7 @@ pub fn parse_design_file(ctx: &mut ParsingContext<'_>) -> ParseResult<DesignFile
                     architecture.context_clause = take_context_clause(&mut context_clause);
                     design_units.push((tokens, AnyDesignUnit::Secondary(AnySecondaryUnit::Architecture(architecture))));
                 }
-                Err(diagnostic) => ctx.diagnostics.push(diagnostic),
+                Err(diagnostic) => push_unless_protected(ctx, unit_start, diagnostic),
             },
 
             Configuration => match parse_configuration_declaration(ctx) {
@@ -222,7 +223,7 @@ pub fn parse_design_file(ctx: &mut ParsingContext<'_>) -> ParseResult<DesignFile
                     configuration.context_clause = take_context_clause(&mut context_clause);
                     design_units.push((tokens, AnyDesignUnit::Primary(AnyPrimaryUnit::Configuration(configuration))));
                 }
-                Err(diagnostic) => ctx.diagnostics.push(diagnostic),
+                Err(diagnostic) => push_unless_protected(ctx, unit_start, diagnostic),
             },
             Package => {
                 if ctx.stream.next_kinds_are(&[Package, Body]) {
@@ -232,7 +233,7 @@ pub fn parse_design_file(ctx: &mut ParsingContext<'_>) -> ParseResult<DesignFile
                             package_body.context_clause = take_context_clause(&mut context_clause);
                             design_units.push((tokens, AnyDesignUnit::Secondary(AnySecondaryUnit::PackageBody(package_body))));
                         }
-                        Err(diagnostic) => ctx.diagnostics.push(diagnostic),
+                        Err(diagnostic) => push_unless_protected(ctx, unit_start, diagnostic),
                     };
                 } else if ctx.stream.next_kinds_are(&[Package, Identifier, Is, New]) {
                     match parse_package_instantiation(ctx) {
@@ -241,7 +242,7 @@ pub fn parse_design_file(ctx: &mut ParsingContext<'_>) -> ParseResult<DesignFile
                             inst.context_clause = take_context_clause(&mut context_clause);
                             design_units.push((tokens, AnyDesignUnit::Primary(AnyPrimaryUnit::PackageInstance(inst))));
                         },
-                        Err(diagnostic) => ctx.diagnostics.push(diagnostic),
+                        Err(diagnostic) => push_unless_protected(ctx, unit_start, diagnostic),
                     }
                 } else {
                     match parse_package_declaration(ctx) {
@@ -250,7 +251,7 @@ pub fn parse_design_file(ctx: &mut ParsingContext<'_>) -> ParseResult<DesignFile
                             package.context_clause = take_context_clause(&mut context_clause);
                             design_units.push((tokens, AnyDesignUnit::Primary(AnyPrimaryUnit::Package(package))));
                         }
-                        Err(diagnostic) => ctx.diagnostics.push(diagnostic),
+                        Err(diagnostic) => push_unless_protected(ctx, unit_start, diagnostic),
                     };
                 }
             }
@@ -268,6 +269,14 @@ pub fn parse_design_file(ctx: &mut ParsingContext<'_>) -> ParseResult<DesignFile
     Ok(DesignFile { design_units })
 }
 
+/// The contents of a design unit with an encrypted envelope are unknown.
+/// Parse errors of such units are not reported, only the units before and after are parsed.
+fn push_unless_protected(ctx: &mut ParsingContext<'_>, unit_start: usize, diagnostic: Diagnostic) {
+    if !ctx.stream.is_protected_since(unit_start) {
+        ctx.diagnostics.push(diagnostic);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -291,6 +300,24 @@ mod tests {
         (code, design_file)
     }
 
+    #[test]
+    fn parse_errors_of_encrypted_unit_are_not_reported() {
+        let (_, design_file) = parse_ok(
+            "\
+entity ent is
+end entity;
+
+architecture rtl of ent is
+`protect begin_protected
+`protect key_keyowner = \"vendor\"
+`protect data_block
+Zm9vIGJhciAtLSAnIjsKZW5kIGVudGl0eTs=
+`protect end_protected
+",
+        );
+        assert_eq!(design_file.design_units.len(), 1);
+    }
+
     fn to_single_entity(design_file: DesignFile) -> EntityDeclaration {
         match design_file.design_units.as_slice() {
             [(_, AnyDesignUnit::Primary(AnyPrimaryUnit::Entity(ref entity)))] => entity.to_owned(),
@@ -6,9 +6,11 @@
 
 use super::tokens::{Symbols, TokenStream, Tokenizer};
 use crate::ast::DesignFile;
+use crate::config::SynthesisPragmas;
 use crate::data::*;
 use crate::standard::VHDLStandard;
 use crate::syntax::design_unit::parse_design_file;
+use crate::{ip_core, verilog};
 use crate::{Token, TokenId};
 use std::io;
 use std::sync::Arc;
@@ -17,6 +19,7 @@ use vhdl_lang::TokenAccess;
 pub struct VHDLParser {
     pub symbols: Arc<Symbols>,
     pub standard: VHDLStandard,
+    pub synthesis_pragmas: SynthesisPragmas,
 }
 
 pub(crate) struct ParsingContext<'a> {
@@ -42,6 +45,7 @@ impl VHDLParser {
         VHDLParser {
             symbols: Arc::new(Symbols::from_standard(vhdl_standard)),
             standard: vhdl_standard,
+            synthesis_pragmas: SynthesisPragmas::default(),
         }
     }
 
@@ -55,8 +59,33 @@ impl VHDLParser {
         diagnostics: &mut dyn DiagnosticHandler,
     ) -> DesignFile {
         let contents = source.contents();
-        let tokenizer = Tokenizer::new(&self.symbols, source, ContentReader::new(&contents));
-        let stream = TokenStream::new(tokenizer, diagnostics);
+        // Verilog files and IP cores are replaced by the entity declarations of their modules
+        let black_box_entities: Option<fn(&str, VHDLStandard) -> String> =
+            if verilog::is_verilog_file(source.file_name()) {
+                Some(verilog::black_box_entities)
+            } else if ip_core::is_ip_core_file(source.file_name()) {
+                Some(ip_core::black_box_entities)
+            } else {
+                None
+            };
+        let black_box;
+        let contents = if let Some(black_box_entities) = black_box_entities {
+            black_box = Contents::from_str(&black_box_entities(
+                &contents.text(contents.range()),
+                self.standard,
+            ));
+            &black_box
+        } else {
+            &*contents
+        };
+        let tokenizer = Tokenizer::new(&self.symbols, source, ContentReader::new(contents));
+        let mut stream = TokenStream::new(tokenizer, diagnostics);
+        if self.synthesis_pragmas != SynthesisPragmas::Analyze {
+            let exclude = self.synthesis_pragmas == SynthesisPragmas::Exclude;
+            for region in stream.synthesis_off_regions(exclude) {
+                diagnostics.push(Diagnostic::excluded_from_synthesis(region));
+            }
+        }
 
         let mut ctx = ParsingContext {
             stream: &stream,
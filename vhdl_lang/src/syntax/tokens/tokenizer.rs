@@ -1817,6 +1817,37 @@ impl<'a> Tokenizer<'a> {
         self.final_comments.clone()
     }
 
+    /// Skip the lines of an encrypted IEEE P1735 envelope until and including the line of
+    /// `` `protect end_protected `` or the end of the file. The encrypted lines are not tokenized.
+    pub fn skip_protected_envelope(&mut self) {
+        let is_end = |line: &str| {
+            let mut words = line
+                .trim_start()
+                .strip_prefix('`')
+                .unwrap_or_default()
+                .split_whitespace();
+            words
+                .next()
+                .is_some_and(|word| word.eq_ignore_ascii_case("protect"))
+                && words
+                    .next()
+                    .is_some_and(|word| word.eq_ignore_ascii_case("end_protected"))
+        };
+
+        let mut line = String::new();
+        while let Some(chr) = self.reader.pop_char() {
+            if chr == '\n' {
+                if is_end(&line) {
+                    break;
+                }
+                line.clear();
+            } else {
+                line.push(chr);
+            }
+        }
+        self.state.start = self.reader.state();
+    }
+
     pub fn text_until_newline(&mut self) -> DiagnosticResult<Token> {
         let start_pos = self.reader.pos();
         if let Err(err) = read_until_newline(&mut self.buffer, &mut self.reader) {
@@ -11,13 +11,15 @@ use super::tokenizer::Kind::*;
 use super::tokenizer::*;
 use crate::ast::token_range::WithToken;
 use crate::ast::{AttributeDesignator, Ident, RangeAttribute, TypeAttribute};
-use crate::data::{DiagnosticHandler, DiagnosticResult};
+use crate::data::{DiagnosticHandler, DiagnosticResult, Position, Range};
 use crate::{Diagnostic, SrcPos};
 
 pub struct TokenStream<'a> {
     tokenizer: Tokenizer<'a>,
     idx: Cell<usize>,
     tokens: Vec<Token>,
+    // The indexes of the tokens that follow an encrypted envelope
+    protected_envelopes: Vec<usize>,
     // This is the offset that a token's ID should be adapted
     // when getting it via `TokenStream::get_current_token_id()`
     // It is updated in the `slice_tokens` method
@@ -30,30 +32,50 @@ impl<'a> TokenStream<'a> {
     /// `identifier { any chars until newline }
     /// ```
     /// This needs special handling as the text that follows the identifier is arbitrary.
+    ///
+    /// The directive `` `protect begin_protected `` starts an encrypted envelope that is skipped
+    /// until `` `protect end_protected `` or the end of the file.
+    /// Returns true if an envelope was skipped.
     fn handle_tool_directive(
         grave_accent: Token,
         tokenizer: &mut Tokenizer,
         diagnostics: &mut dyn DiagnosticHandler,
-    ) {
+    ) -> bool {
         let start_pos = grave_accent.pos.clone();
+        let mut is_protect = false;
         match tokenizer.pop() {
             Ok(Some(tok)) => {
                 if tok.kind != Identifier {
                     diagnostics.push(Diagnostic::syntax_error(tok, "Expecting identifier"));
                     let _ = tokenizer.text_until_newline(); // skip potentially invalid tokens
-                    return;
+                    return false;
                 }
+                is_protect = matches!(
+                    tok.value,
+                    Value::Identifier(ref sym) if sym.name_utf8().eq_ignore_ascii_case("protect")
+                );
             }
             Err(err) => diagnostics.push(err),
             Ok(None) => {
                 diagnostics.push(Diagnostic::syntax_error(start_pos, "Expecting identifier"));
-                return;
+                return false;
             }
         }
         match tokenizer.text_until_newline() {
-            Ok(_) => {}
+            Ok(text) => {
+                let is_begin_protected = matches!(
+                    text.value,
+                    Value::Text(ref text)
+                        if text.to_string().trim().eq_ignore_ascii_case("begin_protected")
+                );
+                if is_protect && is_begin_protected {
+                    tokenizer.skip_protected_envelope();
+                    return true;
+                }
+            }
             Err(err) => diagnostics.push(err),
         }
+        false
     }
 
     pub fn new(
@@ -61,10 +83,13 @@ impl<'a> TokenStream<'a> {
         diagnostics: &mut dyn DiagnosticHandler,
     ) -> TokenStream<'a> {
         let mut tokens = Vec::new();
+        let mut protected_envelopes = Vec::new();
         loop {
             match tokenizer.pop() {
                 Ok(Some(token)) if token.kind == GraveAccent => {
-                    TokenStream::handle_tool_directive(token, &mut tokenizer, diagnostics)
+                    if TokenStream::handle_tool_directive(token, &mut tokenizer, diagnostics) {
+                        protected_envelopes.push(tokens.len());
+                    }
                 }
                 Ok(Some(token)) => tokens.push(token),
                 Ok(None) => break,
@@ -75,10 +100,72 @@ impl<'a> TokenStream<'a> {
             tokenizer,
             idx: Cell::new(0),
             tokens,
+            protected_envelopes,
             token_offset: Cell::new(0),
         }
     }
 
+    /// The regions of the source between pragmas such as
+    /// `-- synthesis translate_off` and `-- synthesis translate_on`.
+    /// A region that is not turned back on extends to the end of the file.
+    /// When `exclude` is true the tokens within the regions are removed from the stream.
+    pub fn synthesis_off_regions(&mut self, exclude: bool) -> Vec<SrcPos> {
+        let source = self.tokenizer.source.clone();
+        let mut regions = Vec::new();
+        let mut off_start: Option<Position> = None;
+        let mut handle_comment = |comment: &Comment, off_start: &mut Option<Position>| {
+            let pragma = synthesis_pragma(&comment.value);
+            match (pragma, *off_start) {
+                (Some(false), None) => *off_start = Some(comment.range.start),
+                (Some(true), Some(start)) => {
+                    let range = Range::new(start, comment.range.end);
+                    regions.push(SrcPos::new(source.clone(), range));
+                    *off_start = None;
+                }
+                _ => {}
+            }
+        };
+
+        let mut excluded = vec![false; self.tokens.len()];
+        for (idx, token) in self.tokens.iter().enumerate() {
+            if let Some(comments) = &token.comments {
+                for comment in comments.leading.iter() {
+                    handle_comment(comment, &mut off_start);
+                }
+            }
+            excluded[idx] = off_start.is_some();
+            if let Some(comment) = token.comments.as_ref().and_then(|c| c.trailing.as_ref()) {
+                handle_comment(comment, &mut off_start);
+            }
+        }
+        for comment in self.tokenizer.get_final_comments().unwrap_or_default() {
+            handle_comment(&comment, &mut off_start);
+        }
+        if let Some(start) = off_start {
+            let end = source.contents().end();
+            regions.push(SrcPos::new(source.clone(), Range::new(start, end)));
+        }
+
+        if exclude {
+            // Envelopes refer to the index of the following token which moves
+            // by the number of removed tokens before it
+            for envelope in self.protected_envelopes.iter_mut() {
+                *envelope -= excluded[..*envelope].iter().filter(|e| **e).count();
+            }
+            let mut excluded = excluded.into_iter();
+            self.tokens.retain(|_| !excluded.next().unwrap_or(false));
+        }
+        regions
+    }
+
+    /// True if an encrypted envelope was skipped after the token at the `start` state
+    /// and no later than the current token
+    pub fn is_protected_since(&self, start: usize) -> bool {
+        self.protected_envelopes
+            .iter()
+            .any(|idx| *idx > start && *idx <= self.get_idx())
+    }
+
     pub fn state(&self) -> usize {
         self.get_idx()
     }
@@ -326,6 +413,32 @@ impl<T> Recover<T> for DiagnosticResult<T> {
     }
 }
 
+/// Some(false) for a pragma that turns synthesis off and Some(true) for a pragma
+/// that turns it back on, i.e.
+/// ```vhdl
+/// -- synthesis translate_off
+/// -- pragma synthesis_off
+/// -- rtl_synthesis off
+/// ```
+fn synthesis_pragma(comment: &str) -> Option<bool> {
+    let mut words = comment.split_whitespace();
+    let tool = words.next()?.to_ascii_lowercase();
+    let directive = words.next()?.to_ascii_lowercase();
+    if words.next().is_some() {
+        return None;
+    }
+    match (tool.as_str(), directive.as_str()) {
+        ("rtl_synthesis", "off") => Some(false),
+        ("rtl_synthesis", "on") => Some(true),
+        ("synthesis" | "synopsys" | "pragma" | "xilinx" | "altera", directive) => match directive {
+            "translate_off" | "synthesis_off" => Some(false),
+            "translate_on" | "synthesis_on" => Some(true),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -516,6 +629,103 @@ mod tests {
         )
     }
 
+    #[test]
+    fn skips_protected_envelope() {
+        let code = Code::new(
+            "\
+entity ent is
+`protect begin_protected
+`protect encrypt_agent = \"vendor\"
+`protect data_method = \"aes128-cbc\"
+`protect data_block
+Zm9vIGJhciAtLSAnIjsKZW5kIGVudGl0eTs=
+`protect end_protected
+end entity;
+",
+        );
+        let mut diagnostics: Vec<Diagnostic> = vec![];
+        new_stream!(code, stream, diagnostics);
+        assert_eq!(diagnostics, vec![]);
+        let kinds: Vec<Kind> = std::iter::from_fn(|| {
+            let kind = stream.peek_kind();
+            stream.skip();
+            kind
+        })
+        .collect();
+        assert_eq!(kinds, vec![Entity, Identifier, Is, End, Entity, SemiColon]);
+        assert!(stream.is_protected_since(0));
+        assert!(!stream.is_protected_since(3));
+    }
+
+    #[test]
+    fn unterminated_protected_envelope() {
+        let code = Code::new("`protect begin_protected\n`protect data_block\nZm9v\n");
+        let mut diagnostics: Vec<Diagnostic> = vec![];
+        new_stream!(code, stream, diagnostics);
+        assert_eq!(diagnostics, vec![]);
+        assert_eq!(stream.peek_kind(), None);
+    }
+
+    #[test]
+    fn synthesis_off_regions() {
+        let code = Code::new(
+            "\
+a := 1;
+-- synthesis translate_off
+b := 2; -- pragma synthesis_off
+-- synthesis translate_on
+c := 3;
+-- rtl_synthesis off
+d := 4;",
+        );
+        new_stream!(code, stream);
+        let mut stream = stream;
+        assert_eq!(
+            stream.synthesis_off_regions(false),
+            vec![
+                code.between("-- synthesis translate_off", "translate_on")
+                    .pos(),
+                code.s1_to_end("-- rtl_synthesis off").pos()
+            ]
+        );
+        assert_eq!(stream.tokens.len(), 16);
+    }
+
+    #[test]
+    fn synthesis_off_regions_are_excluded() {
+        let code = Code::new(
+            "\
+a := 1;
+-- synthesis translate_off
+b := 2;
+-- synthesis translate_on
+c := 3;
+",
+        );
+        new_stream!(code, stream);
+        let mut stream = stream;
+        assert_eq!(stream.synthesis_off_regions(true).len(), 1);
+        let kinds: Vec<Kind> = std::iter::from_fn(|| {
+            let kind = stream.peek_kind();
+            stream.skip();
+            kind
+        })
+        .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Identifier,
+                ColonEq,
+                AbstractLiteral,
+                SemiColon,
+                Identifier,
+                ColonEq,
+                AbstractLiteral,
+                SemiColon
+            ]
+        );
+    }
+
     #[test]
     fn pop_tokens() {
         let code = Code::new(
@@ -0,0 +1,977 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! Black-box entities of the modules of Verilog and SystemVerilog files.
+//!
+//! Only the headers of modules and interfaces are scanned, i.e. the name, the parameters
+//! and the ports. Each module is translated to a VHDL entity declaration that is parsed instead of the file.
+//! The names of the entity, its generics and its ports are placed on the same line and column
+//! as in the Verilog file whenever possible, such that positions refer to the original file.
+
+use crate::data::Position;
+use crate::standard::VHDLStandard;
+use std::path::Path;
+
+/// True if the file is a Verilog or SystemVerilog file that is scanned for modules
+pub(crate) fn is_verilog_file(file_name: &Path) -> bool {
+    file_name
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("v") || ext.eq_ignore_ascii_case("sv"))
+}
+
+/// The VHDL entity declarations of the modules and interfaces of a Verilog file
+pub(crate) fn black_box_entities(text: &str, standard: VHDLStandard) -> String {
+    entity_declarations(&parse_modules(&tokenize(text)), standard)
+}
+
+/// The VHDL entity declarations of black-box modules.
+/// Identifiers are placed at their position in the original file.
+pub(crate) fn entity_declarations(modules: &[Module], standard: VHDLStandard) -> String {
+    let mut emitter = Emitter::default();
+    for module in modules {
+        emitter.entity(module, standard);
+    }
+    emitter.text
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum TokenKind {
+    Identifier(String),
+    Number(String),
+    String(String),
+    Punctuation(char),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    pos: Position,
+}
+
+impl Token {
+    fn is_identifier(&self, name: &str) -> bool {
+        matches!(self.kind, TokenKind::Identifier(ref ident) if ident == name)
+    }
+
+    fn is_punctuation(&self, chr: char) -> bool {
+        self.kind == TokenKind::Punctuation(chr)
+    }
+}
+
+/// Split the text into tokens.
+/// Comments, attributes and compiler directives are skipped.
+fn tokenize(text: &str) -> Vec<Token> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    let mut pos = Position::default();
+
+    // Advance over `count` characters while keeping track of the position
+    let advance = |idx: &mut usize, pos: &mut Position, count: usize| {
+        for _ in 0..count {
+            if let Some(chr) = chars.get(*idx) {
+                if *chr == '\n' {
+                    *pos = Position::new(pos.line + 1, 0);
+                } else {
+                    pos.character += 1;
+                }
+                *idx += 1;
+            }
+        }
+    };
+    let find = |start: usize, pattern: &str| -> usize {
+        let pattern: Vec<char> = pattern.chars().collect();
+        (start..chars.len())
+            .find(|idx| chars[*idx..].starts_with(&pattern))
+            .map(|idx| idx - start + pattern.len())
+            .unwrap_or(chars.len() - start)
+    };
+    let take_while = |start: usize, pred: &dyn Fn(char) -> bool| -> usize {
+        chars[start..].iter().take_while(|chr| pred(**chr)).count()
+    };
+
+    while let Some(&chr) = chars.get(idx) {
+        let next = chars.get(idx + 1).copied();
+        let start = pos;
+        let len = if chr.is_whitespace() {
+            1
+        } else if chr == '/' && next == Some('/') {
+            find(idx, "\n")
+        } else if chr == '/' && next == Some('*') {
+            find(idx + 2, "*/") + 2
+        } else if chr == '(' && next == Some('*') && chars.get(idx + 2) != Some(&')') {
+            find(idx + 2, "*)") + 2
+        } else if chr == '`' {
+            // Skip the directive together with the rest of the line
+            find(idx, "\n")
+        } else if chr == '"' {
+            let mut len = 1;
+            while let Some(chr) = chars.get(idx + len) {
+                len += if *chr == '\\' { 2 } else { 1 };
+                if *chr == '"' {
+                    break;
+                }
+            }
+            let end = (idx + len - 1).clamp(idx + 1, chars.len());
+            let value: String = chars[idx + 1..end].iter().collect();
+            tokens.push(Token {
+                kind: TokenKind::String(value),
+                pos: start,
+            });
+            len
+        } else if chr == '\\' {
+            // Escaped identifier
+            let len = take_while(idx, &|chr| !chr.is_whitespace());
+            tokens.push(Token {
+                kind: TokenKind::Identifier(chars[idx + 1..idx + len].iter().collect()),
+                pos: start,
+            });
+            len
+        } else if chr.is_ascii_alphabetic() || chr == '_' || chr == '$' {
+            let len = take_while(idx, &|chr| {
+                chr.is_ascii_alphanumeric() || chr == '_' || chr == '$'
+            });
+            tokens.push(Token {
+                kind: TokenKind::Identifier(chars[idx..idx + len].iter().collect()),
+                pos: start,
+            });
+            len
+        } else if chr.is_ascii_digit() || chr == '\'' {
+            // Also sized and based numbers such as 8'hff
+            let mut len = take_while(idx, &|chr| chr.is_ascii_digit() || chr == '_' || chr == '.');
+            if chars.get(idx + len) == Some(&'\'') {
+                len += 1;
+                len += take_while(idx + len, &|chr| {
+                    chr.is_ascii_alphanumeric() || chr == '_' || chr == '?'
+                });
+            }
+            tokens.push(Token {
+                kind: TokenKind::Number(chars[idx..idx + len.max(1)].iter().collect()),
+                pos: start,
+            });
+            len.max(1)
+        } else {
+            tokens.push(Token {
+                kind: TokenKind::Punctuation(chr),
+                pos: start,
+            });
+            1
+        };
+        advance(&mut idx, &mut pos, len);
+    }
+    tokens
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Identifier {
+    pub(crate) name: String,
+    pub(crate) pos: Position,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ParameterValue {
+    Integer(i64),
+    Real(String),
+    String(String),
+    /// The value is an expression that is not evaluated
+    Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Parameter {
+    pub(crate) ident: Identifier,
+    pub(crate) value: ParameterValue,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Direction {
+    Input,
+    Output,
+    Inout,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Width {
+    Bit,
+    /// A vector with the given bounds or an unconstrained vector when the bounds are not literals
+    Vector(Option<(i64, i64)>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Port {
+    pub(crate) ident: Identifier,
+    pub(crate) direction: Option<Direction>,
+    pub(crate) width: Width,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Module {
+    pub(crate) ident: Identifier,
+    pub(crate) parameters: Vec<Parameter>,
+    pub(crate) ports: Vec<Port>,
+    /// The position of `endmodule`
+    pub(crate) end: Option<Position>,
+}
+
+fn parse_modules(tokens: &[Token]) -> Vec<Module> {
+    let mut modules = Vec::new();
+    let mut idx = 0;
+    while let Some(token) = tokens.get(idx) {
+        let TokenKind::Identifier(ref keyword) = token.kind else {
+            idx += 1;
+            continue;
+        };
+        let is_virtual = idx > 0 && tokens[idx - 1].is_identifier("virtual");
+        match keyword.as_str() {
+            "module" | "macromodule" => {
+                let (module, end) = parse_module(tokens, idx + 1, "endmodule");
+                modules.extend(module);
+                idx = end;
+            }
+            // Not `virtual interface` or `interface class`
+            "interface"
+                if !is_virtual
+                    && !tokens
+                        .get(idx + 1)
+                        .is_some_and(|token| token.is_identifier("class")) =>
+            {
+                let (module, end) = parse_module(tokens, idx + 1, "endinterface");
+                modules.extend(module);
+                idx = end;
+            }
+            // Packages and classes contain no modules
+            "package" | "class" => {
+                let end_keyword = format!("end{keyword}");
+                idx = tokens[idx..]
+                    .iter()
+                    .position(|token| token.is_identifier(&end_keyword))
+                    .map_or(tokens.len(), |offset| idx + offset + 1);
+            }
+            _ => idx += 1,
+        }
+    }
+    modules
+}
+
+/// Parse the module or interface after its keyword and return the index after it
+fn parse_module(tokens: &[Token], start: usize, end_keyword: &str) -> (Option<Module>, usize) {
+    let mut idx = start;
+    if tokens
+        .get(idx)
+        .is_some_and(|token| token.is_identifier("automatic") || token.is_identifier("static"))
+    {
+        idx += 1;
+    }
+    let Some(ident) = tokens.get(idx).and_then(identifier) else {
+        return (None, start);
+    };
+    idx += 1;
+
+    // Package imports of the header such as `module m import pkg::*; (...)`
+    while tokens
+        .get(idx)
+        .is_some_and(|token| token.is_identifier("import"))
+    {
+        idx = statement_end(tokens, idx) + 1;
+    }
+
+    let mut parameters = Vec::new();
+    let has_parameter_list = tokens
+        .get(idx)
+        .is_some_and(|token| token.is_punctuation('#'));
+    if has_parameter_list {
+        let end = closing_paren(tokens, idx + 1);
+        for item in split_list(&tokens[(idx + 2).min(end)..end]) {
+            parameters.extend(parse_parameter(item));
+        }
+        idx = end + 1;
+    }
+
+    let mut ports: Vec<Port> = Vec::new();
+    if tokens
+        .get(idx)
+        .is_some_and(|token| token.is_punctuation('('))
+    {
+        let end = closing_paren(tokens, idx);
+        let mut previous: Option<Port> = None;
+        for item in split_list(&tokens[idx + 1..end]) {
+            if let Some(port) = parse_port(item, previous.as_ref()) {
+                previous = Some(port.clone());
+                ports.push(port);
+            }
+        }
+        idx = end + 1;
+    }
+
+    // Non-ANSI port declarations and parameters of the body
+    let mut end = None;
+    while let Some(token) = tokens.get(idx) {
+        let TokenKind::Identifier(ref keyword) = token.kind else {
+            idx += 1;
+            continue;
+        };
+        match keyword.as_str() {
+            keyword if keyword == end_keyword => {
+                end = Some(token.pos);
+                idx += 1;
+                break;
+            }
+            // The arguments of functions and tasks and the directions of modports
+            // and clocking blocks are not ports
+            "function" | "task" | "class" | "clocking" | "covergroup" | "property" | "sequence" => {
+                let nested_end = format!("end{keyword}");
+                idx = tokens[idx..]
+                    .iter()
+                    .position(|token| {
+                        token.is_identifier(&nested_end) || token.is_identifier(end_keyword)
+                    })
+                    .map_or(tokens.len(), |offset| idx + offset);
+                if tokens
+                    .get(idx)
+                    .is_some_and(|token| token.is_identifier(&nested_end))
+                {
+                    idx += 1;
+                }
+            }
+            "modport" | "import" | "export" | "extern" => idx = statement_end(tokens, idx),
+            "input" | "output" | "inout" => {
+                let stmt_end = statement_end(tokens, idx);
+                let mut previous = None;
+                for item in split_list(&tokens[idx..stmt_end]) {
+                    let Some(declared) = parse_port(item, previous.as_ref()) else {
+                        continue;
+                    };
+                    match ports
+                        .iter_mut()
+                        .find(|port| port.ident.name == declared.ident.name)
+                    {
+                        Some(port) => {
+                            port.direction = declared.direction;
+                            port.width = declared.width;
+                        }
+                        None => ports.push(declared.clone()),
+                    }
+                    previous = Some(declared);
+                }
+                idx = stmt_end;
+            }
+            // Parameters of the body can only be overridden without a parameter list
+            "parameter" if !has_parameter_list => {
+                let stmt_end = statement_end(tokens, idx);
+                for item in split_list(&tokens[idx..stmt_end]) {
+                    parameters.extend(parse_parameter(item));
+                }
+                idx = stmt_end;
+            }
+            _ => idx += 1,
+        }
+    }
+
+    (
+        Some(Module {
+            ident,
+            parameters,
+            ports,
+            end,
+        }),
+        idx,
+    )
+}
+
+fn identifier(token: &Token) -> Option<Identifier> {
+    match token.kind {
+        TokenKind::Identifier(ref name) => Some(Identifier {
+            name: name.clone(),
+            pos: token.pos,
+        }),
+        _ => None,
+    }
+}
+
+/// The index of the parenthesis that closes the one at `start`
+fn closing_paren(tokens: &[Token], start: usize) -> usize {
+    let mut depth = 0;
+    for (idx, token) in tokens.iter().enumerate().skip(start) {
+        if token.is_punctuation('(') {
+            depth += 1;
+        } else if token.is_punctuation(')') {
+            depth -= 1;
+            if depth == 0 {
+                return idx;
+            }
+        }
+    }
+    tokens.len()
+}
+
+/// The index of the `;` that ends the statement starting at `start`
+fn statement_end(tokens: &[Token], start: usize) -> usize {
+    tokens[start..]
+        .iter()
+        .position(|token| token.is_punctuation(';'))
+        .map_or(tokens.len(), |offset| start + offset)
+}
+
+/// Split a list at the commas that are not nested within parentheses, brackets or braces
+fn split_list(tokens: &[Token]) -> Vec<&[Token]> {
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (idx, token) in tokens.iter().enumerate() {
+        match token.kind {
+            TokenKind::Punctuation('(' | '[' | '{') => depth += 1,
+            TokenKind::Punctuation(')' | ']' | '}') => depth -= 1,
+            TokenKind::Punctuation(',') if depth == 0 => {
+                items.push(&tokens[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < tokens.len() {
+        items.push(&tokens[start..]);
+    }
+    items
+}
+
+/// Keywords that may precede the name of a port or a parameter
+fn is_type_keyword(name: &str) -> bool {
+    matches!(
+        name,
+        "wire"
+            | "reg"
+            | "tri"
+            | "tri0"
+            | "tri1"
+            | "triand"
+            | "trior"
+            | "trireg"
+            | "wand"
+            | "wor"
+            | "uwire"
+            | "supply0"
+            | "supply1"
+            | "signed"
+            | "unsigned"
+            | "real"
+            | "realtime"
+            | "shortreal"
+            | "logic"
+            | "bit"
+            | "var"
+            | "interconnect"
+            | "string"
+            | "parameter"
+            | "localparam"
+    )
+}
+
+/// The width of the integer types
+fn integer_type_width(name: &str) -> Option<Width> {
+    let msb = match name {
+        "byte" => 7,
+        "shortint" => 15,
+        "int" | "integer" => 31,
+        "longint" | "time" => 63,
+        _ => return None,
+    };
+    Some(Width::Vector(Some((msb, 0))))
+}
+
+/// Parse a port of a port list or of a port declaration.
+/// A port without direction and type inherits those of the previous port.
+fn parse_port(tokens: &[Token], previous: Option<&Port>) -> Option<Port> {
+    let mut direction = None;
+    let mut width = None;
+    let mut ident: Option<Identifier> = None;
+    let mut unpacked = false;
+    let mut idx = 0;
+
+    while let Some(token) = tokens.get(idx) {
+        match token.kind {
+            TokenKind::Identifier(ref name) => match name.as_str() {
+                "input" => direction = Some(Direction::Input),
+                "output" => direction = Some(Direction::Output),
+                "inout" => direction = Some(Direction::Inout),
+                name if is_type_keyword(name) => {}
+                name => {
+                    if let Some(integer_width) = integer_type_width(name) {
+                        width = Some(integer_width);
+                    } else {
+                        // The name follows a user-defined type such as an interface
+                        if ident.is_some() {
+                            width = Some(Width::Vector(None));
+                        }
+                        ident = identifier(token);
+                    }
+                }
+            },
+            TokenKind::Punctuation('[') => {
+                let end = tokens[idx..]
+                    .iter()
+                    .position(|token| token.is_punctuation(']'))
+                    .map_or(tokens.len(), |offset| idx + offset);
+                if ident.is_some() {
+                    unpacked = true;
+                } else {
+                    width = Some(match width {
+                        // A multi-dimensional vector
+                        Some(_) => Width::Vector(None),
+                        None => Width::Vector(literal_range(&tokens[idx + 1..end])),
+                    });
+                }
+                idx = end;
+            }
+            // Explicit ports `.name(expr)`, modports `bus_if.master` and packages `pkg::t`
+            TokenKind::Punctuation('.' | ':') => {}
+            // The expression of an explicit port or the default value
+            TokenKind::Punctuation('(' | '=') => break,
+            _ => return None,
+        }
+        idx += 1;
+    }
+
+    let (direction, width) = match (direction, width, previous) {
+        (None, None, Some(previous)) => (previous.direction, previous.width),
+        (direction, width, _) => (direction, width.unwrap_or(Width::Bit)),
+    };
+    Some(Port {
+        ident: ident?,
+        direction,
+        width: if unpacked { Width::Vector(None) } else { width },
+    })
+}
+
+/// The bounds of `msb:lsb` when both are integer literals
+fn literal_range(tokens: &[Token]) -> Option<(i64, i64)> {
+    match tokens {
+        [msb, colon, lsb] if colon.is_punctuation(':') => Some((integer(msb)?, integer(lsb)?)),
+        _ => None,
+    }
+}
+
+fn integer(token: &Token) -> Option<i64> {
+    match token.kind {
+        TokenKind::Number(ref value) => value.replace('_', "").parse().ok(),
+        _ => None,
+    }
+}
+
+fn parse_parameter(tokens: &[Token]) -> Option<Parameter> {
+    let assign = tokens.iter().position(|token| token.is_punctuation('='));
+    let name_tokens = &tokens[..assign.unwrap_or(tokens.len())];
+    // Local parameters cannot be overridden and type parameters have no VHDL-93 counterpart
+    if name_tokens
+        .iter()
+        .any(|token| token.is_identifier("localparam") || token.is_identifier("type"))
+    {
+        return None;
+    }
+    let ident = name_tokens.iter().rev().find_map(identifier)?;
+
+    let value = match assign.map(|idx| &tokens[idx + 1..]) {
+        Some([token]) => match token.kind {
+            TokenKind::String(ref value) => ParameterValue::String(value.clone()),
+            TokenKind::Number(ref value) if value.contains('.') => {
+                ParameterValue::Real(value.clone())
+            }
+            _ => integer(token).map_or(ParameterValue::Unknown, ParameterValue::Integer),
+        },
+        _ => ParameterValue::Unknown,
+    };
+    Some(Parameter { ident, value })
+}
+
+/// Writes VHDL text such that identifiers are placed at their position in the Verilog file
+#[derive(Default)]
+struct Emitter {
+    text: String,
+    pos: Position,
+}
+
+impl Emitter {
+    fn push(&mut self, text: &str) {
+        for chr in text.chars() {
+            if chr == '\n' {
+                self.pos = Position::new(self.pos.line + 1, 0);
+            } else {
+                self.pos.character += 1;
+            }
+        }
+        self.text.push_str(text);
+    }
+
+    /// Continue at `pos` if it is ahead, otherwise continue after a space
+    fn goto(&mut self, pos: Position) {
+        if pos.line > self.pos.line {
+            self.push(&"\n".repeat((pos.line - self.pos.line) as usize));
+        }
+        if pos.line == self.pos.line && pos.character >= self.pos.character {
+            self.push(&" ".repeat((pos.character - self.pos.character) as usize));
+        } else {
+            self.push(" ");
+        }
+    }
+
+    /// Write `prefix` followed by the identifier such that the identifier is at its position
+    fn identifier(&mut self, prefix: &str, ident: &Identifier, standard: VHDLStandard) {
+        let prefix_len = prefix.chars().count() as u32;
+        let start = Position::new(
+            ident.pos.line,
+            ident.pos.character.saturating_sub(prefix_len),
+        );
+        self.goto(start);
+        self.push(prefix);
+        self.push(&vhdl_identifier(&ident.name, standard));
+    }
+
+    fn entity(&mut self, module: &Module, standard: VHDLStandard) {
+        if !module.ports.is_empty() {
+            let context = "library ieee; use ieee.std_logic_1164.all;";
+            match module.ident.pos.line.checked_sub(1) {
+                Some(line) if line > self.pos.line || self.text.is_empty() => {
+                    self.goto(Position::new(line, 0));
+                }
+                _ if !self.text.is_empty() => self.push(" "),
+                _ => {}
+            }
+            self.push(context);
+        }
+        self.identifier("entity ", &module.ident, standard);
+        self.push(" is");
+
+        if !module.parameters.is_empty() {
+            self.push(" generic (");
+            for (i, parameter) in module.parameters.iter().enumerate() {
+                if i > 0 {
+                    self.push(";");
+                }
+                self.identifier("", &parameter.ident, standard);
+                self.push(&match parameter.value {
+                    ParameterValue::Integer(value) => format!(" : integer := {value}"),
+                    ParameterValue::Real(ref value) => format!(" : real := {value}"),
+                    ParameterValue::String(ref value) => {
+                        format!(" : string := \"{}\"", value.replace('"', "\"\""))
+                    }
+                    ParameterValue::Unknown => " : integer := 0".to_owned(),
+                });
+            }
+            self.push(");");
+        }
+
+        if !module.ports.is_empty() {
+            self.push(" port (");
+            for (i, port) in module.ports.iter().enumerate() {
+                if i > 0 {
+                    self.push(";");
+                }
+                self.identifier("", &port.ident, standard);
+                let mode = match port.direction {
+                    Some(Direction::Input) | None => "in",
+                    Some(Direction::Output) => "out",
+                    Some(Direction::Inout) => "inout",
+                };
+                let typ = match port.width {
+                    Width::Bit => "std_logic".to_owned(),
+                    Width::Vector(Some((left, right))) if left >= right => {
+                        format!("std_logic_vector({left} downto {right})")
+                    }
+                    Width::Vector(Some((left, right))) => {
+                        format!("std_logic_vector({left} to {right})")
+                    }
+                    Width::Vector(None) => "std_logic_vector".to_owned(),
+                };
+                self.push(&format!(" : {mode} {typ}"));
+            }
+            self.push(");");
+        }
+
+        if let Some(end) = module.end {
+            self.goto(end);
+        } else {
+            self.push(" ");
+        }
+        self.push("end entity;");
+    }
+}
+
+/// Verilog names that are VHDL keywords become extended identifiers
+fn vhdl_identifier(name: &str, standard: VHDLStandard) -> String {
+    let lowercase = name.to_ascii_lowercase();
+    let is_basic = name.starts_with(|chr: char| chr.is_ascii_alphabetic())
+        && !name.contains("__")
+        && !name.ends_with('_')
+        && name
+            .chars()
+            .all(|chr| chr.is_ascii_alphanumeric() || chr == '_')
+        && !standard
+            .keywords()
+            .iter()
+            .any(|kind| kind.as_str() == lowercase);
+    if is_basic {
+        name.to_owned()
+    } else {
+        format!("\\{}\\", name.replace('\\', "\\\\"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{check_no_diagnostics, LibraryBuilder};
+
+    fn module(text: &str) -> Module {
+        parse_modules(&tokenize(text)).remove(0)
+    }
+
+    #[test]
+    fn scans_ansi_header() {
+        let module = module(
+            "\
+// A FIFO
+module fifo #(
+  parameter WIDTH = 8,
+  parameter NAME = \"rx\",
+  parameter DEPTH = 2 ** 4
+) (
+  input wire clk,
+  input [WIDTH-1:0] din,
+  output reg [7:0] dout, level,
+  inout ext
+);
+  function f;
+    input a;
+  endfunction
+endmodule
+",
+        );
+        assert_eq!(module.ident.name, "fifo");
+        assert_eq!(module.ident.pos, Position::new(1, 7));
+        assert_eq!(
+            module
+                .parameters
+                .iter()
+                .map(|parameter| (parameter.ident.name.as_str(), parameter.value.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("WIDTH", ParameterValue::Integer(8)),
+                ("NAME", ParameterValue::String("rx".to_owned())),
+                ("DEPTH", ParameterValue::Unknown),
+            ]
+        );
+        assert_eq!(
+            module
+                .ports
+                .iter()
+                .map(|port| (port.ident.name.as_str(), port.direction, port.width))
+                .collect::<Vec<_>>(),
+            vec![
+                ("clk", Some(Direction::Input), Width::Bit),
+                ("din", Some(Direction::Input), Width::Vector(None)),
+                ("dout", Some(Direction::Output), Width::Vector(Some((7, 0)))),
+                (
+                    "level",
+                    Some(Direction::Output),
+                    Width::Vector(Some((7, 0)))
+                ),
+                ("ext", Some(Direction::Inout), Width::Bit),
+            ]
+        );
+        assert_eq!(module.end, Some(Position::new(14, 0)));
+    }
+
+    #[test]
+    fn scans_non_ansi_header() {
+        let module = module(
+            "\
+module counter (clk, q);
+  parameter MAX = 10;
+  localparam HALF = MAX / 2;
+  input clk;
+  output [3:0] q;
+  reg [3:0] q;
+endmodule
+",
+        );
+        assert_eq!(
+            module.parameters,
+            vec![Parameter {
+                ident: Identifier {
+                    name: "MAX".to_owned(),
+                    pos: Position::new(1, 12),
+                },
+                value: ParameterValue::Integer(10),
+            }]
+        );
+        assert_eq!(
+            module.ports,
+            vec![
+                Port {
+                    ident: Identifier {
+                        name: "clk".to_owned(),
+                        pos: Position::new(0, 16),
+                    },
+                    direction: Some(Direction::Input),
+                    width: Width::Bit,
+                },
+                Port {
+                    ident: Identifier {
+                        name: "q".to_owned(),
+                        pos: Position::new(0, 21),
+                    },
+                    direction: Some(Direction::Output),
+                    width: Width::Vector(Some((3, 0))),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn scans_systemverilog_header() {
+        let modules = parse_modules(&tokenize(
+            "\
+package types_pkg;
+  class item;
+    virtual interface bus_if vif;
+  endclass
+endpackage
+
+interface bus_if (input logic clk);
+  logic valid;
+  modport master (output valid);
+endinterface
+
+module automatic core import types_pkg::*; #(
+  parameter int unsigned WIDTH = 16,
+  parameter type T = logic,
+  parameter real GAIN = 1.5
+) (
+  input logic clk,
+  input logic [WIDTH-1:0] din,
+  output int count,
+  bus_if.master bus,
+  output logic [3:0] mem [4]
+);
+endmodule : core
+",
+        ));
+        assert_eq!(
+            modules
+                .iter()
+                .map(|module| module.ident.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["bus_if", "core"]
+        );
+        assert_eq!(
+            modules[0]
+                .ports
+                .iter()
+                .map(|port| (port.ident.name.as_str(), port.direction, port.width))
+                .collect::<Vec<_>>(),
+            vec![("clk", Some(Direction::Input), Width::Bit)]
+        );
+
+        let core = &modules[1];
+        assert_eq!(
+            core.parameters
+                .iter()
+                .map(|parameter| (parameter.ident.name.as_str(), parameter.value.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("WIDTH", ParameterValue::Integer(16)),
+                ("GAIN", ParameterValue::Real("1.5".to_owned())),
+            ]
+        );
+        assert_eq!(
+            core.ports
+                .iter()
+                .map(|port| (port.ident.name.as_str(), port.direction, port.width))
+                .collect::<Vec<_>>(),
+            vec![
+                ("clk", Some(Direction::Input), Width::Bit),
+                ("din", Some(Direction::Input), Width::Vector(None)),
+                (
+                    "count",
+                    Some(Direction::Output),
+                    Width::Vector(Some((31, 0)))
+                ),
+                ("bus", None, Width::Vector(None)),
+                ("mem", Some(Direction::Output), Width::Vector(None)),
+            ]
+        );
+        assert_eq!(core.end, Some(Position::new(22, 0)));
+    }
+
+    #[test]
+    fn entity_names_keep_their_position() {
+        let text = black_box_entities(
+            "\
+`timescale 1ns/1ps
+
+module adder (
+  input [7:0] a,
+  input [7:0] b,
+  output [8:0] sum,
+  output out
+);
+endmodule
+",
+            VHDLStandard::VHDL2008,
+        );
+        assert_eq!(
+            text,
+            [
+                "",
+                "library ieee; use ieee.std_logic_1164.all;",
+                "entity adder is port (",
+                "              a : in std_logic_vector(7 downto 0);",
+                "              b : in std_logic_vector(7 downto 0);",
+                "               sum : out std_logic_vector(8 downto 0);",
+                "         \\out\\ : out std_logic);",
+                "",
+                "end entity;",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn instantiation_of_verilog_module_is_checked() {
+        let mut builder = LibraryBuilder::new();
+        builder.code(
+            "libname",
+            &black_box_entities(
+                "\
+module dff #(parameter INIT = 0) (input clk, input d, output reg q);
+endmodule
+",
+                VHDLStandard::VHDL2008,
+            ),
+        );
+        builder.code(
+            "libname",
+            "\
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity top is
+end entity;
+
+architecture a of top is
+  signal clk, d, q : std_logic;
+begin
+  inst: entity work.dff
+    generic map (INIT => 1)
+    port map (clk => clk, d => d, q => q);
+end architecture;
+",
+        );
+        builder.add_std_logic_1164();
+        let (_, diagnostics) = builder.get_analyzed_root();
+        check_no_diagnostics(&diagnostics);
+    }
+}
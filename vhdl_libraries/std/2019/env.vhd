@@ -0,0 +1,139 @@
+-- Package env as defined by IEEE 1076-2019
+
+use std.textio.all;
+
+package env is
+  procedure stop(status : integer);
+  procedure stop;
+
+  procedure finish(status : integer);
+  procedure finish;
+
+  function resolution_limit return delay_length;
+
+  -- Date and time
+  type dayofweek is (sunday, monday, tuesday, wednesday, thursday, friday, saturday);
+
+  type time_record is record
+    microsecond : integer range 0 to 999_999;
+    second      : integer range 0 to 60;
+    minute      : integer range 0 to 59;
+    hour        : integer range 0 to 23;
+    day         : integer range 1 to 31;
+    month       : integer range 0 to 11;
+    year        : integer range 1 to 4095;
+    weekday     : dayofweek;
+    dayofyear   : integer range 0 to 365;
+  end record time_record;
+
+  impure function localtime return time_record;
+  impure function gmtime return time_record;
+  impure function epoch return real;
+
+  function localtime(timer : real) return time_record;
+  function gmtime(timer : real) return time_record;
+  function epoch(trec : time_record) return real;
+  function localtime(trec : time_record) return time_record;
+  function gmtime(trec : time_record) return time_record;
+
+  function "+"(trec : time_record; delta : real) return time_record;
+  function "+"(delta : real; trec : time_record) return time_record;
+  function "-"(trec : time_record; delta : real) return time_record;
+  function "-"(delta : real; trec : time_record) return time_record;
+  function "-"(trec1, trec2 : time_record) return real;
+
+  function time_to_seconds(time_in : time) return real;
+  function seconds_to_time(real_in : real) return time;
+  function to_string(trec : time_record; frac_digits : integer range 0 to 6 := 0) return string;
+
+  -- Environment variables
+  impure function getenv(name : string) return string;
+  impure function getenv(name : string) return line;
+
+  -- Tool and language versions
+  function vhdl_version return string;
+  function tool_type return string;
+  function tool_vendor return string;
+  function tool_name return string;
+  function tool_edition return string;
+  function tool_version return string;
+
+  -- Directories and files
+  type directory_items is access line_vector;
+
+  type directory is record
+    name  : line;
+    items : directory_items;
+  end record directory;
+
+  type dir_open_status is (status_ok, status_not_found, status_no_directory, status_access_denied, status_error);
+  type dir_create_status is (status_ok, status_item_exists, status_access_denied, status_error);
+  type dir_delete_status is (status_ok, status_no_directory, status_not_empty, status_access_denied, status_error);
+  type file_delete_status is (status_ok, status_no_file, status_access_denied, status_error);
+
+  procedure dir_open(dir : out directory; path : in string; status : out dir_open_status);
+  procedure dir_close(variable dir : inout directory);
+
+  impure function dir_itemexists(path : in string) return boolean;
+  impure function dir_itemisdir(path : in string) return boolean;
+  impure function dir_itemisfile(path : in string) return boolean;
+
+  procedure dir_workingdir(path : in string; status : out dir_open_status);
+  impure function dir_workingdir(path : in string) return dir_open_status;
+  impure function dir_workingdir return string;
+
+  procedure dir_createdir(path : in string; status : out dir_create_status);
+  procedure dir_createdir(path : in string; parents : in boolean; status : out dir_create_status);
+  impure function dir_createdir(path : in string; parents : in boolean := false) return dir_create_status;
+
+  procedure dir_deletedir(path : in string; status : out dir_delete_status);
+  procedure dir_deletedir(path : in string; recursive : in boolean; status : out dir_delete_status);
+  impure function dir_deletedir(path : in string; recursive : in boolean := false) return dir_delete_status;
+
+  procedure dir_deletefile(path : in string; status : out file_delete_status);
+  impure function dir_deletefile(path : in string) return file_delete_status;
+
+  -- The value is tool dependent, a deferred constant in the standard
+  constant dir_separator : string := "/";
+
+  -- Call path
+  type call_path_element is record
+    name      : line;
+    file_name : line;
+    file_path : line;
+    file_line : positive;
+  end record call_path_element;
+
+  type call_path_vector is array (natural range <>) of call_path_element;
+  type call_path_vector_ptr is access call_path_vector;
+
+  impure function get_call_path return call_path_vector_ptr;
+  impure function file_name return line;
+  impure function file_name return string;
+  impure function file_path return line;
+  impure function file_path return string;
+  impure function file_line return positive;
+  impure function file_line return string;
+
+  -- Assertions
+  impure function IsVhdlAssertFailed return boolean;
+  impure function IsVhdlAssertFailed(level : severity_level) return boolean;
+  impure function GetVhdlAssertCount return natural;
+  impure function GetVhdlAssertCount(level : severity_level) return natural;
+  procedure ClearVhdlAssert;
+  procedure SetVhdlAssertEnable(enable : boolean := true);
+  procedure SetVhdlAssertEnable(level : severity_level := note; enable : boolean := true);
+  impure function GetVhdlAssertEnable(level : severity_level := note) return boolean;
+  procedure SetVhdlAssertFormat(level : severity_level; format : string);
+  procedure SetVhdlAssertFormat(level : severity_level; format : string; valid : out boolean);
+  impure function GetVhdlAssertFormat(level : severity_level) return string;
+  procedure SetVhdlReadSeverity(level : severity_level := error);
+  impure function GetVhdlReadSeverity return severity_level;
+
+  impure function PslAssertFailed return boolean;
+  impure function PslIsCovered return boolean;
+  procedure SetPslCoverAssert(enable : boolean := true);
+  impure function GetPslCoverAssert return boolean;
+  impure function PslIsAssertCovered return boolean;
+  procedure ClearPslState;
+end package;
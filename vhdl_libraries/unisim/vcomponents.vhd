@@ -0,0 +1,481 @@
+-- Declaration-only version of the component declarations of the Xilinx UNISIM library.
+-- Only the interface of the commonly instantiated primitives is declared,
+-- the simulation models are part of the Vivado installation.
+
+library ieee;
+use ieee.std_logic_1164.all;
+
+package vcomponents is
+
+  ----------------------------------------------------------------------------
+  -- Clock buffers
+  ----------------------------------------------------------------------------
+
+  component BUFG
+    port (
+      O : out std_ulogic;
+      I : in std_ulogic
+    );
+  end component;
+
+  component BUFGCE
+    generic (
+      CE_TYPE : string := "SYNC";
+      IS_CE_INVERTED : bit := '0';
+      IS_I_INVERTED : bit := '0'
+    );
+    port (
+      O : out std_ulogic;
+      CE : in std_ulogic;
+      I : in std_ulogic
+    );
+  end component;
+
+  component BUFGMUX
+    generic (
+      CLK_SEL_TYPE : string := "SYNC"
+    );
+    port (
+      O : out std_ulogic;
+      I0 : in std_ulogic;
+      I1 : in std_ulogic;
+      S : in std_ulogic
+    );
+  end component;
+
+  component BUFH
+    port (
+      O : out std_ulogic;
+      I : in std_ulogic
+    );
+  end component;
+
+  component BUFIO
+    port (
+      O : out std_ulogic;
+      I : in std_ulogic
+    );
+  end component;
+
+  component BUFR
+    generic (
+      BUFR_DIVIDE : string := "BYPASS";
+      SIM_DEVICE : string := "7SERIES"
+    );
+    port (
+      O : out std_ulogic;
+      CE : in std_ulogic;
+      CLR : in std_ulogic;
+      I : in std_ulogic
+    );
+  end component;
+
+  ----------------------------------------------------------------------------
+  -- I/O buffers
+  ----------------------------------------------------------------------------
+
+  component IBUF
+    generic (
+      CAPACITANCE : string := "DONT_CARE";
+      IBUF_DELAY_VALUE : string := "0";
+      IBUF_LOW_PWR : boolean := true;
+      IFD_DELAY_VALUE : string := "AUTO";
+      IOSTANDARD : string := "DEFAULT"
+    );
+    port (
+      O : out std_ulogic;
+      I : in std_ulogic
+    );
+  end component;
+
+  component IBUFG
+    generic (
+      CAPACITANCE : string := "DONT_CARE";
+      IBUF_DELAY_VALUE : string := "0";
+      IBUF_LOW_PWR : boolean := true;
+      IOSTANDARD : string := "DEFAULT"
+    );
+    port (
+      O : out std_ulogic;
+      I : in std_ulogic
+    );
+  end component;
+
+  component IBUFDS
+    generic (
+      CAPACITANCE : string := "DONT_CARE";
+      DIFF_TERM : boolean := false;
+      DQS_BIAS : string := "FALSE";
+      IBUF_DELAY_VALUE : string := "0";
+      IBUF_LOW_PWR : boolean := true;
+      IFD_DELAY_VALUE : string := "AUTO";
+      IOSTANDARD : string := "DEFAULT"
+    );
+    port (
+      O : out std_ulogic;
+      I : in std_ulogic;
+      IB : in std_ulogic
+    );
+  end component;
+
+  component IBUFGDS
+    generic (
+      CAPACITANCE : string := "DONT_CARE";
+      DIFF_TERM : boolean := false;
+      IBUF_DELAY_VALUE : string := "0";
+      IBUF_LOW_PWR : boolean := true;
+      IOSTANDARD : string := "DEFAULT"
+    );
+    port (
+      O : out std_ulogic;
+      I : in std_ulogic;
+      IB : in std_ulogic
+    );
+  end component;
+
+  component IBUFDS_GTE2
+    generic (
+      CLKCM_CFG : boolean := true;
+      CLKRCV_TRST : boolean := true;
+      CLKSWING_CFG : bit_vector(1 downto 0) := "11"
+    );
+    port (
+      O : out std_ulogic;
+      ODIV2 : out std_ulogic;
+      CEB : in std_ulogic;
+      I : in std_ulogic;
+      IB : in std_ulogic
+    );
+  end component;
+
+  component OBUF
+    generic (
+      CAPACITANCE : string := "DONT_CARE";
+      DRIVE : integer := 12;
+      IOSTANDARD : string := "DEFAULT";
+      SLEW : string := "SLOW"
+    );
+    port (
+      O : out std_ulogic;
+      I : in std_ulogic
+    );
+  end component;
+
+  component OBUFT
+    generic (
+      CAPACITANCE : string := "DONT_CARE";
+      DRIVE : integer := 12;
+      IOSTANDARD : string := "DEFAULT";
+      SLEW : string := "SLOW"
+    );
+    port (
+      O : out std_ulogic;
+      I : in std_ulogic;
+      T : in std_ulogic
+    );
+  end component;
+
+  component OBUFDS
+    generic (
+      CAPACITANCE : string := "DONT_CARE";
+      IOSTANDARD : string := "DEFAULT";
+      SLEW : string := "SLOW"
+    );
+    port (
+      O : out std_ulogic;
+      OB : out std_ulogic;
+      I : in std_ulogic
+    );
+  end component;
+
+  component IOBUF
+    generic (
+      CAPACITANCE : string := "DONT_CARE";
+      DRIVE : integer := 12;
+      IBUF_DELAY_VALUE : string := "0";
+      IBUF_LOW_PWR : boolean := true;
+      IFD_DELAY_VALUE : string := "AUTO";
+      IOSTANDARD : string := "DEFAULT";
+      SLEW : string := "SLOW"
+    );
+    port (
+      O : out std_ulogic;
+      IO : inout std_ulogic;
+      I : in std_ulogic;
+      T : in std_ulogic
+    );
+  end component;
+
+  ----------------------------------------------------------------------------
+  -- Registers and shift registers
+  ----------------------------------------------------------------------------
+
+  component FDCE
+    generic (
+      INIT : bit := '0';
+      IS_CLR_INVERTED : bit := '0';
+      IS_C_INVERTED : bit := '0';
+      IS_D_INVERTED : bit := '0'
+    );
+    port (
+      Q : out std_ulogic;
+      C : in std_ulogic;
+      CE : in std_ulogic;
+      CLR : in std_ulogic;
+      D : in std_ulogic
+    );
+  end component;
+
+  component FDPE
+    generic (
+      INIT : bit := '1';
+      IS_C_INVERTED : bit := '0';
+      IS_D_INVERTED : bit := '0';
+      IS_PRE_INVERTED : bit := '0'
+    );
+    port (
+      Q : out std_ulogic;
+      C : in std_ulogic;
+      CE : in std_ulogic;
+      D : in std_ulogic;
+      PRE : in std_ulogic
+    );
+  end component;
+
+  component FDRE
+    generic (
+      INIT : bit := '0';
+      IS_C_INVERTED : bit := '0';
+      IS_D_INVERTED : bit := '0';
+      IS_R_INVERTED : bit := '0'
+    );
+    port (
+      Q : out std_ulogic;
+      C : in std_ulogic;
+      CE : in std_ulogic;
+      D : in std_ulogic;
+      R : in std_ulogic
+    );
+  end component;
+
+  component FDSE
+    generic (
+      INIT : bit := '1';
+      IS_C_INVERTED : bit := '0';
+      IS_D_INVERTED : bit := '0';
+      IS_S_INVERTED : bit := '0'
+    );
+    port (
+      Q : out std_ulogic;
+      C : in std_ulogic;
+      CE : in std_ulogic;
+      D : in std_ulogic;
+      S : in std_ulogic
+    );
+  end component;
+
+  component SRL16E
+    generic (
+      INIT : bit_vector(15 downto 0) := X"0000";
+      IS_CLK_INVERTED : bit := '0'
+    );
+    port (
+      Q : out std_ulogic;
+      A0 : in std_ulogic;
+      A1 : in std_ulogic;
+      A2 : in std_ulogic;
+      A3 : in std_ulogic;
+      CE : in std_ulogic;
+      CLK : in std_ulogic;
+      D : in std_ulogic
+    );
+  end component;
+
+  component SRLC32E
+    generic (
+      INIT : bit_vector(31 downto 0) := X"00000000";
+      IS_CLK_INVERTED : bit := '0'
+    );
+    port (
+      Q : out std_ulogic;
+      Q31 : out std_ulogic;
+      A : in std_logic_vector(4 downto 0);
+      CE : in std_ulogic;
+      CLK : in std_ulogic;
+      D : in std_ulogic
+    );
+  end component;
+
+  ----------------------------------------------------------------------------
+  -- Double data rate registers
+  ----------------------------------------------------------------------------
+
+  component IDDR
+    generic (
+      DDR_CLK_EDGE : string := "OPPOSITE_EDGE";
+      INIT_Q1 : bit := '0';
+      INIT_Q2 : bit := '0';
+      IS_C_INVERTED : bit := '0';
+      IS_D_INVERTED : bit := '0';
+      SRTYPE : string := "SYNC"
+    );
+    port (
+      Q1 : out std_ulogic;
+      Q2 : out std_ulogic;
+      C : in std_ulogic;
+      CE : in std_ulogic;
+      D : in std_ulogic;
+      R : in std_ulogic := 'L';
+      S : in std_ulogic := 'L'
+    );
+  end component;
+
+  component ODDR
+    generic (
+      DDR_CLK_EDGE : string := "OPPOSITE_EDGE";
+      INIT : bit := '0';
+      IS_C_INVERTED : bit := '0';
+      IS_D1_INVERTED : bit := '0';
+      IS_D2_INVERTED : bit := '0';
+      SRTYPE : string := "SYNC"
+    );
+    port (
+      Q : out std_ulogic;
+      C : in std_ulogic;
+      CE : in std_ulogic;
+      D1 : in std_ulogic;
+      D2 : in std_ulogic;
+      R : in std_ulogic := 'L';
+      S : in std_ulogic := 'L'
+    );
+  end component;
+
+  ----------------------------------------------------------------------------
+  -- Clock management
+  ----------------------------------------------------------------------------
+
+  component MMCME2_BASE
+    generic (
+      BANDWIDTH : string := "OPTIMIZED";
+      CLKFBOUT_MULT_F : real := 5.0;
+      CLKFBOUT_PHASE : real := 0.0;
+      CLKIN1_PERIOD : real := 0.0;
+      CLKOUT0_DIVIDE_F : real := 1.0;
+      CLKOUT0_DUTY_CYCLE : real := 0.5;
+      CLKOUT0_PHASE : real := 0.0;
+      CLKOUT1_DIVIDE : integer := 1;
+      CLKOUT1_DUTY_CYCLE : real := 0.5;
+      CLKOUT1_PHASE : real := 0.0;
+      CLKOUT2_DIVIDE : integer := 1;
+      CLKOUT2_DUTY_CYCLE : real := 0.5;
+      CLKOUT2_PHASE : real := 0.0;
+      CLKOUT3_DIVIDE : integer := 1;
+      CLKOUT3_DUTY_CYCLE : real := 0.5;
+      CLKOUT3_PHASE : real := 0.0;
+      CLKOUT4_CASCADE : boolean := false;
+      CLKOUT4_DIVIDE : integer := 1;
+      CLKOUT4_DUTY_CYCLE : real := 0.5;
+      CLKOUT4_PHASE : real := 0.0;
+      CLKOUT5_DIVIDE : integer := 1;
+      CLKOUT5_DUTY_CYCLE : real := 0.5;
+      CLKOUT5_PHASE : real := 0.0;
+      CLKOUT6_DIVIDE : integer := 1;
+      CLKOUT6_DUTY_CYCLE : real := 0.5;
+      CLKOUT6_PHASE : real := 0.0;
+      DIVCLK_DIVIDE : integer := 1;
+      REF_JITTER1 : real := 0.0;
+      STARTUP_WAIT : boolean := false
+    );
+    port (
+      CLKFBOUT : out std_ulogic;
+      CLKFBOUTB : out std_ulogic;
+      CLKOUT0 : out std_ulogic;
+      CLKOUT0B : out std_ulogic;
+      CLKOUT1 : out std_ulogic;
+      CLKOUT1B : out std_ulogic;
+      CLKOUT2 : out std_ulogic;
+      CLKOUT2B : out std_ulogic;
+      CLKOUT3 : out std_ulogic;
+      CLKOUT3B : out std_ulogic;
+      CLKOUT4 : out std_ulogic;
+      CLKOUT5 : out std_ulogic;
+      CLKOUT6 : out std_ulogic;
+      LOCKED : out std_ulogic;
+      CLKFBIN : in std_ulogic;
+      CLKIN1 : in std_ulogic;
+      PWRDWN : in std_ulogic;
+      RST : in std_ulogic
+    );
+  end component;
+
+  component PLLE2_BASE
+    generic (
+      BANDWIDTH : string := "OPTIMIZED";
+      CLKFBOUT_MULT : integer := 5;
+      CLKFBOUT_PHASE : real := 0.0;
+      CLKIN1_PERIOD : real := 0.0;
+      CLKOUT0_DIVIDE : integer := 1;
+      CLKOUT0_DUTY_CYCLE : real := 0.5;
+      CLKOUT0_PHASE : real := 0.0;
+      CLKOUT1_DIVIDE : integer := 1;
+      CLKOUT1_DUTY_CYCLE : real := 0.5;
+      CLKOUT1_PHASE : real := 0.0;
+      CLKOUT2_DIVIDE : integer := 1;
+      CLKOUT2_DUTY_CYCLE : real := 0.5;
+      CLKOUT2_PHASE : real := 0.0;
+      CLKOUT3_DIVIDE : integer := 1;
+      CLKOUT3_DUTY_CYCLE : real := 0.5;
+      CLKOUT3_PHASE : real := 0.0;
+      CLKOUT4_DIVIDE : integer := 1;
+      CLKOUT4_DUTY_CYCLE : real := 0.5;
+      CLKOUT4_PHASE : real := 0.0;
+      CLKOUT5_DIVIDE : integer := 1;
+      CLKOUT5_DUTY_CYCLE : real := 0.5;
+      CLKOUT5_PHASE : real := 0.0;
+      DIVCLK_DIVIDE : integer := 1;
+      REF_JITTER1 : real := 0.0;
+      STARTUP_WAIT : string := "FALSE"
+    );
+    port (
+      CLKFBOUT : out std_ulogic;
+      CLKOUT0 : out std_ulogic;
+      CLKOUT1 : out std_ulogic;
+      CLKOUT2 : out std_ulogic;
+      CLKOUT3 : out std_ulogic;
+      CLKOUT4 : out std_ulogic;
+      CLKOUT5 : out std_ulogic;
+      LOCKED : out std_ulogic;
+      CLKFBIN : in std_ulogic;
+      CLKIN1 : in std_ulogic;
+      PWRDWN : in std_ulogic;
+      RST : in std_ulogic
+    );
+  end component;
+
+  ----------------------------------------------------------------------------
+  -- Configuration
+  ----------------------------------------------------------------------------
+
+  component STARTUPE2
+    generic (
+      PROG_USR : string := "FALSE";
+      SIM_CCLK_FREQ : real := 0.0
+    );
+    port (
+      CFGCLK : out std_ulogic;
+      CFGMCLK : out std_ulogic;
+      EOS : out std_ulogic;
+      PREQ : out std_ulogic;
+      CLK : in std_ulogic;
+      GSR : in std_ulogic;
+      GTS : in std_ulogic;
+      KEYCLEARB : in std_ulogic;
+      PACK : in std_ulogic;
+      USRCCLKO : in std_ulogic;
+      USRCCLKTS : in std_ulogic;
+      USRDONEO : in std_ulogic;
+      USRDONETS : in std_ulogic
+    );
+  end component;
+
+end package;
@@ -9,7 +9,10 @@
 extern crate log;
 
 mod rpc_channel;
+mod socket_server;
 mod stdio_server;
 mod vhdl_server;
+mod websocket;
+pub use crate::socket_server::{listen, Transport};
 pub use crate::stdio_server::start;
 pub use crate::vhdl_server::VHDLServerSettings;
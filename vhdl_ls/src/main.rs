@@ -5,7 +5,7 @@
 // Copyright (c) 2018, Olof Kraigher olof.kraigher@gmail.com
 
 use clap::Parser;
-use vhdl_ls::VHDLServerSettings;
+use vhdl_ls::{Transport, VHDLServerSettings};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -18,6 +18,19 @@ struct Args {
     /// This will silence all window/showMessage and only use window/logMessage
     #[arg(long, default_value_t = false)]
     silent: bool,
+
+    /// Listen for clients on this TCP port instead of communicating over stdio.
+    /// The clients are served one after another
+    #[arg(long)]
+    port: Option<u16>,
+
+    /// The address to listen on, use 0.0.0.0 to accept clients of other machines
+    #[arg(long, default_value = "127.0.0.1", requires = "port")]
+    host: String,
+
+    /// Accept WebSocket connections on the port, e.g. of browser-based editors
+    #[arg(long, default_value_t = false, requires = "port")]
+    websocket: bool,
 }
 
 fn main() {
@@ -25,9 +38,24 @@ fn main() {
 
     env_logger::init();
     log::info!("Starting language server");
-    vhdl_ls::start(VHDLServerSettings {
+    let settings = VHDLServerSettings {
         no_lint: args.no_lint,
         silent: args.silent,
         ..Default::default()
-    });
+    };
+    match args.port {
+        Some(port) => {
+            let transport = if args.websocket {
+                Transport::WebSocket
+            } else {
+                Transport::Tcp
+            };
+            if let Err(err) = vhdl_ls::listen(settings, &format!("{}:{port}", args.host), transport)
+            {
+                eprintln!("Failed to listen on port {port}: {err}");
+                std::process::exit(1);
+            }
+        }
+        None => vhdl_ls::start(settings),
+    }
 }
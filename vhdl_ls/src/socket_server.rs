@@ -0,0 +1,128 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! This module handles serving clients that connect over TCP or WebSocket,
+//! such as editors in remote containers or in the browser.
+//! The clients are served one at a time, each by a new `VHDLServer`.
+
+use lsp_server::{Connection, Message};
+use std::io::{self, BufReader};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::stdio_server::serve;
+use crate::vhdl_server::VHDLServerSettings;
+use crate::websocket;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Transport {
+    /// The base protocol of LSP, i.e. messages with a `Content-Length` header
+    Tcp,
+    /// One JSON-RPC message per WebSocket message
+    WebSocket,
+}
+
+/// Listen on the address and serve the clients that connect until the process is terminated.
+pub fn listen(settings: VHDLServerSettings, address: &str, transport: Transport) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    info!("Listening on {}", listener.local_addr()?);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!("Failed to accept a connection: {err}");
+                continue;
+            }
+        };
+        let peer = stream
+            .peer_addr()
+            .map(|addr| addr.to_string())
+            .unwrap_or_default();
+        info!("Client {peer} connected");
+        match serve_stream(stream, settings.clone(), transport) {
+            Ok(()) => info!("Client {peer} disconnected"),
+            Err(err) => warn!("Connection to client {peer} failed: {err}"),
+        }
+    }
+    Ok(())
+}
+
+/// Serve the client of the stream until it disconnects or exits
+fn serve_stream(
+    stream: TcpStream,
+    settings: VHDLServerSettings,
+    transport: Transport,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream.try_clone()?;
+    if transport == Transport::WebSocket {
+        websocket::handshake(&mut reader, &mut writer)?;
+    }
+    // The reader answers pings, so frames are written while holding the lock
+    let writer = Arc::new(Mutex::new(writer));
+    let pong_writer = writer.clone();
+
+    let (connection, client) = Connection::memory();
+    let Connection {
+        sender: to_server,
+        receiver: from_server,
+    } = client;
+
+    let reader_thread = thread::spawn(move || -> io::Result<()> {
+        loop {
+            let message = match transport {
+                Transport::Tcp => Message::read(&mut reader)?,
+                Transport::WebSocket => websocket::read_message(&mut reader, &pong_writer)?,
+            };
+            // The client has closed the connection
+            let Some(message) = message else {
+                return Ok(());
+            };
+            // The server has finished the session
+            if to_server.send(message).is_err() {
+                return Ok(());
+            }
+        }
+    });
+    let writer_thread = thread::spawn(move || -> io::Result<()> {
+        let mut result = Ok(());
+        // Messages are received until the server has finished, even when the client is gone
+        while let Ok(message) = from_server.recv() {
+            if result.is_err() {
+                continue;
+            }
+            let mut writer = writer.lock().unwrap();
+            result = match transport {
+                Transport::Tcp => message.write(&mut *writer),
+                Transport::WebSocket => websocket::write_message(&mut *writer, message),
+            };
+            if result.is_err() {
+                // Let the reader and thereby the server finish
+                let _ = writer.shutdown(Shutdown::Both);
+            }
+        }
+        result
+    });
+
+    let result = serve(connection, settings, true).map_err(|err| io::Error::other(err.to_string()));
+    // The writer finishes once it has sent the remaining messages of the server
+    let writer_result = writer_thread.join().unwrap_or(Ok(()));
+    // The reader waits for the client, which may keep the connection open after the exit notification
+    let _ = stream.shutdown(Shutdown::Both);
+    let reader_result = reader_thread.join().unwrap_or(Ok(()));
+    result.and(writer_result).and(reader_result.or_else(|err| {
+        // Reading fails when the session is closed by shutting down the connection
+        if matches!(
+            err.kind(),
+            io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset
+        ) {
+            Ok(())
+        } else {
+            Err(err)
+        }
+    }))
+}
@@ -8,26 +8,38 @@
 //! It also contains the main event loop for handling incoming messages from the LSP client and
 //! dispatching them to the appropriate server methods.
 
-use lsp_server::{Connection, ExtractError, Request, RequestId};
+use lsp_server::{Connection, ExtractError, ProtocolError, Request, RequestId};
 use lsp_types::{notification, request, InitializeParams};
 use serde_json::Value;
 
 use std::{cell::RefCell, rc::Rc};
 
 use crate::rpc_channel::{RpcChannel, SharedRpcChannel};
-use crate::vhdl_server::VHDLServer;
 use crate::vhdl_server::VHDLServerSettings;
+use crate::vhdl_server::{IncomingInstances, OutgoingInstances, ReferencesWithKind, VHDLServer};
 
 /// Set up the IO channel for `stdio` and start the VHDL language server.
 pub fn start(settings: VHDLServerSettings) {
     let (connection, io_threads) = Connection::stdio();
-    let connection_rpc = Rc::new(ConnectionRpcChannel::new(connection));
+    serve(connection, settings, false).unwrap();
+
+    io_threads.join().unwrap();
+}
+
+/// Serve a client on the connection until the connection is closed.
+/// With `keep_running`, the exit notification of the client closes the session
+/// instead of exiting the process, so that the next client can be served.
+pub(crate) fn serve(
+    connection: Connection,
+    settings: VHDLServerSettings,
+    keep_running: bool,
+) -> Result<(), ProtocolError> {
+    let connection_rpc = Rc::new(ConnectionRpcChannel::new(connection, keep_running));
     let rpc = SharedRpcChannel::new(connection_rpc.clone());
     let mut server = VHDLServer::new_settings(rpc, settings);
-    connection_rpc.handle_initialization(&mut server);
+    connection_rpc.handle_initialization(&mut server)?;
     connection_rpc.main_event_loop(server);
-
-    io_threads.join().unwrap();
+    Ok(())
 }
 
 /// Wrapper for Connection implementing RpcChannel + Clone
@@ -36,6 +48,8 @@ pub fn start(settings: VHDLServerSettings) {
 struct ConnectionRpcChannel {
     connection: Rc<Connection>,
     next_outgoing_request_id: Rc<RefCell<i32>>,
+    // End the session instead of the process on the exit notification
+    keep_running: bool,
 }
 
 impl RpcChannel for ConnectionRpcChannel {
@@ -57,33 +71,46 @@ impl RpcChannel for ConnectionRpcChannel {
 }
 
 impl ConnectionRpcChannel {
-    fn new(connection: Connection) -> Self {
+    fn new(connection: Connection, keep_running: bool) -> Self {
         Self {
             connection: Rc::new(connection),
             next_outgoing_request_id: Rc::new(RefCell::new(0)),
+            keep_running,
         }
     }
 
     /// Wait for initialize request from the client and let the server respond to it.
-    fn handle_initialization(&self, server: &mut VHDLServer) {
-        let (initialize_id, initialize_params) = self.connection.initialize_start().unwrap();
+    fn handle_initialization(&self, server: &mut VHDLServer) -> Result<(), ProtocolError> {
+        let (initialize_id, initialize_params) = self.connection.initialize_start()?;
         let initialize_params =
             serde_json::from_value::<InitializeParams>(initialize_params).unwrap();
         let initialize_result = server.initialize_request(initialize_params);
-        self.connection
-            .initialize_finish(
-                initialize_id,
-                serde_json::to_value(initialize_result).unwrap(),
-            )
-            .unwrap();
+        self.connection.initialize_finish(
+            initialize_id,
+            serde_json::to_value(initialize_result).unwrap(),
+        )?;
 
         server.initialized_notification();
+        Ok(())
     }
 
     /// Main event loop handling incoming messages from the client.
     fn main_event_loop(&self, mut server: VHDLServer) {
         info!("Language server initialized, waiting for messages ...");
-        while let Ok(message) = self.connection.receiver.recv() {
+        let worker_results = server.worker_results();
+        loop {
+            let message = crossbeam_channel::select! {
+                recv(self.connection.receiver) -> message => match message {
+                    Ok(message) => message,
+                    Err(_) => break,
+                },
+                recv(worker_results) -> result => {
+                    if let Ok(result) = result {
+                        server.worker_result(result);
+                    }
+                    continue;
+                }
+            };
             trace!("Received message: {:?}", message);
             match message {
                 lsp_server::Message::Request(request) => {
@@ -99,6 +126,13 @@ impl ConnectionRpcChannel {
                     }
                 }
                 lsp_server::Message::Notification(notification) => {
+                    if self.keep_running
+                        && notification.method
+                            == <notification::Exit as notification::Notification>::METHOD
+                    {
+                        info!("Client exited");
+                        break;
+                    }
                     self.handle_notification(&mut server, notification);
                 }
                 lsp_server::Message::Response(response) => {
@@ -160,15 +194,101 @@ impl ConnectionRpcChannel {
         };
         let request = match extract::<request::Rename>(request) {
             Ok((id, params)) => {
-                let result = server.rename(&params);
-                self.send_response(lsp_server::Response::new_ok(id, result));
+                let response = match server.rename(&params) {
+                    Ok(res) => lsp_server::Response::new_ok(id, res),
+                    Err(err) => lsp_server::Response::new_err(
+                        id,
+                        lsp_server::ErrorCode::InvalidParams as i32,
+                        err,
+                    ),
+                };
+                self.send_response(response);
                 return;
             }
             Err(request) => request,
         };
         let request = match extract::<request::PrepareRenameRequest>(request) {
             Ok((id, params)) => {
-                let result = server.prepare_rename(&params);
+                let response = match server.prepare_rename(&params) {
+                    Ok(res) => lsp_server::Response::new_ok(id, res),
+                    Err(err) => lsp_server::Response::new_err(
+                        id,
+                        lsp_server::ErrorCode::InvalidParams as i32,
+                        err,
+                    ),
+                };
+                self.send_response(response);
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::CallHierarchyPrepare>(request) {
+            Ok((id, params)) => {
+                let result = server.prepare_call_hierarchy(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::CallHierarchyIncomingCalls>(request) {
+            Ok((id, params)) => {
+                let result = server.incoming_calls(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::CallHierarchyOutgoingCalls>(request) {
+            Ok((id, params)) => {
+                let result = server.outgoing_calls(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::InlayHintRequest>(request) {
+            Ok((id, params)) => {
+                let result = server.text_document_inlay_hint(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::CodeLensRequest>(request) {
+            Ok((id, params)) => {
+                let result = server.text_document_code_lens(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::FoldingRangeRequest>(request) {
+            Ok((id, params)) => {
+                let result = server.text_document_folding_range(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::LinkedEditingRange>(request) {
+            Ok((id, params)) => {
+                let result = server.text_document_linked_editing_range(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::SemanticTokensFullRequest>(request) {
+            Ok((id, params)) => {
+                let result = server.semantic_tokens_full(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::SemanticTokensFullDeltaRequest>(request) {
+            Ok((id, params)) => {
+                let result = server.semantic_tokens_full_delta(&params);
                 self.send_response(lsp_server::Response::new_ok(id, result));
                 return;
             }
@@ -190,6 +310,14 @@ impl ConnectionRpcChannel {
             }
             Err(request) => request,
         };
+        let request = match extract::<request::DocumentLinkRequest>(request) {
+            Ok((id, params)) => {
+                let result = server.text_document_document_link(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
         let request = match extract::<request::DocumentHighlightRequest>(request) {
             Ok((id, params)) => {
                 let result = server.document_highlight(&params.text_document_position_params);
@@ -230,6 +358,85 @@ impl ConnectionRpcChannel {
             }
             Err(request) => request,
         };
+        let request = match extract::<request::SignatureHelpRequest>(request) {
+            Ok((id, params)) => {
+                let res = server.signature_help(&params);
+                self.send_response(lsp_server::Response::new_ok(id, res));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::CodeActionRequest>(request) {
+            Ok((id, params)) => {
+                let res = server.text_document_code_action(&params);
+                self.send_response(lsp_server::Response::new_ok(id, res));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::ExecuteCommand>(request) {
+            Ok((id, params)) => {
+                let res = server.workspace_execute_command(&params);
+                self.send_response(lsp_server::Response::new_ok(id, res));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::Formatting>(request) {
+            Ok((id, params)) => {
+                let res = server.text_document_formatting(&params);
+                self.send_response(lsp_server::Response::new_ok(id, res));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::RangeFormatting>(request) {
+            Ok((id, params)) => {
+                let res = server.text_document_range_formatting(&params);
+                self.send_response(lsp_server::Response::new_ok(id, res));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<request::OnTypeFormatting>(request) {
+            Ok((id, params)) => {
+                let res = server.text_document_on_type_formatting(&params);
+                self.send_response(lsp_server::Response::new_ok(id, res));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<ReferencesWithKind>(request) {
+            Ok((id, params)) => {
+                let response = match server.references_with_kind(params) {
+                    Ok(res) => lsp_server::Response::new_ok(id, res),
+                    Err(err) => lsp_server::Response::new_err(
+                        id,
+                        lsp_server::ErrorCode::InvalidParams as i32,
+                        err,
+                    ),
+                };
+                self.send_response(response);
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<IncomingInstances>(request) {
+            Ok((id, params)) => {
+                let result = server.incoming_instances(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
+        let request = match extract::<OutgoingInstances>(request) {
+            Ok((id, params)) => {
+                let result = server.outgoing_instances(&params);
+                self.send_response(lsp_server::Response::new_ok(id, result));
+                return;
+            }
+            Err(request) => request,
+        };
 
         debug!("Unhandled request: {:?}", request);
         self.send_response(lsp_server::Response::new_err(
@@ -267,11 +474,21 @@ impl ConnectionRpcChannel {
             Ok(params) => return server.text_document_did_open_notification(&params),
             Err(notification) => notification,
         };
+        // textDocument/didSave
+        let notification = match extract::<notification::DidSaveTextDocument>(notification) {
+            Ok(params) => return server.text_document_did_save_notification(&params),
+            Err(notification) => notification,
+        };
         // workspace.didChangeWatchedFiles
         let notification = match extract::<notification::DidChangeWatchedFiles>(notification) {
             Ok(params) => return server.workspace_did_change_watched_files(&params),
             Err(notification) => notification,
         };
+        // workspace/didChangeConfiguration
+        let notification = match extract::<notification::DidChangeConfiguration>(notification) {
+            Ok(params) => return server.workspace_did_change_configuration(&params),
+            Err(notification) => notification,
+        };
         // exit
         let notification = match extract::<notification::Exit>(notification) {
             Ok(_params) => return server.exit_notification(),
@@ -4,26 +4,41 @@
 //
 // Copyright (c) 2018, Olof Kraigher olof.kraigher@gmail.com
 
+mod call_hierarchy;
+mod code_action;
+mod code_lens;
+mod commands;
 mod completion;
+mod instance_hierarchy;
 mod lifecycle;
 mod rename;
+mod semantic_tokens;
 mod text_document;
 mod workspace;
 
+pub use instance_hierarchy::{IncomingInstances, OutgoingInstances};
+pub use text_document::ReferencesWithKind;
+
 use lsp_types::*;
 
-use fnv::FnvHashMap;
+use fnv::{FnvHashMap, FnvHashSet};
 use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 use vhdl_lang::ast::ObjectClass;
 
 use crate::rpc_channel::SharedRpcChannel;
+use crossbeam_channel::{Receiver, Sender};
 use fuzzy_matcher::skim::SkimMatcherV2;
+use serde_json::Value;
 use std::io;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::SystemTime;
 use vhdl_lang::{
-    AnyEntKind, Concurrent, Config, Diagnostic, EntHierarchy, EntRef, Message, MessageHandler,
-    Object, Overloaded, Project, Severity, SeverityMap, SrcPos, Token, Type, VHDLStandard,
+    AnyEntKind, Concurrent, Config, Diagnostic, EntHierarchy, EntRef, ErrorCode,
+    ExternalDiagnostic, Message, MessageCatalog, MessageHandler, Object, Overloaded, Project,
+    Severity, SeverityMap, SrcPos, Token, Type, VHDLStandard, CROSS_CHECK_SOURCE,
 };
 
 /// Defines how the language server handles files
@@ -53,6 +68,25 @@ pub struct VHDLServerSettings {
     pub no_lint: bool,
     pub silent: bool,
     pub non_project_file_handling: NonProjectFileHandling,
+    /// Selected name of the package, i.e. `lib.pkg`, where component declarations are kept
+    pub components_package: Option<String>,
+    /// Name of the file that an architecture or package body is moved to,
+    /// see `vhdl_lang::DEFAULT_FILE_NAME_SCHEME`
+    pub unit_file_name_scheme: Option<String>,
+}
+
+/// The result of work that runs on a worker thread instead of the thread of the server
+pub enum WorkerResult {
+    /// The diagnostics of an external checker and the errors of the runs that failed
+    ExternalChecker {
+        name: String,
+        /// The checked file, `None` when the whole project was checked
+        file_name: Option<PathBuf>,
+        diagnostics: Vec<ExternalDiagnostic>,
+        errors: Vec<String>,
+    },
+    /// The diagnostics of the cross-check with GHDL
+    GhdlCrossCheck(Result<Vec<ExternalDiagnostic>, String>),
 }
 
 pub struct VHDLServer {
@@ -62,39 +96,86 @@ pub struct VHDLServer {
     use_external_config: bool,
     project: Project,
     files_with_notifications: FnvHashMap<Url, ()>,
+    // Opened files that are not part of the project
+    unmapped_files: FnvHashSet<Url>,
+    // The diagnostics of the last run of each external checker
+    external_diagnostics: Vec<ExternalDiagnostic>,
+    // The results of the worker threads
+    worker_sender: Sender<WorkerResult>,
+    worker_receiver: Receiver<WorkerResult>,
     init_params: Option<InitializeParams>,
     config_file: Option<PathBuf>,
+    // The target selected with the selectTarget command, overriding the target of the configuration.
+    // `Some(None)` selects all libraries of the project.
+    target: Option<Option<String>>,
+    // The modification time of the configuration file when it was loaded
+    config_modified: Option<SystemTime>,
+    // The severities of the config
+    config_severities: SeverityMap,
+    // The severities of the `diagnostics` editor setting, by error code or class of diagnostics.
+    // `None` hides the diagnostics.
+    severity_overwrites: Vec<(String, Option<Severity>)>,
+    // The severities of the config with the severities of the editor applied
     severity_map: SeverityMap,
+    // Translations of the diagnostic messages
+    message_catalog: MessageCatalog,
     string_matcher: SkimMatcherV2,
+    // The last semantic tokens of each file together with their result id
+    semantic_tokens: FnvHashMap<Url, (String, Vec<SemanticToken>)>,
+    semantic_tokens_id: u64,
 }
 
 impl VHDLServer {
     pub fn new_settings(rpc: SharedRpcChannel, settings: VHDLServerSettings) -> VHDLServer {
+        let (worker_sender, worker_receiver) = crossbeam_channel::unbounded();
         VHDLServer {
             rpc,
             settings,
             use_external_config: true,
             project: Project::new(VHDLStandard::default()),
             files_with_notifications: FnvHashMap::default(),
+            unmapped_files: FnvHashSet::default(),
+            external_diagnostics: Vec::new(),
+            worker_sender,
+            worker_receiver,
             init_params: None,
             config_file: None,
+            target: None,
+            config_modified: None,
+            config_severities: SeverityMap::default(),
+            severity_overwrites: Vec::new(),
             severity_map: SeverityMap::default(),
+            message_catalog: MessageCatalog::default(),
             string_matcher: SkimMatcherV2::default().use_cache(true).ignore_case(),
+            semantic_tokens: FnvHashMap::default(),
+            semantic_tokens_id: 0,
         }
     }
 
     #[cfg(test)]
     fn new_external_config(rpc: SharedRpcChannel, use_external_config: bool) -> VHDLServer {
+        let (worker_sender, worker_receiver) = crossbeam_channel::unbounded();
         VHDLServer {
             rpc,
             settings: Default::default(),
             use_external_config,
             project: Project::new(VHDLStandard::default()),
             files_with_notifications: FnvHashMap::default(),
+            unmapped_files: FnvHashSet::default(),
+            external_diagnostics: Vec::new(),
+            worker_sender,
+            worker_receiver,
             init_params: None,
             config_file: None,
+            target: None,
+            config_modified: None,
+            config_severities: SeverityMap::default(),
+            severity_overwrites: Vec::new(),
             severity_map: SeverityMap::default(),
+            message_catalog: MessageCatalog::default(),
             string_matcher: SkimMatcherV2::default(),
+            semantic_tokens: FnvHashMap::default(),
+            semantic_tokens_id: 0,
         }
     }
 
@@ -129,6 +210,11 @@ impl VHDLServer {
         match self.load_root_uri_config() {
             Ok(root_config) => {
                 config.append(&root_config, &mut self.message_filter());
+                if let Some(ref target) = self.target {
+                    if let Err(err) = config.select_target(target.as_deref()) {
+                        self.message(Message::error(err));
+                    }
+                }
             }
             Err(ref err) => {
                 if matches!(err.kind(), ErrorKind::NotFound) {
@@ -147,6 +233,94 @@ impl VHDLServer {
         config
     }
 
+    /// The modification time of the workspace root configuration file
+    fn config_file_modified(&self) -> Option<SystemTime> {
+        std::fs::metadata(self.config_file.as_ref()?)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    /// Load the configuration again and analyze the files whose libraries have changed
+    fn reload_config(&mut self) {
+        self.config_modified = self.config_file_modified();
+        let config = self.load_config();
+        self.config_severities = *config.severities();
+        self.update_severity_map();
+        let locale = self
+            .init_params
+            .as_ref()
+            .and_then(|params| params.locale.clone());
+        self.load_message_catalog(&config, locale.as_deref());
+
+        self.project
+            .update_config(config, &mut self.message_filter());
+        // Files that are added to the configuration are part of the project now
+        self.unmapped_files.retain(|uri| {
+            self.project
+                .get_source(&uri_to_file_name(uri))
+                .is_some_and(|source| self.project.library_mapping_of(&source).is_empty())
+        });
+        self.publish_diagnostics();
+    }
+
+    /// Apply the severities of the editor settings to the severities of the config
+    fn update_severity_map(&mut self) {
+        let mut severity_map = self.config_severities;
+        for (name, severity) in self.severity_overwrites.iter() {
+            if let Err(err) = severity_map.set(name, *severity) {
+                self.message(Message::error(format!(
+                    "Invalid diagnostics setting: {err}"
+                )));
+            }
+        }
+        self.severity_map = severity_map;
+    }
+
+    /// Read the `diagnostics` editor setting, which maps error codes or classes of diagnostics
+    /// to a severity or to `false` to hide them, e.g. `{ "unused": false, "missing_bodies": "hint" }`
+    fn read_severity_overwrites(&self, diagnostics: &Value) -> Vec<(String, Option<Severity>)> {
+        let Some(diagnostics) = diagnostics.as_object() else {
+            self.message(Message::error("diagnostics must be an object"));
+            return Vec::new();
+        };
+        let mut overwrites = Vec::new();
+        for (name, severity) in diagnostics {
+            match severity {
+                Value::String(severity) => match Severity::try_from(severity.as_str()) {
+                    Ok(severity) => overwrites.push((name.clone(), Some(severity))),
+                    Err(_) => self.message(Message::error(format!(
+                        "'{severity}' is not a valid severity level"
+                    ))),
+                },
+                Value::Bool(false) => overwrites.push((name.clone(), None)),
+                Value::Bool(true) => {}
+                _ => self.message(Message::error(format!(
+                    "The severity of {name} must be a string or boolean"
+                ))),
+            }
+        }
+        overwrites
+    }
+
+    /// Use the message catalog of the locale of the config or else of the client
+    fn load_message_catalog(&mut self, config: &Config, client_locale: Option<&str>) {
+        self.message_catalog = config.message_catalog(client_locale).unwrap_or_else(|err| {
+            self.message(Message::error(err));
+            MessageCatalog::default()
+        });
+    }
+
+    /// Reload the configuration when the configuration file has been modified since it was loaded.
+    /// This detects edits of clients that do not watch the configuration file.
+    fn reload_modified_config(&mut self) {
+        if self.config_modified.is_some() && self.config_file_modified() != self.config_modified {
+            self.message(Message::log(
+                "Configuration file has been modified, reloading project...",
+            ));
+            self.reload_config();
+        }
+    }
+
     /// Extract path of workspace root configuration file from InitializeParams
     fn root_uri_config_file(&self, params: &InitializeParams) -> Option<PathBuf> {
         #[allow(deprecated)]
@@ -246,13 +420,37 @@ impl VHDLServer {
             }
         };
 
-        let mut files_with_notifications = std::mem::take(&mut self.files_with_notifications);
-        for (file_uri, diagnostics) in diagnostics_by_uri(diagnostics).into_iter() {
-            let lsp_diagnostics = diagnostics
+        let mut lsp_diagnostics_by_uri: FnvHashMap<Url, Vec<lsp_types::Diagnostic>> =
+            diagnostics_by_uri(diagnostics)
                 .into_iter()
-                .filter_map(|diag| to_lsp_diagnostic(diag, &self.severity_map))
+                .map(|(file_uri, diagnostics)| {
+                    let lsp_diagnostics = diagnostics
+                        .into_iter()
+                        .filter_map(|diag| {
+                            to_lsp_diagnostic(
+                                self.message_catalog.translate_diagnostic(diag),
+                                &self.severity_map,
+                            )
+                        })
+                        .collect();
+                    (file_uri, lsp_diagnostics)
+                })
                 .collect();
+        for file_uri in self.unmapped_files.iter() {
+            lsp_diagnostics_by_uri
+                .entry(file_uri.clone())
+                .or_default()
+                .push(unmapped_file_diagnostic());
+        }
+        for diagnostic in self.external_diagnostics.iter() {
+            lsp_diagnostics_by_uri
+                .entry(file_name_to_uri(&diagnostic.file))
+                .or_default()
+                .push(external_to_lsp_diagnostic(diagnostic));
+        }
 
+        let mut files_with_notifications = std::mem::take(&mut self.files_with_notifications);
+        for (file_uri, lsp_diagnostics) in lsp_diagnostics_by_uri.into_iter() {
             let publish_diagnostics = PublishDiagnosticsParams {
                 uri: file_uri.clone(),
                 diagnostics: lsp_diagnostics,
@@ -280,6 +478,86 @@ impl VHDLServer {
         }
     }
 
+    /// Run the external checkers of the config on worker threads.
+    /// The diagnostics are published when a checker finishes, see `worker_result`.
+    fn run_external_checkers(&self, file_name: Option<&Path>, on_save: bool) {
+        for run in self.project.external_checker_runs(file_name, on_save) {
+            let sender = self.worker_sender.clone();
+            let file_name = file_name
+                .filter(|_| run.checker.is_per_file())
+                .map(Path::to_owned);
+            thread::spawn(move || {
+                let (diagnostics, errors) = run.run();
+                // The server may have shut down in the meantime
+                let _ = sender.send(WorkerResult::ExternalChecker {
+                    name: run.checker.name,
+                    file_name,
+                    diagnostics,
+                    errors,
+                });
+            });
+        }
+    }
+
+    /// The results of the worker threads, to be passed to `worker_result`
+    pub fn worker_results(&self) -> Receiver<WorkerResult> {
+        self.worker_receiver.clone()
+    }
+
+    /// Publish the result of a worker thread
+    pub fn worker_result(&mut self, result: WorkerResult) {
+        match result {
+            WorkerResult::ExternalChecker {
+                name,
+                file_name,
+                diagnostics,
+                errors,
+            } => {
+                for err in errors {
+                    self.message(Message::error(err));
+                }
+                // Replace the previous diagnostics of the checker
+                self.external_diagnostics.retain(|diagnostic| {
+                    diagnostic.source != name
+                        || file_name
+                            .as_ref()
+                            .is_some_and(|file_name| diagnostic.file != *file_name)
+                });
+                self.external_diagnostics.extend(diagnostics);
+            }
+            WorkerResult::GhdlCrossCheck(Ok(diagnostics)) => {
+                // Replace the previous diagnostics of the cross-check
+                self.external_diagnostics
+                    .retain(|diagnostic| diagnostic.source != CROSS_CHECK_SOURCE);
+                self.external_diagnostics.extend(diagnostics);
+            }
+            WorkerResult::GhdlCrossCheck(Err(err)) => {
+                self.message(Message::error(format!("GHDL cross-check failed: {err}")))
+            }
+        }
+        self.publish_diagnostics();
+    }
+
+    /// Cross-check the analysis with GHDL on a worker thread.
+    /// The diagnostics are published when the cross-check finishes, see `worker_result`.
+    /// Returns false when no cross-check is configured or runs on demand only.
+    fn run_ghdl_cross_check(&mut self, on_save: bool) -> bool {
+        match self.project.ghdl_cross_check_run(on_save) {
+            Some(Ok(run)) => {
+                let sender = self.worker_sender.clone();
+                thread::spawn(move || {
+                    // The server may have shut down in the meantime
+                    let _ = sender.send(WorkerResult::GhdlCrossCheck(run.run()));
+                });
+            }
+            Some(Err(err)) => {
+                self.message(Message::error(format!("GHDL cross-check failed: {err}")))
+            }
+            None => return false,
+        }
+        true
+    }
+
     pub fn document_symbol(&self, params: &DocumentSymbolParams) -> Option<DocumentSymbolResponse> {
         let source = self
             .project
@@ -449,6 +727,23 @@ fn from_lsp_range(range: lsp_types::Range) -> vhdl_lang::Range {
     }
 }
 
+fn to_lsp_workspace_edit(edits: Vec<vhdl_lang::TextEdit>) -> WorkspaceEdit {
+    let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::default();
+
+    for edit in edits {
+        let loc = srcpos_to_location(&edit.pos);
+        changes.entry(loc.uri).or_default().push(TextEdit {
+            range: loc.range,
+            new_text: edit.new_text,
+        });
+    }
+
+    WorkspaceEdit {
+        changes: Some(changes),
+        ..Default::default()
+    }
+}
+
 fn diagnostics_by_uri(diagnostics: Vec<Diagnostic>) -> FnvHashMap<Url, Vec<Diagnostic>> {
     let mut map: FnvHashMap<Url, Vec<Diagnostic>> = FnvHashMap::default();
 
@@ -489,12 +784,7 @@ fn to_lsp_diagnostic(
     diagnostic: Diagnostic,
     severity_map: &SeverityMap,
 ) -> Option<lsp_types::Diagnostic> {
-    let severity = match severity_map[diagnostic.code]? {
-        Severity::Error => DiagnosticSeverity::ERROR,
-        Severity::Warning => DiagnosticSeverity::WARNING,
-        Severity::Info => DiagnosticSeverity::INFORMATION,
-        Severity::Hint => DiagnosticSeverity::HINT,
-    };
+    let severity = to_lsp_severity(severity_map[diagnostic.code]?);
 
     let related_information = if !diagnostic.related.is_empty() {
         let mut related_information = Vec::new();
@@ -520,10 +810,58 @@ fn to_lsp_diagnostic(
         source: Some("vhdl ls".to_owned()),
         message: diagnostic.message,
         related_information,
+        tags: to_lsp_tags(diagnostic.code),
         ..Default::default()
     })
 }
 
+/// Editors render unnecessary code faded and deprecated code struck through
+fn to_lsp_tags(code: ErrorCode) -> Option<Vec<DiagnosticTag>> {
+    match code {
+        ErrorCode::Unused | ErrorCode::UnusedPort | ErrorCode::UnnecessaryWorkLibrary => {
+            Some(vec![DiagnosticTag::UNNECESSARY])
+        }
+        ErrorCode::Deprecated => Some(vec![DiagnosticTag::DEPRECATED]),
+        _ => None,
+    }
+}
+
+/// The code of the diagnostic of a file that is not part of the project
+pub const UNMAPPED_FILE: &str = "unmapped_file";
+
+fn unmapped_file_diagnostic() -> lsp_types::Diagnostic {
+    lsp_types::Diagnostic {
+        range: lsp_types::Range::default(),
+        severity: Some(DiagnosticSeverity::WARNING),
+        code: Some(NumberOrString::String(UNMAPPED_FILE.to_owned())),
+        source: Some("vhdl ls".to_owned()),
+        message: "File is not part of any library of vhdl_ls.toml".to_owned(),
+        ..Default::default()
+    }
+}
+
+fn external_to_lsp_diagnostic(diagnostic: &ExternalDiagnostic) -> lsp_types::Diagnostic {
+    lsp_types::Diagnostic {
+        range: lsp_types::Range {
+            start: to_lsp_pos(diagnostic.pos),
+            end: to_lsp_pos(diagnostic.pos),
+        },
+        severity: Some(to_lsp_severity(diagnostic.severity)),
+        source: Some(diagnostic.source.clone()),
+        message: diagnostic.message.clone(),
+        ..Default::default()
+    }
+}
+
+fn to_lsp_severity(severity: Severity) -> DiagnosticSeverity {
+    match severity {
+        Severity::Error => DiagnosticSeverity::ERROR,
+        Severity::Warning => DiagnosticSeverity::WARNING,
+        Severity::Info => DiagnosticSeverity::INFORMATION,
+        Severity::Hint => DiagnosticSeverity::HINT,
+    }
+}
+
 fn overloaded_kind(overloaded: &Overloaded) -> SymbolKind {
     match overloaded {
         Overloaded::SubprogramDecl(_) => SymbolKind::FUNCTION,
@@ -696,14 +1034,21 @@ end entity ent;
 
         let did_open = DidOpenTextDocumentParams {
             text_document: TextDocumentItem {
-                uri: file_url,
+                uri: file_url.clone(),
                 language_id: "vhdl".to_owned(),
                 version: 0,
                 text: code,
             },
         };
 
+        let publish_diagnostics = PublishDiagnosticsParams {
+            uri: file_url,
+            diagnostics: vec![unmapped_file_diagnostic()],
+            version: None,
+        };
+
         mock.expect_warning_contains("is not part of the project");
+        mock.expect_notification("textDocument/publishDiagnostics", publish_diagnostics);
 
         server.text_document_did_open_notification(&did_open);
     }
@@ -734,23 +1079,26 @@ end entity ent2;
 
         let publish_diagnostics = PublishDiagnosticsParams {
             uri: file_url.clone(),
-            diagnostics: vec![lsp_types::Diagnostic {
-                range: Range {
-                    start: lsp_types::Position {
-                        line: 2,
-                        character: "end entity ".len() as u32,
-                    },
-                    end: lsp_types::Position {
-                        line: 2,
-                        character: "end entity ent2".len() as u32,
+            diagnostics: vec![
+                lsp_types::Diagnostic {
+                    range: Range {
+                        start: lsp_types::Position {
+                            line: 2,
+                            character: "end entity ".len() as u32,
+                        },
+                        end: lsp_types::Position {
+                            line: 2,
+                            character: "end entity ent2".len() as u32,
+                        },
                     },
+                    code: Some(NumberOrString::String("syntax_error".to_owned())),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    source: Some("vhdl ls".to_owned()),
+                    message: "End identifier mismatch, expected ent".to_owned(),
+                    ..Default::default()
                 },
-                code: Some(NumberOrString::String("syntax_error".to_owned())),
-                severity: Some(DiagnosticSeverity::ERROR),
-                source: Some("vhdl ls".to_owned()),
-                message: "End identifier mismatch, expected ent".to_owned(),
-                ..Default::default()
-            }],
+                unmapped_file_diagnostic(),
+            ],
             version: None,
         };
 
@@ -779,7 +1127,7 @@ end entity ent;
 
         let publish_diagnostics = PublishDiagnosticsParams {
             uri: file_url,
-            diagnostics: vec![],
+            diagnostics: vec![unmapped_file_diagnostic()],
             version: None,
         };
 
@@ -852,6 +1200,95 @@ lib.files = [
         initialize_server(&mut server, root_uri);
     }
 
+    #[test]
+    #[cfg(unix)]
+    fn publishes_diagnostics_of_external_checkers_when_they_finish() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        let file_uri = write_file(&root_uri, "file.vhd", "entity ent is\nend entity;\n");
+        let config_uri = write_config(
+            &root_uri,
+            "
+[libraries]
+lib.files = ['file.vhd']
+
+[checkers.lint]
+command = ['echo', 'file.vhd:2:1: warning: from lint']
+",
+        );
+
+        expect_loaded_config_messages(&mock, &config_uri);
+        initialize_server(&mut server, root_uri);
+
+        server.run_external_checkers(None, false);
+        let result = server
+            .worker_results()
+            .recv_timeout(std::time::Duration::from_secs(10))
+            .unwrap();
+
+        let publish_diagnostics = PublishDiagnosticsParams {
+            uri: file_uri,
+            diagnostics: vec![lsp_types::Diagnostic {
+                range: Range {
+                    start: lsp_types::Position {
+                        line: 1,
+                        character: 0,
+                    },
+                    end: lsp_types::Position {
+                        line: 1,
+                        character: 0,
+                    },
+                },
+                severity: Some(DiagnosticSeverity::WARNING),
+                source: Some("lint".to_owned()),
+                message: "from lint".to_owned(),
+                ..Default::default()
+            }],
+            version: None,
+        };
+        mock.expect_notification("textDocument/publishDiagnostics", publish_diagnostics);
+        server.worker_result(result);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn runs_ghdl_cross_check_on_worker_thread() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        write_file(
+            &root_uri,
+            "file.vhd",
+            "entity ent is\nend entity;\n\narchitecture a of ent is\nbegin\nend architecture;\n",
+        );
+        let config_uri = write_config(
+            &root_uri,
+            "
+[libraries]
+lib.files = ['file.vhd']
+
+[ghdl_cross_check]
+top = 'lib.ent'
+ghdl = 'true'
+",
+        );
+
+        expect_loaded_config_messages(&mock, &config_uri);
+        initialize_server(&mut server, root_uri);
+
+        // The cross-check runs on demand only
+        assert!(!server.run_ghdl_cross_check(true));
+        assert!(server.run_ghdl_cross_check(false));
+        let result = server
+            .worker_results()
+            .recv_timeout(std::time::Duration::from_secs(10))
+            .unwrap();
+        assert!(matches!(
+            result,
+            WorkerResult::GhdlCrossCheck(Ok(ref diagnostics)) if diagnostics.is_empty()
+        ));
+        server.worker_result(result);
+    }
+
     #[test]
     fn initialize_with_bad_config() {
         let (mock, mut server) = setup_server();
@@ -967,6 +1404,74 @@ lib.files = [
         assert_eq!(response, Some(expected));
     }
 
+    #[test]
+    fn document_highlight_distinguishes_reads_and_writes() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+
+        let code = "\
+entity ent is
+end entity;
+
+architecture a of ent is
+  signal s, t : bit;
+begin
+  s <= '1';
+  t <= s;
+end architecture;
+"
+        .to_owned();
+        let file_url = write_file(&root_uri, "ent.vhd", &code);
+
+        let config_uri = write_config(
+            &root_uri,
+            format!(
+                "
+[libraries]
+std.files = [
+'{}/../vhdl_libraries/std/*.vhd',
+]
+lib.files = [
+  '*.vhd'
+]
+",
+                std::env::var("CARGO_MANIFEST_DIR").unwrap()
+            ),
+        );
+
+        expect_loaded_config_messages(&mock, &config_uri);
+        initialize_server(&mut server, root_uri);
+
+        let highlight = |line: u32, character: u32, kind: DocumentHighlightKind| {
+            let start = lsp_types::Position { line, character };
+            let end = lsp_types::Position {
+                line,
+                character: character + 1,
+            };
+            DocumentHighlight {
+                range: Range { start, end },
+                kind: Some(kind),
+            }
+        };
+
+        let response = server.document_highlight(&TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri: file_url },
+            position: lsp_types::Position {
+                line: 6,
+                character: 2,
+            },
+        });
+
+        assert_eq!(
+            response,
+            Some(vec![
+                highlight(4, "  signal ".len() as u32, DocumentHighlightKind::TEXT),
+                highlight(6, 2, DocumentHighlightKind::WRITE),
+                highlight(7, "  t <= ".len() as u32, DocumentHighlightKind::READ),
+            ])
+        );
+    }
+
     #[test]
     fn client_register_capability() {
         let (mock, mut server) = setup_server();
@@ -1107,4 +1612,96 @@ lib.files = [
             }],
         });
     }
+
+    #[test]
+    fn update_component_declarations_command() {
+        let (mock, mut server) = setup_server();
+        let (_tempdir, root_uri) = temp_root_uri();
+        write_file(
+            &root_uri,
+            "ent.vhd",
+            "\
+entity ent is
+  port (
+    clk : in bit
+  );
+end entity;
+
+architecture rtl of ent is
+begin
+  process (clk)
+  begin
+  end process;
+end architecture;
+",
+        );
+        let pkg_uri = write_file(
+            &root_uri,
+            "components.vhd",
+            "\
+package components is
+end package;
+",
+        );
+        let config_uri = write_config(
+            &root_uri,
+            format!(
+                "
+[libraries]
+std.files = [
+'{}/../vhdl_libraries/std/*.vhd',
+]
+lib.files = [
+  '*.vhd'
+]
+",
+                std::env::var("CARGO_MANIFEST_DIR").unwrap()
+            ),
+        );
+
+        expect_loaded_config_messages(&mock, &config_uri);
+        initialize_server(&mut server, root_uri);
+        server.settings.components_package = Some("lib.components".to_owned());
+
+        let mut changes = HashMap::new();
+        changes.insert(
+            pkg_uri,
+            vec![TextEdit {
+                range: Range {
+                    start: lsp_types::Position {
+                        line: 1,
+                        character: 0,
+                    },
+                    end: lsp_types::Position {
+                        line: 1,
+                        character: 0,
+                    },
+                },
+                new_text: "
+  component ent
+    port (
+      clk : in bit
+    );
+  end component;
+"
+                .to_owned(),
+            }],
+        );
+        mock.expect_request(
+            "workspace/applyEdit",
+            ApplyWorkspaceEditParams {
+                label: Some("Update component declarations in lib.components".to_owned()),
+                edit: WorkspaceEdit {
+                    changes: Some(changes),
+                    ..Default::default()
+                },
+            },
+        );
+
+        server.workspace_execute_command(&ExecuteCommandParams {
+            command: commands::UPDATE_COMPONENT_DECLARATIONS.to_owned(),
+            arguments: vec![serde_json::Value::String("lib.ent".to_owned())],
+            work_done_progress_params: Default::default(),
+        });
+    }
 }
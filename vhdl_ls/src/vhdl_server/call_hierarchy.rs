@@ -0,0 +1,91 @@
+use crate::vhdl_server::{
+    from_lsp_pos, srcpos_to_location, to_lsp_range, to_symbol_kind, uri_to_file_name, VHDLServer,
+};
+use lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
+    CallHierarchyOutgoingCall, CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams,
+};
+use vhdl_lang::EntRef;
+
+impl VHDLServer {
+    pub fn prepare_call_hierarchy(
+        &mut self,
+        params: &CallHierarchyPrepareParams,
+    ) -> Option<Vec<CallHierarchyItem>> {
+        let params = &params.text_document_position_params;
+        let source = self
+            .project
+            .get_source(&uri_to_file_name(&params.text_document.uri))?;
+        let ent = self
+            .project
+            .find_subprogram(&source, from_lsp_pos(params.position))?;
+        Some(vec![to_call_hierarchy_item(ent)?])
+    }
+
+    pub fn incoming_calls(
+        &mut self,
+        params: &CallHierarchyIncomingCallsParams,
+    ) -> Option<Vec<CallHierarchyIncomingCall>> {
+        let ent = self.call_hierarchy_ent(&params.item)?;
+        Some(
+            self.project
+                .incoming_calls(ent)
+                .into_iter()
+                .filter_map(|calls| {
+                    Some(CallHierarchyIncomingCall {
+                        from: to_call_hierarchy_item(calls.ent)?,
+                        from_ranges: calls
+                            .positions
+                            .iter()
+                            .map(|pos| to_lsp_range(pos.range()))
+                            .collect(),
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    pub fn outgoing_calls(
+        &mut self,
+        params: &CallHierarchyOutgoingCallsParams,
+    ) -> Option<Vec<CallHierarchyOutgoingCall>> {
+        let ent = self.call_hierarchy_ent(&params.item)?;
+        Some(
+            self.project
+                .outgoing_calls(ent)
+                .into_iter()
+                .filter_map(|calls| {
+                    Some(CallHierarchyOutgoingCall {
+                        to: to_call_hierarchy_item(calls.ent)?,
+                        from_ranges: calls
+                            .positions
+                            .iter()
+                            .map(|pos| to_lsp_range(pos.range()))
+                            .collect(),
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// The declaration of an item of a previous call hierarchy request
+    fn call_hierarchy_ent(&self, item: &CallHierarchyItem) -> Option<EntRef> {
+        let source = self.project.get_source(&uri_to_file_name(&item.uri))?;
+        self.project
+            .find_declaration(&source, from_lsp_pos(item.selection_range.start))
+    }
+}
+
+fn to_call_hierarchy_item(ent: EntRef) -> Option<CallHierarchyItem> {
+    let location = srcpos_to_location(ent.decl_pos()?);
+    Some(CallHierarchyItem {
+        name: ent.designator().to_string(),
+        kind: to_symbol_kind(ent.kind()),
+        tags: None,
+        detail: Some(ent.describe()),
+        uri: location.uri,
+        range: location.range,
+        selection_range: location.range,
+        data: None,
+    })
+}
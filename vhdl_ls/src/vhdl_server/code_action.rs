@@ -0,0 +1,527 @@
+use crate::vhdl_server::commands::{CREATE_TESTBENCH, UPDATE_COMPONENT_DECLARATIONS};
+use crate::vhdl_server::{
+    file_name_to_uri, from_lsp_pos, from_lsp_range, srcpos_to_location, to_lsp_range,
+    to_lsp_workspace_edit, uri_to_file_name, VHDLServer,
+};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, CodeActionResponse, Command,
+    CreateFile, CreateFileOptions, DocumentChangeOperation, DocumentChanges, OneOf,
+    OptionalVersionedTextDocumentIdentifier, ResourceOp, TextDocumentEdit, TextEdit, Url,
+    WorkspaceEdit,
+};
+use serde_json::Value;
+use std::path::Path;
+use vhdl_lang::{
+    align_lines, AnyEntKind, Design, MovedUnit, Position, Source, DEFAULT_FILE_NAME_SCHEME,
+};
+
+impl VHDLServer {
+    pub fn text_document_code_action(
+        &self,
+        params: &CodeActionParams,
+    ) -> Option<CodeActionResponse> {
+        let source = self
+            .project
+            .get_source(&uri_to_file_name(&params.text_document.uri))?;
+        let cursor = from_lsp_pos(params.range.start);
+
+        let mut actions = Vec::new();
+        actions.extend(self.update_component_declaration_action(&source, cursor));
+        actions.extend(self.move_unit_to_file_action(&source, cursor));
+        actions.extend(self.create_entity_action(&source, cursor));
+        actions.extend(self.create_testbench_actions(&source, cursor));
+        actions.extend(self.remove_unused_declarations_action(&source));
+        actions.extend(self.organize_imports_action(&source));
+        actions.extend(self.organize_context_clause_action(&source));
+        actions.extend(align_selection_action(&source, params.range));
+        actions.extend(self.extract_procedure_action(&source, params.range));
+        actions.extend(self.add_to_library_actions(&params.text_document.uri, &source));
+        actions.extend(self.auto_import_actions(&source, params));
+        for action in self.project.code_actions(&source, cursor) {
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: action.title,
+                kind: Some(CodeActionKind::REFACTOR_REWRITE),
+                edit: Some(to_lsp_workspace_edit(action.edits)),
+                ..Default::default()
+            }));
+        }
+        Some(actions)
+    }
+
+    /// Offer to make an unresolved name within the range visible
+    /// when only one package of the design declares it
+    fn auto_import_actions(
+        &self,
+        source: &Source,
+        params: &CodeActionParams,
+    ) -> Vec<CodeActionOrCommand> {
+        let range = from_lsp_range(params.range);
+        self.project
+            .auto_imports(source)
+            .into_iter()
+            .filter(|(pos, _)| pos.start() <= range.end && range.start <= pos.end())
+            .map(|(pos, action)| {
+                let name_range = to_lsp_range(pos.range());
+                CodeActionOrCommand::CodeAction(CodeAction {
+                    title: action.title,
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(
+                        params
+                            .context
+                            .diagnostics
+                            .iter()
+                            .filter(|diagnostic| diagnostic.range == name_range)
+                            .cloned()
+                            .collect(),
+                    ),
+                    edit: Some(to_lsp_workspace_edit(action.edits)),
+                    is_preferred: Some(true),
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    /// Offer to update the component of the entity under the cursor
+    /// when a components package has been configured.
+    fn update_component_declaration_action(
+        &self,
+        source: &Source,
+        cursor: Position,
+    ) -> Option<CodeActionOrCommand> {
+        let package = self.settings.components_package.as_ref()?;
+        let ent = self.project.find_declaration(source, cursor)?;
+        if !matches!(ent.kind(), AnyEntKind::Design(Design::Entity(..))) {
+            return None;
+        }
+        let name = format!("{}.{}", ent.library_name()?, ent.designator());
+        let title = format!("Update component declaration in {package}");
+
+        Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title: title.clone(),
+            kind: Some(CodeActionKind::REFACTOR),
+            command: Some(Command {
+                title,
+                command: UPDATE_COMPONENT_DECLARATIONS.to_owned(),
+                arguments: Some(vec![Value::String(name)]),
+            }),
+            ..Default::default()
+        }))
+    }
+
+    /// Remove all unused declarations of the document in one edit
+    fn remove_unused_declarations_action(&self, source: &Source) -> Option<CodeActionOrCommand> {
+        let edits = self.project.remove_unused_declarations(source);
+        if edits.is_empty() {
+            return None;
+        }
+
+        Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Remove unused declarations".to_owned(),
+            kind: Some(CodeActionKind::SOURCE_FIX_ALL),
+            edit: Some(to_lsp_workspace_edit(edits)),
+            ..Default::default()
+        }))
+    }
+
+    /// Sort the library clauses, context references and use clauses and remove the duplicates
+    fn organize_imports_action(&self, source: &Source) -> Option<CodeActionOrCommand> {
+        let edits = self.project.sort_context_clauses(source)?;
+        if edits.is_empty() {
+            return None;
+        }
+
+        Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Organize imports".to_owned(),
+            kind: Some(CodeActionKind::SOURCE_ORGANIZE_IMPORTS),
+            edit: Some(to_lsp_workspace_edit(edits)),
+            ..Default::default()
+        }))
+    }
+
+    /// Remove the use clauses and library clauses of the document that are not used
+    fn organize_context_clause_action(&self, source: &Source) -> Option<CodeActionOrCommand> {
+        let edits = self.project.remove_unused_context_items(source);
+        if edits.is_empty() {
+            return None;
+        }
+
+        Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Organize context clause".to_owned(),
+            kind: Some(CodeActionKind::SOURCE),
+            edit: Some(to_lsp_workspace_edit(edits)),
+            ..Default::default()
+        }))
+    }
+
+    /// Offer to move the architecture or package body under the cursor into a file of its own.
+    /// The new file is added to `vhdl_ls.toml` when the original file is listed explicitly.
+    fn move_unit_to_file_action(
+        &self,
+        source: &Source,
+        cursor: Position,
+    ) -> Option<CodeActionOrCommand> {
+        let scheme = self
+            .settings
+            .unit_file_name_scheme
+            .as_deref()
+            .unwrap_or(DEFAULT_FILE_NAME_SCHEME);
+        let moved = self.project.move_unit_to_file(source, cursor, scheme)?;
+        if moved.file_name.exists() {
+            return None;
+        }
+
+        Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title: format!(
+                "Move {} to {}",
+                moved.description,
+                moved.file_name.file_name()?.to_string_lossy()
+            ),
+            kind: Some(CodeActionKind::REFACTOR),
+            edit: self.move_unit_edit(source, moved),
+            ..Default::default()
+        }))
+    }
+
+    /// The edit that moves a design unit to a file of the project or to a new file.
+    /// Returns `None` if the file exists but is not part of the project.
+    pub(super) fn move_unit_edit(
+        &self,
+        source: &Source,
+        moved: MovedUnit,
+    ) -> Option<WorkspaceEdit> {
+        if let Some(target) = self.project.get_source(&moved.file_name) {
+            return Some(to_lsp_workspace_edit(vec![
+                moved.append_to(&target),
+                moved.removal,
+            ]));
+        }
+        if moved.file_name.exists() {
+            return None;
+        }
+
+        let removal = srcpos_to_location(&moved.removal.pos);
+        let mut edit = self.create_file_edit(source.file_name(), &moved.file_name, moved.text);
+        if let Some(DocumentChanges::Operations(ref mut operations)) = edit.document_changes {
+            operations.push(DocumentChangeOperation::Edit(text_document_edit(
+                removal.uri,
+                removal.range,
+                moved.removal.new_text,
+            )));
+        }
+        Some(edit)
+    }
+
+    /// Offer to add a file that is not part of the project, or all files of its directory
+    /// with the same extension, to a library of the configuration file
+    fn add_to_library_actions(&self, uri: &Url, source: &Source) -> Vec<CodeActionOrCommand> {
+        let Some(config_file) = self.config_file.as_ref() else {
+            return Vec::new();
+        };
+        if !self.unmapped_files.contains(uri) {
+            return Vec::new();
+        }
+        let Some(dir) = config_file.parent() else {
+            return Vec::new();
+        };
+        let file_name = source.file_name();
+        let file_pattern = file_name.strip_prefix(dir).unwrap_or(file_name);
+        let dir_pattern = file_pattern.with_file_name(match file_name.extension() {
+            Some(extension) => format!("*.{}", extension.to_string_lossy()),
+            None => "*".to_owned(),
+        });
+
+        let mut libraries = self.project.project_library_names();
+        if libraries.is_empty() {
+            libraries.push("lib".to_owned());
+        }
+        let mut actions = Vec::new();
+        for library in libraries.iter() {
+            for (pattern, title) in [
+                (file_pattern, format!("Add file to library '{library}'")),
+                (
+                    dir_pattern.as_path(),
+                    format!(
+                        "Add '{}' to library '{library}'",
+                        dir_pattern.to_string_lossy()
+                    ),
+                ),
+            ] {
+                let Some(operations) = add_to_library_edit(config_file, library, pattern) else {
+                    continue;
+                };
+                actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                    title,
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    edit: Some(WorkspaceEdit {
+                        document_changes: Some(DocumentChanges::Operations(operations)),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }));
+            }
+        }
+        actions
+    }
+
+    /// Offer to create the entity of an instantiation or component declaration
+    /// when the entity does not exist.
+    /// The new file is added to `vhdl_ls.toml` when the file of the instantiation is listed explicitly.
+    fn create_entity_action(
+        &self,
+        source: &Source,
+        cursor: Position,
+    ) -> Option<CodeActionOrCommand> {
+        let stub = self.project.entity_stub(source, cursor)?;
+        if stub.file_name.exists() {
+            return None;
+        }
+
+        Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title: format!(
+                "Create entity '{}' in {}",
+                stub.name,
+                stub.file_name.file_name()?.to_string_lossy()
+            ),
+            kind: Some(CodeActionKind::QUICKFIX),
+            edit: Some(self.create_file_edit(source.file_name(), &stub.file_name, stub.text)),
+            ..Default::default()
+        }))
+    }
+
+    /// Offer to move the selected statements of a process into a new procedure.
+    /// The procedure is named `new_procedure`, which is to be renamed afterwards.
+    fn extract_procedure_action(
+        &self,
+        source: &Source,
+        range: lsp_types::Range,
+    ) -> Option<CodeActionOrCommand> {
+        if range.start == range.end {
+            return None;
+        }
+        let action =
+            self.project
+                .extract_procedure(source, from_lsp_range(range), "new_procedure")?;
+
+        Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title: action.title,
+            kind: Some(CodeActionKind::REFACTOR_EXTRACT),
+            edit: Some(to_lsp_workspace_edit(action.edits)),
+            ..Default::default()
+        }))
+    }
+
+    /// Offer to create a testbench, with or without VUnit, of the entity under the cursor
+    fn create_testbench_actions(
+        &self,
+        source: &Source,
+        cursor: Position,
+    ) -> Vec<CodeActionOrCommand> {
+        let Some(ent) = self.project.find_declaration(source, cursor) else {
+            return Vec::new();
+        };
+        if !matches!(ent.kind(), AnyEntKind::Design(Design::Entity(..))) {
+            return Vec::new();
+        }
+        let Some(library) = ent.library_name() else {
+            return Vec::new();
+        };
+        let name = format!("{library}.{}", ent.designator());
+
+        let mut actions = Vec::new();
+        for (vunit, description) in [(false, "testbench"), (true, "VUnit testbench")] {
+            let Ok(testbench) = self.project.testbench(&name, vunit) else {
+                continue;
+            };
+            if testbench.file_name.exists() {
+                continue;
+            }
+            let Some(file_name) = testbench.file_name.file_name() else {
+                continue;
+            };
+            let title = format!(
+                "Create {description} '{}' in {}",
+                testbench.name,
+                file_name.to_string_lossy()
+            );
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: title.clone(),
+                kind: Some(CodeActionKind::REFACTOR),
+                command: Some(Command {
+                    title,
+                    command: CREATE_TESTBENCH.to_owned(),
+                    arguments: Some(vec![Value::String(name.clone()), Value::Bool(vunit)]),
+                }),
+                ..Default::default()
+            }));
+        }
+        actions
+    }
+
+    /// Create `new_file` with `text`. The new file is added to `vhdl_ls.toml`
+    /// after `old_file` when `old_file` is listed explicitly.
+    pub(super) fn create_file_edit(
+        &self,
+        old_file: &Path,
+        new_file: &Path,
+        text: String,
+    ) -> WorkspaceEdit {
+        let new_uri = file_name_to_uri(new_file);
+        let mut operations = vec![
+            DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+                uri: new_uri.clone(),
+                options: Some(CreateFileOptions {
+                    overwrite: Some(false),
+                    ignore_if_exists: Some(false),
+                }),
+                annotation_id: None,
+            })),
+            DocumentChangeOperation::Edit(text_document_edit(
+                new_uri,
+                lsp_types::Range::default(),
+                text,
+            )),
+        ];
+        if let Some(config_file) = self.config_file.as_ref() {
+            operations.extend(
+                config_file_edit(config_file, old_file, new_file)
+                    .map(DocumentChangeOperation::Edit),
+            );
+        }
+        WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(operations)),
+            ..Default::default()
+        }
+    }
+}
+
+/// Align `:`, `:=` and `=>` of the selected lines without formatting the rest of the file
+fn align_selection_action(source: &Source, range: lsp_types::Range) -> Option<CodeActionOrCommand> {
+    if range.start.line == range.end.line {
+        return None;
+    }
+    let edits = align_lines(source, from_lsp_range(range));
+    if edits.is_empty() {
+        return None;
+    }
+
+    Some(CodeActionOrCommand::CodeAction(CodeAction {
+        title: "Align declarations in selection".to_owned(),
+        kind: Some(CodeActionKind::REFACTOR_REWRITE),
+        edit: Some(to_lsp_workspace_edit(edits)),
+        ..Default::default()
+    }))
+}
+
+fn text_document_edit(uri: Url, range: lsp_types::Range, new_text: String) -> TextDocumentEdit {
+    TextDocumentEdit {
+        text_document: OptionalVersionedTextDocumentIdentifier { uri, version: None },
+        edits: vec![OneOf::Left(TextEdit { range, new_text })],
+    }
+}
+
+/// Add `new_file` after `old_file` in the configuration file.
+/// Files that are included through a glob pattern need no update,
+/// only literal file names are searched for.
+fn config_file_edit(
+    config_file: &Path,
+    old_file: &Path,
+    new_file: &Path,
+) -> Option<TextDocumentEdit> {
+    let contents = std::fs::read_to_string(config_file).ok()?;
+    let dir = config_file.parent()?;
+    let old_name = old_file.strip_prefix(dir).unwrap_or(old_file).to_str()?;
+    let new_name = new_file.strip_prefix(dir).unwrap_or(new_file).to_str()?;
+
+    ['\'', '"'].into_iter().find_map(|quote| {
+        let literal = format!("{quote}{old_name}{quote}");
+        let end = contents.find(&literal)? + literal.len();
+        let before = &contents[..end];
+        let pos = lsp_types::Position {
+            line: before.matches('\n').count() as u32,
+            character: before.rsplit('\n').next()?.encode_utf16().count() as u32,
+        };
+        Some(text_document_edit(
+            file_name_to_uri(config_file),
+            lsp_types::Range::new(pos, pos),
+            format!(", {quote}{new_name}{quote}"),
+        ))
+    })
+}
+
+/// Add a file name or glob pattern to the files of a library in the configuration file.
+/// The library and the `[libraries]` table are added when they do not exist,
+/// as is the configuration file itself.
+fn add_to_library_edit(
+    config_file: &Path,
+    library: &str,
+    pattern: &Path,
+) -> Option<Vec<DocumentChangeOperation>> {
+    let pattern = pattern.to_str()?;
+    let uri = file_name_to_uri(config_file);
+    let mut operations = Vec::new();
+    let contents = if config_file.exists() {
+        std::fs::read_to_string(config_file).ok()?
+    } else {
+        operations.push(DocumentChangeOperation::Op(ResourceOp::Create(
+            CreateFile {
+                uri: uri.clone(),
+                options: None,
+                annotation_id: None,
+            },
+        )));
+        String::new()
+    };
+    let lines: Vec<&str> = contents.lines().collect();
+    let position = |line: usize, prefix: &str| lsp_types::Position {
+        line: line as u32,
+        character: prefix.encode_utf16().count() as u32,
+    };
+    // The position after the opening bracket of a files array
+    let files_array = |line: usize, key: &str| {
+        let text = lines[line].trim_start();
+        let rest = text.strip_prefix(key)?.trim_start().strip_prefix('=')?;
+        rest.trim_start().strip_prefix('[')?;
+        let bracket = lines[line].find('[')?;
+        Some(position(line, &lines[line][..=bracket]))
+    };
+
+    let library_key = format!("{library}.files");
+    let library_table = format!("[libraries.{library}]");
+    let mut edit = None;
+    for (idx, line) in lines.iter().enumerate() {
+        if let Some(pos) = files_array(idx, &library_key) {
+            edit = Some((pos, format!("'{pattern}', ")));
+            break;
+        }
+        if line.trim() == library_table {
+            edit = lines
+                .iter()
+                .enumerate()
+                .skip(idx + 1)
+                .take_while(|(_, line)| !line.trim_start().starts_with('['))
+                .find_map(|(idx, _)| files_array(idx, "files"))
+                .map(|pos| (pos, format!("'{pattern}', ")));
+            if edit.is_some() {
+                break;
+            }
+        }
+    }
+    let (pos, new_text) = edit.or_else(|| {
+        let entry = format!("{library_key} = ['{pattern}']\n");
+        match lines.iter().position(|line| line.trim() == "[libraries]") {
+            Some(idx) => Some((position(idx + 1, ""), entry)),
+            None => {
+                let end = position(lines.len(), "");
+                let separator = if lines.is_empty() { "" } else { "\n" };
+                Some((end, format!("{separator}[libraries]\n{entry}")))
+            }
+        }
+    })?;
+    operations.push(DocumentChangeOperation::Edit(text_document_edit(
+        uri,
+        lsp_types::Range::new(pos, pos),
+        new_text,
+    )));
+    Some(operations)
+}
@@ -0,0 +1,38 @@
+use crate::vhdl_server::{srcpos_to_location, to_lsp_range, uri_to_file_name, VHDLServer};
+use lsp_types::{CodeLens, CodeLensParams, Command};
+use serde_json::json;
+
+/// The command of the client that shows a list of locations at a position
+const SHOW_REFERENCES: &str = "editor.action.showReferences";
+
+impl VHDLServer {
+    /// Annotate each entity and component with the number of its instantiations
+    pub fn text_document_code_lens(&mut self, params: &CodeLensParams) -> Option<Vec<CodeLens>> {
+        let uri = &params.text_document.uri;
+        let source = self.project.get_source(&uri_to_file_name(uri))?;
+
+        Some(
+            self.project
+                .instantiations_of_units(&source)
+                .into_iter()
+                .filter_map(|(unit, positions)| {
+                    let range = to_lsp_range(unit.decl_pos()?.range());
+                    let title = match positions.len() {
+                        1 => "1 instantiation".to_owned(),
+                        count => format!("{count} instantiations"),
+                    };
+                    let locations: Vec<_> = positions.iter().map(srcpos_to_location).collect();
+                    Some(CodeLens {
+                        range,
+                        command: Some(Command {
+                            title,
+                            command: SHOW_REFERENCES.to_owned(),
+                            arguments: Some(vec![json!(uri), json!(range.start), json!(locations)]),
+                        }),
+                        data: None,
+                    })
+                })
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,410 @@
+use crate::vhdl_server::{
+    from_lsp_pos, from_lsp_range, to_lsp_workspace_edit, uri_to_file_name, VHDLServer,
+};
+use lsp_types::{ApplyWorkspaceEditParams, ExecuteCommandParams, Position, Range, Url};
+use serde_json::Value;
+use vhdl_lang::{Message, TextEdit};
+
+/// Create or update the component declarations of entities in the components package.
+/// The arguments are the selected names of the entities, e.g. `"lib.ent"`.
+pub const UPDATE_COMPONENT_DECLARATIONS: &str = "vhdl_ls.updateComponentDeclarations";
+
+/// Migrate from `std_logic_arith`, `std_logic_unsigned` and `std_logic_signed` to `numeric_std`.
+/// The argument is either the URI of a document or the name of a library.
+pub const MIGRATE_TO_NUMERIC_STD: &str = "vhdl_ls.migrateToNumericStd";
+
+/// Rename all symbols of the workspace that match a regular expression.
+/// The arguments are the pattern and the replacement, e.g. `["^s_", "sig_"]`.
+pub const RENAME_MATCHING: &str = "vhdl_ls.renameMatching";
+
+/// Replace the signals declared on the selected lines with one signal of a new record type.
+/// The arguments are the document URI, the selected range and the package of the record type,
+/// e.g. `["file:///src/top.vhd", {"start": ..., "end": ...}, "lib.types_pkg"]`.
+pub const BUNDLE_SIGNALS: &str = "vhdl_ls.bundleSignals";
+
+/// Insert a clocked process that uses the clock and reset of the `registers` config.
+/// The arguments are the document URI and the position of the process.
+pub const INSERT_REGISTERED_PROCESS: &str = "vhdl_ls.insertRegisteredProcess";
+
+/// Insert an instantiation that associates every generic and port with a same-named signal.
+/// The arguments are the document URI, the position of the instantiation and the entity
+/// as a selected name such as `lib.ent`, or a component of a package such as `lib.pkg.comp`.
+pub const INSTANTIATE_ENTITY: &str = "vhdl_ls.instantiateEntity";
+
+/// Update the associations of all instantiations of an entity after its generics or ports
+/// changed. The argument is the entity as a selected name such as `lib.ent`.
+pub const UPDATE_INSTANTIATIONS: &str = "vhdl_ls.updateInstantiations";
+
+/// Create a testbench of an entity in a new file next to the file of the entity.
+/// The arguments are the entity as a selected name such as `lib.ent` and optionally
+/// `true` for a testbench with the test runner of VUnit.
+pub const CREATE_TESTBENCH: &str = "vhdl_ls.createTestbench";
+
+/// Move the design unit at a position, together with its context clause, to another file.
+/// The arguments are the document URI, the position and the URI of the target file.
+/// A new file is added to the library of the original file in `vhdl_ls.toml`.
+pub const MOVE_UNIT: &str = "vhdl_ls.moveUnit";
+
+/// Run the external checkers of the `checkers` config and publish their diagnostics when they finish.
+/// The optional argument is the URI of a document that checkers of a single file check,
+/// otherwise they check all files of the project.
+pub const RUN_EXTERNAL_CHECKERS: &str = "vhdl_ls.runExternalCheckers";
+
+/// Let GHDL analyze and elaborate the top-level entity of the `ghdl_cross_check` config and
+/// publish the errors that only one of GHDL and VHDL-LS finds when GHDL finishes.
+/// The command has no arguments.
+pub const GHDL_CROSS_CHECK: &str = "vhdl_ls.ghdlCrossCheck";
+
+/// Load `vhdl_ls.toml` again and analyze the files whose libraries have changed,
+/// without restarting the server. The command has no arguments.
+pub const RELOAD_PROJECT: &str = "vhdl_ls.reloadProject";
+
+/// Analyze the libraries and files of a target of `vhdl_ls.toml`.
+/// The argument is the name of the target, without an argument all libraries are analyzed.
+pub const SELECT_TARGET: &str = "vhdl_ls.selectTarget";
+
+/// All commands that can be executed using `workspace/executeCommand`
+pub const COMMANDS: &[&str] = &[
+    UPDATE_COMPONENT_DECLARATIONS,
+    MIGRATE_TO_NUMERIC_STD,
+    RENAME_MATCHING,
+    BUNDLE_SIGNALS,
+    INSERT_REGISTERED_PROCESS,
+    INSTANTIATE_ENTITY,
+    UPDATE_INSTANTIATIONS,
+    CREATE_TESTBENCH,
+    MOVE_UNIT,
+    RUN_EXTERNAL_CHECKERS,
+    GHDL_CROSS_CHECK,
+    RELOAD_PROJECT,
+    SELECT_TARGET,
+];
+
+impl VHDLServer {
+    pub fn workspace_execute_command(&mut self, params: &ExecuteCommandParams) -> Option<Value> {
+        match params.command.as_str() {
+            UPDATE_COMPONENT_DECLARATIONS => self.update_component_declarations(&params.arguments),
+            MIGRATE_TO_NUMERIC_STD => self.migrate_to_numeric_std(&params.arguments),
+            RENAME_MATCHING => self.rename_matching(&params.arguments),
+            BUNDLE_SIGNALS => self.bundle_signals(&params.arguments),
+            INSERT_REGISTERED_PROCESS => self.insert_registered_process(&params.arguments),
+            INSTANTIATE_ENTITY => self.instantiate_entity(&params.arguments),
+            UPDATE_INSTANTIATIONS => self.update_instantiations(&params.arguments),
+            CREATE_TESTBENCH => self.create_testbench(&params.arguments),
+            MOVE_UNIT => self.move_unit(&params.arguments),
+            RUN_EXTERNAL_CHECKERS => self.run_external_checkers_command(&params.arguments),
+            GHDL_CROSS_CHECK => self.ghdl_cross_check_command(),
+            RELOAD_PROJECT => self.reload_config(),
+            SELECT_TARGET => self.select_target(&params.arguments),
+            command => self.message(Message::error(format!("Unknown command {command}"))),
+        }
+        None
+    }
+
+    fn update_component_declarations(&mut self, arguments: &[Value]) {
+        let Some(package) = self.settings.components_package.clone() else {
+            self.message(Message::error(
+                "Cannot update component declarations, the componentsPackage setting is missing",
+            ));
+            return;
+        };
+        let entities: Vec<&str> = arguments.iter().filter_map(Value::as_str).collect();
+
+        match self
+            .project
+            .update_component_declarations(&package, &entities)
+        {
+            Ok(edits) => {
+                self.apply_edits(format!("Update component declarations in {package}"), edits)
+            }
+            Err(err) => self.message(Message::error(format!(
+                "Cannot update component declarations: {err}"
+            ))),
+        }
+    }
+
+    fn migrate_to_numeric_std(&mut self, arguments: &[Value]) {
+        let Some(target) = arguments.first().and_then(Value::as_str) else {
+            self.message(Message::error(
+                "Cannot migrate to numeric_std, expected a document URI or a library name",
+            ));
+            return;
+        };
+
+        let edits = if let Ok(uri) = Url::parse(target) {
+            match self.project.get_source(&uri_to_file_name(&uri)) {
+                Some(source) => Ok(self.project.migrate_to_numeric_std(&source)),
+                None => Err(format!("No such document '{uri}'")),
+            }
+        } else {
+            self.project.migrate_library_to_numeric_std(target)
+        };
+
+        match edits {
+            Ok(edits) => self.apply_edits(format!("Migrate {target} to numeric_std"), edits),
+            Err(err) => self.message(Message::error(format!(
+                "Cannot migrate to numeric_std: {err}"
+            ))),
+        }
+    }
+
+    fn rename_matching(&mut self, arguments: &[Value]) {
+        let [Value::String(pattern), Value::String(replacement)] = arguments else {
+            self.message(Message::error(
+                "Cannot rename symbols, expected a pattern and a replacement",
+            ));
+            return;
+        };
+
+        match self.project.rename_matching(pattern, replacement) {
+            Ok(edits) => self.apply_edits(
+                format!("Rename symbols matching '{pattern}' to '{replacement}'"),
+                edits,
+            ),
+            Err(err) => self.message(Message::error(format!("Cannot rename symbols: {err}"))),
+        }
+    }
+
+    fn bundle_signals(&mut self, arguments: &[Value]) {
+        let parsed = match arguments {
+            [Value::String(uri), range, Value::String(package)] => Url::parse(uri)
+                .ok()
+                .zip(serde_json::from_value::<Range>(range.clone()).ok())
+                .map(|(uri, range)| (uri, range, package)),
+            _ => None,
+        };
+        let Some((uri, range, package)) = parsed else {
+            self.message(Message::error(
+                "Cannot bundle signals, expected a document URI, a range and a package",
+            ));
+            return;
+        };
+        let Some(source) = self.project.get_source(&uri_to_file_name(&uri)) else {
+            self.message(Message::error(format!(
+                "Cannot bundle signals, no such document '{uri}'"
+            )));
+            return;
+        };
+
+        match self
+            .project
+            .bundle_signals(&source, from_lsp_range(range), package)
+        {
+            Ok(edits) => {
+                self.apply_edits(format!("Bundle signals into a record of {package}"), edits)
+            }
+            Err(err) => self.message(Message::error(format!("Cannot bundle signals: {err}"))),
+        }
+    }
+
+    fn insert_registered_process(&mut self, arguments: &[Value]) {
+        let parsed = match arguments {
+            [Value::String(uri), position] => Url::parse(uri)
+                .ok()
+                .zip(serde_json::from_value::<Position>(position.clone()).ok()),
+            _ => None,
+        };
+        let Some((uri, position)) = parsed else {
+            self.message(Message::error(
+                "Cannot insert a registered process, expected a document URI and a position",
+            ));
+            return;
+        };
+        let Some(source) = self.project.get_source(&uri_to_file_name(&uri)) else {
+            self.message(Message::error(format!(
+                "Cannot insert a registered process, no such document '{uri}'"
+            )));
+            return;
+        };
+
+        let edit = self
+            .project
+            .insert_registered_process(&source, from_lsp_pos(position));
+        self.apply_edits("Insert registered process".to_owned(), vec![edit]);
+    }
+
+    fn instantiate_entity(&mut self, arguments: &[Value]) {
+        let parsed = match arguments {
+            [Value::String(uri), position, Value::String(name)] => Url::parse(uri)
+                .ok()
+                .zip(serde_json::from_value::<Position>(position.clone()).ok())
+                .map(|(uri, position)| (uri, position, name)),
+            _ => None,
+        };
+        let Some((uri, position, name)) = parsed else {
+            self.message(Message::error(
+                "Cannot instantiate entity, expected a document URI, a position and an entity name",
+            ));
+            return;
+        };
+        let Some(source) = self.project.get_source(&uri_to_file_name(&uri)) else {
+            self.message(Message::error(format!(
+                "Cannot instantiate entity, no such document '{uri}'"
+            )));
+            return;
+        };
+
+        match self
+            .project
+            .instantiate(&source, from_lsp_pos(position), name)
+        {
+            Ok(edit) => self.apply_edits(format!("Instantiate {name}"), vec![edit]),
+            Err(err) => self.message(Message::error(format!("Cannot instantiate entity: {err}"))),
+        }
+    }
+
+    fn update_instantiations(&mut self, arguments: &[Value]) {
+        let [Value::String(entity)] = arguments else {
+            self.message(Message::error(
+                "Cannot update instantiations, expected an entity name",
+            ));
+            return;
+        };
+
+        match self.project.update_instantiations(entity) {
+            Ok(edits) => self.apply_edits(format!("Update instantiations of {entity}"), edits),
+            Err(err) => self.message(Message::error(format!(
+                "Cannot update instantiations: {err}"
+            ))),
+        }
+    }
+
+    fn create_testbench(&mut self, arguments: &[Value]) {
+        let (entity, vunit) = match arguments {
+            [Value::String(entity)] => (entity, false),
+            [Value::String(entity), Value::Bool(vunit)] => (entity, *vunit),
+            _ => {
+                self.message(Message::error(
+                    "Cannot create testbench, expected an entity name",
+                ));
+                return;
+            }
+        };
+
+        let testbench = match self.project.testbench(entity, vunit) {
+            Ok(testbench) => testbench,
+            Err(err) => {
+                self.message(Message::error(format!("Cannot create testbench: {err}")));
+                return;
+            }
+        };
+        if testbench.file_name.exists() {
+            self.message(Message::error(format!(
+                "Cannot create testbench, {} already exists",
+                testbench.file_name.display()
+            )));
+            return;
+        }
+        let edit = self.create_file_edit(
+            &testbench.entity_file_name,
+            &testbench.file_name,
+            testbench.text,
+        );
+        self.rpc.send_request(
+            "workspace/applyEdit",
+            ApplyWorkspaceEditParams {
+                label: Some(format!("Create testbench of {entity}")),
+                edit,
+            },
+        );
+    }
+
+    fn move_unit(&mut self, arguments: &[Value]) {
+        let parsed = match arguments {
+            [Value::String(uri), position, Value::String(target)] => Url::parse(uri)
+                .ok()
+                .zip(serde_json::from_value::<Position>(position.clone()).ok())
+                .zip(Url::parse(target).ok())
+                .map(|((uri, position), target)| (uri, position, target)),
+            _ => None,
+        };
+        let Some((uri, position, target)) = parsed else {
+            self.message(Message::error(
+                "Cannot move design unit, expected a document URI, a position and a target URI",
+            ));
+            return;
+        };
+        let Some(source) = self.project.get_source(&uri_to_file_name(&uri)) else {
+            self.message(Message::error(format!(
+                "Cannot move design unit, no such document '{uri}'"
+            )));
+            return;
+        };
+
+        let file_name = uri_to_file_name(&target);
+        let moved = match self
+            .project
+            .move_unit(&source, from_lsp_pos(position), &file_name)
+        {
+            Ok(moved) => moved,
+            Err(err) => {
+                self.message(Message::error(format!("Cannot move design unit: {err}")));
+                return;
+            }
+        };
+        let label = format!("Move {} to {}", moved.description, file_name.display());
+        let Some(edit) = self.move_unit_edit(&source, moved) else {
+            self.message(Message::error(format!(
+                "Cannot move design unit, {} is not a file of the project",
+                file_name.display()
+            )));
+            return;
+        };
+        self.rpc.send_request(
+            "workspace/applyEdit",
+            ApplyWorkspaceEditParams {
+                label: Some(label),
+                edit,
+            },
+        );
+    }
+
+    fn run_external_checkers_command(&mut self, arguments: &[Value]) {
+        let file_name = match arguments.first() {
+            Some(Value::String(uri)) => match Url::parse(uri) {
+                Ok(uri) => Some(uri_to_file_name(&uri)),
+                Err(_) => {
+                    self.message(Message::error(format!(
+                        "Cannot run external checkers, '{uri}' is not a document URI"
+                    )));
+                    return;
+                }
+            },
+            _ => None,
+        };
+        self.run_external_checkers(file_name.as_deref(), false);
+    }
+
+    fn ghdl_cross_check_command(&mut self) {
+        if !self.run_ghdl_cross_check(false) {
+            self.message(Message::error(
+                "Cannot cross-check with GHDL, the ghdl_cross_check config is missing",
+            ));
+        }
+    }
+
+    fn select_target(&mut self, arguments: &[Value]) {
+        let target = arguments.first().and_then(Value::as_str).map(str::to_owned);
+        self.message(Message::log(match target {
+            Some(ref target) => format!("Selecting target '{target}'"),
+            None => "Selecting all libraries".to_owned(),
+        }));
+        self.target = Some(target);
+        self.reload_config();
+    }
+
+    /// Ask the client to apply the edits to the workspace.
+    /// The client notifies the server about the changed documents afterwards.
+    fn apply_edits(&self, label: String, edits: Vec<TextEdit>) {
+        if edits.is_empty() {
+            return;
+        }
+        self.rpc.send_request(
+            "workspace/applyEdit",
+            ApplyWorkspaceEditParams {
+                label: Some(label),
+                edit: to_lsp_workspace_edit(edits),
+            },
+        );
+    }
+}
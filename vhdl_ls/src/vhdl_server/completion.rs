@@ -1,7 +1,8 @@
 use crate::vhdl_server::{from_lsp_pos, uri_to_file_name, VHDLServer};
 use lsp_types::{
     CompletionItem, CompletionItemKind, CompletionList, CompletionParams, Documentation,
-    InsertTextFormat, MarkupContent, MarkupKind,
+    InsertTextFormat, MarkupContent, MarkupKind, ParameterInformation, ParameterLabel,
+    SignatureHelp, SignatureHelpParams, SignatureInformation,
 };
 use vhdl_lang::ast::{Designator, ObjectClass};
 use vhdl_lang::{kind_str, AnyEntKind, Design, EntRef, InterfaceEnt, Overloaded};
@@ -18,6 +19,8 @@ impl VHDLServer {
                 if self.client_supports_snippets() {
                     item.insert_text_format = Some(InsertTextFormat::SNIPPET);
                     item.insert_text = Some(format!("{} => $1,", item.insert_text.unwrap()));
+                } else {
+                    item.insert_text = Some(format!("{} => ", item.insert_text.unwrap()));
                 }
                 item
             }
@@ -32,6 +35,13 @@ impl VHDLServer {
                 insert_text: Some(desi.to_string()),
                 ..Default::default()
             },
+            vhdl_lang::CompletionItem::Attribute(attribute) => CompletionItem {
+                label: attribute.to_string(),
+                detail: Some("predefined attribute".to_string()),
+                insert_text: Some(attribute.to_string()),
+                kind: Some(CompletionItemKind::REFERENCE),
+                ..Default::default()
+            },
             vhdl_lang::CompletionItem::Keyword(kind) => CompletionItem {
                 label: kind_str(kind).to_string(),
                 detail: Some(kind_str(kind).to_string()),
@@ -110,6 +120,61 @@ impl VHDLServer {
                     ..Default::default()
                 }
             }
+            vhdl_lang::CompletionItem::RecordAggregate(typ, elements) => {
+                let (insert_text, insert_text_format) = if self.client_supports_snippets() {
+                    let associations: Vec<String> = elements
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, elem)| format!("{} => ${}", elem.designator, idx + 1))
+                        .collect();
+                    (
+                        format!("({})", associations.join(", ")),
+                        InsertTextFormat::SNIPPET,
+                    )
+                } else {
+                    let associations: Vec<String> = elements
+                        .iter()
+                        .map(|elem| format!("{} => ", elem.designator))
+                        .collect();
+                    (
+                        format!("({})", associations.join(", ")),
+                        InsertTextFormat::PLAIN_TEXT,
+                    )
+                };
+                CompletionItem {
+                    label: format!("{} aggregate", typ.designator),
+                    detail: Some(format!("aggregate of {}", typ.describe())),
+                    insert_text: Some(insert_text),
+                    insert_text_format: Some(insert_text_format),
+                    kind: Some(CompletionItemKind::STRUCT),
+                    ..Default::default()
+                }
+            }
+            vhdl_lang::CompletionItem::Snippet(snippet)
+            | vhdl_lang::CompletionItem::Template(snippet) => {
+                let (insert_text, insert_text_format) = if self.client_supports_snippets() {
+                    (snippet.body.clone(), InsertTextFormat::SNIPPET)
+                } else {
+                    (snippet.plain_text(), InsertTextFormat::PLAIN_TEXT)
+                };
+                CompletionItem {
+                    label: snippet.prefix.clone(),
+                    detail: Some(
+                        snippet
+                            .description
+                            .clone()
+                            .unwrap_or_else(|| snippet.name.clone()),
+                    ),
+                    documentation: Some(Documentation::MarkupContent(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: format!("```vhdl\n{}\n```", snippet.plain_text()),
+                    })),
+                    insert_text: Some(insert_text),
+                    insert_text_format: Some(insert_text_format),
+                    kind: Some(CompletionItemKind::SNIPPET),
+                    ..Default::default()
+                }
+            }
         }
     }
 
@@ -137,6 +202,11 @@ impl VHDLServer {
             .project
             .list_completion_options(&source, cursor)
             .into_iter()
+            .filter(|item| {
+                // Templates without placeholders are not useful
+                self.client_supports_snippets()
+                    || !matches!(item, vhdl_lang::CompletionItem::Template(_))
+            })
             .map(|item| self.completion_item_to_lsp_item(item))
             .collect();
 
@@ -163,6 +233,49 @@ impl VHDLServer {
         }
         params
     }
+
+    /// Called when `(` or `,` is typed within a subprogram call or a map aspect
+    pub fn signature_help(&mut self, params: &SignatureHelpParams) -> Option<SignatureHelp> {
+        let params = &params.text_document_position_params;
+        let source = self
+            .project
+            .get_source(&uri_to_file_name(&params.text_document.uri))?;
+        let help = self
+            .project
+            .signature_help(&source, from_lsp_pos(params.position))?;
+
+        Some(SignatureHelp {
+            signatures: help
+                .signatures
+                .into_iter()
+                .map(|signature| SignatureInformation {
+                    parameters: Some(
+                        signature
+                            .parameters
+                            .iter()
+                            .map(|&(start, end)| ParameterInformation {
+                                // The offsets of the protocol are in UTF-16 code units
+                                label: ParameterLabel::LabelOffsets([
+                                    utf16_len(&signature.label[..start]),
+                                    utf16_len(&signature.label[..end]),
+                                ]),
+                                documentation: None,
+                            })
+                            .collect(),
+                    ),
+                    active_parameter: signature.active_parameter.map(|idx| idx as u32),
+                    label: signature.label,
+                    documentation: None,
+                })
+                .collect(),
+            active_signature: Some(help.active_signature as u32),
+            active_parameter: None,
+        })
+    }
+}
+
+fn utf16_len(text: &str) -> u32 {
+    text.encode_utf16().count() as u32
 }
 
 fn entity_to_completion_item(ent: EntRef) -> CompletionItem {
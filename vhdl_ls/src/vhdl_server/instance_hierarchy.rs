@@ -0,0 +1,79 @@
+use crate::vhdl_server::{from_lsp_pos, srcpos_to_location, uri_to_file_name, VHDLServer};
+use lsp_types::TextDocumentPositionParams;
+use serde_json::{json, Value};
+use vhdl_lang::{EntRef, Instantiation};
+
+/// The custom request for the instantiations of the entity, architecture or component
+/// at the position. Each instantiation is an object with the `uri` and `range` of its label,
+/// the architecture that contains it as `parent` and the instantiated `unit`.
+pub enum IncomingInstances {}
+
+impl lsp_types::request::Request for IncomingInstances {
+    type Params = TextDocumentPositionParams;
+    type Result = Value;
+    const METHOD: &'static str = "vhdl_ls/incomingInstances";
+}
+
+/// The custom request for the instantiations within the architecture,
+/// or the architectures of the entity, at the position.
+/// The result has the same format as the result of `vhdl_ls/incomingInstances`
+/// such that clients can expand the `unit` of each instantiation into a tree.
+pub enum OutgoingInstances {}
+
+impl lsp_types::request::Request for OutgoingInstances {
+    type Params = TextDocumentPositionParams;
+    type Result = Value;
+    const METHOD: &'static str = "vhdl_ls/outgoingInstances";
+}
+
+impl VHDLServer {
+    pub fn incoming_instances(&mut self, params: &TextDocumentPositionParams) -> Value {
+        let Some(ent) = self.design_unit_at(params) else {
+            return Value::Array(Vec::new());
+        };
+        to_json(self.project.incoming_instances(ent))
+    }
+
+    pub fn outgoing_instances(&mut self, params: &TextDocumentPositionParams) -> Value {
+        let Some(ent) = self.design_unit_at(params) else {
+            return Value::Array(Vec::new());
+        };
+        to_json(self.project.outgoing_instances(ent))
+    }
+
+    fn design_unit_at(&self, params: &TextDocumentPositionParams) -> Option<EntRef> {
+        let source = self
+            .project
+            .get_source(&uri_to_file_name(&params.text_document.uri))?;
+        self.project
+            .find_declaration(&source, from_lsp_pos(params.position))
+    }
+}
+
+fn to_json(instantiations: Vec<Instantiation>) -> Value {
+    Value::Array(
+        instantiations
+            .into_iter()
+            .map(|instantiation| {
+                let location = srcpos_to_location(&instantiation.pos);
+                json!({
+                    "uri": location.uri,
+                    "range": location.range,
+                    "parent": ent_json(instantiation.parent),
+                    "unit": ent_json(instantiation.unit),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// The name, description and location of a design unit or component
+fn ent_json(ent: EntRef) -> Value {
+    let location = ent.decl_pos().map(srcpos_to_location);
+    json!({
+        "name": ent.designator().to_string(),
+        "detail": ent.describe(),
+        "uri": location.as_ref().map(|location| &location.uri),
+        "range": location.as_ref().map(|location| location.range),
+    })
+}
@@ -1,3 +1,5 @@
+use crate::vhdl_server::commands::COMMANDS;
+use crate::vhdl_server::semantic_tokens::semantic_tokens_legend;
 use crate::vhdl_server::{NonProjectFileHandling, VHDLServer};
 use lsp_types::*;
 use serde_json::Value;
@@ -5,17 +7,32 @@ use vhdl_lang::{Message, Project};
 
 impl VHDLServer {
     fn apply_initial_options(&mut self, options: &Value) {
-        let Some(non_project_file_handling) = options.get("nonProjectFiles") else {
-            return;
-        };
-        match non_project_file_handling {
-            Value::String(handling) => match NonProjectFileHandling::from_string(handling) {
-                None => self.message(Message::error(format!(
-                    "Illegal setting {handling} for nonProjectFiles setting"
-                ))),
-                Some(handling) => self.settings.non_project_file_handling = handling,
-            },
-            _ => self.message(Message::error("nonProjectFiles must be a string")),
+        if let Some(non_project_file_handling) = options.get("nonProjectFiles") {
+            match non_project_file_handling {
+                Value::String(handling) => match NonProjectFileHandling::from_string(handling) {
+                    None => self.message(Message::error(format!(
+                        "Illegal setting {handling} for nonProjectFiles setting"
+                    ))),
+                    Some(handling) => self.settings.non_project_file_handling = handling,
+                },
+                _ => self.message(Message::error("nonProjectFiles must be a string")),
+            }
+        }
+        if let Some(components_package) = options.get("componentsPackage") {
+            match components_package {
+                Value::String(package) => self.settings.components_package = Some(package.clone()),
+                _ => self.message(Message::error("componentsPackage must be a string")),
+            }
+        }
+        if let Some(diagnostics) = options.get("diagnostics") {
+            self.severity_overwrites = self.read_severity_overwrites(diagnostics);
+            self.update_severity_map();
+        }
+        if let Some(scheme) = options.get("unitFileNameScheme") {
+            match scheme {
+                Value::String(scheme) => self.settings.unit_file_name_scheme = Some(scheme.clone()),
+                _ => self.message(Message::error("unitFileNameScheme must be a string")),
+            }
         }
     }
 
@@ -47,8 +64,11 @@ impl VHDLServer {
 
     pub fn initialize_request(&mut self, init_params: InitializeParams) -> InitializeResult {
         self.config_file = self.root_uri_config_file(&init_params);
+        self.config_modified = self.config_file_modified();
         let config = self.load_config();
-        self.severity_map = *config.severities();
+        self.config_severities = *config.severities();
+        self.update_severity_map();
+        self.load_message_catalog(&config, init_params.locale.as_deref());
         self.project = Project::from_config(config, &mut self.message_filter());
         self.project.enable_unused_declaration_detection();
         if let Some(options) = &init_params.initialization_options {
@@ -58,8 +78,13 @@ impl VHDLServer {
         let trigger_chars: Vec<String> = r".".chars().map(|ch| ch.to_string()).collect();
 
         let capabilities = ServerCapabilities {
-            text_document_sync: Some(TextDocumentSyncCapability::Kind(
-                TextDocumentSyncKind::INCREMENTAL,
+            text_document_sync: Some(TextDocumentSyncCapability::Options(
+                TextDocumentSyncOptions {
+                    open_close: Some(true),
+                    change: Some(TextDocumentSyncKind::INCREMENTAL),
+                    save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                    ..Default::default()
+                },
             )),
             declaration_provider: Some(DeclarationCapability::Simple(true)),
             definition_provider: Some(OneOf::Left(true)),
@@ -72,6 +97,10 @@ impl VHDLServer {
             })),
             workspace_symbol_provider: Some(OneOf::Left(true)),
             document_symbol_provider: Some(OneOf::Left(true)),
+            document_link_provider: Some(DocumentLinkOptions {
+                resolve_provider: Some(false),
+                work_done_progress_options: Default::default(),
+            }),
             document_highlight_provider: Some(OneOf::Left(true)),
             completion_provider: Some(CompletionOptions {
                 resolve_provider: Some(true),
@@ -81,6 +110,37 @@ impl VHDLServer {
                 }),
                 ..Default::default()
             }),
+            signature_help_provider: Some(SignatureHelpOptions {
+                trigger_characters: Some(vec!["(".to_owned(), ",".to_owned()]),
+                retrigger_characters: None,
+                work_done_progress_options: Default::default(),
+            }),
+            code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+            document_formatting_provider: Some(OneOf::Left(true)),
+            document_range_formatting_provider: Some(OneOf::Left(true)),
+            document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+                first_trigger_character: ";".to_owned(),
+                // The `d` of `end`
+                more_trigger_character: Some(vec!["d".to_owned(), "\n".to_owned()]),
+            }),
+            execute_command_provider: Some(ExecuteCommandOptions {
+                commands: COMMANDS.iter().map(|command| command.to_string()).collect(),
+                work_done_progress_options: Default::default(),
+            }),
+            inlay_hint_provider: Some(OneOf::Left(true)),
+            code_lens_provider: Some(CodeLensOptions {
+                resolve_provider: Some(false),
+            }),
+            folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+            linked_editing_range_provider: Some(LinkedEditingRangeServerCapabilities::Simple(true)),
+            call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+            semantic_tokens_provider: Some(
+                SemanticTokensServerCapabilities::SemanticTokensOptions(SemanticTokensOptions {
+                    legend: semantic_tokens_legend(),
+                    full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                    ..Default::default()
+                }),
+            ),
             ..Default::default()
         };
 
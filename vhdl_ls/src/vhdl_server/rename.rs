@@ -1,58 +1,46 @@
 use crate::vhdl_server::{
-    from_lsp_pos, srcpos_to_location, to_lsp_range, uri_to_file_name, VHDLServer,
+    from_lsp_pos, to_lsp_range, to_lsp_workspace_edit, uri_to_file_name, VHDLServer,
 };
-use lsp_types::{
-    PrepareRenameResponse, RenameParams, TextDocumentPositionParams, TextEdit, Url, WorkspaceEdit,
-};
-use std::collections::HashMap;
-use vhdl_lang::ast::Designator;
+use lsp_types::{PrepareRenameResponse, RenameParams, TextDocumentPositionParams, WorkspaceEdit};
 
 impl VHDLServer {
     pub fn prepare_rename(
         &mut self,
         params: &TextDocumentPositionParams,
-    ) -> Option<PrepareRenameResponse> {
-        let source = self
+    ) -> Result<Option<PrepareRenameResponse>, String> {
+        let Some(source) = self
             .project
-            .get_source(&uri_to_file_name(&params.text_document.uri))?;
+            .get_source(&uri_to_file_name(&params.text_document.uri))
+        else {
+            return Ok(None);
+        };
 
-        let (pos, ent) = self
+        let Some((pos, ent)) = self
             .project
-            .item_at_cursor(&source, from_lsp_pos(params.position))?;
+            .item_at_cursor(&source, from_lsp_pos(params.position))
+        else {
+            return Ok(None);
+        };
 
-        if let Designator::Identifier(_) = ent.designator() {
-            Some(PrepareRenameResponse::Range(to_lsp_range(pos.range)))
-        } else {
-            // It does not make sense to rename operator symbols and character literals
-            // Also they have different representations that would not be handled consistently
-            // Such as function "+"(arg1, arg2 : integer) but used as foo + bar
-            None
-        }
+        self.project.check_rename(ent)?;
+        Ok(Some(PrepareRenameResponse::Range(to_lsp_range(pos.range))))
     }
 
-    pub fn rename(&mut self, params: &RenameParams) -> Option<WorkspaceEdit> {
-        let source = self.project.get_source(&uri_to_file_name(
+    pub fn rename(&mut self, params: &RenameParams) -> Result<Option<WorkspaceEdit>, String> {
+        let Some(source) = self.project.get_source(&uri_to_file_name(
             &params.text_document_position.text_document.uri,
-        ))?;
+        )) else {
+            return Ok(None);
+        };
 
-        let ent = self.project.find_declaration(
+        let Some(ent) = self.project.find_declaration(
             &source,
             from_lsp_pos(params.text_document_position.position),
-        )?;
-
-        let mut changes: HashMap<Url, Vec<TextEdit>> = Default::default();
-
-        for srcpos in self.project.find_all_references(ent) {
-            let loc = srcpos_to_location(&srcpos);
-            changes.entry(loc.uri).or_default().push(TextEdit {
-                range: loc.range,
-                new_text: params.new_name.clone(),
-            });
-        }
+        ) else {
+            return Ok(None);
+        };
 
-        Some(WorkspaceEdit {
-            changes: Some(changes),
-            ..Default::default()
-        })
+        let edits = self.project.rename(ent, &params.new_name)?;
+        Ok(Some(to_lsp_workspace_edit(edits)))
     }
 }
@@ -0,0 +1,142 @@
+use crate::vhdl_server::{uri_to_file_name, VHDLServer};
+use lsp_types::{
+    SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens, SemanticTokensDelta,
+    SemanticTokensDeltaParams, SemanticTokensEdit, SemanticTokensFullDeltaResult,
+    SemanticTokensLegend, SemanticTokensParams, SemanticTokensResult, Url,
+};
+use vhdl_lang::SemanticTokenKind;
+
+/// The modifiers in the order of their bits
+const TOKEN_MODIFIERS: [SemanticTokenModifier; 3] = [
+    SemanticTokenModifier::DECLARATION,
+    SemanticTokenModifier::READONLY,
+    SemanticTokenModifier::DEPRECATED,
+];
+
+/// The number of integers that encode one token
+const TOKEN_SIZE: usize = 5;
+
+pub fn semantic_tokens_legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: SemanticTokenKind::ALL
+            .iter()
+            .map(|kind| SemanticTokenType::new(kind.as_str()))
+            .collect(),
+        token_modifiers: TOKEN_MODIFIERS.to_vec(),
+    }
+}
+
+impl VHDLServer {
+    pub fn semantic_tokens_full(
+        &mut self,
+        params: &SemanticTokensParams,
+    ) -> Option<SemanticTokensResult> {
+        let data = self.encode_semantic_tokens(&params.text_document.uri)?;
+        let result_id = self.store_semantic_tokens(&params.text_document.uri, data.clone());
+        Some(SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: Some(result_id),
+            data,
+        }))
+    }
+
+    pub fn semantic_tokens_full_delta(
+        &mut self,
+        params: &SemanticTokensDeltaParams,
+    ) -> Option<SemanticTokensFullDeltaResult> {
+        let uri = &params.text_document.uri;
+        let data = self.encode_semantic_tokens(uri)?;
+        let previous = self
+            .semantic_tokens
+            .get(uri)
+            .filter(|(result_id, _)| *result_id == params.previous_result_id)
+            .map(|(_, previous)| semantic_tokens_edit(previous, &data));
+        let result_id = self.store_semantic_tokens(uri, data.clone());
+
+        Some(match previous {
+            Some(edit) => SemanticTokensFullDeltaResult::TokensDelta(SemanticTokensDelta {
+                result_id: Some(result_id),
+                edits: edit.into_iter().collect(),
+            }),
+            // The client refers to tokens that are no longer known
+            None => SemanticTokensFullDeltaResult::Tokens(SemanticTokens {
+                result_id: Some(result_id),
+                data,
+            }),
+        })
+    }
+
+    fn store_semantic_tokens(&mut self, uri: &Url, data: Vec<SemanticToken>) -> String {
+        self.semantic_tokens_id += 1;
+        let result_id = self.semantic_tokens_id.to_string();
+        self.semantic_tokens
+            .insert(uri.clone(), (result_id.clone(), data));
+        result_id
+    }
+
+    /// Encode the tokens relative to the previous token as required by the protocol
+    fn encode_semantic_tokens(&self, uri: &Url) -> Option<Vec<SemanticToken>> {
+        let source = self.project.get_source(&uri_to_file_name(uri))?;
+
+        let mut data = Vec::new();
+        let mut previous = vhdl_lang::Position::default();
+        for token in self.project.semantic_tokens(&source) {
+            let range = token.pos.range();
+            // Extended identifiers may span several lines which is not supported by all clients
+            if range.start.line != range.end.line {
+                continue;
+            }
+            let modifiers = [token.is_declaration, token.is_readonly, token.is_deprecated];
+            data.push(SemanticToken {
+                delta_line: range.start.line - previous.line,
+                delta_start: if range.start.line == previous.line {
+                    range.start.character - previous.character
+                } else {
+                    range.start.character
+                },
+                length: range.end.character - range.start.character,
+                token_type: SemanticTokenKind::ALL
+                    .iter()
+                    .position(|kind| *kind == token.kind)
+                    .unwrap() as u32,
+                token_modifiers_bitset: modifiers
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, is_set)| **is_set)
+                    .map(|(bit, _)| 1u32 << bit)
+                    .sum(),
+            });
+            previous = range.start;
+        }
+        Some(data)
+    }
+}
+
+/// A single edit that replaces the tokens between the common prefix and suffix,
+/// or `None` if the tokens are unchanged
+fn semantic_tokens_edit(
+    previous: &[SemanticToken],
+    current: &[SemanticToken],
+) -> Option<SemanticTokensEdit> {
+    let prefix = previous
+        .iter()
+        .zip(current.iter())
+        .take_while(|(previous, current)| previous == current)
+        .count();
+    let suffix = previous[prefix..]
+        .iter()
+        .rev()
+        .zip(current[prefix..].iter().rev())
+        .take_while(|(previous, current)| previous == current)
+        .count();
+
+    let deleted = previous.len() - prefix - suffix;
+    let inserted = &current[prefix..current.len() - suffix];
+    if deleted == 0 && inserted.is_empty() {
+        return None;
+    }
+    Some(SemanticTokensEdit {
+        start: (prefix * TOKEN_SIZE) as u32,
+        delete_count: (deleted * TOKEN_SIZE) as u32,
+        data: Some(inserted.to_vec()),
+    })
+}
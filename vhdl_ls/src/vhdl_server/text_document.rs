@@ -1,16 +1,34 @@
 use crate::vhdl_server::{
-    from_lsp_pos, from_lsp_range, srcpos_to_location, to_lsp_range, uri_to_file_name,
-    NonProjectFileHandling, VHDLServer,
+    file_name_to_uri, from_lsp_pos, from_lsp_range, srcpos_to_location, to_lsp_pos, to_lsp_range,
+    uri_to_file_name, NonProjectFileHandling, VHDLServer,
 };
 use lsp_types::{
-    DidChangeTextDocumentParams, DidOpenTextDocumentParams, DocumentHighlight,
-    DocumentHighlightKind, GotoDefinitionResponse, Hover, HoverContents, Location, MarkupContent,
-    MarkupKind, ReferenceParams, TextDocumentItem, TextDocumentPositionParams,
+    DidChangeTextDocumentParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams,
+    DocumentFormattingParams, DocumentHighlight, DocumentHighlightKind, DocumentLink,
+    DocumentLinkParams, DocumentOnTypeFormattingParams, DocumentRangeFormattingParams,
+    FoldingRange, FoldingRangeKind, FoldingRangeParams, FormattingOptions, GotoDefinitionResponse,
+    Hover, HoverContents, InlayHint, InlayHintKind, InlayHintLabel, InlayHintParams,
+    LinkedEditingRangeParams, LinkedEditingRanges, Location, MarkupContent, MarkupKind,
+    ReferenceParams, TextDocumentItem, TextDocumentPositionParams, TextEdit,
 };
-use vhdl_lang::{Message, Source};
+use serde_json::{json, Value};
+use std::path::Path;
+use vhdl_lang::{FormatterConfig, Message, ReferenceKind, Source};
+
+/// The custom request for the references of a declaration together with the kind of each reference.
+/// The params are those of `textDocument/references` with an optional `kinds` array
+/// of the kinds to return, e.g. `["write"]` to find the statements that drive a signal.
+pub enum ReferencesWithKind {}
+
+impl lsp_types::request::Request for ReferencesWithKind {
+    type Params = Value;
+    type Result = Value;
+    const METHOD: &'static str = "vhdl_ls/references";
+}
 
 impl VHDLServer {
     pub fn text_document_did_open_notification(&mut self, params: &DidOpenTextDocumentParams) {
+        self.reload_modified_config();
         let TextDocumentItem { uri, text, .. } = &params.text_document;
         let file_name = uri_to_file_name(uri);
         if let Some(source) = self.project.get_source(&file_name) {
@@ -27,6 +45,7 @@ impl VHDLServer {
                     )));
                     self.project
                         .update_source(&Source::inline(&file_name, text));
+                    self.unmapped_files.insert(uri.clone());
                     self.publish_diagnostics();
                 }
             }
@@ -50,6 +69,14 @@ impl VHDLServer {
         }
     }
 
+    pub fn text_document_did_save_notification(&mut self, params: &DidSaveTextDocumentParams) {
+        self.reload_modified_config();
+        let file_name = uri_to_file_name(&params.text_document.uri);
+        self.run_external_checkers(Some(&file_name), true);
+        self.run_ghdl_cross_check(true);
+        self.publish_diagnostics();
+    }
+
     pub fn text_document_declaration(
         &mut self,
         params: &TextDocumentPositionParams,
@@ -105,12 +132,16 @@ impl VHDLServer {
             .project
             .find_declaration(&source, from_lsp_pos(params.position))?;
 
-        let value = self.project.format_declaration(ent)?;
+        let mut value = format!("```vhdl\n{}\n```", self.project.format_declaration(ent)?);
+        if let Some(documentation) = self.project.documentation(ent) {
+            value.push_str("\n---\n");
+            value.push_str(&documentation);
+        }
 
         Some(Hover {
             contents: HoverContents::Markup(MarkupContent {
                 kind: MarkupKind::Markdown,
-                value: format!("```vhdl\n{value}\n```"),
+                value,
             }),
             range: None,
         })
@@ -140,6 +171,84 @@ impl VHDLServer {
         }
     }
 
+    /// The references as an array of objects with the `uri`, `range` and `kind` of each reference
+    pub fn references_with_kind(&mut self, params: Value) -> Result<Value, String> {
+        let kinds = match params.get("kinds") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(kinds)) => kinds
+                .iter()
+                .map(|kind| {
+                    kind.as_str()
+                        .ok_or_else(|| format!("{kind} is not a valid reference kind"))
+                        .and_then(ReferenceKind::try_from)
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Some(kinds) => return Err(format!("kinds must be an array, got {kinds}")),
+        };
+        let params: ReferenceParams =
+            serde_json::from_value(params).map_err(|err| err.to_string())?;
+
+        let ent = self
+            .project
+            .get_source(&uri_to_file_name(
+                &params.text_document_position.text_document.uri,
+            ))
+            .and_then(|source| {
+                self.project.find_declaration(
+                    &source,
+                    from_lsp_pos(params.text_document_position.position),
+                )
+            });
+        let Some(ent) = ent else {
+            return Ok(Value::Array(Vec::new()));
+        };
+
+        Ok(Value::Array(
+            self.project
+                .find_all_references_with_kind(ent)
+                .into_iter()
+                .filter(|(_, kind)| kinds.is_empty() || kinds.contains(kind))
+                .map(|(pos, kind)| {
+                    let location = srcpos_to_location(&pos);
+                    json!({
+                        "uri": location.uri,
+                        "range": location.range,
+                        "kind": kind.as_str(),
+                    })
+                })
+                .collect(),
+        ))
+    }
+
+    /// Links from the use clauses, context references and instantiations
+    /// to the files of the referenced design units
+    pub fn text_document_document_link(
+        &self,
+        params: &DocumentLinkParams,
+    ) -> Option<Vec<DocumentLink>> {
+        let source = self
+            .project
+            .get_source(&uri_to_file_name(&params.text_document.uri))?;
+
+        Some(
+            self.project
+                .find_design_unit_references(&source)
+                .into_iter()
+                .filter_map(|(pos, ent)| {
+                    let decl_pos = ent.decl_pos()?;
+                    let mut target = file_name_to_uri(decl_pos.source.file_name());
+                    target.set_fragment(Some(&format!("L{}", decl_pos.start().line + 1)));
+                    Some(DocumentLink {
+                        range: to_lsp_range(pos.range()),
+                        target: Some(target),
+                        tooltip: Some(format!("Open {}", ent.describe())),
+                        data: None,
+                    })
+                })
+                .collect(),
+        )
+    }
+
     pub fn document_highlight(
         &mut self,
         params: &TextDocumentPositionParams,
@@ -154,13 +263,163 @@ impl VHDLServer {
 
         Some(
             self.project
-                .find_all_references_in_source(&source, ent)
-                .iter()
-                .map(|pos| DocumentHighlight {
+                .find_all_references_in_source_with_kind(&source, ent)
+                .into_iter()
+                .map(|(pos, kind)| DocumentHighlight {
                     range: to_lsp_range(pos.range()),
-                    kind: Some(DocumentHighlightKind::TEXT),
+                    kind: Some(match kind {
+                        ReferenceKind::Write => DocumentHighlightKind::WRITE,
+                        ReferenceKind::Read => DocumentHighlightKind::READ,
+                        _ => DocumentHighlightKind::TEXT,
+                    }),
+                })
+                .collect(),
+        )
+    }
+
+    /// Show the names of the formals before the actuals of positional associations
+    pub fn text_document_inlay_hint(&self, params: &InlayHintParams) -> Option<Vec<InlayHint>> {
+        let source = self
+            .project
+            .get_source(&uri_to_file_name(&params.text_document.uri))?;
+        let range = from_lsp_range(params.range);
+
+        Some(
+            self.project
+                .formal_name_hints(&source)
+                .into_iter()
+                .filter(|edit| range.contains(edit.pos.start()))
+                .map(|edit| InlayHint {
+                    position: to_lsp_pos(edit.pos.start()),
+                    label: InlayHintLabel::String(edit.new_text.trim_end().to_owned()),
+                    kind: Some(InlayHintKind::PARAMETER),
+                    // Accepting the hint converts the association into a named association
+                    text_edits: Some(vec![TextEdit {
+                        range: to_lsp_range(edit.pos.range()),
+                        new_text: edit.new_text,
+                    }]),
+                    tooltip: None,
+                    padding_left: None,
+                    padding_right: Some(true),
+                    data: None,
                 })
                 .collect(),
         )
     }
+
+    pub fn text_document_folding_range(
+        &self,
+        params: &FoldingRangeParams,
+    ) -> Option<Vec<FoldingRange>> {
+        let source = self
+            .project
+            .get_source(&uri_to_file_name(&params.text_document.uri))?;
+
+        Some(
+            self.project
+                .folding_ranges(&source)
+                .into_iter()
+                .map(|range| FoldingRange {
+                    start_line: range.start_line,
+                    start_character: None,
+                    end_line: range.end_line,
+                    end_character: None,
+                    kind: Some(match range.kind {
+                        vhdl_lang::FoldingRangeKind::Region => FoldingRangeKind::Region,
+                        vhdl_lang::FoldingRangeKind::Comment => FoldingRangeKind::Comment,
+                    }),
+                    collapsed_text: None,
+                })
+                .collect(),
+        )
+    }
+
+    /// The name of the declaration at the cursor and the name after its `end`
+    /// such that renaming one of them renames both
+    pub fn text_document_linked_editing_range(
+        &self,
+        params: &LinkedEditingRangeParams,
+    ) -> Option<LinkedEditingRanges> {
+        let params = &params.text_document_position_params;
+        let source = self
+            .project
+            .get_source(&uri_to_file_name(&params.text_document.uri))?;
+        let ranges = self
+            .project
+            .linked_editing_ranges(&source, from_lsp_pos(params.position))?;
+        Some(LinkedEditingRanges {
+            ranges: ranges.iter().map(|pos| to_lsp_range(pos.range())).collect(),
+            word_pattern: None,
+        })
+    }
+
+    /// Re-indent the document with the indentation of the formatting options of the client
+    pub fn text_document_formatting(
+        &self,
+        params: &DocumentFormattingParams,
+    ) -> Option<Vec<TextEdit>> {
+        let file_name = uri_to_file_name(&params.text_document.uri);
+        let source = self.project.get_source(&file_name)?;
+        let edits = self
+            .project
+            .format_source(&source, self.formatter_config(&file_name, &params.options))?;
+        Some(to_lsp_text_edits(edits))
+    }
+
+    /// Re-indent the smallest design unit, declaration or statement that encloses the range
+    pub fn text_document_range_formatting(
+        &self,
+        params: &DocumentRangeFormattingParams,
+    ) -> Option<Vec<TextEdit>> {
+        let file_name = uri_to_file_name(&params.text_document.uri);
+        let source = self.project.get_source(&file_name)?;
+        let edits = self.project.format_source_range(
+            &source,
+            from_lsp_range(params.range),
+            self.formatter_config(&file_name, &params.options),
+        )?;
+        Some(to_lsp_text_edits(edits))
+    }
+
+    /// Re-indent the statement that ends with a typed `;`, the block that is closed by
+    /// a typed `end` or the line before a typed newline
+    pub fn text_document_on_type_formatting(
+        &self,
+        params: &DocumentOnTypeFormattingParams,
+    ) -> Option<Vec<TextEdit>> {
+        let position = &params.text_document_position;
+        let file_name = uri_to_file_name(&position.text_document.uri);
+        let source = self.project.get_source(&file_name)?;
+        let edits = self.project.format_on_type(
+            &source,
+            from_lsp_pos(position.position),
+            params.ch.chars().next()?,
+            self.formatter_config(&file_name, &params.options),
+        )?;
+        Some(to_lsp_text_edits(edits))
+    }
+
+    /// The indentation of the editor, overwritten by the settings of the nearest
+    /// formatter configuration file of the project
+    fn formatter_config(&self, file_name: &Path, options: &FormattingOptions) -> FormatterConfig {
+        let mut config = FormatterConfig {
+            indent_width: options.tab_size as usize,
+            use_tabs: !options.insert_spaces,
+            ..Default::default()
+        };
+        if let Err(err) = config.update_from_nearest_file(file_name) {
+            self.message(Message::error(err));
+        }
+        config
+    }
+}
+
+fn to_lsp_text_edits(edits: Vec<vhdl_lang::TextEdit>) -> Vec<TextEdit> {
+    edits
+        .into_iter()
+        .map(|edit| TextEdit {
+            range: to_lsp_range(edit.pos.range()),
+            new_text: edit.new_text,
+        })
+        .collect()
 }
@@ -1,8 +1,8 @@
 use crate::vhdl_server::{srcpos_to_location, to_symbol_kind, uri_to_file_name, VHDLServer};
 use fuzzy_matcher::FuzzyMatcher;
 use lsp_types::{
-    DidChangeWatchedFilesParams, OneOf, WorkspaceSymbol, WorkspaceSymbolParams,
-    WorkspaceSymbolResponse,
+    DidChangeConfigurationParams, DidChangeWatchedFilesParams, OneOf, WorkspaceSymbol,
+    WorkspaceSymbolParams, WorkspaceSymbolResponse,
 };
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
@@ -20,16 +20,22 @@ impl VHDLServer {
                 self.message(Message::log(
                     "Configuration file has changed, reloading project...",
                 ));
-                let config = self.load_config();
-                self.severity_map = *config.severities();
-
-                self.project
-                    .update_config(config, &mut self.message_filter());
-                self.publish_diagnostics();
+                self.reload_config();
             }
         }
     }
 
+    /// Apply the `diagnostics` setting of the editor immediately.
+    /// The settings are either the settings of vhdl_ls or nested in a `vhdl_ls` object.
+    pub fn workspace_did_change_configuration(&mut self, params: &DidChangeConfigurationParams) {
+        let settings = params.settings.get("vhdl_ls").unwrap_or(&params.settings);
+        if let Some(diagnostics) = settings.get("diagnostics") {
+            self.severity_overwrites = self.read_severity_overwrites(diagnostics);
+            self.update_severity_map();
+            self.publish_diagnostics();
+        }
+    }
+
     pub fn workspace_symbol(
         &self,
         params: &WorkspaceSymbolParams,
@@ -0,0 +1,434 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) 2024, Olof Kraigher olof.kraigher@gmail.com
+
+//! The server side of the WebSocket protocol (RFC 6455) as used by browser-based editors.
+//! Each text or binary message contains one JSON-RPC message,
+//! with or without the `Content-Length` header of the base protocol.
+
+use lsp_server::Message;
+use std::io::{self, BufRead, Write};
+use std::sync::Mutex;
+
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xa;
+
+/// The largest message that is accepted from a client
+const MAX_MESSAGE_LENGTH: u64 = 64 * 1024 * 1024;
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Read the opening handshake of the client and accept it.
+/// Browsers send the origin of the page that opens the connection, which must be
+/// the host of the server or a loopback address so that other web pages cannot connect.
+pub fn handshake(reader: &mut impl BufRead, writer: &mut impl Write) -> io::Result<()> {
+    let mut key = None;
+    let mut host = None;
+    let mut origin = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid_data(
+                "Connection closed during the WebSocket handshake",
+            ));
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let name = name.trim();
+            let value = Some(value.trim().to_owned());
+            if name.eq_ignore_ascii_case("Sec-WebSocket-Key") {
+                key = value;
+            } else if name.eq_ignore_ascii_case("Host") {
+                host = value;
+            } else if name.eq_ignore_ascii_case("Origin") {
+                origin = value;
+            }
+        }
+    }
+
+    let Some(key) = key else {
+        writer.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")?;
+        return Err(invalid_data("Expected a WebSocket handshake"));
+    };
+    if let Some(origin) = origin {
+        if !is_allowed_origin(&origin, host.as_deref()) {
+            writer.write_all(b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n")?;
+            return Err(invalid_data(format!("Rejected origin '{origin}'")));
+        }
+    }
+    write!(
+        writer,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(&key)
+    )?;
+    writer.flush()
+}
+
+/// True if the origin is the host of the server or a loopback address
+fn is_allowed_origin(origin: &str, host: Option<&str>) -> bool {
+    let authority = origin
+        .split_once("://")
+        .map_or(origin, |(_, rest)| rest)
+        .split('/')
+        .next()
+        .unwrap_or_default();
+    if host.is_some_and(|host| host.eq_ignore_ascii_case(authority)) {
+        return true;
+    }
+    let hostname = match authority.rsplit_once(':') {
+        Some((hostname, port)) if !port.contains(']') => hostname,
+        _ => authority,
+    };
+    ["localhost", "127.0.0.1", "[::1]"]
+        .iter()
+        .any(|loopback| hostname.eq_ignore_ascii_case(loopback))
+}
+
+/// The value of the `Sec-WebSocket-Accept` header for the key of the client
+fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{key}{ACCEPT_GUID}").as_bytes()))
+}
+
+/// Read the next message, `None` when the client closes the connection.
+/// Ping frames are answered with a pong frame and pong frames are ignored.
+pub fn read_message(
+    reader: &mut impl BufRead,
+    writer: &Mutex<impl Write>,
+) -> io::Result<Option<Message>> {
+    let mut payload = Vec::new();
+    loop {
+        let mut header = [0; 2];
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err),
+        }
+        let fin = header[0] & 0x80 != 0;
+        let opcode = header[0] & 0x0f;
+        // Clients must mask every frame, RFC 6455 section 5.1
+        if header[1] & 0x80 == 0 {
+            return Err(invalid_data("Received an unmasked WebSocket frame"));
+        }
+        let len = match header[1] & 0x7f {
+            126 => {
+                let mut len = [0; 2];
+                reader.read_exact(&mut len)?;
+                u16::from_be_bytes(len) as u64
+            }
+            127 => {
+                let mut len = [0; 8];
+                reader.read_exact(&mut len)?;
+                u64::from_be_bytes(len)
+            }
+            len => len as u64,
+        };
+        if payload.len() as u64 + len > MAX_MESSAGE_LENGTH {
+            return Err(invalid_data(format!(
+                "WebSocket message exceeds the maximum length of {MAX_MESSAGE_LENGTH} bytes"
+            )));
+        }
+        let mut mask = [0; 4];
+        reader.read_exact(&mut mask)?;
+        let mut data = vec![0; len as usize];
+        reader.read_exact(&mut data)?;
+        for (idx, byte) in data.iter_mut().enumerate() {
+            *byte ^= mask[idx % 4];
+        }
+
+        match opcode {
+            OPCODE_CLOSE => return Ok(None),
+            OPCODE_TEXT | OPCODE_BINARY | OPCODE_CONTINUATION => {
+                payload.extend(data);
+                if fin {
+                    return parse_message(&payload).map(Some);
+                }
+            }
+            OPCODE_PING => {
+                let mut writer = writer.lock().unwrap();
+                write_frame(&mut *writer, OPCODE_PONG, &data)?;
+            }
+            // Pong
+            _ => {}
+        }
+    }
+}
+
+fn parse_message(payload: &[u8]) -> io::Result<Message> {
+    if payload.starts_with(b"Content-Length") {
+        let mut payload = payload;
+        Message::read(&mut payload)?.ok_or_else(|| invalid_data("Empty WebSocket message"))
+    } else {
+        serde_json::from_slice(payload).map_err(|err| invalid_data(err.to_string()))
+    }
+}
+
+/// Write the message as a text frame
+pub fn write_message(writer: &mut impl Write, message: Message) -> io::Result<()> {
+    let mut value = serde_json::to_value(message)?;
+    if let Some(object) = value.as_object_mut() {
+        object.insert("jsonrpc".to_owned(), "2.0".into());
+    }
+    let payload = serde_json::to_vec(&value)?;
+    write_frame(writer, OPCODE_TEXT, &payload)
+}
+
+/// Write an unfragmented and unmasked frame
+fn write_frame(writer: &mut impl Write, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xffff => {
+            frame.push(126);
+            frame.extend((len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend((len as u64).to_be_bytes());
+        }
+    }
+    frame.extend(payload);
+    writer.write_all(&frame)?;
+    writer.flush()
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend((data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut words = [0u32; 80];
+        for (idx, word) in block.chunks(4).enumerate() {
+            words[idx] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for idx in 16..80 {
+            words[idx] = (words[idx - 3] ^ words[idx - 8] ^ words[idx - 14] ^ words[idx - 16])
+                .rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (idx, word) in words.iter().enumerate() {
+            let (f, k) = match idx {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (value, add) in state.iter_mut().zip([a, b, c, d, e]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (idx, value) in state.iter().enumerate() {
+        digest[idx * 4..idx * 4 + 4].copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut result = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                result.push(ALPHABET[(bits >> (18 - 6 * idx) & 0x3f) as usize] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_handshake() {
+        // The example of RFC 6455
+        let request = "GET /chat HTTP/1.1\r\nHost: server.example.com\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n";
+        let mut response = Vec::new();
+        handshake(&mut request.as_bytes(), &mut response).unwrap();
+        let response = String::from_utf8(response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+        assert!(response.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+    }
+
+    #[test]
+    fn reads_masked_fragmented_messages() {
+        let text = br#"{"jsonrpc": "2.0", "method": "exit"}"#;
+        let mask = [1, 2, 3, 4];
+        let mut frames = Vec::new();
+        for (opcode, fin, part) in [(OPCODE_TEXT, 0, &text[..10]), (0, 0x80, &text[10..])] {
+            frames.push(fin | opcode);
+            frames.push(0x80 | part.len() as u8);
+            frames.extend(mask);
+            frames.extend(
+                part.iter()
+                    .enumerate()
+                    .map(|(idx, byte)| byte ^ mask[idx % 4]),
+            );
+        }
+        frames.extend([0x80 | OPCODE_CLOSE, 0x80, 0, 0, 0, 0]);
+
+        let mut reader = frames.as_slice();
+        let writer = Mutex::new(Vec::new());
+        let Some(Message::Notification(notification)) = read_message(&mut reader, &writer).unwrap()
+        else {
+            panic!("Expected a notification");
+        };
+        assert_eq!(notification.method, "exit");
+        assert!(read_message(&mut reader, &writer).unwrap().is_none());
+    }
+
+    #[test]
+    fn answers_ping_with_pong() {
+        // An all-zero mask leaves the payload unchanged
+        let frames = [
+            0x80 | OPCODE_PING,
+            0x80 | 2,
+            0,
+            0,
+            0,
+            0,
+            b'h',
+            b'i',
+            0x80 | OPCODE_CLOSE,
+            0x80,
+            0,
+            0,
+            0,
+            0,
+        ];
+        let writer = Mutex::new(Vec::new());
+        assert!(read_message(&mut frames.as_slice(), &writer)
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            writer.into_inner().unwrap(),
+            vec![0x80 | OPCODE_PONG, 2, b'h', b'i']
+        );
+    }
+
+    #[test]
+    fn rejects_too_long_messages() {
+        let mut frames = vec![0x80 | OPCODE_TEXT, 0x80 | 127];
+        frames.extend((MAX_MESSAGE_LENGTH + 1).to_be_bytes());
+        let writer = Mutex::new(Vec::new());
+        let err = read_message(&mut frames.as_slice(), &writer).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_unmasked_frames() {
+        let frames = [0x80 | OPCODE_TEXT, 2, b'{', b'}'];
+        let writer = Mutex::new(Vec::new());
+        let err = read_message(&mut frames.as_slice(), &writer).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn computes_sha1() {
+        // The examples of FIPS 180-2
+        let hex = |data: &[u8]| {
+            sha1(data)
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>()
+        };
+        assert_eq!(hex(b""), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(hex(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(
+            hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+        );
+        assert_eq!(
+            hex(&[b'a'; 1000]),
+            "291e9a6c66994949b57ba5e650361e98fc36b1ba"
+        );
+    }
+
+    #[test]
+    fn encodes_base64() {
+        // The examples of RFC 4648
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foob"), "Zm9vYg==");
+        assert_eq!(base64(b"fooba"), "Zm9vYmE=");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn rejects_foreign_origin() {
+        let request = "GET / HTTP/1.1\r\nHost: localhost:8080\r\nOrigin: http://example.com\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n";
+        let mut response = Vec::new();
+        assert!(handshake(&mut request.as_bytes(), &mut response).is_err());
+        assert!(response.starts_with(b"HTTP/1.1 403 Forbidden\r\n"));
+    }
+
+    #[test]
+    fn allows_origins_of_the_server() {
+        assert!(is_allowed_origin(
+            "http://server.example.com",
+            Some("server.example.com")
+        ));
+        assert!(is_allowed_origin(
+            "http://localhost:3000",
+            Some("localhost:8080")
+        ));
+        assert!(is_allowed_origin("http://[::1]:3000", None));
+        assert!(!is_allowed_origin("http://localhost.example.com", None));
+    }
+
+    #[test]
+    fn writes_text_frames() {
+        let mut frame = Vec::new();
+        let message = lsp_server::Notification::new("exit".to_owned(), ());
+        write_message(&mut frame, message.into()).unwrap();
+        assert_eq!(frame[0], 0x80 | OPCODE_TEXT);
+        let payload: serde_json::Value = serde_json::from_slice(&frame[2..]).unwrap();
+        assert_eq!(payload["jsonrpc"], "2.0");
+        assert_eq!(payload["method"], "exit");
+        assert_eq!(frame[1] as usize, frame.len() - 2);
+    }
+}